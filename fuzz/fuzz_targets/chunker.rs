@@ -0,0 +1,36 @@
+//! Feed arbitrary bytes to the semantic chunker
+//!
+//! The first byte picks the file extension (and so the grammar); the rest is
+//! decoded lossily like an unreadable source file. Run with
+//! `cargo +nightly fuzz run chunker` from the repository root.
+
+#![no_main]
+
+use codesearch::chunker::SemanticChunker;
+use codesearch::Language;
+use libfuzzer_sys::fuzz_target;
+use std::path::PathBuf;
+
+const EXTENSIONS: &[&str] = &[
+    "rs", "py", "js", "ts", "tsx", "go", "java", "cs", "c", "cpp", "rb", "php", "sql", "proto",
+    "graphql", "yaml", "json", "md", "txt",
+];
+
+fuzz_target!(|data: &[u8]| {
+    let Some((&selector, rest)) = data.split_first() else {
+        return;
+    };
+    let path = PathBuf::from(format!(
+        "fuzz.{}",
+        EXTENSIONS[selector as usize % EXTENSIONS.len()]
+    ));
+    let content = String::from_utf8_lossy(rest);
+
+    let mut chunker = SemanticChunker::new(100, 2000, 10);
+    if let Ok(chunks) = chunker.chunk_semantic(Language::from_path(&path), &path, &content) {
+        for chunk in chunks {
+            assert!(chunk.start_line <= chunk.end_line);
+            assert!(chunk.content.len() <= 2000 * 4);
+        }
+    }
+});
@@ -1,3 +1,4 @@
+use crate::out_print;
 use anyhow::{anyhow, Result};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
@@ -6,7 +7,13 @@ use std::fs;
 use std::path::Path;
 use std::time::SystemTime;
 
-use crate::constants::FILE_META_DB_NAME;
+use crate::constants::{
+    CHUNKER_SCHEMA_VERSION, FILE_FAILURE_BASE_BACKOFF_SECS, FILE_FAILURE_CIRCUIT_THRESHOLD,
+    FILE_FAILURE_MAX_BACKOFF_SECS, FILE_META_DB_NAME,
+};
+
+use super::{IndexMetadata, LanguageStats};
+use crate::file::Language;
 
 /// Normalize a file path for consistent HashMap lookups.
 ///
@@ -37,6 +44,52 @@ pub struct FileMeta {
     pub chunk_count: usize,
     /// Chunk IDs in the vector store (for deletion on update)
     pub chunk_ids: Vec<u32>,
+    /// Chunker schema version the file was indexed with
+    #[serde(default = "default_chunker_version")]
+    pub chunker_version: u32,
+    /// SPDX license identifier from the file's header
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+}
+
+/// Entries written before schema versioning are version 1
+fn default_chunker_version() -> u32 {
+    1
+}
+
+/// Consecutive indexing failures of a file (parser crash, embedding error)
+///
+/// Failing files are retried with exponential backoff instead of on every
+/// save; a successful index or deletion clears the entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileFailure {
+    /// Number of consecutive failures
+    pub count: u32,
+    /// Unix timestamp of the last failure
+    pub last_failure: u64,
+    /// Error message of the last failure
+    pub last_error: String,
+}
+
+impl FileFailure {
+    /// Whether the circuit breaker is open (too many consecutive failures)
+    pub fn is_quarantined(&self) -> bool {
+        self.count >= FILE_FAILURE_CIRCUIT_THRESHOLD
+    }
+
+    /// Unix timestamp before which the file is not retried
+    ///
+    /// The delay doubles with each failure; once quarantined the file is only
+    /// retried after the maximum delay.
+    pub fn retry_after(&self) -> u64 {
+        let delay = if self.is_quarantined() {
+            FILE_FAILURE_MAX_BACKOFF_SECS
+        } else {
+            let exponent = self.count.saturating_sub(1).min(20);
+            (FILE_FAILURE_BASE_BACKOFF_SECS << exponent).min(FILE_FAILURE_MAX_BACKOFF_SECS)
+        };
+        self.last_failure + delay
+    }
 }
 
 /// Persistent store for file metadata - enables incremental indexing
@@ -57,6 +110,9 @@ pub struct FileMetaStore {
     pub last_full_index: Option<u64>,
     /// Version for format compatibility
     version: u32,
+    /// Files that failed to index, keyed like `files`
+    #[serde(default)]
+    failures: HashMap<String, FileFailure>,
 }
 
 impl FileMetaStore {
@@ -71,6 +127,7 @@ impl FileMetaStore {
             dimensions,
             last_full_index: None,
             version: Self::CURRENT_VERSION,
+            failures: HashMap::new(),
         }
     }
 
@@ -85,9 +142,10 @@ impl FileMetaStore {
 
             // Check if model changed - if so, invalidate everything
             if store.model_name != model_name || store.dimensions != dimensions {
-                println!(
+                out_print!(
                     "⚠️  Model changed ({} -> {}), full re-index required",
-                    store.model_name, model_name
+                    store.model_name,
+                    model_name
                 );
                 store = Self::new(model_name.to_string(), dimensions);
             }
@@ -103,13 +161,67 @@ impl FileMetaStore {
     }
 
     /// Save to database directory
+    ///
+    /// Also records the content checksum in metadata.json, so `codesearch
+    /// verify` can tell when file_meta.json no longer matches what was indexed,
+    /// and the files and chunks per language.
     pub fn save(&self, db_path: &Path) -> Result<()> {
         let meta_path = db_path.join(Self::FILENAME);
         let content = serde_json::to_string_pretty(self)?;
         fs::write(meta_path, content)?;
+        if let Err(e) = IndexMetadata::record_file_meta(
+            db_path,
+            &self.content_checksum(),
+            self.language_stats(),
+        ) {
+            tracing::warn!("⚠️  Could not record the content checksum: {:#}", e);
+        }
         Ok(())
     }
 
+    /// Checksum of the `(path, content hash)` pairs of all tracked files
+    pub fn content_checksum(&self) -> String {
+        rolling_checksum(self.files.iter().map(|(path, meta)| (path, &meta.hash)))
+    }
+
+    /// Tracked files and their chunks per language, most chunks first
+    pub fn language_stats(&self) -> Vec<LanguageStats> {
+        let mut by_language: HashMap<&'static str, (usize, usize)> = HashMap::new();
+        for (path, meta) in &self.files {
+            let language = Language::from_path(Path::new(path)).name();
+            let (files, chunks) = by_language.entry(language).or_default();
+            *files += 1;
+            *chunks += meta.chunk_count;
+        }
+        let mut stats: Vec<LanguageStats> = by_language
+            .into_iter()
+            .map(|(language, (files, chunks))| LanguageStats {
+                language: language.to_string(),
+                files,
+                chunks,
+            })
+            .collect();
+        stats.sort_by(|a, b| {
+            (b.chunks, b.files)
+                .cmp(&(a.chunks, a.files))
+                .then_with(|| a.language.cmp(&b.language))
+        });
+        stats
+    }
+
+    /// Number of chunks of a tracked file, by normalized path
+    pub fn chunk_count(&self, path: &str) -> Option<usize> {
+        self.files.get(path).map(|meta| meta.chunk_count)
+    }
+
+    /// Chunk IDs of every tracked file
+    pub fn chunk_ids_by_file(&self) -> HashMap<String, Vec<u32>> {
+        self.files
+            .iter()
+            .map(|(path, meta)| (path.clone(), meta.chunk_ids.clone()))
+            .collect()
+    }
+
     /// Migrate stored paths to normalized format.
     ///
     /// Existing stores may have Windows backslash paths (`C:\foo\bar.rs`) or
@@ -137,11 +249,17 @@ impl FileMetaStore {
     }
 
     /// Compute SHA256 hash of file content
+    ///
+    /// Hashes the decoded, BOM/CRLF-normalized text so a line-ending-only
+    /// difference (e.g. `core.autocrlf`) doesn't count as a change.
     pub fn compute_hash(path: &Path) -> Result<String> {
-        let content = fs::read(path)?;
+        Ok(Self::hash_content(&crate::file::read_source(path)?))
+    }
+
+    fn hash_content(content: &str) -> String {
         let mut hasher = Sha256::new();
-        hasher.update(&content);
-        Ok(format!("{:x}", hasher.finalize()))
+        hasher.update(content.as_bytes());
+        format!("{:x}", hasher.finalize())
     }
 
     /// Get file modification time as unix timestamp
@@ -168,6 +286,11 @@ impl FileMetaStore {
         let current_size = fs::metadata(path)?.len();
 
         if let Some(meta) = self.files.get(&path_str) {
+            // Indexed with an older chunk/embedding layout - always re-index
+            if meta.chunker_version != CHUNKER_SCHEMA_VERSION {
+                return Ok((true, meta.chunk_ids.clone()));
+            }
+
             // Quick check: if mtime and size unchanged, file is unchanged
             if meta.mtime == current_mtime && meta.size == current_size {
                 return Ok((false, vec![]));
@@ -191,7 +314,8 @@ impl FileMetaStore {
     /// Update metadata for a file after indexing
     pub fn update_file(&mut self, path: &Path, chunk_ids: Vec<u32>) -> Result<()> {
         let path_str = normalize_path(path);
-        let hash = Self::compute_hash(path)?;
+        let content = crate::file::read_source(path)?;
+        let hash = Self::hash_content(&content);
         let mtime = Self::get_mtime(path)?;
         let size = fs::metadata(path)?.len();
 
@@ -203,8 +327,11 @@ impl FileMetaStore {
                 size,
                 chunk_count: chunk_ids.len(),
                 chunk_ids,
+                chunker_version: CHUNKER_SCHEMA_VERSION,
+                license: crate::file::spdx_license(&content),
             },
         );
+        self.failures.remove(&normalize_path(path));
 
         Ok(())
     }
@@ -212,15 +339,105 @@ impl FileMetaStore {
     /// Mark a file as deleted
     pub fn remove_file(&mut self, path: &Path) -> Option<FileMeta> {
         let path_str = normalize_path(path);
+        self.failures.remove(&path_str);
         self.files.remove(&path_str)
     }
 
+    /// Record a failed attempt to index a file, returning the updated entry
+    pub fn record_failure(&mut self, path: &Path, error: &str) -> &FileFailure {
+        let failure = self
+            .failures
+            .entry(normalize_path(path))
+            .or_insert(FileFailure {
+                count: 0,
+                last_failure: 0,
+                last_error: String::new(),
+            });
+        failure.count += 1;
+        failure.last_failure = unix_now();
+        failure.last_error = error.to_string();
+        failure
+    }
+
+    /// Whether a file is still backing off after recent failures
+    pub fn should_skip(&self, path: &Path) -> bool {
+        self.failures
+            .get(&normalize_path(path))
+            .is_some_and(|f| unix_now() < f.retry_after())
+    }
+
+    /// Files currently failing to index, most failures first
+    pub fn failing_files(&self) -> Vec<(String, FileFailure)> {
+        let mut failing: Vec<(String, FileFailure)> = self
+            .failures
+            .iter()
+            .map(|(path, failure)| (path.clone(), failure.clone()))
+            .collect();
+        failing.sort_by(|a, b| b.1.count.cmp(&a.1.count).then_with(|| a.0.cmp(&b.0)));
+        failing
+    }
+
+    /// Read the file metadata of a database without loading it for indexing
+    /// (no model check, no output), for status reporting
+    pub fn read(db_path: &Path) -> Option<Self> {
+        fs::read_to_string(db_path.join(Self::FILENAME))
+            .ok()
+            .and_then(|content| serde_json::from_str::<Self>(&content).ok())
+    }
+
+    /// Read the failing files of a database, for status reporting
+    pub fn read_failing_files(db_path: &Path) -> Vec<(String, FileFailure)> {
+        Self::read(db_path)
+            .map(|store| store.failing_files())
+            .unwrap_or_default()
+    }
+
+    /// Count tracked files that changed or were deleted since they were indexed
+    ///
+    /// Only compares mtime and size (no hashing), so a file that was touched
+    /// without changing counts as stale.
+    pub fn count_stale_files(&self) -> usize {
+        self.files
+            .iter()
+            .filter(|(path, meta)| {
+                let path = Path::new(path.as_str());
+                match (Self::get_mtime(path), fs::metadata(path)) {
+                    (Ok(mtime), Ok(metadata)) => {
+                        meta.chunker_version != CHUNKER_SCHEMA_VERSION
+                            || meta.mtime != mtime
+                            || meta.size != metadata.len()
+                    }
+                    _ => true,
+                }
+            })
+            .count()
+    }
+
+    /// Tracked files with their metadata, keyed by normalized absolute path
+    pub fn entries(&self) -> impl Iterator<Item = (&String, &FileMeta)> {
+        self.files.iter()
+    }
+
     /// Get all tracked files
     #[allow(dead_code)] // Reserved for file listing feature
     pub fn tracked_files(&self) -> impl Iterator<Item = &String> {
         self.files.keys()
     }
 
+    /// SPDX license identifier recorded for a file at index time
+    pub fn license_of(&self, path: &str) -> Option<&str> {
+        self.files
+            .get(&normalize_path_str(path))
+            .and_then(|meta| meta.license.as_deref())
+    }
+
+    /// Tracked files with the SPDX license identifier of each, if any
+    pub fn file_licenses(&self) -> impl Iterator<Item = (&String, Option<&str>)> {
+        self.files
+            .iter()
+            .map(|(path, meta)| (path, meta.license.as_deref()))
+    }
+
     /// Find files that were deleted (exist in store but not on disk)
     pub fn find_deleted_files(&self) -> Vec<(String, Vec<u32>)> {
         self.files
@@ -249,16 +466,36 @@ impl FileMetaStore {
         self.files.clear();
         self.last_full_index = None;
     }
+}
 
-    /// Set last full index time
-    pub fn mark_full_index(&mut self) {
-        self.last_full_index = Some(
-            SystemTime::now()
-                .duration_since(SystemTime::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
-        );
-    }
+/// Order-independent checksum of `(key, value)` pairs
+///
+/// Each pair adds the first 8 bytes of its SHA-256 (wrapping), so adding or
+/// removing one pair changes the checksum by that pair's term only.
+pub fn rolling_checksum<K: AsRef<[u8]>, V: AsRef<[u8]>>(
+    pairs: impl IntoIterator<Item = (K, V)>,
+) -> String {
+    let mut sum = 0u64;
+    let mut count = 0u64;
+    for (key, value) in pairs {
+        let mut hasher = Sha256::new();
+        hasher.update(key.as_ref());
+        hasher.update([0u8]);
+        hasher.update(value.as_ref());
+        let digest = hasher.finalize();
+        let mut term = [0u8; 8];
+        term.copy_from_slice(&digest[..8]);
+        sum = sum.wrapping_add(u64::from_le_bytes(term));
+        count += 1;
+    }
+    format!("{}:{:016x}", count, sum)
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
 }
 
 #[derive(Debug)]
@@ -375,6 +612,8 @@ mod tests {
                 size: 100,
                 chunk_count: 2,
                 chunk_ids: vec![1, 2],
+                chunker_version: CHUNKER_SCHEMA_VERSION,
+                license: None,
             },
         );
         store.files.insert(
@@ -385,6 +624,8 @@ mod tests {
                 size: 200,
                 chunk_count: 3,
                 chunk_ids: vec![3, 4, 5],
+                chunker_version: CHUNKER_SCHEMA_VERSION,
+                license: None,
             },
         );
 
@@ -435,6 +676,70 @@ mod tests {
         assert_eq!(loaded.files.len(), 1);
     }
 
+    #[test]
+    fn test_old_chunker_version_forces_reindex() {
+        let dir = tempdir().unwrap();
+        let test_file = dir.path().join("test.rs");
+        fs::write(&test_file, "fn main() {}").unwrap();
+
+        let mut store = FileMetaStore::new("test-model".to_string(), 384);
+        store.update_file(&test_file, vec![7, 8]).unwrap();
+        assert!(!store.check_file(&test_file).unwrap().0);
+
+        // Entries saved before versioning deserialize as version 1
+        let key = normalize_path(&test_file);
+        let mut json = serde_json::to_value(&store.files[&key]).unwrap();
+        json.as_object_mut().unwrap().remove("chunker_version");
+        let legacy: FileMeta = serde_json::from_value(json).unwrap();
+        assert_eq!(legacy.chunker_version, 1);
+        store.files.insert(key, legacy);
+
+        let (needs_reindex, old_chunks) = store.check_file(&test_file).unwrap();
+        assert!(needs_reindex);
+        assert_eq!(old_chunks, vec![7, 8]);
+    }
+
+    #[test]
+    fn test_failing_file_backoff_and_reset() {
+        let dir = tempdir().unwrap();
+        let test_file = dir.path().join("broken.rs");
+        fs::write(&test_file, "fn broken(").unwrap();
+
+        let mut store = FileMetaStore::new("test-model".to_string(), 384);
+        assert!(!store.should_skip(&test_file));
+
+        for _ in 0..FILE_FAILURE_CIRCUIT_THRESHOLD {
+            store.record_failure(&test_file, "parser crashed");
+        }
+        assert!(store.should_skip(&test_file));
+        let failing = store.failing_files();
+        assert_eq!(failing.len(), 1);
+        assert!(failing[0].1.is_quarantined());
+        assert_eq!(
+            failing[0].1.retry_after() - failing[0].1.last_failure,
+            FILE_FAILURE_MAX_BACKOFF_SECS
+        );
+
+        // A successful index clears the failure record
+        store.update_file(&test_file, vec![1]).unwrap();
+        assert!(!store.should_skip(&test_file));
+        assert!(store.failing_files().is_empty());
+    }
+
+    #[test]
+    fn test_hash_ignores_line_endings_and_bom() {
+        let dir = tempdir().unwrap();
+        let lf = dir.path().join("lf.rs");
+        let crlf = dir.path().join("crlf.rs");
+        fs::write(&lf, "fn main() {\n}\n").unwrap();
+        fs::write(&crlf, "\u{FEFF}fn main() {\r\n}\r\n").unwrap();
+
+        assert_eq!(
+            FileMetaStore::compute_hash(&lf).unwrap(),
+            FileMetaStore::compute_hash(&crlf).unwrap()
+        );
+    }
+
     // =========================================================================
     // Path comparison tests — verify that different path formats match correctly
     // These test the exact bug patterns that have caused issues in production.
@@ -512,4 +817,58 @@ mod tests {
         let from_str = normalize_path_str(input);
         assert_eq!(from_path, from_str);
     }
+
+    #[test]
+    fn test_count_stale_files() {
+        let dir = tempdir().unwrap();
+        let kept = dir.path().join("kept.rs");
+        let changed = dir.path().join("changed.rs");
+        let deleted = dir.path().join("deleted.rs");
+        for file in [&kept, &changed, &deleted] {
+            fs::write(file, "fn main() {}").unwrap();
+        }
+
+        let mut store = FileMetaStore::new("test-model".to_string(), 384);
+        for file in [&kept, &changed, &deleted] {
+            store.update_file(file, vec![1]).unwrap();
+        }
+        store.save(dir.path()).unwrap();
+        assert_eq!(store.count_stale_files(), 0);
+
+        fs::write(&changed, "fn main() { println!(); }").unwrap();
+        fs::remove_file(&deleted).unwrap();
+        let read = FileMetaStore::read(dir.path()).unwrap();
+        assert_eq!(read.count_stale_files(), 2);
+    }
+
+    #[test]
+    fn test_content_checksum_is_recorded_on_save() {
+        let dir = tempdir().unwrap();
+        let a = dir.path().join("a.rs");
+        let b = dir.path().join("b.rs");
+        fs::write(&a, "fn a() {}").unwrap();
+        fs::write(&b, "fn b() {}").unwrap();
+        IndexMetadata::new("minilm-l6-q", "AllMiniLML6V2Q", 384)
+            .save(dir.path())
+            .unwrap();
+
+        let mut store = FileMetaStore::new("test-model".to_string(), 384);
+        store.update_file(&a, vec![1]).unwrap();
+        let only_a = store.content_checksum();
+        store.update_file(&b, vec![2]).unwrap();
+        store.save(dir.path()).unwrap();
+        let recorded = IndexMetadata::require(dir.path()).unwrap().content_checksum;
+        assert_eq!(recorded, Some(store.content_checksum()));
+
+        // Rolling: independent of order, and removing a file undoes its term
+        assert_eq!(
+            rolling_checksum([("x", "1"), ("y", "2")]),
+            rolling_checksum([("y", "2"), ("x", "1")])
+        );
+        store.remove_file(&b);
+        assert_eq!(store.content_checksum(), only_a);
+        fs::write(&a, "fn a() { changed() }").unwrap();
+        store.update_file(&a, vec![3]).unwrap();
+        assert_ne!(store.content_checksum(), only_a);
+    }
 }
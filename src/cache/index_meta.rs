@@ -0,0 +1,407 @@
+//! Index metadata (metadata.json)
+//!
+//! Records which embedding model(s) built an index and any switch to another
+//! model in progress, the index's language scope and its files and chunks per
+//! language. Files carry a `schema_version`; older layouts are migrated step
+//! by step on load, and anything that doesn't fit the current layout is an
+//! error instead of a silent default.
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::Path;
+
+use crate::constants::INDEX_METADATA_FILE;
+use crate::embed::ModelType;
+use crate::file::Language;
+
+/// Metadata of an index, stored as `metadata.json` in the database directory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndexMetadata {
+    pub schema_version: u32,
+    /// Short name of the code embedding model (e.g. "minilm-l6-q")
+    pub model_short_name: String,
+    /// Full name of the code embedding model
+    pub model_name: String,
+    /// Dimensions of the stored code vectors
+    pub dimensions: usize,
+    /// When the index was last built (RFC 3339)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indexed_at: Option<String>,
+    /// Prose model of a dual-embedded index
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prose_model_short_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prose_dimensions: Option<usize>,
+    /// Languages the index is restricted to (absent = all)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub languages: Option<Vec<String>>,
+    /// Dominant language of the project, boosted in search results
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub primary_language: Option<String>,
+    /// Indexed files and chunks per language, most chunks first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub language_stats: Vec<LanguageStats>,
+    /// Checksum of the (path, content hash) pairs in file_meta.json as last saved
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_checksum: Option<String>,
+    /// Directories (relative to the project root) pruned to keep the index
+    /// under its size budget, and left out of later indexing
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pruned_dirs: Vec<String>,
+    /// Lines of code stored before and after each chunk (absent = the default)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_lines: Option<usize>,
+    /// Switch to another code model in progress, as of its last run
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub migration: Option<ModelMigration>,
+}
+
+/// A switch of the code embedding model, staged chunk by chunk
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelMigration {
+    /// Short name of the model being switched to
+    pub model_short_name: String,
+    pub dimensions: usize,
+    /// Chunks that have a vector from the new model
+    pub migrated_chunks: usize,
+    pub total_chunks: usize,
+}
+
+/// Indexed files and chunks of one language
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LanguageStats {
+    /// Language name, as in `Language::name` (e.g. "Rust", "C++")
+    pub language: String,
+    pub files: usize,
+    pub chunks: usize,
+}
+
+/// Languages that document or configure a project, never its primary language
+const SUPPORTING_LANGUAGES: &[Language] = &[
+    Language::Markdown,
+    Language::Json,
+    Language::Yaml,
+    Language::Toml,
+    Language::Xml,
+    Language::PlainText,
+    Language::Unknown,
+];
+
+/// Upgrades a raw metadata object by one schema version
+type Migration = fn(&mut Map<String, Value>) -> Result<()>;
+
+/// Upgrade steps: `MIGRATIONS[n]` turns schema version `n` into `n + 1`
+const MIGRATIONS: &[Migration] = &[migrate_v0_to_v1];
+
+impl IndexMetadata {
+    pub const CURRENT_VERSION: u32 = MIGRATIONS.len() as u32;
+
+    /// Metadata for an index built with the given code model, at the current schema version
+    pub fn new(model_short_name: &str, model_name: &str, dimensions: usize) -> Self {
+        Self {
+            schema_version: Self::CURRENT_VERSION,
+            model_short_name: model_short_name.to_string(),
+            model_name: model_name.to_string(),
+            dimensions,
+            indexed_at: Some(chrono::Utc::now().to_rfc3339()),
+            prose_model_short_name: None,
+            prose_dimensions: None,
+            languages: None,
+            primary_language: None,
+            language_stats: Vec::new(),
+            content_checksum: None,
+            pruned_dirs: Vec::new(),
+            context_lines: None,
+            migration: None,
+        }
+    }
+
+    /// Load the metadata of a database; `Ok(None)` when there is no metadata.json
+    pub fn load(db_path: &Path) -> Result<Option<Self>> {
+        let path = db_path.join(INDEX_METADATA_FILE);
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Cannot read {}", path.display())),
+        };
+        Self::parse(&content)
+            .map(Some)
+            .with_context(|| format!("Invalid {}", path.display()))
+    }
+
+    /// Load the metadata of a database, failing when there is none
+    pub fn require(db_path: &Path) -> Result<Self> {
+        Self::load(db_path)?
+            .ok_or_else(|| anyhow!("No {} found in {}", INDEX_METADATA_FILE, db_path.display()))
+    }
+
+    /// Parse metadata.json content, migrating older schema versions
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut json: Value = serde_json::from_str(content)?;
+        let object = json
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("expected a JSON object"))?;
+
+        let version = match object.get("schema_version") {
+            None => 0,
+            Some(v) => v
+                .as_u64()
+                .ok_or_else(|| anyhow!("schema_version must be a number"))?
+                as u32,
+        };
+        if version > Self::CURRENT_VERSION {
+            bail!(
+                "schema version {} is newer than this codesearch supports ({})",
+                version,
+                Self::CURRENT_VERSION
+            );
+        }
+        for migrate in &MIGRATIONS[version as usize..] {
+            migrate(object)?;
+        }
+        object.insert("schema_version".to_string(), Self::CURRENT_VERSION.into());
+
+        let metadata: Self = serde_json::from_value(json)?;
+        metadata.validate()?;
+        Ok(metadata)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.model_short_name.trim().is_empty() {
+            bail!("model_short_name is empty");
+        }
+        if self.dimensions == 0 {
+            bail!("dimensions must be greater than 0");
+        }
+        match (&self.prose_model_short_name, self.prose_dimensions) {
+            (Some(_), Some(0)) => bail!("prose_dimensions must be greater than 0"),
+            (Some(_), None) | (None, Some(_)) => {
+                bail!("prose_model_short_name and prose_dimensions must be set together")
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Write metadata.json at the current schema version
+    pub fn save(&self, db_path: &Path) -> Result<()> {
+        let metadata = Self {
+            schema_version: Self::CURRENT_VERSION,
+            ..self.clone()
+        };
+        fs::write(
+            db_path.join(INDEX_METADATA_FILE),
+            serde_json::to_string_pretty(&metadata)?,
+        )?;
+        Ok(())
+    }
+
+    /// Record the content checksum and language breakdown of file_meta.json
+    /// in an existing metadata.json
+    pub fn record_file_meta(
+        db_path: &Path,
+        checksum: &str,
+        language_stats: Vec<LanguageStats>,
+    ) -> Result<()> {
+        let Some(mut metadata) = Self::load(db_path)? else {
+            return Ok(());
+        };
+        if metadata.content_checksum.as_deref() == Some(checksum)
+            && metadata.language_stats == language_stats
+        {
+            return Ok(());
+        }
+        metadata.content_checksum = Some(checksum.to_string());
+        metadata.set_language_stats(language_stats);
+        metadata.save(db_path)
+    }
+
+    /// Record the per-language breakdown, and the language with the most
+    /// chunks (docs and config aside) as the primary language
+    pub fn set_language_stats(&mut self, language_stats: Vec<LanguageStats>) {
+        self.primary_language = language_stats
+            .iter()
+            .find(|stats| {
+                Language::from_name(&stats.language)
+                    .is_some_and(|language| !SUPPORTING_LANGUAGES.contains(&language))
+            })
+            .map(|stats| stats.language.clone());
+        self.language_stats = language_stats;
+    }
+
+    /// Code model to embed queries with, corrected to match the stored dimensions
+    ///
+    /// Returns the model and whether a correction was needed (see `ModelType::for_index`).
+    pub fn model_type(&self) -> (ModelType, bool) {
+        ModelType::for_index(Some(&self.model_short_name), Some(self.dimensions))
+    }
+
+    /// Prose model of a dual-embedded index as `(short_name, dimensions)`
+    pub fn prose(&self) -> Option<(String, usize)> {
+        Some((self.prose_model_short_name.clone()?, self.prose_dimensions?))
+    }
+
+    /// The model to embed with in the prose space of a dual-embedded index
+    ///
+    /// Unlike the code model, it isn't guessed from the dimensions: an unknown
+    /// name, or a model producing other dimensions, is an error.
+    pub fn prose_model(&self) -> Option<Result<ModelType>> {
+        let (name, dims) = self.prose()?;
+        Some(match ModelType::parse(&name) {
+            Some(model) if model.dimensions() == dims => Ok(model),
+            Some(model) => Err(anyhow!(
+                "prose model '{}' produces {} dimensions, but the index has {}",
+                name,
+                model.dimensions(),
+                dims
+            )),
+            None => Err(anyhow!("unknown prose model '{}'", name)),
+        })
+    }
+
+    /// Lines of code stored before and after each chunk
+    pub fn context_lines(&self) -> usize {
+        self.context_lines
+            .unwrap_or(crate::chunker::DEFAULT_CONTEXT_LINES)
+    }
+
+    /// Languages the index is restricted to (`None` = all)
+    pub fn language_filter(&self) -> Option<Vec<Language>> {
+        let languages: Vec<Language> = self
+            .languages
+            .as_ref()?
+            .iter()
+            .filter_map(|name| Language::from_name(name))
+            .collect();
+        (!languages.is_empty()).then_some(languages)
+    }
+
+    /// Up to `top` code languages (docs and config aside) with the most
+    /// chunks, each with its chunks relative to the largest (1.0 for the first)
+    pub fn language_shares(&self, top: usize) -> Vec<(Language, f32)> {
+        let code: Vec<(Language, usize)> = self
+            .language_stats
+            .iter()
+            .filter_map(|stats| Some((Language::from_name(&stats.language)?, stats.chunks)))
+            .filter(|(language, chunks)| !SUPPORTING_LANGUAGES.contains(language) && *chunks > 0)
+            .take(top)
+            .collect();
+        let Some(&(_, largest)) = code.first() else {
+            return Vec::new();
+        };
+        code.into_iter()
+            .map(|(language, chunks)| (language, chunks as f32 / largest as f32))
+            .collect()
+    }
+
+    /// Restrict the index to `languages` (`None` = all)
+    pub fn set_language_filter(&mut self, languages: Option<&[Language]>) {
+        self.languages =
+            languages.map(|languages| languages.iter().map(|l| l.name().to_string()).collect());
+    }
+}
+
+/// Version 0: files written before schema versioning
+///
+/// Some only recorded one of the two model names, or no dimensions. Both are
+/// recovered from the model when it is known.
+fn migrate_v0_to_v1(object: &mut Map<String, Value>) -> Result<()> {
+    let short_name = object
+        .get("model_short_name")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let name = object
+        .get("model_name")
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let model = short_name
+        .as_deref()
+        .or(name.as_deref())
+        .and_then(ModelType::parse);
+
+    if short_name.is_none() {
+        let model =
+            model.ok_or_else(|| anyhow!("no model_short_name and no recognizable model_name"))?;
+        object.insert("model_short_name".to_string(), model.short_name().into());
+    }
+    if name.is_none() {
+        let name = match model {
+            Some(model) => format!("{:?}", model),
+            None => short_name.unwrap_or_default(),
+        };
+        object.insert("model_name".to_string(), name.into());
+    }
+    if !object.contains_key("dimensions") {
+        let model = model.ok_or_else(|| anyhow!("no dimensions and unknown model"))?;
+        object.insert("dimensions".to_string(), model.dimensions().into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_migrates_and_rejects_malformed() {
+        // Legacy files without schema_version are migrated
+        let legacy = IndexMetadata::parse(r#"{"model_short_name": "bge-base"}"#).unwrap();
+        assert_eq!(legacy.schema_version, IndexMetadata::CURRENT_VERSION);
+        assert_eq!(legacy.dimensions, 768);
+        assert_eq!(legacy.model_name, "BGEBaseENV15");
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut metadata = IndexMetadata::new("minilm-l6-q", "AllMiniLML6V2Q", 384);
+        metadata.set_language_filter(Some(&[Language::Rust]));
+        let stats = |language: &str, files, chunks| LanguageStats {
+            language: language.to_string(),
+            files,
+            chunks,
+        };
+        metadata.set_language_stats(vec![stats("Markdown", 40, 300), stats("C++", 12, 90)]);
+        assert_eq!(metadata.primary_language.as_deref(), Some("C++"));
+        metadata.set_language_stats(vec![
+            stats("Go", 50, 400),
+            stats("Markdown", 40, 300),
+            stats("C++", 45, 380),
+            stats("Python", 3, 20),
+        ]);
+        assert_eq!(
+            metadata.language_shares(2),
+            [(Language::Go, 1.0), (Language::Cpp, 0.95)]
+        );
+        metadata.save(dir.path()).unwrap();
+        let loaded = IndexMetadata::require(dir.path()).unwrap();
+        assert_eq!(loaded, metadata);
+        assert_eq!(loaded.language_filter(), Some(vec![Language::Rust]));
+
+        for malformed in [
+            r#"{"model_short_name": "minilm-l6-q", "dimensions": "384"}"#,
+            r#"{"model_short_name": "mystery", "model_name": "Mystery"}"#,
+            r#"{"schema_version": 1, "model_short_name": "bge-base", "dimensions": 768}"#,
+            r#"{"schema_version": 99, "model_short_name": "bge-base", "model_name": "x", "dimensions": 768}"#,
+            r#"{"model_short_name": "bge-base", "dimensions": 0}"#,
+            r#"["not", "an", "object"]"#,
+        ] {
+            assert!(IndexMetadata::parse(malformed).is_err(), "{}", malformed);
+        }
+
+        // The prose model is never guessed
+        assert!(metadata.prose_model().is_none());
+        metadata.prose_model_short_name = Some("bge-small".into());
+        metadata.prose_dimensions = Some(384);
+        assert_eq!(
+            metadata.prose_model().unwrap().unwrap(),
+            ModelType::BGESmallENV15
+        );
+        metadata.prose_dimensions = Some(768);
+        assert!(metadata.prose_model().unwrap().is_err());
+        metadata.prose_model_short_name = Some("mystery".into());
+        assert!(metadata.prose_model().unwrap().is_err());
+
+        let empty = tempfile::tempdir().unwrap();
+        assert_eq!(IndexMetadata::load(empty.path()).unwrap(), None);
+        assert!(IndexMetadata::require(empty.path()).is_err());
+    }
+}
@@ -1,6 +1,8 @@
 mod file_meta;
+mod index_meta;
 
-pub use file_meta::{normalize_path, normalize_path_str, FileMetaStore};
+pub use file_meta::{normalize_path, normalize_path_str, rolling_checksum, FileMetaStore};
+pub use index_meta::{IndexMetadata, LanguageStats, ModelMigration};
 
 use moka::sync::Cache;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -245,26 +245,12 @@ impl LanguageExtractor for RustExtractor {
         // Tree-sitter includes them as named siblings in some grammars
 
         // For now, we'll look at the previous siblings
-        let parent = node.parent()?;
-        let node_index = (0..parent.named_child_count())
-            .find(|&i| parent.named_child(i as u32).map(|c| c.id()) == Some(node.id()))?;
-
-        if node_index > 0 {
-            if let Some(prev) = parent.named_child((node_index - 1) as u32) {
-                if prev.kind() == "line_comment" || prev.kind() == "block_comment" {
-                    if let Ok(text) = prev.utf8_text(source) {
-                        // Check if it's a doc comment (/// or /**)
-                        if text.trim_start().starts_with("///")
-                            || text.trim_start().starts_with("/**")
-                        {
-                            return Some(text.to_string());
-                        }
-                    }
-                }
-            }
-        }
-
-        None
+        preceding_doc_comment(
+            node,
+            source,
+            &["line_comment", "block_comment"],
+            &["///", "/**"],
+        )
     }
 
     fn classify(&self, node: Node) -> ChunkKind {
@@ -490,23 +476,7 @@ impl LanguageExtractor for TypeScriptExtractor {
     fn extract_docstring(&self, node: Node, source: &[u8]) -> Option<String> {
         // Look for JSDoc comments (/** */) before the node
         // Similar to Rust approach
-        let parent = node.parent()?;
-        let node_index = (0..parent.named_child_count())
-            .find(|&i| parent.named_child(i as u32).map(|c| c.id()) == Some(node.id()))?;
-
-        if node_index > 0 {
-            if let Some(prev) = parent.named_child((node_index - 1) as u32) {
-                if prev.kind() == "comment" {
-                    if let Ok(text) = prev.utf8_text(source) {
-                        if text.trim_start().starts_with("/**") {
-                            return Some(text.to_string());
-                        }
-                    }
-                }
-            }
-        }
-
-        None
+        preceding_doc_comment(node, source, &["comment"], &["/**"])
     }
 
     fn classify(&self, node: Node) -> ChunkKind {
@@ -770,22 +740,7 @@ impl LanguageExtractor for CSharpExtractor {
 
     fn extract_docstring(&self, node: Node, source: &[u8]) -> Option<String> {
         // C# uses /// XML doc comments
-        let parent = node.parent()?;
-        let node_index = (0..parent.named_child_count())
-            .find(|&i| parent.named_child(i as u32).map(|c| c.id()) == Some(node.id()))?;
-
-        if node_index > 0 {
-            if let Some(prev) = parent.named_child((node_index - 1) as u32) {
-                if prev.kind() == "comment" {
-                    if let Ok(text) = prev.utf8_text(source) {
-                        if text.trim_start().starts_with("///") {
-                            return Some(text.to_string());
-                        }
-                    }
-                }
-            }
-        }
-        None
+        preceding_doc_comment(node, source, &["comment"], &["///"])
     }
 
     fn classify(&self, node: Node) -> ChunkKind {
@@ -854,18 +809,7 @@ impl LanguageExtractor for GoExtractor {
 
     fn extract_docstring(&self, node: Node, source: &[u8]) -> Option<String> {
         // Go uses // comments before declarations
-        let parent = node.parent()?;
-        let node_index = (0..parent.named_child_count())
-            .find(|&i| parent.named_child(i as u32).map(|c| c.id()) == Some(node.id()))?;
-
-        if node_index > 0 {
-            if let Some(prev) = parent.named_child((node_index - 1) as u32) {
-                if prev.kind() == "comment" {
-                    return prev.utf8_text(source).ok().map(String::from);
-                }
-            }
-        }
-        None
+        preceding_doc_comment(node, source, &["comment"], &[])
     }
 
     fn classify(&self, node: Node) -> ChunkKind {
@@ -940,22 +884,7 @@ impl LanguageExtractor for JavaExtractor {
 
     fn extract_docstring(&self, node: Node, source: &[u8]) -> Option<String> {
         // Java uses /** */ Javadoc comments
-        let parent = node.parent()?;
-        let node_index = (0..parent.named_child_count())
-            .find(|&i| parent.named_child(i as u32).map(|c| c.id()) == Some(node.id()))?;
-
-        if node_index > 0 {
-            if let Some(prev) = parent.named_child((node_index - 1) as u32) {
-                if prev.kind() == "block_comment" || prev.kind() == "comment" {
-                    if let Ok(text) = prev.utf8_text(source) {
-                        if text.trim_start().starts_with("/**") {
-                            return Some(text.to_string());
-                        }
-                    }
-                }
-            }
-        }
-        None
+        preceding_doc_comment(node, source, &["block_comment", "comment"], &["/**"])
     }
 
     fn classify(&self, node: Node) -> ChunkKind {
@@ -1006,23 +935,29 @@ fn find_identifier(node: Node, source: &[u8]) -> Option<String> {
 
 /// Helper: extract C-style doc comments (/** */ or ///) before a node
 fn extract_c_style_doc(node: Node, source: &[u8]) -> Option<String> {
-    let parent = node.parent()?;
-    let node_index = (0..parent.named_child_count())
-        .find(|&i| parent.named_child(i as u32).map(|c| c.id()) == Some(node.id()))?;
-
-    if node_index > 0 {
-        if let Some(prev) = parent.named_child((node_index - 1) as u32) {
-            if prev.kind() == "comment" || prev.kind() == "block_comment" {
-                if let Ok(text) = prev.utf8_text(source) {
-                    let trimmed = text.trim_start();
-                    if trimmed.starts_with("///") || trimmed.starts_with("/**") {
-                        return Some(text.to_string());
-                    }
-                }
-            }
-        }
-    }
-    None
+    preceding_doc_comment(node, source, &["comment", "block_comment"], &["///", "/**"])
+}
+
+/// Helper: the comment right before a node, if it is of one of `kinds` and
+/// starts with one of `prefixes` (any comment when `prefixes` is empty)
+///
+/// The previous named sibling holds the doc comment, if any. Finding the
+/// node's index among its parent's children instead made files with many
+/// top-level items quadratic to chunk.
+fn preceding_doc_comment(
+    node: Node,
+    source: &[u8],
+    kinds: &[&str],
+    prefixes: &[&str],
+) -> Option<String> {
+    let prev = node.prev_named_sibling()?;
+    if !kinds.contains(&prev.kind()) {
+        return None;
+    }
+    let text = prev.utf8_text(source).ok()?;
+    let trimmed = text.trim_start();
+    (prefixes.is_empty() || prefixes.iter().any(|p| trimmed.starts_with(p)))
+        .then(|| text.to_string())
 }
 
 #[cfg(test)]
@@ -0,0 +1,281 @@
+//! Chunking for build and CI configuration
+//!
+//! Dockerfiles are split per build stage, Makefiles per target and GitHub
+//! Actions workflows per job, each with the stage/target/job name as its
+//! signature, so infrastructure questions ("where do we set the node version
+//! in CI") land on the right block instead of an arbitrary line window.
+
+use super::schema::{to_chunks, Definition, YamlKeys};
+use super::{Chunk, ChunkKind};
+use crate::cache::normalize_path;
+use std::path::Path;
+
+/// Kind of infrastructure file, detected from its path
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InfraFile {
+    Dockerfile,
+    Makefile,
+    GithubWorkflow,
+}
+
+impl InfraFile {
+    /// Detect an infrastructure file from its path
+    pub fn detect(path: &Path) -> Option<Self> {
+        let file_name = path.file_name()?.to_string_lossy().to_lowercase();
+        let normalized = normalize_path(path).to_lowercase();
+
+        if file_name == "dockerfile"
+            || file_name == "containerfile"
+            || file_name.starts_with("dockerfile.")
+            || file_name.ends_with(".dockerfile")
+        {
+            Some(Self::Dockerfile)
+        } else if matches!(file_name.as_str(), "makefile" | "gnumakefile")
+            || file_name.ends_with(".mk")
+        {
+            Some(Self::Makefile)
+        } else if normalized.contains(".github/workflows/")
+            && (file_name.ends_with(".yml") || file_name.ends_with(".yaml"))
+        {
+            Some(Self::GithubWorkflow)
+        } else {
+            None
+        }
+    }
+
+    /// Split the file into stage/target/job chunks
+    pub fn chunk(self, path: &Path, content: &str) -> Vec<Chunk> {
+        match self {
+            Self::Dockerfile => chunk_dockerfile(path, content),
+            Self::Makefile => chunk_makefile(path, content),
+            Self::GithubWorkflow => chunk_github_workflow(path, content),
+        }
+    }
+}
+
+/// One chunk per build stage (`FROM ... [AS name]` up to the next `FROM`)
+fn chunk_dockerfile(path: &Path, content: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut defs: Vec<Definition> = Vec::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim();
+        let words: Vec<&str> = trimmed.split_whitespace().collect();
+        if !words
+            .first()
+            .is_some_and(|w| w.eq_ignore_ascii_case("FROM"))
+        {
+            continue;
+        }
+        if let Some(previous) = defs.last_mut() {
+            previous.end_line = i;
+        }
+
+        let image = words
+            .iter()
+            .skip(1)
+            .find(|w| !w.starts_with("--"))
+            .copied()
+            .unwrap_or("");
+        let stage = words
+            .iter()
+            .position(|w| w.eq_ignore_ascii_case("AS"))
+            .and_then(|p| words.get(p + 1))
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| defs.len().to_string());
+
+        defs.push(Definition {
+            kind: ChunkKind::Block,
+            label: format!("Stage: {}", stage),
+            signature: trimmed.to_string(),
+            start_line: i,
+            end_line: lines.len(),
+            parent: None,
+            tags: vec![format!("STAGE {}", stage), format!("IMAGE {}", image)],
+            emit: true,
+        });
+    }
+
+    trim_trailing_blank_lines(&mut defs, &lines);
+    to_chunks(path, &lines, defs, "#")
+}
+
+/// One chunk per target: the `target: deps` line plus its recipe lines
+fn chunk_makefile(path: &Path, content: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut defs: Vec<Definition> = Vec::new();
+    let mut current: Option<usize> = None;
+
+    for (i, line) in lines.iter().enumerate() {
+        let continues_previous = i > 0 && lines[i - 1].trim_end().ends_with('\\');
+        let in_recipe = current.is_some()
+            && (line.starts_with('\t') || line.trim().is_empty() || continues_previous);
+        if in_recipe {
+            if let Some(idx) = current {
+                defs[idx].end_line = i + 1;
+            }
+            continue;
+        }
+        current = None;
+
+        let Some(targets) = makefile_targets(line) else {
+            continue;
+        };
+        let name = targets.join(" ");
+        defs.push(Definition {
+            kind: ChunkKind::Function,
+            label: format!("Target: {}", name),
+            signature: line.trim().to_string(),
+            start_line: i,
+            end_line: i + 1,
+            parent: None,
+            tags: targets.iter().map(|t| format!("TARGET {}", t)).collect(),
+            emit: true,
+        });
+        current = Some(defs.len() - 1);
+    }
+
+    trim_trailing_blank_lines(&mut defs, &lines);
+    to_chunks(path, &lines, defs, "#")
+}
+
+/// Target names of a rule line (`build test: deps`), excluding special
+/// targets (`.PHONY`) and variable assignments (`X := y`)
+fn makefile_targets(line: &str) -> Option<Vec<String>> {
+    if line.starts_with([' ', '#']) {
+        return None;
+    }
+    let colon = line.find(':')?;
+    let after = &line[colon + 1..];
+    if after.starts_with('=') || after.starts_with(":=") || line[..colon].contains('=') {
+        return None;
+    }
+    let targets: Vec<String> = line[..colon]
+        .split_whitespace()
+        .filter(|t| !t.starts_with('.') && !t.contains('$'))
+        .map(str::to_string)
+        .collect();
+    (!targets.is_empty()).then_some(targets)
+}
+
+/// One chunk per job under the top-level `jobs:` key
+fn chunk_github_workflow(path: &Path, content: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    let yaml = YamlKeys::new(&lines);
+    let mut defs: Vec<Definition> = Vec::new();
+
+    let workflow = lines
+        .iter()
+        .find_map(|l| l.strip_prefix("name:"))
+        .map(|n| n.trim().trim_matches(['"', '\'']).to_string());
+
+    if let Some(jobs) = yaml.top_level("jobs") {
+        for job in yaml.children(jobs) {
+            let (start, _, name) = &yaml.keys[job];
+            let end = yaml.block_end(job);
+            let display_name = yaml
+                .children(job)
+                .into_iter()
+                .find(|&c| yaml.keys[c].2 == "name")
+                .and_then(|c| lines[yaml.keys[c].0].split_once(':'))
+                .map(|(_, v)| v.trim().trim_matches(['"', '\'']).to_string())
+                .filter(|v| !v.is_empty());
+            let signature = match (&workflow, &display_name) {
+                (Some(w), Some(d)) => format!("{} / job {} ({})", w, name, d),
+                (Some(w), None) => format!("{} / job {}", w, name),
+                (None, Some(d)) => format!("job {} ({})", name, d),
+                (None, None) => format!("job {}", name),
+            };
+            defs.push(Definition {
+                kind: ChunkKind::Function,
+                label: format!("Job: {}", name),
+                signature,
+                start_line: *start,
+                end_line: end,
+                parent: None,
+                tags: vec![format!("JOB {}", name)],
+                emit: true,
+            });
+        }
+    }
+
+    trim_trailing_blank_lines(&mut defs, &lines);
+    to_chunks(path, &lines, defs, "#")
+}
+
+/// Blank (and comment-only) lines at the end of a block belong to what follows
+fn trim_trailing_blank_lines(defs: &mut [Definition], lines: &[&str]) {
+    for def in defs.iter_mut() {
+        while def.end_line > def.start_line + 1 {
+            let last = lines[def.end_line - 1].trim();
+            if last.is_empty() || last.starts_with('#') {
+                def.end_line -= 1;
+            } else {
+                break;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_infra_files() {
+        assert_eq!(
+            InfraFile::detect(Path::new("docker/Dockerfile.prod")),
+            Some(InfraFile::Dockerfile)
+        );
+        assert_eq!(
+            InfraFile::detect(Path::new("Makefile")),
+            Some(InfraFile::Makefile)
+        );
+        assert_eq!(
+            InfraFile::detect(Path::new("/repo/.github/workflows/ci.yml")),
+            Some(InfraFile::GithubWorkflow)
+        );
+        assert_eq!(InfraFile::detect(Path::new("config/app.yml")), None);
+    }
+
+    #[test]
+    fn test_dockerfile_stages() {
+        let dockerfile = "ARG NODE_VERSION=20\nFROM node:${NODE_VERSION} AS build\nRUN npm ci\n\nFROM --platform=linux/amd64 nginx:alpine\nCOPY --from=build /app/dist /usr/share/nginx/html\n";
+        let chunks = InfraFile::Dockerfile.chunk(Path::new("Dockerfile"), dockerfile);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (1, 3));
+        assert_eq!(chunks[0].context[1], "Stage: build");
+        assert_eq!(
+            chunks[0].tags,
+            vec!["STAGE build", "IMAGE node:${NODE_VERSION}"]
+        );
+        assert_eq!(chunks[1].tags, vec!["STAGE 1", "IMAGE nginx:alpine"]);
+    }
+
+    #[test]
+    fn test_makefile_targets() {
+        let makefile = "CARGO := cargo\n.PHONY: build test\n\n# Build everything\nbuild: deps\n\t$(CARGO) build \\\n  --release\n\ntest:\n\t$(CARGO) test\n";
+        let chunks = InfraFile::Makefile.chunk(Path::new("Makefile"), makefile);
+        let names: Vec<&str> = chunks
+            .iter()
+            .map(|c| c.context.last().unwrap().as_str())
+            .collect();
+        assert_eq!(names, vec!["Target: build", "Target: test"]);
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (4, 7));
+        assert_eq!(chunks[0].docstring.as_deref(), Some("# Build everything"));
+    }
+
+    #[test]
+    fn test_github_workflow_jobs() {
+        let workflow = "name: CI\non:\n  push:\njobs:\n  test:\n    name: Unit tests\n    runs-on: ubuntu-latest\n    steps:\n      - uses: actions/setup-node@v4\n        with:\n          node-version: 20\n  lint:\n    runs-on: ubuntu-latest\n";
+        let chunks =
+            InfraFile::GithubWorkflow.chunk(Path::new(".github/workflows/ci.yml"), workflow);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(
+            chunks[0].signature.as_deref(),
+            Some("CI / job test (Unit tests)")
+        );
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (4, 11));
+        assert_eq!(chunks[1].tags, vec!["JOB lint"]);
+    }
+}
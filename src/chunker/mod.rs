@@ -8,8 +8,12 @@ mod dedup;
 mod extractor;
 mod fallback;
 mod grammar;
+mod infra;
 mod parser;
+mod routes;
+mod schema;
 mod semantic;
+mod sql;
 mod tree_sitter;
 
 pub use semantic::SemanticChunker;
@@ -59,6 +63,9 @@ pub struct Chunk {
 
     /// Lines of code immediately after this chunk (for context)
     pub context_next: Option<String>,
+
+    /// Searchable tags detected in the chunk (e.g., HTTP routes like "POST /users")
+    pub tags: Vec<String>,
 }
 
 impl Chunk {
@@ -86,6 +93,18 @@ impl Chunk {
             hash,
             context_prev: None,
             context_next: None,
+            tags: Vec::new(),
+        }
+    }
+
+    /// Signature text for the full-text index: the signature followed by any tags
+    pub fn fts_signature(&self) -> Option<String> {
+        let mut parts: Vec<&str> = self.signature.iter().map(String::as_str).collect();
+        parts.extend(self.tags.iter().map(String::as_str));
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join("\n"))
         }
     }
 
@@ -158,6 +177,28 @@ pub enum ChunkKind {
     Other,      // Catch-all
 }
 
+/// The `lines` source lines before and after a chunk spanning
+/// `start_line..end_line`, as `(context_prev, context_next)`
+///
+/// Windows of blank lines only are `None`.
+pub fn context_windows(
+    source_lines: &[&str],
+    start_line: usize,
+    end_line: usize,
+    lines: usize,
+) -> (Option<String>, Option<String>) {
+    let window = |from: usize, to: usize| {
+        let to = to.min(source_lines.len());
+        (from < to)
+            .then(|| source_lines[from..to].join("\n"))
+            .filter(|content| !content.trim().is_empty())
+    };
+    (
+        window(start_line.saturating_sub(lines), start_line),
+        window(end_line, end_line.saturating_add(lines)),
+    )
+}
+
 /// Trait for chunking strategies
 pub trait Chunker: Send + Sync {
     /// Chunk a file into semantic pieces
@@ -0,0 +1,241 @@
+//! HTTP route detection for web framework code
+//!
+//! Recognizes route definitions in axum/actix (`.route("/x", get(h))`,
+//! `#[post("/x")]`), Express (`app.get('/x', ...)`), FastAPI/Flask
+//! (`@app.post("/x")`) and Spring (`@GetMapping("/x")`,
+//! `@RequestMapping(value = "/x", method = RequestMethod.POST)`), producing
+//! tags like `"POST /users"` so route queries hit the defining chunk.
+
+use super::Chunk;
+
+/// HTTP methods recognized as route helpers (`get(...)`, `@app.post(...)`, ...)
+const HTTP_METHODS: &[&str] = &["get", "post", "put", "delete", "patch", "head", "options"];
+
+/// Receivers that conventionally hold a router (`app.get`, `router.post`, ...)
+const ROUTER_RECEIVERS: &[&str] = &[
+    "app",
+    "router",
+    "server",
+    "api",
+    "routes",
+    "route",
+    "fastify",
+    "blueprint",
+    "bp",
+];
+
+/// Tag a chunk with the HTTP routes it defines
+///
+/// Besides the chunk content, attribute/decorator lines directly above the
+/// chunk (the tail of `context_prev`) are scanned, since handlers like
+/// `#[post("/users")] async fn create_user` keep the route outside the body.
+pub fn tag_routes(chunk: &mut Chunk) {
+    let mut routes = Vec::new();
+
+    if let Some(prev) = &chunk.context_prev {
+        let decorators: Vec<&str> = prev
+            .lines()
+            .rev()
+            .take_while(|l| {
+                let t = l.trim_start();
+                t.starts_with('@') || t.starts_with("#[")
+            })
+            .collect();
+        for line in decorators.into_iter().rev() {
+            routes.extend(extract_routes_from_line(line));
+        }
+    }
+
+    for line in chunk.content.lines() {
+        routes.extend(extract_routes_from_line(line));
+    }
+
+    for route in routes {
+        if !chunk.tags.contains(&route) {
+            chunk.tags.push(route);
+        }
+    }
+}
+
+/// Extract route tags ("METHOD /path") from a block of source text
+pub fn extract_routes(text: &str) -> Vec<String> {
+    let mut routes = Vec::new();
+    for line in text.lines() {
+        for route in extract_routes_from_line(line) {
+            if !routes.contains(&route) {
+                routes.push(route);
+            }
+        }
+    }
+    routes
+}
+
+fn extract_routes_from_line(line: &str) -> Vec<String> {
+    let trimmed = line.trim();
+    let mut routes = Vec::new();
+
+    // axum / actix builder: .route("/users", get(list).post(create))
+    if let Some(pos) = trimmed.find(".route(") {
+        let rest = &trimmed[pos + ".route(".len()..];
+        if let Some((path, after)) = first_path_literal(rest) {
+            let methods: Vec<&str> = HTTP_METHODS
+                .iter()
+                .copied()
+                .filter(|m| find_call(after, m).is_some())
+                .collect();
+            if methods.is_empty() {
+                routes.push(format!("ANY {}", path));
+            }
+            for method in methods {
+                routes.push(format!("{} {}", method.to_uppercase(), path));
+            }
+        }
+        return routes;
+    }
+
+    // Spring: @GetMapping("/x"), @RequestMapping(value = "/x", method = RequestMethod.POST)
+    if let Some(rest) = trimmed.strip_prefix('@') {
+        if let Some(idx) = rest.find("Mapping") {
+            let prefix = &rest[..idx];
+            let args = &rest[idx + "Mapping".len()..];
+            let method = match prefix {
+                "Request" => HTTP_METHODS
+                    .iter()
+                    .find(|m| args.contains(&format!("RequestMethod.{}", m.to_uppercase())))
+                    .map(|m| m.to_uppercase())
+                    .unwrap_or_else(|| "ANY".to_string()),
+                p if HTTP_METHODS.contains(&p.to_lowercase().as_str()) => p.to_uppercase(),
+                _ => return routes,
+            };
+            let path = first_path_literal(args)
+                .map(|(p, _)| p)
+                .unwrap_or_else(|| "/".to_string());
+            routes.push(format!("{} {}", method, path));
+            return routes;
+        }
+    }
+
+    // Attributes/decorators (#[post("/x")], @app.get("/x")) and router calls (app.get('/x', ...))
+    let is_annotation = trimmed.starts_with('@') || trimmed.starts_with("#[");
+    for method in HTTP_METHODS {
+        let Some(pos) = find_call(trimmed, method) else {
+            continue;
+        };
+        let before = &trimmed[..pos];
+        let receiver = before
+            .strip_suffix('.')
+            .map(|b| {
+                b.rsplit(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .next()
+                    .unwrap_or("")
+            })
+            .unwrap_or("");
+        let attribute_style = is_annotation && (before.ends_with('.') || before == "#[");
+        if !(attribute_style || ROUTER_RECEIVERS.contains(&receiver)) {
+            continue;
+        }
+        // The path must be the first argument
+        let args = &trimmed[pos + method.len() + 1..];
+        let first_arg = args.split([',', ')']).next().unwrap_or("");
+        if let Some((path, _)) = first_path_literal(first_arg) {
+            routes.push(format!("{} {}", method.to_uppercase(), path));
+        }
+    }
+
+    routes
+}
+
+/// Find `name(` in `text` where `name` is not part of a longer identifier
+fn find_call(text: &str, name: &str) -> Option<usize> {
+    let pattern = format!("{}(", name);
+    let mut start = 0;
+    while let Some(found) = text[start..].find(&pattern) {
+        let pos = start + found;
+        let preceded_by_ident = text[..pos]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_alphanumeric() || c == '_');
+        if !preceded_by_ident {
+            return Some(pos);
+        }
+        start = pos + pattern.len();
+    }
+    None
+}
+
+/// Return the first string literal starting with '/' and the text after it
+fn first_path_literal(text: &str) -> Option<(String, &str)> {
+    let mut rest = text;
+    while let Some(open) = rest.find(['"', '\'', '`']) {
+        let quote = rest[open..].chars().next()?;
+        let body = &rest[open + 1..];
+        let close = body.find(quote)?;
+        let literal = &body[..close];
+        let after = &body[close + 1..];
+        if literal.starts_with('/') {
+            return Some((literal.to_string(), after));
+        }
+        rest = after;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_axum_router_routes() {
+        let routes = extract_routes(
+            r#"Router::new()
+        .route("/users", get(list_users).post(create_user))
+        .route("/health", axum::routing::get(health))"#,
+        );
+        assert_eq!(routes, vec!["GET /users", "POST /users", "GET /health"]);
+    }
+
+    #[test]
+    fn test_attribute_and_decorator_routes() {
+        assert_eq!(extract_routes(r#"#[post("/users")]"#), vec!["POST /users"]);
+        assert_eq!(
+            extract_routes(r#"@app.delete("/items/{id}")"#),
+            vec!["DELETE /items/{id}"]
+        );
+        assert_eq!(
+            extract_routes("router.put('/orders/:id', auth, updateOrder);"),
+            vec!["PUT /orders/:id"]
+        );
+    }
+
+    #[test]
+    fn test_spring_mappings() {
+        assert_eq!(
+            extract_routes(r#"@PostMapping("/users")"#),
+            vec!["POST /users"]
+        );
+        assert_eq!(
+            extract_routes(r#"@RequestMapping(value = "/orders", method = RequestMethod.PATCH)"#),
+            vec!["PATCH /orders"]
+        );
+    }
+
+    #[test]
+    fn test_non_route_calls_ignored() {
+        assert!(extract_routes(r#"let v = map.get("/etc/hosts");"#).is_empty());
+        assert!(extract_routes("let resp = client.get(url).send().await?;").is_empty());
+    }
+
+    #[test]
+    fn test_tag_routes_uses_decorator_above_chunk() {
+        let mut chunk = Chunk::new(
+            "async fn create_user() {}".to_string(),
+            3,
+            4,
+            super::super::ChunkKind::Function,
+            "src/api.rs".to_string(),
+        );
+        chunk.context_prev = Some("}\n\n#[post(\"/users\")]".to_string());
+        tag_routes(&mut chunk);
+        assert_eq!(chunk.tags, vec!["POST /users"]);
+    }
+}
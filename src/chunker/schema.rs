@@ -0,0 +1,576 @@
+//! Chunking for API schema files: Protobuf, GraphQL and OpenAPI
+//!
+//! These files have no tree-sitter grammar here but a very regular shape, so
+//! they are split with small line-based scanners into message/service/RPC
+//! (Protobuf), type/operation (GraphQL) and path-operation/schema (OpenAPI)
+//! chunks. Each chunk carries the definition name in its signature so
+//! queries like "where is the CreateOrder RPC defined" hit the schema.
+
+use super::{Chunk, ChunkKind};
+use crate::cache::normalize_path;
+use std::path::Path;
+
+/// A definition found by a scanner, before it is turned into a `Chunk`
+pub(super) struct Definition {
+    pub kind: ChunkKind,
+    /// Breadcrumb label, e.g. "Service: OrderService"
+    pub label: String,
+    pub signature: String,
+    pub start_line: usize,
+    /// Exclusive end line
+    pub end_line: usize,
+    /// Index of the enclosing definition, if nested
+    pub parent: Option<usize>,
+    pub tags: Vec<String>,
+    /// Containers used only for breadcrumbs (OpenAPI paths) are not emitted
+    pub emit: bool,
+}
+
+/// HTTP methods that introduce an operation under an OpenAPI path
+const OPENAPI_METHODS: &[&str] = &[
+    "get", "put", "post", "delete", "options", "head", "patch", "trace",
+];
+
+/// GraphQL root types whose fields are operations
+const GRAPHQL_ROOT_TYPES: &[&str] = &["Query", "Mutation", "Subscription"];
+
+/// Check whether YAML content is an OpenAPI/Swagger document
+pub fn is_openapi(content: &str) -> bool {
+    content
+        .lines()
+        .any(|l| l.starts_with("openapi:") || l.starts_with("swagger:"))
+}
+
+/// Split a `.proto` file into message, enum, service and RPC chunks
+pub fn chunk_proto(path: &Path, content: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut defs: Vec<Definition> = Vec::new();
+    let mut open: Vec<(usize, i32)> = Vec::new(); // (definition index, depth at start)
+    let mut depth = 0i32;
+
+    for (i, raw) in lines.iter().enumerate() {
+        let line = raw.split("//").next().unwrap_or("");
+        let trimmed = line.trim();
+        let mut words = trimmed.split_whitespace();
+        let keyword = words.next().unwrap_or("");
+
+        let kind = match keyword {
+            "message" => Some(ChunkKind::Struct),
+            "enum" => Some(ChunkKind::Enum),
+            "service" => Some(ChunkKind::Interface),
+            "rpc" => Some(ChunkKind::Method),
+            "extend" => Some(ChunkKind::Other),
+            _ => None,
+        };
+
+        if let Some(kind) = kind {
+            let name = words
+                .next()
+                .unwrap_or("")
+                .split(['(', '{'])
+                .next()
+                .unwrap_or("")
+                .to_string();
+            let signature = trimmed.trim_end_matches(['{', ';']).trim().to_string();
+            let label_kind = match keyword {
+                "rpc" => "RPC".to_string(),
+                k => capitalize(k),
+            };
+            let mut tags = Vec::new();
+            if keyword == "rpc" {
+                tags.push(format!("RPC {}", name));
+            }
+            defs.push(Definition {
+                kind,
+                label: format!("{}: {}", label_kind, name),
+                signature,
+                start_line: i,
+                end_line: i + 1,
+                parent: open.last().map(|(idx, _)| *idx),
+                tags,
+                emit: true,
+            });
+            if trimmed.contains('{') {
+                open.push((defs.len() - 1, depth));
+            }
+        }
+
+        for c in line.chars() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if let Some(&(idx, start_depth)) = open.last() {
+                        if depth == start_depth {
+                            defs[idx].end_line = i + 1;
+                            open.pop();
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    to_chunks(path, &lines, defs, "//")
+}
+
+/// Split a GraphQL schema or document into type and operation chunks
+///
+/// Fields of `Query`/`Mutation`/`Subscription` become their own chunks since
+/// each one is an API operation.
+pub fn chunk_graphql(path: &Path, content: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut defs: Vec<Definition> = Vec::new();
+    let mut open: Option<usize> = None; // top-level definitions only
+    let mut root_type: Option<String> = None;
+    let mut depth = 0i32;
+    let mut field: Option<(usize, i32)> = None; // (definition index, open parens)
+
+    for (i, raw) in lines.iter().enumerate() {
+        let line = raw.split('#').next().unwrap_or("");
+        let trimmed = line.trim();
+
+        if depth == 0 && !trimmed.is_empty() {
+            let mut words = trimmed.split_whitespace();
+            let mut keyword = words.next().unwrap_or("");
+            if keyword == "extend" {
+                keyword = words.next().unwrap_or("");
+            }
+            let kind = match keyword {
+                "type" | "input" => Some(ChunkKind::Struct),
+                "interface" => Some(ChunkKind::Interface),
+                "enum" => Some(ChunkKind::Enum),
+                "union" | "scalar" | "directive" => Some(ChunkKind::TypeAlias),
+                "query" | "mutation" | "subscription" | "fragment" => Some(ChunkKind::Function),
+                "schema" => Some(ChunkKind::Other),
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                let name = words
+                    .next()
+                    .unwrap_or("")
+                    .trim_start_matches('@')
+                    .split(['(', '{', ':', '='])
+                    .next()
+                    .unwrap_or("")
+                    .to_string();
+                root_type = (keyword == "type" && GRAPHQL_ROOT_TYPES.contains(&name.as_str()))
+                    .then(|| name.clone());
+                let label = if name.is_empty() {
+                    capitalize(keyword)
+                } else {
+                    format!("{}: {}", capitalize(keyword), name)
+                };
+                defs.push(Definition {
+                    kind,
+                    label,
+                    signature: trimmed.trim_end_matches('{').trim().to_string(),
+                    start_line: i,
+                    end_line: i + 1,
+                    parent: None,
+                    tags: Vec::new(),
+                    emit: true,
+                });
+                open = trimmed.contains('{').then_some(defs.len() - 1);
+            }
+        } else if depth == 1 && field.is_none() {
+            // Operation field inside a root type: createOrder(input: X!): Order!
+            if let (Some(root), Some(parent)) = (&root_type, open) {
+                let name: String = trimmed
+                    .chars()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_')
+                    .collect();
+                if !name.is_empty() {
+                    defs.push(Definition {
+                        kind: ChunkKind::Method,
+                        label: format!("{}: {}", root, name),
+                        signature: trimmed.to_string(),
+                        start_line: i,
+                        end_line: i + 1,
+                        parent: Some(parent),
+                        tags: vec![format!("{} {}", root.to_uppercase(), name)],
+                        emit: true,
+                    });
+                    field = Some((defs.len() - 1, 0));
+                }
+            }
+        }
+
+        for c in line.chars() {
+            match c {
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        if let Some(idx) = open.take() {
+                            defs[idx].end_line = i + 1;
+                        }
+                        root_type = None;
+                    }
+                }
+                '(' => {
+                    if let Some((_, parens)) = field.as_mut() {
+                        *parens += 1;
+                    }
+                }
+                ')' => {
+                    if let Some((_, parens)) = field.as_mut() {
+                        *parens -= 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // A field ends on the line where its argument list is closed
+        if let Some((idx, parens)) = field {
+            defs[idx].end_line = i + 1;
+            if i > defs[idx].start_line {
+                let sig = format!("{} {}", defs[idx].signature, trimmed);
+                defs[idx].signature = sig.split_whitespace().collect::<Vec<_>>().join(" ");
+            }
+            if parens <= 0 {
+                field = None;
+            }
+        }
+    }
+
+    to_chunks(path, &lines, defs, "#")
+}
+
+/// Split an OpenAPI/Swagger YAML document into operation and schema chunks
+///
+/// Every `paths.<path>.<method>` becomes a Method chunk with signature and
+/// tag "POST /users" (plus its `operationId`); every entry under
+/// `components.schemas` (or Swagger 2 `definitions`) becomes a Struct chunk.
+pub fn chunk_openapi(path: &Path, content: &str) -> Vec<Chunk> {
+    let lines: Vec<&str> = content.lines().collect();
+    let mut defs: Vec<Definition> = Vec::new();
+
+    let yaml = YamlKeys::new(&lines);
+
+    for k in 0..yaml.keys.len() {
+        let (_, indent, key) = &yaml.keys[k];
+        if *indent != 0 {
+            continue;
+        }
+        match key.as_str() {
+            "paths" => {
+                for p in yaml.children(k) {
+                    let route = yaml.keys[p].2.clone();
+                    let path_def = defs.len();
+                    defs.push(Definition {
+                        kind: ChunkKind::Other,
+                        label: format!("Path: {}", route),
+                        signature: route.clone(),
+                        start_line: yaml.keys[p].0,
+                        end_line: yaml.block_end(p),
+                        parent: None,
+                        tags: Vec::new(),
+                        emit: false,
+                    });
+                    for m in yaml.children(p) {
+                        let method = yaml.keys[m].2.to_lowercase();
+                        if !OPENAPI_METHODS.contains(&method.as_str()) {
+                            continue;
+                        }
+                        let end = yaml.block_end(m);
+                        let operation_id = lines[yaml.keys[m].0..end]
+                            .iter()
+                            .find_map(|l| l.trim().strip_prefix("operationId:"))
+                            .map(|v| v.trim().trim_matches(['"', '\'']).to_string());
+                        let endpoint = format!("{} {}", method.to_uppercase(), route);
+                        let mut tags = vec![endpoint.clone()];
+                        let signature = match &operation_id {
+                            Some(id) => {
+                                tags.push(id.clone());
+                                format!("{} ({})", endpoint, id)
+                            }
+                            None => endpoint.clone(),
+                        };
+                        defs.push(Definition {
+                            kind: ChunkKind::Method,
+                            label: format!("Operation: {}", endpoint),
+                            signature,
+                            start_line: yaml.keys[m].0,
+                            end_line: end,
+                            parent: Some(path_def),
+                            tags,
+                            emit: true,
+                        });
+                    }
+                }
+            }
+            "components" | "definitions" => {
+                let schema_parents = if key == "definitions" {
+                    vec![k]
+                } else {
+                    yaml.children(k)
+                        .into_iter()
+                        .filter(|&c| yaml.keys[c].2 == "schemas")
+                        .collect()
+                };
+                for parent in schema_parents {
+                    for s in yaml.children(parent) {
+                        let name = yaml.keys[s].2.clone();
+                        defs.push(Definition {
+                            kind: ChunkKind::Struct,
+                            label: format!("Schema: {}", name),
+                            signature: format!("schema {}", name),
+                            start_line: yaml.keys[s].0,
+                            end_line: yaml.block_end(s),
+                            parent: None,
+                            tags: Vec::new(),
+                            emit: true,
+                        });
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    to_chunks(path, &lines, defs, "#")
+}
+
+/// Mapping keys of a YAML document with their line and indentation
+///
+/// Enough structure to walk OpenAPI specs and CI workflows without a YAML parser.
+pub(super) struct YamlKeys {
+    /// (line index, indent, key) for every mapping key
+    pub keys: Vec<(usize, usize, String)>,
+    total_lines: usize,
+}
+
+impl YamlKeys {
+    pub fn new(lines: &[&str]) -> Self {
+        let keys = lines
+            .iter()
+            .enumerate()
+            .filter_map(|(i, l)| yaml_key(l).map(|(indent, key)| (i, indent, key)))
+            .collect();
+        Self {
+            keys,
+            total_lines: lines.len(),
+        }
+    }
+
+    /// Index of a top-level key
+    pub fn top_level(&self, name: &str) -> Option<usize> {
+        self.keys
+            .iter()
+            .position(|(_, indent, key)| *indent == 0 && key == name)
+    }
+
+    /// End line (exclusive) of the block opened by key `k`: the next key at
+    /// the same or lower indent
+    pub fn block_end(&self, k: usize) -> usize {
+        let indent = self.keys[k].1;
+        self.keys[k + 1..]
+            .iter()
+            .find(|(_, other, _)| *other <= indent)
+            .map(|(line, _, _)| *line)
+            .unwrap_or(self.total_lines)
+    }
+
+    /// Direct child keys of key `k`
+    pub fn children(&self, k: usize) -> Vec<usize> {
+        let end = self.block_end(k);
+        let child_indent = self.keys[k + 1..]
+            .iter()
+            .take_while(|(line, _, _)| *line < end)
+            .map(|(_, indent, _)| *indent)
+            .min();
+        (k + 1..self.keys.len())
+            .take_while(|&c| self.keys[c].0 < end)
+            .filter(|&c| Some(self.keys[c].1) == child_indent)
+            .collect()
+    }
+}
+
+/// Parse `  key:` / `  "/users/{id}":` lines into (indent, key)
+fn yaml_key(line: &str) -> Option<(usize, String)> {
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() || trimmed.starts_with(['#', '-']) {
+        return None;
+    }
+    let indent = line.len() - trimmed.len();
+
+    if let Some(quote) = trimmed.chars().next().filter(|c| matches!(c, '"' | '\'')) {
+        let close = trimmed[1..].find(quote)? + 1;
+        trimmed[close + 1..].strip_prefix(':')?;
+        return Some((indent, trimmed[1..close].to_string()));
+    }
+
+    // The key ends at the first colon followed by whitespace or end of line
+    let colon = trimmed.char_indices().find_map(|(i, c)| {
+        let next = trimmed[i + c.len_utf8()..].chars().next();
+        (c == ':' && next.is_none_or(char::is_whitespace)).then_some(i)
+    })?;
+    Some((indent, trimmed[..colon].to_string()))
+}
+
+pub(super) fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+/// Turn scanned definitions into chunks with breadcrumbs and leading comments
+pub(super) fn to_chunks(
+    path: &Path,
+    lines: &[&str],
+    defs: Vec<Definition>,
+    comment_prefix: &str,
+) -> Vec<Chunk> {
+    let path_str = normalize_path(path);
+
+    defs.iter()
+        .filter(|def| def.emit)
+        .map(|def| {
+            let mut context = vec![format!("File: {}", path_str)];
+            let mut ancestors = Vec::new();
+            let mut parent = def.parent;
+            while let Some(idx) = parent {
+                ancestors.push(defs[idx].label.clone());
+                parent = defs[idx].parent;
+            }
+            context.extend(ancestors.into_iter().rev());
+            context.push(def.label.clone());
+
+            // Comment lines directly above the definition are its documentation
+            let doc_lines: Vec<&str> = lines[..def.start_line]
+                .iter()
+                .rev()
+                .take_while(|l| l.trim_start().starts_with(comment_prefix))
+                .map(|l| l.trim())
+                .collect();
+            let docstring = (!doc_lines.is_empty())
+                .then(|| doc_lines.into_iter().rev().collect::<Vec<_>>().join("\n"));
+
+            let end = def.end_line.min(lines.len()).max(def.start_line + 1);
+            let mut chunk = Chunk::new(
+                lines[def.start_line..end].join("\n"),
+                def.start_line,
+                end,
+                def.kind,
+                path_str.clone(),
+            );
+            chunk.context = context;
+            chunk.signature = Some(def.signature.clone());
+            chunk.docstring = docstring;
+            chunk.tags = def.tags.clone();
+            chunk
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proto_services_and_rpcs() {
+        let proto = r#"syntax = "proto3";
+
+// Order management
+service OrderService {
+  // Creates an order
+  rpc CreateOrder(CreateOrderRequest) returns (Order);
+  rpc StreamOrders(Empty) returns (stream Order) {}
+}
+
+message Order {
+  string id = 1;
+  message Line { int32 qty = 1; }
+}
+"#;
+        let chunks = chunk_proto(Path::new("api/order.proto"), proto);
+        let names: Vec<&str> = chunks
+            .iter()
+            .map(|c| c.context.last().unwrap().as_str())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "Service: OrderService",
+                "RPC: CreateOrder",
+                "RPC: StreamOrders",
+                "Message: Order",
+                "Message: Line"
+            ]
+        );
+        let create = &chunks[1];
+        assert_eq!(
+            create.signature.as_deref(),
+            Some("rpc CreateOrder(CreateOrderRequest) returns (Order)")
+        );
+        assert_eq!(create.context[1], "Service: OrderService");
+        assert_eq!(create.docstring.as_deref(), Some("// Creates an order"));
+        assert_eq!(create.tags, vec!["RPC CreateOrder"]);
+        assert_eq!((chunks[0].start_line, chunks[0].end_line), (3, 8));
+        assert_eq!((chunks[3].start_line, chunks[3].end_line), (9, 13));
+    }
+
+    #[test]
+    fn test_graphql_root_fields_are_operations() {
+        let schema = "type Query {\n  order(id: ID!): Order\n}\n\ntype Mutation {\n  createOrder(\n    input: CreateOrderInput!\n  ): Order!\n}\n\ntype Order {\n  id: ID!\n}\n";
+        let chunks = chunk_graphql(Path::new("schema.graphql"), schema);
+        let create = chunks
+            .iter()
+            .find(|c| c.context.last().map(String::as_str) == Some("Mutation: createOrder"))
+            .unwrap();
+        assert_eq!((create.start_line, create.end_line), (5, 8));
+        assert_eq!(
+            create.signature.as_deref(),
+            Some("createOrder( input: CreateOrderInput! ): Order!")
+        );
+        assert_eq!(create.tags, vec!["MUTATION createOrder"]);
+        assert!(chunks
+            .iter()
+            .any(|c| c.signature.as_deref() == Some("type Order") && c.kind == ChunkKind::Struct));
+        // Fields of plain types are not split out
+        assert_eq!(chunks.len(), 5);
+    }
+
+    #[test]
+    fn test_openapi_operations_and_schemas() {
+        let spec = "openapi: 3.0.0\npaths:\n  /orders:\n    get:\n      summary: List orders\n    post:\n      operationId: createOrder\n      responses:\n        '201':\n          description: Created\n  \"/orders/{id}\":\n    delete:\n      summary: Remove\ncomponents:\n  schemas:\n    Order:\n      type: object\n";
+        assert!(is_openapi(spec));
+        let chunks = chunk_openapi(Path::new("openapi.yaml"), spec);
+        let signatures: Vec<&str> = chunks
+            .iter()
+            .filter_map(|c| c.signature.as_deref())
+            .collect();
+        assert_eq!(
+            signatures,
+            vec![
+                "GET /orders",
+                "POST /orders (createOrder)",
+                "DELETE /orders/{id}",
+                "schema Order"
+            ]
+        );
+        let post = &chunks[1];
+        assert_eq!((post.start_line, post.end_line), (5, 10));
+        assert_eq!(post.tags, vec!["POST /orders", "createOrder"]);
+        assert_eq!(post.context[1], "Path: /orders");
+    }
+
+    #[test]
+    fn test_yaml_key_with_multibyte_characters() {
+        assert_eq!(
+            yaml_key("      description: Liste der Aufträge: offen"),
+            Some((6, "description".to_string()))
+        );
+        assert_eq!(yaml_key("  größe: 3"), Some((2, "größe".to_string())));
+        assert_eq!(
+            yaml_key("    run: echo \"✓ done\""),
+            Some((4, "run".to_string()))
+        );
+        assert_eq!(yaml_key("ключ:"), Some((0, "ключ".to_string())));
+    }
+}
@@ -1,6 +1,7 @@
 #![allow(dead_code)]
 
-use super::{Chunk, ChunkKind, Chunker, DEFAULT_CONTEXT_LINES};
+use super::infra::InfraFile;
+use super::{context_windows, routes, schema, Chunk, ChunkKind, Chunker, DEFAULT_CONTEXT_LINES};
 use crate::cache::normalize_path;
 use crate::chunker::extractor::{get_extractor, LanguageExtractor};
 use crate::chunker::parser::CodeParser;
@@ -9,6 +10,26 @@ use anyhow::Result;
 use std::path::Path;
 use tree_sitter::Node;
 
+/// Lines taken from the start of each part when summarizing a split function
+const SUMMARY_LINES_PER_PART: usize = 3;
+
+/// Files larger than this skip tree-sitter and are chunked by lines
+const MAX_PARSE_BYTES: usize = 2 * 1024 * 1024;
+
+/// Deepest AST level visited; deeper nesting would overflow the stack
+const MAX_AST_DEPTH: usize = 256;
+
+/// Definition chunks of a file hold at most this many times its size in
+/// total (nested definitions repeat the text of their parents)
+const MAX_DEFINITION_AMPLIFICATION: usize = 8;
+
+/// Chunks kept per file; the rest of a pathological file is dropped
+const MAX_CHUNKS_PER_FILE: usize = 5_000;
+
+/// No chunk or context window exceeds `max_chunk_chars` times this; longer
+/// content (minified code, one enormous line) is cut into pieces
+const HARD_CHUNK_CHARS_FACTOR: usize = 4;
+
 /// Smart semantic chunker using tree-sitter and language-specific extractors
 pub struct SemanticChunker {
     parser: CodeParser,
@@ -35,13 +56,62 @@ impl SemanticChunker {
         self
     }
 
+    /// Number of context lines extracted before/after each chunk
+    pub fn context_lines(&self) -> usize {
+        self.context_lines
+    }
+
     /// Chunk a file using semantic analysis
+    ///
+    /// Pathological input is bounded: huge files are chunked by lines, AST
+    /// depth and nested-definition text are capped, oversized chunks are cut
+    /// and the number of chunks per file is limited.
     pub fn chunk_semantic(
         &mut self,
         language: Language,
         path: &Path,
         content: &str,
     ) -> Result<Vec<Chunk>> {
+        let chunks = if content.len() > MAX_PARSE_BYTES {
+            tracing::warn!(
+                "⚠️  {} is larger than {} MB, chunking it by lines",
+                path.display(),
+                MAX_PARSE_BYTES / (1024 * 1024)
+            );
+            self.fallback_chunk(path, content)
+        } else {
+            self.chunk_unbounded(language, path, content)?
+        };
+        Ok(self.enforce_limits(chunks, path))
+    }
+
+    fn chunk_unbounded(
+        &mut self,
+        language: Language,
+        path: &Path,
+        content: &str,
+    ) -> Result<Vec<Chunk>> {
+        // SQL has no tree-sitter grammar here; chunk it by statement instead
+        if language == Language::Sql {
+            return Ok(self.finish_chunks(super::sql::chunk_sql(path, content), content));
+        }
+
+        // API schemas and build/CI config: line-based scanners, uncovered
+        // lines become gap chunks
+        let schema_chunks = match (InfraFile::detect(path), language) {
+            (Some(infra), _) => Some(infra.chunk(path, content)),
+            (None, Language::Protobuf) => Some(schema::chunk_proto(path, content)),
+            (None, Language::GraphQL) => Some(schema::chunk_graphql(path, content)),
+            (None, Language::Yaml) if schema::is_openapi(content) => {
+                Some(schema::chunk_openapi(path, content))
+            }
+            _ => None,
+        };
+        if let Some(chunks) = schema_chunks {
+            let chunks = self.with_gap_chunks(chunks, path, content);
+            return Ok(self.finish_chunks(chunks, content));
+        }
+
         // 1. Check if we have an extractor for this language
         let extractor = match get_extractor(language) {
             Some(ext) => ext,
@@ -55,8 +125,12 @@ impl SemanticChunker {
         let parsed = self.parser.parse(language, content)?;
 
         // 3. Visit AST and extract chunks
-        let mut definition_chunks = Vec::new();
-        let mut gap_tracker = GapTracker::new(content);
+        let mut state = VisitState {
+            chunks: Vec::new(),
+            gap_tracker: GapTracker::new(content),
+            bytes_left: content.len().saturating_mul(MAX_DEFINITION_AMPLIFICATION),
+            limited: false,
+        };
 
         let file_context = format!("File: {}", normalize_path(path));
         self.visit_node(
@@ -64,78 +138,111 @@ impl SemanticChunker {
             parsed.source().as_bytes(),
             &*extractor,
             &[file_context],
-            &mut definition_chunks,
-            &mut gap_tracker,
+            0,
+            &mut state,
         );
+        if state.limited {
+            tracing::warn!(
+                "⚠️  {} is too deeply nested or has too many definitions; \
+                 the rest is indexed as plain blocks",
+                path.display()
+            );
+        }
 
         // 4. Extract gap chunks (code between definitions)
-        let gap_chunks = gap_tracker.extract_gaps(path);
+        let gap_chunks = state.gap_tracker.extract_gaps(path);
 
-        // 5. Combine and sort all chunks by position
-        let mut all_chunks = definition_chunks;
+        // 5. Combine all chunks
+        let mut all_chunks = state.chunks;
         all_chunks.extend(gap_chunks);
+
+        Ok(self.finish_chunks(all_chunks, content))
+    }
+
+    /// Add gap chunks for lines not covered by any of `chunks`
+    fn with_gap_chunks(&self, mut chunks: Vec<Chunk>, path: &Path, content: &str) -> Vec<Chunk> {
+        let mut gap_tracker = GapTracker::new(content);
+        for chunk in &chunks {
+            gap_tracker.mark_covered(chunk.start_line, chunk.end_line.saturating_sub(1));
+        }
+        chunks.extend(gap_tracker.extract_gaps(path));
+        chunks
+    }
+
+    /// Sort chunks by position, attach context windows, split oversized chunks
+    /// and tag route definitions
+    fn finish_chunks(&self, mut all_chunks: Vec<Chunk>, content: &str) -> Vec<Chunk> {
         all_chunks.sort_by_key(|c| c.start_line);
 
         // 6. Populate context windows (lines before/after each chunk)
         let source_lines: Vec<&str> = content.lines().collect();
         self.populate_context_windows(&mut all_chunks, &source_lines);
 
-        // 7. Split oversized chunks
-        let final_chunks = all_chunks
-            .into_iter()
-            .flat_map(|c| self.split_if_needed(c))
-            .collect();
+        // 7. Split oversized chunks; split functions also get a summary chunk
+        // covering the whole definition
+        let mut final_chunks = Vec::with_capacity(all_chunks.len());
+        for chunk in all_chunks {
+            let parts = self.split_if_needed(chunk);
+            let summary = self.summarize_splits(&parts);
+            final_chunks.extend(parts);
+            final_chunks.extend(summary);
+        }
+
+        // 8. Tag chunks that define HTTP routes ("POST /users")
+        for chunk in &mut final_chunks {
+            routes::tag_routes(chunk);
+        }
 
-        Ok(final_chunks)
+        final_chunks
     }
 
     /// Populate context_prev and context_next for each chunk
     fn populate_context_windows(&self, chunks: &mut [Chunk], source_lines: &[&str]) {
-        let total_lines = source_lines.len();
-
+        if self.context_lines == 0 {
+            return;
+        }
         for chunk in chunks.iter_mut() {
-            // Extract context_prev (N lines before start_line)
-            if chunk.start_line > 0 && self.context_lines > 0 {
-                let prev_start = chunk.start_line.saturating_sub(self.context_lines);
-                let prev_end = chunk.start_line;
-                if prev_start < prev_end && prev_end <= total_lines {
-                    let prev_lines = &source_lines[prev_start..prev_end];
-                    let prev_content = prev_lines.join("\n");
-                    if !prev_content.trim().is_empty() {
-                        chunk.context_prev = Some(prev_content);
-                    }
-                }
-            }
-
-            // Extract context_next (N lines after end_line)
-            if chunk.end_line < total_lines && self.context_lines > 0 {
-                let next_start = chunk.end_line;
-                let next_end = (chunk.end_line + self.context_lines).min(total_lines);
-                if next_start < next_end {
-                    let next_lines = &source_lines[next_start..next_end];
-                    let next_content = next_lines.join("\n");
-                    if !next_content.trim().is_empty() {
-                        chunk.context_next = Some(next_content);
-                    }
-                }
-            }
+            let (prev, next) = context_windows(
+                source_lines,
+                chunk.start_line,
+                chunk.end_line,
+                self.context_lines,
+            );
+            chunk.context_prev = prev.or(chunk.context_prev.take());
+            chunk.context_next = next.or(chunk.context_next.take());
         }
     }
 
     /// Recursively visit AST nodes and extract chunks
+    ///
+    /// Stops at `MAX_AST_DEPTH` and once the definition budget is spent;
+    /// definitions left out are picked up as gap chunks.
     fn visit_node(
         &self,
         node: Node,
         source: &[u8],
         extractor: &dyn LanguageExtractor,
         context_stack: &[String],
-        chunks: &mut Vec<Chunk>,
-        gap_tracker: &mut GapTracker,
+        depth: usize,
+        state: &mut VisitState,
     ) {
+        if depth >= MAX_AST_DEPTH || state.chunks.len() >= MAX_CHUNKS_PER_FILE {
+            state.limited = true;
+            return;
+        }
+
         // Check if this node is a definition
         let is_definition = extractor.definition_types().contains(&node.kind());
 
         if is_definition {
+            let size = node.end_byte().saturating_sub(node.start_byte());
+            if size > state.bytes_left {
+                state.limited = true;
+                return;
+            }
+            state.bytes_left -= size;
+            let gap_tracker = &mut state.gap_tracker;
+
             // Mark this range as covered (not a gap)
             gap_tracker.mark_covered(node.start_position().row, node.end_position().row);
 
@@ -214,27 +321,97 @@ impl SemanticChunker {
             chunk.signature = signature;
             chunk.docstring = docstring;
 
-            chunks.push(chunk);
+            state.chunks.push(chunk);
 
             // Visit children with updated context
             let mut cursor = node.walk();
             for child in node.named_children(&mut cursor) {
-                self.visit_node(child, source, extractor, &new_context, chunks, gap_tracker);
+                self.visit_node(child, source, extractor, &new_context, depth + 1, state);
             }
         } else {
             // Not a definition, just visit children with same context
             let mut cursor = node.walk();
             for child in node.named_children(&mut cursor) {
-                self.visit_node(child, source, extractor, context_stack, chunks, gap_tracker);
+                self.visit_node(child, source, extractor, context_stack, depth + 1, state);
+            }
+        }
+    }
+
+    /// Cut chunks and context windows longer than the hard size limit and
+    /// cap the number of chunks of one file
+    fn enforce_limits(&self, chunks: Vec<Chunk>, path: &Path) -> Vec<Chunk> {
+        let hard_limit = self
+            .max_chunk_chars
+            .saturating_mul(HARD_CHUNK_CHARS_FACTOR)
+            .max(1);
+        let mut limited = Vec::with_capacity(chunks.len().min(MAX_CHUNKS_PER_FILE));
+        let mut cut = false;
+
+        for mut chunk in chunks {
+            for window in [&mut chunk.context_prev, &mut chunk.context_next]
+                .into_iter()
+                .flatten()
+            {
+                if window.len() > hard_limit {
+                    window.truncate(floor_char_boundary(window, hard_limit));
+                }
+            }
+            if chunk.content.len() <= hard_limit {
+                limited.push(chunk);
+                continue;
+            }
+
+            cut = true;
+            let mut rest = chunk.content.as_str();
+            let mut piece_index = chunk.split_index.unwrap_or(0);
+            while !rest.is_empty() && limited.len() < MAX_CHUNKS_PER_FILE {
+                let (piece, tail) = rest.split_at(floor_char_boundary(rest, hard_limit).max(
+                    // A single character wider than the limit still moves forward
+                    rest.chars().next().map_or(rest.len(), char::len_utf8),
+                ));
+                let mut part = Chunk::new(
+                    piece.to_string(),
+                    chunk.start_line,
+                    chunk.end_line,
+                    chunk.kind,
+                    chunk.path.clone(),
+                );
+                part.context = chunk.context.clone();
+                part.signature = chunk.signature.clone();
+                part.tags = chunk.tags.clone();
+                part.context_prev = chunk.context_prev.clone();
+                part.context_next = chunk.context_next.clone();
+                part.is_complete = false;
+                part.split_index = Some(piece_index);
+                limited.push(part);
+                piece_index += 1;
+                rest = tail;
             }
         }
+
+        if limited.len() > MAX_CHUNKS_PER_FILE {
+            cut = true;
+            limited.truncate(MAX_CHUNKS_PER_FILE);
+        }
+        if cut {
+            tracing::warn!(
+                "⚠️  {} has oversized content; chunks were cut to {} bytes (at most {} per file)",
+                path.display(),
+                hard_limit,
+                MAX_CHUNKS_PER_FILE
+            );
+        }
+        limited
     }
 
     /// Fallback chunking for unsupported languages
     fn fallback_chunk(&self, path: &Path, content: &str) -> Vec<Chunk> {
         let lines: Vec<&str> = content.lines().collect();
         let mut chunks = Vec::new();
-        let stride = (self.max_chunk_lines - self.overlap_lines).max(1);
+        let stride = self
+            .max_chunk_lines
+            .saturating_sub(self.overlap_lines)
+            .max(1);
 
         let path_str = normalize_path(path);
         let context = vec![format!("File: {}", path_str)];
@@ -270,7 +447,10 @@ impl SemanticChunker {
         // Need to split
         let lines: Vec<&str> = chunk.content.lines().collect();
         let mut split_chunks = Vec::new();
-        let stride = (self.max_chunk_lines - self.overlap_lines).max(1);
+        let stride = self
+            .max_chunk_lines
+            .saturating_sub(self.overlap_lines)
+            .max(1);
 
         let mut i = 0;
         let mut split_index = 0;
@@ -292,6 +472,7 @@ impl SemanticChunker {
                 // Preserve metadata
                 split_chunk.context = chunk.context.clone();
                 split_chunk.signature = chunk.signature.clone();
+                split_chunk.tags = chunk.tags.clone();
                 split_chunk.docstring = if split_index == 0 {
                     chunk.docstring.clone() // Only first chunk gets docstring
                 } else {
@@ -326,6 +507,61 @@ impl SemanticChunker {
 
         split_chunks
     }
+
+    /// Build a synthetic summary chunk for a function that was split into parts
+    ///
+    /// The summary holds the signature, the docstring and the first few lines of
+    /// every part, and spans the whole function, so a query describing the
+    /// function as a whole can match one vector instead of several partial ones.
+    /// Returns `None` for unsplit chunks and for non-function kinds.
+    fn summarize_splits(&self, parts: &[Chunk]) -> Option<Chunk> {
+        let (first, last) = (parts.first()?, parts.last()?);
+        if parts.len() < 2 || !matches!(first.kind, ChunkKind::Function | ChunkKind::Method) {
+            return None;
+        }
+
+        let mut content = format!(
+            "// [Summary of {} parts] {}\n",
+            parts.len(),
+            first.signature.as_deref().unwrap_or("(unnamed)")
+        );
+        if let Some(doc) = &first.docstring {
+            content.push_str(doc);
+            content.push('\n');
+        }
+        for part in parts {
+            // Skip the "// [Part i/n]" header added by split_if_needed
+            for line in part.content.lines().skip(1).take(SUMMARY_LINES_PER_PART) {
+                content.push_str(line);
+                content.push('\n');
+            }
+            content.push_str("    // ...\n");
+        }
+
+        // Keep the summary within the same size budget as regular chunks
+        if content.len() > self.max_chunk_chars {
+            let mut cut = self.max_chunk_chars;
+            while !content.is_char_boundary(cut) {
+                cut -= 1;
+            }
+            content.truncate(cut);
+        }
+
+        let mut summary = Chunk::new(
+            content,
+            first.start_line,
+            last.end_line,
+            first.kind,
+            first.path.clone(),
+        );
+        summary.context = first.context.clone();
+        summary.signature = first.signature.clone();
+        summary.docstring = first.docstring.clone();
+        summary.is_complete = false;
+        summary.context_prev = first.context_prev.clone();
+        summary.context_next = last.context_next.clone();
+        Some(summary)
+    }
 }
 
 impl Chunker for SemanticChunker {
@@ -345,6 +581,25 @@ impl Chunker for SemanticChunker {
     }
 }
 
+/// Largest char boundary of `text` at or below `index`
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+/// Output and limits of one file's AST walk
+struct VisitState<'a> {
+    chunks: Vec<Chunk>,
+    gap_tracker: GapTracker<'a>,
+    /// Definition text still allowed, in bytes
+    bytes_left: usize,
+    /// Whether a depth or size limit stopped the walk somewhere
+    limited: bool,
+}
+
 /// Helper to track gaps (code between definitions)
 struct GapTracker<'a> {
     #[allow(dead_code)]
@@ -552,6 +807,32 @@ struct Point {
         }
     }
 
+    #[test]
+    fn test_context_lines_setting() {
+        let code = "fn a() {\n    1\n}\n\nfn b() {\n    2\n}\n\nfn c() {\n    3\n}\n";
+        let path = Path::new("test.rs");
+        let windows = |lines: usize| {
+            let mut chunker = SemanticChunker::new(100, 2000, 10).with_context_lines(lines);
+            let chunks = chunker.chunk_semantic(Language::Rust, path, code).unwrap();
+            let b = chunks
+                .into_iter()
+                .find(|c| c.content.starts_with("fn b"))
+                .unwrap();
+            (b.context_prev, b.context_next)
+        };
+
+        // Blank lines only: no window
+        assert_eq!(windows(1), (None, None));
+        assert_eq!(
+            windows(3),
+            (
+                Some("    1\n}\n".to_string()),
+                Some("\nfn c() {\n    3".to_string())
+            )
+        );
+        assert_eq!(windows(0), (None, None));
+    }
+
     #[test]
     fn test_chunk_python_code() {
         let mut chunker = SemanticChunker::new(100, 2000, 10);
@@ -655,6 +936,36 @@ class Calculator:
         }
     }
 
+    #[test]
+    fn test_split_function_gets_summary_chunk() {
+        let chunker = SemanticChunker::new(5, 2000, 1);
+
+        let body = (0..20)
+            .map(|i| format!("    step_{}();", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut chunk = Chunk::new(body, 10, 30, ChunkKind::Function, "test.rs".to_string());
+        chunk.signature = Some("fn run_pipeline()".to_string());
+        chunk.docstring = Some("/// Runs every pipeline step".to_string());
+
+        let parts = chunker.split_if_needed(chunk);
+        let summary = chunker.summarize_splits(&parts).unwrap();
+
+        assert_eq!(summary.start_line, 10);
+        assert_eq!(summary.end_line, parts.last().unwrap().end_line);
+        assert!(summary.split_index.is_none());
+        assert!(summary.content.contains("fn run_pipeline()"));
+        assert!(summary.content.contains("Runs every pipeline step"));
+        // First line of the last part is included
+        let last_first_line = parts.last().unwrap().content.lines().nth(1).unwrap();
+        assert!(summary.content.contains(last_first_line));
+
+        // Unsplit chunks and non-function kinds get no summary
+        assert!(chunker.summarize_splits(&parts[..1]).is_none());
+        let block = Chunk::new("x".repeat(10), 0, 1, ChunkKind::Block, "t.rs".to_string());
+        assert!(chunker.summarize_splits(&[block.clone(), block]).is_none());
+    }
+
     #[test]
     fn test_context_breadcrumbs() {
         let mut chunker = SemanticChunker::new(100, 2000, 10);
@@ -681,4 +992,118 @@ impl MyStruct {
             assert!(chunk.context[0].contains("File:"));
         }
     }
+
+    /// Chunk `content` and check the invariants that must hold for any input
+    fn assert_bounded(
+        chunker: &mut SemanticChunker,
+        language: Language,
+        path: &str,
+        content: &str,
+    ) {
+        let chunks = chunker
+            .chunk_semantic(language, Path::new(path), content)
+            .unwrap_or_default();
+        let hard_limit = 2000 * HARD_CHUNK_CHARS_FACTOR;
+        assert!(
+            chunks.len() <= MAX_CHUNKS_PER_FILE,
+            "{}: too many chunks",
+            path
+        );
+        for chunk in &chunks {
+            assert!(
+                chunk.content.len() <= hard_limit,
+                "{}: oversized chunk",
+                path
+            );
+            assert!(
+                chunk.start_line <= chunk.end_line,
+                "{}: inverted range",
+                path
+            );
+            for window in [&chunk.context_prev, &chunk.context_next]
+                .into_iter()
+                .flatten()
+            {
+                assert!(window.len() <= hard_limit, "{}: oversized context", path);
+            }
+        }
+    }
+
+    #[test]
+    fn test_adversarial_inputs_stay_bounded() {
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        let mut chunker = SemanticChunker::new(100, 2000, 10);
+
+        // Deeply nested code: nested definitions, expressions and classes
+        let depth = 5_000;
+        let nested_fns = format!("{}{}", "fn f() {\n".repeat(depth), "}\n".repeat(depth));
+        assert_bounded(&mut chunker, Language::Rust, "nested.rs", &nested_fns);
+        let nested_parens = format!("x = {}1{}\n", "(".repeat(depth), ")".repeat(depth));
+        assert_bounded(&mut chunker, Language::Python, "nested.py", &nested_parens);
+        let nested_classes: String = (0..500)
+            .map(|i| format!("{}class C{}:\n", "    ".repeat(i), i))
+            .collect();
+        assert_bounded(
+            &mut chunker,
+            Language::Python,
+            "classes.py",
+            &nested_classes,
+        );
+
+        // Enormous single lines: minified code and a long string literal
+        let minified = "function a(){return 1};".repeat(40_000);
+        assert_bounded(&mut chunker, Language::JavaScript, "app.min.js", &minified);
+        let literal = format!("const S: &str = \"{}\";\n", "é".repeat(100_000));
+        assert_bounded(&mut chunker, Language::Rust, "literal.rs", &literal);
+
+        // Undecodable bytes in comments (replacement characters after lossy
+        // decoding) and mixed encodings
+        let bytes = b"// caf\xe9 \xff\xfe\n/* \xc3\x28 */\nfn ok() {}\n";
+        let lossy = String::from_utf8_lossy(bytes).into_owned();
+        assert_bounded(&mut chunker, Language::Rust, "latin1.rs", &lossy);
+        let mixed = "# \u{feff}caf\u{e9} \u{0}\u{fffd}\u{1F600}\r\ndef f():\r\n    pass\r\n";
+        assert_bounded(&mut chunker, Language::Python, "mixed.py", mixed);
+
+        // Random soup of code fragments, control characters and multi-byte text
+        let fragments = [
+            "fn ",
+            "class ",
+            "def ",
+            "{",
+            "}",
+            "(",
+            ")",
+            "\n",
+            "    ",
+            "/*",
+            "*/",
+            "//",
+            "\"",
+            "é",
+            "\u{fffd}",
+            "\u{0}",
+            "\t",
+            "=>",
+            "impl X for Y ",
+            "async ",
+            "@decorator\n",
+        ];
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        let languages = [
+            Language::Rust,
+            Language::Python,
+            Language::JavaScript,
+            Language::Go,
+        ];
+        for i in 0..40 {
+            let len = rng.gen_range(0..4_000);
+            let content: String = (0..len)
+                .map(|_| fragments[rng.gen_range(0..fragments.len())])
+                .collect();
+            let language = languages[i % languages.len()];
+            assert_bounded(&mut chunker, language, &format!("random{}", i), &content);
+        }
+    }
 }
@@ -0,0 +1,447 @@
+//! SQL chunking by statement
+//!
+//! SQL files (schemas, migrations, seed scripts) are split into one chunk per
+//! top-level statement instead of fixed-size line windows. Each chunk is
+//! tagged with the tables and columns it touches ("ALTER TABLE orders",
+//! "COLUMN orders.status") so schema queries land on the right migration.
+
+use super::{Chunk, ChunkKind};
+use crate::cache::normalize_path;
+use std::path::Path;
+
+/// Keywords that open a constraint rather than a column in a column list
+const CONSTRAINT_KEYWORDS: &[&str] = &[
+    "PRIMARY",
+    "FOREIGN",
+    "CONSTRAINT",
+    "UNIQUE",
+    "CHECK",
+    "KEY",
+    "INDEX",
+    "EXCLUDE",
+    "FULLTEXT",
+];
+
+/// Maximum signature length (first line of the statement)
+const MAX_SIGNATURE_CHARS: usize = 120;
+
+/// Split a SQL file into one chunk per statement
+///
+/// Comments directly above a statement stay with it. Files under a
+/// `migrations`/`migrate` directory get a `Migration: <file>` breadcrumb.
+pub fn chunk_sql(path: &Path, content: &str) -> Vec<Chunk> {
+    let path_str = normalize_path(path);
+    let mut context = vec![format!("File: {}", path_str)];
+    if is_migration_path(&path_str) {
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        context.push(format!("Migration: {}", name));
+    }
+
+    split_statements(content)
+        .into_iter()
+        .map(|stmt| {
+            let code = strip_comments(stmt.text);
+            let mut chunk = Chunk::new(
+                stmt.text.to_string(),
+                stmt.start_line,
+                stmt.end_line,
+                statement_kind(&code),
+                path_str.clone(),
+            );
+            chunk.context = context.clone();
+            chunk.signature = statement_signature(&code);
+            chunk.tags = statement_tags(&code);
+            chunk
+        })
+        .collect()
+}
+
+/// Whether a path points into a migrations directory
+fn is_migration_path(path: &str) -> bool {
+    path.to_lowercase()
+        .split('/')
+        .rev()
+        .skip(1)
+        .any(|c| c.contains("migration") || c == "migrate")
+}
+
+/// A top-level statement with its 0-based start line and exclusive end line
+struct Statement<'a> {
+    text: &'a str,
+    start_line: usize,
+    end_line: usize,
+}
+
+/// Split SQL text at top-level semicolons
+///
+/// Semicolons inside string literals, quoted identifiers, comments and
+/// dollar-quoted bodies (`$$ ... $$`, PostgreSQL functions) are ignored.
+fn split_statements(content: &str) -> Vec<Statement<'_>> {
+    let bytes = content.as_bytes();
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'-' if bytes.get(i + 1) == Some(&b'-') => {
+                i = content[i..].find('\n').map_or(bytes.len(), |n| i + n);
+            }
+            b'/' if bytes.get(i + 1) == Some(&b'*') => {
+                i = content[i + 2..]
+                    .find("*/")
+                    .map_or(bytes.len(), |n| i + 2 + n + 2);
+            }
+            quote @ (b'\'' | b'"' | b'`') => {
+                i += 1;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+                i += 1;
+            }
+            b'$' => {
+                // Dollar quoting: $$ or $tag$
+                let tag_end = content[i + 1..]
+                    .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                    .map(|n| i + 1 + n);
+                match tag_end {
+                    Some(end) if bytes[end] == b'$' => {
+                        let tag = &content[i..=end];
+                        i = content[end + 1..]
+                            .find(tag)
+                            .map_or(bytes.len(), |n| end + 1 + n + tag.len());
+                    }
+                    _ => i += 1,
+                }
+            }
+            b';' => {
+                push_statement(content, start, i + 1, &mut statements);
+                start = i + 1;
+                i += 1;
+            }
+            _ => i += 1,
+        }
+    }
+    push_statement(content, start, bytes.len(), &mut statements);
+
+    statements
+}
+
+fn push_statement<'a>(content: &'a str, start: usize, end: usize, out: &mut Vec<Statement<'a>>) {
+    let end = end.min(content.len());
+    let raw = &content[start..end];
+    let text = raw.trim();
+    if text.is_empty() || strip_comments(text).trim().is_empty() {
+        return;
+    }
+    let offset = start + (raw.len() - raw.trim_start().len());
+    let start_line = content[..offset].matches('\n').count();
+    let end_line = start_line + text.matches('\n').count() + 1;
+    out.push(Statement {
+        text,
+        start_line,
+        end_line,
+    });
+}
+
+/// Remove `--` and `/* */` comments (string contents are left as is)
+fn strip_comments(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut rest = sql;
+    loop {
+        let line = rest.find("--");
+        let block = rest.find("/*");
+        match (line, block) {
+            (Some(l), b) if b.is_none_or(|b| l < b) => {
+                out.push_str(&rest[..l]);
+                rest = rest[l..].find('\n').map_or("", |n| &rest[l + n..]);
+            }
+            (_, Some(b)) => {
+                out.push_str(&rest[..b]);
+                rest = rest[b + 2..]
+                    .find("*/")
+                    .map_or("", |n| &rest[b + 2 + n + 2..]);
+            }
+            _ => {
+                out.push_str(rest);
+                return out;
+            }
+        }
+    }
+}
+
+/// Tokenize SQL into words and single-character punctuation, skipping string literals
+fn tokenize(sql: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = sql.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '\'' {
+            chars.next();
+            for c in chars.by_ref() {
+                if c == '\'' {
+                    break;
+                }
+            }
+        } else if c.is_alphanumeric() || matches!(c, '_' | '"' | '`' | '[' | '.' | '$') {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_alphanumeric() || matches!(c, '_' | '"' | '`' | '[' | ']' | '.' | '$') {
+                    word.push(c);
+                    chars.next();
+                } else {
+                    break;
+                }
+            }
+            tokens.push(word);
+        } else {
+            tokens.push(c.to_string());
+            chars.next();
+        }
+    }
+    tokens
+}
+
+/// Strip identifier quoting: "orders", `orders`, [orders]
+fn unquote(ident: &str) -> String {
+    ident
+        .chars()
+        .filter(|c| !matches!(c, '"' | '`' | '[' | ']'))
+        .collect()
+}
+
+fn upper(tokens: &[String], i: usize) -> String {
+    tokens.get(i).map(|t| t.to_uppercase()).unwrap_or_default()
+}
+
+/// Skip optional modifiers (`IF NOT EXISTS`, `IF EXISTS`, `ONLY`) and return the next index
+fn skip_modifiers(tokens: &[String], mut i: usize) -> usize {
+    loop {
+        match upper(tokens, i).as_str() {
+            "IF" | "NOT" | "EXISTS" | "ONLY" => i += 1,
+            _ => return i,
+        }
+    }
+}
+
+fn statement_kind(code: &str) -> ChunkKind {
+    let tokens = tokenize(code);
+    let head: Vec<String> = (0..5).map(|i| upper(&tokens, i)).collect();
+    if head[0] == "CREATE"
+        && head
+            .iter()
+            .any(|w| matches!(w.as_str(), "FUNCTION" | "PROCEDURE" | "TRIGGER"))
+    {
+        ChunkKind::Function
+    } else {
+        ChunkKind::Block
+    }
+}
+
+/// First line of the statement with whitespace collapsed
+fn statement_signature(code: &str) -> Option<String> {
+    let line = code.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let collapsed = line.split_whitespace().collect::<Vec<_>>().join(" ");
+    let signature: String = collapsed.chars().take(MAX_SIGNATURE_CHARS).collect();
+    Some(signature)
+}
+
+/// Tag the tables and columns a statement defines or touches
+fn statement_tags(code: &str) -> Vec<String> {
+    let tokens = tokenize(code);
+    let mut tags = Vec::new();
+    let mut push = |tag: String| {
+        if !tags.contains(&tag) {
+            tags.push(tag);
+        }
+    };
+
+    let first = upper(&tokens, 0);
+    // Position of the object type after CREATE [OR REPLACE] [TEMP|TEMPORARY|UNIQUE|MATERIALIZED]
+    let mut obj = 1;
+    while matches!(
+        upper(&tokens, obj).as_str(),
+        "OR" | "REPLACE" | "TEMP" | "TEMPORARY" | "UNIQUE" | "MATERIALIZED" | "UNLOGGED"
+    ) {
+        obj += 1;
+    }
+
+    match (first.as_str(), upper(&tokens, obj).as_str()) {
+        ("CREATE", "TABLE") => {
+            let name_at = skip_modifiers(&tokens, obj + 1);
+            if let Some(table) = tokens.get(name_at).map(|t| unquote(t)) {
+                push(format!("CREATE TABLE {}", table));
+                if tokens.get(name_at + 1).map(String::as_str) == Some("(") {
+                    for column in column_names(&tokens, name_at + 2) {
+                        push(format!("COLUMN {}.{}", table, column));
+                    }
+                }
+            }
+        }
+        ("CREATE", object @ ("VIEW" | "FUNCTION" | "PROCEDURE" | "TRIGGER" | "TYPE"))
+        | ("DROP", object @ ("TABLE" | "VIEW" | "INDEX" | "FUNCTION" | "TYPE")) => {
+            let name_at = skip_modifiers(&tokens, obj + 1);
+            if let Some(name) = tokens.get(name_at) {
+                push(format!("{} {} {}", first, object, unquote(name)));
+            }
+        }
+        ("CREATE", "INDEX") => {
+            if let Some(on) = tokens.iter().position(|t| t.eq_ignore_ascii_case("ON")) {
+                let table_at = skip_modifiers(&tokens, on + 1);
+                if let Some(table) = tokens.get(table_at).map(|t| unquote(t)) {
+                    push(format!("CREATE INDEX ON {}", table));
+                    if tokens.get(table_at + 1).map(String::as_str) == Some("(") {
+                        for column in column_names(&tokens, table_at + 2) {
+                            push(format!("COLUMN {}.{}", table, column));
+                        }
+                    }
+                }
+            }
+        }
+        ("ALTER", "TABLE") => {
+            let name_at = skip_modifiers(&tokens, obj + 1);
+            if let Some(table) = tokens.get(name_at).map(|t| unquote(t)) {
+                push(format!("ALTER TABLE {}", table));
+                for i in name_at + 1..tokens.len() {
+                    let word = upper(&tokens, i);
+                    if !matches!(
+                        word.as_str(),
+                        "ADD" | "DROP" | "ALTER" | "RENAME" | "MODIFY"
+                    ) {
+                        continue;
+                    }
+                    let mut col = i + 1;
+                    if upper(&tokens, col) == "COLUMN" {
+                        col += 1;
+                    }
+                    col = skip_modifiers(&tokens, col);
+                    let Some(column) = tokens.get(col) else {
+                        continue;
+                    };
+                    let column_upper = column.to_uppercase();
+                    if column.len() > 1
+                        && !CONSTRAINT_KEYWORDS.contains(&column_upper.as_str())
+                        && !matches!(column_upper.as_str(), "TO" | "COLUMN")
+                    {
+                        push(format!("COLUMN {}.{}", table, unquote(column)));
+                    }
+                }
+            }
+        }
+        ("INSERT", _) | ("UPDATE", _) | ("DELETE", _) | ("MERGE", _) => {
+            let target_at = match first.as_str() {
+                "UPDATE" => skip_modifiers(&tokens, 1),
+                _ => tokens
+                    .iter()
+                    .position(|t| t.eq_ignore_ascii_case("INTO") || t.eq_ignore_ascii_case("FROM"))
+                    .map_or(tokens.len(), |p| p + 1),
+            };
+            if let Some(table) = tokens.get(target_at) {
+                push(format!("{} {}", first, unquote(table)));
+            }
+        }
+        _ => {}
+    }
+
+    // Tables read by any statement (SELECT ... FROM / JOIN)
+    for (i, token) in tokens.iter().enumerate() {
+        if token.eq_ignore_ascii_case("FROM") || token.eq_ignore_ascii_case("JOIN") {
+            if let Some(table) = tokens.get(i + 1) {
+                if table != "(" && table.chars().next().is_some_and(|c| !c.is_numeric()) {
+                    push(format!("TABLE {}", unquote(table)));
+                }
+            }
+        }
+    }
+
+    tags
+}
+
+/// Column names from a parenthesized definition list starting at `start`
+fn column_names(tokens: &[String], start: usize) -> Vec<String> {
+    let mut columns = Vec::new();
+    let mut depth = 0usize;
+    let mut at_item_start = true;
+
+    for token in &tokens[start.min(tokens.len())..] {
+        match token.as_str() {
+            "(" => depth += 1,
+            ")" if depth == 0 => break,
+            ")" => depth -= 1,
+            "," if depth == 0 => {
+                at_item_start = true;
+                continue;
+            }
+            _ if at_item_start
+                && depth == 0
+                && !CONSTRAINT_KEYWORDS.contains(&token.to_uppercase().as_str()) =>
+            {
+                columns.push(unquote(token));
+            }
+            _ => {}
+        }
+        at_item_start = false;
+    }
+
+    columns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_statements_respects_quotes_and_bodies() {
+        let sql = "-- orders\nCREATE TABLE orders (id INT, note TEXT DEFAULT 'a;b');\n\nCREATE FUNCTION f() RETURNS void AS $$ BEGIN PERFORM 1; END $$ LANGUAGE plpgsql;\n";
+        let chunks = chunk_sql(Path::new("schema.sql"), sql);
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].start_line, 0);
+        assert_eq!(chunks[0].end_line, 2);
+        assert!(chunks[0].content.starts_with("-- orders"));
+        assert_eq!(chunks[1].kind, ChunkKind::Function);
+        assert_eq!(chunks[1].start_line, 3);
+    }
+
+    #[test]
+    fn test_create_and_alter_table_tags() {
+        let sql = "CREATE TABLE IF NOT EXISTS \"orders\" (\n  id BIGINT PRIMARY KEY,\n  total NUMERIC(10, 2),\n  CONSTRAINT fk FOREIGN KEY (id) REFERENCES users(id)\n);\nALTER TABLE orders ADD COLUMN status TEXT, DROP COLUMN legacy;";
+        let chunks = chunk_sql(Path::new("db/migrations/0002_orders.sql"), sql);
+        assert_eq!(
+            chunks[0].tags,
+            vec![
+                "CREATE TABLE orders",
+                "COLUMN orders.id",
+                "COLUMN orders.total"
+            ]
+        );
+        assert_eq!(
+            chunks[1].tags,
+            vec![
+                "ALTER TABLE orders",
+                "COLUMN orders.status",
+                "COLUMN orders.legacy"
+            ]
+        );
+        assert_eq!(chunks[1].context[1], "Migration: 0002_orders");
+        assert_eq!(
+            chunks[1].signature.as_deref(),
+            Some("ALTER TABLE orders ADD COLUMN status TEXT, DROP COLUMN legacy;")
+        );
+    }
+
+    #[test]
+    fn test_dml_tags() {
+        assert_eq!(
+            statement_tags("UPDATE orders SET status = 'x' WHERE id IN (SELECT id FROM items)"),
+            vec!["UPDATE orders", "TABLE items"]
+        );
+        assert_eq!(
+            statement_tags("INSERT INTO audit_log (id) VALUES (1)"),
+            vec!["INSERT audit_log"]
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! `codesearch activity`: live view of what the index writer is doing
+//!
+//! Tails the writer's log file (`.codesearch.db/logs/codesearch.log.YYYY-MM-DD`)
+//! and shows batch flushes, branch refreshes and resyncs, plus the files being
+//! indexed or removed when the writer logs at debug level. Follows daily log
+//! rotation until interrupted.
+
+use crate::db_discovery::find_best_database;
+use crate::index::is_database_locked;
+use crate::logger::{get_log_dir, parse_log_name};
+use crate::out_print;
+use anyhow::{anyhow, Result};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+
+/// Log message fragments that describe index activity
+const ACTIVITY_MARKERS: &[&str] = &[
+    "Indexing:",
+    "Removing:",
+    "Directory deleted",
+    "Flushing batch",
+    "Batch complete",
+    "Batch processing failed",
+    "Failed to index",
+    "Failed to remove",
+    "quarantined",
+    "Git branch changed",
+    "Full refresh",
+    "resynchronizing",
+    "Resync",
+    "incremental refresh",
+    "Incremental refresh",
+    "Indexed ",
+    "Replaying",
+    "File watcher",
+];
+
+/// How often the log file is checked for new lines while following
+const FOLLOW_POLL_MS: u64 = 500;
+
+/// Bytes read per step while scanning the log backwards for the backlog
+const BACKLOG_BLOCK_BYTES: u64 = 64 * 1024;
+
+pub async fn run(
+    path: Option<PathBuf>,
+    lines: usize,
+    all: bool,
+    follow: bool,
+    cancel_token: CancellationToken,
+) -> Result<()> {
+    let db_info = find_best_database(path.as_deref())?
+        .ok_or_else(|| anyhow!("No database found. Run 'codesearch index' first."))?;
+    let log_dir = get_log_dir(&db_info.db_path);
+
+    out_print!("📂 Project: {}", db_info.project_path.display());
+    if is_database_locked(&db_info.db_path) {
+        out_print!("✍️  Writer instance is running");
+    } else {
+        out_print!("💤 No writer instance is running; showing the last recorded activity");
+    }
+
+    let Some(mut log_file) = latest_log_file(&log_dir) else {
+        out_print!("No log files in {}", log_dir.display());
+        return Ok(());
+    };
+
+    // Backlog: the last `lines` activity lines of the current log file
+    let (backlog, mut offset) = read_backlog(&log_file, lines, BACKLOG_BLOCK_BYTES, |line| {
+        all || is_activity_line(line)
+    })?;
+    for line in backlog {
+        out_print!("{}", line);
+    }
+
+    if !follow {
+        return Ok(());
+    }
+    out_print!("── following {} (Ctrl-C to stop) ──", log_file.display());
+
+    let mut pending = String::new();
+    loop {
+        tokio::select! {
+            _ = tokio::time::sleep(std::time::Duration::from_millis(FOLLOW_POLL_MS)) => {}
+            _ = cancel_token.cancelled() => break,
+        }
+
+        // Daily rotation starts a new file; truncation restarts the current one
+        if let Some(latest) = latest_log_file(&log_dir) {
+            if latest != log_file {
+                log_file = latest;
+                offset = 0;
+                pending.clear();
+            }
+        }
+        let len = std::fs::metadata(&log_file).map(|m| m.len()).unwrap_or(0);
+        if len < offset {
+            offset = 0;
+            pending.clear();
+        }
+        if len == offset {
+            continue;
+        }
+
+        let (chunk, read) = read_from(&log_file, offset)?;
+        offset += read;
+        pending.push_str(&chunk);
+
+        // Only print complete lines; keep a partially written one for the next poll
+        let complete = pending.rfind('\n').map(|i| i + 1).unwrap_or(0);
+        for line in pending[..complete].lines() {
+            if all || is_activity_line(line) {
+                out_print!("{}", line);
+            }
+        }
+        pending.drain(..complete);
+    }
+
+    Ok(())
+}
+
+/// Whether a log line describes index activity (as opposed to startup/search noise)
+fn is_activity_line(line: &str) -> bool {
+    ACTIVITY_MARKERS.iter().any(|marker| line.contains(marker))
+}
+
+/// Most recent daily log file, leaving out the parts rotated by size
+fn latest_log_file(log_dir: &Path) -> Option<PathBuf> {
+    std::fs::read_dir(log_dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter_map(|path| {
+            let name = parse_log_name(path.file_name()?.to_str()?)?;
+            name.is_live().then_some((name.date, path))
+        })
+        .max()
+        .map(|(_, path)| path)
+}
+
+/// The last `count` complete lines of a file that `keep` accepts, oldest
+/// first, with the offset following them
+///
+/// The file is read backwards in blocks of `block_bytes` until enough lines
+/// are found, so a long-lived writer's log isn't read whole. A partially
+/// written last line is left for the caller to read from the offset.
+fn read_backlog(
+    path: &Path,
+    count: usize,
+    block_bytes: u64,
+    keep: impl Fn(&str) -> bool,
+) -> Result<(Vec<String>, u64)> {
+    let mut file = std::fs::File::open(path)?;
+    // Bytes before `pos` are still unread; `rest` holds those from `pos` up to
+    // the line break of the last line looked at
+    let mut pos = file.metadata()?.len();
+    let mut rest: Vec<u8> = Vec::new();
+    // End of the last complete line, once a line break has been seen
+    let mut offset = None;
+    let mut found = Vec::new();
+    let take = |line: &[u8], found: &mut Vec<String>| {
+        let line = String::from_utf8_lossy(line);
+        let line = line.strip_suffix('\r').unwrap_or(&line);
+        if keep(line) {
+            found.push(line.to_string());
+        }
+    };
+
+    while offset.is_none() || found.len() < count {
+        if pos == 0 {
+            // What's left is the first line of the file
+            if offset.is_some() {
+                take(&rest, &mut found);
+            }
+            break;
+        }
+        let start = pos.saturating_sub(block_bytes);
+        let mut block = vec![0; (pos - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(&mut block)?;
+        block.append(&mut rest);
+        rest = block;
+        pos = start;
+
+        while let Some(i) = rest.iter().rposition(|&b| b == b'\n') {
+            let line = rest.split_off(i + 1);
+            rest.truncate(i);
+            match offset {
+                Some(_) => take(&line, &mut found),
+                None => offset = Some(pos + i as u64 + 1),
+            }
+            if found.len() >= count {
+                break;
+            }
+        }
+    }
+
+    found.reverse();
+    Ok((found, offset.unwrap_or(0)))
+}
+
+/// Read a file from `offset` to its current end (lossy UTF-8), with the byte count read
+fn read_from(path: &Path, offset: u64) -> Result<(String, u64)> {
+    let mut file = std::fs::File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes)?;
+    Ok((
+        String::from_utf8_lossy(&bytes).into_owned(),
+        bytes.len() as u64,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_activity_lines_and_latest_log() {
+        assert!(is_activity_line(
+            "2025-01-15T10:42:07Z  INFO codesearch::index::manager: 📦 Flushing batch: 2 to index, 0 to remove"
+        ));
+        assert!(is_activity_line(
+            "2025-01-15T10:42:07Z  INFO codesearch::index::manager: 📄 Indexing: src/main.rs"
+        ));
+        assert!(!is_activity_line(
+            "2025-01-15T10:42:07Z  INFO codesearch::mcp: 🚀 Starting codesearch MCP server"
+        ));
+
+        let dir = tempfile::tempdir().unwrap();
+        assert!(latest_log_file(dir.path()).is_none());
+        std::fs::write(dir.path().join("codesearch.log.2025-01-14"), "old").unwrap();
+        std::fs::write(dir.path().join("codesearch.log.2025-01-15"), "new").unwrap();
+        std::fs::write(dir.path().join("codesearch.log.2025-01-15.1.gz"), "gz").unwrap();
+        std::fs::write(dir.path().join("other.txt"), "x").unwrap();
+        assert_eq!(
+            latest_log_file(dir.path()).unwrap(),
+            dir.path().join("codesearch.log.2025-01-15")
+        );
+        assert_eq!(
+            read_from(&dir.path().join("codesearch.log.2025-01-15"), 1).unwrap(),
+            ("ew".to_string(), 2)
+        );
+    }
+
+    #[test]
+    fn test_read_backlog_scans_backwards() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("codesearch.log.2025-01-15");
+        std::fs::write(&log, "a1\nb2\r\na3\nb4\na5\npartial").unwrap();
+        let odd = |line: &str| line.starts_with('a');
+
+        // Blocks smaller than a line still find whole lines
+        for block in [3, 4, 1024] {
+            assert_eq!(
+                read_backlog(&log, 2, block, odd).unwrap(),
+                (vec!["a3".to_string(), "a5".to_string()], 16)
+            );
+            assert_eq!(
+                read_backlog(&log, 10, block, |_| true).unwrap().0,
+                vec!["a1", "b2", "a3", "b4", "a5"]
+            );
+        }
+        assert_eq!(read_backlog(&log, 0, 4, odd).unwrap(), (vec![], 16));
+
+        std::fs::write(&log, "no line break yet").unwrap();
+        assert_eq!(read_backlog(&log, 5, 4, odd).unwrap(), (vec![], 0));
+    }
+}
@@ -0,0 +1,408 @@
+//! `codesearch coverage`: what the index leaves out, and why
+//!
+//! Walks the project like indexing does and groups every file that search
+//! can't find by reason: skipped by the walk (empty, generated, binary,
+//! unsupported language, outside `--lang`, excluded or pruned directory),
+//! hidden or ignored by `.gitignore`/`.codesearchignore`, failing to index,
+//! indexed without chunks, or not indexed yet. Given a file, it explains just
+//! that file.
+
+use crate::cache::{normalize_path, FileMetaStore};
+use crate::db_discovery::find_best_database;
+use crate::file::{FileWalker, Language, SkipReason};
+use crate::{data_print, out_print};
+use anyhow::{anyhow, Result};
+use colored::Colorize;
+use ignore::WalkBuilder;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Why a file or directory isn't searchable, most actionable first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Reason {
+    NotIndexed,
+    Failed,
+    NoChunks,
+    Empty,
+    Generated,
+    Binary,
+    UnsupportedLanguage,
+    LanguageFilter,
+    ExcludedDir,
+    PrunedDir,
+    Ignored,
+    Hidden,
+}
+
+impl From<SkipReason> for Reason {
+    fn from(reason: SkipReason) -> Self {
+        match reason {
+            SkipReason::Empty => Reason::Empty,
+            SkipReason::Generated => Reason::Generated,
+            SkipReason::Binary => Reason::Binary,
+            SkipReason::UnsupportedLanguage => Reason::UnsupportedLanguage,
+            SkipReason::LanguageFilter => Reason::LanguageFilter,
+            SkipReason::ExcludedDir => Reason::ExcludedDir,
+            SkipReason::PrunedDir => Reason::PrunedDir,
+        }
+    }
+}
+
+impl Reason {
+    fn describe(self) -> &'static str {
+        match self {
+            Reason::NotIndexed => "Not indexed yet (run `codesearch index`)",
+            Reason::Failed => "Failed to index (see `codesearch doctor`)",
+            Reason::NoChunks => "Indexed, but nothing to chunk",
+            Reason::Empty => "Empty files",
+            Reason::Generated => "Generated, minified or scratch files",
+            Reason::Binary => "Binary files",
+            Reason::UnsupportedLanguage => "Unsupported file types",
+            Reason::LanguageFilter => "Outside the index's languages (`index --lang`)",
+            Reason::ExcludedDir => "Directories never indexed",
+            Reason::PrunedDir => "Directories pruned for CODESEARCH_INDEX_MAX_MB",
+            Reason::Ignored => "Ignored by .gitignore or .codesearchignore",
+            Reason::Hidden => "Hidden files and directories",
+        }
+    }
+}
+
+/// A path left out of the index, relative to the project root
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct Sample {
+    path: String,
+    /// The error of a file that failed to index
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+}
+
+/// Paths left out for one reason
+#[derive(Debug, Serialize)]
+struct ReasonSummary {
+    reason: Reason,
+    description: &'static str,
+    count: usize,
+    samples: Vec<Sample>,
+}
+
+#[derive(Debug, Serialize)]
+struct CoverageReport {
+    project: String,
+    indexed_files: usize,
+    skipped: Vec<ReasonSummary>,
+}
+
+/// Everything the walk and the index say about the project's files
+struct Coverage {
+    indexed_files: usize,
+    skipped: Vec<(Reason, Sample)>,
+}
+
+pub async fn run(path: Option<PathBuf>, samples: usize, json: bool) -> Result<()> {
+    // A file is explained within the project of its directory
+    let file = path.as_ref().filter(|path| path.is_file()).cloned();
+    let search_dir = match &file {
+        Some(file) => file.parent().map(Path::to_path_buf),
+        None => path,
+    };
+    let db_info = find_best_database(search_dir.as_deref())?
+        .ok_or_else(|| anyhow!("No database found. Run 'codesearch index' first."))?;
+    let project_path = db_info.project_path.as_path();
+    let file_meta = FileMetaStore::read(&db_info.db_path);
+    let coverage = collect(
+        project_path,
+        crate::index::read_language_filter(&db_info.db_path),
+        crate::index::read_pruned_dirs(&db_info.db_path),
+        file_meta.as_ref(),
+    )?;
+
+    if let Some(file) = file {
+        return explain_file(&file, project_path, &coverage, file_meta.as_ref());
+    }
+
+    let report = CoverageReport {
+        project: project_path.display().to_string(),
+        indexed_files: coverage.indexed_files,
+        skipped: summarize(coverage.skipped, samples),
+    };
+    if json {
+        data_print!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    out_print!("📂 Project: {}", report.project);
+    out_print!("✅ Indexed files: {}", report.indexed_files);
+    if report.skipped.is_empty() {
+        out_print!("Nothing was left out");
+    }
+    for summary in &report.skipped {
+        out_print!(
+            "\n{} {}",
+            summary.description.bright_yellow(),
+            format!("({})", summary.count).dimmed()
+        );
+        for sample in &summary.samples {
+            match &sample.detail {
+                Some(detail) => out_print!("   {}: {}", sample.path, detail.dimmed()),
+                None => out_print!("   {}", sample.path),
+            }
+        }
+        if summary.count > summary.samples.len() {
+            out_print!(
+                "   {}",
+                format!("... and {} more", summary.count - summary.samples.len()).dimmed()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Say whether one file is searchable, and if not why
+fn explain_file(
+    file: &Path,
+    project_path: &Path,
+    coverage: &Coverage,
+    file_meta: Option<&FileMetaStore>,
+) -> Result<()> {
+    let file = file.canonicalize()?;
+    let root = project_path
+        .canonicalize()
+        .unwrap_or_else(|_| project_path.to_path_buf());
+    let relative = file
+        .strip_prefix(&root)
+        .map_err(|_| anyhow!("{} is outside {}", file.display(), root.display()))?;
+    let relative = normalize_path(relative);
+
+    // The file itself, or the directory it was left out with
+    let skipped = coverage.skipped.iter().find(|(_, sample)| {
+        relative == sample.path
+            || relative.starts_with(&format!("{}/", sample.path.trim_end_matches('/')))
+    });
+    match skipped {
+        Some((reason, sample)) => {
+            out_print!("❌ {}: {}", relative, reason.describe());
+            if sample.path != relative {
+                out_print!("   (in {})", sample.path);
+            }
+            if let Some(detail) = &sample.detail {
+                out_print!("   {}", detail);
+            }
+        }
+        None => {
+            let chunks = file_meta
+                .and_then(|meta| meta.chunk_count(&normalize_path(&project_path.join(&relative))))
+                .unwrap_or(0);
+            out_print!("✅ {} is indexed ({} chunks)", relative, chunks);
+        }
+    }
+    Ok(())
+}
+
+/// Walk the project and compare what is found with the file metadata
+fn collect(
+    project_path: &Path,
+    languages: Option<Vec<Language>>,
+    pruned_dirs: Vec<String>,
+    file_meta: Option<&FileMetaStore>,
+) -> Result<Coverage> {
+    // Directories end in `/`
+    let relative = |path: &Path| {
+        let mut relative = normalize_path(path.strip_prefix(project_path).unwrap_or(path));
+        if path.is_dir() {
+            relative.push('/');
+        }
+        relative
+    };
+
+    let mut skipped = Vec::new();
+    // Paths the walk visited, to tell ignored paths apart in the second walk
+    let mut seen: HashSet<PathBuf> = HashSet::new();
+    let (files, _) = FileWalker::new(project_path)
+        .with_languages(languages)
+        .with_excluded_dirs(pruned_dirs)
+        .walk_with_skips(|path, reason| {
+            seen.insert(path.to_path_buf());
+            skipped.push((
+                Reason::from(reason),
+                Sample {
+                    path: relative(path),
+                    detail: None,
+                },
+            ));
+        })?;
+
+    let failures: BTreeMap<String, String> = file_meta
+        .map(|meta| {
+            meta.failing_files()
+                .into_iter()
+                .map(|(path, failure)| (path, failure.last_error))
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut indexed_files = 0;
+    for file in &files {
+        seen.insert(file.path.clone());
+        let key = normalize_path(&file.path);
+        let (reason, detail) = if let Some(error) = failures.get(&key) {
+            (Reason::Failed, Some(error.clone()))
+        } else {
+            match file_meta.and_then(|meta| meta.chunk_count(&key)) {
+                Some(0) => (Reason::NoChunks, None),
+                Some(_) => {
+                    indexed_files += 1;
+                    continue;
+                }
+                None => (Reason::NotIndexed, None),
+            }
+        };
+        skipped.push((
+            reason,
+            Sample {
+                path: relative(&file.path),
+                detail,
+            },
+        ));
+    }
+
+    // Directories the walk went through
+    let seen_dirs: HashSet<PathBuf> = seen
+        .iter()
+        .flat_map(|path| path.ancestors().skip(1))
+        .map(Path::to_path_buf)
+        .collect();
+
+    // Walk again without ignore rules: what the first walk didn't visit is
+    // hidden or ignored; unvisited directories are reported, not entered
+    let unseen = Arc::new(Mutex::new(Vec::new()));
+    let unseen_dirs = Arc::clone(&unseen);
+    let reported = Arc::new(seen.clone());
+    let mut builder = WalkBuilder::new(project_path);
+    builder.standard_filters(false).filter_entry(move |entry| {
+        if entry.depth() == 0 {
+            return true;
+        }
+        let path = entry.path();
+        if reported.contains(path) {
+            // Excluded directory, reported by the first walk
+            return false;
+        }
+        let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+        if is_dir && !seen_dirs.contains(path) {
+            // An empty directory hides nothing
+            if std::fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_some()) {
+                if let Ok(mut unseen) = unseen_dirs.lock() {
+                    unseen.push(path.to_path_buf());
+                }
+            }
+            return false;
+        }
+        true
+    });
+    let mut unseen_paths = Vec::new();
+    for entry in builder.build().flatten() {
+        let is_file = entry.file_type().is_some_and(|t| t.is_file());
+        if is_file && !seen.contains(entry.path()) {
+            unseen_paths.push(entry.into_path());
+        }
+    }
+    unseen_paths.extend(std::mem::take(
+        &mut *unseen.lock().unwrap_or_else(|e| e.into_inner()),
+    ));
+    for path in unseen_paths {
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        skipped.push((
+            if hidden {
+                Reason::Hidden
+            } else {
+                Reason::Ignored
+            },
+            Sample {
+                path: relative(&path),
+                detail: None,
+            },
+        ));
+    }
+
+    Ok(Coverage {
+        indexed_files,
+        skipped,
+    })
+}
+
+/// Group skipped paths by reason, keeping `samples` paths of each (0 = all)
+fn summarize(mut skipped: Vec<(Reason, Sample)>, samples: usize) -> Vec<ReasonSummary> {
+    skipped.sort_by(|a, b| a.1.path.cmp(&b.1.path));
+    let mut summaries: BTreeMap<Reason, ReasonSummary> = BTreeMap::new();
+    for (reason, sample) in skipped {
+        let summary = summaries.entry(reason).or_insert_with(|| ReasonSummary {
+            reason,
+            description: reason.describe(),
+            count: 0,
+            samples: Vec::new(),
+        });
+        summary.count += 1;
+        if samples == 0 || summary.samples.len() < samples {
+            summary.samples.push(sample);
+        }
+    }
+    summaries.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_coverage_reasons() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let write = |path: &str, content: &[u8]| {
+            let path = root.join(path);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, content).unwrap();
+        };
+        write("src/main.rs", b"fn main() {}");
+        write("src/empty.rs", b"");
+        write("app.min.js", b"var a=1;");
+        write("blob.dat", b"\x89PNG\x00\x01\x00\x00");
+        write("data.xyz", b"text");
+        write("node_modules/pkg/index.js", b"module.exports = 1;");
+        write(".codesearchignore", b"build/\nnotes.rs\n");
+        write("build/out.rs", b"fn out() {}");
+        write("notes.rs", b"fn notes() {}");
+        write(".github/ci.yml", b"on: push");
+
+        let coverage = collect(&root, None, Vec::new(), None).unwrap();
+        let reasons: BTreeMap<String, Reason> = coverage
+            .skipped
+            .iter()
+            .map(|(reason, sample)| (sample.path.clone(), *reason))
+            .collect();
+        assert_eq!(coverage.indexed_files, 0);
+        assert_eq!(reasons["src/main.rs"], Reason::NotIndexed);
+        assert_eq!(reasons["src/empty.rs"], Reason::Empty);
+        assert_eq!(reasons["app.min.js"], Reason::Generated);
+        assert_eq!(reasons["blob.dat"], Reason::Binary);
+        assert_eq!(reasons["data.xyz"], Reason::UnsupportedLanguage);
+        assert_eq!(reasons["node_modules/"], Reason::ExcludedDir);
+        assert_eq!(reasons["build/"], Reason::Ignored);
+        assert_eq!(reasons["notes.rs"], Reason::Ignored);
+        assert_eq!(reasons[".github/"], Reason::Hidden);
+        assert_eq!(reasons[".codesearchignore"], Reason::Hidden);
+
+        let summaries = summarize(coverage.skipped, 1);
+        let ignored = summaries
+            .iter()
+            .find(|s| s.reason == Reason::Ignored)
+            .unwrap();
+        assert_eq!(ignored.count, 2);
+        assert_eq!(ignored.samples.len(), 1);
+        assert_eq!(summaries[0].reason, Reason::NotIndexed);
+    }
+}
@@ -0,0 +1,825 @@
+//! Headless background mode that keeps indexes warm
+//!
+//! `codesearch daemon` opens the repositories given on the command line, or else
+//! those listed in `~/.codesearch/daemon.json`, or else every repository tracked in
+//! `~/.codesearch/repos.json`. It brings each index up to date with an incremental
+//! refresh and then keeps a file watcher running until it is stopped. With a port
+//! (`--port` or `daemon.json`), it also serves the HTTP search API over all of them.
+//!
+//! ```json
+//! { "repositories": ["/src/api", "/src/web"], "port": 4444 }
+//! ```
+//!
+//! `codesearch daemon --install` registers the daemon to start automatically:
+//! - Linux: systemd user unit (`~/.config/systemd/user/codesearch-daemon.service`)
+//! - macOS: launchd agent (`~/Library/LaunchAgents/com.codesearch.daemon.plist`)
+//! - Windows: Windows service (`codesearch-daemon`), started at boot
+//!
+//! The Windows service runs as LocalSystem, which can't read the installing user's
+//! `~/.codesearch`: the repositories, port and host are resolved at install time and
+//! passed on its command line, and it logs to that user's `~/.codesearch/daemon.log`.
+
+use crate::cache::IndexMetadata;
+use crate::constants::{
+    CONFIG_DIR_NAME, DAEMON_CONFIG_FILE, DAEMON_LAUNCHD_LABEL, DAEMON_LOG_FILE,
+    DAEMON_SERVICE_NAME, SHUTDOWN_FLUSH_TIMEOUT_SECS,
+};
+use crate::db_discovery::{find_best_database, find_global_databases, DatabaseInfo};
+use crate::embed::ModelType;
+use crate::index::{IndexManager, SharedStores};
+use crate::out_print;
+use crate::server::ServedProject;
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+/// Daemon settings from `~/.codesearch/daemon.json` (or `CODESEARCH_DAEMON_CONFIG`)
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct DaemonConfig {
+    /// Project roots to keep indexed instead of every registered repository
+    repositories: Vec<PathBuf>,
+    /// Port of the HTTP search API over the repositories (none = no API)
+    port: Option<u16>,
+    /// Address the search API listens on
+    host: Option<String>,
+}
+
+impl DaemonConfig {
+    fn path() -> Option<PathBuf> {
+        match std::env::var("CODESEARCH_DAEMON_CONFIG") {
+            Ok(path) if !path.trim().is_empty() => Some(PathBuf::from(path.trim())),
+            _ => dirs::home_dir().map(|home| home.join(CONFIG_DIR_NAME).join(DAEMON_CONFIG_FILE)),
+        }
+    }
+
+    /// Settings from `path`, or the defaults when it doesn't exist
+    fn load(path: &Path) -> Result<Self> {
+        match std::fs::read_to_string(path) {
+            Ok(content) => serde_json::from_str(&content)
+                .with_context(|| format!("Invalid daemon config {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(anyhow!("Cannot read {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Settings of the current user
+    fn current() -> Result<Self> {
+        match Self::path() {
+            Some(path) => Self::load(&path),
+            None => Ok(Self::default()),
+        }
+    }
+}
+
+/// A repository the daemon keeps up to date
+struct WatchedRepository {
+    manager: Arc<IndexManager>,
+    served: ServedProject,
+}
+
+/// Run the daemon in the foreground until cancelled (Ctrl-C or SIGTERM)
+pub async fn run(
+    paths: Vec<PathBuf>,
+    port: Option<u16>,
+    host: Option<String>,
+    cancel_token: CancellationToken,
+) -> Result<()> {
+    // Same model cache as the MCP server, so models aren't downloaded per working dir
+    if let Ok(models_dir) = crate::constants::get_global_models_cache_dir() {
+        std::env::set_var("FASTEMBED_CACHE_DIR", &models_dir);
+    }
+
+    let config = DaemonConfig::current()?;
+    let paths = if paths.is_empty() {
+        config.repositories
+    } else {
+        paths
+    };
+    let port = port.or(config.port);
+    let host = host
+        .or(config.host)
+        .unwrap_or_else(|| "127.0.0.1".to_string());
+
+    // Bind before the refreshes start, so a busy port fails right away
+    let listener = match port {
+        Some(port) => Some(
+            tokio::net::TcpListener::bind(format!("{}:{}", host, port))
+                .await
+                .with_context(|| format!("Cannot listen on {}:{}", host, port))?,
+        ),
+        None => None,
+    };
+
+    let repositories = resolve_repositories(&paths)?;
+    if repositories.is_empty() {
+        return Err(anyhow!(
+            "No indexed repositories to watch. Run 'codesearch index' in a repository first, \
+             or pass repository paths to 'codesearch daemon'."
+        ));
+    }
+
+    let mut watched = Vec::new();
+    for info in &repositories {
+        match start_repository(info, cancel_token.clone()).await {
+            Ok(Some(repository)) => watched.push(repository),
+            Ok(None) => {}
+            Err(e) => tracing::error!(
+                "❌ Failed to start watching {}: {}",
+                info.project_path.display(),
+                e
+            ),
+        }
+    }
+    if watched.is_empty() {
+        return Err(anyhow!(
+            "None of the {} repositories could be opened for writing",
+            repositories.len()
+        ));
+    }
+    tracing::info!(
+        "✅ Daemon keeping {} of {} repositories up to date",
+        watched.len(),
+        repositories.len()
+    );
+
+    let (managers, projects): (Vec<_>, Vec<_>) = watched
+        .into_iter()
+        .map(|repository| (repository.manager, repository.served))
+        .unzip();
+    let api = listener.map(|listener| {
+        let cancel_token = cancel_token.clone();
+        tokio::spawn(async move {
+            if let Err(e) = crate::server::serve_projects(listener, projects, cancel_token).await {
+                tracing::error!("❌ Search API failed: {}", e);
+            }
+        })
+    });
+
+    crate::server::wait_for_shutdown(&cancel_token).await;
+    tracing::info!("🛑 Daemon shutting down...");
+    cancel_token.cancel();
+
+    // Searches in flight finish before the stores are released
+    if let Some(api) = api {
+        let _ = api.await;
+    }
+    let timeout = std::time::Duration::from_secs(SHUTDOWN_FLUSH_TIMEOUT_SECS);
+    for manager in managers {
+        manager.wait_for_idle(timeout).await;
+    }
+    Ok(())
+}
+
+/// Resolve the repositories to watch: explicit paths, or every registered repository
+fn resolve_repositories(paths: &[PathBuf]) -> Result<Vec<DatabaseInfo>> {
+    if paths.is_empty() {
+        return find_global_databases();
+    }
+
+    let mut repositories = Vec::new();
+    for path in paths {
+        match find_best_database(Some(path))? {
+            Some(info) => repositories.push(info),
+            None => tracing::warn!("⚠️  No index found for {}, skipping", path.display()),
+        }
+    }
+    Ok(repositories)
+}
+
+/// Open a repository's stores and start its refresh + file watcher in the background
+///
+/// Returns `None` when another instance already holds the writer lock: that
+/// instance keeps the index fresh, so the daemon leaves the repository alone.
+async fn start_repository(
+    info: &DatabaseInfo,
+    cancel_token: CancellationToken,
+) -> Result<Option<WatchedRepository>> {
+    let model = match IndexMetadata::load(&info.db_path)? {
+        Some(metadata) => metadata.model_type().0,
+        None => ModelType::default(),
+    };
+    let dimensions = read_dimensions(&info.db_path)?;
+    let (stores, is_readonly) = SharedStores::new_or_readonly(&info.db_path, dimensions)?;
+    if is_readonly {
+        tracing::warn!(
+            "🔒 {} is already being updated by another instance, skipping",
+            info.project_path.display()
+        );
+        return Ok(None);
+    }
+    let stores = Arc::new(stores);
+    let served = ServedProject {
+        root: info.project_path.clone(),
+        model,
+        stores: stores.clone(),
+    };
+
+    tracing::info!("📂 Watching {}", info.project_path.display());
+    let manager =
+        Arc::new(IndexManager::new_without_refresh(&info.project_path, stores.clone()).await?);
+
+    let background = manager.clone();
+    let project_path = info.project_path.clone();
+    let db_path = info.db_path.clone();
+    tokio::spawn(async move {
+        // Collect events during the refresh so nothing is missed before the watcher loop runs
+        if let Err(e) = background.start_watching().await {
+            tracing::warn!("⚠️ Could not pre-start file watcher: {}", e);
+        }
+        if let Err(e) =
+            IndexManager::perform_incremental_refresh_with_stores(&project_path, &db_path, &stores)
+                .await
+        {
+            tracing::error!(
+                "❌ Incremental refresh of {} failed: {}",
+                project_path.display(),
+                e
+            );
+        }
+        if cancel_token.is_cancelled() {
+            return;
+        }
+        if let Err(e) = background.start_file_watcher(cancel_token).await {
+            tracing::error!(
+                "❌ Failed to start file watcher for {}: {}",
+                project_path.display(),
+                e
+            );
+        }
+    });
+
+    Ok(Some(WatchedRepository { manager, served }))
+}
+
+/// Read the embedding dimensions from metadata.json (default model's when missing)
+fn read_dimensions(db_path: &Path) -> Result<usize> {
+    Ok(IndexMetadata::load(db_path)?.map_or(ModelType::default().dimensions(), |m| m.dimensions))
+}
+
+/// Arguments the installed service passes to the codesearch binary
+fn daemon_args(paths: &[PathBuf], port: Option<u16>, host: Option<&str>) -> Result<Vec<String>> {
+    let mut args = vec!["daemon".to_string()];
+    if let Some(port) = port {
+        args.extend(["--port".to_string(), port.to_string()]);
+    }
+    if let Some(host) = host {
+        args.extend(["--host".to_string(), host.to_string()]);
+    }
+    for path in paths {
+        let canonical = path
+            .canonicalize()
+            .map_err(|e| anyhow!("Cannot resolve {}: {}", path.display(), e))?;
+        args.push(canonical.to_string_lossy().to_string());
+    }
+    Ok(args)
+}
+
+/// Install the daemon as a login service for the current user (a Windows service on Windows)
+pub fn install(paths: &[PathBuf], port: Option<u16>, host: Option<&str>) -> Result<()> {
+    let exe = std::env::current_exe()?;
+    if cfg!(target_os = "windows") {
+        return install_windows_service(&exe, paths, port, host);
+    }
+    let args = daemon_args(paths, port, host)?;
+
+    if cfg!(target_os = "macos") {
+        let home = dirs::home_dir().ok_or_else(|| anyhow!("No home directory found"))?;
+        let log_path = home.join(CONFIG_DIR_NAME).join(DAEMON_LOG_FILE);
+        let plist_path = launchd_plist_path()?;
+        write_service_file(&plist_path, &render_launchd_plist(&exe, &args, &log_path))?;
+        run_service_command("launchctl", &["load", "-w", &plist_path.to_string_lossy()])?;
+        out_print!("✅ Installed launchd agent {}", plist_path.display());
+        out_print!("   Logs: {}", log_path.display());
+    } else {
+        let unit_path = systemd_unit_path()?;
+        write_service_file(&unit_path, &render_systemd_unit(&exe, &args))?;
+        run_service_command("systemctl", &["--user", "daemon-reload"])?;
+        let unit = format!("{}.service", DAEMON_SERVICE_NAME);
+        run_service_command("systemctl", &["--user", "enable", "--now", &unit])?;
+        out_print!("✅ Installed systemd user unit {}", unit_path.display());
+        out_print!("   Logs: journalctl --user -u {}", DAEMON_SERVICE_NAME);
+    }
+
+    if paths.is_empty() {
+        out_print!(
+            "   Watching the repositories in ~/.codesearch/{}, or else all registered in ~/.codesearch/repos.json",
+            DAEMON_CONFIG_FILE
+        );
+    }
+    Ok(())
+}
+
+/// Install the Windows service, with the current user's daemon settings on its command line
+fn install_windows_service(
+    exe: &Path,
+    paths: &[PathBuf],
+    port: Option<u16>,
+    host: Option<&str>,
+) -> Result<()> {
+    let config = DaemonConfig::current()?;
+    let paths = if paths.is_empty() {
+        config.repositories
+    } else {
+        paths.to_vec()
+    };
+    let roots: Vec<PathBuf> = resolve_repositories(&paths)?
+        .into_iter()
+        .map(|info| info.project_path)
+        .collect();
+    if roots.is_empty() {
+        return Err(anyhow!(
+            "No indexed repositories to watch. Run 'codesearch index' in a repository first, \
+             or pass repository paths to 'codesearch daemon --install'."
+        ));
+    }
+
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("No home directory found"))?;
+    let log_path = home.join(CONFIG_DIR_NAME).join(DAEMON_LOG_FILE);
+    let mut args = daemon_args(
+        &roots,
+        port.or(config.port),
+        host.or(config.host.as_deref()),
+    )?;
+    args.extend([
+        "--windows-service".to_string(),
+        log_path.to_string_lossy().to_string(),
+    ]);
+    windows::install_service(exe, &args)?;
+
+    out_print!("✅ Installed Windows service '{}'", DAEMON_SERVICE_NAME);
+    out_print!(
+        "   Starts at boot and restarts on failure; watching {} repositories",
+        roots.len()
+    );
+    out_print!("   Logs: {}", log_path.display());
+    out_print!("   Reinstall after changing the repositories in ~/.codesearch");
+    Ok(())
+}
+
+/// Run the daemon as the Windows service installed by [`install`], until the service stops
+pub fn run_service(
+    paths: Vec<PathBuf>,
+    port: Option<u16>,
+    host: Option<String>,
+    log_file: PathBuf,
+) -> Result<()> {
+    windows::run_service(paths, port, host, log_file)
+}
+
+/// Stop and remove the login service installed by [`install`]
+pub fn uninstall() -> Result<()> {
+    if cfg!(target_os = "windows") {
+        windows::uninstall_service()?;
+        out_print!("✅ Removed Windows service '{}'", DAEMON_SERVICE_NAME);
+    } else if cfg!(target_os = "macos") {
+        let plist_path = launchd_plist_path()?;
+        if plist_path.exists() {
+            run_service_command(
+                "launchctl",
+                &["unload", "-w", &plist_path.to_string_lossy()],
+            )?;
+            std::fs::remove_file(&plist_path)?;
+        }
+        out_print!("✅ Removed launchd agent {}", plist_path.display());
+    } else {
+        let unit_path = systemd_unit_path()?;
+        if unit_path.exists() {
+            let unit = format!("{}.service", DAEMON_SERVICE_NAME);
+            run_service_command("systemctl", &["--user", "disable", "--now", &unit])?;
+            std::fs::remove_file(&unit_path)?;
+            run_service_command("systemctl", &["--user", "daemon-reload"])?;
+        }
+        out_print!("✅ Removed systemd user unit {}", unit_path.display());
+    }
+    Ok(())
+}
+
+fn systemd_unit_path() -> Result<PathBuf> {
+    let config = dirs::config_dir().ok_or_else(|| anyhow!("No config directory found"))?;
+    Ok(config
+        .join("systemd")
+        .join("user")
+        .join(format!("{}.service", DAEMON_SERVICE_NAME)))
+}
+
+fn launchd_plist_path() -> Result<PathBuf> {
+    let home = dirs::home_dir().ok_or_else(|| anyhow!("No home directory found"))?;
+    Ok(home
+        .join("Library")
+        .join("LaunchAgents")
+        .join(format!("{}.plist", DAEMON_LAUNCHD_LABEL)))
+}
+
+fn write_service_file(path: &Path, contents: &str) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, contents)?;
+    Ok(())
+}
+
+/// Run a service manager command, failing with its output if it exits non-zero
+fn run_service_command(program: &str, args: &[&str]) -> Result<()> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| anyhow!("Failed to run {}: {}", program, e))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "'{} {}' failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(())
+}
+
+/// Render the systemd user unit running the daemon
+fn render_systemd_unit(exe: &Path, args: &[String]) -> String {
+    let exec_start = std::iter::once(exe.to_string_lossy().to_string())
+        .chain(args.iter().cloned())
+        .map(|arg| systemd_quote(&arg))
+        .collect::<Vec<_>>()
+        .join(" ");
+    format!(
+        "[Unit]\n\
+         Description=codesearch index daemon\n\
+         \n\
+         [Service]\n\
+         ExecStart={}\n\
+         Restart=on-failure\n\
+         RestartSec=10\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        exec_start
+    )
+}
+
+fn systemd_quote(arg: &str) -> String {
+    format!(
+        "\"{}\"",
+        arg.replace('\\', "\\\\")
+            .replace('"', "\\\"")
+            .replace('%', "%%")
+    )
+}
+
+/// Render the launchd agent plist running the daemon
+fn render_launchd_plist(exe: &Path, args: &[String], log_path: &Path) -> String {
+    let program_arguments: String = std::iter::once(exe.to_string_lossy().to_string())
+        .chain(args.iter().cloned())
+        .map(|arg| format!("        <string>{}</string>\n", xml_escape(&arg)))
+        .collect();
+    let log = xml_escape(&log_path.to_string_lossy());
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+    <key>Label</key>
+    <string>{label}</string>
+    <key>ProgramArguments</key>
+    <array>
+{program_arguments}    </array>
+    <key>RunAtLoad</key>
+    <true/>
+    <key>KeepAlive</key>
+    <dict>
+        <key>SuccessfulExit</key>
+        <false/>
+    </dict>
+    <key>StandardOutPath</key>
+    <string>{log}</string>
+    <key>StandardErrorPath</key>
+    <string>{log}</string>
+</dict>
+</plist>
+"#,
+        label = DAEMON_LAUNCHD_LABEL,
+    )
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// The daemon as a Windows service: installed through the service control manager,
+/// which starts it at boot, stops it at shutdown and restarts it when it fails
+#[cfg(windows)]
+mod windows {
+    use super::*;
+    use std::ffi::OsString;
+    use std::sync::atomic::Ordering;
+    use std::sync::OnceLock;
+    use std::time::Duration;
+    use windows_service::service::{
+        ServiceAccess, ServiceAction, ServiceActionType, ServiceControl, ServiceControlAccept,
+        ServiceErrorControl, ServiceExitCode, ServiceFailureActions, ServiceFailureResetPeriod,
+        ServiceInfo, ServiceStartType, ServiceState, ServiceStatus, ServiceType,
+    };
+    use windows_service::service_control_handler::{
+        self, ServiceControlHandlerResult, ServiceStatusHandle,
+    };
+    use windows_service::service_manager::{ServiceManager, ServiceManagerAccess};
+    use windows_service::{define_windows_service, service_dispatcher};
+
+    /// What `service_main` runs, set before the dispatcher starts it
+    static SERVICE_DAEMON: OnceLock<ServiceDaemon> = OnceLock::new();
+
+    struct ServiceDaemon {
+        paths: Vec<PathBuf>,
+        port: Option<u16>,
+        host: Option<String>,
+        log_file: PathBuf,
+    }
+
+    pub(super) fn install_service(exe: &Path, args: &[String]) -> Result<()> {
+        let manager = ServiceManager::local_computer(
+            None::<&str>,
+            ServiceManagerAccess::CONNECT | ServiceManagerAccess::CREATE_SERVICE,
+        )
+        .context("Cannot open the service manager; run --install from an elevated prompt")?;
+        if manager
+            .open_service(DAEMON_SERVICE_NAME, ServiceAccess::QUERY_STATUS)
+            .is_ok()
+        {
+            return Err(anyhow!(
+                "Windows service '{}' is already installed; run 'codesearch daemon --uninstall' first",
+                DAEMON_SERVICE_NAME
+            ));
+        }
+
+        let info = ServiceInfo {
+            name: DAEMON_SERVICE_NAME.into(),
+            display_name: "codesearch index daemon".into(),
+            service_type: ServiceType::OWN_PROCESS,
+            start_type: ServiceStartType::AutoStart,
+            error_control: ServiceErrorControl::Normal,
+            executable_path: exe.to_path_buf(),
+            launch_arguments: args.iter().map(OsString::from).collect(),
+            dependencies: vec![],
+            // LocalSystem
+            account_name: None,
+            account_password: None,
+        };
+        let service = manager
+            .create_service(&info, ServiceAccess::CHANGE_CONFIG | ServiceAccess::START)
+            .context("Cannot create the Windows service")?;
+        service.set_description("Keeps codesearch indexes up to date")?;
+        // Like the systemd unit's Restart=on-failure, RestartSec=10
+        let restart = ServiceAction {
+            action_type: ServiceActionType::Restart,
+            delay: Duration::from_secs(10),
+        };
+        service.update_failure_actions(ServiceFailureActions {
+            reset_period: ServiceFailureResetPeriod::After(Duration::from_secs(24 * 60 * 60)),
+            reboot_msg: None,
+            command: None,
+            actions: Some(vec![restart; 3]),
+        })?;
+        // Also restart after the daemon stops itself with an error, not only after a crash
+        service.set_failure_actions_on_non_crash_failures(true)?;
+        service
+            .start(&[] as &[&str])
+            .context("Installed the Windows service, but it did not start")?;
+        Ok(())
+    }
+
+    pub(super) fn uninstall_service() -> Result<()> {
+        let manager = ServiceManager::local_computer(None::<&str>, ServiceManagerAccess::CONNECT)
+            .context("Cannot open the service manager")?;
+        let service = manager
+            .open_service(
+                DAEMON_SERVICE_NAME,
+                ServiceAccess::QUERY_STATUS | ServiceAccess::STOP | ServiceAccess::DELETE,
+            )
+            .with_context(|| {
+                format!(
+                    "Cannot open Windows service '{}'; is it installed, and is this an elevated prompt?",
+                    DAEMON_SERVICE_NAME
+                )
+            })?;
+        if service.query_status()?.current_state != ServiceState::Stopped {
+            service.stop()?;
+        }
+        // Removed once the service has stopped
+        service.delete()?;
+        Ok(())
+    }
+
+    define_windows_service!(ffi_service_main, service_main);
+
+    pub(super) fn run_service(
+        paths: Vec<PathBuf>,
+        port: Option<u16>,
+        host: Option<String>,
+        log_file: PathBuf,
+    ) -> Result<()> {
+        let _ = SERVICE_DAEMON.set(ServiceDaemon {
+            paths,
+            port,
+            host,
+            log_file,
+        });
+        service_dispatcher::start(DAEMON_SERVICE_NAME, ffi_service_main)
+            .context("--windows-service only works when started by the service manager")?;
+        Ok(())
+    }
+
+    fn service_main(_arguments: Vec<OsString>) {
+        if let Err(e) = run_under_service_manager() {
+            tracing::error!("❌ Daemon service failed: {:#}", e);
+        }
+    }
+
+    fn run_under_service_manager() -> Result<()> {
+        let daemon = SERVICE_DAEMON
+            .get()
+            .ok_or_else(|| anyhow!("Daemon service started without its settings"))?;
+        init_service_log(&daemon.log_file)?;
+
+        let cancel_token = CancellationToken::new();
+        let stop = cancel_token.clone();
+        let status =
+            service_control_handler::register(DAEMON_SERVICE_NAME, move |control| match control {
+                ServiceControl::Stop | ServiceControl::Shutdown => {
+                    crate::constants::SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
+                    stop.cancel();
+                    ServiceControlHandlerResult::NoError
+                }
+                ServiceControl::Interrogate => ServiceControlHandlerResult::NoError,
+                _ => ServiceControlHandlerResult::NotImplemented,
+            })?;
+        set_status(status, ServiceState::Running, ServiceExitCode::Win32(0))?;
+
+        let result = tokio::runtime::Runtime::new()
+            .map_err(anyhow::Error::from)
+            .and_then(|runtime| {
+                runtime.block_on(async {
+                    // Tell the service manager the pending index writes may take a while
+                    let stopping = cancel_token.clone();
+                    tokio::spawn(async move {
+                        stopping.cancelled().await;
+                        let _ = set_status(
+                            status,
+                            ServiceState::StopPending,
+                            ServiceExitCode::Win32(0),
+                        );
+                    });
+                    super::run(
+                        daemon.paths.clone(),
+                        daemon.port,
+                        daemon.host.clone(),
+                        cancel_token,
+                    )
+                    .await
+                })
+            });
+        if let Err(e) = &result {
+            tracing::error!("❌ Daemon stopped: {:#}", e);
+        }
+        // A service-specific exit code makes the service manager apply the restart actions
+        let exit_code = match result {
+            Ok(()) => ServiceExitCode::Win32(0),
+            Err(_) => ServiceExitCode::ServiceSpecific(1),
+        };
+        set_status(status, ServiceState::Stopped, exit_code)?;
+        Ok(())
+    }
+
+    fn set_status(
+        status: ServiceStatusHandle,
+        state: ServiceState,
+        exit_code: ServiceExitCode,
+    ) -> Result<()> {
+        let (controls_accepted, wait_hint) = match state {
+            ServiceState::Running => (
+                ServiceControlAccept::STOP | ServiceControlAccept::SHUTDOWN,
+                Duration::default(),
+            ),
+            ServiceState::StopPending => (
+                ServiceControlAccept::empty(),
+                Duration::from_secs(SHUTDOWN_FLUSH_TIMEOUT_SECS + 5),
+            ),
+            _ => (ServiceControlAccept::empty(), Duration::default()),
+        };
+        status.set_service_status(ServiceStatus {
+            service_type: ServiceType::OWN_PROCESS,
+            current_state: state,
+            controls_accepted,
+            exit_code,
+            checkpoint: 0,
+            wait_hint,
+            process_id: None,
+        })?;
+        Ok(())
+    }
+
+    /// Log to the service's log file: a service has no console
+    fn init_service_log(log_file: &Path) -> Result<()> {
+        use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+
+        if let Some(parent) = log_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(log_file)
+            .with_context(|| format!("Cannot open {}", log_file.display()))?;
+        tracing_subscriber::registry()
+            .with(
+                tracing_subscriber::EnvFilter::try_from_default_env()
+                    .unwrap_or_else(|_| "codesearch=info".into()),
+            )
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .with_ansi(false)
+                    .with_writer(crate::logger::Plain(std::sync::Mutex::new(file))),
+            )
+            .try_init()?;
+        Ok(())
+    }
+}
+
+/// Stand-ins for the Windows service API on other platforms
+#[cfg(not(windows))]
+mod windows {
+    use super::*;
+
+    pub(super) fn install_service(_exe: &Path, _args: &[String]) -> Result<()> {
+        Err(anyhow!("Windows services are only available on Windows"))
+    }
+
+    pub(super) fn uninstall_service() -> Result<()> {
+        Err(anyhow!("Windows services are only available on Windows"))
+    }
+
+    pub(super) fn run_service(
+        _paths: Vec<PathBuf>,
+        _port: Option<u16>,
+        _host: Option<String>,
+        _log_file: PathBuf,
+    ) -> Result<()> {
+        Err(anyhow!("--windows-service is only available on Windows"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_daemon_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(DAEMON_CONFIG_FILE);
+        let config = DaemonConfig::load(&path).unwrap();
+        assert!(config.repositories.is_empty() && config.port.is_none());
+
+        std::fs::write(
+            &path,
+            r#"{"repositories": ["/src/api", "/src/web"], "port": 4444}"#,
+        )
+        .unwrap();
+        let config = DaemonConfig::load(&path).unwrap();
+        assert_eq!(
+            config.repositories,
+            vec![PathBuf::from("/src/api"), PathBuf::from("/src/web")]
+        );
+        assert_eq!((config.port, config.host), (Some(4444), None));
+
+        std::fs::write(&path, r#"{"repos": []}"#).unwrap();
+        assert!(DaemonConfig::load(&path).is_err());
+    }
+
+    #[test]
+    fn test_render_systemd_unit_quotes_arguments() {
+        let unit = render_systemd_unit(
+            Path::new("/opt/code search/codesearch"),
+            &["daemon".to_string(), "/home/me/100%".to_string()],
+        );
+        assert!(unit
+            .contains("ExecStart=\"/opt/code search/codesearch\" \"daemon\" \"/home/me/100%%\"\n"));
+        assert!(unit.contains("WantedBy=default.target"));
+    }
+
+    #[test]
+    fn test_render_launchd_plist_escapes_paths() {
+        let plist = render_launchd_plist(
+            Path::new("/usr/local/bin/codesearch"),
+            &["daemon".to_string(), "/Users/me/R&D".to_string()],
+            Path::new("/Users/me/.codesearch/daemon.log"),
+        );
+        assert!(plist.contains("<string>com.codesearch.daemon</string>"));
+        assert!(plist.contains("<string>/Users/me/R&amp;D</string>"));
+        assert!(plist.contains("<string>/Users/me/.codesearch/daemon.log</string>"));
+    }
+}
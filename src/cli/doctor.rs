@@ -1,12 +1,14 @@
 //! Doctor command - diagnose and repair index health
 
-use crate::cache::FileMetaStore;
+use crate::cache::{FileMetaStore, IndexMetadata};
 use crate::constants::{DB_DIR_NAME, FILE_META_DB_NAME};
 use crate::db_discovery::{find_best_database, is_valid_database};
-use crate::embed::PersistentEmbeddingCache;
+use crate::embed::{CacheUsageLog, ModelType, PersistentEmbeddingCache};
 use crate::fts::FtsStore;
-use crate::index::find_git_root;
+use crate::index::{find_git_root, find_orphans, is_database_locked, sweep_orphans};
+use crate::output::format_bytes;
 use crate::vectordb::VectorStore;
+use crate::{data_print, err_print, out_print};
 use anyhow::Result;
 use colored::Colorize;
 use serde::Serialize;
@@ -78,7 +80,7 @@ impl CheckResult {
 }
 
 /// Check 1: Find database
-fn check_find_database(project_path: &Path) -> CheckResult {
+pub(super) fn check_find_database(project_path: &Path) -> CheckResult {
     match find_best_database(Some(project_path)) {
         Ok(Some(db_info)) => CheckResult::pass(
             "Database found",
@@ -143,18 +145,13 @@ fn check_database_structure(db_path: &Path) -> CheckResult {
 
 /// Check 3: Model consistency between metadata.json and file_meta.json
 fn check_model_consistency(db_path: &Path) -> CheckResult {
-    let metadata_path = db_path.join("metadata.json");
     let file_meta_path = db_path.join(FILE_META_DB_NAME);
 
     // Read model from metadata.json
-    let metadata_model: Option<String> = fs::read_to_string(&metadata_path)
-        .ok()
-        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-        .and_then(|v| {
-            v.get("model_short_name")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-        });
+    let metadata_model = match IndexMetadata::load(db_path) {
+        Ok(metadata) => metadata.map(|m| m.model_short_name),
+        Err(e) => return invalid_metadata("Model consistency", &e),
+    };
 
     // Read model from file_meta.json
     let file_meta_model: Option<String> = fs::read_to_string(&file_meta_path)
@@ -249,7 +246,9 @@ fn check_file_integrity(db_path: &Path, project_path: &Path) -> CheckResult {
     let stale_count = stale_files.len();
 
     // Walk disk to find all indexable files (uses the real FileWalker)
-    let walker = crate::file::FileWalker::new(project_path.to_path_buf());
+    let walker = crate::file::FileWalker::new(project_path.to_path_buf())
+        .with_languages(crate::index::read_language_filter(db_path))
+        .with_excluded_dirs(crate::index::read_pruned_dirs(db_path));
     let files = match walker.walk() {
         Ok((files, _)) => files,
         Err(e) => {
@@ -321,33 +320,55 @@ fn check_file_integrity(db_path: &Path, project_path: &Path) -> CheckResult {
     }
 }
 
-/// Read model name and dimensions from file_meta.json
+/// Files that repeatedly fail to chunk/embed and are being backed off
+fn check_failing_files(db_path: &Path) -> CheckResult {
+    let failing = FileMetaStore::read_failing_files(db_path);
+    if failing.is_empty() {
+        return CheckResult::pass("Failing files", "No files failing to index");
+    }
+
+    let quarantined = failing.iter().filter(|(_, f)| f.is_quarantined()).count();
+    let details: Vec<String> = failing
+        .iter()
+        .take(10)
+        .map(|(path, f)| format!("{} ({} failures: {})", path, f.count, f.last_error))
+        .collect();
+
+    CheckResult::warn(
+        "Failing files",
+        format!(
+            "{} files failing to index ({} quarantined)",
+            failing.len(),
+            quarantined
+        ),
+    )
+    .with_details(details.join("; "))
+    .with_hint("Fix the files or add them to .codesearchignore; a successful index clears them")
+}
+
+/// Read model name and dimensions from file_meta.json (default model when absent)
 fn read_model_info(file_meta_path: &Path) -> (String, usize) {
-    fs::read_to_string(file_meta_path)
+    let json = fs::read_to_string(file_meta_path)
         .ok()
-        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-        .map(|json| {
-            let model = json
-                .get("model_name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("minilm-l6-q")
-                .to_string();
-            let dims = json
-                .get("dimensions")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(384) as usize;
-            (model, dims)
-        })
-        .unwrap_or_else(|| ("minilm-l6-q".to_string(), 384))
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok());
+    let model = json
+        .as_ref()
+        .and_then(|json| json.get("model_name").and_then(|v| v.as_str()))
+        .unwrap_or(ModelType::default().short_name())
+        .to_string();
+    let dims = json
+        .as_ref()
+        .and_then(|json| json.get("dimensions").and_then(|v| v.as_u64()))
+        .map(|d| d as usize)
+        .or_else(|| ModelType::dimensions_for(&model))
+        .unwrap_or(ModelType::default().dimensions());
+    (model, dims)
 }
 
-/// Read dimensions from metadata.json (fallback to 384)
-fn read_dimensions(db_path: &Path) -> usize {
-    fs::read_to_string(db_path.join("metadata.json"))
-        .ok()
-        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-        .and_then(|v| v.get("dimensions").and_then(|v| v.as_u64()))
-        .unwrap_or(384) as usize
+/// Failed check for a metadata.json that can't be parsed
+fn invalid_metadata(name: &str, err: &anyhow::Error) -> CheckResult {
+    CheckResult::fail(name, format!("metadata.json is invalid: {:#}", err))
+        .with_hint("Run 'codesearch index --force' to rebuild the index")
 }
 
 /// Check 6: Chunk integrity - vector store health
@@ -374,6 +395,63 @@ fn check_chunk_integrity(store: &VectorStore) -> CheckResult {
     }
 }
 
+/// Check 6b: Orphaned chunks - chunks of files that are untracked or gone
+fn check_orphaned_chunks(db_path: &Path, store: &VectorStore) -> CheckResult {
+    let Some(file_meta) = FileMetaStore::read(db_path) else {
+        return CheckResult::warn(
+            "Orphaned chunks",
+            "No file metadata to check chunks against",
+        );
+    };
+    match find_orphans(store, &file_meta) {
+        Ok(orphans) if orphans.is_empty() => {
+            CheckResult::pass("Orphaned chunks", "Every chunk belongs to an indexed file")
+        }
+        Ok(orphans) => {
+            let chunks: usize = orphans.iter().map(|(_, ids)| ids.len()).sum();
+            let examples: Vec<&str> = orphans.iter().take(3).map(|(p, _)| p.as_str()).collect();
+            CheckResult::warn(
+                "Orphaned chunks",
+                format!("{} orphaned chunks of {} files", chunks, orphans.len()),
+            )
+            .with_details(format!("e.g. {}", examples.join(", ")))
+            .with_hint("Run 'codesearch doctor --fix' to remove them")
+        }
+        Err(e) => CheckResult::warn("Orphaned chunks", format!("Could not scan chunks: {}", e)),
+    }
+}
+
+/// Remove orphaned chunks from both stores (doctor --fix)
+fn fix_orphaned_chunks(db_path: &Path) -> Result<()> {
+    if is_database_locked(db_path) {
+        out_print!(
+            "{}",
+            "⚠️  Skipping the orphan sweep: the index is in use by another process".yellow()
+        );
+        return Ok(());
+    }
+    let Some(file_meta) = FileMetaStore::read(db_path) else {
+        return Ok(());
+    };
+    let dims =
+        IndexMetadata::load(db_path)?.map_or(ModelType::default().dimensions(), |m| m.dimensions);
+    let mut store = VectorStore::new(db_path, dims)?;
+    if find_orphans(&store, &file_meta)?.is_empty() {
+        return Ok(());
+    }
+    let mut fts_store = FtsStore::new_with_writer(db_path)?;
+    let sweep = sweep_orphans(&mut store, &mut fts_store, &file_meta)?;
+    out_print!(
+        "{}",
+        format!(
+            "✅ Removed {} orphaned chunks of {} files",
+            sweep.chunks, sweep.files
+        )
+        .green()
+    );
+    Ok(())
+}
+
 /// Check 7: FTS health
 fn check_fts_health(db_path: &Path) -> CheckResult {
     match FtsStore::new(db_path) {
@@ -412,9 +490,9 @@ fn check_lmdb_bloat(_db_path: &Path, store: &VectorStore) -> CheckResult {
             format!(
                 "Bloat ratio: {:.2}x ({} used, {} file, {} free)",
                 bloat_ratio,
-                format_bytes(page_stats.used_bytes as usize),
-                format_bytes(page_stats.disk_size as usize),
-                format_bytes(free_bytes as usize),
+                format_bytes(page_stats.used_bytes),
+                format_bytes(page_stats.disk_size),
+                format_bytes(free_bytes),
             ),
         )
     } else if bloat_ratio < 3.0 {
@@ -423,9 +501,9 @@ fn check_lmdb_bloat(_db_path: &Path, store: &VectorStore) -> CheckResult {
             format!(
                 "Bloat ratio: {:.2}x ({} used, {} file, {} free pages)",
                 bloat_ratio,
-                format_bytes(page_stats.used_bytes as usize),
-                format_bytes(page_stats.disk_size as usize),
-                format_bytes(free_bytes as usize),
+                format_bytes(page_stats.used_bytes),
+                format_bytes(page_stats.disk_size),
+                format_bytes(free_bytes),
             ),
         )
         .with_hint("Consider re-indexing with `codesearch index -f` to reclaim free pages")
@@ -435,30 +513,29 @@ fn check_lmdb_bloat(_db_path: &Path, store: &VectorStore) -> CheckResult {
             format!(
                 "High bloat ratio: {:.2}x ({} used, {} file, {} free pages)",
                 bloat_ratio,
-                format_bytes(page_stats.used_bytes as usize),
-                format_bytes(page_stats.disk_size as usize),
-                format_bytes(free_bytes as usize),
+                format_bytes(page_stats.used_bytes),
+                format_bytes(page_stats.disk_size),
+                format_bytes(free_bytes),
             ),
         )
         .with_hint("Run 'codesearch index -f' to rebuild and compact the database")
     }
 }
 
-/// Format bytes in human-readable format
-fn format_bytes(bytes: usize) -> String {
-    if bytes < 1024 {
-        format!("{}B", bytes)
-    } else if bytes < 1024 * 1024 {
-        format!("{:.1}KB", bytes as f64 / 1024.0)
-    } else if bytes < 1024 * 1024 * 1024 {
-        format!("{:.1}MB", bytes as f64 / (1024.0 * 1024.0))
-    } else {
-        format!("{:.2}GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
-    }
-}
-
 /// Check 9: Embedding cache
-fn check_embedding_cache(_db_path: &Path, model_name: &str) -> CheckResult {
+fn check_embedding_cache(db_path: &Path, model_name: &str) -> CheckResult {
+    // Hits vs computations of recorded index runs: is the cache actually used?
+    let usage = CacheUsageLog::load(db_path)
+        .map(|log| {
+            format!(
+                "; last run: {}; all {} runs: {}",
+                log.last_run.summary(log.total.ms_per_chunk()),
+                log.runs,
+                log.total.summary(None)
+            )
+        })
+        .unwrap_or_default();
+
     // PersistentEmbeddingCache::open takes model_name as &str
     match PersistentEmbeddingCache::open(model_name) {
         Ok(cache) => match cache.stats() {
@@ -467,9 +544,10 @@ fn check_embedding_cache(_db_path: &Path, model_name: &str) -> CheckResult {
                     CheckResult::pass(
                         "Embedding cache",
                         format!(
-                            "{} entries ({})",
+                            "{} entries ({}){}",
                             stats.entries,
-                            format_bytes(stats.file_size_bytes as usize)
+                            format_bytes(stats.file_size_bytes),
+                            usage
                         ),
                     )
                 } else {
@@ -485,78 +563,151 @@ fn check_embedding_cache(_db_path: &Path, model_name: &str) -> CheckResult {
     }
 }
 
-/// Run all checks and return results
-pub async fn run(fix: bool, json: bool) -> Result<()> {
-    let project_path = Path::new(".");
+/// Check 10: Model dimensions - does the recorded model match the stored vectors?
+fn check_model_dimensions(db_path: &Path, store: Option<&VectorStore>) -> CheckResult {
+    let metadata = match IndexMetadata::load(db_path) {
+        Ok(Some(metadata)) => metadata,
+        Ok(None) => return CheckResult::warn("Model dimensions", "No metadata.json to validate"),
+        Err(e) => return invalid_metadata("Model dimensions", &e),
+    };
 
-    // Find database (single call)
-    let db_info = match find_best_database(Some(project_path))? {
-        Some(info) => info,
-        None => {
-            let results = vec![check_find_database(project_path)];
-            if json {
-                let output = serde_json::json!({
-                    "checks": results,
-                    "summary": { "warnings": 0, "errors": 1 }
-                });
-                println!("{}", serde_json::to_string_pretty(&output)?);
-            } else {
-                print_results(&results, false);
-            }
-            anyhow::bail!("No database found");
+    let model_name = metadata.model_short_name.as_str();
+    let recorded_dims = metadata.dimensions;
+    let stored_dims = store.and_then(|s| s.stored_dimensions().ok().flatten());
+
+    // The stored vectors are authoritative, then metadata.json
+    let index_dims = stored_dims.unwrap_or(recorded_dims);
+
+    if let Some(stored) = stored_dims {
+        if recorded_dims != stored {
+            return CheckResult::fail(
+                "Model dimensions",
+                format!(
+                    "metadata.json records {} dimensions but the vector index stores {}",
+                    recorded_dims, stored
+                ),
+            )
+            .with_hint("Run 'codesearch index --force' to rebuild the index");
         }
-    };
+    }
 
-    let db_path = db_info.db_path;
-    // Use absolute project_path from database info — ensures FileWalker paths
-    // match the normalized absolute paths stored in FileMetaStore by the indexer
-    let project_path = db_info.project_path;
+    match ModelType::dimensions_for(model_name) {
+        Some(model_dims) if model_dims == index_dims => CheckResult::pass(
+            "Model dimensions",
+            format!("{} produces {} dimensions", model_name, index_dims),
+        ),
+        Some(model_dims) => {
+            let (fallback, _) = ModelType::for_index(Some(model_name), Some(index_dims));
+            CheckResult::fail(
+                "Model dimensions",
+                format!(
+                    "Model '{}' produces {} dimensions but the index has {}",
+                    model_name, model_dims, index_dims
+                ),
+            )
+            .with_details(format!(
+                "Searches will fall back to '{}' ({} dims)",
+                fallback.short_name(),
+                fallback.dimensions()
+            ))
+            .with_hint("Run 'codesearch index --force' to rebuild with a consistent model")
+        }
+        None => {
+            let (fallback, _) = ModelType::for_index(Some(model_name), Some(index_dims));
+            CheckResult::warn(
+                "Model dimensions",
+                format!(
+                    "Unknown model '{}'; searches will use '{}' ({} dims)",
+                    model_name,
+                    fallback.short_name(),
+                    fallback.dimensions()
+                ),
+            )
+        }
+    }
+}
 
-    // Read model name for cache check
-    let model_name = fs::read_to_string(db_path.join("metadata.json"))
-        .ok()
-        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
-        .and_then(|v| {
-            v.get("model_short_name")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string())
-        })
+/// Run every check against a database
+pub(super) fn run_checks(db_path: &Path, project_path: &Path) -> Vec<CheckResult> {
+    // Read model name for cache check (an invalid metadata.json is reported by the checks)
+    let metadata = IndexMetadata::load(db_path).ok().flatten();
+    let model_name = metadata
+        .as_ref()
+        .map(|m| m.model_short_name.clone())
         .unwrap_or_else(|| "unknown".to_string());
 
     // Open VectorStore once for checks that need it
-    let dims = read_dimensions(&db_path);
-    let vector_store = VectorStore::new(&db_path, dims);
+    let dims = metadata.map_or(ModelType::default().dimensions(), |m| m.dimensions);
+    let vector_store = VectorStore::new(db_path, dims);
 
     // Run all checks in order
     let mut results = vec![
-        check_find_database(&project_path),
-        check_database_structure(&db_path),
-        check_model_consistency(&db_path),
-        check_git_root_placement(&db_path, &project_path),
-        check_file_integrity(&db_path, &project_path),
+        check_find_database(project_path),
+        check_database_structure(db_path),
+        check_model_consistency(db_path),
+        check_git_root_placement(db_path, project_path),
+        check_file_integrity(db_path, project_path),
+        check_failing_files(db_path),
     ];
 
     // Checks that need VectorStore
     match &vector_store {
         Ok(store) => {
             results.push(check_chunk_integrity(store));
-            results.push(check_fts_health(&db_path));
-            results.push(check_lmdb_bloat(&db_path, store));
+            results.push(check_orphaned_chunks(db_path, store));
+            results.push(check_fts_health(db_path));
+            results.push(check_lmdb_bloat(db_path, store));
+            results.push(check_model_dimensions(db_path, Some(store)));
         }
         Err(e) => {
             results.push(CheckResult::fail(
                 "Chunk integrity",
                 format!("Failed to open vector store: {}", e),
             ));
-            results.push(check_fts_health(&db_path));
+            results.push(CheckResult::fail(
+                "Orphaned chunks",
+                "Could not open vector store".to_string(),
+            ));
+            results.push(check_fts_health(db_path));
             results.push(CheckResult::fail(
                 "LMDB bloat",
                 "Could not open vector store".to_string(),
             ));
+            results.push(check_model_dimensions(db_path, None));
         }
     }
 
-    results.push(check_embedding_cache(&db_path, &model_name));
+    results.push(check_embedding_cache(db_path, &model_name));
+    results
+}
+
+/// Run all checks and return results
+pub async fn run(fix: bool, json: bool) -> Result<()> {
+    let project_path = Path::new(".");
+
+    // Find database (single call)
+    let db_info = match find_best_database(Some(project_path))? {
+        Some(info) => info,
+        None => {
+            let results = vec![check_find_database(project_path)];
+            if json {
+                let output = serde_json::json!({
+                    "checks": results,
+                    "summary": { "warnings": 0, "errors": 1 }
+                });
+                data_print!("{}", serde_json::to_string_pretty(&output)?);
+            } else {
+                print_results(&results, false);
+            }
+            anyhow::bail!("No database found");
+        }
+    };
+
+    let db_path = db_info.db_path;
+    // Use absolute project_path from database info — ensures FileWalker paths
+    // match the normalized absolute paths stored in FileMetaStore by the indexer
+    let project_path = db_info.project_path;
+    let results = run_checks(&db_path, &project_path);
 
     // Print results
     print_results(&results, json);
@@ -580,13 +731,13 @@ pub async fn run(fix: bool, json: bool) -> Result<()> {
                 "errors": errors,
             }
         });
-        println!("{}", serde_json::to_string_pretty(&output)?);
+        data_print!("{}", serde_json::to_string_pretty(&output)?);
     } else {
         // Normal mode: print summary
-        println!();
-        println!("{}", "Summary".bold());
-        println!("{}", "=".repeat(60));
-        println!("  {} warnings, {} errors", warnings, errors);
+        out_print!();
+        out_print!("{}", "Summary".bold());
+        out_print!("{}", "=".repeat(60));
+        out_print!("  {} warnings, {} errors", warnings, errors);
 
         // Add hints based on issues found
         if warnings > 0 || errors > 0 {
@@ -594,21 +745,25 @@ pub async fn run(fix: bool, json: bool) -> Result<()> {
                 .iter()
                 .any(|r| r.status == CheckStatus::Warn || r.status == CheckStatus::Fail)
             {
-                println!();
-                println!(
+                out_print!();
+                out_print!(
                     "{}",
                     "💡 Run 'codesearch index' to fix stale/missing files".bright_yellow()
                 );
             }
             if fix {
-                println!();
-                println!("Running incremental refresh...");
+                out_print!();
+                out_print!("Removing orphaned chunks...");
+                if let Err(e) = fix_orphaned_chunks(&db_path) {
+                    err_print!("{} Failed to remove orphaned chunks: {}", "❌".red(), e);
+                }
+                out_print!("Running incremental refresh...");
                 if let Err(e) =
                     crate::index::index_quiet(None, false, CancellationToken::new()).await
                 {
-                    eprintln!("{} Failed to run index: {}", "❌".red(), e);
+                    err_print!("{} Failed to run index: {}", "❌".red(), e);
                 } else {
-                    println!("{}", "✅ Index refresh completed".green());
+                    out_print!("{}", "✅ Index refresh completed".green());
                 }
             }
         }
@@ -627,24 +782,31 @@ fn print_results(results: &[CheckResult], json: bool) {
         return; // JSON output handled in run() as single root object
     }
 
-    println!("{}", "🔍 Codesearch Doctor".bold());
-    println!("{}", "=".repeat(60));
+    // -q: only the checks that need attention
+    let quiet = crate::output::is_quiet();
+    if !quiet {
+        out_print!("{}", "🔍 Codesearch Doctor".bold());
+        out_print!("{}", "=".repeat(60));
+    }
 
     for result in results {
+        if quiet && result.status == CheckStatus::Pass {
+            continue;
+        }
         let icon = match result.status {
             CheckStatus::Pass => "✅".green(),
             CheckStatus::Warn => "⚠️".yellow(),
             CheckStatus::Fail => "❌".red(),
         };
 
-        println!("  {} {}", icon, result.message);
+        out_print!("  {} {}", icon, result.message);
 
         if let Some(details) = &result.details {
-            println!("    {}", details.dimmed());
+            out_print!("    {}", details.dimmed());
         }
 
         if let Some(hint) = &result.hint {
-            println!("    {}", hint.bright_cyan());
+            out_print!("    {}", hint.bright_cyan());
         }
     }
 }
@@ -773,6 +935,44 @@ mod tests {
         assert!(result.message.contains("minilm-l6-q"));
     }
 
+    #[test]
+    fn test_doctor_model_dimensions_match() {
+        let temp_dir = tempdir().unwrap();
+        let db_dir = temp_dir.path().join(".codesearch.db");
+        fs::create_dir_all(&db_dir).unwrap();
+        create_metadata_json(&db_dir, "minilm-l6-q");
+
+        let result = check_model_dimensions(&db_dir, None);
+        assert_eq!(result.status, CheckStatus::Pass);
+        assert!(result.message.contains("384"));
+    }
+
+    #[test]
+    fn test_doctor_model_dimensions_mismatch() {
+        let temp_dir = tempdir().unwrap();
+        let db_dir = temp_dir.path().join(".codesearch.db");
+        fs::create_dir_all(&db_dir).unwrap();
+        // bge-base produces 768 dims, metadata says 384
+        create_metadata_json(&db_dir, "bge-base");
+
+        let result = check_model_dimensions(&db_dir, None);
+        assert_eq!(result.status, CheckStatus::Fail);
+        assert!(result.message.contains("768"));
+        assert!(result.hint.is_some());
+    }
+
+    #[test]
+    fn test_doctor_model_dimensions_unknown_model() {
+        let temp_dir = tempdir().unwrap();
+        let db_dir = temp_dir.path().join(".codesearch.db");
+        fs::create_dir_all(&db_dir).unwrap();
+        create_metadata_json(&db_dir, "mystery-model");
+
+        let result = check_model_dimensions(&db_dir, None);
+        assert_eq!(result.status, CheckStatus::Warn);
+        assert!(result.message.contains("mystery-model"));
+    }
+
     #[test]
     fn test_doctor_misplaced_index() {
         let temp_dir = tempdir().unwrap();
@@ -886,14 +1086,6 @@ mod tests {
         // If store fails to open, that's fine — check_chunk_integrity handles it in run()
     }
 
-    #[test]
-    fn test_format_bytes() {
-        assert_eq!(format_bytes(512), "512B");
-        assert_eq!(format_bytes(2048), "2.0KB");
-        assert_eq!(format_bytes(2_097_152), "2.0MB");
-        assert_eq!(format_bytes(2_147_483_648), "2.00GB");
-    }
-
     #[test]
     fn test_check_result_with_details_and_hint() {
         let result = CheckResult::pass("test", "message")
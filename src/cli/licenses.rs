@@ -0,0 +1,141 @@
+//! `codesearch licenses`: the licenses declared in the indexed files
+//!
+//! Lists the SPDX license identifiers recorded at index time with how many
+//! files declare each, and how much of the index is vendored third-party code.
+
+use crate::cache::FileMetaStore;
+use crate::db_discovery::find_best_database;
+use crate::file::is_vendored_path;
+use crate::{data_print, out_print};
+use anyhow::{anyhow, Result};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+/// Files declaring one license expression
+#[derive(Debug, PartialEq, Serialize)]
+struct LicenseFiles {
+    license: String,
+    files: usize,
+    /// Of `files`, those in vendored directories
+    vendored_files: usize,
+}
+
+/// Licenses found in an index
+#[derive(Debug, Default, PartialEq, Serialize)]
+struct LicenseReport {
+    /// Most files first
+    licenses: Vec<LicenseFiles>,
+    /// Files without an SPDX header
+    undeclared_files: usize,
+    /// Files in vendored directories, with or without a header
+    vendored_files: usize,
+}
+
+pub async fn run(path: Option<PathBuf>, json: bool) -> Result<()> {
+    let db_info = find_best_database(path.as_deref())?
+        .ok_or_else(|| anyhow!("No database found. Run 'codesearch index' first."))?;
+    let file_meta = FileMetaStore::read(&db_info.db_path)
+        .ok_or_else(|| anyhow!("No file metadata in {}", db_info.db_path.display()))?;
+    let report = build_report(
+        file_meta.file_licenses(),
+        &db_info.project_path.to_string_lossy(),
+    );
+
+    if json {
+        data_print!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    out_print!("📂 Project: {}", db_info.project_path.display());
+    if report.licenses.is_empty() {
+        out_print!("No SPDX license headers found");
+    }
+    for license in &report.licenses {
+        if license.vendored_files > 0 {
+            out_print!(
+                "  {:<30} {:>6} files ({} vendored)",
+                license.license,
+                license.files,
+                license.vendored_files
+            );
+        } else {
+            out_print!("  {:<30} {:>6} files", license.license, license.files);
+        }
+    }
+    out_print!(
+        "  {:<30} {:>6} files",
+        "(no header)",
+        report.undeclared_files
+    );
+    out_print!("📦 Vendored files: {}", report.vendored_files);
+    Ok(())
+}
+
+/// Count tracked files per license
+fn build_report<'a>(
+    files: impl IntoIterator<Item = (&'a String, Option<&'a str>)>,
+    project_root: &str,
+) -> LicenseReport {
+    let mut report = LicenseReport::default();
+    let mut licenses: BTreeMap<&str, LicenseFiles> = BTreeMap::new();
+    for (path, license) in files {
+        let vendored = is_vendored_path(path, project_root);
+        report.vendored_files += usize::from(vendored);
+        let Some(license) = license else {
+            report.undeclared_files += 1;
+            continue;
+        };
+        let entry = licenses.entry(license).or_insert_with(|| LicenseFiles {
+            license: license.to_string(),
+            files: 0,
+            vendored_files: 0,
+        });
+        entry.files += 1;
+        entry.vendored_files += usize::from(vendored);
+    }
+    report.licenses = licenses.into_values().collect();
+    report.licenses.sort_by(|a, b| {
+        b.files
+            .cmp(&a.files)
+            .then_with(|| a.license.cmp(&b.license))
+    });
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_license_report() {
+        let files = [
+            ("/repo/src/main.rs", Some("MIT")),
+            ("/repo/src/lib.rs", Some("MIT")),
+            ("/repo/src/util.rs", None),
+            ("/repo/third_party/zlib/inflate.c", Some("Zlib")),
+            ("/repo/third_party/zlib/deflate.c", None),
+        ]
+        .map(|(path, license)| (path.to_string(), license));
+        let report = build_report(files.iter().map(|(p, l)| (p, *l)), "/repo");
+        assert_eq!(
+            report,
+            LicenseReport {
+                licenses: vec![
+                    LicenseFiles {
+                        license: "MIT".to_string(),
+                        files: 2,
+                        vendored_files: 0,
+                    },
+                    LicenseFiles {
+                        license: "Zlib".to_string(),
+                        files: 1,
+                        vendored_files: 1,
+                    },
+                ],
+                undeclared_files: 2,
+                vendored_files: 2,
+            }
+        );
+    }
+}
@@ -1,5 +1,7 @@
+use crate::{err_print, out_print};
 use anyhow::Result;
 use clap::{Parser, Subcommand};
+use colored::Colorize;
 use std::path::PathBuf;
 use tokio_util::sync::CancellationToken;
 
@@ -58,14 +60,22 @@ pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
 
-    /// Set log level (error, warn, info, debug, trace)
-    #[arg(short = 'l', long, global = true, default_value = "info")]
-    pub loglevel: String,
+    /// Set log level (error, warn, info, debug, trace); overrides -v (default: info)
+    #[arg(short = 'l', long, global = true)]
+    pub loglevel: Option<String>,
 
     /// Suppress informational output (only show results/errors)
     #[arg(short, long, global = true)]
     pub quiet: bool,
 
+    /// More output: -v for debug logs and background work, -vv for trace logs
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    pub verbose: u8,
+
+    /// Replace emoji with plain ASCII in output and logs (also CODESEARCH_NO_EMOJI=1)
+    #[arg(long, global = true)]
+    pub no_emoji: bool,
+
     /// Override default store name
     #[arg(long, global = true)]
     pub store: Option<String>,
@@ -76,6 +86,14 @@ pub struct Cli {
     ///            jina-code, e5-multilingual, mxbai-large, modernbert-large
     #[arg(long, global = true)]
     pub model: Option<String>,
+
+    /// Parent directories searched for a database (default: CODESEARCH_DISCOVERY_DEPTH or 5)
+    #[arg(long, global = true, value_name = "LEVELS")]
+    pub discovery_depth: Option<usize>,
+
+    /// Don't fall back to globally registered repositories when no database is found nearby
+    #[arg(long, global = true)]
+    pub no_global_discovery: bool,
 }
 
 #[derive(Subcommand, Debug)]
@@ -83,7 +101,14 @@ pub enum Commands {
     /// Search the codebase using natural language
     Search {
         /// Search query (e.g., "where do we handle authentication?")
-        query: String,
+        #[arg(required_unless_present = "stdin")]
+        query: Option<String>,
+
+        /// Read queries from stdin, one per line (plain text, or JSON objects with
+        /// "query" and optional "id", "max_results" and "filter_path"), and print
+        /// one JSON line of results per query; the model is loaded once
+        #[arg(long, conflicts_with_all = ["query", "stream", "merge_children", "template"])]
+        stdin: bool,
 
         /// Maximum total results to return
         #[arg(short = 'm', long, default_value = "25")]
@@ -113,6 +138,12 @@ pub enum Commands {
         #[arg(long)]
         json: bool,
 
+        /// Print one line per result from a template, e.g. '{path}:{start_line} {signature}'.
+        /// Values stay on one line; `{path|shell}` quotes for the shell, `{content|json}`
+        /// prints a JSON string and `{content|raw}` the value as is
+        #[arg(long, conflicts_with_all = ["json", "compact"])]
+        template: Option<String>,
+
         /// Path to search in (defaults to current directory)
         #[arg(long)]
         path: Option<PathBuf>,
@@ -133,13 +164,47 @@ pub enum Commands {
         #[arg(long, default_value = "50")]
         rerank_top: usize,
 
-        /// Filter results to files under this path (e.g., "src/")
+        /// Filter results to files under this path (e.g., "src/"), or matching
+        /// comma-separated globs; `!` excludes (e.g., "src/**/*.rs,!**/tests/")
         #[arg(long)]
         filter_path: Option<String>,
 
+        /// Only search files with uncommitted changes (modified, staged or
+        /// untracked) in the git work tree
+        #[arg(long)]
+        changed: bool,
+
         /// Automatically create index if it doesn't exist (default: true)
         #[arg(long, default_value = "true")]
         create_index: bool,
+
+        /// Return every chunk in the index, ranked (overrides --max-results)
+        #[arg(long)]
+        all: bool,
+
+        /// Print results as JSON lines as they are read, without collecting them
+        /// first (vector-only ranking; for processing thousands of matches)
+        #[arg(long, conflicts_with = "rerank")]
+        stream: bool,
+
+        /// When the directory has no index but several subdirectories do
+        /// (e.g. frontend/ and backend/), search all of them and merge the results
+        #[arg(long, conflicts_with = "stream")]
+        merge_children: bool,
+
+        /// Drop results scoring below this (0-100, relative to the top result)
+        /// and results unrelated to the query; full-text matches are kept
+        #[arg(long)]
+        min_score: Option<f32>,
+
+        /// Search identifiers that match nothing in the index as the closest
+        /// indexed symbol (otherwise it is only suggested)
+        #[arg(long)]
+        auto_correct: bool,
+
+        /// Leave out files in vendored directories (third_party/, external/, ...)
+        #[arg(long)]
+        project_only: bool,
     },
 
     /// Index the repository or manage global index registry
@@ -170,26 +235,66 @@ pub enum Commands {
         /// Show index status (local or global)
         #[arg(long)]
         list: bool,
+
+        /// Also embed every chunk with a natural-language model (dual embedding).
+        /// Natural-language questions then search this prose space too.
+        #[arg(long, value_name = "MODEL")]
+        prose_model: Option<String>,
+
+        /// Only index these languages, comma-separated (e.g. rust,ts); "all" clears
+        /// the restriction. Remembered by the index for later refreshes.
+        #[arg(long, value_name = "LANGS", value_delimiter = ',')]
+        lang: Option<Vec<String>>,
+
+        /// When --model differs from the index's model, embed at most N chunks
+        /// with the new one in this run; later runs continue where it stopped
+        #[arg(long, value_name = "N")]
+        max_chunks: Option<usize>,
     },
 
     /// Run a background server with live file watching
     Serve {
         /// Port to listen on
-        #[arg(short, long, default_value = "4444")]
+        #[arg(
+            short,
+            long,
+            visible_alias = "http",
+            default_value = "4444",
+            env = "CODESEARCH_PORT"
+        )]
         port: u16,
 
+        /// Address to listen on (0.0.0.0 in a container)
+        #[arg(long, default_value = "127.0.0.1", env = "CODESEARCH_HOST")]
+        host: String,
+
         /// Path to serve (defaults to current directory)
+        #[arg(env = "CODESEARCH_PROJECT")]
         path: Option<PathBuf>,
 
         /// Automatically create index if it doesn't exist (default: true)
-        #[arg(short = 'c', long, default_value = "true")]
+        #[arg(
+            short = 'c',
+            long,
+            default_value = "true",
+            env = "CODESEARCH_CREATE_INDEX"
+        )]
         create_index: bool,
+
+        /// Number of embedder instances for concurrent queries
+        /// (default: CODESEARCH_EMBEDDER_POOL_SIZE or 1)
+        #[arg(long)]
+        embedders: Option<usize>,
     },
 
     /// Show statistics about the vector database
     Stats {
         /// Path to show stats for (defaults to current directory)
         path: Option<PathBuf>,
+
+        /// Show MCP tool call counts, latencies and the slowest queries instead
+        #[arg(long)]
+        tools: bool,
     },
 
     /// Clear the vector database
@@ -202,9 +307,64 @@ pub enum Commands {
         yes: bool,
     },
 
+    /// Pin the database to use from this directory, overriding discovery
+    Use {
+        /// Project (or .codesearch.db) whose database to use; omit to show the current pin
+        path: Option<PathBuf>,
+
+        /// Remove the pin of this directory
+        #[arg(long, conflicts_with = "path")]
+        clear: bool,
+    },
+
+    /// Snapshot the index (vector store, full-text index, metadata) to a file
+    Backup {
+        /// Path to back up (defaults to current directory)
+        path: Option<PathBuf>,
+
+        /// Backup file (defaults to codesearch-backup-<project>-<timestamp>.csbak)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Replace the index with a snapshot written by `codesearch backup`
+    Restore {
+        /// Backup file to restore
+        input: PathBuf,
+
+        /// Path to restore into (defaults to current directory)
+        path: Option<PathBuf>,
+
+        /// Skip confirmation prompt
+        #[arg(short = 'y', long)]
+        yes: bool,
+    },
+
+    /// Upload the index to shared storage for teammates to pull
+    ///
+    /// Only files whose content changed since the last publish are uploaded.
+    Publish {
+        /// Path to publish (defaults to current directory)
+        path: Option<PathBuf>,
+
+        /// Shared storage: s3://bucket/prefix or a directory
+        #[arg(long, env = "CODESEARCH_REMOTE")]
+        remote: String,
+    },
+
+    /// Index with the vectors published to shared storage, embedding only local changes
+    Pull {
+        /// Path to index (defaults to current directory)
+        path: Option<PathBuf>,
+
+        /// Shared storage: s3://bucket/prefix or a directory
+        #[arg(long, env = "CODESEARCH_REMOTE")]
+        remote: String,
+    },
+
     /// Check installation health
     Doctor {
-        /// Auto-repair stale/missing files by running incremental refresh
+        /// Auto-repair: remove orphaned chunks and run an incremental refresh
         #[arg(long)]
         fix: bool,
 
@@ -213,6 +373,53 @@ pub enum Commands {
         json: bool,
     },
 
+    /// Bundle version, OS, doctor results, recent logs and index stats into a
+    /// zip for bug reports (no source code)
+    Report {
+        /// Project path (defaults to current directory)
+        path: Option<PathBuf>,
+
+        /// Zip file (defaults to codesearch-report-<project>-<timestamp>.zip)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// List the SPDX licenses declared in the indexed files and how much of the
+    /// index is vendored third-party code
+    Licenses {
+        /// Project path (defaults to current directory)
+        path: Option<PathBuf>,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Show which files the index leaves out and why (binary, ignored, excluded
+    /// directory, unsupported language, no chunks, ...), or explain a single file
+    Coverage {
+        /// Project path, or a file to explain (defaults to current directory)
+        path: Option<PathBuf>,
+
+        /// Paths to list per reason (0 = all)
+        #[arg(long, default_value = "5")]
+        samples: usize,
+
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Check that metadata.json, file_meta.json and the stores of an index agree
+    Verify {
+        /// Project path (defaults to current directory)
+        path: Option<PathBuf>,
+
+        /// Also compare every file's chunks with the vector and full-text stores
+        #[arg(long)]
+        deep: bool,
+    },
+
     /// Download embedding models
     Setup {
         /// Model to download (defaults to mxbai-embed-xsmall-v1)
@@ -228,6 +435,66 @@ pub enum Commands {
         /// Automatically create index if it doesn't exist (default: true)
         #[arg(short = 'c', long, default_value = "true")]
         create_index: bool,
+
+        /// Serve MCP over streamable HTTP on this port instead of stdio, so
+        /// several clients share one server
+        #[arg(long, value_name = "PORT")]
+        http: Option<u16>,
+
+        /// Address to listen on with --http
+        #[arg(long, default_value = "127.0.0.1", requires = "http")]
+        host: String,
+
+        /// Also open every other valid database find_databases reports, and
+        /// offer the search_all tool to search them all with one query
+        #[arg(long)]
+        multi_repo: bool,
+    },
+
+    /// Show live index activity (files indexed/removed, batches, refreshes)
+    Activity {
+        /// Path to project (defaults to current directory)
+        path: Option<PathBuf>,
+
+        /// Number of recent activity lines to show first
+        #[arg(short = 'n', long, default_value = "20")]
+        lines: usize,
+
+        /// Show every log line, not just index activity
+        #[arg(long)]
+        all: bool,
+
+        /// Print the recent activity and exit instead of following the log
+        #[arg(long)]
+        no_follow: bool,
+    },
+
+    /// Keep indexes of registered repositories fresh in the background
+    Daemon {
+        /// Repositories to keep indexed (defaults to the repositories in ~/.codesearch/daemon.json,
+        /// or else all repositories in ~/.codesearch/repos.json)
+        paths: Vec<PathBuf>,
+
+        /// Register the daemon to start at login (systemd user unit or launchd agent),
+        /// or at boot as a Windows service
+        #[arg(long, conflicts_with = "uninstall")]
+        install: bool,
+
+        /// Remove the login service installed with --install
+        #[arg(long)]
+        uninstall: bool,
+
+        /// Serve the HTTP search API over all repositories on this port
+        #[arg(long)]
+        port: Option<u16>,
+
+        /// Address the search API listens on (default: 127.0.0.1)
+        #[arg(long, requires = "port")]
+        host: Option<String>,
+
+        /// Run as the Windows service installed with --install, logging to LOG_FILE
+        #[arg(long, hide = true, value_name = "LOG_FILE")]
+        windows_service: Option<PathBuf>,
     },
 
     /// Manage persistent embedding cache
@@ -242,29 +509,40 @@ pub async fn run(cancel_token: CancellationToken) -> Result<()> {
 
     // Parse model from CLI flag
     let model_type = cli.model.as_ref().and_then(|m| ModelType::parse(m));
+    #[allow(clippy::unnecessary_unwrap)]
     if cli.model.is_some() && model_type.is_none() {
-        eprintln!(
+        err_print!(
             "Unknown model: '{}'. Available models:",
             cli.model.as_ref().unwrap()
         );
-        eprintln!("  minilm-l6, minilm-l6-q, minilm-l12, minilm-l12-q, paraphrase-minilm");
-        eprintln!("  bge-small, bge-small-q, bge-base, nomic-v1, nomic-v1.5, nomic-v1.5-q");
-        eprintln!("  jina-code, e5-multilingual, mxbai-large, modernbert-large");
+        err_print!("  minilm-l6, minilm-l6-q, minilm-l12, minilm-l12-q, paraphrase-minilm");
+        err_print!("  bge-small, bge-small-q, bge-base, nomic-v1, nomic-v1.5, nomic-v1.5-q");
+        err_print!("  jina-code, e5-multilingual, mxbai-large, modernbert-large");
         std::process::exit(1);
     }
 
-    // Set quiet mode if requested
-    if cli.quiet {
-        crate::output::set_quiet(true);
+    // One verbosity for every subsystem
+    let verbosity = crate::output::Verbosity::from_flags(cli.quiet, cli.verbose);
+    crate::output::set_verbosity(verbosity);
+    if cli.no_emoji {
+        crate::output::set_plain(true);
+    }
+
+    // Database discovery scope
+    if let Some(depth) = cli.discovery_depth {
+        crate::db_discovery::set_discovery_depth(depth);
+    }
+    if cli.no_global_discovery {
+        crate::db_discovery::disable_global_discovery();
     }
 
     // Parse loglevel from CLI
-    let log_level =
-        crate::logger::LogLevel::parse(&cli.loglevel).unwrap_or(crate::logger::LogLevel::Info);
+    let log_level = crate::logger::LogLevel::resolve(cli.loglevel.as_deref(), verbosity);
 
     match cli.command {
         Commands::Search {
             query,
+            stdin,
             max_results,
             per_file,
             content,
@@ -272,16 +550,24 @@ pub async fn run(cancel_token: CancellationToken) -> Result<()> {
             compact,
             sync,
             json,
+            template,
             path,
             vector_only,
             rrf_k,
             rerank,
             rerank_top,
             filter_path,
+            changed,
             create_index,
+            all,
+            stream,
+            merge_children,
+            min_score,
+            auto_correct,
+            project_only,
         } => {
             // Auto-enable quiet mode for JSON output
-            if json {
+            if json || stream || stdin || template.is_some() {
                 crate::output::set_quiet(true);
             }
             let options = SearchOptions {
@@ -292,7 +578,13 @@ pub async fn run(cancel_token: CancellationToken) -> Result<()> {
                 compact,
                 sync,
                 json,
+                template: template
+                    .as_deref()
+                    .map(crate::search::OutputTemplate::parse)
+                    .transpose()?,
+                query_id: None,
                 filter_path,
+                changed_only: changed,
                 model_override: model_type.map(|mt| format!("{:?}", mt)),
                 vector_only,
                 rrf_k: if rrf_k == 60.0 {
@@ -307,9 +599,18 @@ pub async fn run(cancel_token: CancellationToken) -> Result<()> {
                     Some(rerank_top)
                 },
                 create_index,
+                all,
+                stream,
+                merge_children,
+                min_score,
+                auto_correct,
+                project_only,
             };
 
-            crate::search::search(&query, path, options).await
+            match query {
+                Some(query) if !stdin => crate::search::search(&query, path, options).await,
+                _ => crate::search::search_stdin(path, options).await,
+            }
         }
         Commands::Index {
             path,
@@ -319,6 +620,9 @@ pub async fn run(cancel_token: CancellationToken) -> Result<()> {
             global,
             remove,
             list,
+            prose_model,
+            lang,
+            max_chunks,
         } => {
             // Check if path is "list", "add", or "rm"/"remove" as special cases (backward compatibility)
             let path_str = path.as_ref().and_then(|p| p.to_str());
@@ -341,22 +645,42 @@ pub async fn run(cancel_token: CancellationToken) -> Result<()> {
             } else {
                 // For 'codesearch index .' or 'codesearch index <path>', just run indexing
                 // The index() function will handle checking for existing indexes
+                let prose_type = match prose_model.as_deref() {
+                    Some(name) => Some(ModelType::parse(name).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "Unknown prose model: '{}' (accepts the same names as --model)",
+                            name
+                        )
+                    })?),
+                    None => None,
+                };
                 crate::index::index(
                     path,
                     dry_run,
                     force,
                     false,
                     model_type,
+                    prose_type,
+                    lang,
+                    max_chunks,
                     cancel_token.clone(),
                 )
                 .await
             }
         }
-        Commands::Stats { path } => crate::index::stats(path).await,
+        Commands::Stats { path, tools } => {
+            if tools {
+                crate::index::tool_stats(path)
+            } else {
+                crate::index::stats(path).await
+            }
+        }
         Commands::Serve {
             port,
+            host,
             path,
             create_index,
+            embedders,
         } => {
             // Discover database path and initialize logger with file output
             // NOTE: For Serve, tracing is NOT initialized in main.rs — init_logger
@@ -370,22 +694,87 @@ pub async fn run(cancel_token: CancellationToken) -> Result<()> {
             {
                 match crate::logger::init_logger(&db_info.db_path, log_level, cli.quiet) {
                     Err(e) => {
-                        eprintln!("Warning: Failed to initialize file logger: {}", e);
+                        err_print!("Warning: Failed to initialize file logger: {}", e);
                     }
                     _ => {
                         // Logger initialized successfully (either FileLogging or ConsoleOnly)
                     }
                 }
             }
-            crate::server::serve(port, path, create_index, cancel_token.clone()).await
+            crate::server::serve(
+                host,
+                port,
+                path,
+                create_index,
+                embedders,
+                cancel_token.clone(),
+            )
+            .await
         }
         Commands::Clear { path, yes } => crate::index::clear(path, yes).await,
+        Commands::Use { path, clear } => run_use(path, clear),
+        Commands::Backup { path, output } => crate::index::backup(path, output).await,
+        Commands::Restore { input, path, yes } => crate::index::restore(input, path, yes).await,
+        Commands::Publish { path, remote } => crate::index::publish(path, &remote).await,
+        Commands::Pull { path, remote } => {
+            crate::index::pull(path, &remote, cancel_token.clone()).await
+        }
         Commands::Doctor { fix, json } => crate::cli::doctor::run(fix, json).await,
+        Commands::Report { path, output } => crate::cli::report::run(path, output).await,
+        Commands::Licenses { path, json } => crate::cli::licenses::run(path, json).await,
+        Commands::Coverage {
+            path,
+            samples,
+            json,
+        } => crate::cli::coverage::run(path, samples, json).await,
+        Commands::Verify { path, deep } => crate::cli::verify::run(path, deep).await,
         Commands::Setup { model } => crate::cli::setup::run(model).await,
-        Commands::Mcp { path, create_index } => {
+        Commands::Mcp {
+            path,
+            create_index,
+            http,
+            host,
+            multi_repo,
+        } => {
             // Logger is initialized inside run_mcp_server() once db_path is known.
             // This handles both the "DB already exists" and "auto-create DB" paths correctly.
-            crate::mcp::run_mcp_server(path, create_index, log_level, cli.quiet, cancel_token).await
+            crate::mcp::run_mcp_server(
+                path,
+                create_index,
+                http.map(|port| format!("{}:{}", host, port)),
+                multi_repo,
+                log_level,
+                cli.quiet,
+                cancel_token,
+            )
+            .await
+        }
+        Commands::Activity {
+            path,
+            lines,
+            all,
+            no_follow,
+        } => crate::cli::activity::run(path, lines, all, !no_follow, cancel_token).await,
+        Commands::Daemon {
+            paths,
+            install,
+            uninstall,
+            port,
+            host,
+            windows_service,
+        } => {
+            if install {
+                crate::cli::daemon::install(&paths, port, host.as_deref())
+            } else if uninstall {
+                crate::cli::daemon::uninstall()
+            } else if let Some(log_file) = windows_service {
+                // The service dispatcher blocks this thread until the service stops
+                tokio::task::block_in_place(|| {
+                    crate::cli::daemon::run_service(paths, port, host, log_file)
+                })
+            } else {
+                crate::cli::daemon::run(paths, port, host, cancel_token).await
+            }
         }
         Commands::Cache { command } => match command {
             CacheCommands::Stats { model } => run_cache_stats(model).await,
@@ -394,6 +783,56 @@ pub async fn run(cancel_token: CancellationToken) -> Result<()> {
     }
 }
 
+/// Pin, unpin or show the database used from the current directory
+fn run_use(path: Option<PathBuf>, clear: bool) -> Result<()> {
+    let current_dir = std::env::current_dir()?;
+
+    if clear {
+        match crate::db_discovery::unpin_database(&current_dir)? {
+            Some(project) => out_print!(
+                "{}",
+                format!("📌 Unpinned {} for this directory", project.display()).green()
+            ),
+            None => out_print!("No database is pinned for this directory"),
+        }
+        return Ok(());
+    }
+
+    if let Some(path) = path {
+        let info = crate::db_discovery::pin_database(&current_dir, &path)?;
+        out_print!(
+            "{}",
+            format!(
+                "📌 Using {} from {} and its subdirectories",
+                info.db_path.display(),
+                current_dir.display()
+            )
+            .green()
+        );
+        out_print!(
+            "{}",
+            "   Run 'codesearch use --clear' here to go back to automatic discovery.".dimmed()
+        );
+        return Ok(());
+    }
+
+    match crate::db_discovery::pinned_database(&current_dir) {
+        Some((pinned_dir, info)) => out_print!(
+            "📌 {} (pinned for {})",
+            info.db_path.display(),
+            pinned_dir.display()
+        ),
+        None => match crate::db_discovery::find_best_database(Some(&current_dir))? {
+            Some(info) => out_print!(
+                "No database pinned; discovery picks {}",
+                info.db_path.display()
+            ),
+            None => out_print!("No database pinned and none found by discovery"),
+        },
+    }
+    Ok(())
+}
+
 /// Show persistent cache statistics
 async fn run_cache_stats(model: Option<String>) -> Result<()> {
     // Parse model name
@@ -403,7 +842,7 @@ async fn run_cache_stats(model: Option<String>) -> Result<()> {
         .ok_or_else(|| anyhow::anyhow!("Failed to parse model name"))?;
 
     if model_name.is_none() {
-        eprintln!("Cache statistics for all models:");
+        err_print!("Cache statistics for all models:");
     }
 
     // Get cache directory
@@ -413,9 +852,9 @@ async fn run_cache_stats(model: Option<String>) -> Result<()> {
 
     if !cache_dir.exists() {
         if let Some(name) = model_name {
-            eprintln!("No cache found for model: {}", name);
+            err_print!("No cache found for model: {}", name);
         } else {
-            eprintln!("No cache directory found: {}", cache_dir.display());
+            err_print!("No cache directory found: {}", cache_dir.display());
         }
         return Ok(());
     }
@@ -424,18 +863,19 @@ async fn run_cache_stats(model: Option<String>) -> Result<()> {
     if let Some(name) = model_name {
         let model_cache_dir = cache_dir.join(name);
         if !model_cache_dir.exists() {
-            eprintln!("No cache found for model: {}", name);
+            err_print!("No cache found for model: {}", name);
             return Ok(());
         }
 
         let cache = crate::embed::PersistentEmbeddingCache::open(name)?;
         let stats = cache.stats()?;
 
-        println!("Persistent Cache Statistics ({})", name);
-        println!("  Cache Directory: {}", model_cache_dir.display());
-        println!("  Total Entries: {}", stats.entries);
-        println!("  Database Size: {} bytes", stats.file_size_bytes);
-        println!(
+        out_print!("Persistent Cache Statistics ({})", name);
+        out_print!("  Cache Directory: {}", model_cache_dir.display());
+        out_print!("  Total Entries: {}", stats.entries);
+        out_print!("  Query Entries: {}", stats.query_entries);
+        out_print!("  Database Size: {} bytes", stats.file_size_bytes);
+        out_print!(
             "    Last Access: {}",
             stats
                 .last_access
@@ -448,7 +888,7 @@ async fn run_cache_stats(model: Option<String>) -> Result<()> {
         let mut model_count = 0;
         let mut total_size = 0;
 
-        println!("Persistent Cache Statistics (All Models)");
+        out_print!("Persistent Cache Statistics (All Models)");
         for entry in dir_entries {
             let entry = entry?;
             if entry.path().is_dir() {
@@ -458,10 +898,11 @@ async fn run_cache_stats(model: Option<String>) -> Result<()> {
                 model_count += stats.entries;
                 total_size += stats.file_size_bytes;
 
-                println!("  {}:", model_name);
-                println!("    Entries: {}", stats.entries);
-                println!("    Size: {} bytes", stats.file_size_bytes);
-                println!(
+                out_print!("  {}:", model_name);
+                out_print!("    Entries: {}", stats.entries);
+                out_print!("    Query Entries: {}", stats.query_entries);
+                out_print!("    Size: {} bytes", stats.file_size_bytes);
+                out_print!(
                     "    Last Access: {}",
                     stats
                         .last_access
@@ -470,7 +911,7 @@ async fn run_cache_stats(model: Option<String>) -> Result<()> {
                 );
             }
         }
-        println!("Total: {} models, {} bytes", model_count, total_size);
+        out_print!("Total: {} models, {} bytes", model_count, total_size);
     }
 
     Ok(())
@@ -490,7 +931,7 @@ async fn run_cache_clear(model: Option<String>, yes: bool) -> Result<()> {
         .join("embedding_cache");
 
     if !cache_dir.exists() {
-        eprintln!("No cache directory found: {}", cache_dir.display());
+        err_print!("No cache directory found: {}", cache_dir.display());
         return Ok(());
     }
 
@@ -507,7 +948,7 @@ async fn run_cache_clear(model: Option<String>, yes: bool) -> Result<()> {
         let mut input = String::new();
         std::io::stdin().read_line(&mut input)?;
         if !input.trim().to_lowercase().starts_with('y') {
-            eprintln!("Aborted.");
+            err_print!("Aborted.");
             return Ok(());
         }
     }
@@ -516,16 +957,17 @@ async fn run_cache_clear(model: Option<String>, yes: bool) -> Result<()> {
     if let Some(name) = model_name {
         let model_cache_dir = cache_dir.join(name);
         if !model_cache_dir.exists() {
-            eprintln!("No cache found for model: {}", name);
+            err_print!("No cache found for model: {}", name);
             return Ok(());
         }
 
         let cache = crate::embed::PersistentEmbeddingCache::open(name)?;
         let stats_before = cache.stats()?;
         cache.clear()?;
-        eprintln!(
+        err_print!(
             "Cleared {} entries from cache for model '{}'",
-            stats_before.entries, name
+            stats_before.entries,
+            name
         );
     } else {
         // Clear all caches
@@ -540,17 +982,24 @@ async fn run_cache_clear(model: Option<String>, yes: bool) -> Result<()> {
                 let stats_before = cache.stats()?;
                 cache.clear()?;
                 total_cleared += stats_before.entries;
-                eprintln!(
+                err_print!(
                     "Cleared {} entries from cache for model '{}'",
-                    stats_before.entries, model_name
+                    stats_before.entries,
+                    model_name
                 );
             }
         }
-        eprintln!("Total: {} entries cleared", total_cleared);
+        err_print!("Total: {} entries cleared", total_cleared);
     }
 
     Ok(())
 }
 
+mod activity;
+mod coverage;
+mod daemon;
 mod doctor;
+mod licenses;
+mod report;
 mod setup;
+mod verify;
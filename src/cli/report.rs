@@ -0,0 +1,248 @@
+//! `codesearch report` - bundle diagnostics for bug reports
+//!
+//! Collects the codesearch version, OS, doctor results, recent logs,
+//! `metadata.json` and index statistics into a zip file that can be attached
+//! to a GitHub issue. Source code and chunk contents are never included; the
+//! bundle does contain file paths (failing files, log lines).
+
+use crate::out_print;
+use anyhow::{bail, Result};
+use colored::Colorize;
+use serde_json::json;
+use std::fs;
+use std::io::{Seek, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+use super::doctor::{check_find_database, run_checks, CheckResult, CheckStatus};
+use crate::cache::{FileMetaStore, IndexMetadata};
+use crate::constants::INDEX_METADATA_FILE;
+use crate::db_discovery::find_best_database;
+use crate::embed::ModelType;
+use crate::logger::get_log_dir;
+use crate::vectordb::VectorStore;
+
+/// Newest log files included in a report
+const REPORT_LOG_FILES: usize = 3;
+/// Only the tail of each log file is included
+const REPORT_LOG_MAX_BYTES: usize = 1024 * 1024;
+
+/// Write a diagnostics bundle for the database of `path` to `output`
+pub async fn run(path: Option<PathBuf>, output: Option<PathBuf>) -> Result<()> {
+    let target = path.unwrap_or_else(|| PathBuf::from("."));
+    let db_info = find_best_database(Some(&target))?;
+
+    let output = output.unwrap_or_else(|| {
+        let project = db_info
+            .as_ref()
+            .and_then(|info| info.project_path.file_name())
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "codesearch".to_string());
+        PathBuf::from(format!(
+            "codesearch-report-{}-{}.zip",
+            project,
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ))
+    });
+    if output.exists() {
+        bail!("{} already exists", output.display());
+    }
+
+    out_print!("{}", "🩺 Codesearch Report".bright_cyan().bold());
+    out_print!("{}", "=".repeat(60));
+
+    let checks = match &db_info {
+        Some(info) => {
+            out_print!("💾 Database: {}", info.db_path.display());
+            run_checks(&info.db_path, &info.project_path)
+        }
+        None => {
+            out_print!(
+                "{}",
+                "⚠️  No database found; reporting the environment only".yellow()
+            );
+            vec![check_find_database(&target)]
+        }
+    };
+    let count = |status: CheckStatus| checks.iter().filter(|c| c.status == status).count();
+    let mut stats = json!({
+        "codesearch_version": env!("CARGO_PKG_VERSION"),
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "cpus": num_cpus::get(),
+        "created_at": chrono::Utc::now().to_rfc3339(),
+        "doctor": { "warnings": count(CheckStatus::Warn), "errors": count(CheckStatus::Fail) },
+    });
+
+    let mut entries: Vec<(String, Vec<u8>)> =
+        vec![("doctor.txt".to_string(), doctor_text(&checks))];
+    if let Some(info) = &db_info {
+        stats["database"] = database_stats(&info.db_path, &info.project_path);
+
+        // Copied as is: an unparsable metadata.json is often the bug
+        if let Ok(metadata) = fs::read(info.db_path.join(INDEX_METADATA_FILE)) {
+            entries.push((INDEX_METADATA_FILE.to_string(), metadata));
+        }
+        for (name, tail) in recent_logs(&get_log_dir(&info.db_path)) {
+            entries.push((format!("logs/{}", name), tail));
+        }
+    }
+    entries.insert(
+        0,
+        ("stats.json".to_string(), serde_json::to_vec_pretty(&stats)?),
+    );
+
+    let file = fs::File::create(&output)?;
+    let written = write_zip(std::io::BufWriter::new(file), &entries);
+    if written.is_err() {
+        let _ = fs::remove_file(&output);
+    }
+    written?;
+
+    out_print!(
+        "{}",
+        format!(
+            "✅ Report written to {} ({} files)",
+            output.display(),
+            entries.len()
+        )
+        .green()
+    );
+    out_print!(
+        "{}",
+        "   It contains paths and log lines but no source code. Review it before attaching it to an issue."
+            .dimmed()
+    );
+    Ok(())
+}
+
+/// Doctor results as printed by `codesearch doctor`, without colors
+fn doctor_text(checks: &[CheckResult]) -> Vec<u8> {
+    let mut text = String::new();
+    for check in checks {
+        let status = match check.status {
+            CheckStatus::Pass => "PASS",
+            CheckStatus::Warn => "WARN",
+            CheckStatus::Fail => "FAIL",
+        };
+        text.push_str(&format!("[{}] {}: {}\n", status, check.name, check.message));
+        for extra in [&check.details, &check.hint].into_iter().flatten() {
+            text.push_str(&format!("    {}\n", extra));
+        }
+    }
+    text.into_bytes()
+}
+
+/// Index statistics without any chunk content
+fn database_stats(db_path: &Path, project_path: &Path) -> serde_json::Value {
+    let metadata = IndexMetadata::load(db_path).ok().flatten();
+    let dims = metadata
+        .as_ref()
+        .map_or(ModelType::default().dimensions(), |m| m.dimensions);
+    let mut stats = json!({
+        "database_path": db_path.display().to_string(),
+        "project_path": project_path.display().to_string(),
+        "size_bytes": walkdir::WalkDir::new(db_path)
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.metadata().ok())
+            .filter(|m| m.is_file())
+            .map(|m| m.len())
+            .sum::<u64>(),
+    });
+    match VectorStore::new(db_path, dims).and_then(|store| store.stats()) {
+        Ok(store) => {
+            stats["total_chunks"] = store.total_chunks.into();
+            stats["total_files"] = store.total_files.into();
+            stats["indexed"] = store.indexed.into();
+            stats["dimensions"] = store.dimensions.into();
+            stats["max_chunk_id"] = store.max_chunk_id.into();
+        }
+        Err(e) => stats["vector_store_error"] = format!("{:#}", e).into(),
+    }
+    if let Some(file_meta) = FileMetaStore::read(db_path) {
+        stats["tracked_files"] = file_meta.tracked_files().count().into();
+        stats["stale_files"] = file_meta.count_stale_files().into();
+        stats["failing_files"] = file_meta
+            .failing_files()
+            .into_iter()
+            .map(|(path, failure)| json!({ "path": path, "failure": failure }))
+            .collect();
+    }
+    stats
+}
+
+/// Tails of the newest log files as `(file name, content)`
+fn recent_logs(log_dir: &Path) -> Vec<(String, Vec<u8>)> {
+    let Ok(entries) = fs::read_dir(log_dir) else {
+        return Vec::new();
+    };
+    let mut logs: Vec<_> = entries
+        .flatten()
+        .filter_map(|e| {
+            let meta = e.metadata().ok().filter(|m| m.is_file())?;
+            Some((meta.modified().ok()?, e.path()))
+        })
+        .collect();
+    logs.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+    logs.into_iter()
+        .take(REPORT_LOG_FILES)
+        .filter_map(|(_, path)| {
+            let content = fs::read(&path).ok()?;
+            let start = content.len().saturating_sub(REPORT_LOG_MAX_BYTES);
+            let name = path.file_name()?.to_string_lossy().into_owned();
+            Some((name, content[start..].to_vec()))
+        })
+        .collect()
+}
+
+/// Write `entries` as a deflate-compressed zip archive
+fn write_zip(out: impl Write + Seek, entries: &[(String, Vec<u8>)]) -> Result<()> {
+    use chrono::{Datelike, Timelike};
+    let now = chrono::Local::now().naive_local();
+    let mut options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    if let Ok(modified) = zip::DateTime::from_date_and_time(
+        now.year() as u16,
+        now.month() as u8,
+        now.day() as u8,
+        now.hour() as u8,
+        now.minute() as u8,
+        now.second() as u8,
+    ) {
+        options = options.last_modified_time(modified);
+    }
+
+    let mut zip = ZipWriter::new(out);
+    for (name, data) in entries {
+        zip.start_file(name.as_str(), options)?;
+        zip.write_all(data)?;
+    }
+    zip.finish()?.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_zip_roundtrip() {
+        let entries = vec![
+            ("stats.json".to_string(), b"{}".to_vec()),
+            ("logs/codesearch.log".to_string(), b"line\n".to_vec()),
+        ];
+        let mut bundle = std::io::Cursor::new(Vec::new());
+        write_zip(&mut bundle, &entries).unwrap();
+
+        let mut archive = zip::ZipArchive::new(bundle).unwrap();
+        assert_eq!(archive.len(), 2);
+        for (name, data) in &entries {
+            let mut file = archive.by_name(name).unwrap();
+            assert_eq!(file.compression(), CompressionMethod::Deflated);
+            let mut content = Vec::new();
+            std::io::Read::read_to_end(&mut file, &mut content).unwrap();
+            assert_eq!(&content, data);
+        }
+    }
+}
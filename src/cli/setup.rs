@@ -1,12 +1,13 @@
+use crate::out_print;
 use anyhow::Result;
 
 pub async fn run(model: Option<String>) -> Result<()> {
     let model_name = model.unwrap_or_else(|| "mxbai-embed-xsmall-v1".to_string());
 
-    println!("📦 Downloading embedding model: {}", model_name);
+    out_print!("📦 Downloading embedding model: {}", model_name);
 
     // TODO: Download model from HuggingFace Hub
 
-    println!("✅ Setup complete!");
+    out_print!("✅ Setup complete!");
     Ok(())
 }
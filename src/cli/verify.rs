@@ -0,0 +1,180 @@
+//! `codesearch verify` - check that the persistence layers of an index agree
+//!
+//! An index is spread over `metadata.json`, `file_meta.json`, the vector store
+//! and the full-text index. Saving `file_meta.json` records a checksum of its
+//! `(path, content hash)` pairs in `metadata.json`; `verify` recomputes it to
+//! catch a `file_meta.json` that was lost, restored or rewritten behind the
+//! index's back. `--deep` also compares the chunk IDs of every file with the
+//! chunks in the vector store, and the vector store with the full-text index.
+
+use crate::out_print;
+use anyhow::{anyhow, bail, Result};
+use colored::Colorize;
+use std::collections::{BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use crate::cache::{rolling_checksum, FileMetaStore, IndexMetadata};
+use crate::constants::FILE_META_DB_NAME;
+use crate::db_discovery::find_best_database;
+use crate::fts::FtsStore;
+use crate::index::is_database_locked;
+use crate::vectordb::VectorStore;
+
+/// Differences listed before the rest are only counted
+const MAX_LISTED_PROBLEMS: usize = 20;
+
+/// Verify the index of `path`; fails when the layers diverge
+pub async fn run(path: Option<PathBuf>, deep: bool) -> Result<()> {
+    let target = path.unwrap_or_else(|| PathBuf::from("."));
+    let Some(db_info) = find_best_database(Some(&target))? else {
+        bail!("No database found");
+    };
+    let db_path = db_info.db_path;
+
+    out_print!("{}", "🔐 Codesearch Verify".bright_cyan().bold());
+    out_print!("{}", "=".repeat(60));
+    out_print!("💾 Database: {}", db_path.display());
+
+    let metadata = IndexMetadata::require(&db_path)?;
+    let file_meta = FileMetaStore::read(&db_path)
+        .ok_or_else(|| anyhow!("No readable {} in {}", FILE_META_DB_NAME, db_path.display()))?;
+    let mut problems = Vec::new();
+
+    let checksum = file_meta.content_checksum();
+    match &metadata.content_checksum {
+        None => out_print!(
+            "{}",
+            "⚠️  No content checksum recorded yet; the next index update records one".yellow()
+        ),
+        Some(recorded) if *recorded == checksum => {
+            out_print!("✅ {} matches the recorded checksum", FILE_META_DB_NAME)
+        }
+        Some(recorded) => problems.push(format!(
+            "{} checksum {} differs from the recorded {}",
+            FILE_META_DB_NAME, checksum, recorded
+        )),
+    }
+
+    if deep {
+        let store = if is_database_locked(&db_path) {
+            VectorStore::open_readonly(&db_path, metadata.dimensions)?
+        } else {
+            VectorStore::new(&db_path, metadata.dimensions)?
+        };
+        let tracked = file_meta.chunk_ids_by_file();
+        let stored = store.get_chunks_by_file()?;
+        let differences = chunk_differences(&tracked, &stored);
+        if differences.is_empty() {
+            out_print!("✅ Chunks of {} files match the vector store", stored.len());
+        }
+        problems.extend(differences);
+
+        let chunks = store.stats()?.total_chunks;
+        match FtsStore::new(&db_path).and_then(|fts| fts.stats()) {
+            Ok(fts) if fts.num_documents == chunks => {
+                out_print!("✅ Full-text index holds all {} chunks", chunks)
+            }
+            Ok(fts) => problems.push(format!(
+                "Full-text index holds {} documents, the vector store {} chunks",
+                fts.num_documents, chunks
+            )),
+            Err(e) => problems.push(format!("Full-text index unreadable: {:#}", e)),
+        }
+    }
+
+    if problems.is_empty() {
+        out_print!("{}", "✅ Index is consistent".green());
+        return Ok(());
+    }
+    for problem in problems.iter().take(MAX_LISTED_PROBLEMS) {
+        out_print!("  {} {}", "❌".red(), problem);
+    }
+    if problems.len() > MAX_LISTED_PROBLEMS {
+        out_print!("  ... and {} more", problems.len() - MAX_LISTED_PROBLEMS);
+    }
+    out_print!(
+        "{}",
+        "💡 Run 'codesearch doctor --fix', or 'codesearch index --force' to rebuild"
+            .bright_yellow()
+    );
+    bail!("Index verification found {} problem(s)", problems.len())
+}
+
+/// Files whose chunk IDs in file_meta.json and the vector store disagree
+fn chunk_differences(
+    tracked: &HashMap<String, Vec<u32>>,
+    stored: &HashMap<String, Vec<u32>>,
+) -> Vec<String> {
+    // Equal checksums are the common case and skip the per-file comparison
+    let checksum = |files: &HashMap<String, Vec<u32>>| {
+        rolling_checksum(
+            files
+                .iter()
+                .filter(|(_, ids)| !ids.is_empty())
+                .map(|(path, ids)| (path.as_str(), id_list(ids))),
+        )
+    };
+    if checksum(tracked) == checksum(stored) {
+        return Vec::new();
+    }
+
+    let paths: BTreeSet<&String> = tracked.keys().chain(stored.keys()).collect();
+    let mut differences = Vec::new();
+    for path in paths {
+        let ids = |files: &HashMap<String, Vec<u32>>| -> BTreeSet<u32> {
+            files.get(path).into_iter().flatten().copied().collect()
+        };
+        let (tracked_ids, stored_ids) = (ids(tracked), ids(stored));
+        let missing = tracked_ids.difference(&stored_ids).count();
+        let untracked = stored_ids.difference(&tracked_ids).count();
+        if missing > 0 {
+            differences.push(format!(
+                "{}: {} chunks in {} are missing from the vector store",
+                path, missing, FILE_META_DB_NAME
+            ));
+        }
+        if untracked > 0 {
+            differences.push(format!(
+                "{}: {} chunks in the vector store are not in {}",
+                path, untracked, FILE_META_DB_NAME
+            ));
+        }
+    }
+    differences
+}
+
+/// Sorted, comma-separated chunk IDs
+fn id_list(ids: &[u32]) -> String {
+    let mut ids = ids.to_vec();
+    ids.sort_unstable();
+    ids.iter().map(u32::to_string).collect::<Vec<_>>().join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_differences() {
+        let files = |entries: &[(&str, &[u32])]| -> HashMap<String, Vec<u32>> {
+            entries
+                .iter()
+                .map(|(path, ids)| (path.to_string(), ids.to_vec()))
+                .collect()
+        };
+        let tracked = files(&[("a.rs", &[1, 2]), ("b.rs", &[3]), ("empty.rs", &[])]);
+
+        // Order of IDs and files without chunks don't matter
+        let same = files(&[("b.rs", &[3]), ("a.rs", &[2, 1])]);
+        assert!(chunk_differences(&tracked, &same).is_empty());
+
+        let diverged = files(&[("a.rs", &[1]), ("b.rs", &[3]), ("ghost.rs", &[9])]);
+        assert_eq!(
+            chunk_differences(&tracked, &diverged),
+            [
+                "a.rs: 1 chunks in file_meta.json are missing from the vector store",
+                "ghost.rs: 1 chunks in the vector store are not in file_meta.json",
+            ]
+        );
+    }
+}
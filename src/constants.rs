@@ -41,33 +41,84 @@ pub const CONFIG_DIR_NAME: &str = ".codesearch";
 /// Name of the file metadata database
 pub const FILE_META_DB_NAME: &str = "file_meta.json";
 
+/// Index metadata (embedding model, language scope) in the database dir
+pub const INDEX_METADATA_FILE: &str = "metadata.json";
+
+/// CODEOWNERS rules of the project as of the last index run, in the database dir
+pub const CODEOWNERS_FILE: &str = "codeowners.json";
+
+/// Embedding cache hits vs computations of past index runs, in the database dir
+pub const CACHE_USAGE_FILE: &str = "embedding_cache_usage.json";
+
+/// LMDB and full-text index sizes over time, in the database dir
+pub const SIZE_HISTORY_FILE: &str = "size_history.json";
+
+/// MCP tool call counts, latencies and slowest queries, in the database dir
+pub const TOOL_USAGE_FILE: &str = "tool_usage.json";
+
+/// Version of the chunk layout, embedded text format and full-text analysis.
+///
+/// Bump whenever chunk boundaries, the text passed to the embedder or the
+/// keywords stripped from the full-text index change; files indexed under an
+/// older version are re-chunked and re-indexed.
+pub const CHUNKER_SCHEMA_VERSION: u32 = 8;
+
+/// Number of trailing path components included in the embedded text
+pub const EMBED_PATH_COMPONENTS: usize = 4;
+
 /// Subdirectory name for embedding models within the global config dir
 const MODELS_SUBDIR: &str = "models";
 
+/// Lock file held while a model is downloaded into the models cache
+pub const MODEL_DOWNLOAD_LOCK_FILE: &str = ".download.lock";
+
+/// How long to wait for another process's model download before giving up
+pub const MODEL_DOWNLOAD_LOCK_TIMEOUT_SECS: u64 = 600;
+
 /// Log directory name within .codesearch.db
 pub const LOG_DIR_NAME: &str = "logs";
 
 /// Default log file name
 pub const LOG_FILE_NAME: &str = "codesearch.log";
 
+/// Phase and file of the indexing run in progress, in the log directory
+pub const INDEXING_CONTEXT_FILE: &str = "indexing.json";
+
+/// Files indexed between saves of the indexing context
+pub const INDEXING_CONTEXT_SAVE_FILES: usize = 100;
+
+/// Indexing context of the last run that panicked or died, in the log directory
+pub const LAST_CRASH_FILE: &str = "last_crash.json";
+
 /// Default number of log files to retain
 pub const DEFAULT_LOG_MAX_FILES: usize = 5;
 
 /// Default log retention period in days
 pub const DEFAULT_LOG_RETENTION_DAYS: u64 = 5;
 
+/// Default size in MB from which a log file is rotated
+pub const DEFAULT_LOG_MAX_FILE_MB: u64 = 50;
+
+/// Default total size in MB of the retained log files
+pub const DEFAULT_LOG_MAX_TOTAL_MB: u64 = 500;
+
 /// Get the global models cache directory (~/.codesearch/models/).
 ///
 /// This centralizes embedding model downloads so they are shared across all
 /// databases instead of being duplicated per-project. The directory is created
 /// if it does not exist.
 ///
-/// Falls back to a temp directory if the home directory cannot be determined.
+/// `CODESEARCH_MODELS_DIR` overrides it (e.g. a volume or a directory baked
+/// into an image). Falls back to a temp directory if the home directory cannot
+/// be determined.
 pub fn get_global_models_cache_dir() -> anyhow::Result<PathBuf> {
-    let base =
-        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("Could not determine home directory"))?;
-
-    let models_dir = base.join(CONFIG_DIR_NAME).join(MODELS_SUBDIR);
+    let models_dir = match std::env::var("CODESEARCH_MODELS_DIR") {
+        Ok(dir) if !dir.trim().is_empty() => PathBuf::from(dir.trim()),
+        _ => match dirs::home_dir() {
+            Some(home) => home.join(CONFIG_DIR_NAME).join(MODELS_SUBDIR),
+            None => std::env::temp_dir().join("codesearch").join(MODELS_SUBDIR),
+        },
+    };
 
     if !models_dir.exists() {
         std::fs::create_dir_all(&models_dir).map_err(|e| {
@@ -82,9 +133,38 @@ pub fn get_global_models_cache_dir() -> anyhow::Result<PathBuf> {
     Ok(models_dir)
 }
 
+/// Directory (in the home directory) holding global databases
+pub const GLOBAL_DB_DIR_NAME: &str = ".codesearch.dbs";
+
 /// Name of the repos configuration file
 pub const REPOS_CONFIG_FILE: &str = "repos.json";
 
+/// Parent directories searched for a database by default
+/// (override with `--discovery-depth` or `CODESEARCH_DISCOVERY_DEPTH`)
+pub const DEFAULT_DISCOVERY_DEPTH: usize = 5;
+
+/// Name of the file recording databases pinned with `codesearch use`
+pub const PINS_CONFIG_FILE: &str = "pins.json";
+
+/// Name of the background daemon's systemd unit / Windows scheduled task
+pub const DAEMON_SERVICE_NAME: &str = "codesearch-daemon";
+
+/// Label of the background daemon's launchd agent (macOS)
+pub const DAEMON_LAUNCHD_LABEL: &str = "com.codesearch.daemon";
+
+/// Log file (in the global config dir) the launchd agent writes to
+pub const DAEMON_LOG_FILE: &str = "daemon.log";
+
+/// Daemon configuration file (in `~/.codesearch/`): repositories and search API
+pub const DAEMON_CONFIG_FILE: &str = "daemon.json";
+
+/// Name of the custom language registry file in the global config dir
+pub const LANGUAGES_CONFIG_FILE: &str = "languages.json";
+
+/// Project-specific guidance for agents, appended to the MCP server's
+/// instructions; at the project root
+pub const INSTRUCTIONS_FILE: &str = ".codesearch-instructions.md";
+
 /// Default LMDB map size in megabytes (1024MB).
 ///
 /// This is the maximum virtual address space reserved for the memory-mapped database.
@@ -96,7 +176,8 @@ pub const DEFAULT_LMDB_MAP_SIZE_MB: usize = 1024;
 
 /// Maximum LMDB map size in megabytes (8192MB = 8GB).
 ///
-/// This is the hard upper limit for auto-resizing when MDB_MAP_FULL errors occur.
+/// This is the hard upper limit for pre-sizing before bulk inserts and for
+/// auto-resizing when MDB_MAP_FULL errors occur.
 /// Prevents unbounded growth and potential disk exhaustion.
 pub const MAX_LMDB_MAP_SIZE_MB: usize = 8192;
 
@@ -111,6 +192,12 @@ pub const MAX_LMDB_MAP_SIZE_MB: usize = 8192;
 /// - Override with `CODESEARCH_EMBEDDING_CACHE_MAX_ENTRIES` environment variable.
 pub const DEFAULT_EMBEDDING_CACHE_MAX_ENTRIES: usize = 200_000;
 
+/// Maximum number of query embeddings kept in the persistent embedding cache.
+///
+/// Query embeddings are stored next to chunk embeddings so repeated queries
+/// skip the model across restarts. 10,000 entries ≈ 15MB at 384 dims.
+pub const QUERY_CACHE_MAX_PERSISTED: usize = 10_000;
+
 /// Default embedding cache memory limit in MB.
 ///
 /// The embedding cache stores recently computed embeddings in memory (Moka LRU cache)
@@ -119,9 +206,152 @@ pub const DEFAULT_EMBEDDING_CACHE_MAX_ENTRIES: usize = 200_000;
 /// Override with `CODESEARCH_CACHE_MAX_MEMORY` environment variable.
 pub const DEFAULT_CACHE_MAX_MEMORY_MB: usize = 100;
 
+/// Default number of embedder instances in the `serve` query pool.
+///
+/// Each instance holds its own ONNX session (~100MB+ for the default model), so
+/// the default keeps memory usage identical to a single embedder.
+/// Override with `--embedders` or the `CODESEARCH_EMBEDDER_POOL_SIZE` environment variable.
+pub const DEFAULT_EMBEDDER_POOL_SIZE: usize = 1;
+
+/// Number of attempts when loading (and possibly downloading) an embedding model.
+pub const MODEL_LOAD_MAX_ATTEMPTS: u32 = 3;
+
+/// Initial backoff between model load attempts in milliseconds (doubles each attempt).
+pub const MODEL_LOAD_BASE_BACKOFF_MS: u64 = 1000;
+
+/// Upper bound on the backoff between model load attempts in milliseconds.
+///
+/// Also caps how long a lazily loading service (MCP) waits after repeated
+/// failures before it tries again on the next request.
+pub const MODEL_LOAD_MAX_BACKOFF_MS: u64 = 60_000;
+
+/// Chunk count above which vector search first picks the best files.
+///
+/// On large indexes, many small chunks crowd the ANN results. Searching
+/// file-level vectors first and then ranking only the chunks of the top files
+/// improves precision. Override with `CODESEARCH_FILE_RETRIEVAL_MIN_CHUNKS`
+/// (set to 0 to always use file-level retrieval).
+pub const DEFAULT_FILE_RETRIEVAL_MIN_CHUNKS: usize = 20_000;
+
+/// Number of files kept by the file-level retrieval stage.
+pub const FILE_RETRIEVAL_TOP_FILES: usize = 50;
+
+/// Ranking penalty for test fixtures, snapshots, golden files and minified assets.
+///
+/// Matching results keep `1 - penalty` of their score so production code wins
+/// ties without the files being excluded from the index. Override with
+/// `CODESEARCH_FIXTURE_PENALTY` (0 disables, 1 pushes them to the bottom).
+pub const DEFAULT_FIXTURE_PENALTY: f32 = 0.3;
+
+/// Ranking boost for results in the project's main languages.
+///
+/// The language with the most chunks gets the full boost and the next ones
+/// a share in proportion to their chunks, so a polyglot project doesn't push
+/// half of its code down. Override with `CODESEARCH_LANGUAGE_BOOST` (0 disables).
+pub const DEFAULT_LANGUAGE_BOOST: f32 = 0.2;
+
+/// Ranking boost for results in the languages a query asks for ("react hook",
+/// "goroutine", `*.py`), instead of the project's main languages.
+///
+/// Override with `CODESEARCH_LANGUAGE_INTENT_BOOST` (0 turns the inference off).
+pub const DEFAULT_LANGUAGE_INTENT_BOOST: f32 = 0.5;
+
+/// Languages, by chunk count, that get a share of the language boost
+pub const LANGUAGE_BOOST_TOP_LANGUAGES: usize = 3;
+
+/// Largest vector distance (cosine, 0 = same direction) of a `high` confidence result
+pub const CONFIDENCE_HIGH_MAX_DISTANCE: f32 = 0.20;
+
+/// Largest vector distance of a `medium` confidence result
+pub const CONFIDENCE_MEDIUM_MAX_DISTANCE: f32 = 0.30;
+
+/// Lead over the next result's score (as a fraction of the score) that a
+/// `high` confidence result needs; close runners-up make it `medium`
+pub const CONFIDENCE_HIGH_MIN_LEAD: f32 = 0.10;
+
+/// Vector distance beyond which a result is unrelated to the query
+///
+/// Such results are dropped when a positive `min_score` is given.
+pub const IRRELEVANT_DISTANCE: f32 = 0.40;
+
 /// File watcher debounce time in milliseconds
 pub const DEFAULT_FSW_DEBOUNCE_MS: u64 = 2000;
 
+/// Interval between mtime scans when the watcher falls back to polling
+/// (network filesystems, or `CODESEARCH_WATCH_STRATEGY=poll`).
+/// Override with `CODESEARCH_WATCH_POLL_MS`.
+pub const DEFAULT_WATCH_POLL_INTERVAL_MS: u64 = 2000;
+
+/// Every this many polls the tree is re-walked to pick up newly created files;
+/// the polls in between only stat the files already known.
+pub const WATCH_POLL_RESCAN_EVERY: u32 = 15;
+
+/// Filesystem types on which native change notifications are unreliable
+/// (NFS, SMB/CIFS, WSL drive mounts, SSHFS)
+pub const NETWORK_FILESYSTEMS: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "9p",
+    "drvfs",
+    "fuse.sshfs",
+    "afs",
+];
+
+/// Delay before retrying a file whose indexing failed once; doubles with
+/// every consecutive failure up to `FILE_FAILURE_MAX_BACKOFF_SECS`
+pub const FILE_FAILURE_BASE_BACKOFF_SECS: u64 = 30;
+
+/// Upper bound on the retry delay for a repeatedly failing file
+pub const FILE_FAILURE_MAX_BACKOFF_SECS: u64 = 3600;
+
+/// Consecutive failures after which a file is reported as quarantined
+/// (circuit open: retried at most once per `FILE_FAILURE_MAX_BACKOFF_SECS`)
+pub const FILE_FAILURE_CIRCUIT_THRESHOLD: u32 = 5;
+
+/// Journal of the file watcher batch being processed (in the database dir).
+/// Written before a batch starts and removed when it completes, so a batch
+/// interrupted by shutdown is replayed at the next start.
+pub const FSW_JOURNAL_FILE: &str = "fsw_journal.json";
+
+/// Files chunked, embedded and committed together during an incremental refresh.
+/// Each committed batch is searchable and advances the `index_status` progress,
+/// so an initial index of a large repository doesn't stay empty until the end.
+pub const REFRESH_BATCH_FILES: usize = 200;
+
+/// Chunks embedded by the `index --dry-run` throughput benchmark
+pub const DRY_RUN_BENCHMARK_CHUNKS: usize = 32;
+
+/// Chunks per searchable tranche of `codesearch index`: the vector index is
+/// built and metadata saved after each tranche, so searches see results long
+/// before a large index completes. Override with `CODESEARCH_INDEX_TRANCHE_CHUNKS`.
+pub const INDEX_TRANCHE_CHUNKS: usize = 5000;
+
+/// Chunks of a file embedded and stored together by `codesearch index`.
+///
+/// Each group is stored before the next is embedded, so memory is bounded by
+/// this size rather than by the largest file. Override with
+/// `CODESEARCH_INDEX_EMBED_BATCH_CHUNKS`.
+pub const INDEX_EMBED_BATCH_CHUNKS: usize = 256;
+
+/// Files in a row that may fail to chunk, embed or store before indexing stops.
+///
+/// Single bad files are skipped; a long streak of failures means the model or
+/// the disk is broken.
+pub const INDEX_MAX_CONSECUTIVE_FAILURES: usize = 20;
+
+/// Most recent commits scanned to order indexing by git recency
+pub const RECENCY_HISTORY_COMMITS: usize = 2000;
+
+/// How long shutdown waits for an in-flight file watcher batch to finish
+pub const SHUTDOWN_FLUSH_TIMEOUT_SECS: u64 = 10;
+
+/// How often a readonly instance checks for updates committed by the writer
+/// instance. Override with `CODESEARCH_READONLY_REFRESH_MS`.
+pub const DEFAULT_READONLY_REFRESH_INTERVAL_MS: u64 = 5000;
+
 /// Lock file name to indicate an active writer instance
 /// This prevents multiple processes from writing to the same database
 pub const WRITER_LOCK_FILE: &str = ".writer.lock";
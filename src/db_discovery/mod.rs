@@ -12,16 +12,64 @@
 //! - `data.mdb` file (LMDB vector store) - directly in db folder
 //! - `fts/` directory (full-text search)
 //!
-//! Invalid/incomplete databases are skipped during discovery.
+//! Invalid/incomplete databases are skipped when picking a database to use;
+//! `find_databases` lists local incomplete ones too so they can be reported.
 
+use crate::err_print;
 use anyhow::Result;
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 
-use crate::constants::{CONFIG_DIR_NAME, DB_DIR_NAME, REPOS_CONFIG_FILE};
+use crate::constants::{
+    CONFIG_DIR_NAME, DB_DIR_NAME, DEFAULT_DISCOVERY_DEPTH, FILE_META_DB_NAME, INDEX_METADATA_FILE,
+    PINS_CONFIG_FILE, REPOS_CONFIG_FILE,
+};
+
+/// Parent-directory depth set with `--discovery-depth` (`usize::MAX` = not set)
+static DISCOVERY_DEPTH: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Set by `--no-global-discovery`
+static NO_GLOBAL_DISCOVERY: AtomicBool = AtomicBool::new(false);
+
+/// Limit how many parent directories are searched for a database
+pub fn set_discovery_depth(depth: usize) {
+    DISCOVERY_DEPTH.store(depth, Ordering::SeqCst);
+}
+
+/// Stop falling back to globally registered repositories
+pub fn disable_global_discovery() {
+    NO_GLOBAL_DISCOVERY.store(true, Ordering::SeqCst);
+}
+
+/// Parent directories searched for a database
+/// (`--discovery-depth`, then `CODESEARCH_DISCOVERY_DEPTH`, default 5)
+pub fn discovery_depth() -> usize {
+    match DISCOVERY_DEPTH.load(Ordering::SeqCst) {
+        usize::MAX => std::env::var("CODESEARCH_DISCOVERY_DEPTH")
+            .ok()
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(DEFAULT_DISCOVERY_DEPTH),
+        depth => depth,
+    }
+}
+
+/// Whether globally registered repositories are consulted
+/// (off with `--no-global-discovery` or `CODESEARCH_NO_GLOBAL_DISCOVERY=1`)
+pub fn global_discovery_enabled() -> bool {
+    if NO_GLOBAL_DISCOVERY.load(Ordering::SeqCst) {
+        return false;
+    }
+    !std::env::var("CODESEARCH_NO_GLOBAL_DISCOVERY").is_ok_and(|v| {
+        matches!(
+            v.trim().to_lowercase().as_str(),
+            "1" | "true" | "yes" | "on"
+        )
+    })
+}
 
 /// Information about a discovered database
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,6 +107,21 @@ pub fn is_valid_database(db_path: &Path) -> bool {
     metadata_exists && lmdb_exists && fts_exists
 }
 
+/// Required components missing from a database directory (`metadata.json`, `data.mdb`, `fts/`)
+pub fn missing_components(db_path: &Path) -> Vec<&'static str> {
+    let mut missing = Vec::new();
+    if !db_path.join("metadata.json").exists() {
+        missing.push("metadata.json");
+    }
+    if !db_path.join("data.mdb").exists() {
+        missing.push("data.mdb");
+    }
+    if !db_path.join("fts").is_dir() {
+        missing.push("fts/");
+    }
+    missing
+}
+
 /// Check if a database directory exists but is incomplete/corrupt
 ///
 /// Returns `Some(reason)` if the database is incomplete, `None` if valid or doesn't exist
@@ -71,18 +134,7 @@ pub fn check_database_integrity(db_path: &Path) -> Option<String> {
         return Some("exists but is not a directory".to_string());
     }
 
-    let mut missing = Vec::new();
-
-    if !db_path.join("metadata.json").exists() {
-        missing.push("metadata.json");
-    }
-    if !db_path.join("data.mdb").exists() {
-        missing.push("data.mdb");
-    }
-    if !db_path.join("fts").is_dir() {
-        missing.push("fts/");
-    }
-
+    let missing = missing_components(db_path);
     if missing.is_empty() {
         None // Valid
     } else {
@@ -92,8 +144,9 @@ pub fn check_database_integrity(db_path: &Path) -> Option<String> {
 
 /// Find databases in current directory and parent directories
 ///
-/// Only returns databases that pass validation (have metadata.json, data.mdb, fts/).
-/// Incomplete/corrupt databases are logged and skipped.
+/// Incomplete databases in the current and parent directories are included so
+/// they can be reported (see `missing_components`); global ones are only
+/// returned when valid.
 pub fn find_databases() -> Result<Vec<DatabaseInfo>> {
     let mut databases = Vec::new();
 
@@ -101,55 +154,31 @@ pub fn find_databases() -> Result<Vec<DatabaseInfo>> {
     let current_dir = std::env::current_dir()?;
     let current_db = current_dir.join(DB_DIR_NAME);
 
-    if current_db.exists() {
-        if is_valid_database(&current_db) {
-            databases.push(DatabaseInfo {
-                project_path: current_dir.clone(),
-                db_path: current_db,
-                is_current: true,
-                depth: 0,
-                is_global: false,
-            });
-        } else if let Some(reason) = check_database_integrity(&current_db) {
-            eprintln!(
-                "{}",
-                format!(
-                    "⚠️  Skipping incomplete database at {}: {}",
-                    current_db.display(),
-                    reason
-                )
-                .yellow()
-            );
-        }
+    if current_db.is_dir() {
+        databases.push(DatabaseInfo {
+            project_path: current_dir.clone(),
+            db_path: current_db,
+            is_current: true,
+            depth: 0,
+            is_global: false,
+        });
     }
 
-    // 2. Check parent directories (up to 5 levels up)
+    // 2. Check parent directories (up to `discovery_depth()` levels up)
     let mut parent_dir = current_dir.clone();
-    for depth in 1..=5 {
+    for depth in 1..=discovery_depth() {
         if let Some(parent) = parent_dir.parent() {
             parent_dir = parent.to_path_buf();
             let parent_db = parent_dir.join(DB_DIR_NAME);
 
-            if parent_db.exists() {
-                if is_valid_database(&parent_db) {
-                    databases.push(DatabaseInfo {
-                        project_path: parent_dir.clone(),
-                        db_path: parent_db,
-                        is_current: false,
-                        depth,
-                        is_global: false,
-                    });
-                } else if let Some(reason) = check_database_integrity(&parent_db) {
-                    eprintln!(
-                        "{}",
-                        format!(
-                            "⚠️  Skipping incomplete database at {}: {}",
-                            parent_db.display(),
-                            reason
-                        )
-                        .yellow()
-                    );
-                }
+            if parent_db.is_dir() {
+                databases.push(DatabaseInfo {
+                    project_path: parent_dir.clone(),
+                    db_path: parent_db,
+                    is_current: false,
+                    depth,
+                    is_global: false,
+                });
             }
         } else {
             break; // Reached filesystem root
@@ -157,8 +186,10 @@ pub fn find_databases() -> Result<Vec<DatabaseInfo>> {
     }
 
     // 3. Check globally tracked repositories
-    if let Ok(global_dbs) = find_global_databases() {
-        databases.extend(global_dbs);
+    if global_discovery_enabled() {
+        if let Ok(global_dbs) = find_global_databases() {
+            databases.extend(global_dbs);
+        }
     }
 
     Ok(databases)
@@ -167,10 +198,12 @@ pub fn find_databases() -> Result<Vec<DatabaseInfo>> {
 /// Find the best database to use for a given directory
 ///
 /// Priority order:
+/// 0. Database pinned with `codesearch use` for the directory or an ancestor
 /// 1. Valid database in current directory
-/// 2. Valid database in a direct child directory (1 level down — matches repo-anchored index)
-/// 3. Valid database in nearest parent directory (up to 5 levels)
-/// 4. First valid global database
+/// 2. Valid database in a direct child directory (1 level down — matches repo-anchored index);
+///    with several, the most recently indexed one, then the first by name
+/// 3. Valid database in nearest parent directory (up to `discovery_depth()` levels)
+/// 4. First valid global database (unless global discovery is disabled)
 ///
 /// Incomplete/corrupt databases are skipped with a warning.
 pub fn find_best_database(target_dir: Option<&Path>) -> Result<Option<DatabaseInfo>> {
@@ -189,6 +222,11 @@ pub fn find_best_database(target_dir: Option<&Path>) -> Result<Option<DatabaseIn
         Err(_) => return Ok(None), // Path doesn't exist, return None
     };
 
+    // 0. A pinned database overrides discovery
+    if let Some((_, info)) = pinned_database(&canonical) {
+        return Ok(Some(info));
+    }
+
     // 1. Check current directory
     let current_db = canonical.join(DB_DIR_NAME);
     if current_db.exists() {
@@ -201,7 +239,7 @@ pub fn find_best_database(target_dir: Option<&Path>) -> Result<Option<DatabaseIn
                 is_global: false,
             }));
         } else if let Some(reason) = check_database_integrity(&current_db) {
-            eprintln!(
+            err_print!(
                 "{}",
                 format!(
                     "⚠️  Found incomplete database at {}: {}",
@@ -210,7 +248,7 @@ pub fn find_best_database(target_dir: Option<&Path>) -> Result<Option<DatabaseIn
                 )
                 .yellow()
             );
-            eprintln!(
+            err_print!(
                 "{}",
                 "   Run 'codesearch index --force' to rebuild it.".yellow()
             );
@@ -220,77 +258,138 @@ pub fn find_best_database(target_dir: Option<&Path>) -> Result<Option<DatabaseIn
     // 2. Check direct child directories (1 level down)
     //    Matches find_git_root Phase 2: index may be at git root inside a child dir
     //    e.g. /workspace/.codesearch.db doesn't exist, but /workspace/frontend/.codesearch.db does
-    if let Ok(entries) = std::fs::read_dir(&canonical) {
-        for entry in entries.flatten() {
-            let child = entry.path();
-            if !child.is_dir() {
-                continue;
-            }
-            // Skip hidden dirs (except the target itself) and known non-project dirs
-            let name = child.file_name().unwrap_or_default().to_string_lossy();
-            if name.starts_with('.') || name == "node_modules" || name == "target" {
-                continue;
-            }
-            let child_db = child.join(DB_DIR_NAME);
-            if child_db.exists() && is_valid_database(&child_db) {
-                return Ok(Some(DatabaseInfo {
-                    project_path: child,
-                    db_path: child_db,
-                    is_current: false,
-                    depth: 1,
-                    is_global: false,
-                }));
-            }
-        }
+    if let Some(child) = find_child_databases(&canonical).into_iter().next() {
+        return Ok(Some(child));
     }
 
     // 3. Check parent directories
-    let mut parent_dir = canonical.clone();
-    for depth in 1..=5 {
-        if let Some(parent) = parent_dir.parent() {
-            parent_dir = parent.to_path_buf();
-            let parent_db = parent_dir.join(DB_DIR_NAME);
-
-            if parent_db.exists() {
-                if is_valid_database(&parent_db) {
-                    return Ok(Some(DatabaseInfo {
-                        project_path: parent_dir.clone(),
-                        db_path: parent_db,
-                        is_current: false,
-                        depth,
-                        is_global: false,
-                    }));
-                } else if let Some(reason) = check_database_integrity(&parent_db) {
-                    eprintln!(
-                        "{}",
-                        format!(
-                            "⚠️  Found incomplete database at {}: {}",
-                            parent_db.display(),
-                            reason
-                        )
-                        .yellow()
-                    );
-                }
-            }
-        } else {
-            break;
-        }
+    if let Some(info) = nearest_parent_database(&canonical, discovery_depth()) {
+        return Ok(Some(info));
     }
 
     // 4. Check global databases
-    let global_dbs = find_global_databases()?;
-    if !global_dbs.is_empty() {
-        return Ok(Some(global_dbs.into_iter().next().unwrap()));
+    if global_discovery_enabled() {
+        let global_dbs = find_global_databases()?;
+        if !global_dbs.is_empty() {
+            return Ok(Some(global_dbs.into_iter().next().unwrap()));
+        }
     }
 
     Ok(None)
 }
 
+/// Valid databases in the direct child directories of `dir`, best candidate first
+///
+/// Hidden directories, `node_modules` and `target` are skipped. Candidates are
+/// ordered by depth, then most recently indexed (see `last_indexed`), then
+/// directory name, so the choice doesn't depend on filesystem order.
+pub fn find_child_databases(dir: &Path) -> Vec<DatabaseInfo> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut children: Vec<_> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|child| {
+            let name = child.file_name().unwrap_or_default().to_string_lossy();
+            child.is_dir() && !name.starts_with('.') && name != "node_modules" && name != "target"
+        })
+        .filter_map(|child| {
+            let child_db = child.join(DB_DIR_NAME);
+            is_valid_database(&child_db).then_some(DatabaseInfo {
+                project_path: child,
+                db_path: child_db,
+                is_current: false,
+                depth: 1,
+                is_global: false,
+            })
+        })
+        .map(|info| (last_indexed(&info.db_path), info))
+        .collect();
+    children.sort_by(|(a_time, a), (b_time, b)| {
+        a.depth
+            .cmp(&b.depth)
+            .then_with(|| b_time.cmp(a_time))
+            .then_with(|| a.project_path.file_name().cmp(&b.project_path.file_name()))
+    });
+    children.into_iter().map(|(_, info)| info).collect()
+}
+
+/// When a database was last updated
+///
+/// `file_meta.json` is rewritten after every index update; databases without
+/// one fall back to the time of their `metadata.json`.
+pub fn last_indexed(db_path: &Path) -> Option<std::time::SystemTime> {
+    [FILE_META_DB_NAME, INDEX_METADATA_FILE]
+        .iter()
+        .find_map(|file| {
+            fs::metadata(db_path.join(file))
+                .and_then(|m| m.modified())
+                .ok()
+        })
+}
+
+/// Child databases to search together when `dir` has no database of its own
+///
+/// Returns the valid child databases of `dir` when discovery would otherwise
+/// pick one of them (no pin and no valid database in `dir`) and there is more
+/// than one; empty otherwise.
+pub fn child_databases_to_merge(target_dir: Option<&Path>) -> Result<Vec<DatabaseInfo>> {
+    let target = target_dir.unwrap_or_else(|| Path::new("."));
+    let Ok(canonical) = std::env::current_dir()?.join(target).canonicalize() else {
+        return Ok(Vec::new());
+    };
+    if pinned_database(&canonical).is_some() || is_valid_database(&canonical.join(DB_DIR_NAME)) {
+        return Ok(Vec::new());
+    }
+    let children = find_child_databases(&canonical);
+    Ok(if children.len() > 1 {
+        children
+    } else {
+        Vec::new()
+    })
+}
+
+/// Valid database in the nearest of up to `max_depth` parent directories of `dir`
+///
+/// Incomplete databases on the way are skipped with a warning.
+fn nearest_parent_database(dir: &Path, max_depth: usize) -> Option<DatabaseInfo> {
+    for (depth, parent_dir) in dir.ancestors().enumerate().skip(1).take(max_depth) {
+        let parent_db = parent_dir.join(DB_DIR_NAME);
+        if !parent_db.exists() {
+            continue;
+        }
+        if is_valid_database(&parent_db) {
+            return Some(DatabaseInfo {
+                project_path: parent_dir.to_path_buf(),
+                db_path: parent_db,
+                is_current: false,
+                depth,
+                is_global: false,
+            });
+        } else if let Some(reason) = check_database_integrity(&parent_db) {
+            err_print!(
+                "{}",
+                format!(
+                    "⚠️  Found incomplete database at {}: {}",
+                    parent_db.display(),
+                    reason
+                )
+                .yellow()
+            );
+        }
+    }
+    None
+}
+
 /// Find globally tracked repositories
 ///
 /// Only returns databases that pass validation.
-fn find_global_databases() -> Result<Vec<DatabaseInfo>> {
-    let home_dir = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("No home directory found"))?;
+pub fn find_global_databases() -> Result<Vec<DatabaseInfo>> {
+    // Without a home directory (e.g. in a container) no repository is tracked
+    let Some(home_dir) = dirs::home_dir() else {
+        return Ok(Vec::new());
+    };
     let config_dir = home_dir.join(CONFIG_DIR_NAME);
     let config_path = config_dir.join(REPOS_CONFIG_FILE);
 
@@ -376,6 +475,121 @@ pub fn unregister_repository(project_path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// Resolve a project directory or `.codesearch.db` directory to its database
+///
+/// Fails unless the database is complete, so it can be used right away.
+pub fn resolve_database(path: &Path) -> Result<DatabaseInfo> {
+    let path = path
+        .canonicalize()
+        .map_err(|e| anyhow::anyhow!("Cannot access {}: {}", path.display(), e))?;
+    let (project_path, db_path) = if path.file_name().is_some_and(|n| n == DB_DIR_NAME) {
+        let project = path.parent().unwrap_or(&path).to_path_buf();
+        (project, path)
+    } else {
+        let db_path = path.join(DB_DIR_NAME);
+        (path, db_path)
+    };
+
+    if !is_valid_database(&db_path) {
+        let reason =
+            check_database_integrity(&db_path).unwrap_or_else(|| "not indexed".to_string());
+        anyhow::bail!(
+            "No usable codesearch database at {} ({}). Run 'codesearch index' there first.",
+            project_path.display(),
+            reason
+        );
+    }
+
+    let is_current = std::env::current_dir()
+        .and_then(|d| d.canonicalize())
+        .is_ok_and(|d| d == project_path);
+    Ok(DatabaseInfo {
+        project_path,
+        db_path,
+        is_current,
+        depth: 0,
+        is_global: false,
+    })
+}
+
+fn pins_config_path() -> Result<PathBuf> {
+    let home_dir = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("No home directory found"))?;
+    Ok(home_dir.join(CONFIG_DIR_NAME).join(PINS_CONFIG_FILE))
+}
+
+/// Pinned directories mapped to the project whose database they use
+fn read_pins() -> HashMap<String, String> {
+    pins_config_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn write_pins(pins: &HashMap<String, String>) -> Result<()> {
+    let config_path = pins_config_path()?;
+    if let Some(config_dir) = config_path.parent() {
+        fs::create_dir_all(config_dir)?;
+    }
+    fs::write(&config_path, serde_json::to_string_pretty(pins)?)?;
+    Ok(())
+}
+
+/// The pin that applies to `dir`: its own or the nearest pinned ancestor's
+fn lookup_pin<'a>(pins: &'a HashMap<String, String>, dir: &Path) -> Option<(PathBuf, &'a str)> {
+    dir.ancestors().find_map(|ancestor| {
+        pins.get(ancestor.to_string_lossy().as_ref())
+            .map(|project| (ancestor.to_path_buf(), project.as_str()))
+    })
+}
+
+/// Pin the database at `path` for `dir` and its subdirectories, overriding discovery
+pub fn pin_database(dir: &Path, path: &Path) -> Result<DatabaseInfo> {
+    let info = resolve_database(path)?;
+    let dir = dir.canonicalize()?;
+    let mut pins = read_pins();
+    pins.insert(
+        dir.to_string_lossy().to_string(),
+        info.project_path.to_string_lossy().to_string(),
+    );
+    write_pins(&pins)?;
+    Ok(info)
+}
+
+/// Remove the pin of `dir`, returning the project it pointed to
+pub fn unpin_database(dir: &Path) -> Result<Option<PathBuf>> {
+    let dir = dir.canonicalize()?;
+    let mut pins = read_pins();
+    let removed = pins.remove(dir.to_string_lossy().as_ref());
+    if removed.is_some() {
+        write_pins(&pins)?;
+    }
+    Ok(removed.map(PathBuf::from))
+}
+
+/// The database pinned for `dir` (or an ancestor), with the pinned directory
+///
+/// A pin whose database is gone or incomplete is ignored with a warning.
+pub fn pinned_database(dir: &Path) -> Option<(PathBuf, DatabaseInfo)> {
+    let pins = read_pins();
+    let (pinned_dir, project) = lookup_pin(&pins, dir)?;
+    match resolve_database(Path::new(project)) {
+        Ok(info) => Some((pinned_dir, info)),
+        Err(e) => {
+            err_print!(
+                "{}",
+                format!(
+                    "⚠️  Ignoring database pinned for {}: {}",
+                    pinned_dir.display(),
+                    e
+                )
+                .yellow()
+            );
+            None
+        }
+    }
+}
+
 /// Resolve database path with user-friendly messaging
 ///
 /// This is a shared utility used by both search and index commands.
@@ -405,7 +619,7 @@ pub fn resolve_database_with_message(
             } else {
                 db_info.project_path.display().to_string()
             };
-            eprintln!(
+            err_print!(
                 "{}",
                 format!(
                     "📂 Using database from: {}\n   ({} from subfolder, project root: {})",
@@ -415,6 +629,9 @@ pub fn resolve_database_with_message(
                 )
                 .dimmed()
             );
+            if let Some(note) = child_choice_note(target, &db_info) {
+                err_print!("{}", note.dimmed());
+            }
         }
         return Ok((db_info.db_path, db_info.project_path));
     }
@@ -432,6 +649,34 @@ pub fn resolve_database_with_message(
     Ok((db_path, canonical_path))
 }
 
+/// Explain which child database was picked when `target` has several
+///
+/// `None` unless `chosen` is a child database of `target` with siblings.
+fn child_choice_note(target: &Path, chosen: &DatabaseInfo) -> Option<String> {
+    let target = std::env::current_dir()
+        .ok()?
+        .join(target)
+        .canonicalize()
+        .ok()?;
+    if chosen.project_path.parent() != Some(target.as_path()) {
+        return None;
+    }
+    let others: Vec<String> = find_child_databases(&target)
+        .iter()
+        .filter(|c| c.project_path != chosen.project_path)
+        .filter_map(|c| Some(c.project_path.file_name()?.to_string_lossy().into_owned()))
+        .collect();
+    if others.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "   Chose {} over {} (most recently indexed, then by name).\n   \
+         Use 'search --merge-children' to search them all or 'codesearch use <path>' to pin one.",
+        chosen.project_path.file_name()?.to_string_lossy(),
+        others.join(", ")
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -453,6 +698,58 @@ mod tests {
         println!("Found {} databases", dbs.len());
     }
 
+    #[test]
+    fn test_resolve_database_and_pin_lookup() {
+        let dir = tempdir().unwrap();
+        let project = dir.path().canonicalize().unwrap().join("repo");
+        create_fake_db(&project.join(DB_DIR_NAME));
+
+        // Both the project and its database directory resolve to the same database
+        let info = resolve_database(&project).unwrap();
+        assert_eq!(info.project_path, project);
+        let info = resolve_database(&project.join(DB_DIR_NAME)).unwrap();
+        assert_eq!(info.project_path, project);
+        assert!(resolve_database(dir.path()).is_err());
+
+        // A pin applies to the pinned directory and everything below it
+        let workspace = dir.path().canonicalize().unwrap();
+        let pins = HashMap::from([(
+            workspace.to_string_lossy().to_string(),
+            project.to_string_lossy().to_string(),
+        )]);
+        let nested = workspace.join("other").join("src");
+        let (pinned_dir, pinned) = lookup_pin(&pins, &nested).unwrap();
+        assert_eq!(pinned_dir, workspace);
+        assert_eq!(Path::new(pinned), project);
+        assert!(lookup_pin(&pins, workspace.parent().unwrap()).is_none());
+    }
+
+    #[test]
+    fn test_nearest_parent_database_respects_depth() {
+        let dir = tempdir().unwrap();
+        create_fake_db(&dir.path().join(DB_DIR_NAME));
+        let nested = dir.path().join("a").join("b");
+        fs::create_dir_all(&nested).unwrap();
+
+        let info = nearest_parent_database(&nested, 2).unwrap();
+        assert_eq!(info.depth, 2);
+        assert_eq!(info.project_path, dir.path());
+        assert!(nearest_parent_database(&nested, 1).is_none());
+        assert!(nearest_parent_database(&nested, 0).is_none());
+    }
+
+    #[test]
+    fn test_missing_components() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join(DB_DIR_NAME);
+        fs::create_dir_all(&db_path).unwrap();
+        fs::write(db_path.join("metadata.json"), "{}").unwrap();
+        assert_eq!(missing_components(&db_path), vec!["data.mdb", "fts/"]);
+
+        create_fake_db(&db_path);
+        assert!(missing_components(&db_path).is_empty());
+    }
+
     #[test]
     fn test_is_valid_database() {
         let dir = tempdir().unwrap();
@@ -495,6 +792,74 @@ mod tests {
         assert!(info.project_path.ends_with("frontend"));
     }
 
+    #[test]
+    fn test_child_databases_to_merge() {
+        let dir = tempdir().unwrap();
+        for name in ["frontend", "backend", ".cache"] {
+            create_fake_db(&dir.path().join(name).join(DB_DIR_NAME));
+        }
+
+        let mut names: Vec<_> = child_databases_to_merge(Some(dir.path()))
+            .unwrap()
+            .iter()
+            .map(|info| info.project_path.file_name().unwrap().to_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["backend", "frontend"]);
+
+        // A database of its own wins over merging the children
+        create_fake_db(&dir.path().join(DB_DIR_NAME));
+        assert!(child_databases_to_merge(Some(dir.path()))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_child_databases_ordered_by_last_indexed_then_name() {
+        let dir = tempdir().unwrap();
+        for name in ["charlie", "alpha", "bravo"] {
+            create_fake_db(&dir.path().join(name).join(DB_DIR_NAME));
+        }
+        let names = |dbs: Vec<DatabaseInfo>| -> Vec<String> {
+            dbs.iter()
+                .map(|d| d.project_path.file_name().unwrap().to_string_lossy().into())
+                .collect()
+        };
+
+        // Same index time: by name
+        let time = std::time::SystemTime::now() - std::time::Duration::from_secs(3600);
+        for name in ["charlie", "alpha", "bravo"] {
+            let meta = dir
+                .path()
+                .join(name)
+                .join(DB_DIR_NAME)
+                .join(INDEX_METADATA_FILE);
+            fs::File::options()
+                .write(true)
+                .open(meta)
+                .unwrap()
+                .set_modified(time)
+                .unwrap();
+        }
+        assert_eq!(
+            names(find_child_databases(dir.path())),
+            ["alpha", "bravo", "charlie"]
+        );
+
+        // A more recent index update wins
+        let charlie_db = dir.path().join("charlie").join(DB_DIR_NAME);
+        fs::write(charlie_db.join(FILE_META_DB_NAME), "{}").unwrap();
+        assert_eq!(
+            names(find_child_databases(dir.path())),
+            ["charlie", "alpha", "bravo"]
+        );
+        let best = find_best_database(Some(dir.path())).unwrap().unwrap();
+        assert!(best.project_path.ends_with("charlie"));
+
+        let note = child_choice_note(dir.path(), &best).unwrap();
+        assert!(note.contains("Chose charlie over alpha, bravo"), "{}", note);
+    }
+
     #[test]
     fn test_find_best_database_child_skips_hidden_dirs() {
         let dir = tempdir().unwrap();
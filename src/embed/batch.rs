@@ -1,6 +1,9 @@
 use super::embedder::FastEmbedder;
 use crate::chunker::Chunk;
+use crate::constants::EMBED_PATH_COMPONENTS;
+use crate::file::Language;
 use anyhow::Result;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 /// Statistics for embedding operations
@@ -106,7 +109,7 @@ impl BatchEmbedder {
                 .embed_batch(texts)?;
 
             // Combine chunks with embeddings
-            for (chunk, embedding) in chunk_batch.iter().zip(embeddings.into_iter()) {
+            for (chunk, embedding) in chunk_batch.iter().zip(embeddings) {
                 embedded_chunks.push(EmbeddedChunk::new(chunk.clone(), embedding));
             }
         }
@@ -127,57 +130,8 @@ impl BatchEmbedder {
     }
 
     /// Prepare chunk text for embedding
-    ///
-    /// Combines different chunk metadata for better embeddings:
-    /// - Context breadcrumbs
-    /// - Function/Struct name (extracted from signature or content)
-    /// - Signature (if available)
-    /// - Docstring (if available)
-    /// - Content
     fn prepare_text(&self, chunk: &Chunk) -> String {
-        let mut parts = Vec::new();
-
-        // Add context breadcrumbs (e.g., "File: main.rs > Class: Server")
-        if !chunk.context.is_empty() {
-            let context = chunk.context.join(" > ");
-            parts.push(format!("Context: {}", context));
-        }
-
-        // Add signature if available (e.g., "fn process(data: Vec<T>) -> Result<T>")
-        if let Some(sig) = &chunk.signature {
-            parts.push(format!("Signature: {}", sig));
-
-            // Extract function/struct name from signature for better searchability
-            // e.g., "fn handle_file_modified" -> "handle_file_modified"
-            if let Some(name) = sig.split_whitespace().nth(1) {
-                // Remove generic parameters and return type
-                let name = name
-                    .split('<')
-                    .next()
-                    .unwrap_or(name)
-                    .split('(')
-                    .next()
-                    .unwrap_or(name)
-                    .split('{')
-                    .next()
-                    .unwrap_or(name);
-                parts.push(format!("Name: {}", name));
-            }
-        }
-
-        // Add docstring if available
-        if let Some(doc) = &chunk.docstring {
-            // Clean up docstring
-            let cleaned = clean_docstring(doc);
-            if !cleaned.is_empty() {
-                parts.push(format!("Documentation: {}", cleaned));
-            }
-        }
-
-        // Add main content
-        parts.push(format!("Code:\n{}", chunk.content));
-
-        parts.join("\n")
+        embedding_text(chunk)
     }
 
     /// Get embedding dimensions
@@ -193,6 +147,88 @@ impl BatchEmbedder {
     }
 }
 
+/// Build the text that is embedded for a chunk
+///
+/// Combines different chunk metadata for better embeddings:
+/// - Language name
+/// - Trailing path components (e.g., "src > index > manager.rs")
+/// - Context breadcrumbs
+/// - Function/Struct name (extracted from signature or content)
+/// - Signature (if available)
+/// - Tags such as HTTP routes (if any)
+/// - Docstring (if available)
+/// - Content
+///
+/// Changing this layout changes every embedding, so it must be accompanied by
+/// a bump of `CHUNKER_SCHEMA_VERSION`.
+pub fn embedding_text(chunk: &Chunk) -> String {
+    let mut parts = Vec::new();
+
+    // Add language name (e.g., "Rust")
+    let language = Language::from_path(Path::new(&chunk.path));
+    if language.is_indexable() {
+        parts.push(format!("Language: {}", language.name()));
+    }
+
+    // Add the last few path components; absolute prefixes are noise for retrieval
+    let components: Vec<&str> = chunk
+        .path
+        .split(['/', '\\'])
+        .filter(|c| !c.is_empty() && *c != ".")
+        .collect();
+    if !components.is_empty() {
+        let skip = components.len().saturating_sub(EMBED_PATH_COMPONENTS);
+        parts.push(format!("Path: {}", components[skip..].join(" > ")));
+    }
+
+    // Add context breadcrumbs (e.g., "File: main.rs > Class: Server")
+    if !chunk.context.is_empty() {
+        let context = chunk.context.join(" > ");
+        parts.push(format!("Context: {}", context));
+    }
+
+    // Add signature if available (e.g., "fn process(data: Vec<T>) -> Result<T>")
+    if let Some(sig) = &chunk.signature {
+        parts.push(format!("Signature: {}", sig));
+
+        // Extract function/struct name from signature for better searchability
+        // e.g., "fn handle_file_modified" -> "handle_file_modified"
+        if let Some(name) = sig.split_whitespace().nth(1) {
+            // Remove generic parameters and return type
+            let name = name
+                .split('<')
+                .next()
+                .unwrap_or(name)
+                .split('(')
+                .next()
+                .unwrap_or(name)
+                .split('{')
+                .next()
+                .unwrap_or(name);
+            parts.push(format!("Name: {}", name));
+        }
+    }
+
+    // Add tags (e.g., HTTP routes like "POST /users")
+    if !chunk.tags.is_empty() {
+        parts.push(format!("Tags: {}", chunk.tags.join(", ")));
+    }
+
+    // Add docstring if available
+    if let Some(doc) = &chunk.docstring {
+        // Clean up docstring
+        let cleaned = clean_docstring(doc);
+        if !cleaned.is_empty() {
+            parts.push(format!("Documentation: {}", cleaned));
+        }
+    }
+
+    // Add main content
+    parts.push(format!("Code:\n{}", chunk.content));
+
+    parts.join("\n")
+}
+
 /// Clean docstring by removing comment markers
 fn clean_docstring(doc: &str) -> String {
     let result = doc
@@ -313,6 +349,29 @@ mod tests {
         std::env::remove_var("FASTEMBED_CACHE_DIR");
     }
 
+    #[test]
+    fn test_embedding_text_includes_language_and_path() {
+        let mut chunk = Chunk::new(
+            "fn refresh() {}".to_string(),
+            0,
+            1,
+            ChunkKind::Function,
+            "/home/user/project/src/index/manager.rs".to_string(),
+        );
+        chunk.context = vec![
+            "File: /home/user/project/src/index/manager.rs".to_string(),
+            "Impl: IndexManager".to_string(),
+            "Function: refresh".to_string(),
+        ];
+
+        let text = embedding_text(&chunk);
+
+        assert!(text.starts_with("Language: Rust\n"));
+        assert!(text.contains("Path: project > src > index > manager.rs\n"));
+        assert!(text.contains("> Impl: IndexManager > Function: refresh"));
+        assert!(text.ends_with("Code:\nfn refresh() {}"));
+    }
+
     fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
         let dot: f32 = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum();
         let mag_a: f32 = a.iter().map(|x| x * x).sum::<f32>().sqrt();
@@ -1,11 +1,13 @@
 use super::batch::EmbeddedChunk;
 use crate::chunker::Chunk;
+use crate::constants::{CACHE_USAGE_FILE, QUERY_CACHE_MAX_PERSISTED};
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use heed::types::*;
 use heed::{Database, Env, EnvOpenOptions};
 use moka::sync::Cache;
-use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 
@@ -278,11 +280,17 @@ impl QueryCacheStats {
 /// Cache location: ~/.codesearch/embedding_cache/<model_short_name>/
 /// Key: content_hash (SHA256) → Vec<f32> (embedding vector)
 ///
+/// A second database in the same environment holds query embeddings keyed by
+/// the SHA256 of the query text, so repeated queries skip the model across
+/// MCP restarts and CLI invocations. The environment is per model, so keys
+/// never mix embeddings from different models.
+///
 /// This is separate from the in-memory EmbeddingCache which uses Moka for
 /// automatic memory management. The persistent cache provides long-term storage.
 pub struct PersistentEmbeddingCache {
     env: Env,
     db: Database<Str, SerdeBincode<Vec<f32>>>,
+    queries: Database<Str, SerdeBincode<Vec<f32>>>,
     cache_dir: PathBuf,
 }
 
@@ -299,7 +307,11 @@ impl PersistentEmbeddingCache {
             .ok_or_else(|| anyhow::anyhow!("Could not get parent directory of models cache"))?
             .join("embedding_cache")
             .join(model_name);
+        Self::open_at(cache_dir)
+    }
 
+    /// Open a persistent cache stored in `cache_dir`
+    fn open_at(cache_dir: PathBuf) -> Result<Self> {
         std::fs::create_dir_all(&cache_dir).map_err(|e| {
             anyhow::anyhow!(
                 "Failed to create embedding cache directory {}: {}",
@@ -311,15 +323,21 @@ impl PersistentEmbeddingCache {
         let env = unsafe {
             EnvOpenOptions::new()
                 .map_size(512 * 1024 * 1024) // 512MB — plenty for cache
-                .max_dbs(1)
+                .max_dbs(2)
                 .open(&cache_dir)?
         };
 
         let mut wtxn = env.write_txn()?;
         let db = env.create_database(&mut wtxn, Some("embeddings"))?;
+        let queries = env.create_database(&mut wtxn, Some("queries"))?;
         wtxn.commit()?;
 
-        Ok(Self { env, db, cache_dir })
+        Ok(Self {
+            env,
+            db,
+            queries,
+            cache_dir,
+        })
     }
 
     /// Get embedding from cache by content hash
@@ -346,10 +364,43 @@ impl PersistentEmbeddingCache {
         Ok(())
     }
 
+    /// Get a query embedding by query text
+    pub fn get_query(&self, query: &str) -> Result<Option<Vec<f32>>> {
+        let rtxn = self.env.read_txn()?;
+        Ok(self.queries.get(&rtxn, &Chunk::compute_hash(query))?)
+    }
+
+    /// Store query embeddings (single transaction), keeping at most
+    /// `QUERY_CACHE_MAX_PERSISTED` entries
+    pub fn put_queries(&self, entries: &[(&str, &[f32])]) -> Result<()> {
+        let mut wtxn = self.env.write_txn()?;
+        for (query, embedding) in entries {
+            self.queries
+                .put(&mut wtxn, &Chunk::compute_hash(query), &embedding.to_vec())?;
+        }
+
+        // Same bounded, effectively random eviction as `evict_if_needed`
+        let count = self.queries.len(&wtxn)? as usize;
+        if count > QUERY_CACHE_MAX_PERSISTED {
+            let keys: Vec<String> = self
+                .queries
+                .iter(&wtxn)?
+                .take(count - QUERY_CACHE_MAX_PERSISTED)
+                .map(|result| result.map(|(key, _)| key.to_string()))
+                .collect::<heed::Result<Vec<_>>>()?;
+            for key in &keys {
+                self.queries.delete(&mut wtxn, key)?;
+            }
+        }
+        wtxn.commit()?;
+        Ok(())
+    }
+
     /// Get cache statistics
     pub fn stats(&self) -> Result<PersistentCacheStats> {
         let rtxn = self.env.read_txn()?;
         let count = self.db.len(&rtxn)?;
+        let query_count = self.queries.len(&rtxn)?;
         let file_size = std::fs::metadata(self.cache_dir.join("data.mdb"))
             .map(|m| m.len())
             .unwrap_or(0);
@@ -359,6 +410,7 @@ impl PersistentEmbeddingCache {
             .map(DateTime::from);
         Ok(PersistentCacheStats {
             entries: count as usize,
+            query_entries: query_count as usize,
             file_size_bytes: file_size,
             last_access,
         })
@@ -407,10 +459,11 @@ impl PersistentEmbeddingCache {
         Ok(keys_to_delete.len())
     }
 
-    /// Clear all cached embeddings
+    /// Clear all cached embeddings (chunks and queries)
     pub fn clear(&self) -> Result<()> {
         let mut wtxn = self.env.write_txn()?;
         self.db.clear(&mut wtxn)?;
+        self.queries.clear(&mut wtxn)?;
         wtxn.commit()?;
         Ok(())
     }
@@ -437,6 +490,7 @@ impl PersistentEmbeddingCache {
 #[derive(Debug, Clone)]
 pub struct PersistentCacheStats {
     pub entries: usize,
+    pub query_entries: usize,
     pub file_size_bytes: u64,
     pub last_access: Option<DateTime<Utc>>,
 }
@@ -499,6 +553,104 @@ impl CacheStats {
     }
 }
 
+/// Persistent-cache hits vs ONNX computations while embedding chunks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CacheUsage {
+    /// Chunks whose embedding came from the persistent cache
+    pub hits: u64,
+    /// Chunks that went through ONNX inference
+    pub computed: u64,
+    /// Time spent on those computations
+    pub compute_ms: u64,
+}
+
+impl CacheUsage {
+    pub fn add(&mut self, other: &CacheUsage) {
+        self.hits += other.hits;
+        self.computed += other.computed;
+        self.compute_ms += other.compute_ms;
+    }
+
+    pub fn hit_rate(&self) -> f64 {
+        let total = self.hits + self.computed;
+        if total == 0 {
+            return 0.0;
+        }
+        self.hits as f64 / total as f64
+    }
+
+    /// Measured inference time per chunk, if anything was computed
+    pub fn ms_per_chunk(&self) -> Option<f64> {
+        (self.computed > 0).then(|| self.compute_ms as f64 / self.computed as f64)
+    }
+
+    /// Inference time the hits saved, at the given per-chunk cost
+    pub fn saved_ms(&self, ms_per_chunk: f64) -> u64 {
+        (self.hits as f64 * ms_per_chunk) as u64
+    }
+
+    /// "120 cache hits, 30 computed (80.0% hit rate), ~4s saved"
+    ///
+    /// The time saved uses this usage's own inference cost, else `fallback_ms_per_chunk`
+    /// (e.g. from earlier runs when every chunk was a hit).
+    pub fn summary(&self, fallback_ms_per_chunk: Option<f64>) -> String {
+        let mut summary = format!(
+            "{} cache hits, {} computed ({:.1}% hit rate)",
+            self.hits,
+            self.computed,
+            self.hit_rate() * 100.0
+        );
+        if let Some(ms_per_chunk) = self.ms_per_chunk().or(fallback_ms_per_chunk) {
+            if self.hits > 0 {
+                let secs = self.saved_ms(ms_per_chunk) / 1000;
+                let saved = if secs >= 60 {
+                    format!("{}m {:02}s", secs / 60, secs % 60)
+                } else {
+                    format!("{}s", secs)
+                };
+                summary.push_str(&format!(", ~{} saved", saved));
+            }
+        }
+        summary
+    }
+}
+
+/// Embedding cache usage of an index, kept across runs in [`CACHE_USAGE_FILE`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CacheUsageLog {
+    /// Most recent run that embedded anything
+    pub last_run: CacheUsage,
+    /// Sum over all recorded runs
+    pub total: CacheUsage,
+    pub runs: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+}
+
+impl CacheUsageLog {
+    pub fn load(db_path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(db_path.join(CACHE_USAGE_FILE)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Add a run's usage to the log in `db_path` (runs that embedded nothing are skipped)
+    pub fn record(db_path: &Path, run: &CacheUsage) -> Result<()> {
+        if run.hits + run.computed == 0 {
+            return Ok(());
+        }
+        let mut log = Self::load(db_path).unwrap_or_default();
+        log.last_run = *run;
+        log.total.add(run);
+        log.runs += 1;
+        log.updated_at = Some(Utc::now().to_rfc3339());
+        std::fs::write(
+            db_path.join(CACHE_USAGE_FILE),
+            serde_json::to_string_pretty(&log)?,
+        )?;
+        Ok(())
+    }
+}
+
 /// Cached batch embedder that uses an embedding cache with memory limits
 pub struct CachedBatchEmbedder {
     pub batch_embedder: super::batch::BatchEmbedder,
@@ -809,4 +961,60 @@ mod tests {
         let stats = cache.stats();
         assert!(stats.size < 10, "Cache should have evicted entries");
     }
+
+    #[test]
+    fn test_cache_usage_summary_and_log() {
+        let run = CacheUsage {
+            hits: 120,
+            computed: 30,
+            compute_ms: 1500,
+        };
+        assert_eq!(run.ms_per_chunk(), Some(50.0));
+        assert_eq!(
+            run.summary(None),
+            "120 cache hits, 30 computed (80.0% hit rate), ~6s saved"
+        );
+
+        // All hits: the time saved falls back to an earlier measured cost
+        let all_hits = CacheUsage {
+            hits: 10,
+            ..CacheUsage::default()
+        };
+        assert_eq!(
+            all_hits.summary(Some(100.0)),
+            "10 cache hits, 0 computed (100.0% hit rate), ~1s saved"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        CacheUsageLog::record(dir.path(), &run).unwrap();
+        CacheUsageLog::record(dir.path(), &CacheUsage::default()).unwrap();
+        CacheUsageLog::record(dir.path(), &all_hits).unwrap();
+        let log = CacheUsageLog::load(dir.path()).unwrap();
+        assert_eq!(log.runs, 2);
+        assert_eq!(log.last_run, all_hits);
+        assert_eq!(log.total.hits, 130);
+        assert_eq!(log.total.computed, 30);
+    }
+
+    #[test]
+    fn test_persistent_query_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = PersistentEmbeddingCache::open_at(dir.path().to_path_buf()).unwrap();
+
+        assert_eq!(cache.get_query("auth flow").unwrap(), None);
+        cache
+            .put_queries(&[("auth flow", &[0.1, 0.2]), ("parse json", &[0.3, 0.4])])
+            .unwrap();
+        assert_eq!(cache.get_query("auth flow").unwrap(), Some(vec![0.1, 0.2]));
+
+        // Queries live apart from chunk embeddings but are cleared with them
+        let stats = cache.stats().unwrap();
+        assert_eq!((stats.entries, stats.query_entries), (0, 2));
+        drop(cache);
+
+        let cache = PersistentEmbeddingCache::open_at(dir.path().to_path_buf()).unwrap();
+        assert_eq!(cache.get_query("parse json").unwrap(), Some(vec![0.3, 0.4]));
+        cache.clear().unwrap();
+        assert_eq!(cache.stats().unwrap().query_entries, 0);
+    }
 }
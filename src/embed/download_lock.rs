@@ -0,0 +1,136 @@
+//! Cross-process lock around model downloads
+//!
+//! Processes starting at the same time would all download a missing model into
+//! the shared models cache (`~/.codesearch/models`) and can leave it corrupted.
+//! A model that is already complete in the cache is loaded without locking;
+//! otherwise loading runs under an exclusive lock on `.download.lock` in the
+//! cache, so one process downloads while the others wait and then reuse it.
+
+use anyhow::{bail, Result};
+use fs2::FileExt;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::constants::{MODEL_DOWNLOAD_LOCK_FILE, MODEL_DOWNLOAD_LOCK_TIMEOUT_SECS};
+use crate::info_print;
+
+/// Tokenizer files fastembed loads next to every model
+const TOKENIZER_FILES: &[&str] = &[
+    "tokenizer.json",
+    "config.json",
+    "special_tokens_map.json",
+    "tokenizer_config.json",
+];
+
+/// Directory the Hugging Face cache actually uses (`HF_HOME` wins, as in fastembed)
+fn hub_cache_dir(cache_dir: &Path) -> PathBuf {
+    std::env::var("HF_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| cache_dir.to_path_buf())
+}
+
+/// Whether a model and its tokenizer files are completely in the cache
+///
+/// `model_code` is the Hugging Face repository (e.g. "Qdrant/all-MiniLM-L6-v2-onnx")
+/// and `files` its model files. Downloads are written to a temporary blob and
+/// only linked into the snapshot when complete, so present, non-empty snapshot
+/// files mean the model is usable.
+pub fn is_model_cached(cache_dir: &Path, model_code: &str, files: &[&str]) -> bool {
+    let repo_dir =
+        hub_cache_dir(cache_dir).join(format!("models--{}", model_code.replace('/', "--")));
+    let Ok(revision) = std::fs::read_to_string(repo_dir.join("refs").join("main")) else {
+        return false;
+    };
+    let snapshot = repo_dir.join("snapshots").join(revision.trim());
+    files
+        .iter()
+        .chain(TOKENIZER_FILES)
+        .all(|file| std::fs::metadata(snapshot.join(file)).is_ok_and(|m| m.len() > 0))
+}
+
+/// Run `load` while holding the models cache download lock
+///
+/// Waits up to `MODEL_DOWNLOAD_LOCK_TIMEOUT_SECS` for another process to finish
+/// its download. The lock is released when `load` returns.
+pub fn with_download_lock<T>(cache_dir: &Path, load: impl FnOnce() -> Result<T>) -> Result<T> {
+    std::fs::create_dir_all(cache_dir)?;
+    let lock_path = cache_dir.join(MODEL_DOWNLOAD_LOCK_FILE);
+    let file = File::options()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&lock_path)?;
+
+    let started = Instant::now();
+    let mut announced = false;
+    while file.try_lock_exclusive().is_err() {
+        if started.elapsed() >= Duration::from_secs(MODEL_DOWNLOAD_LOCK_TIMEOUT_SECS) {
+            bail!(
+                "Timed out after {}s waiting for another codesearch process to download a model \
+                 (lock: {})",
+                MODEL_DOWNLOAD_LOCK_TIMEOUT_SECS,
+                lock_path.display()
+            );
+        }
+        if !announced {
+            info_print!("⏳ Waiting for another codesearch process to download the model...");
+            announced = true;
+        }
+        std::thread::sleep(Duration::from_millis(250));
+    }
+
+    let result = load();
+    let _ = FileExt::unlock(&file);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_model_cache_check_and_download_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let code = "Org/model-onnx";
+        assert!(!is_model_cached(dir.path(), code, &["model.onnx"]));
+
+        let repo = dir.path().join("models--Org--model-onnx");
+        let snapshot = repo.join("snapshots").join("abc123");
+        std::fs::create_dir_all(&snapshot).unwrap();
+        std::fs::create_dir_all(repo.join("refs")).unwrap();
+        std::fs::write(repo.join("refs").join("main"), "abc123\n").unwrap();
+        for file in TOKENIZER_FILES.iter().chain(&["model.onnx"]) {
+            std::fs::write(snapshot.join(file), "{}").unwrap();
+        }
+        assert!(is_model_cached(dir.path(), code, &["model.onnx"]));
+        // An empty (interrupted) file is not a usable model
+        std::fs::write(snapshot.join("model.onnx"), "").unwrap();
+        assert!(!is_model_cached(dir.path(), code, &["model.onnx"]));
+
+        // A second loader waits until the first releases the lock
+        let in_first = Arc::new(AtomicBool::new(false));
+        let first = {
+            let cache = dir.path().to_path_buf();
+            let in_first = in_first.clone();
+            std::thread::spawn(move || {
+                with_download_lock(&cache, || {
+                    in_first.store(true, Ordering::SeqCst);
+                    std::thread::sleep(Duration::from_millis(300));
+                    in_first.store(false, Ordering::SeqCst);
+                    Ok(())
+                })
+            })
+        };
+        while !in_first.load(Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let overlapped =
+            with_download_lock(dir.path(), || Ok(in_first.load(Ordering::SeqCst))).unwrap();
+        first.join().unwrap().unwrap();
+        assert!(!overlapped);
+    }
+}
@@ -2,6 +2,8 @@ use anyhow::{anyhow, Result};
 use fastembed::{EmbeddingModel as FastEmbedModel, InitOptions, TextEmbedding};
 use ort::execution_providers::CPUExecutionProvider;
 
+use super::download_lock::{is_model_cached, with_download_lock};
+
 /// Available embedding models
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ModelType {
@@ -173,6 +175,45 @@ impl ModelType {
         ]
     }
 
+    /// Dimensions produced by the model with the given short name (or full name)
+    ///
+    /// The single place that maps recorded model names to dimensions; `None` for
+    /// unknown models, whose dimensions must come from the index itself.
+    pub fn dimensions_for(short_name: &str) -> Option<usize> {
+        Self::parse(short_name).map(|mt| mt.dimensions())
+    }
+
+    /// Default model for a given embedding dimensionality
+    ///
+    /// Used when an index's model name is unknown but its dimensions are known.
+    pub fn default_for_dimensions(dimensions: usize) -> Option<Self> {
+        match dimensions {
+            384 => Some(Self::default()),
+            768 => Some(Self::JinaEmbeddingsV2BaseCode),
+            1024 => Some(Self::MxbaiEmbedLargeV1),
+            _ => None,
+        }
+    }
+
+    /// Pick the model to query an index with, given its recorded model name and dimensions
+    ///
+    /// The recorded dimensions are authoritative (they describe the stored vectors):
+    /// if the named model is unknown or produces a different dimensionality, a model
+    /// matching the index dimensions is returned instead. Returns the model and whether
+    /// a correction was needed.
+    pub fn for_index(model_name: Option<&str>, dimensions: Option<usize>) -> (Self, bool) {
+        let named = model_name.and_then(Self::parse);
+        match (named, dimensions) {
+            (Some(mt), None) => (mt, false),
+            (Some(mt), Some(dims)) if mt.dimensions() == dims => (mt, false),
+            (_, Some(dims)) => match Self::default_for_dimensions(dims) {
+                Some(mt) => (mt, true),
+                None => (named.unwrap_or_default(), named.is_none()),
+            },
+            (None, None) => (Self::default(), model_name.is_some()),
+        }
+    }
+
     /// Parse model from string (for CLI)
     pub fn parse(s: &str) -> Option<Self> {
         match s.to_lowercase().as_str() {
@@ -234,12 +275,32 @@ impl FastEmbedder {
             .with_arena_allocator(true)
             .build();
 
-        let model = TextEmbedding::try_new(
-            InitOptions::new(model_type.to_fastembed_model())
-                .with_show_download_progress(false)
-                .with_execution_providers(vec![cpu_ep]),
-        )
-        .map_err(|e| anyhow!("Failed to initialize embedding model: {}", e))?;
+        let fastembed_model = model_type.to_fastembed_model();
+        let load = || {
+            TextEmbedding::try_new(
+                InitOptions::new(fastembed_model.clone())
+                    .with_show_download_progress(false)
+                    .with_execution_providers(vec![cpu_ep]),
+            )
+            .map_err(|e| anyhow!("Failed to initialize embedding model: {}", e))
+        };
+
+        // Reuse a complete cached model directly; otherwise download under the
+        // cache lock so concurrent processes don't write the same files
+        let model = match cache_dir {
+            Some(cache_dir) => {
+                let info = TextEmbedding::get_model_info(&fastembed_model)?;
+                let files: Vec<&str> = std::iter::once(info.model_file.as_str())
+                    .chain(info.additional_files.iter().map(String::as_str))
+                    .collect();
+                if is_model_cached(cache_dir, &info.model_code, &files) {
+                    load()?
+                } else {
+                    with_download_lock(cache_dir, load)?
+                }
+            }
+            None => load()?,
+        };
 
         Ok(Self { model, model_type })
     }
@@ -435,6 +496,58 @@ mod tests {
         assert!(!ModelType::JinaEmbeddingsV2BaseCode.is_quantized());
     }
 
+    #[test]
+    fn test_for_index_matching_model() {
+        assert_eq!(
+            ModelType::for_index(Some("bge-base"), Some(768)),
+            (ModelType::BGEBaseENV15, false)
+        );
+        assert_eq!(
+            ModelType::for_index(Some("minilm-l6-q"), None),
+            (ModelType::AllMiniLML6V2Q, false)
+        );
+    }
+
+    #[test]
+    fn test_for_index_corrects_dimension_mismatch() {
+        // Unknown model name with 768-dim vectors must not fall back to the 384-dim default
+        let (mt, corrected) = ModelType::for_index(Some("unknown-model"), Some(768));
+        assert_eq!(mt.dimensions(), 768);
+        assert!(corrected);
+
+        // Named model disagrees with recorded dimensions
+        let (mt, corrected) = ModelType::for_index(Some("minilm-l6-q"), Some(1024));
+        assert_eq!(mt.dimensions(), 1024);
+        assert!(corrected);
+
+        // Unsupported dimensions: keep the named model
+        let (mt, _) = ModelType::for_index(Some("bge-small"), Some(512));
+        assert_eq!(mt, ModelType::BGESmallENV15);
+    }
+
+    #[test]
+    fn test_dimensions_for() {
+        for mt in ModelType::all() {
+            assert_eq!(
+                ModelType::dimensions_for(mt.short_name()),
+                Some(mt.dimensions())
+            );
+        }
+        assert_eq!(ModelType::dimensions_for("BGEBaseENV15"), Some(768));
+        assert_eq!(ModelType::dimensions_for("mystery-model"), None);
+    }
+
+    #[test]
+    fn test_default_for_dimensions() {
+        for dims in [384, 768, 1024] {
+            assert_eq!(
+                ModelType::default_for_dimensions(dims).map(|m| m.dimensions()),
+                Some(dims)
+            );
+        }
+        assert_eq!(ModelType::default_for_dimensions(100), None);
+    }
+
     #[test]
     #[ignore] // Requires downloading model
     fn test_embedder_creation() {
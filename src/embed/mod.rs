@@ -1,24 +1,134 @@
 mod batch;
 mod cache;
+mod download_lock;
 mod embedder;
+mod pool;
 
-pub use batch::{BatchEmbedder, EmbeddedChunk};
+pub use batch::{embedding_text, BatchEmbedder, EmbeddedChunk};
 pub use cache::{
-    CacheStats, CachedBatchEmbedder, PersistentCacheStats, PersistentEmbeddingCache, QueryCache,
-    QueryCacheStats,
+    CacheStats, CacheUsage, CacheUsageLog, CachedBatchEmbedder, PersistentCacheStats,
+    PersistentEmbeddingCache, QueryCache, QueryCacheStats,
 };
 pub use embedder::{FastEmbedder, ModelType};
+pub use pool::{resolve_pool_size, EmbedderPool, EmbedderPoolStats};
 
 use anyhow::Result;
 use std::env;
 use std::sync::{Arc, Mutex};
 
+/// Load state of a lazily initialized embedding model
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelLoadState {
+    /// No load has been attempted yet
+    NotLoaded,
+    /// A load (including a possible model download) is in progress
+    Downloading { attempt: u32 },
+    /// The model is loaded and ready
+    Ready,
+    /// The last load failed; another attempt is allowed after `retry_at`
+    Failed {
+        message: String,
+        failures: u32,
+        retry_at: std::time::Instant,
+    },
+}
+
+impl ModelLoadState {
+    /// Machine-readable status string for status reporting
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ModelLoadState::NotLoaded => "not_loaded",
+            ModelLoadState::Downloading { .. } => "model_downloading",
+            ModelLoadState::Ready => "ready",
+            ModelLoadState::Failed { .. } => "model_failed",
+        }
+    }
+}
+
+/// Exponential backoff delay for the given (1-based) attempt, capped at `max`
+pub fn backoff_delay(
+    base: std::time::Duration,
+    attempt: u32,
+    max: std::time::Duration,
+) -> std::time::Duration {
+    let factor = 1u32 << attempt.saturating_sub(1).min(16);
+    base.saturating_mul(factor).min(max)
+}
+
+/// Run `f` up to `max_attempts` times, sleeping with exponential backoff between failures.
+///
+/// `f` receives the 1-based attempt number. Returns the last error if all attempts fail.
+pub fn retry_with_backoff<T>(
+    max_attempts: u32,
+    base_delay: std::time::Duration,
+    mut f: impl FnMut(u32) -> Result<T>,
+) -> Result<T> {
+    let max_attempts = max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        match f(attempt) {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < max_attempts => {
+                std::thread::sleep(retry_delay(base_delay, attempt, max_attempts, &e));
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// [`retry_with_backoff`] for async attempts: the backoff is awaited, so it
+/// doesn't block a runtime thread
+pub async fn retry_with_backoff_async<T, F, Fut>(
+    max_attempts: u32,
+    base_delay: std::time::Duration,
+    mut f: F,
+) -> Result<T>
+where
+    F: FnMut(u32) -> Fut,
+    Fut: std::future::Future<Output = Result<T>>,
+{
+    let max_attempts = max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        match f(attempt).await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < max_attempts => {
+                tokio::time::sleep(retry_delay(base_delay, attempt, max_attempts, &e)).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Log a failed attempt and return how long to wait before the next one
+fn retry_delay(
+    base_delay: std::time::Duration,
+    attempt: u32,
+    max_attempts: u32,
+    error: &anyhow::Error,
+) -> std::time::Duration {
+    let max_delay = std::time::Duration::from_millis(crate::constants::MODEL_LOAD_MAX_BACKOFF_MS);
+    let delay = backoff_delay(base_delay, attempt, max_delay);
+    tracing::warn!(
+        "⚠️  Attempt {}/{} failed: {} (retrying in {:?})",
+        attempt,
+        max_attempts,
+        error,
+        delay
+    );
+    delay
+}
+
 /// High-level embedding service that combines all features
 pub struct EmbeddingService {
     cached_embedder: CachedBatchEmbedder,
     model_type: ModelType,
     query_cache: QueryCache,
     persistent_cache: Option<PersistentEmbeddingCache>,
+    /// Persistent-cache hits vs computations since this service was created
+    cache_usage: CacheUsage,
 }
 
 impl EmbeddingService {
@@ -75,12 +185,61 @@ impl EmbeddingService {
             model_type,
             query_cache,
             persistent_cache,
+            cache_usage: CacheUsage::default(),
         })
     }
 
+    /// Create an embedding service, retrying with exponential backoff.
+    ///
+    /// Model downloads can fail on transient network errors; this retries
+    /// `MODEL_LOAD_MAX_ATTEMPTS` times before giving up. `on_attempt` is called
+    /// with the 1-based attempt number before each try.
+    pub fn with_cache_dir_retry(
+        model_type: ModelType,
+        cache_dir: Option<&std::path::Path>,
+        mut on_attempt: impl FnMut(u32),
+    ) -> Result<Self> {
+        retry_with_backoff(
+            crate::constants::MODEL_LOAD_MAX_ATTEMPTS,
+            std::time::Duration::from_millis(crate::constants::MODEL_LOAD_BASE_BACKOFF_MS),
+            |attempt| {
+                on_attempt(attempt);
+                Self::with_cache_dir(model_type, cache_dir)
+            },
+        )
+    }
+
+    /// [`with_cache_dir_retry`](Self::with_cache_dir_retry) for async callers
+    ///
+    /// Each attempt loads on a blocking thread and the backoff is awaited, so a
+    /// slow download doesn't hold up the runtime.
+    pub async fn with_cache_dir_retry_async(
+        model_type: ModelType,
+        cache_dir: std::path::PathBuf,
+        mut on_attempt: impl FnMut(u32),
+    ) -> Result<Self> {
+        retry_with_backoff_async(
+            crate::constants::MODEL_LOAD_MAX_ATTEMPTS,
+            std::time::Duration::from_millis(crate::constants::MODEL_LOAD_BASE_BACKOFF_MS),
+            |attempt| {
+                on_attempt(attempt);
+                let cache_dir = cache_dir.clone();
+                async move {
+                    tokio::task::spawn_blocking(move || {
+                        Self::with_cache_dir(model_type, Some(&cache_dir))
+                    })
+                    .await?
+                }
+            },
+        )
+        .await
+    }
+
     /// Embed a batch of chunks with caching.
     ///
-    /// When persistent cache is available, checks it first by content hash.
+    /// When persistent cache is available, checks it first by the hash of the
+    /// embedded text (see `embedding_text`), so a change in text layout or
+    /// file path never reuses a stale vector.
     /// Only chunks not found in the persistent cache go through ONNX inference.
     /// Newly computed embeddings are stored back in the persistent cache.
     pub fn embed_chunks(
@@ -94,16 +253,25 @@ impl EmbeddingService {
         let persistent_cache = self.persistent_cache.as_ref();
         if persistent_cache.is_none() {
             // No persistent cache — use in-memory only path
-            return self.cached_embedder.embed_chunks(chunks);
+            let start = std::time::Instant::now();
+            let computed = chunks.len() as u64;
+            let embedded = self.cached_embedder.embed_chunks(chunks)?;
+            self.cache_usage.computed += computed;
+            self.cache_usage.compute_ms += start.elapsed().as_millis() as u64;
+            return Ok(embedded);
         }
         let cache = persistent_cache.unwrap();
 
-        // Phase 1: Check persistent cache for each chunk by content hash
+        // Phase 1: Check persistent cache for each chunk by embedded-text hash
+        let keys: Vec<String> = chunks
+            .iter()
+            .map(|c| crate::chunker::Chunk::compute_hash(&embedding_text(c)))
+            .collect();
         let mut results: Vec<(usize, EmbeddedChunk)> = Vec::with_capacity(chunks.len());
         let mut misses: Vec<(usize, crate::chunker::Chunk)> = Vec::new();
 
         for (i, chunk) in chunks.iter().enumerate() {
-            match cache.get(&chunk.hash) {
+            match cache.get(&keys[i]) {
                 Ok(Some(embedding)) => {
                     results.push((i, EmbeddedChunk::new(chunk.clone(), embedding)));
                 }
@@ -115,17 +283,22 @@ impl EmbeddingService {
 
         let cache_hits = results.len();
         let cache_misses = misses.len();
+        self.cache_usage.hits += cache_hits as u64;
+        self.cache_usage.computed += cache_misses as u64;
 
         // Phase 2: Embed cache misses via the normal pipeline (ONNX inference)
         if !misses.is_empty() {
             let miss_chunks: Vec<crate::chunker::Chunk> =
                 misses.iter().map(|(_, c)| c.clone()).collect();
+            let start = std::time::Instant::now();
             let embedded = self.cached_embedder.embed_chunks(miss_chunks)?;
+            self.cache_usage.compute_ms += start.elapsed().as_millis() as u64;
 
             // Phase 3: Store newly computed embeddings in persistent cache
-            let entries: Vec<(&str, &[f32])> = embedded
+            let entries: Vec<(&str, &[f32])> = misses
                 .iter()
-                .map(|ec| (ec.chunk.hash.as_str(), ec.embedding.as_slice()))
+                .zip(&embedded)
+                .map(|((i, _), ec)| (keys[*i].as_str(), ec.embedding.as_slice()))
                 .collect();
             if let Err(e) = cache.put_batch(&entries) {
                 tracing::warn!("⚠️  Failed to write to persistent embedding cache: {}", e);
@@ -141,7 +314,7 @@ impl EmbeddingService {
             }
 
             // Merge with cache hits, preserving original order
-            for ((original_idx, _), embedded_chunk) in misses.iter().zip(embedded.into_iter()) {
+            for ((original_idx, _), embedded_chunk) in misses.iter().zip(embedded) {
                 results.push((*original_idx, embedded_chunk));
             }
         }
@@ -161,11 +334,18 @@ impl EmbeddingService {
     }
 
     /// Embed query text (with caching)
+    ///
+    /// Checks the in-memory query cache, then the persistent cache, and only
+    /// runs the model for queries neither has seen.
     pub fn embed_query(&mut self, query: &str) -> Result<Vec<f32>> {
         // Check query cache first
         if let Some(cached) = self.query_cache.get(query) {
             return Ok(cached);
         }
+        if let Some(cached) = self.persisted_query(query) {
+            self.query_cache.put(query, cached.clone());
+            return Ok(cached);
+        }
 
         // Cache miss - embed the query
         let embedder_arc = &self.cached_embedder.batch_embedder.embedder;
@@ -176,10 +356,32 @@ impl EmbeddingService {
 
         // Store in cache
         self.query_cache.put(query, embedding.clone());
+        self.persist_queries(&[(query, embedding.as_slice())]);
 
         Ok(embedding)
     }
 
+    /// Query embedding from the persistent cache, if any
+    fn persisted_query(&self, query: &str) -> Option<Vec<f32>> {
+        let cache = self.persistent_cache.as_ref()?;
+        match cache.get_query(query) {
+            Ok(embedding) => embedding,
+            Err(e) => {
+                tracing::debug!("Persistent query cache read failed: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Store query embeddings in the persistent cache (failures only logged)
+    fn persist_queries(&self, entries: &[(&str, &[f32])]) {
+        if let Some(cache) = &self.persistent_cache {
+            if let Err(e) = cache.put_queries(entries) {
+                tracing::warn!("⚠️  Failed to write to persistent query cache: {}", e);
+            }
+        }
+    }
+
     /// Batch embed multiple query texts with caching (single ONNX call for misses)
     pub fn embed_queries_batch(&mut self, queries: &[String]) -> Result<Vec<Vec<f32>>> {
         if queries.is_empty() {
@@ -195,6 +397,9 @@ impl EmbeddingService {
         for (idx, query) in queries.iter().enumerate() {
             if let Some(cached) = self.query_cache.get(query) {
                 results.push(cached);
+            } else if let Some(cached) = self.persisted_query(query) {
+                self.query_cache.put(query, cached.clone());
+                results.push(cached);
             } else {
                 queries_to_embed.push(query.clone());
                 cache_indices.push(idx);
@@ -211,6 +416,14 @@ impl EmbeddingService {
                 .map_err(|e| anyhow::anyhow!("Embedder mutex poisoned: {}", e))?;
 
             let new_embeddings = embedder.embed_batch(queries_to_embed)?;
+            drop(embedder);
+
+            let entries: Vec<(&str, &[f32])> = queries_for_caching
+                .iter()
+                .zip(&new_embeddings)
+                .map(|(q, e)| (q.as_str(), e.as_slice()))
+                .collect();
+            self.persist_queries(&entries);
 
             // Store in cache and add to results
             for (i, embedding) in new_embeddings.into_iter().enumerate() {
@@ -230,6 +443,21 @@ impl EmbeddingService {
         self.cached_embedder.dimensions()
     }
 
+    /// Verify this service produces embeddings matching an index's dimensions
+    pub fn check_index_dimensions(&self, index_dimensions: usize) -> Result<()> {
+        let model_dimensions = self.model_type.dimensions();
+        if model_dimensions != index_dimensions {
+            return Err(anyhow::anyhow!(
+                "Embedding dimension mismatch: model '{}' produces {} dimensions but the index has {}. \
+                 Re-index with 'codesearch index --force' or use the model the index was built with.",
+                self.model_type.short_name(),
+                model_dimensions,
+                index_dimensions
+            ));
+        }
+        Ok(())
+    }
+
     /// Get model information
     pub fn model_name(&self) -> &str {
         self.model_type.name()
@@ -266,6 +494,11 @@ impl EmbeddingService {
         Ok(())
     }
 
+    /// Persistent-cache hits vs ONNX computations of `embed_chunks` so far
+    pub fn cache_usage(&self) -> CacheUsage {
+        self.cache_usage
+    }
+
     #[allow(dead_code)]
     /// Get persistent cache statistics
     pub fn persistent_cache_stats(&self) -> Option<PersistentCacheStats> {
@@ -307,6 +540,70 @@ mod tests {
         assert_eq!(model.dimensions(), 384);
     }
 
+    #[test]
+    fn test_backoff_delay_doubles_and_caps() {
+        use std::time::Duration;
+        let base = Duration::from_millis(100);
+        let max = Duration::from_millis(1000);
+        assert_eq!(backoff_delay(base, 1, max), Duration::from_millis(100));
+        assert_eq!(backoff_delay(base, 2, max), Duration::from_millis(200));
+        assert_eq!(backoff_delay(base, 3, max), Duration::from_millis(400));
+        assert_eq!(backoff_delay(base, 10, max), max);
+        assert_eq!(backoff_delay(base, u32::MAX, max), max);
+    }
+
+    #[test]
+    fn test_retry_with_backoff_succeeds_after_failures() {
+        let mut calls = 0;
+        let result = retry_with_backoff(3, std::time::Duration::from_millis(1), |attempt| {
+            calls += 1;
+            if attempt < 3 {
+                anyhow::bail!("network blip");
+            }
+            Ok(attempt)
+        });
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(calls, 3);
+    }
+
+    #[test]
+    fn test_retry_with_backoff_gives_up() {
+        let mut calls = 0;
+        let result: Result<()> = retry_with_backoff(2, std::time::Duration::from_millis(1), |_| {
+            calls += 1;
+            anyhow::bail!("still down")
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 2);
+    }
+
+    #[tokio::test]
+    async fn test_retry_with_backoff_async() {
+        let mut calls = 0;
+        let result = retry_with_backoff_async(3, std::time::Duration::from_millis(1), |attempt| {
+            calls += 1;
+            async move {
+                if attempt < 2 {
+                    anyhow::bail!("network blip");
+                }
+                Ok(attempt)
+            }
+        })
+        .await;
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn test_model_load_state_strings() {
+        assert_eq!(ModelLoadState::NotLoaded.as_str(), "not_loaded");
+        assert_eq!(
+            ModelLoadState::Downloading { attempt: 1 }.as_str(),
+            "model_downloading"
+        );
+        assert_eq!(ModelLoadState::Ready.as_str(), "ready");
+    }
+
     #[test]
     #[ignore] // Requires model download
     fn test_embedding_service_creation() {
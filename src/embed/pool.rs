@@ -0,0 +1,349 @@
+//! Pool of embedding sessions for concurrent query embedding
+//!
+//! A single `EmbeddingService` wraps one ONNX session behind a mutex, so every
+//! query embedding in `codesearch serve` is serialized. The pool holds N
+//! independent instances and hands out whichever one is idle first: requests
+//! pull from a shared idle queue, so a slow request never pins later requests
+//! to a busy session. Per-slot counters feed the server's `/metrics` endpoint.
+
+use anyhow::{anyhow, Result};
+use std::collections::VecDeque;
+use std::ops::{Deref, DerefMut};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Instant;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use super::{EmbeddingService, ModelType};
+
+/// Resolve the embedder pool size.
+///
+/// Priority: explicit value (CLI) > `CODESEARCH_EMBEDDER_POOL_SIZE` > default.
+/// Always returns at least 1.
+pub fn resolve_pool_size(explicit: Option<usize>) -> usize {
+    explicit
+        .or_else(|| {
+            std::env::var("CODESEARCH_EMBEDDER_POOL_SIZE")
+                .ok()
+                .and_then(|s| s.parse::<usize>().ok())
+        })
+        .unwrap_or(crate::constants::DEFAULT_EMBEDDER_POOL_SIZE)
+        .max(1)
+}
+
+/// Per-slot usage counters
+struct SlotCounters {
+    requests: AtomicU64,
+    busy_micros: AtomicU64,
+    in_use: AtomicBool,
+}
+
+impl SlotCounters {
+    fn new() -> Self {
+        Self {
+            requests: AtomicU64::new(0),
+            busy_micros: AtomicU64::new(0),
+            in_use: AtomicBool::new(false),
+        }
+    }
+}
+
+/// Fixed-size pool of embedder instances
+///
+/// Generic over the pooled type so the scheduling logic can be tested without
+/// loading a model; in practice `T` is `EmbeddingService`.
+pub struct EmbedderPool<T = EmbeddingService> {
+    slots: Vec<Mutex<T>>,
+    counters: Vec<SlotCounters>,
+    idle: Mutex<VecDeque<usize>>,
+    permits: Semaphore,
+    waiting: AtomicU64,
+    created_at: Instant,
+}
+
+impl EmbedderPool<EmbeddingService> {
+    /// Load `size` embedding services for `model_type`.
+    ///
+    /// Each slot owns its own ONNX session, so memory usage grows linearly
+    /// with the pool size.
+    pub fn load(
+        model_type: ModelType,
+        cache_dir: Option<&std::path::Path>,
+        size: usize,
+    ) -> Result<Self> {
+        let size = size.max(1);
+        let mut instances = Vec::with_capacity(size);
+        for i in 0..size {
+            tracing::debug!("Loading embedder pool slot {}/{}", i + 1, size);
+            instances.push(EmbeddingService::with_cache_dir_retry(
+                model_type,
+                cache_dir,
+                |_| {},
+            )?);
+        }
+        Self::from_instances(instances)
+    }
+}
+
+impl<T> EmbedderPool<T> {
+    /// Build a pool from already constructed instances
+    pub fn from_instances(instances: Vec<T>) -> Result<Self> {
+        if instances.is_empty() {
+            return Err(anyhow!("Embedder pool requires at least one instance"));
+        }
+
+        let size = instances.len();
+        Ok(Self {
+            counters: (0..size).map(|_| SlotCounters::new()).collect(),
+            slots: instances.into_iter().map(Mutex::new).collect(),
+            idle: Mutex::new((0..size).collect()),
+            permits: Semaphore::new(size),
+            waiting: AtomicU64::new(0),
+            created_at: Instant::now(),
+        })
+    }
+
+    /// Number of instances in the pool
+    #[allow(dead_code)] // Part of public API for debugging/monitoring
+    pub fn size(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Wait for an idle instance and check it out.
+    ///
+    /// The returned guard must not be held across an `.await`; release it as
+    /// soon as the embedding call returns.
+    pub async fn acquire(&self) -> Result<PooledEmbedder<'_, T>> {
+        self.waiting.fetch_add(1, Ordering::Relaxed);
+        let permit = self.permits.acquire().await;
+        self.waiting.fetch_sub(1, Ordering::Relaxed);
+        let permit = permit.map_err(|e| anyhow!("Embedder pool closed: {}", e))?;
+        self.checkout(permit)
+    }
+
+    /// Check out an instance without waiting, if one is idle
+    #[allow(dead_code)] // Reserved for non-blocking callers
+    pub fn try_acquire(&self) -> Option<PooledEmbedder<'_, T>> {
+        let permit = self.permits.try_acquire().ok()?;
+        self.checkout(permit).ok()
+    }
+
+    fn checkout<'a>(&'a self, permit: SemaphorePermit<'a>) -> Result<PooledEmbedder<'a, T>> {
+        // Holding a permit guarantees at least one index is in the idle queue
+        let slot = self
+            .idle
+            .lock()
+            .map_err(|e| anyhow!("Mutex poisoned: {}", e))?
+            .pop_front()
+            .ok_or_else(|| anyhow!("Embedder pool idle queue is empty"))?;
+
+        let guard = self.slots[slot]
+            .lock()
+            .map_err(|e| anyhow!("Embedder mutex poisoned: {}", e))?;
+
+        let counters = &self.counters[slot];
+        counters.requests.fetch_add(1, Ordering::Relaxed);
+        counters.in_use.store(true, Ordering::Relaxed);
+
+        Ok(PooledEmbedder {
+            pool: self,
+            slot,
+            guard: Some(guard),
+            started: Instant::now(),
+            _permit: permit,
+        })
+    }
+
+    fn release(&self, slot: usize, busy_micros: u64) {
+        let counters = &self.counters[slot];
+        counters
+            .busy_micros
+            .fetch_add(busy_micros, Ordering::Relaxed);
+        counters.in_use.store(false, Ordering::Relaxed);
+
+        match self.idle.lock() {
+            Ok(mut idle) => idle.push_back(slot),
+            Err(e) => tracing::warn!("Embedder pool idle queue poisoned: {}", e),
+        }
+    }
+
+    /// Snapshot of pool usage for metrics reporting
+    pub fn stats(&self) -> EmbedderPoolStats {
+        let uptime_secs = self.created_at.elapsed().as_secs_f64();
+        let slots = self
+            .counters
+            .iter()
+            .enumerate()
+            .map(|(id, c)| {
+                let busy_secs = c.busy_micros.load(Ordering::Relaxed) as f64 / 1_000_000.0;
+                SlotStats {
+                    id,
+                    requests: c.requests.load(Ordering::Relaxed),
+                    busy_secs,
+                    in_use: c.in_use.load(Ordering::Relaxed),
+                    utilization: if uptime_secs > 0.0 {
+                        (busy_secs / uptime_secs).min(1.0)
+                    } else {
+                        0.0
+                    },
+                }
+            })
+            .collect();
+
+        EmbedderPoolStats {
+            size: self.slots.len(),
+            available: self.permits.available_permits(),
+            waiting: self.waiting.load(Ordering::Relaxed),
+            uptime_secs,
+            slots,
+        }
+    }
+}
+
+/// An instance checked out of an [`EmbedderPool`]
+///
+/// Returns the instance to the idle queue on drop.
+pub struct PooledEmbedder<'a, T> {
+    pool: &'a EmbedderPool<T>,
+    slot: usize,
+    guard: Option<MutexGuard<'a, T>>,
+    started: Instant,
+    // Released after `drop()` has pushed the slot back onto the idle queue
+    _permit: SemaphorePermit<'a>,
+}
+
+impl<T> PooledEmbedder<'_, T> {
+    /// Index of the pool slot this instance belongs to
+    #[allow(dead_code)] // Used in tests
+    pub fn slot(&self) -> usize {
+        self.slot
+    }
+}
+
+impl<T> Deref for PooledEmbedder<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        // The guard is only taken in drop()
+        self.guard.as_deref().unwrap_or_else(|| unreachable!())
+    }
+}
+
+impl<T> DerefMut for PooledEmbedder<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.guard.as_deref_mut().unwrap_or_else(|| unreachable!())
+    }
+}
+
+impl<T> Drop for PooledEmbedder<'_, T> {
+    fn drop(&mut self) {
+        // Unlock the slot before advertising it as idle
+        self.guard.take();
+        let busy_micros = self.started.elapsed().as_micros() as u64;
+        self.pool.release(self.slot, busy_micros);
+    }
+}
+
+/// Usage snapshot of an embedder pool
+#[derive(Debug, Clone)]
+pub struct EmbedderPoolStats {
+    pub size: usize,
+    /// Instances currently idle
+    pub available: usize,
+    /// Requests currently waiting for an instance
+    pub waiting: u64,
+    pub uptime_secs: f64,
+    pub slots: Vec<SlotStats>,
+}
+
+impl EmbedderPoolStats {
+    /// Mean utilization across all slots (0.0 to 1.0)
+    pub fn utilization(&self) -> f64 {
+        if self.slots.is_empty() {
+            return 0.0;
+        }
+        self.slots.iter().map(|s| s.utilization).sum::<f64>() / self.slots.len() as f64
+    }
+}
+
+/// Usage counters for a single pool slot
+#[derive(Debug, Clone)]
+pub struct SlotStats {
+    pub id: usize,
+    pub requests: u64,
+    pub busy_secs: f64,
+    pub in_use: bool,
+    /// Fraction of pool uptime this slot spent embedding (0.0 to 1.0)
+    pub utilization: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_pool_size_explicit_wins() {
+        assert_eq!(resolve_pool_size(Some(4)), 4);
+        assert_eq!(resolve_pool_size(Some(0)), 1);
+    }
+
+    #[test]
+    fn test_empty_pool_rejected() {
+        assert!(EmbedderPool::<u32>::from_instances(vec![]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_acquire_hands_out_distinct_slots() {
+        let pool = EmbedderPool::from_instances(vec![0u32, 0u32]).unwrap();
+
+        let a = pool.acquire().await.unwrap();
+        let b = pool.acquire().await.unwrap();
+        assert_ne!(a.slot(), b.slot());
+
+        // Pool exhausted while both are checked out
+        assert!(pool.try_acquire().is_none());
+        assert_eq!(pool.stats().available, 0);
+
+        drop(a);
+        assert!(pool.try_acquire().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_stats_track_requests_per_slot() {
+        let pool = EmbedderPool::from_instances(vec![0u32, 0u32]).unwrap();
+
+        for _ in 0..3 {
+            let mut e = pool.acquire().await.unwrap();
+            *e += 1;
+        }
+
+        let stats = pool.stats();
+        assert_eq!(stats.size, 2);
+        assert_eq!(stats.available, 2);
+        assert_eq!(stats.slots.iter().map(|s| s.requests).sum::<u64>(), 3);
+        assert!(stats.slots.iter().all(|s| !s.in_use));
+        assert!(stats.utilization() <= 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_waiter_gets_released_slot() {
+        let pool = std::sync::Arc::new(EmbedderPool::from_instances(vec![7u32]).unwrap());
+
+        let held = pool.acquire().await.unwrap();
+        let held_slot = held.slot();
+
+        let waiter_pool = pool.clone();
+        let waiter = tokio::spawn(async move {
+            let e = waiter_pool.acquire().await.unwrap();
+            (e.slot(), *e)
+        });
+
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        assert_eq!(pool.stats().waiting, 1);
+        drop(held);
+
+        let (slot, value) = waiter.await.unwrap();
+        assert_eq!(slot, held_slot);
+        assert_eq!(value, 7);
+    }
+}
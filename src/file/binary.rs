@@ -67,6 +67,11 @@ fn is_binary_by_content(path: &Path) -> bool {
 
     let data = &buffer[..bytes_read];
 
+    // UTF-16 text is full of NUL bytes but gets transcoded when read
+    if super::encoding::detect_utf16(data).is_some() {
+        return false;
+    }
+
     // Check 1: Null bytes are a strong indicator of binary content
     if data.contains(&0) {
         return true;
@@ -177,6 +182,23 @@ mod tests {
         assert!(is_binary_by_content(&invalid_path));
     }
 
+    #[test]
+    fn test_utf16_text_is_not_binary() {
+        let dir = TempDir::new().unwrap();
+        let file_path = dir.path().join("legacy.cpp");
+        let bytes: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain(
+                "#include <windows.h>\r\n"
+                    .encode_utf16()
+                    .flat_map(u16::to_le_bytes),
+            )
+            .collect();
+        fs::write(&file_path, bytes).unwrap();
+
+        assert!(!is_binary_by_content(&file_path));
+    }
+
     #[test]
     fn test_printable_or_whitespace() {
         assert!(is_printable_or_whitespace(b' ')); // space
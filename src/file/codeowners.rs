@@ -0,0 +1,320 @@
+//! CODEOWNERS: who owns which paths
+//!
+//! The file is parsed when a project is indexed and its rules are saved in the
+//! database (`codeowners.json`), so search results and the `owners` tool can
+//! name the people to consult about a file without re-reading the repository.
+//! Patterns follow GitHub/GitLab semantics: gitignore-style globs, and the last
+//! matching rule wins.
+
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::cache::normalize_path_str;
+use crate::constants::CODEOWNERS_FILE;
+
+/// Where CODEOWNERS may live, relative to the project root, in lookup order
+const LOCATIONS: [&str; 4] = [
+    ".github/CODEOWNERS",
+    "CODEOWNERS",
+    "docs/CODEOWNERS",
+    ".gitlab/CODEOWNERS",
+];
+
+/// One `pattern owner...` line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OwnerRule {
+    pub pattern: String,
+    /// Users (`@name`), teams (`@org/team`) or emails; empty = explicitly unowned
+    pub owners: Vec<String>,
+}
+
+/// Rules as saved in the database
+#[derive(Serialize, Deserialize)]
+struct SavedCodeOwners {
+    /// CODEOWNERS path relative to the project root
+    source: String,
+    /// Project root the patterns are relative to
+    root: String,
+    rules: Vec<OwnerRule>,
+}
+
+/// Parsed CODEOWNERS rules of a project
+#[derive(Debug, Clone)]
+pub struct CodeOwners {
+    /// CODEOWNERS path relative to the project root
+    pub source: String,
+    root: String,
+    rules: Vec<OwnerRule>,
+    matchers: Vec<GlobSet>,
+}
+
+impl CodeOwners {
+    /// Parse CODEOWNERS `content` for the project at `root`
+    ///
+    /// Blank lines, comments and GitLab `[Section]` headers are skipped.
+    pub fn parse(content: &str, root: &Path, source: &str) -> Self {
+        let rules = content
+            .lines()
+            .map(|line| line.split_once(" #").map_or(line, |(rule, _)| rule).trim())
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter(|line| !line.starts_with('[') && !line.starts_with("^["))
+            .filter_map(|line| {
+                let mut words = line.split_whitespace();
+                Some(OwnerRule {
+                    pattern: words.next()?.to_string(),
+                    owners: words.map(String::from).collect(),
+                })
+            })
+            .collect();
+        Self::from_rules(
+            source.to_string(),
+            normalize_path_str(&root.to_string_lossy()),
+            rules,
+        )
+    }
+
+    fn from_rules(source: String, root: String, rules: Vec<OwnerRule>) -> Self {
+        let matchers = rules
+            .iter()
+            .map(|rule| rule_matcher(&rule.pattern))
+            .collect();
+        Self {
+            source,
+            root: root.trim_end_matches('/').to_string(),
+            rules,
+            matchers,
+        }
+    }
+
+    /// Read the project's CODEOWNERS file, if it has one
+    pub fn discover(root: &Path) -> Option<Self> {
+        LOCATIONS.iter().find_map(|location| {
+            let content = std::fs::read_to_string(root.join(location)).ok()?;
+            Some(Self::parse(&content, root, location))
+        })
+    }
+
+    /// Parse the project's CODEOWNERS into the database, or remove stale rules
+    /// when the project no longer has one
+    pub fn save_for_project(root: &Path, db_path: &Path) -> Result<()> {
+        let path = db_path.join(CODEOWNERS_FILE);
+        let Some(owners) = Self::discover(root) else {
+            if path.exists() {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Cannot remove {}", path.display()))?;
+            }
+            return Ok(());
+        };
+        let saved = SavedCodeOwners {
+            source: owners.source,
+            root: owners.root,
+            rules: owners.rules,
+        };
+        std::fs::write(&path, serde_json::to_string_pretty(&saved)?)
+            .with_context(|| format!("Cannot write {}", path.display()))
+    }
+
+    /// The rules saved in a database; `Ok(None)` when the project had no CODEOWNERS
+    pub fn load(db_path: &Path) -> Result<Option<Self>> {
+        let path = db_path.join(CODEOWNERS_FILE);
+        let content = match std::fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Cannot read {}", path.display())),
+        };
+        let saved: SavedCodeOwners = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid {}", path.display()))?;
+        Ok(Some(Self::from_rules(
+            saved.source,
+            saved.root,
+            saved.rules,
+        )))
+    }
+
+    /// `path` relative to the project root; `None` for absolute paths outside it
+    pub fn relative_path(&self, path: &str) -> Option<String> {
+        let path = normalize_path_str(path);
+        let relative = match path.strip_prefix(&self.root) {
+            Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+            _ if Path::new(&path).is_absolute() => return None,
+            _ => &path,
+        };
+        Some(
+            relative
+                .trim_start_matches('/')
+                .trim_start_matches("./")
+                .to_string(),
+        )
+    }
+
+    /// The last rule matching `path` (absolute or relative to the project root)
+    pub fn rule_for(&self, path: &str) -> Option<&OwnerRule> {
+        let relative = self.relative_path(path)?;
+        self.matchers
+            .iter()
+            .rposition(|matcher| matcher.is_match(&relative))
+            .map(|i| &self.rules[i])
+    }
+
+    /// Owners of `path`; empty when no rule assigns any
+    pub fn owners_of(&self, path: &str) -> &[String] {
+        self.rule_for(path).map_or(&[], |rule| &rule.owners)
+    }
+
+    /// Owners of the files under directory `dir`, by number of files owned
+    ///
+    /// `files` are indexed file paths; those outside `dir` are ignored.
+    pub fn directory_owners<'a>(
+        &self,
+        dir: &str,
+        files: impl IntoIterator<Item = &'a String>,
+    ) -> DirectoryOwners {
+        let dir = self.relative_path(dir).unwrap_or_default();
+        let dir = dir.trim_end_matches('/');
+        let mut owners = DirectoryOwners::default();
+        for file in files {
+            let Some(relative) = self.relative_path(file) else {
+                continue;
+            };
+            if !dir.is_empty() && !relative.starts_with(&format!("{}/", dir)) {
+                continue;
+            }
+            let file_owners = self.owners_of(&relative);
+            if file_owners.is_empty() {
+                owners.unowned_files += 1;
+            }
+            for owner in file_owners {
+                match owners.by_owner.iter_mut().find(|(o, _)| o == owner) {
+                    Some((_, files)) => *files += 1,
+                    None => owners.by_owner.push((owner.clone(), 1)),
+                }
+            }
+        }
+        owners
+            .by_owner
+            .sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        owners
+    }
+}
+
+/// Ownership of a directory's files (see [`CodeOwners::directory_owners`])
+#[derive(Debug, Default, PartialEq)]
+pub struct DirectoryOwners {
+    /// Owners with the number of files they own, most first
+    pub by_owner: Vec<(String, usize)>,
+    /// Files no rule assigns owners to
+    pub unowned_files: usize,
+}
+
+/// Matcher for a CODEOWNERS pattern
+///
+/// A pattern without an inner `/` matches at any depth; one with a leading or
+/// inner `/` is anchored at the root. Every pattern also matches the contents
+/// of the directories it names. Invalid patterns match nothing.
+fn rule_matcher(pattern: &str) -> GlobSet {
+    let trimmed = pattern.trim_end_matches('/');
+    let anchored = trimmed.contains('/');
+    let base = trimmed.trim_start_matches('/');
+    let base = if anchored || base.starts_with("**") {
+        base.to_string()
+    } else {
+        format!("**/{}", base)
+    };
+    let mut globs = vec![format!("{}/**", base)];
+    if !pattern.ends_with('/') {
+        // A directory-only pattern doesn't match a file of that name
+        globs.push(base);
+    }
+
+    let mut builder = GlobSetBuilder::new();
+    for glob in globs {
+        match GlobBuilder::new(&glob).literal_separator(true).build() {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => tracing::debug!("Skipping CODEOWNERS pattern '{}': {}", pattern, e),
+        }
+    }
+    builder.build().unwrap_or_else(|_| GlobSet::empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_codeowners_rules() {
+        let content = "\
+# Default owners
+*       @org/core
+*.md    @docs-team   # prose
+/src/api/ @alice @org/api
+docs/legacy
+[Frontend]
+web/**/*.ts @bob
+";
+        let root = Path::new("/repo");
+        let owners = CodeOwners::parse(content, root, ".github/CODEOWNERS");
+
+        assert_eq!(owners.owners_of("/repo/src/main.rs"), ["@org/core"]);
+        assert_eq!(
+            owners.owners_of("src/api/handlers/user.rs"),
+            ["@alice", "@org/api"]
+        );
+        // Last match wins
+        assert_eq!(owners.owners_of("docs/guide.md"), ["@docs-team"]);
+        assert_eq!(
+            owners.owners_of("src/api/README.md"),
+            ["@alice", "@org/api"]
+        );
+        assert_eq!(owners.owners_of("web/app/main.ts"), ["@bob"]);
+        // Anchored: `src/api/` elsewhere isn't matched
+        assert_eq!(owners.owners_of("lib/src/api/x.rs"), ["@org/core"]);
+        // A rule without owners unsets them
+        assert!(owners.owners_of("docs/legacy/old.txt").is_empty());
+        assert_eq!(
+            owners.rule_for("docs/legacy/old.txt").unwrap().pattern,
+            "docs/legacy"
+        );
+        // Absolute paths of another project have no owners here
+        assert!(owners.rule_for("/elsewhere/src/main.rs").is_none());
+
+        let files = [
+            "/repo/src/api/a.rs",
+            "/repo/src/api/b.md",
+            "/repo/src/main.rs",
+            "/repo/docs/legacy/x",
+        ]
+        .map(String::from);
+        assert_eq!(
+            owners.directory_owners("src/api", &files),
+            DirectoryOwners {
+                by_owner: vec![("@alice".to_string(), 2), ("@org/api".to_string(), 2)],
+                unowned_files: 0,
+            }
+        );
+        assert_eq!(owners.directory_owners("/repo", &files).unowned_files, 1);
+    }
+
+    #[test]
+    fn test_codeowners_saved_and_removed() -> Result<()> {
+        let project = tempfile::tempdir()?;
+        let db_path = project.path().join(".codesearch.db");
+        std::fs::create_dir_all(project.path().join(".github"))?;
+        std::fs::create_dir_all(&db_path)?;
+        std::fs::write(project.path().join(".github/CODEOWNERS"), "/src/ @alice\n")?;
+
+        CodeOwners::save_for_project(project.path(), &db_path)?;
+        let owners = CodeOwners::load(&db_path)?.unwrap();
+        assert_eq!(owners.source, ".github/CODEOWNERS");
+        let file = project.path().join("src/lib.rs");
+        assert_eq!(owners.owners_of(&file.to_string_lossy()), ["@alice"]);
+
+        std::fs::remove_file(project.path().join(".github/CODEOWNERS"))?;
+        CodeOwners::save_for_project(project.path(), &db_path)?;
+        assert!(CodeOwners::load(&db_path)?.is_none());
+        Ok(())
+    }
+}
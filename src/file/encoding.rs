@@ -0,0 +1,150 @@
+//! Text decoding for source files that aren't UTF-8
+//!
+//! Older C++ and Windows codebases often contain UTF-16, Latin-1/Windows-1252
+//! or Shift-JIS files. Rather than dropping them, they are transcoded to UTF-8:
+//! UTF-16 is detected from its BOM or from the NUL-byte pattern of mostly-ASCII
+//! text, and the encoding of anything else that isn't valid UTF-8 is guessed
+//! by `chardetng` and decoded with `encoding_rs`.
+//!
+//! Decoded text is also normalized (BOM stripped, CRLF → LF) so the same file
+//! checked out with different `core.autocrlf` settings produces identical
+//! chunks, hashes and embedding cache keys on every machine.
+
+use chardetng::EncodingDetector;
+use encoding_rs::{UTF_16BE, UTF_16LE};
+use std::path::Path;
+
+/// Byte order of a UTF-16 file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Utf16Order {
+    LittleEndian,
+    BigEndian,
+}
+
+/// Detect UTF-16 text from its BOM, or from the NUL bytes that ASCII
+/// characters leave in every other position
+pub fn detect_utf16(data: &[u8]) -> Option<Utf16Order> {
+    if data.len() < 2 || !data.len().is_multiple_of(2) {
+        return None;
+    }
+    match data {
+        [0xFF, 0xFE, ..] => return Some(Utf16Order::LittleEndian),
+        [0xFE, 0xFF, ..] => return Some(Utf16Order::BigEndian),
+        _ => {}
+    }
+
+    let pairs = data.len() / 2;
+    let (mut even_nuls, mut odd_nuls) = (0usize, 0usize);
+    for pair in data.chunks_exact(2) {
+        even_nuls += (pair[0] == 0) as usize;
+        odd_nuls += (pair[1] == 0) as usize;
+    }
+    // Require most code units to be ASCII and the other half of each pair to
+    // be (almost) never NUL, so binary files with scattered NULs don't match
+    let threshold = pairs * 7 / 10;
+    if odd_nuls >= threshold && odd_nuls > 0 && even_nuls * 20 <= pairs {
+        Some(Utf16Order::LittleEndian)
+    } else if even_nuls >= threshold && even_nuls > 0 && odd_nuls * 20 <= pairs {
+        Some(Utf16Order::BigEndian)
+    } else {
+        None
+    }
+}
+
+/// Decode file bytes to a String: UTF-8 as-is, UTF-16 transcoded, anything
+/// else from the encoding `chardetng` detects
+pub fn decode_source(bytes: Vec<u8>) -> String {
+    let encoding = match detect_utf16(&bytes) {
+        Some(Utf16Order::LittleEndian) => UTF_16LE,
+        Some(Utf16Order::BigEndian) => UTF_16BE,
+        None => match String::from_utf8(bytes) {
+            Ok(text) => return text,
+            Err(e) => return decode_detected(e.as_bytes()),
+        },
+    };
+    // `decode` strips the BOM (and follows it if it disagrees)
+    encoding.decode(&bytes).0.into_owned()
+}
+
+/// Strip a leading BOM and convert CRLF line endings to LF
+pub fn normalize_text(text: String) -> String {
+    let text = match text.strip_prefix('\u{FEFF}') {
+        Some(stripped) => stripped.to_string(),
+        None => text,
+    };
+    if text.contains("\r\n") {
+        text.replace("\r\n", "\n")
+    } else {
+        text
+    }
+}
+
+/// Read a source file, transcoding non-UTF-8 content (see [`decode_source`])
+/// and normalizing BOM/line endings (see [`normalize_text`])
+pub fn read_source(path: &Path) -> std::io::Result<String> {
+    std::fs::read(path).map(|bytes| normalize_text(decode_source(bytes)))
+}
+
+/// Decode bytes in the legacy encoding `chardetng` guesses for them
+fn decode_detected(bytes: &[u8]) -> String {
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    encoding.decode(bytes).0.into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_utf16_with_and_without_bom() {
+        let le: Vec<u8> = [0xFF, 0xFE]
+            .into_iter()
+            .chain(
+                "int main() {}\r\n"
+                    .encode_utf16()
+                    .flat_map(u16::to_le_bytes),
+            )
+            .collect();
+        assert_eq!(decode_source(le), "int main() {}\r\n");
+
+        let be: Vec<u8> = "void f(); // café"
+            .encode_utf16()
+            .flat_map(u16::to_be_bytes)
+            .collect();
+        assert_eq!(detect_utf16(&be), Some(Utf16Order::BigEndian));
+        assert_eq!(decode_source(be), "void f(); // café");
+    }
+
+    #[test]
+    fn test_decode_latin1_and_windows_1252() {
+        // "// Größe – “quoted”" in Windows-1252
+        let bytes = b"// Gr\xF6\xDFe \x96 \x93quoted\x94".to_vec();
+        assert_eq!(decode_source(bytes), "// Größe – “quoted”");
+        assert_eq!(decode_source("déjà vu".as_bytes().to_vec()), "déjà vu");
+    }
+
+    #[test]
+    fn test_decode_shift_jis() {
+        let text = "// 注文を作成する\nfn create_order() {}\n";
+        let (bytes, _, unmappable) = encoding_rs::SHIFT_JIS.encode(text);
+        assert!(!unmappable);
+        assert_eq!(decode_source(bytes.into_owned()), text);
+    }
+
+    #[test]
+    fn test_normalize_text_strips_bom_and_crlf() {
+        assert_eq!(
+            normalize_text("\u{FEFF}fn main() {\r\n}\r\n".to_string()),
+            "fn main() {\n}\n"
+        );
+        assert_eq!(normalize_text("a\nb".to_string()), "a\nb");
+    }
+
+    #[test]
+    fn test_binary_data_is_not_utf16() {
+        assert_eq!(detect_utf16(&[0x00, 0x01, 0x02, 0x03, 0xFF, 0x00]), None);
+        assert_eq!(detect_utf16(&[0xFF, 0xFE, 0xFD]), None);
+    }
+}
@@ -1,4 +1,9 @@
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::constants::{CONFIG_DIR_NAME, LANGUAGES_CONFIG_FILE};
 
 /// Supported programming languages
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -22,9 +27,13 @@ pub enum Language {
     Yaml,
     Toml,
     Sql,
+    Protobuf,
+    GraphQL,
     Html,
     Css,
     Xml,
+    /// Indexed as plain text (custom extensions from the language registry)
+    PlainText,
     Unknown,
 }
 
@@ -57,7 +66,28 @@ impl Language {
     }
 
     /// Detect language from extension string
+    ///
+    /// Custom mappings from the language registry take precedence over the
+    /// built-in table.
     pub fn from_extension(ext: &str) -> Self {
+        if let Some(language) = LanguageRegistry::global().resolve(ext) {
+            return language;
+        }
+        Self::from_builtin_extension(ext)
+    }
+
+    /// Look up a language by name ("html", "C#") or by one of its extensions ("rs")
+    pub fn from_name(name: &str) -> Option<Self> {
+        let lower = name.to_lowercase();
+        ALL_LANGUAGES
+            .iter()
+            .copied()
+            .find(|l| l.name().to_lowercase() == lower)
+            .or_else(|| Some(Self::from_builtin_extension(&lower)).filter(|l| *l != Self::Unknown))
+    }
+
+    /// Built-in extension table
+    fn from_builtin_extension(ext: &str) -> Self {
         match ext.to_lowercase().as_str() {
             "rs" => Self::Rust,
             "py" | "pyw" | "pyi" => Self::Python,
@@ -79,6 +109,8 @@ impl Language {
             "yaml" | "yml" => Self::Yaml,
             "toml" => Self::Toml,
             "sql" => Self::Sql,
+            "proto" => Self::Protobuf,
+            "graphql" | "graphqls" | "gql" => Self::GraphQL,
             "html" | "htm" => Self::Html,
             "css" | "scss" | "sass" | "less" => Self::Css,
             "xml" | "csproj" | "props" | "targets" | "resx" | "config" => Self::Xml,
@@ -130,14 +162,118 @@ impl Language {
             Self::Yaml => "YAML",
             Self::Toml => "TOML",
             Self::Sql => "SQL",
+            Self::Protobuf => "Protobuf",
+            Self::GraphQL => "GraphQL",
             Self::Html => "HTML",
             Self::Css => "CSS",
             Self::Xml => "XML",
+            Self::PlainText => "Text",
             Self::Unknown => "Unknown",
         }
     }
 }
 
+/// Every known language, for name lookups
+const ALL_LANGUAGES: &[Language] = &[
+    Language::Rust,
+    Language::Python,
+    Language::JavaScript,
+    Language::TypeScript,
+    Language::Go,
+    Language::Java,
+    Language::C,
+    Language::Cpp,
+    Language::CSharp,
+    Language::Ruby,
+    Language::Php,
+    Language::Swift,
+    Language::Kotlin,
+    Language::Shell,
+    Language::Markdown,
+    Language::Json,
+    Language::Yaml,
+    Language::Toml,
+    Language::Sql,
+    Language::Protobuf,
+    Language::GraphQL,
+    Language::Html,
+    Language::Css,
+    Language::Xml,
+    Language::PlainText,
+];
+
+/// User-defined extension mappings
+///
+/// Loaded once from `~/.codesearch/languages.json` (or the file named by
+/// `CODESEARCH_LANGUAGES_CONFIG`):
+///
+/// ```json
+/// {
+///   "extensions": { "cshtml": "html", "tf": "hcl" },
+///   "plaintext": ["tfvars", "proto3"]
+/// }
+/// ```
+///
+/// Mapping to a language name this build doesn't know (like `hcl`) indexes
+/// the files as plain text.
+#[derive(Debug, Default, Deserialize)]
+pub struct LanguageRegistry {
+    /// Extension (without dot) → language name
+    #[serde(default)]
+    extensions: HashMap<String, String>,
+    /// Extra extensions indexed as plain text
+    #[serde(default)]
+    plaintext: Vec<String>,
+}
+
+impl LanguageRegistry {
+    /// Registry loaded from the user's config (empty if there is none)
+    pub fn global() -> &'static Self {
+        static REGISTRY: OnceLock<LanguageRegistry> = OnceLock::new();
+        REGISTRY.get_or_init(|| {
+            let path = match std::env::var("CODESEARCH_LANGUAGES_CONFIG") {
+                Ok(path) => std::path::PathBuf::from(path),
+                Err(_) => match dirs::home_dir() {
+                    Some(home) => home.join(CONFIG_DIR_NAME).join(LANGUAGES_CONFIG_FILE),
+                    None => return Self::default(),
+                },
+            };
+            Self::load(&path).unwrap_or_else(|e| {
+                tracing::warn!("⚠️  Ignoring language config {}: {}", path.display(), e);
+                Self::default()
+            })
+        })
+    }
+
+    /// Load a registry from a JSON file; a missing file is an empty registry
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path)?;
+        let mut registry: Self = serde_json::from_str(&content)?;
+        // Normalize keys: case-insensitive, leading dot optional
+        registry.extensions = registry
+            .extensions
+            .into_iter()
+            .map(|(ext, lang)| (ext.trim_start_matches('.').to_lowercase(), lang))
+            .collect();
+        for ext in &mut registry.plaintext {
+            *ext = ext.trim_start_matches('.').to_lowercase();
+        }
+        Ok(registry)
+    }
+
+    /// Language for a custom extension, if the registry maps it
+    pub fn resolve(&self, ext: &str) -> Option<Language> {
+        let ext = ext.to_lowercase();
+        if let Some(name) = self.extensions.get(&ext) {
+            return Some(Language::from_name(name).unwrap_or(Language::PlainText));
+        }
+        self.plaintext.contains(&ext).then_some(Language::PlainText)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -180,4 +316,26 @@ mod tests {
         assert!(Language::Markdown.is_indexable());
         assert!(!Language::Unknown.is_indexable());
     }
+
+    #[test]
+    fn test_language_registry_custom_extensions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("languages.json");
+        std::fs::write(
+            &path,
+            r#"{"extensions": {".CSHTML": "html", "tf": "hcl", "h": "cpp"}, "plaintext": ["tfvars"]}"#,
+        )
+        .unwrap();
+        let registry = LanguageRegistry::load(&path).unwrap();
+
+        assert_eq!(registry.resolve("cshtml"), Some(Language::Html));
+        assert_eq!(registry.resolve("tf"), Some(Language::PlainText));
+        assert_eq!(registry.resolve("h"), Some(Language::Cpp));
+        assert_eq!(registry.resolve("TFVARS"), Some(Language::PlainText));
+        assert_eq!(registry.resolve("rs"), None);
+        assert!(Language::PlainText.is_indexable());
+        assert_eq!(Language::from_name("C#"), Some(Language::CSharp));
+        assert_eq!(Language::from_name("py"), Some(Language::Python));
+        assert_eq!(Language::from_name("hcl"), None);
+    }
 }
@@ -1,16 +1,25 @@
 use anyhow::Result;
 use ignore::WalkBuilder;
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
 use tracing::{debug, info, warn};
 
 use crate::constants::{ALWAYS_EXCLUDED, ALWAYS_SKIP_EXTENSIONS, ALWAYS_SKIP_FILENAME_SUFFIXES};
 
 mod binary;
+mod codeowners;
+mod encoding;
 mod language;
+mod provenance;
+mod recency;
 
 pub use binary::is_binary_file;
+pub use codeowners::CodeOwners;
+pub use encoding::read_source;
 pub use language::Language;
+pub use provenance::{is_vendored_dir, is_vendored_path, spdx_license};
+pub use recency::{order_by_recency, uncommitted_files};
 
 /// Information about a discovered file
 #[derive(Debug, Clone)]
@@ -20,6 +29,24 @@ pub struct FileInfo {
     pub size: u64,
 }
 
+/// Why the walk leaves a file or directory out of the index
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SkipReason {
+    /// 0-byte file
+    Empty,
+    /// Generated, minified or scratch file (`.min.js`, `.map`, `.tmp`, ...)
+    Generated,
+    Binary,
+    /// No parser or text support for the file type
+    UnsupportedLanguage,
+    /// Outside the languages the index is restricted to (`index --lang`)
+    LanguageFilter,
+    /// Directory that is never indexed (`node_modules`, `.git`, ...)
+    ExcludedDir,
+    /// Directory pruned to keep the index under its size budget
+    PrunedDir,
+}
+
 /// Statistics about walked files
 #[derive(Debug, Default, Clone)]
 #[allow(dead_code)] // skipped_ignored reserved for future ignore stats
@@ -74,6 +101,10 @@ pub struct FileWalker {
     root: PathBuf,
     respect_gitignore: bool,
     include_hidden: bool,
+    /// Only these languages are indexed (`None` = all)
+    languages: Option<Vec<Language>>,
+    /// Directories (relative to `root`) that are not indexed
+    excluded_dirs: Vec<String>,
 }
 
 impl FileWalker {
@@ -82,16 +113,122 @@ impl FileWalker {
             root: root.into(),
             respect_gitignore: true,
             include_hidden: false,
+            languages: None,
+            excluded_dirs: Vec::new(),
         }
     }
 
+    /// Restrict the walk to the given languages (`None` keeps every language)
+    pub fn with_languages(mut self, languages: Option<Vec<Language>>) -> Self {
+        self.languages = languages;
+        self
+    }
+
+    /// Leave out these directories, given relative to the root (`docs`, `web/tests`)
+    pub fn with_excluded_dirs(mut self, dirs: Vec<String>) -> Self {
+        self.excluded_dirs = dirs;
+        self
+    }
+
     /// Walk files, returning detailed file information
     pub fn walk(&self) -> Result<(Vec<FileInfo>, WalkStats)> {
+        let (files, stats) = self.collect()?;
+        stats.print_summary();
+        Ok((files, stats))
+    }
+
+    /// Walk files, returning just the paths (no summary logging, for periodic rescans)
+    pub fn walk_paths(&self) -> Result<Vec<PathBuf>> {
+        let (files, _) = self.collect()?;
+        Ok(files.into_iter().map(|f| f.path).collect())
+    }
+
+    /// Walk files, calling `on_skip` with each file or directory left out and why
+    ///
+    /// Files hidden or ignored by `.gitignore`/`.codesearchignore` are not
+    /// visited, so they are not reported.
+    pub fn walk_with_skips(
+        &self,
+        on_skip: impl FnMut(&Path, SkipReason),
+    ) -> Result<(Vec<FileInfo>, WalkStats)> {
+        self.visit(on_skip)
+    }
+
+    fn collect(&self) -> Result<(Vec<FileInfo>, WalkStats)> {
+        self.visit(|_, _| {})
+    }
+
+    /// Why a file is not indexed, or its language if it is
+    fn classify(&self, path: &Path, size: u64) -> std::result::Result<Language, SkipReason> {
+        // Skip 0-byte files — nothing to index
+        if size == 0 {
+            debug!("Skipping empty file: {}", path.display());
+            return Err(SkipReason::Empty);
+        }
+
+        // Skip always-excluded file extensions (e.g. .tmp, .map, .lock, .min.js)
+        if let Some(fname) = path.file_name().and_then(|n| n.to_str()) {
+            let fname_lower = fname.to_ascii_lowercase();
+            // Check compound suffix patterns first (.min.js, .d.ts, etc.)
+            if ALWAYS_SKIP_FILENAME_SUFFIXES
+                .iter()
+                .any(|s| fname_lower.ends_with(s))
+            {
+                debug!("Skipping generated/minified file: {}", path.display());
+                return Err(SkipReason::Generated);
+            }
+            // Check single extensions (.tmp, .map, .lock, etc.)
+            if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
+                if ALWAYS_SKIP_EXTENSIONS
+                    .iter()
+                    .any(|s| s.eq_ignore_ascii_case(ext))
+                {
+                    debug!("Skipping excluded extension .{}: {}", ext, path.display());
+                    return Err(SkipReason::Generated);
+                }
+            }
+        }
+
+        // Check if file is binary
+        if is_binary_file(path) {
+            debug!("Skipping binary file: {}", path.display());
+            return Err(SkipReason::Binary);
+        }
+
+        // Skip unknown/non-indexable files
+        let language = Language::from_path(path);
+        if !language.is_indexable() {
+            return Err(SkipReason::UnsupportedLanguage);
+        }
+
+        // Skip languages outside the configured scope (`index --lang`)
+        if let Some(ref languages) = self.languages {
+            if !languages.contains(&language) {
+                debug!("Skipping {} file: {}", language.name(), path.display());
+                return Err(SkipReason::LanguageFilter);
+            }
+        }
+        Ok(language)
+    }
+
+    fn visit(
+        &self,
+        mut on_skip: impl FnMut(&Path, SkipReason),
+    ) -> Result<(Vec<FileInfo>, WalkStats)> {
         let mut files = Vec::new();
         let mut stats = WalkStats::new();
+        // Directories left out while walking, reported once the walk is done
+        let skipped_dirs: Arc<Mutex<Vec<(PathBuf, SkipReason)>>> = Arc::default();
+        let skipped = Arc::clone(&skipped_dirs);
 
         debug!("Starting file walk in: {}", self.root.display());
 
+        let excluded_dirs: Vec<PathBuf> = self
+            .excluded_dirs
+            .iter()
+            .map(|dir| self.root.join(dir))
+            .collect();
+
         let mut builder = WalkBuilder::new(&self.root);
         builder
             .git_ignore(self.respect_gitignore)
@@ -101,20 +238,30 @@ impl FileWalker {
             .add_custom_ignore_filename(".codesearchignore")
             .add_custom_ignore_filename(".osgrepignore") // Compatibility with osgrep
             // Filter out excluded directories BEFORE descending into them
-            .filter_entry(|entry| {
+            .filter_entry(move |entry| {
                 // Always allow the root entry
                 if entry.depth() == 0 {
                     return true;
                 }
 
                 // Check if this entry's name is in the excluded list
-                if let Some(name) = entry.file_name().to_str() {
-                    if ALWAYS_EXCLUDED.contains(&name) {
-                        debug!("Excluding directory: {}", entry.path().display());
-                        return false;
-                    }
+                let reason = if entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| ALWAYS_EXCLUDED.contains(&name))
+                {
+                    debug!("Excluding directory: {}", entry.path().display());
+                    SkipReason::ExcludedDir
+                } else if excluded_dirs.iter().any(|dir| entry.path() == dir) {
+                    debug!("Excluding pruned directory: {}", entry.path().display());
+                    SkipReason::PrunedDir
+                } else {
+                    return true;
+                };
+                if let Ok(mut skipped) = skipped.lock() {
+                    skipped.push((entry.path().to_path_buf(), reason));
                 }
-                true
+                false
             });
 
         for result in builder.build() {
@@ -130,54 +277,17 @@ impl FileWalker {
 
                     let path = entry.path();
 
-                    // Skip 0-byte files — nothing to index
                     let size = entry.metadata().ok().map(|m| m.len()).unwrap_or(0);
-                    if size == 0 {
-                        stats.add_skipped_binary();
-                        debug!("Skipping empty file: {}", path.display());
-                        continue;
-                    }
-
-                    // Skip always-excluded file extensions (e.g. .tmp, .map, .lock, .min.js)
-                    if let Some(fname) = path.file_name().and_then(|n| n.to_str()) {
-                        let fname_lower = fname.to_ascii_lowercase();
-                        // Check compound suffix patterns first (.min.js, .d.ts, etc.)
-                        if ALWAYS_SKIP_FILENAME_SUFFIXES
-                            .iter()
-                            .any(|s| fname_lower.ends_with(s))
-                        {
-                            stats.add_skipped_binary();
-                            debug!("Skipping generated/minified file: {}", path.display());
-                            continue;
-                        }
-                        // Check single extensions (.tmp, .map, .lock, etc.)
-                        if let Some(ext) = path.extension().and_then(|e| e.to_str()) {
-                            if ALWAYS_SKIP_EXTENSIONS
-                                .iter()
-                                .any(|s| s.eq_ignore_ascii_case(ext))
-                            {
+                    let language = match self.classify(path, size) {
+                        Ok(language) => language,
+                        Err(reason) => {
+                            if reason != SkipReason::LanguageFilter {
                                 stats.add_skipped_binary();
-                                debug!("Skipping excluded extension .{}: {}", ext, path.display());
-                                continue;
                             }
+                            on_skip(path, reason);
+                            continue;
                         }
-                    }
-
-                    // Check if file is binary
-                    if is_binary_file(path) {
-                        stats.add_skipped_binary();
-                        debug!("Skipping binary file: {}", path.display());
-                        continue;
-                    }
-
-                    // Get file info
-                    let language = Language::from_path(path);
-
-                    // Skip unknown/non-indexable files
-                    if !language.is_indexable() {
-                        stats.add_skipped_binary();
-                        continue;
-                    }
+                    };
 
                     let file_info = FileInfo {
                         path: path.to_path_buf(),
@@ -194,17 +304,13 @@ impl FileWalker {
             }
         }
 
-        stats.print_summary();
-
+        let skipped_dirs =
+            std::mem::take(&mut *skipped_dirs.lock().unwrap_or_else(|e| e.into_inner()));
+        for (dir, reason) in skipped_dirs {
+            on_skip(&dir, reason);
+        }
         Ok((files, stats))
     }
-
-    /// Walk files, returning just the paths (simpler API)
-    #[allow(dead_code)] // Convenience method for simpler use cases
-    pub fn walk_paths(&self) -> Result<Vec<PathBuf>> {
-        let (files, _) = self.walk()?;
-        Ok(files.into_iter().map(|f| f.path).collect())
-    }
 }
 
 #[cfg(test)]
@@ -284,4 +390,38 @@ mod tests {
         assert_eq!(files.len(), 1);
         assert_eq!(files[0].path.file_name().unwrap(), "index.js");
     }
+
+    #[test]
+    fn test_language_filter() {
+        let dir = TempDir::new().unwrap();
+        fs::write(dir.path().join("main.rs"), "fn main() {}").unwrap();
+        fs::write(dir.path().join("app.ts"), "const x = 1;").unwrap();
+        fs::write(dir.path().join("script.py"), "print('hi')").unwrap();
+
+        let walker = FileWalker::new(dir.path())
+            .with_languages(Some(vec![Language::Rust, Language::TypeScript]));
+        let (files, _) = walker.walk().unwrap();
+
+        assert_eq!(files.len(), 2);
+        assert!(files.iter().all(|f| f.language != Language::Python));
+    }
+
+    #[test]
+    fn test_excluded_dirs() {
+        let dir = TempDir::new().unwrap();
+        fs::create_dir_all(dir.path().join("src/docs")).unwrap();
+        fs::create_dir_all(dir.path().join("docs")).unwrap();
+        fs::write(dir.path().join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(dir.path().join("src/docs/gen.rs"), "fn gen() {}").unwrap();
+        fs::write(dir.path().join("docs/example.rs"), "fn example() {}").unwrap();
+
+        let walker = FileWalker::new(dir.path()).with_excluded_dirs(vec!["docs".to_string()]);
+        let (files, _) = walker.walk().unwrap();
+
+        // Only the top-level docs/ is excluded, not every directory of that name
+        assert_eq!(files.len(), 2);
+        assert!(files
+            .iter()
+            .all(|f| !f.path.starts_with(dir.path().join("docs"))));
+    }
 }
@@ -0,0 +1,103 @@
+//! License and provenance of indexed files
+//!
+//! Files are tagged with the SPDX license identifier in their header, if any,
+//! and as vendored when they sit in a directory conventionally used for
+//! third-party code (`third_party/`, `external/`, ...). Vendored code can then
+//! be kept out of search results, and `codesearch licenses` lists what the
+//! index contains.
+
+use crate::cache::normalize_path_str;
+
+/// Lines at the top of a file searched for an SPDX header
+const SPDX_HEADER_LINES: usize = 30;
+
+const SPDX_TAG: &str = "SPDX-License-Identifier:";
+
+/// Directory names that hold code copied from other projects
+///
+/// `vendor/` and `node_modules/` are never indexed, so they aren't listed.
+const VENDORED_DIRS: &[&str] = &[
+    "third_party",
+    "third-party",
+    "thirdparty",
+    "3rdparty",
+    "3rd_party",
+    "external",
+    "externals",
+    "extern",
+    "vendored",
+    "bower_components",
+    "Pods",
+];
+
+/// SPDX license expression declared in the file's header (`MIT`, `Apache-2.0 OR MIT`)
+pub fn spdx_license(content: &str) -> Option<String> {
+    content.lines().take(SPDX_HEADER_LINES).find_map(|line| {
+        let (_, expression) = line.split_once(SPDX_TAG)?;
+        // Drop comment closers after the expression (`*/`, `-->`)
+        let expression = expression
+            .split(|c: char| !(c.is_ascii_alphanumeric() || " .-+()".contains(c)))
+            .next()?
+            .trim()
+            .trim_end_matches(['-', ' ']);
+        (!expression.is_empty()).then(|| expression.to_string())
+    })
+}
+
+/// Whether a directory name is one used for vendored code
+pub fn is_vendored_dir(name: &str) -> bool {
+    VENDORED_DIRS.contains(&name)
+}
+
+/// Whether a file lies in a vendored directory of the project at `root`
+///
+/// Only directories below `root` count, so a project checked out under
+/// `external/` isn't entirely vendored.
+pub fn is_vendored_path(path: &str, root: &str) -> bool {
+    let path = normalize_path_str(path);
+    let root = normalize_path_str(root);
+    let root = root.trim_end_matches('/');
+    let relative = match path.strip_prefix(root) {
+        Some(rest) if !root.is_empty() && rest.starts_with('/') => rest,
+        _ => &path,
+    };
+    relative.split('/').rev().skip(1).any(is_vendored_dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provenance() {
+        assert_eq!(
+            spdx_license("// SPDX-License-Identifier: MIT\nfn main() {}").as_deref(),
+            Some("MIT")
+        );
+        assert_eq!(
+            spdx_license("/* SPDX-License-Identifier: Apache-2.0 OR MIT */").as_deref(),
+            Some("Apache-2.0 OR MIT")
+        );
+        assert_eq!(
+            spdx_license("<!-- SPDX-License-Identifier: GPL-2.0+ -->").as_deref(),
+            Some("GPL-2.0+")
+        );
+        assert_eq!(spdx_license("fn main() {}"), None);
+        let late = format!("{}// SPDX-License-Identifier: MIT", "\n".repeat(40));
+        assert_eq!(spdx_license(&late), None);
+
+        assert!(is_vendored_path(
+            "/repo/third_party/zlib/inflate.c",
+            "/repo"
+        ));
+        assert!(is_vendored_path(r"C:\repo\external\lib.rs", r"C:\repo"));
+        assert!(is_vendored_path("third_party/zlib/inflate.c", "/repo"));
+        assert!(!is_vendored_path("/repo/src/external.rs", "/repo"));
+        assert!(!is_vendored_path("/repo/src/main.rs", "/repo"));
+        // The project's own location doesn't make it vendored
+        assert!(!is_vendored_path(
+            "/external/repo/src/main.rs",
+            "/external/repo"
+        ));
+    }
+}
@@ -0,0 +1,201 @@
+//! Order files by how recently they were touched
+//!
+//! Files with uncommitted changes come first, then files by their latest commit
+//! in recent git history, then everything else by modification time. Indexing
+//! in this order makes the code people are working on searchable first.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::UNIX_EPOCH;
+
+use super::FileInfo;
+use crate::constants::RECENCY_HISTORY_COMMITS;
+
+/// Sort key: higher sorts first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Recency {
+    /// Last modified (unix seconds), for files outside recent git history
+    Modified(i64),
+    /// Latest commit touching the file (unix seconds)
+    Committed(i64),
+    /// Modified, staged or untracked in the working tree
+    Uncommitted,
+}
+
+/// Sort `files` (found under `root`) most recently touched first
+///
+/// Falls back to modification time alone when `root` isn't in a git work tree
+/// or git isn't installed.
+pub fn order_by_recency(root: &Path, files: &mut [FileInfo]) {
+    let git = GitRecency::load(root);
+    files.sort_by_cached_key(|file| {
+        let recency = git
+            .as_ref()
+            .and_then(|git| git.get(&file.path))
+            .unwrap_or_else(|| Recency::Modified(modified_secs(&file.path)));
+        std::cmp::Reverse(recency)
+    });
+}
+
+/// Files with uncommitted changes (modified, staged or untracked) in the git
+/// work tree containing `root`, as absolute paths
+///
+/// Returns `None` when `root` isn't in a git work tree or git isn't installed.
+pub fn uncommitted_files(root: &Path) -> Option<Vec<PathBuf>> {
+    let toplevel = PathBuf::from(git(root, &["rev-parse", "--show-toplevel"])?.trim());
+    let toplevel = toplevel.canonicalize().unwrap_or(toplevel);
+    let status = git(
+        root,
+        &["status", "--porcelain", "-z", "--untracked-files=all"],
+    )?;
+    Some(
+        parse_git_status(&status)
+            .into_iter()
+            .map(|path| toplevel.join(path))
+            .collect(),
+    )
+}
+
+/// Recency of files from `git status` and `git log`
+struct GitRecency {
+    root: PathBuf,
+    /// `root` relative to the repository top level (empty at the top level)
+    prefix: PathBuf,
+    /// Keyed by path relative to the repository top level
+    files: HashMap<PathBuf, Recency>,
+}
+
+impl GitRecency {
+    fn load(root: &Path) -> Option<Self> {
+        let toplevel = PathBuf::from(git(root, &["rev-parse", "--show-toplevel"])?.trim());
+        let toplevel = toplevel.canonicalize().unwrap_or(toplevel);
+        let canonical_root = root.canonicalize().ok()?;
+        let prefix = canonical_root.strip_prefix(&toplevel).ok()?.to_path_buf();
+
+        let mut files = parse_git_log(&git(
+            root,
+            &[
+                // Non-ASCII paths unquoted, as they are on disk
+                "-c",
+                "core.quotePath=false",
+                "log",
+                "-n",
+                &RECENCY_HISTORY_COMMITS.to_string(),
+                "--format=%x1e%ct",
+                "--name-only",
+            ],
+        )?);
+        // Porcelain paths are relative to the top level regardless of the cwd
+        if let Some(status) = git(
+            root,
+            &["status", "--porcelain", "-z", "--untracked-files=all"],
+        ) {
+            for path in parse_git_status(&status) {
+                files.insert(path, Recency::Uncommitted);
+            }
+        }
+        Some(Self {
+            root: root.to_path_buf(),
+            prefix,
+            files,
+        })
+    }
+
+    fn get(&self, path: &Path) -> Option<Recency> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        self.files.get(&self.prefix.join(relative)).copied()
+    }
+}
+
+/// Run git in `root`, returning stdout on success
+fn git(root: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(args)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Latest commit time per file from `git log --format=%x1e%ct --name-only`
+///
+/// The log lists the newest commit first, so the first time seen for a path wins.
+fn parse_git_log(log: &str) -> HashMap<PathBuf, Recency> {
+    let mut files = HashMap::new();
+    for commit in log.split('\x1e').filter(|c| !c.trim().is_empty()) {
+        let mut lines = commit.lines();
+        let Some(time) = lines.next().and_then(|l| l.trim().parse::<i64>().ok()) else {
+            continue;
+        };
+        for path in lines.map(str::trim).filter(|l| !l.is_empty()) {
+            files
+                .entry(PathBuf::from(path))
+                .or_insert(Recency::Committed(time));
+        }
+    }
+    files
+}
+
+/// Paths from `git status --porcelain -z` (renames report the new path)
+fn parse_git_status(status: &str) -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    let mut entries = status.split('\0').filter(|e| !e.is_empty());
+    while let Some(entry) = entries.next() {
+        if entry.len() < 4 {
+            continue;
+        }
+        let (code, path) = entry.split_at(3);
+        paths.push(PathBuf::from(path));
+        // Renames and copies are followed by the original path
+        if code.starts_with('R') || code.starts_with('C') {
+            entries.next();
+        }
+    }
+    paths
+}
+
+fn modified_secs(path: &Path) -> i64 {
+    std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_output_parsing_and_ordering() {
+        let log = "\x1e1700000200\n\nsrc/new.rs\nsrc/shared.rs\n\x1e1700000100\n\nsrc/shared.rs\nsrc/old.rs\n";
+        let files = parse_git_log(log);
+        assert_eq!(
+            files.get(Path::new("src/shared.rs")),
+            Some(&Recency::Committed(1700000200))
+        );
+        assert_eq!(
+            files.get(Path::new("src/old.rs")),
+            Some(&Recency::Committed(1700000100))
+        );
+
+        let status = " M src/a.rs\0?? notes.md\0R  src/renamed.rs\0src/original.rs\0";
+        assert_eq!(
+            parse_git_status(status),
+            vec![
+                PathBuf::from("src/a.rs"),
+                PathBuf::from("notes.md"),
+                PathBuf::from("src/renamed.rs"),
+            ]
+        );
+
+        assert!(Recency::Uncommitted > Recency::Committed(i64::MAX));
+        assert!(Recency::Committed(0) > Recency::Modified(i64::MAX));
+    }
+}
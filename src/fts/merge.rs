@@ -0,0 +1,161 @@
+//! Explicit FTS segment merging
+//!
+//! The writer runs without a merge policy (see `create_writer_with_retry`), so
+//! every commit adds a segment and deleted documents stay on disk. Instead of
+//! tantivy merging in the background right after a commit, while searches
+//! follow a batch of changes, segments are merged at quiet moments: at the end
+//! of `codesearch index`, and by a server once no file events arrived for
+//! `CODESEARCH_FTS_MERGE_IDLE_SECS`.
+
+use anyhow::Result;
+use tantivy::{FutureResult, SegmentMeta};
+
+/// Segments kept before merging the smallest ones
+const DEFAULT_MAX_SEGMENTS: usize = 10;
+
+/// Merge threads of tantivy's pool; more concurrent merges only queue
+const MAX_MERGE_THREADS: usize = 4;
+
+/// Seconds without file events before a server merges
+const DEFAULT_MERGE_IDLE_SECS: u64 = 60;
+
+/// Share of deleted documents that gets a segment rewritten
+const DELETED_RATIO_BEFORE_MERGE: f64 = 0.3;
+
+/// How segments are merged, from the environment
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeSettings {
+    /// `CODESEARCH_FTS_MAX_SEGMENTS`: segments left after a merge
+    pub max_segments: usize,
+    /// `CODESEARCH_FTS_MERGE_THREADS`: merges run at once (1-4)
+    pub threads: usize,
+}
+
+impl MergeSettings {
+    pub fn from_env() -> Self {
+        let var = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .and_then(|v| v.trim().parse::<usize>().ok())
+        };
+        Self {
+            max_segments: var("CODESEARCH_FTS_MAX_SEGMENTS")
+                .unwrap_or(DEFAULT_MAX_SEGMENTS)
+                .max(1),
+            threads: var("CODESEARCH_FTS_MERGE_THREADS")
+                .unwrap_or(1)
+                .clamp(1, MAX_MERGE_THREADS),
+        }
+    }
+}
+
+/// How long a server waits without file events before merging, from
+/// `CODESEARCH_FTS_MERGE_IDLE_SECS` (`0` = never merge while serving)
+pub fn merge_idle_time() -> Option<std::time::Duration> {
+    let secs = std::env::var("CODESEARCH_FTS_MERGE_IDLE_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_MERGE_IDLE_SECS);
+    (secs > 0).then(|| std::time::Duration::from_secs(secs))
+}
+
+/// Live and deleted documents of a segment
+#[derive(Debug, Clone, Copy)]
+pub(super) struct SegmentSize {
+    pub docs: u32,
+    pub deleted_docs: u32,
+}
+
+/// Groups of segments (indices into `segments`) to merge, one per merge thread
+///
+/// The smallest segments are merged until at most `max_segments` remain.
+/// Segments that are mostly deleted documents join a merge too, which drops
+/// those documents.
+pub(super) fn plan_merges(segments: &[SegmentSize], settings: &MergeSettings) -> Vec<Vec<usize>> {
+    let mut by_size: Vec<usize> = (0..segments.len()).collect();
+    by_size.sort_by_key(|&i| segments[i].docs);
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    let excess = segments.len().saturating_sub(settings.max_segments);
+    if excess > 0 {
+        // Merging k segments in g groups leaves k - g fewer; each group needs two
+        let group_count = settings.threads.min(excess);
+        let selected = (excess + group_count).min(segments.len());
+        groups = vec![Vec::new(); group_count];
+        for (n, &i) in by_size[..selected].iter().enumerate() {
+            groups[n % group_count].push(i);
+        }
+    }
+
+    let mostly_deleted = by_size.iter().copied().filter(|&i| {
+        let size = segments[i];
+        let total = size.docs as f64 + size.deleted_docs as f64;
+        size.deleted_docs > 0 && size.deleted_docs as f64 >= total * DELETED_RATIO_BEFORE_MERGE
+    });
+    for i in mostly_deleted {
+        if groups.iter().any(|group| group.contains(&i)) {
+            continue;
+        }
+        match groups.iter_mut().min_by_key(|group| group.len()) {
+            Some(group) => group.push(i),
+            None => groups.push(vec![i]),
+        }
+    }
+    groups
+}
+
+/// Merges started by [`FtsStore::start_merge`](super::FtsStore::start_merge)
+pub struct PendingMerge {
+    pub(super) merges: Vec<FutureResult<Option<SegmentMeta>>>,
+    /// Segments being merged
+    pub segments: usize,
+}
+
+impl PendingMerge {
+    /// Block until every merge finished; returns the segments merged
+    ///
+    /// The store's reader sees the merged segments after
+    /// [`FtsStore::reload`](super::FtsStore::reload).
+    pub fn wait(self) -> Result<usize> {
+        for merge in self.merges {
+            merge.wait()?;
+        }
+        Ok(self.segments)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sizes(docs: &[(u32, u32)]) -> Vec<SegmentSize> {
+        docs.iter()
+            .map(|&(docs, deleted_docs)| SegmentSize { docs, deleted_docs })
+            .collect()
+    }
+
+    #[test]
+    fn test_plan_merges() {
+        let settings = |max_segments, threads| MergeSettings {
+            max_segments,
+            threads,
+        };
+        let segments = sizes(&[(1000, 0), (5, 0), (50, 0), (10, 0), (200, 0)]);
+
+        assert!(plan_merges(&segments, &settings(5, 1)).is_empty());
+        // 5 segments down to 3: the 3 smallest become one
+        assert_eq!(plan_merges(&segments, &settings(3, 1)), [vec![1, 3, 2]]);
+        // Down to 2 with two threads: everything, in two merges
+        assert_eq!(
+            plan_merges(&segments, &settings(2, 2)),
+            [vec![1, 2, 0], vec![3, 4]]
+        );
+        // More threads than segments to shed
+        assert_eq!(plan_merges(&segments, &settings(4, 4)), [vec![1, 3]]);
+
+        // A mostly deleted segment is rewritten on its own, or joins a merge
+        let segments = sizes(&[(1000, 0), (100, 900), (10, 0)]);
+        assert_eq!(plan_merges(&segments, &settings(10, 1)), [vec![1]]);
+        assert_eq!(plan_merges(&segments, &settings(2, 1)), [vec![2, 1]]);
+    }
+}
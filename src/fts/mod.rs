@@ -3,6 +3,9 @@
 //! Provides BM25-based full-text search to complement vector similarity search.
 //! Used in hybrid search mode with RRF (Reciprocal Rank Fusion).
 
+mod merge;
+mod stop_words;
 mod tantivy_store;
 
+pub use merge::{merge_idle_time, MergeSettings};
 pub use tantivy_store::{FtsResult, FtsStore};
@@ -0,0 +1,163 @@
+//! Code-aware stop words for BM25
+//!
+//! English filler ("the", "is") and tokens that appear in nearly every chunk
+//! (`return`, `self`, `this`, `void`) carry no signal but still dominate BM25
+//! scores through sheer frequency. Two layers remove them:
+//!
+//! - English filler and the tokens above are dropped by the `code` tokenizer for every field, at
+//!   index and query time.
+//! - Language keywords are stripped from chunk text before indexing, using the
+//!   list of the chunk's own language. They aren't stop words globally: `func`
+//!   is noise in Go but may be a variable name in Python.
+
+use std::borrow::Cow;
+use tantivy::tokenizer::{
+    LowerCaser, RemoveLongFilter, SimpleTokenizer, StopWordFilter, TextAnalyzer,
+};
+
+use crate::file::Language;
+
+/// Name the code tokenizer is registered under
+pub const CODE_TOKENIZER: &str = "code";
+
+/// Tokens longer than this are dropped (same limit as tantivy's default)
+const MAX_TOKEN_LEN: usize = 40;
+
+/// English filler words (lowercase)
+const ENGLISH_STOP_WORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "but", "by", "for", "if", "in", "into", "is", "it",
+    "no", "not", "of", "on", "or", "such", "that", "the", "their", "then", "there", "these",
+    "they", "to", "was", "will", "with",
+];
+
+/// Tokens common to nearly every chunk in any language (lowercase)
+const CODE_STOP_WORDS: &[&str] = &[
+    "return", "self", "this", "void", "null", "nil", "none", "true", "false",
+];
+
+/// Analyzer for the content and signature fields: the default tokenizer plus
+/// the stop words
+pub fn code_analyzer() -> TextAnalyzer {
+    TextAnalyzer::builder(SimpleTokenizer::default())
+        .filter(RemoveLongFilter::limit(MAX_TOKEN_LEN))
+        .filter(LowerCaser)
+        .filter(StopWordFilter::remove(
+            ENGLISH_STOP_WORDS
+                .iter()
+                .chain(CODE_STOP_WORDS)
+                .map(|w| w.to_string()),
+        ))
+        .build()
+}
+
+/// Keywords of a language that appear in most of its chunks
+///
+/// Matched case-sensitively against whole tokens. Keywords people search for
+/// (`Some`, `Self`, `where`, `async`, `new`) stay indexed. Changing a list
+/// requires a bump of `CHUNKER_SCHEMA_VERSION`, so indexed files pick it up.
+fn keywords(language: Language) -> &'static [&'static str] {
+    match language {
+        Language::Rust => &["fn", "let", "mut", "pub", "impl", "crate", "super", "ref"],
+        Language::Python => &["def", "elif", "pass", "import", "from", "lambda", "cls"],
+        Language::JavaScript | Language::TypeScript => &[
+            "const",
+            "let",
+            "var",
+            "function",
+            "undefined",
+            "export",
+            "import",
+            "from",
+        ],
+        Language::Java | Language::CSharp | Language::Kotlin => &[
+            "public",
+            "private",
+            "protected",
+            "static",
+            "final",
+            "var",
+            "val",
+            "fun",
+            "using",
+            "import",
+            "package",
+            "override",
+        ],
+        Language::Go => &["func", "package", "import", "var", "err"],
+        Language::C | Language::Cpp => &[
+            "int", "char", "const", "static", "unsigned", "include", "define", "std", "auto",
+        ],
+        Language::Ruby => &["def", "end", "do", "require"],
+        Language::Php => &["function", "public", "private", "static", "php"],
+        Language::Swift => &["func", "let", "var", "guard"],
+        Language::Shell => &["fi", "then", "do", "done", "echo", "local"],
+        _ => &[],
+    }
+}
+
+/// `content` with the keywords of `path`'s language blanked out
+///
+/// Only whole identifiers (runs of alphanumerics and `_`) are matched, so
+/// `fn_name` is kept and only a standalone `fn` is removed. Borrows when
+/// nothing is stripped.
+pub fn strip_keywords<'a>(content: &'a str, path: &str) -> Cow<'a, str> {
+    let keywords = keywords(Language::from_path(std::path::Path::new(path)));
+    if keywords.is_empty() {
+        return Cow::Borrowed(content);
+    }
+
+    let mut out: Option<String> = None;
+    let mut copied = 0;
+    let mut start = None;
+    for (i, c) in content
+        .char_indices()
+        .chain(std::iter::once((content.len(), ' ')))
+    {
+        match (c.is_alphanumeric() || c == '_', start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                if keywords.contains(&&content[s..i]) {
+                    let buf = out.get_or_insert_with(|| String::with_capacity(content.len()));
+                    buf.push_str(&content[copied..s]);
+                    copied = i;
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    match out {
+        Some(mut buf) => {
+            buf.push_str(&content[copied..]);
+            Cow::Owned(buf)
+        }
+        None => Cow::Borrowed(content),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_keywords() {
+        assert_eq!(
+            strip_keywords("pub fn fn_name(&mut self) {}", "src/lib.rs"),
+            "  fn_name(& self) {}"
+        );
+        // Keywords of other languages are left alone
+        assert_eq!(
+            strip_keywords("def func(): pass", "main.go"),
+            "def (): pass"
+        );
+        assert!(matches!(
+            strip_keywords("fn main() {}", "README.md"),
+            Cow::Borrowed(_)
+        ));
+        // Keywords that are also search terms are kept
+        assert!(matches!(
+            strip_keywords("Some(Self::new()) where T: Ok", "src/lib.rs"),
+            Cow::Borrowed(_)
+        ));
+    }
+}
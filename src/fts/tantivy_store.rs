@@ -11,13 +11,18 @@ use anyhow::{anyhow, Result};
 use std::path::Path;
 use tantivy::{
     collector::TopDocs,
-    directory::MmapDirectory,
+    directory::{Directory as _, MmapDirectory},
     merge_policy::NoMergePolicy,
     query::QueryParser,
-    schema::{Field, NumericOptions, Schema, Value, STORED, STRING, TEXT},
-    Index, IndexReader, IndexSettings, IndexWriter, TantivyDocument, Term,
+    schema::{
+        Field, IndexRecordOption, NumericOptions, Schema, TextFieldIndexing, TextOptions, Value,
+        STORED, STRING,
+    },
+    HasLen as _, Index, IndexReader, IndexSettings, IndexWriter, TantivyDocument, Term,
 };
 
+use super::merge::{plan_merges, MergeSettings, PendingMerge, SegmentSize};
+use super::stop_words::{code_analyzer, strip_keywords, CODE_TOKENIZER};
 use crate::chunker::ChunkKind;
 
 /// Result from FTS search
@@ -36,6 +41,8 @@ pub struct FtsResult {
 pub struct FtsStore {
     index: Index,
     reader: IndexReader,
+    /// Opstamp of the commit the reader was last reloaded at
+    loaded_opstamp: u64,
     writer: Option<IndexWriter>,
     #[allow(dead_code)]
     schema: Schema,
@@ -65,14 +72,31 @@ impl FtsStore {
             NumericOptions::default().set_indexed().set_stored(),
         );
 
-        // Content - full text indexed for BM25 search
-        let content_field = schema_builder.add_text_field("content", TEXT);
+        // Content - full text indexed for BM25 search, without stop words.
+        // Length normalization stays on: a match in a short chunk means more.
+        let content_field = schema_builder.add_text_field(
+            "content",
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(CODE_TOKENIZER)
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions),
+            ),
+        );
 
         // Path - stored and string indexed for filtering
         let path_field = schema_builder.add_text_field("path", STRING | STORED);
 
-        // Signature - indexed for function/method name search
-        let signature_field = schema_builder.add_text_field("signature", TEXT);
+        // Signature - indexed for function/method name search. No length
+        // normalization: a long parameter list shouldn't bury the name.
+        let signature_field = schema_builder.add_text_field(
+            "signature",
+            TextOptions::default().set_indexing_options(
+                TextFieldIndexing::default()
+                    .set_tokenizer(CODE_TOKENIZER)
+                    .set_index_option(IndexRecordOption::WithFreqsAndPositions)
+                    .set_fieldnorms(false),
+            ),
+        );
 
         // Kind - stored for filtering (function, class, etc)
         let kind_field = schema_builder.add_text_field("kind", STRING | STORED);
@@ -81,13 +105,18 @@ impl FtsStore {
 
         // Open or create index with retry logic for Windows file locking
         let index = Self::open_or_create_index_with_retry(&fts_path, &schema)?;
+        // Indexes built before the code tokenizer keep their stored schema
+        // (default tokenizer) until rebuilt with `index --force`
+        index.tokenizers().register(CODE_TOKENIZER, code_analyzer());
 
-        // Create reader for searching
+        // Create reader for searching (opstamp first: the reader is at least as new)
+        let loaded_opstamp = index.load_metas()?.opstamp;
         let reader = index.reader()?;
 
         Ok(Self {
             index,
             reader,
+            loaded_opstamp,
             writer: None, // Lazy-initialized on first write
             schema,
             chunk_id_field,
@@ -170,9 +199,9 @@ impl FtsStore {
             // thread and kill the IndexWriter — causing the intermittent
             // "An index writer was killed" error (~1/5 indexing runs).
             //
-            // With NoMergePolicy, all segment management is explicit: we accumulate
-            // segments during indexing and they're consolidated at commit points.
-            // This trades slightly more segments for 100% reliability.
+            // With NoMergePolicy, all segment management is explicit: segments
+            // accumulate during indexing and refreshes, and are merged at quiet
+            // moments through `start_merge` (see the `merge` module).
             match index.writer(50_000_000) {
                 Ok(writer) => {
                     writer.set_merge_policy(Box::new(NoMergePolicy));
@@ -234,6 +263,11 @@ impl FtsStore {
         let signature_field = self.signature_field;
         let kind_field = self.kind_field;
 
+        // Keywords of the chunk's language carry no signal
+        let content = &*strip_keywords(content, path);
+        let signature = signature.map(|sig| strip_keywords(sig, path));
+        let signature = signature.as_deref();
+
         let mut doc = TantivyDocument::new();
         doc.add_u64(chunk_id_field, chunk_id as u64);
         doc.add_text(content_field, content);
@@ -324,11 +358,12 @@ impl FtsStore {
 
             let writer = self.writer.as_mut().unwrap();
             match writer.commit() {
-                Ok(_) => {
+                Ok(opstamp) => {
                     // Reload reader to see changes
-                    if let Err(e) = self.reader.reload() {
+                    match self.reader.reload() {
+                        Ok(()) => self.loaded_opstamp = opstamp,
                         // Non-fatal: reader will eventually catch up
-                        tracing::debug!("Reader reload warning: {}", e);
+                        Err(e) => tracing::debug!("Reader reload warning: {}", e),
                     }
                     return Ok(());
                 }
@@ -388,6 +423,76 @@ impl FtsStore {
         ))
     }
 
+    /// Start merging segments as `settings` asks; `None` when nothing needs it
+    ///
+    /// The merges run on tantivy's merge threads. Searches keep using the
+    /// current segments meanwhile; call [`reload`](Self::reload) once the
+    /// returned merges finished.
+    pub fn start_merge(&mut self, settings: &MergeSettings) -> Result<Option<PendingMerge>> {
+        let metas = self.index.searchable_segment_metas()?;
+        let sizes: Vec<SegmentSize> = metas
+            .iter()
+            .map(|meta| SegmentSize {
+                docs: meta.num_docs(),
+                deleted_docs: meta.num_deleted_docs(),
+            })
+            .collect();
+        let groups = plan_merges(&sizes, settings);
+        if groups.is_empty() {
+            return Ok(None);
+        }
+
+        self.ensure_writer()?;
+        let writer = self.writer.as_mut().unwrap();
+        let segments = groups.iter().map(Vec::len).sum();
+        let merges = groups
+            .iter()
+            .map(|group| {
+                let ids: Vec<_> = group.iter().map(|&i| metas[i].id()).collect();
+                writer.merge(&ids)
+            })
+            .collect();
+        Ok(Some(PendingMerge { merges, segments }))
+    }
+
+    /// Merge segments as `settings` asks and wait for it; returns the segments merged
+    pub fn merge_segments(&mut self, settings: &MergeSettings) -> Result<usize> {
+        let Some(pending) = self.start_merge(settings)? else {
+            return Ok(0);
+        };
+        let merged = pending.wait()?;
+        self.reload()?;
+        Ok(merged)
+    }
+
+    /// Point the reader at the index's current segments
+    pub fn reload(&mut self) -> Result<()> {
+        self.loaded_opstamp = self.index.load_metas()?.opstamp;
+        self.reader.reload()?;
+        Ok(())
+    }
+
+    /// Whether another process committed since the reader was last loaded
+    pub fn has_new_commits(&self) -> Result<bool> {
+        Ok(self.index.load_metas()?.opstamp != self.loaded_opstamp)
+    }
+
+    /// Reload the reader if another process committed to the index
+    ///
+    /// A commit rewrites `fts/meta.json` with a new opstamp, which is compared
+    /// to the one the reader was last loaded at. Tantivy's own meta.json
+    /// watcher gives up on a failed reload until the next commit; this check
+    /// retries on every call. Returns whether the reader was reloaded.
+    pub fn reload_if_changed(&mut self) -> Result<bool> {
+        let opstamp = self.index.load_metas()?.opstamp;
+        if opstamp == self.loaded_opstamp {
+            return Ok(false);
+        }
+        self.reader.reload()?;
+        self.loaded_opstamp = opstamp;
+        Ok(true)
+    }
+
     /// Search using BM25
     ///
     /// If `target_kind` is provided, boosts results matching that ChunkKind (e.g., "class", "function").
@@ -464,7 +569,6 @@ impl FtsStore {
         target_kind: Option<ChunkKind>,
     ) -> Result<Vec<FtsResult>> {
         use tantivy::query::{BooleanQuery, BoostQuery, Occur, TermQuery};
-        use tantivy::schema::IndexRecordOption;
 
         let searcher = self.reader.searcher();
 
@@ -522,13 +626,90 @@ impl FtsStore {
         Ok(results)
     }
 
+    /// The signature term closest to `word` within `max_edits`, with a chunk containing it
+    ///
+    /// Terms are lowercase tokens of indexed signatures (symbol names, or their
+    /// `snake_case` parts). Ties go to the term in more chunks. Exact matches
+    /// are not returned. A Levenshtein automaton walks the term dictionary, so
+    /// only terms within `max_edits` are visited.
+    pub fn closest_signature_term(
+        &self,
+        word: &str,
+        max_edits: usize,
+    ) -> Result<Option<(String, u32)>> {
+        use levenshtein_automata::{Distance, LevenshteinAutomatonBuilder};
+        use tantivy::query::TermQuery;
+
+        let word = word.to_lowercase();
+        let dfa = LevenshteinDfa(
+            LevenshteinAutomatonBuilder::new(max_edits as u8, false).build_dfa(&word),
+        );
+        let searcher = self.reader.searcher();
+        // (edits, chunks containing it, term)
+        let mut best: Option<(usize, u32, String)> = None;
+        for segment in searcher.segment_readers() {
+            let inverted_index = segment.inverted_index(self.signature_field)?;
+            let mut terms = inverted_index.terms().search(&dfa).into_stream()?;
+            while terms.advance() {
+                let Ok(term) = std::str::from_utf8(terms.key()) else {
+                    continue;
+                };
+                let edits = match dfa.0.eval(term) {
+                    Distance::Exact(0) | Distance::AtLeast(_) => continue,
+                    Distance::Exact(edits) => edits as usize,
+                };
+                let doc_freq = terms.value().doc_freq;
+                let better = best.as_ref().is_none_or(|(best_edits, best_freq, _)| {
+                    (edits, std::cmp::Reverse(doc_freq))
+                        < (*best_edits, std::cmp::Reverse(*best_freq))
+                });
+                if better {
+                    best = Some((edits, doc_freq, term.to_string()));
+                }
+            }
+        }
+        let Some((_, _, term)) = best else {
+            return Ok(None);
+        };
+
+        let query = TermQuery::new(
+            Term::from_field_text(self.signature_field, &term),
+            IndexRecordOption::Basic,
+        );
+        for (_, doc_address) in searcher.search(&query, &TopDocs::with_limit(1))? {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            if let Some(id) = doc.get_first(self.chunk_id_field).and_then(|v| v.as_u64()) {
+                return Ok(Some((term, id as u32)));
+            }
+        }
+        Ok(None)
+    }
+
     /// Get statistics about the index
     pub fn stats(&self) -> Result<FtsStats> {
         let searcher = self.reader.searcher();
         let num_docs = searcher.num_docs() as usize;
 
+        let directory = self.index.directory();
+        let mut segments = Vec::new();
+        for meta in self.index.searchable_segment_metas()? {
+            let bytes = meta
+                .list_files()
+                .iter()
+                .filter_map(|file| directory.open_read(file).ok())
+                .map(|file| file.len() as u64)
+                .sum();
+            segments.push(FtsSegmentStats {
+                docs: meta.num_docs(),
+                deleted_docs: meta.num_deleted_docs(),
+                bytes,
+            });
+        }
+        segments.sort_by_key(|s| std::cmp::Reverse(s.bytes));
+
         Ok(FtsStats {
             num_documents: num_docs,
+            segments,
         })
     }
 
@@ -538,18 +719,57 @@ impl FtsStore {
         self.ensure_writer()?;
         let writer = self.writer.as_mut().unwrap();
         writer.delete_all_documents()?;
-        writer.commit()?;
+        let opstamp = writer.commit()?;
         self.reader.reload()?;
+        self.loaded_opstamp = opstamp;
         Ok(())
     }
 }
 
+/// Levenshtein automaton over the bytes of term dictionary keys
+struct LevenshteinDfa(levenshtein_automata::DFA);
+
+impl tantivy_fst::Automaton for LevenshteinDfa {
+    type State = u32;
+
+    fn start(&self) -> u32 {
+        self.0.initial_state()
+    }
+
+    fn is_match(&self, state: &u32) -> bool {
+        matches!(
+            self.0.distance(*state),
+            levenshtein_automata::Distance::Exact(_)
+        )
+    }
+
+    fn can_match(&self, state: &u32) -> bool {
+        *state != levenshtein_automata::SINK_STATE
+    }
+
+    fn accept(&self, state: &u32, byte: u8) -> u32 {
+        self.0.transition(*state, byte)
+    }
+}
+
 /// Statistics about the FTS index
 #[derive(Debug, Clone)]
 #[allow(dead_code)] // Part of public API for debugging/monitoring
 pub struct FtsStats {
     #[allow(dead_code)] // Part of public API for debugging/monitoring
     pub num_documents: usize,
+    /// Searchable segments, largest first
+    pub segments: Vec<FtsSegmentStats>,
+}
+
+/// Size of one FTS segment
+#[derive(Debug, Clone)]
+pub struct FtsSegmentStats {
+    pub docs: u32,
+    /// Deleted documents still taking space until the segment is merged
+    pub deleted_docs: u32,
+    /// Bytes of the segment's files
+    pub bytes: u64,
 }
 
 #[cfg(test)]
@@ -633,4 +853,122 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_merge_segments() -> Result<()> {
+        let dir = tempdir()?;
+        let mut store = FtsStore::new(dir.path())?;
+        for segment in 0..4 {
+            for id in [segment, segment + 10] {
+                let content = format!("fn merged_{}() {{ shared_body() }}", id);
+                store.add_chunk(id, &content, "src/lib.rs", None, "function")?;
+            }
+            store.commit()?;
+        }
+        store.delete_chunk(0)?;
+        store.commit()?;
+        assert_eq!(store.stats()?.segments.len(), 4);
+
+        let settings = MergeSettings {
+            max_segments: 2,
+            threads: 1,
+        };
+        assert_eq!(store.merge_segments(&settings)?, 3);
+        let stats = store.stats()?;
+        assert_eq!(stats.segments.len(), 2);
+        assert!(stats.segments.iter().all(|s| s.deleted_docs == 0));
+        assert_eq!(store.search("shared_body", 10, None)?.len(), 7);
+
+        assert!(store.start_merge(&settings)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_reload_if_changed_sees_external_commits() -> Result<()> {
+        let dir = tempdir()?;
+        let mut writer = FtsStore::new_with_writer(dir.path())?;
+        let mut reader = FtsStore::new(dir.path())?;
+        assert!(!reader.reload_if_changed()?);
+
+        writer.add_chunk(7, "fn external_commit() {}", "src/lib.rs", None, "function")?;
+        writer.commit()?;
+        assert!(!writer.reload_if_changed()?);
+
+        assert!(reader.reload_if_changed()?);
+        assert_eq!(reader.search("external_commit", 10, None)?[0].chunk_id, 7);
+        assert!(!reader.reload_if_changed()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_closest_signature_term() -> Result<()> {
+        let dir = tempdir()?;
+        let mut store = FtsStore::new(dir.path())?;
+        store.add_chunk(
+            1,
+            "fn parseConfig() {}",
+            "a.rs",
+            Some("fn parseConfig()"),
+            "function",
+        )?;
+        store.add_chunk(
+            2,
+            "fn parseConfigs() {}",
+            "b.rs",
+            Some("fn parseConfigs()"),
+            "function",
+        )?;
+        store.add_chunk(
+            3,
+            "fn parseConfigs() {}",
+            "c.rs",
+            Some("fn parseConfigs()"),
+            "function",
+        )?;
+        store.commit()?;
+
+        // One edit away from both; the term in more chunks wins
+        let (term, chunk_id) = store.closest_signature_term("parseConfigz", 2)?.unwrap();
+        assert_eq!(term, "parseconfigs");
+        assert!(chunk_id == 2 || chunk_id == 3);
+        assert_eq!(
+            store.closest_signature_term("parsConfig", 1)?,
+            Some(("parseconfig".to_string(), 1))
+        );
+        assert_eq!(store.closest_signature_term("renderPage", 2)?, None);
+        // Exact matches are not corrections
+        assert_eq!(store.closest_signature_term("parseconfig", 0)?, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_stop_words_ignored() -> Result<()> {
+        let dir = tempdir()?;
+        let mut store = FtsStore::new(dir.path())?;
+        store.add_chunk(
+            1,
+            "fn cache_lookup(&self) -> Option<u32> { return self.cache.get(); return None; }",
+            "a.rs",
+            Some("fn cache_lookup(&self)"),
+            "function",
+        )?;
+        store.add_chunk(
+            2,
+            "def refresh_token(): return fetch_token()",
+            "b.py",
+            Some("def refresh_token()"),
+            "function",
+        )?;
+        store.commit()?;
+
+        assert!(store.search("return self", 10, None)?.is_empty());
+        // Keywords of the chunk's language aren't indexed
+        assert!(store.search("fn", 10, None)?.is_empty());
+        assert!(store.search("def", 10, None)?.is_empty());
+        // Repeated `return`s don't outrank the word that matters
+        let results = store.search("return the token", 10, None)?;
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].chunk_id, 2);
+        Ok(())
+    }
 }
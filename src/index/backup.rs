@@ -0,0 +1,452 @@
+//! `codesearch backup` / `codesearch restore`
+//!
+//! A backup is a single file: a magic line, a JSON manifest line, then one JSON
+//! header line per entry (path relative to the database directory, size and
+//! SHA-256) followed by the entry's raw bytes. Snapshots are taken while a
+//! writer (MCP server, `serve`, daemon) may be live:
+//!
+//! - JSON state files (`file_meta.json`, `metadata.json`, ...) are copied first,
+//!   so they never claim more than the vector store holds; anything newer is
+//!   re-indexed by the next incremental refresh
+//! - the LMDB environment is copied inside one read transaction
+//! - tantivy's `meta.json` is read first and only the segment files it lists
+//!   are copied, retrying if a merge removes a segment mid-copy
+
+use crate::out_print;
+use anyhow::{anyhow, bail, Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use super::{get_db_path, is_database_locked};
+use crate::constants::{LOG_DIR_NAME, WRITER_LOCK_FILE};
+use crate::vectordb::VectorStore;
+
+/// First line of every backup file
+const BACKUP_MAGIC: &str = "CODESEARCH-BACKUP";
+/// Backup format version, bumped on incompatible layout changes
+const BACKUP_FORMAT_VERSION: u32 = 1;
+/// Tantivy index subdirectory of the database directory
+const FTS_DIR: &str = "fts";
+/// Attempts to copy a consistent FTS snapshot while segments are being merged
+const FTS_SNAPSHOT_ATTEMPTS: usize = 5;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    created_at: String,
+    codesearch_version: String,
+    project_path: String,
+    entries: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct EntryHeader {
+    path: String,
+    size: u64,
+    sha256: String,
+}
+
+/// Snapshot the database of `path` into `output`
+pub async fn backup(path: Option<PathBuf>, output: Option<PathBuf>) -> Result<()> {
+    let (db_path, project_path) = get_db_path(path)?;
+    if !db_path.exists() {
+        out_print!("{}", "❌ No database found!".red());
+        return Ok(());
+    }
+
+    let output = output.unwrap_or_else(|| default_backup_name(&project_path));
+    if output.exists() {
+        bail!("{} already exists", output.display());
+    }
+
+    out_print!("{}", "💾 Backup Database".bright_cyan().bold());
+    out_print!("{}", "=".repeat(60));
+    out_print!("💾 Database: {}", db_path.display());
+    out_print!("📂 Project: {}", project_path.display());
+
+    // Stage next to the output so the final file appears only when complete
+    let staging = output.with_extension("staging");
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    let result = stage_snapshot(&db_path, &staging)
+        .and_then(|()| write_archive(&staging, &project_path, &output));
+    let _ = fs::remove_dir_all(&staging);
+    if result.is_err() {
+        let _ = fs::remove_file(&output);
+    }
+    let entries = result?;
+
+    let size = fs::metadata(&output).map(|m| m.len()).unwrap_or(0);
+    out_print!(
+        "{}",
+        format!(
+            "✅ Backup written to {} ({} files, {:.1} MB)",
+            output.display(),
+            entries,
+            size as f64 / (1024.0 * 1024.0)
+        )
+        .green()
+    );
+    Ok(())
+}
+
+/// Replace the database of `path` with the contents of `input`
+pub async fn restore(input: PathBuf, path: Option<PathBuf>, yes: bool) -> Result<()> {
+    let (db_path, project_path) = get_db_path(path)?;
+
+    let file = File::open(&input).with_context(|| format!("Cannot open {}", input.display()))?;
+    let mut reader = BufReader::new(file);
+    let manifest = read_manifest(&mut reader)?;
+
+    out_print!("{}", "♻️  Restore Database".bright_yellow().bold());
+    out_print!("{}", "=".repeat(60));
+    out_print!("📦 Backup: {}", input.display());
+    out_print!(
+        "   Created {} from {} ({} files)",
+        manifest.created_at,
+        manifest.project_path,
+        manifest.entries
+    );
+    out_print!("💾 Database: {}", db_path.display());
+    out_print!("📂 Project: {}", project_path.display());
+
+    if is_database_locked(&db_path) {
+        bail!(
+            "The database is in use by another codesearch process (MCP server, serve or daemon). \
+             Stop it before restoring."
+        );
+    }
+
+    if db_path.exists() && !yes {
+        out_print!(
+            "\n{}",
+            "⚠️  This will replace the current index with the backup!".yellow()
+        );
+        print!("Are you sure? (y/N): ");
+        io::stdout().flush()?;
+
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+
+        if !input.trim().eq_ignore_ascii_case("y") {
+            out_print!("{}", "Cancelled.".dimmed());
+            return Ok(());
+        }
+    }
+
+    // Extract and verify everything before touching the current database
+    let restoring = db_path.with_extension("restoring");
+    if restoring.exists() {
+        fs::remove_dir_all(&restoring)?;
+    }
+    if let Err(e) = extract_entries(&mut reader, manifest.entries, &restoring) {
+        let _ = fs::remove_dir_all(&restoring);
+        return Err(e.context(format!("Backup {} is invalid", input.display())));
+    }
+
+    let previous = db_path.with_extension("pre-restore");
+    if db_path.exists() {
+        if previous.exists() {
+            fs::remove_dir_all(&previous)?;
+        }
+        fs::rename(&db_path, &previous)?;
+    }
+    if let Err(e) = fs::rename(&restoring, &db_path) {
+        if previous.exists() {
+            fs::rename(&previous, &db_path)?;
+        }
+        return Err(e.into());
+    }
+    if previous.exists() {
+        // Logs aren't part of backups; keep them with the restored database
+        let logs = previous.join(LOG_DIR_NAME);
+        if logs.exists() {
+            let _ = fs::rename(&logs, db_path.join(LOG_DIR_NAME));
+        }
+        fs::remove_dir_all(&previous)?;
+    }
+
+    out_print!(
+        "{}",
+        "✅ Database restored! Changes since the backup are picked up by the next refresh.".green()
+    );
+    Ok(())
+}
+
+/// `codesearch-backup-<project>-<timestamp>.csbak` in the current directory
+fn default_backup_name(project_path: &Path) -> PathBuf {
+    let project = project_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "index".to_string());
+    PathBuf::from(format!(
+        "codesearch-backup-{}-{}.csbak",
+        project,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ))
+}
+
+/// Copy a consistent snapshot of `db_path` into the new directory `staging`
+fn stage_snapshot(db_path: &Path, staging: &Path) -> Result<()> {
+    fs::create_dir_all(staging)?;
+
+    for entry in walkdir::WalkDir::new(db_path)
+        .min_depth(1)
+        .into_iter()
+        .filter_entry(|e| e.depth() > 1 || !is_excluded(&e.file_name().to_string_lossy()))
+    {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(db_path)?;
+        let dest = staging.join(relative);
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::copy(entry.path(), &dest)?;
+    }
+
+    out_print!("🔄 Copying vector store...");
+    VectorStore::copy_environment(db_path, &staging.join("data.mdb"))?;
+
+    let fts_path = db_path.join(FTS_DIR);
+    if fts_path.join("meta.json").exists() {
+        out_print!("🔄 Copying full-text index...");
+        copy_fts_snapshot(&fts_path, &staging.join(FTS_DIR))?;
+    }
+    Ok(())
+}
+
+/// Top-level database entries that backups skip or copy separately
+fn is_excluded(name: &str) -> bool {
+    matches!(name, "data.mdb" | "lock.mdb" | FTS_DIR | LOG_DIR_NAME)
+        || name == WRITER_LOCK_FILE
+        || name.ends_with(".del")
+}
+
+/// Copy tantivy's `meta.json` and the segment files it references
+fn copy_fts_snapshot(fts_path: &Path, dest: &Path) -> Result<()> {
+    let mut last_error = None;
+    for _ in 0..FTS_SNAPSHOT_ATTEMPTS {
+        if dest.exists() {
+            fs::remove_dir_all(dest)?;
+        }
+        fs::create_dir_all(dest)?;
+        match try_copy_fts(fts_path, dest) {
+            Ok(()) => return Ok(()),
+            // A merge committed and garbage-collected a segment: start over
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error
+        .unwrap_or_else(|| anyhow!("no attempts made"))
+        .context("Full-text index kept changing during backup"))
+}
+
+fn try_copy_fts(fts_path: &Path, dest: &Path) -> Result<()> {
+    let meta = fs::read(fts_path.join("meta.json"))?;
+    let segments = segment_file_prefixes(&meta)?;
+    for entry in fs::read_dir(fts_path)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if segments.iter().any(|s| name.starts_with(s.as_str())) {
+            fs::copy(entry.path(), dest.join(&name))?;
+        }
+    }
+    // Written last: the copied meta.json describes exactly the copied segments
+    fs::write(dest.join("meta.json"), meta)?;
+    Ok(())
+}
+
+/// File name prefixes of the segments listed in a tantivy `meta.json`
+///
+/// Segment ids are stored as hyphenated UUIDs; segment files are named after
+/// the UUID without hyphens (`<id>.idx`, `<id>.<opstamp>.del`, ...).
+fn segment_file_prefixes(meta: &[u8]) -> Result<Vec<String>> {
+    let meta: serde_json::Value = serde_json::from_slice(meta)?;
+    let segments = meta["segments"]
+        .as_array()
+        .ok_or_else(|| anyhow!("meta.json has no segment list"))?;
+    segments
+        .iter()
+        .map(|s| {
+            s["segment_id"]
+                .as_str()
+                .map(|id| format!("{}.", id.replace('-', "")))
+                .ok_or_else(|| anyhow!("segment without id in meta.json"))
+        })
+        .collect()
+}
+
+/// Write the files under `staging` as a backup archive, returning the entry count
+fn write_archive(staging: &Path, project_path: &Path, output: &Path) -> Result<usize> {
+    let mut files = Vec::new();
+    for entry in walkdir::WalkDir::new(staging).sort_by_file_name() {
+        let entry = entry?;
+        if entry.file_type().is_file() {
+            files.push(entry.into_path());
+        }
+    }
+
+    let mut out = BufWriter::new(File::create(output)?);
+    let manifest = Manifest {
+        created_at: chrono::Utc::now().to_rfc3339(),
+        codesearch_version: env!("CARGO_PKG_VERSION").to_string(),
+        project_path: project_path.display().to_string(),
+        entries: files.len(),
+    };
+    writeln!(out, "{} {}", BACKUP_MAGIC, BACKUP_FORMAT_VERSION)?;
+    writeln!(out, "{}", serde_json::to_string(&manifest)?)?;
+
+    for file in &files {
+        let relative = file.strip_prefix(staging)?;
+        let header = EntryHeader {
+            path: relative
+                .components()
+                .map(|c| c.as_os_str().to_string_lossy())
+                .collect::<Vec<_>>()
+                .join("/"),
+            size: fs::metadata(file)?.len(),
+            sha256: file_sha256(file)?,
+        };
+        writeln!(out, "{}", serde_json::to_string(&header)?)?;
+        let copied = io::copy(&mut File::open(file)?, &mut out)?;
+        if copied != header.size {
+            bail!("{} changed while writing the backup", header.path);
+        }
+    }
+    out.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    Ok(files.len())
+}
+
+fn file_sha256(path: &Path) -> Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Check the magic line and read the manifest of a backup
+fn read_manifest(reader: &mut impl BufRead) -> Result<Manifest> {
+    let magic = read_line(reader)?;
+    let version = magic
+        .strip_prefix(BACKUP_MAGIC)
+        .and_then(|v| v.trim().parse::<u32>().ok())
+        .ok_or_else(|| anyhow!("Not a codesearch backup"))?;
+    if version > BACKUP_FORMAT_VERSION {
+        bail!(
+            "Backup format version {} is newer than this codesearch supports ({})",
+            version,
+            BACKUP_FORMAT_VERSION
+        );
+    }
+    Ok(serde_json::from_str(&read_line(reader)?)?)
+}
+
+/// Extract `count` entries into the new directory `dest`, verifying each checksum
+fn extract_entries(reader: &mut impl BufRead, count: usize, dest: &Path) -> Result<()> {
+    fs::create_dir_all(dest)?;
+    for _ in 0..count {
+        let header: EntryHeader = serde_json::from_str(&read_line(reader)?)?;
+        let relative = Path::new(&header.path);
+        if relative.is_absolute()
+            || relative
+                .components()
+                .any(|c| !matches!(c, std::path::Component::Normal(_)))
+        {
+            bail!("Unsafe path in backup: {}", header.path);
+        }
+        let target = dest.join(relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut hasher = Sha256::new();
+        let mut out = BufWriter::new(File::create(&target)?);
+        let mut remaining = reader.take(header.size);
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = remaining.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            hasher.update(&buf[..n]);
+            out.write_all(&buf[..n])?;
+        }
+        out.flush()?;
+        if remaining.limit() != 0 {
+            bail!("{} is truncated", header.path);
+        }
+        if format!("{:x}", hasher.finalize()) != header.sha256 {
+            bail!("Checksum mismatch for {}", header.path);
+        }
+    }
+    Ok(())
+}
+
+fn read_line(reader: &mut impl BufRead) -> Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        bail!("Unexpected end of backup");
+    }
+    Ok(line.trim_end_matches('\n').to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_round_trip_and_fts_segments() {
+        let meta = br#"{"segments":[{"segment_id":"0a1b2c3d-0000-4000-8000-000000000001","max_doc":3,"deletes":null}],"opstamp":4}"#;
+        assert_eq!(
+            segment_file_prefixes(meta).unwrap(),
+            vec!["0a1b2c3d000040008000000000000001.".to_string()]
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let staging = dir.path().join("staging");
+        fs::create_dir_all(staging.join("fts")).unwrap();
+        fs::write(staging.join("file_meta.json"), "{}").unwrap();
+        fs::write(staging.join("fts").join("meta.json"), meta).unwrap();
+
+        let archive = dir.path().join("index.csbak");
+        assert_eq!(
+            write_archive(&staging, Path::new("/repo"), &archive).unwrap(),
+            2
+        );
+
+        let mut reader = BufReader::new(File::open(&archive).unwrap());
+        let manifest = read_manifest(&mut reader).unwrap();
+        assert_eq!(
+            (manifest.entries, manifest.project_path.as_str()),
+            (2, "/repo")
+        );
+        let restored = dir.path().join("restored");
+        extract_entries(&mut reader, manifest.entries, &restored).unwrap();
+        assert_eq!(
+            fs::read(restored.join("fts").join("meta.json")).unwrap(),
+            meta
+        );
+
+        // Corrupted payloads are rejected
+        let mut bytes = fs::read(&archive).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(&archive, bytes).unwrap();
+        let mut reader = BufReader::new(File::open(&archive).unwrap());
+        let manifest = read_manifest(&mut reader).unwrap();
+        assert!(extract_entries(&mut reader, manifest.entries, &dir.path().join("bad")).is_err());
+
+        // The LMDB copy opens as a vector store
+        let db = dir.path().join("db");
+        drop(VectorStore::new(&db, 4).unwrap());
+        fs::create_dir_all(dir.path().join("copy")).unwrap();
+        VectorStore::copy_environment(&db, &dir.path().join("copy").join("data.mdb")).unwrap();
+        assert!(VectorStore::open_readonly(&dir.path().join("copy"), 4).is_ok());
+    }
+}
@@ -0,0 +1,434 @@
+//! Index size budget
+//!
+//! With `CODESEARCH_INDEX_MAX_MB` set, the database directory is kept under
+//! that size by pruning in tiers, stopping as soon as the index fits:
+//!
+//! 1. The context lines stored around each chunk and its searchable-text copy
+//! 2. The content of low-value chunks (gap blocks, comments, imports); their
+//!    signatures and embeddings are kept, so they are still found
+//! 3. Whole directories, by priority: vendored code first, then the names in
+//!    `CODESEARCH_PRUNE_DIRS` (default [`DEFAULT_PRUNE_DIRS`]) in order.
+//!    Pruned directories are recorded in metadata.json and left out of later
+//!    indexing until the next `codesearch index --force`.
+//!
+//! The budget is checked after `codesearch index` and after the incremental
+//! refresh a server runs on startup.
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::cache::{normalize_path_str, FileMetaStore, IndexMetadata};
+use crate::file::is_vendored_dir;
+use crate::fts::FtsStore;
+use crate::output::format_bytes;
+use crate::vectordb::VectorStore;
+
+/// Chunk kinds whose content goes in the second tier
+const LOW_VALUE_KINDS: &[&str] = &["Block", "Comment", "Imports", "Other"];
+
+/// Directory names pruned in the third tier after vendored code, in order
+const DEFAULT_PRUNE_DIRS: &[&str] = &[
+    "generated",
+    "gen",
+    "fixtures",
+    "testdata",
+    "test_data",
+    "examples",
+    "example",
+    "samples",
+    "benches",
+    "benchmarks",
+    "docs",
+    "doc",
+    "tests",
+    "test",
+    "__tests__",
+    "spec",
+];
+
+/// LMDB files, counted by the pages in use rather than their size on disk
+const LMDB_FILES: &[&str] = &["data.mdb", "lock.mdb"];
+
+/// What keeping an index under its budget pruned
+#[derive(Debug, Default, PartialEq)]
+pub struct BudgetPruning {
+    pub budget: u64,
+    pub size_before: u64,
+    pub size_after: u64,
+    /// Chunks whose content was replaced by their signature
+    pub stripped_chunks: usize,
+    /// Directories removed from the index, relative to the project root
+    pub pruned_dirs: Vec<String>,
+}
+
+impl BudgetPruning {
+    /// One-line report for logs
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "Index pruned from {} to {} (budget {})",
+            format_bytes(self.size_before),
+            format_bytes(self.size_after),
+            format_bytes(self.budget)
+        );
+        if self.stripped_chunks > 0 {
+            summary.push_str(&format!(
+                ", content of {} low-value chunks dropped",
+                self.stripped_chunks
+            ));
+        }
+        if !self.pruned_dirs.is_empty() {
+            summary.push_str(&format!(", pruned {}", self.pruned_dirs.join(", ")));
+        }
+        summary
+    }
+
+    /// Whether the index is still over budget after pruning everything it could
+    pub fn over_budget(&self) -> bool {
+        self.size_after > self.budget
+    }
+}
+
+/// A directory the third tier may remove
+#[derive(Debug, PartialEq)]
+struct PruneCandidate {
+    /// Relative to the project root
+    dir: String,
+    /// 0 for vendored code, then the position in the priority list plus one
+    rank: usize,
+    files: Vec<String>,
+    chunks: usize,
+}
+
+/// Budget in bytes from `CODESEARCH_INDEX_MAX_MB` (unset or 0 = unlimited)
+pub fn size_budget() -> Option<u64> {
+    std::env::var("CODESEARCH_INDEX_MAX_MB")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&mb| mb > 0)
+        .map(|mb| mb * 1024 * 1024)
+}
+
+/// Directory names pruned in the third tier, from `CODESEARCH_PRUNE_DIRS`
+fn prune_priority() -> Vec<String> {
+    match std::env::var("CODESEARCH_PRUNE_DIRS") {
+        Ok(names) => names
+            .split(',')
+            .map(str::trim)
+            .filter(|n| !n.is_empty())
+            .map(String::from)
+            .collect(),
+        Err(_) => DEFAULT_PRUNE_DIRS.iter().map(|n| n.to_string()).collect(),
+    }
+}
+
+/// Bytes the index occupies: LMDB pages in use plus every other file in the
+/// database directory
+///
+/// Free LMDB pages are reused by later writes, so they don't count.
+fn index_size(db_path: &Path, store: &VectorStore) -> Result<u64> {
+    let mut size = store.lmdb_page_stats()?.used_bytes;
+    let mut dirs = vec![db_path.to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir)?.flatten() {
+            let metadata = entry.metadata()?;
+            if metadata.is_dir() {
+                dirs.push(entry.path());
+            } else if dir != db_path
+                || !LMDB_FILES.contains(&entry.file_name().to_string_lossy().as_ref())
+            {
+                size += metadata.len();
+            }
+        }
+    }
+    Ok(size)
+}
+
+/// Prune the index in tiers until it fits `CODESEARCH_INDEX_MAX_MB`
+///
+/// Returns `None` when no budget is set or the index already fits. The
+/// caller must hold the writer lock of the database.
+pub fn enforce_size_budget(
+    db_path: &Path,
+    project_path: &Path,
+    store: &mut VectorStore,
+    fts_store: &mut FtsStore,
+) -> Result<Option<BudgetPruning>> {
+    match size_budget() {
+        Some(budget) => prune_to_budget(budget, db_path, project_path, store, fts_store),
+        None => Ok(None),
+    }
+}
+
+/// [`enforce_size_budget`] for a budget of `budget` bytes
+fn prune_to_budget(
+    budget: u64,
+    db_path: &Path,
+    project_path: &Path,
+    store: &mut VectorStore,
+    fts_store: &mut FtsStore,
+) -> Result<Option<BudgetPruning>> {
+    let size_before = index_size(db_path, store)?;
+    if size_before <= budget {
+        return Ok(None);
+    }
+    let mut pruning = BudgetPruning {
+        budget,
+        size_before,
+        size_after: size_before,
+        ..Default::default()
+    };
+
+    // Vectors kept for moved code are only a cache, so they go first
+    store.forget_moved_vectors()?;
+
+    // Tier 1: context lines; searches read them from the files instead, and
+    // later runs no longer store them
+    store.strip_chunks(|_| false)?;
+    if let Some(mut metadata) = IndexMetadata::load(db_path)? {
+        metadata.context_lines = Some(0);
+        metadata.save(db_path)?;
+    }
+    pruning.size_after = index_size(db_path, store)?;
+    if pruning.size_after <= budget {
+        return Ok(Some(pruning));
+    }
+
+    // Tier 2: content of low-value chunks, in both stores
+    let stripped = store.strip_chunks(|chunk| LOW_VALUE_KINDS.contains(&chunk.kind.as_str()))?;
+    for &chunk_id in &stripped {
+        if let Some(chunk) = store.get_chunk(chunk_id)? {
+            fts_store.delete_chunk(chunk_id)?;
+            fts_store.add_chunk(
+                chunk_id,
+                &chunk.content,
+                &chunk.path,
+                chunk.signature.as_deref(),
+                &chunk.kind,
+            )?;
+        }
+    }
+    fts_store.commit()?;
+    pruning.stripped_chunks = stripped.len();
+    pruning.size_after = index_size(db_path, store)?;
+    if pruning.size_after <= budget {
+        return Ok(Some(pruning));
+    }
+
+    // Tier 3: whole directories, until the estimated size fits
+    let Some(mut file_meta) = FileMetaStore::read(db_path) else {
+        return Ok(Some(pruning));
+    };
+    let chunk_ids = file_meta.chunk_ids_by_file();
+    let stats = store.stats()?;
+    let bytes_per_chunk = store.lmdb_page_stats()?.used_bytes / stats.total_chunks.max(1) as u64;
+    let mut estimate = pruning.size_after;
+    for candidate in prune_candidates(
+        &chunk_ids,
+        &project_path.to_string_lossy(),
+        &prune_priority(),
+    ) {
+        if estimate <= budget {
+            break;
+        }
+        for path in &candidate.files {
+            let ids = &chunk_ids[path];
+            store.delete_chunks(ids)?;
+            store.delete_by_path(path)?;
+            for &chunk_id in ids {
+                fts_store.delete_chunk(chunk_id)?;
+            }
+            fts_store.delete_by_path(path)?;
+            file_meta.remove_file(Path::new(path));
+        }
+        estimate = estimate.saturating_sub(candidate.chunks as u64 * bytes_per_chunk);
+        tracing::info!(
+            "✂️  Pruned {} ({} files, {} chunks) to fit the index size budget",
+            candidate.dir,
+            candidate.files.len(),
+            candidate.chunks
+        );
+        pruning.pruned_dirs.push(candidate.dir);
+    }
+
+    if !pruning.pruned_dirs.is_empty() {
+        fts_store.commit()?;
+        // Pruned code isn't coming back, so don't keep its vectors either
+        store.forget_moved_vectors()?;
+        store.build_index()?;
+        file_meta.save(db_path)?;
+        if let Some(mut metadata) = IndexMetadata::load(db_path)? {
+            metadata
+                .pruned_dirs
+                .extend(pruning.pruned_dirs.iter().cloned());
+            metadata.save(db_path)?;
+        }
+    }
+    pruning.size_after = index_size(db_path, store)?;
+    Ok(Some(pruning))
+}
+
+/// Directories of the indexed files that the third tier may remove, in
+/// pruning order: by priority, then the most chunks first
+///
+/// A file's candidate is its outermost directory that is vendored or named in
+/// `priority`.
+fn prune_candidates(
+    chunk_ids: &std::collections::HashMap<String, Vec<u32>>,
+    project_root: &str,
+    priority: &[String],
+) -> Vec<PruneCandidate> {
+    let root = normalize_path_str(project_root);
+    let root = root.trim_end_matches('/');
+    let mut candidates: Vec<PruneCandidate> = Vec::new();
+    for (path, ids) in chunk_ids {
+        let Some(relative) = path
+            .strip_prefix(root)
+            .and_then(|rest| rest.strip_prefix('/'))
+        else {
+            continue;
+        };
+        let dirs: Vec<&str> = relative.split('/').collect();
+        let dirs = &dirs[..dirs.len() - 1];
+        let Some((depth, rank)) = dirs.iter().enumerate().find_map(|(depth, name)| {
+            let rank = if is_vendored_dir(name) {
+                Some(0)
+            } else {
+                priority.iter().position(|p| p == name).map(|p| p + 1)
+            };
+            rank.map(|rank| (depth, rank))
+        }) else {
+            continue;
+        };
+        let dir = dirs[..=depth].join("/");
+        match candidates.iter_mut().find(|c| c.dir == dir) {
+            Some(candidate) => {
+                candidate.files.push(path.clone());
+                candidate.chunks += ids.len();
+            }
+            None => candidates.push(PruneCandidate {
+                dir,
+                rank,
+                files: vec![path.clone()],
+                chunks: ids.len(),
+            }),
+        }
+    }
+    candidates.sort_by(|a, b| {
+        a.rank
+            .cmp(&b.rank)
+            .then_with(|| b.chunks.cmp(&a.chunks))
+            .then_with(|| a.dir.cmp(&b.dir))
+    });
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::normalize_path;
+    use crate::chunker::{Chunk, ChunkKind};
+    use crate::embed::EmbeddedChunk;
+
+    #[test]
+    fn test_prune_to_budget_tiers() -> Result<()> {
+        let project = tempfile::tempdir()?;
+        let db_path = project.path().join(".codesearch.db");
+        let mut store = VectorStore::new(&db_path, 4)?;
+        let mut fts_store = FtsStore::new_with_writer(&db_path)?;
+        let mut file_meta = FileMetaStore::new("test-model".to_string(), 4);
+        IndexMetadata::new("test-model", "TestModel", 4).save(&db_path)?;
+
+        let chunks = [
+            ("src/lib.rs", ChunkKind::Function, "fn lib() { body() }"),
+            ("src/lib.rs", ChunkKind::Comment, "// a long comment"),
+            ("docs/guide.rs", ChunkKind::Function, "fn guide() {}"),
+        ];
+        let mut file_chunks: std::collections::HashMap<_, Vec<u32>> = Default::default();
+        for (name, kind, content) in chunks {
+            let path = project.path().join(name);
+            std::fs::create_dir_all(path.parent().unwrap())?;
+            std::fs::write(&path, content)?;
+            let mut chunk = Chunk::new(content.to_string(), 0, 1, kind, normalize_path(&path));
+            chunk.signature = (kind == ChunkKind::Function).then(|| content[..6].to_string());
+            chunk.context_prev = Some("// before".to_string());
+            let ids = store.insert_chunks_with_ids(vec![EmbeddedChunk::new(
+                chunk,
+                vec![1.0, 0.0, 0.5, 0.2],
+            )])?;
+            fts_store.add_chunk(ids[0], content, &normalize_path(&path), None, "Function")?;
+            file_chunks.entry(path).or_default().extend(ids);
+        }
+        for (path, ids) in file_chunks {
+            file_meta.update_file(&path, ids)?;
+        }
+        fts_store.commit()?;
+        store.build_index()?;
+        file_meta.save(&db_path)?;
+
+        // A generous budget prunes nothing
+        assert!(prune_to_budget(
+            u64::MAX,
+            &db_path,
+            project.path(),
+            &mut store,
+            &mut fts_store
+        )?
+        .is_none());
+
+        let pruning =
+            prune_to_budget(1, &db_path, project.path(), &mut store, &mut fts_store)?.unwrap();
+        assert_eq!(pruning.stripped_chunks, 1);
+        assert_eq!(pruning.pruned_dirs, ["docs"]);
+
+        let chunks: Vec<_> = store
+            .get_chunks_by_file()?
+            .into_values()
+            .flatten()
+            .map(|id| store.get_chunk(id).unwrap().unwrap())
+            .collect();
+        assert_eq!(chunks.len(), 2);
+        assert!(chunks.iter().all(|c| c.context_prev.is_none()));
+        assert_eq!(crate::index::context_lines(&db_path), 0);
+        // The comment has no signature left to keep; the function keeps its content
+        assert!(chunks
+            .iter()
+            .any(|c| c.kind == "Comment" && c.content.is_empty()));
+        assert!(chunks.iter().any(|c| c.content == "fn lib() { body() }"));
+
+        assert_eq!(crate::index::read_pruned_dirs(&db_path), ["docs"]);
+        let file_meta = FileMetaStore::read(&db_path).unwrap();
+        assert_eq!(file_meta.tracked_files().count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_prune_candidates_order() {
+        let chunk_ids = [
+            ("/repo/src/main.rs", 5),
+            ("/repo/docs/guide.rs", 2),
+            ("/repo/src/tests/a.rs", 3),
+            ("/repo/src/tests/b.rs", 3),
+            ("/repo/tests/it.rs", 1),
+            ("/repo/third_party/zlib/docs/x.c", 1),
+        ]
+        .into_iter()
+        .map(|(path, chunks)| (path.to_string(), vec![0; chunks]))
+        .collect();
+        let priority = ["docs".to_string(), "tests".to_string()];
+
+        let candidates = prune_candidates(&chunk_ids, "/repo", &priority);
+        let order: Vec<(&str, usize)> = candidates
+            .iter()
+            .map(|c| (c.dir.as_str(), c.chunks))
+            .collect();
+        assert_eq!(
+            order,
+            [
+                ("third_party", 1),
+                ("docs", 2),
+                ("src/tests", 6),
+                ("tests", 1)
+            ]
+        );
+    }
+}
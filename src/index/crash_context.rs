@@ -0,0 +1,234 @@
+//! What the indexer was doing when the process died
+//!
+//! While indexing runs, the current phase, file and counts are kept in memory.
+//! They are saved to `logs/indexing.json` at every phase and every
+//! `INDEXING_CONTEXT_SAVE_FILES` files, not for each file. The panic hook logs
+//! the in-memory context and saves it with the panic message to
+//! `indexing.json`. A panic that is recovered (such as a chunker panic, which
+//! skips the file) is overwritten by the next save and removed when the run
+//! ends; one that unwinds out of the run is kept as `logs/last_crash.json`.
+//! Native aborts (ONNX runtime, tree-sitter) bypass the hook, so a leftover
+//! `indexing.json` at the next start means the previous run died; it is
+//! reported and kept as `last_crash.json`. Either way, crash reports name the
+//! offending file, or the files since the last save, without bisecting the
+//! repository.
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::constants::{INDEXING_CONTEXT_FILE, INDEXING_CONTEXT_SAVE_FILES, LAST_CRASH_FILE};
+use crate::logger::get_log_dir;
+
+/// Indexing state recorded for crash reports
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexingContext {
+    pub db_path: String,
+    pub phase: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    pub files_done: usize,
+    pub files_total: usize,
+    pub chunks: usize,
+    pub updated_at: String,
+    /// Panic message, or why the run is known to have died
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crash: Option<String>,
+}
+
+impl IndexingContext {
+    /// One-line summary for log messages
+    pub fn describe(&self) -> String {
+        format!(
+            "phase '{}', file {}, {}/{} files, {} chunks",
+            self.phase,
+            self.file.as_deref().unwrap_or("(none)"),
+            self.files_done,
+            self.files_total,
+            self.chunks
+        )
+    }
+}
+
+/// Context of the latest indexing run in progress, for the panic hook
+static CURRENT: Mutex<Option<IndexingContext>> = Mutex::new(None);
+
+/// Records the progress of one indexing run; clears it when dropped
+pub struct IndexingTracker {
+    log_dir: PathBuf,
+    ctx: Mutex<IndexingContext>,
+}
+
+impl IndexingTracker {
+    /// Start tracking a run over `files_total` files of the database at `db_path`
+    ///
+    /// Reports a previous run of this database that died without cleaning up.
+    pub fn start(db_path: &Path, files_total: usize) -> Self {
+        let log_dir = get_log_dir(db_path);
+        if let Some(mut previous) = read_context(&log_dir.join(INDEXING_CONTEXT_FILE)) {
+            tracing::warn!(
+                "⚠️  The previous indexing run ended abnormally ({}); see {}",
+                previous.describe(),
+                log_dir.join(LAST_CRASH_FILE).display()
+            );
+            previous
+                .crash
+                .get_or_insert_with(|| "process exited during indexing".to_string());
+            write_context(&log_dir.join(LAST_CRASH_FILE), &previous);
+        }
+
+        let tracker = Self {
+            log_dir,
+            ctx: Mutex::new(IndexingContext {
+                db_path: db_path.display().to_string(),
+                phase: "starting".to_string(),
+                file: None,
+                files_done: 0,
+                files_total,
+                chunks: 0,
+                updated_at: String::new(),
+                crash: None,
+            }),
+        };
+        tracker.update(true, |_| {});
+        tracker
+    }
+
+    /// Enter a phase that isn't about a single file (saved to disk)
+    pub fn phase(&self, phase: &str) {
+        self.update(true, |ctx| {
+            ctx.phase = phase.to_string();
+            ctx.file = None;
+        });
+    }
+
+    /// Start on `file` after `files_done` files and `chunks` chunks (saved to
+    /// disk every `INDEXING_CONTEXT_SAVE_FILES` files)
+    pub fn file(&self, file: &Path, files_done: usize, chunks: usize) {
+        self.update(
+            files_done.is_multiple_of(INDEXING_CONTEXT_SAVE_FILES),
+            |ctx| {
+                ctx.phase = "reading".to_string();
+                ctx.file = Some(file.display().to_string());
+                ctx.files_done = files_done;
+                ctx.chunks = chunks;
+            },
+        );
+    }
+
+    /// Move to the next step of the current file (in memory only)
+    pub fn step(&self, phase: &str) {
+        self.update(false, |ctx| ctx.phase = phase.to_string());
+    }
+
+    fn update(&self, persist: bool, change: impl FnOnce(&mut IndexingContext)) {
+        let Ok(mut ctx) = self.ctx.lock() else {
+            return;
+        };
+        change(&mut ctx);
+        if persist {
+            ctx.updated_at = chrono::Utc::now().to_rfc3339();
+            if std::fs::create_dir_all(&self.log_dir).is_ok() {
+                write_context(&self.log_dir.join(INDEXING_CONTEXT_FILE), &ctx);
+            }
+        }
+        if let Ok(mut current) = CURRENT.lock() {
+            *current = Some(ctx.clone());
+        }
+    }
+}
+
+impl Drop for IndexingTracker {
+    fn drop(&mut self) {
+        let current = CURRENT.lock().ok().and_then(|mut current| current.take());
+        // Unwinding out of the run: the panic was not recovered
+        if std::thread::panicking() {
+            let ctx = current.or_else(|| self.ctx.lock().ok().map(|ctx| ctx.clone()));
+            if let Some(mut ctx) = ctx {
+                ctx.crash
+                    .get_or_insert_with(|| "panicked during indexing".to_string());
+                write_context(&self.log_dir.join(LAST_CRASH_FILE), &ctx);
+            }
+        }
+        let _ = std::fs::remove_file(self.log_dir.join(INDEXING_CONTEXT_FILE));
+    }
+}
+
+fn read_context(path: &Path) -> Option<IndexingContext> {
+    serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+}
+
+fn write_context(path: &Path, ctx: &IndexingContext) {
+    if let Ok(json) = serde_json::to_string_pretty(ctx) {
+        let _ = std::fs::write(path, json);
+    }
+}
+
+/// Log the indexing context when the process panics
+///
+/// Chains to the previously installed hook, which still prints the panic.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // try_lock: the panic may have happened while the context was locked
+        let ctx = CURRENT.try_lock().ok().and_then(|mut current| {
+            let ctx = current.as_mut()?;
+            ctx.crash = Some(info.to_string());
+            Some(ctx.clone())
+        });
+        if let Some(ctx) = ctx {
+            tracing::error!("💥 Panic while indexing ({}): {}", ctx.describe(), info);
+            // Kept as a crash only if the panic isn't recovered, see `Drop`
+            let log_dir = get_log_dir(Path::new(&ctx.db_path));
+            if std::fs::create_dir_all(&log_dir).is_ok() {
+                write_context(&log_dir.join(INDEXING_CONTEXT_FILE), &ctx);
+            }
+        }
+        previous(info);
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_leftover_context_is_reported_as_crash() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path();
+        let log_dir = get_log_dir(db_path);
+
+        {
+            let tracker = IndexingTracker::start(db_path, 300);
+            tracker.file(Path::new("src/huge.min.js"), 100, 42);
+            tracker.step("embedding");
+            // Files between saves stay in memory
+            tracker.file(Path::new("src/next.js"), 101, 50);
+            let saved = read_context(&log_dir.join(INDEXING_CONTEXT_FILE)).unwrap();
+            assert_eq!(saved.file.as_deref(), Some("src/huge.min.js"));
+            assert_eq!((saved.files_done, saved.chunks), (100, 42));
+            // Simulate an abort: the tracker is never dropped
+            std::mem::forget(tracker);
+        }
+
+        let tracker = IndexingTracker::start(db_path, 3);
+        let crash = read_context(&log_dir.join(LAST_CRASH_FILE)).unwrap();
+        assert_eq!(crash.file.as_deref(), Some("src/huge.min.js"));
+        assert!(crash.crash.is_some());
+
+        // A run that ends normally leaves nothing behind
+        drop(tracker);
+        assert!(!log_dir.join(INDEXING_CONTEXT_FILE).exists());
+
+        // A panic unwinding out of the run is kept as a crash
+        std::fs::remove_file(log_dir.join(LAST_CRASH_FILE)).unwrap();
+        let _ = std::panic::catch_unwind(|| {
+            let tracker = IndexingTracker::start(db_path, 3);
+            tracker.file(Path::new("src/bad.rs"), 2, 7);
+            panic!("chunker bug");
+        });
+        let crash = read_context(&log_dir.join(LAST_CRASH_FILE)).unwrap();
+        assert_eq!(crash.file.as_deref(), Some("src/bad.rs"));
+        assert!(!log_dir.join(INDEXING_CONTEXT_FILE).exists());
+    }
+}
@@ -0,0 +1,178 @@
+//! Size and time estimate for `codesearch index --dry-run`
+//!
+//! Files are chunked exactly as indexing would, without embedding them. A short
+//! benchmark on a sample of chunks not already in the persistent embedding cache
+//! gives the model's throughput on this machine. The projected database size is
+//! a rough model of what arroy, the chunk metadata and tantivy store per chunk.
+
+use anyhow::Result;
+use std::time::Instant;
+
+use crate::chunker::{Chunk, SemanticChunker};
+use crate::constants::DRY_RUN_BENCHMARK_CHUNKS;
+use crate::embed::{embedding_text, FastEmbedder, ModelType, PersistentEmbeddingCache};
+use crate::file::FileInfo;
+
+/// Vector bytes per dimension: f32 item vector plus roughly as much again in
+/// arroy's split nodes
+const VECTOR_BYTES_PER_DIM: u64 = 8;
+/// Serialized chunk metadata besides the content (path, signature, kind, lines)
+const METADATA_OVERHEAD_PER_CHUNK: u64 = 300;
+/// Tantivy index size relative to the indexed text (postings, positions, fast fields)
+const FTS_BYTES_PER_CONTENT_BYTE: f64 = 0.6;
+/// Chunk content per source byte: nested definitions repeat their parents' text
+const CONTENT_BYTES_PER_SOURCE_BYTE: u64 = 2;
+/// Source bytes per chunk, for projecting chunk counts without chunking
+const SOURCE_BYTES_PER_CHUNK: u64 = 1200;
+
+/// Rough estimate of what indexing `files` will produce, from their sizes alone
+///
+/// Used to pre-size the database before a bulk insert, where chunking every
+/// file twice would cost too much.
+pub fn project_from_sizes(files: &[FileInfo]) -> IndexEstimate {
+    let source_bytes: u64 = files.iter().map(|f| f.size).sum();
+    IndexEstimate {
+        files: files.len(),
+        chunks: source_bytes.div_ceil(SOURCE_BYTES_PER_CHUNK) as usize,
+        content_bytes: source_bytes * CONTENT_BYTES_PER_SOURCE_BYTE,
+        ..IndexEstimate::default()
+    }
+}
+
+/// What indexing a set of files would produce
+#[derive(Debug, Default)]
+pub struct IndexEstimate {
+    pub files: usize,
+    pub chunks: usize,
+    /// Total bytes of chunk content
+    pub content_bytes: u64,
+    /// Chunks whose embedding is already in the persistent cache
+    pub cached_chunks: usize,
+    /// Embedding texts of uncached chunks, used for the benchmark
+    sample: Vec<String>,
+}
+
+impl IndexEstimate {
+    /// Chunk `files` and count chunks, content and embedding cache hits
+    pub fn scan(files: &[FileInfo], model_type: ModelType) -> Self {
+        let cache = PersistentEmbeddingCache::open(model_type.short_name()).ok();
+        let mut chunker = SemanticChunker::new(100, 2000, 10);
+        let mut estimate = Self {
+            files: files.len(),
+            ..Self::default()
+        };
+
+        for file in files {
+            let Ok(content) = crate::file::read_source(&file.path) else {
+                continue;
+            };
+            let Ok(chunks) = chunker.chunk_semantic(file.language, &file.path, &content) else {
+                continue;
+            };
+            for chunk in &chunks {
+                estimate.add_chunk(chunk, cache.as_ref());
+            }
+        }
+        estimate
+    }
+
+    fn add_chunk(&mut self, chunk: &Chunk, cache: Option<&PersistentEmbeddingCache>) {
+        self.chunks += 1;
+        self.content_bytes += chunk.content.len() as u64;
+
+        let text = embedding_text(chunk);
+        let cached = cache
+            .and_then(|c| c.get(&Chunk::compute_hash(&text)).ok().flatten())
+            .is_some();
+        if cached {
+            self.cached_chunks += 1;
+        } else if self.sample.len() < DRY_RUN_BENCHMARK_CHUNKS {
+            self.sample.push(text);
+        }
+    }
+
+    /// Chunks that still need to go through the model
+    pub fn uncached_chunks(&self) -> usize {
+        self.chunks - self.cached_chunks
+    }
+
+    /// Measure embedding throughput (chunks/second) on the sample
+    ///
+    /// Returns `None` when every chunk is already cached.
+    pub fn benchmark(
+        &self,
+        model_type: ModelType,
+        cache_dir: &std::path::Path,
+    ) -> Result<Option<f64>> {
+        if self.sample.is_empty() {
+            return Ok(None);
+        }
+        let mut embedder = FastEmbedder::with_cache_dir(model_type, Some(cache_dir))?;
+        // Warm-up: the first inference pays for session/graph initialization
+        embedder.embed_batch(self.sample[..1].to_vec())?;
+
+        let start = Instant::now();
+        embedder.embed_batch(self.sample.clone())?;
+        let elapsed = start.elapsed().as_secs_f64().max(1e-3);
+        Ok(Some(self.sample.len() as f64 / elapsed))
+    }
+
+    /// Projected bytes the new chunks add to the database
+    pub fn projected_db_bytes(&self, dimensions: usize, prose_dimensions: Option<usize>) -> u64 {
+        let dims = (dimensions + prose_dimensions.unwrap_or(0)) as u64;
+        let vectors = self.chunks as u64 * dims * VECTOR_BYTES_PER_DIM;
+        let metadata = self.content_bytes + self.chunks as u64 * METADATA_OVERHEAD_PER_CHUNK;
+        let fts = (self.content_bytes as f64 * FTS_BYTES_PER_CONTENT_BYTE) as u64;
+        vectors + metadata + fts
+    }
+}
+
+/// Format seconds as "45s", "12m 30s" or "2h 05m"
+pub fn format_duration(secs: f64) -> String {
+    let secs = secs.round() as u64;
+    if secs >= 3600 {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    } else if secs >= 60 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_projected_size_and_formatting() {
+        let estimate = IndexEstimate {
+            files: 10,
+            chunks: 1000,
+            content_bytes: 1_000_000,
+            ..IndexEstimate::default()
+        };
+        // 1000 * 384 * 8 + (1_000_000 + 1000 * 300) + 600_000
+        assert_eq!(estimate.projected_db_bytes(384, None), 4_972_000);
+        assert!(estimate.projected_db_bytes(384, Some(768)) > 4_972_000);
+        assert_eq!(estimate.uncached_chunks(), 1000);
+
+        let files = vec![
+            FileInfo {
+                path: "a.rs".into(),
+                language: crate::file::Language::Rust,
+                size: 12_000,
+            },
+            FileInfo {
+                path: "b.rs".into(),
+                language: crate::file::Language::Rust,
+                size: 1,
+            },
+        ];
+        let projected = project_from_sizes(&files);
+        assert_eq!((projected.chunks, projected.content_bytes), (11, 24_002));
+
+        assert_eq!(format_duration(42.4), "42s");
+        assert_eq!(format_duration(750.0), "12m 30s");
+        assert_eq!(format_duration(7500.0), "2h 05m");
+    }
+}
@@ -0,0 +1,233 @@
+//! User commands run around indexing
+//!
+//! Hooks are shell commands configured in the environment, never in the
+//! repository, so opening an untrusted project can't run anything:
+//!
+//! - `CODESEARCH_PRE_INDEX_HOOK` runs before an indexing run (`codesearch
+//!   index`, or the catch-up when a server starts) walks the files, e.g. to
+//!   generate code; when it fails, the run is aborted.
+//! - `CODESEARCH_POST_INDEX_HOOK` runs after an indexing run that changed the
+//!   index.
+//! - `CODESEARCH_POST_REFRESH_HOOK` runs after a server or daemon refresh
+//!   (the startup catch-up, a branch switch or a resync) that changed the
+//!   index, e.g. to export it to CI storage. It runs in the background, so
+//!   searches and shutdown don't wait for it. File watcher batches don't run
+//!   it: they are too frequent.
+//!
+//! Hooks run through the shell in the project root, with `CODESEARCH_HOOK`,
+//! `CODESEARCH_PROJECT_PATH` and `CODESEARCH_DB_PATH` set. Their output is
+//! logged line by line, and they are killed after `CODESEARCH_HOOK_TIMEOUT_SECS`.
+
+use anyhow::{anyhow, Result};
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Hooks are killed after this long, unless configured
+const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 300;
+
+/// Output lines of a hook kept in the log
+const MAX_LOGGED_LINES: usize = 200;
+
+/// When a hook runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hook {
+    PreIndex,
+    PostIndex,
+    PostRefresh,
+}
+
+impl Hook {
+    pub fn name(self) -> &'static str {
+        match self {
+            Hook::PreIndex => "pre_index",
+            Hook::PostIndex => "post_index",
+            Hook::PostRefresh => "post_refresh",
+        }
+    }
+
+    fn env_var(self) -> &'static str {
+        match self {
+            Hook::PreIndex => "CODESEARCH_PRE_INDEX_HOOK",
+            Hook::PostIndex => "CODESEARCH_POST_INDEX_HOOK",
+            Hook::PostRefresh => "CODESEARCH_POST_REFRESH_HOOK",
+        }
+    }
+
+    /// Command configured for this hook
+    pub fn command(self) -> Option<String> {
+        std::env::var(self.env_var())
+            .ok()
+            .map(|command| command.trim().to_string())
+            .filter(|command| !command.is_empty())
+    }
+}
+
+fn hook_timeout() -> Duration {
+    let secs = std::env::var("CODESEARCH_HOOK_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// Run `hook` for the project, if it is configured
+///
+/// Fails when the command can't start, exits non-zero or times out.
+pub async fn run(hook: Hook, project_path: &Path, db_path: &Path) -> Result<()> {
+    let Some(command) = hook.command() else {
+        return Ok(());
+    };
+    let project_path = project_path.to_path_buf();
+    let db_path = db_path.to_path_buf();
+    tokio::task::spawn_blocking(move || {
+        run_command(hook, &command, &project_path, &db_path, hook_timeout())
+    })
+    .await?
+}
+
+/// Run a post hook; its failure is logged, as the index is already written
+pub async fn run_post(hook: Hook, project_path: &Path, db_path: &Path) {
+    if let Err(e) = run(hook, project_path, db_path).await {
+        tracing::warn!("⚠️  {}", e);
+    }
+}
+
+/// [`run_post`] in the background, so the refresh that triggered it can
+/// return (and the watcher stop) without waiting for the hook
+pub fn spawn_post(hook: Hook, project_path: &Path, db_path: &Path) {
+    if hook.command().is_none() {
+        return;
+    }
+    let project_path = project_path.to_path_buf();
+    let db_path = db_path.to_path_buf();
+    tokio::spawn(async move { run_post(hook, &project_path, &db_path).await });
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
+
+fn run_command(
+    hook: Hook,
+    command: &str,
+    project_path: &Path,
+    db_path: &Path,
+    timeout: Duration,
+) -> Result<()> {
+    tracing::info!("🪝 Running {} hook: {}", hook.name(), command);
+    let start = Instant::now();
+    let mut child = shell(command)
+        .current_dir(project_path)
+        .env("CODESEARCH_HOOK", hook.name())
+        .env("CODESEARCH_PROJECT_PATH", project_path)
+        .env("CODESEARCH_DB_PATH", db_path)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| anyhow!("{} hook could not start: {}", hook.name(), e))?;
+
+    // Drain both pipes while waiting, so a chatty hook can't block on a full pipe
+    let stdout = child.stdout.take().map(|out| log_output(hook, out));
+    let stderr = child.stderr.take().map(|err| log_output(hook, err));
+
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break Some(status);
+        }
+        if start.elapsed() >= timeout {
+            let _ = child.kill();
+            let _ = child.wait();
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    };
+    // A killed hook's own children may keep the pipes open, so don't wait for them then
+    if status.is_some() {
+        for reader in [stdout, stderr].into_iter().flatten() {
+            let _ = reader.join();
+        }
+    }
+
+    match status {
+        None => Err(anyhow!(
+            "{} hook timed out after {}s and was killed",
+            hook.name(),
+            timeout.as_secs()
+        )),
+        Some(status) if !status.success() => {
+            Err(anyhow!("{} hook failed ({})", hook.name(), status))
+        }
+        Some(_) => {
+            tracing::info!(
+                "🪝 {} hook finished in {:.1}s",
+                hook.name(),
+                start.elapsed().as_secs_f64()
+            );
+            Ok(())
+        }
+    }
+}
+
+/// Log the lines of a hook's output from a background thread
+fn log_output(hook: Hook, output: impl Read + Send + 'static) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut lines = BufReader::new(output).lines();
+        for line in lines.by_ref().take(MAX_LOGGED_LINES).map_while(|l| l.ok()) {
+            tracing::info!("🪝 [{}] {}", hook.name(), line);
+        }
+        let skipped = lines.count();
+        if skipped > 0 {
+            tracing::info!("🪝 [{}] ... {} more lines", hook.name(), skipped);
+        }
+    })
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_hook_command() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().to_path_buf();
+        let db = project.join(".codesearch.db");
+        let timeout = Duration::from_secs(10);
+
+        run_command(
+            Hook::PostIndex,
+            "echo \"$CODESEARCH_HOOK $CODESEARCH_DB_PATH\" > hook.out",
+            &project,
+            &db,
+            timeout,
+        )
+        .unwrap();
+        let written = std::fs::read_to_string(dir.path().join("hook.out")).unwrap();
+        assert_eq!(written.trim(), format!("post_index {}", db.display()));
+
+        let failed = run_command(Hook::PreIndex, "exit 3", &project, &db, timeout);
+        assert!(failed
+            .unwrap_err()
+            .to_string()
+            .contains("pre_index hook failed"));
+
+        let slow = run_command(
+            Hook::PostRefresh,
+            "sleep 5",
+            &project,
+            &db,
+            Duration::from_millis(200),
+        );
+        assert!(slow.unwrap_err().to_string().contains("timed out"));
+    }
+}
@@ -15,20 +15,25 @@
 //!
 #![allow(dead_code)]
 
-use crate::cache::{normalize_path, normalize_path_str};
-use crate::constants::{DB_DIR_NAME, DEFAULT_FSW_DEBOUNCE_MS, FILE_META_DB_NAME, WRITER_LOCK_FILE};
-use crate::embed::ModelType;
+use crate::cache::{normalize_path, normalize_path_str, IndexMetadata};
+use crate::constants::{
+    DB_DIR_NAME, DEFAULT_FSW_DEBOUNCE_MS, FILE_META_DB_NAME, FSW_JOURNAL_FILE, REFRESH_BATCH_FILES,
+    WRITER_LOCK_FILE,
+};
+use crate::embed::{backoff_delay, EmbeddingService, ModelLoadState, ModelType};
 use crate::fts::FtsStore;
 use crate::vectordb::VectorStore;
 use crate::watch::{FileEvent, FileWatcher, GitHeadWatcher};
 use std::collections::HashSet;
 use std::fs::File;
 use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicI64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use tokio::sync::{Mutex, RwLock};
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, error, info, warn};
 
+use super::crash_context::IndexingTracker;
 // Import Result from the parent module
 use super::Result;
 
@@ -122,6 +127,159 @@ pub fn release_writer_lock(_lock: File) {
     debug!("🔓 Writer lock released");
 }
 
+// === Batch Journal ===
+
+/// File watcher batch recorded on disk while it is processed (or when
+/// shutdown interrupts buffering), replayed at the next start
+#[derive(Debug, Default, serde::Serialize, serde::Deserialize)]
+struct PendingBatch {
+    to_index: Vec<PathBuf>,
+    to_remove: Vec<PathBuf>,
+}
+
+/// Record a batch in the journal; failures only cost the replay, so they are logged
+fn write_journal(db_path: &Path, to_index: &[PathBuf], to_remove: &[PathBuf]) {
+    let batch = PendingBatch {
+        to_index: to_index.to_vec(),
+        to_remove: to_remove.to_vec(),
+    };
+    let result = serde_json::to_string(&batch)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| Ok(std::fs::write(db_path.join(FSW_JOURNAL_FILE), json)?));
+    if let Err(e) = result {
+        warn!("⚠️  Failed to write file watcher journal: {}", e);
+    }
+}
+
+/// Remove the journal after its batch completed
+fn clear_journal(db_path: &Path) {
+    let _ = std::fs::remove_file(db_path.join(FSW_JOURNAL_FILE));
+}
+
+/// Load and remove the journal left by an interrupted session
+fn take_journal(db_path: &Path) -> Option<PendingBatch> {
+    let path = db_path.join(FSW_JOURNAL_FILE);
+    let content = std::fs::read_to_string(&path).ok()?;
+    let _ = std::fs::remove_file(&path);
+    match serde_json::from_str(&content) {
+        Ok(batch) => Some(batch),
+        Err(e) => {
+            warn!("⚠️  Ignoring unreadable file watcher journal: {}", e);
+            None
+        }
+    }
+}
+
+/// Load the file metadata of a database, using the model recorded in metadata.json
+fn load_file_meta(db_path: &Path) -> Option<crate::cache::FileMetaStore> {
+    let metadata = IndexMetadata::load(db_path).ok()??;
+    crate::cache::FileMetaStore::load_or_create(
+        db_path,
+        &metadata.model_short_name,
+        metadata.dimensions,
+    )
+    .ok()
+}
+
+/// Live state of the background work writing to the stores
+///
+/// Lets `index_status` tell "ready and live" apart from "ready but frozen":
+/// whether the file watcher runs, how many events wait for the next batch,
+/// when the index was last brought up to date and what is running right now
+/// (with a percentage while a large refresh is in progress).
+#[derive(Debug, Default)]
+pub struct IndexActivity {
+    refreshing: AtomicBool,
+    files_done: AtomicUsize,
+    files_total: AtomicUsize,
+    watcher_active: AtomicBool,
+    pending_events: AtomicUsize,
+    /// Unix seconds of the last completed refresh or batch (0 = none yet)
+    last_refresh: AtomicI64,
+    operation: std::sync::Mutex<Option<&'static str>>,
+}
+
+impl IndexActivity {
+    fn start_progress(&self, files_total: usize) {
+        self.files_done.store(0, Ordering::SeqCst);
+        self.files_total.store(files_total, Ordering::SeqCst);
+        self.refreshing.store(true, Ordering::SeqCst);
+    }
+
+    fn advance(&self, files: usize) {
+        self.files_done.fetch_add(files, Ordering::SeqCst);
+    }
+
+    fn finish_progress(&self) {
+        self.refreshing.store(false, Ordering::SeqCst);
+    }
+
+    /// `(files_done, files_total)` while a refresh is running
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        if !self.refreshing.load(Ordering::SeqCst) {
+            return None;
+        }
+        Some((
+            self.files_done.load(Ordering::SeqCst),
+            self.files_total.load(Ordering::SeqCst),
+        ))
+    }
+
+    /// Mark `name` as the running operation until the returned guard is dropped
+    fn begin(&self, name: &'static str) -> OperationGuard<'_> {
+        if let Ok(mut operation) = self.operation.lock() {
+            *operation = Some(name);
+        }
+        OperationGuard(self)
+    }
+
+    /// Background operation currently writing to the stores
+    pub fn current_operation(&self) -> Option<&'static str> {
+        self.operation.lock().ok().and_then(|operation| *operation)
+    }
+
+    fn mark_refreshed(&self) {
+        self.last_refresh
+            .store(chrono::Utc::now().timestamp(), Ordering::SeqCst);
+    }
+
+    /// When the index was last brought up to date by this process
+    pub fn last_refresh(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        match self.last_refresh.load(Ordering::SeqCst) {
+            0 => None,
+            secs => chrono::DateTime::from_timestamp(secs, 0),
+        }
+    }
+
+    fn set_watcher_active(&self, active: bool) {
+        self.watcher_active.store(active, Ordering::SeqCst);
+    }
+
+    pub fn watcher_active(&self) -> bool {
+        self.watcher_active.load(Ordering::SeqCst)
+    }
+
+    fn set_pending_events(&self, count: usize) {
+        self.pending_events.store(count, Ordering::SeqCst);
+    }
+
+    /// File events buffered for the next watcher batch
+    pub fn pending_events(&self) -> usize {
+        self.pending_events.load(Ordering::SeqCst)
+    }
+}
+
+/// Clears the running operation of an [`IndexActivity`] when dropped
+struct OperationGuard<'a>(&'a IndexActivity);
+
+impl Drop for OperationGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut operation) = self.0.operation.lock() {
+            *operation = None;
+        }
+    }
+}
+
 /// Shared stores for concurrent access between MCP service and file watcher.
 ///
 /// Uses RwLock to allow multiple concurrent readers (searches) with exclusive writer (indexing).
@@ -133,24 +291,64 @@ pub struct SharedStores {
     writer_lock: Option<File>,
     /// Whether this instance is in readonly mode
     pub readonly: bool,
+    /// State of the refresh/watcher work writing to these stores
+    pub activity: IndexActivity,
+    /// Prose model of a dual-embedded database, loaded at first use
+    prose_service: Mutex<ProseService>,
+}
+
+/// Lazily loaded prose model, with the state of its last load
+struct ProseService {
+    /// `None` until loaded, and for single-model databases
+    service: Option<EmbeddingService>,
+    state: ModelLoadState,
+}
+
+impl Default for ProseService {
+    fn default() -> Self {
+        Self {
+            service: None,
+            state: ModelLoadState::NotLoaded,
+        }
+    }
 }
 
 impl SharedStores {
     /// Create new shared stores from the database path (read-write mode).
     ///
     /// This acquires a writer lock. If another process already has the lock,
-    /// this will fail with an error.
+    /// this will fail with an error. A corrupted database is quarantined and
+    /// replaced by an empty one (see `quarantine`), which the next refresh fills.
     pub fn new(db_path: &Path, dimensions: usize) -> Result<Self> {
         // Try to acquire writer lock
-        let lock = acquire_writer_lock(db_path);
+        let mut lock = acquire_writer_lock(db_path);
         if lock.is_none() {
             return Err(anyhow::anyhow!(
                 "Database is locked by another process. Use new_readonly() instead."
             ));
         }
 
-        let vector_store = VectorStore::new(db_path, dimensions)?;
-        let fts_store = FtsStore::new_with_writer(db_path)?;
+        let open = || -> Result<(VectorStore, FtsStore)> {
+            Ok((
+                VectorStore::new(db_path, dimensions)?,
+                FtsStore::new_with_writer(db_path)?,
+            ))
+        };
+        let (vector_store, fts_store) = match open() {
+            Err(e) if super::quarantine::should_quarantine(&e) => {
+                // Release the lock file so the directory can be moved
+                drop(lock.take());
+                super::quarantine::quarantine_database(db_path, &e)?;
+                lock = acquire_writer_lock(db_path);
+                if lock.is_none() {
+                    return Err(anyhow::anyhow!(
+                        "Database is locked by another process. Use new_readonly() instead."
+                    ));
+                }
+                open()?
+            }
+            result => result?,
+        };
 
         info!("📦 SharedStores created in read-write mode");
 
@@ -159,6 +357,8 @@ impl SharedStores {
             fts_store: Arc::new(RwLock::new(fts_store)),
             writer_lock: lock,
             readonly: false,
+            activity: IndexActivity::default(),
+            prose_service: Mutex::default(),
         })
     }
 
@@ -177,9 +377,113 @@ impl SharedStores {
             fts_store: Arc::new(RwLock::new(fts_store)),
             writer_lock: None,
             readonly: true,
+            activity: IndexActivity::default(),
+            prose_service: Mutex::default(),
         })
     }
 
+    /// The prose model of a dual-embedded database, loaded once for all batches
+    ///
+    /// `None` for single-model databases, or while the model fails to load:
+    /// the chunks then only get code vectors, and prose-routed queries still
+    /// find them through the code space. A failed load is retried by a later
+    /// batch once its backoff has passed, so a transient download failure
+    /// doesn't drop prose vectors until restart.
+    pub(crate) async fn prose_service(
+        &self,
+        db_path: &Path,
+    ) -> Option<tokio::sync::MappedMutexGuard<'_, EmbeddingService>> {
+        let mut prose = self.prose_service.lock().await;
+        let previous_failures = match &prose.state {
+            ModelLoadState::NotLoaded => Some(0),
+            ModelLoadState::Failed {
+                failures, retry_at, ..
+            } if std::time::Instant::now() >= *retry_at => Some(*failures),
+            _ => None,
+        };
+        if let Some(previous_failures) = previous_failures {
+            prose.state = ModelLoadState::Downloading { attempt: 1 };
+            match super::load_prose_service(db_path).await {
+                Ok(service) => {
+                    prose.service = service;
+                    prose.state = ModelLoadState::Ready;
+                }
+                Err(e) => {
+                    let failures = previous_failures + 1;
+                    let delay = backoff_delay(
+                        std::time::Duration::from_millis(
+                            crate::constants::MODEL_LOAD_BASE_BACKOFF_MS,
+                        ),
+                        failures,
+                        std::time::Duration::from_millis(
+                            crate::constants::MODEL_LOAD_MAX_BACKOFF_MS,
+                        ),
+                    );
+                    warn!(
+                        "⚠️  Prose model failed to load, skipping prose embeddings (retry in {:?}): {}",
+                        delay, e
+                    );
+                    prose.state = ModelLoadState::Failed {
+                        message: e.to_string(),
+                        failures,
+                        retry_at: std::time::Instant::now() + delay,
+                    };
+                }
+            }
+        }
+        tokio::sync::MutexGuard::try_map(prose, |prose| prose.service.as_mut()).ok()
+    }
+
+    /// Re-read what the writer instance committed (readonly mode)
+    ///
+    /// See [`VectorStore::refresh_snapshot`] and [`FtsStore::reload_if_changed`].
+    /// Each store is checked under its read lock first, so searches aren't
+    /// held up while nothing changed, and the full-text index is reloaded even
+    /// when the vector store fails to. Returns whether anything changed.
+    pub async fn refresh_snapshot(&self) -> Result<bool> {
+        let vectors = async {
+            if !self.vector_store.read().await.has_new_commits() {
+                return Ok(false);
+            }
+            self.vector_store.write().await.refresh_snapshot()
+        }
+        .await;
+        let fts = async {
+            if !self.fts_store.read().await.has_new_commits()? {
+                return Ok(false);
+            }
+            self.fts_store.write().await.reload_if_changed()
+        }
+        .await;
+        Ok(vectors? | fts?)
+    }
+
+    /// Re-read the writer instance's commits every
+    /// `CODESEARCH_READONLY_REFRESH_MS` until `cancel_token` is cancelled
+    /// (readonly mode)
+    pub fn follow_writer(self: &Arc<Self>, cancel_token: CancellationToken) {
+        let refresh_ms = std::env::var("CODESEARCH_READONLY_REFRESH_MS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(crate::constants::DEFAULT_READONLY_REFRESH_INTERVAL_MS);
+        let stores = self.clone();
+        tokio::spawn(async move {
+            let interval = std::time::Duration::from_millis(refresh_ms.max(100));
+            loop {
+                tokio::select! {
+                    _ = tokio::time::sleep(interval) => {
+                        match stores.refresh_snapshot().await {
+                            Ok(true) => debug!("🔄 Picked up changes from the writer instance"),
+                            Ok(false) => {}
+                            Err(e) => warn!("⚠️ Could not refresh readonly snapshot: {}", e),
+                        }
+                    }
+                    _ = cancel_token.cancelled() => break,
+                }
+            }
+        });
+    }
+
     /// Try to create shared stores, falling back to readonly mode if locked.
     ///
     /// Returns (SharedStores, is_readonly) tuple.
@@ -224,6 +528,8 @@ pub struct IndexManager {
     git_head_watcher: Option<GitHeadWatcher>,
     /// Shared stores for concurrent access
     stores: Arc<SharedStores>,
+    /// The watcher task, once started, for shutdown to wait on
+    watcher_task: std::sync::Mutex<Option<tokio::task::JoinHandle<()>>>,
 }
 
 impl IndexManager {
@@ -274,7 +580,7 @@ impl IndexManager {
 
         // Create file watcher (but don't start it yet)
         debug!("👀 Creating file watcher...");
-        let watcher = FileWatcher::new(path_buf.clone());
+        let watcher = FileWatcher::new(path_buf.clone()).with_db_path(&db_path);
         let watcher = Arc::new(Mutex::new(watcher));
 
         // Create Git HEAD watcher for branch change detection
@@ -289,6 +595,7 @@ impl IndexManager {
             watcher,
             git_head_watcher: Some(git_head_watcher),
             stores,
+            watcher_task: std::sync::Mutex::new(None),
         })
     }
 
@@ -297,6 +604,33 @@ impl IndexManager {
         self.stores.clone()
     }
 
+    /// Wait (bounded) for the file watcher task to stop.
+    ///
+    /// Call after cancelling the watcher, before the process exits, so the
+    /// batch it is writing is committed and the vector index rebuilt, and the
+    /// events it buffered are journaled. Returns `false` on timeout; the batch
+    /// then stays in the journal and is replayed at the next start.
+    pub async fn wait_for_idle(&self, timeout: std::time::Duration) -> bool {
+        let task = self
+            .watcher_task
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        let Some(task) = task else {
+            return true;
+        };
+        match tokio::time::timeout(timeout, task).await {
+            Ok(_) => true,
+            Err(_) => {
+                warn!(
+                    "⏱️  Index update still running after {}s; it will be replayed at next start",
+                    timeout.as_secs()
+                );
+                false
+            }
+        }
+    }
+
     /// Find and create Git HEAD watcher for branch change detection.
     ///
     /// This method attempts to find the git repository root and creates
@@ -369,7 +703,7 @@ impl IndexManager {
 
         // Create file watcher (but don't start it yet)
         debug!("👀 Creating file watcher...");
-        let watcher = FileWatcher::new(path_buf.clone());
+        let watcher = FileWatcher::new(path_buf.clone()).with_db_path(&db_path);
         let watcher = Arc::new(Mutex::new(watcher));
 
         // Create Git HEAD watcher for branch change detection
@@ -384,6 +718,7 @@ impl IndexManager {
             watcher,
             git_head_watcher: Some(git_head_watcher),
             stores,
+            watcher_task: std::sync::Mutex::new(None),
         })
     }
 
@@ -397,36 +732,26 @@ impl IndexManager {
         stores: &SharedStores,
     ) -> Result<()> {
         use crate::cache::FileMetaStore;
-        use crate::chunker::SemanticChunker;
-        use crate::embed::EmbeddingService;
         use crate::file::FileWalker;
 
         info!("🔄 Performing incremental refresh with shared stores...");
         let start = std::time::Instant::now();
+        let _operation = stores.activity.begin("incremental_refresh");
+
+        // Like `codesearch index`, let the pre-index hook generate code first
+        super::hooks::run(super::hooks::Hook::PreIndex, codebase_path, db_path).await?;
 
         // Read model metadata
-        let metadata_path = db_path.join("metadata.json");
-        let (model_name, dimensions) = if metadata_path.exists() {
-            let content = std::fs::read_to_string(&metadata_path)?;
-            let json: serde_json::Value = serde_json::from_str(&content)?;
-            let model = json
-                .get("model_short_name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("minilm-l6-q");
-            let dims = json
-                .get("dimensions")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(384) as usize;
-            (model.to_string(), dims)
-        } else {
-            return Err(anyhow::anyhow!("No metadata.json found in database"));
-        };
+        let metadata = IndexMetadata::require(db_path)?;
+        let (model_name, dimensions) = (metadata.model_short_name, metadata.dimensions);
 
         // Load FileMetaStore
         let mut file_meta_store = FileMetaStore::load_or_create(db_path, &model_name, dimensions)?;
 
         // Walk files
-        let walker = FileWalker::new(codebase_path.to_path_buf());
+        let walker = FileWalker::new(codebase_path.to_path_buf())
+            .with_languages(super::read_language_filter(db_path))
+            .with_excluded_dirs(super::read_pruned_dirs(db_path));
         let (files, _stats) = walker.walk()?;
 
         // Find changed and deleted files
@@ -456,27 +781,29 @@ impl IndexManager {
         // If no changes, we're done
         if changed_files.is_empty() && deleted_files.is_empty() {
             info!("✅ Index is up to date!");
+            stores.activity.mark_refreshed();
             return Ok(());
         }
 
-        // Delete chunks for deleted files
+        // Delete chunks for deleted files. Deleting by path as well catches
+        // chunks the file metadata lost track of.
         for (file_path, chunk_ids) in &deleted_files {
-            if !chunk_ids.is_empty() {
-                debug!("🗑️  Deleting {} chunks for: {}", chunk_ids.len(), file_path);
+            debug!("🗑️  Deleting {} chunks for: {}", chunk_ids.len(), file_path);
 
-                // Delete from vector store
-                {
-                    let mut store = stores.vector_store.write().await;
-                    store.delete_chunks(chunk_ids)?;
-                }
+            // Delete from vector store
+            {
+                let mut store = stores.vector_store.write().await;
+                store.delete_chunks(chunk_ids)?;
+                store.delete_by_path(file_path)?;
+            }
 
-                // Delete from FTS
-                {
-                    let mut fts_store = stores.fts_store.write().await;
-                    for chunk_id in chunk_ids {
-                        fts_store.delete_chunk(*chunk_id)?;
-                    }
+            // Delete from FTS
+            {
+                let mut fts_store = stores.fts_store.write().await;
+                for chunk_id in chunk_ids {
+                    fts_store.delete_chunk(*chunk_id)?;
                 }
+                fts_store.delete_by_path(file_path)?;
             }
             file_meta_store.remove_file(Path::new(file_path));
         }
@@ -484,26 +811,27 @@ impl IndexManager {
         // Delete old chunks for changed files
         for file in &changed_files {
             let (_, old_chunk_ids) = file_meta_store.check_file(&file.path)?;
-            if !old_chunk_ids.is_empty() {
-                debug!(
-                    "🔄 Deleting {} old chunks for: {}",
-                    old_chunk_ids.len(),
-                    file.path.display()
-                );
+            let file_path = normalize_path(&file.path);
+            debug!(
+                "🔄 Deleting {} old chunks for: {}",
+                old_chunk_ids.len(),
+                file_path
+            );
 
-                // Delete from vector store
-                {
-                    let mut store = stores.vector_store.write().await;
-                    store.delete_chunks(&old_chunk_ids)?;
-                }
+            // Delete from vector store
+            {
+                let mut store = stores.vector_store.write().await;
+                store.delete_chunks(&old_chunk_ids)?;
+                store.delete_by_path(&file_path)?;
+            }
 
-                // Delete from FTS
-                {
-                    let mut fts_store = stores.fts_store.write().await;
-                    for chunk_id in &old_chunk_ids {
-                        fts_store.delete_chunk(*chunk_id)?;
-                    }
+            // Delete from FTS
+            {
+                let mut fts_store = stores.fts_store.write().await;
+                for chunk_id in &old_chunk_ids {
+                    fts_store.delete_chunk(*chunk_id)?;
                 }
+                fts_store.delete_by_path(&file_path)?;
             }
         }
 
@@ -513,100 +841,203 @@ impl IndexManager {
             fts_store.commit()?;
         }
 
-        // Chunk changed files
+        // Chunk, embed and commit changed files in batches so large refreshes
+        // (e.g. the initial index of a freshly created database) become
+        // searchable incrementally and report progress through index_status.
+        // Recently touched files go first so they are searchable soonest.
         if !changed_files.is_empty() {
             info!("🔄 Processing {} changed files...", changed_files.len());
+            crate::file::order_by_recency(codebase_path, &mut changed_files);
+            stores.activity.start_progress(changed_files.len());
+            let result =
+                Self::index_changed_files(db_path, stores, &mut file_meta_store, &changed_files)
+                    .await;
+            stores.activity.finish_progress();
+            result?;
+        }
 
-            let mut chunker = SemanticChunker::new(100, 2000, 10);
-            let mut all_chunks = Vec::new();
+        // Save file metadata
+        file_meta_store.save(db_path)?;
+
+        if super::budget::size_budget().is_some() {
+            let mut store = stores.vector_store.write().await;
+            let mut fts_store = stores.fts_store.write().await;
+            match super::budget::enforce_size_budget(
+                db_path,
+                codebase_path,
+                &mut store,
+                &mut fts_store,
+            ) {
+                Ok(Some(pruning)) if pruning.over_budget() => warn!(
+                    "✂️  {}; still over CODESEARCH_INDEX_MAX_MB",
+                    pruning.summary()
+                ),
+                Ok(Some(pruning)) => info!("✂️  {}", pruning.summary()),
+                Ok(None) => {}
+                Err(e) => warn!("⚠️  Failed to enforce the index size budget: {}", e),
+            }
+        }
+        if let Err(e) =
+            super::size_history::SizeHistory::record(db_path, &*stores.vector_store.read().await)
+        {
+            warn!("Failed to record index size: {}", e);
+        }
+
+        stores.activity.mark_refreshed();
+
+        let elapsed = start.elapsed();
+        info!(
+            "✅ Incremental refresh completed in {:.2}s",
+            elapsed.as_secs_f64()
+        );
+
+        super::hooks::spawn_post(super::hooks::Hook::PostRefresh, codebase_path, db_path);
 
-            for file in &changed_files {
-                let content = match std::fs::read_to_string(&file.path) {
+        Ok(())
+    }
+
+    /// Chunk, embed and insert changed files, committing every `REFRESH_BATCH_FILES` files
+    ///
+    /// File metadata is saved after each batch, so an interrupted refresh
+    /// resumes where it stopped instead of starting over.
+    async fn index_changed_files(
+        db_path: &Path,
+        stores: &SharedStores,
+        file_meta_store: &mut crate::cache::FileMetaStore,
+        changed_files: &[crate::file::FileInfo],
+    ) -> Result<()> {
+        use crate::chunker::SemanticChunker;
+
+        let mut chunker =
+            SemanticChunker::new(100, 2000, 10).with_context_lines(super::context_lines(db_path));
+        let mut embedding_service: Option<EmbeddingService> = None;
+        let mut total_chunks = 0;
+        let tracker = IndexingTracker::start(db_path, changed_files.len());
+        let mut files_done = 0;
+
+        let projected = super::estimate::project_from_sizes(changed_files);
+        {
+            let mut store = stores.vector_store.write().await;
+            let added = projected.projected_db_bytes(store.dimensions(), store.prose_dimensions());
+            store.reserve(added)?;
+        }
+
+        for batch in changed_files.chunks(REFRESH_BATCH_FILES) {
+            let mut all_chunks = Vec::new();
+            for file in batch {
+                tracker.file(&file.path, files_done, total_chunks);
+                files_done += 1;
+                let content = match crate::file::read_source(&file.path) {
                     Ok(c) => c,
                     Err(_) => continue,
                 };
+                tracker.step("chunking");
                 let chunks = chunker.chunk_semantic(file.language, &file.path, &content)?;
                 all_chunks.extend(chunks);
             }
+            tracker.phase("embedding and storing a batch of changed files");
 
-            if !all_chunks.is_empty() {
-                // Embed chunks
-                info!("📦 Embedding {} chunks...", all_chunks.len());
-                let cache_dir = crate::constants::get_global_models_cache_dir()?;
-                let mut embedding_service = EmbeddingService::with_cache_dir(
-                    ModelType::default(),
-                    Some(cache_dir.as_path()),
-                )?;
-                let embedded_chunks = embedding_service.embed_chunks(all_chunks)?;
-
-                // Insert into vector store
-                let chunk_ids = {
-                    let mut store = stores.vector_store.write().await;
-                    let ids = store.insert_chunks_with_ids(embedded_chunks.clone())?;
-                    store.build_index()?;
-                    ids
-                };
+            if all_chunks.is_empty() {
+                // Batch produced 0 chunks — still track the files so they are
+                // not flagged as unindexed on every subsequent run.
+                for file in batch {
+                    file_meta_store.update_file(&file.path, vec![])?;
+                }
+                stores.activity.advance(batch.len());
+                continue;
+            }
 
-                // Insert into FTS
-                {
-                    let mut fts_store = stores.fts_store.write().await;
-                    for (chunk, chunk_id) in embedded_chunks.iter().zip(chunk_ids.iter()) {
-                        let path_str = chunk.chunk.path.to_string();
-                        let signature = chunk.chunk.signature.as_deref();
-                        let kind = format!("{:?}", chunk.chunk.kind);
-                        fts_store.add_chunk(
-                            *chunk_id,
-                            &chunk.chunk.content,
-                            &path_str,
-                            signature,
-                            &kind,
-                        )?;
-                    }
-                    fts_store.commit()?;
+            // Embed chunks
+            debug!("📦 Embedding {} chunks...", all_chunks.len());
+            let service = match embedding_service.as_mut() {
+                Some(service) => service,
+                None => {
+                    let cache_dir = crate::constants::get_global_models_cache_dir()?;
+                    embedding_service.insert(EmbeddingService::with_cache_dir(
+                        ModelType::default(),
+                        Some(cache_dir.as_path()),
+                    )?)
                 }
+            };
+            let stored = stores
+                .vector_store
+                .read()
+                .await
+                .moved_vectors(&all_chunks)?;
+            let mut prose_service = stores.prose_service(db_path).await;
+            let embedded =
+                super::embed_reusing(all_chunks, stored, service, prose_service.as_deref_mut())?;
+            drop(prose_service);
+            let (embedded_chunks, prose_embeddings) = (embedded.chunks, embedded.prose);
+
+            // Insert into vector store
+            let chunk_ids = {
+                let mut store = stores.vector_store.write().await;
+                let ids = store.insert_chunks_with_ids(embedded_chunks.clone())?;
+                if let Some(prose_embeddings) = &prose_embeddings {
+                    store.insert_prose_embeddings(&ids, prose_embeddings)?;
+                }
+                store.build_index()?;
+                ids
+            };
 
-                // Update file metadata
-                // Group chunks by file path (normalize for consistent lookup)
-                let mut chunks_by_file: std::collections::HashMap<String, Vec<u32>> =
-                    std::collections::HashMap::new();
+            // Insert into FTS
+            {
+                let mut fts_store = stores.fts_store.write().await;
                 for (chunk, chunk_id) in embedded_chunks.iter().zip(chunk_ids.iter()) {
-                    chunks_by_file
-                        .entry(normalize_path_str(&chunk.chunk.path))
-                        .or_default()
-                        .push(*chunk_id);
+                    let path_str = chunk.chunk.path.to_string();
+                    let signature = chunk.chunk.fts_signature();
+                    let kind = format!("{:?}", chunk.chunk.kind);
+                    fts_store.add_chunk(
+                        *chunk_id,
+                        &chunk.chunk.content,
+                        &path_str,
+                        signature.as_deref(),
+                        &kind,
+                    )?;
                 }
+                fts_store.commit()?;
+            }
 
-                for file in &changed_files {
-                    let path_str = normalize_path(&file.path);
-                    if let Some(ids) = chunks_by_file.get(&path_str) {
-                        file_meta_store.update_file(&file.path, ids.clone())?;
-                    } else {
-                        // File was processed but produced 0 chunks (e.g. minified JS,
-                        // empty file). Track it with empty chunk list so it is not
-                        // re-processed on every run and doctor doesn't flag it.
-                        file_meta_store.update_file(&file.path, vec![])?;
-                    }
-                }
+            // Update file metadata
+            // Group chunks by file path (normalize for consistent lookup)
+            let mut chunks_by_file: std::collections::HashMap<String, Vec<u32>> =
+                std::collections::HashMap::new();
+            for (chunk, chunk_id) in embedded_chunks.iter().zip(chunk_ids.iter()) {
+                chunks_by_file
+                    .entry(normalize_path_str(&chunk.chunk.path))
+                    .or_default()
+                    .push(*chunk_id);
+            }
 
-                info!("✅ Indexed {} chunks", embedded_chunks.len());
-            } else {
-                // ALL changed files produced 0 chunks — still track them so they
-                // are not flagged as unindexed on every subsequent run.
-                for file in &changed_files {
+            for file in batch {
+                let path_str = normalize_path(&file.path);
+                if let Some(ids) = chunks_by_file.get(&path_str) {
+                    file_meta_store.update_file(&file.path, ids.clone())?;
+                } else {
+                    // File was processed but produced 0 chunks (e.g. minified JS,
+                    // empty file). Track it with empty chunk list so it is not
+                    // re-processed on every run and doctor doesn't flag it.
                     file_meta_store.update_file(&file.path, vec![])?;
                 }
             }
-        }
-
-        // Save file metadata
-        file_meta_store.save(db_path)?;
+            file_meta_store.save(db_path)?;
 
-        let elapsed = start.elapsed();
-        info!(
-            "✅ Incremental refresh completed in {:.2}s",
-            elapsed.as_secs_f64()
-        );
+            total_chunks += embedded_chunks.len();
+            stores.activity.advance(batch.len());
+            if let Some((done, total)) = stores.activity.progress() {
+                info!("   Indexed {}/{} files", done, total);
+            }
+        }
 
+        info!("✅ Indexed {} chunks", total_chunks);
+        if let Some(service) = &embedding_service {
+            let usage = service.cache_usage();
+            info!("📦 Embedding cache: {}", usage.summary(None));
+            if let Err(e) = crate::embed::CacheUsageLog::record(db_path, &usage) {
+                warn!("Failed to record embedding cache usage: {}", e);
+            }
+        }
         Ok(())
     }
 
@@ -619,7 +1050,11 @@ impl IndexManager {
         let mut w = self.watcher.lock().await;
         if !w.is_started() {
             w.start(DEFAULT_FSW_DEBOUNCE_MS)?;
-            info!("👀 File watcher pre-started (collecting events)");
+            if w.is_polling() {
+                info!("👀 File watcher pre-started (polling for changes)");
+            } else {
+                info!("👀 File watcher pre-started (collecting events)");
+            }
         }
         Ok(())
     }
@@ -653,7 +1088,7 @@ impl IndexManager {
         info!("🚀 Starting background file watcher...");
 
         // Spawn background task
-        tokio::spawn(async move {
+        let task = tokio::spawn(async move {
             info!("👀 File watcher task started for: {}", path.display());
 
             // Start the watcher inside the task (if not already started by start_watching)
@@ -668,12 +1103,32 @@ impl IndexManager {
                     debug!("👀 File watcher already started (pre-started), skipping init");
                 }
             }
+            stores.activity.set_watcher_active(true);
 
             // Event buffers - use HashSet to deduplicate
             let mut files_to_index: HashSet<PathBuf> = HashSet::new();
             let mut files_to_remove: HashSet<PathBuf> = HashSet::new();
+            // Set when events were lost: the next flush diffs the whole tree
+            let mut resync_pending = false;
             let mut last_event_time = std::time::Instant::now();
             let flush_duration = std::time::Duration::from_millis(FSW_BATCH_FLUSH_MS);
+            // Segments left by refreshes and batches are merged once files stop changing
+            let merge_idle = if stores.readonly {
+                None
+            } else {
+                crate::fts::merge_idle_time()
+            };
+            let mut merge_due = true;
+
+            // Replay a batch interrupted by the previous shutdown
+            if let Some(pending) = take_journal(&db_path) {
+                info!(
+                    "🔁 Replaying {} pending file event(s) from the previous session",
+                    pending.to_index.len() + pending.to_remove.len()
+                );
+                files_to_index.extend(pending.to_index);
+                files_to_remove.extend(pending.to_remove);
+            }
 
             loop {
                 // Check if shutdown was requested
@@ -689,10 +1144,11 @@ impl IndexManager {
                             info!("🔀 Git branch changed, triggering full incremental refresh...");
                             // Perform a real incremental refresh: walk filesystem,
                             // detect changed/deleted files, clean stale chunks, re-index
-                            if let Err(e) =
-                                Self::refresh_index_with_stores(&path, &db_path, &stores).await
-                            {
-                                error!("❌ Branch change refresh failed: {}", e);
+                            let _operation = stores.activity.begin("branch_refresh");
+                            merge_due = true;
+                            match Self::refresh_index_with_stores(&path, &db_path, &stores).await {
+                                Ok(()) => stores.activity.mark_refreshed(),
+                                Err(e) => error!("❌ Branch change refresh failed: {}", e),
                             }
                             // Clear any buffered file events that arrived during the
                             // branch switch — the full refresh already handled everything
@@ -719,6 +1175,7 @@ impl IndexManager {
                                 old.display(),
                                 new.display()
                             ),
+                            FileEvent::Rescan => debug!("  🔁 Buffered full resync"),
                         }
                     }
                     debug!("📥 Buffered {} file event(s)", events.len());
@@ -744,15 +1201,34 @@ impl IndexManager {
                                 files_to_remove.remove(&new_p);
                                 files_to_index.insert(new_p);
                             }
+                            FileEvent::Rescan => resync_pending = true,
                         }
                     }
                 }
 
+                stores
+                    .activity
+                    .set_pending_events(files_to_index.len() + files_to_remove.len());
+
                 // Check if we should flush the buffer
                 let has_buffered_events = !files_to_index.is_empty() || !files_to_remove.is_empty();
                 let time_since_last_event = now.duration_since(last_event_time);
 
-                if has_buffered_events && time_since_last_event >= flush_duration {
+                // After an overflow, wait for the burst (git checkout, npm install)
+                // to settle, then diff the whole tree instead of the lossy batch
+                if resync_pending && time_since_last_event >= flush_duration {
+                    info!("🔁 File events were lost, resynchronizing index with disk...");
+                    let _operation = stores.activity.begin("resync");
+                    merge_due = true;
+                    match Self::refresh_index_with_stores(&path, &db_path, &stores).await {
+                        Ok(()) => stores.activity.mark_refreshed(),
+                        Err(e) => error!("❌ Resync after lost file events failed: {}", e),
+                    }
+                    files_to_index.clear();
+                    files_to_remove.clear();
+                    resync_pending = false;
+                    last_event_time = now;
+                } else if has_buffered_events && time_since_last_event >= flush_duration {
                     // Flush the buffer
                     let to_index: Vec<PathBuf> = files_to_index.drain().collect();
                     let to_remove: Vec<PathBuf> = files_to_remove.drain().collect();
@@ -763,19 +1239,41 @@ impl IndexManager {
                         to_remove.len()
                     );
 
-                    // Process batch using shared stores
-                    if let Err(e) = Self::process_batch_with_stores(
+                    // Process batch using shared stores. Shutdown waits for the
+                    // task; the journal covers a batch that doesn't finish in time.
+                    let _operation = stores.activity.begin("file_batch");
+                    merge_due = true;
+                    stores.activity.set_pending_events(0);
+                    write_journal(&db_path, &to_index, &to_remove);
+                    match Self::process_batch_with_stores(
                         &path, &db_path, &stores, to_index, to_remove,
                     )
                     .await
                     {
-                        error!("❌ Batch processing failed: {}", e);
+                        Ok(()) => stores.activity.mark_refreshed(),
+                        Err(e) => error!("❌ Batch processing failed: {}", e),
                     }
+                    clear_journal(&db_path);
 
                     // Reset timer
                     last_event_time = now;
                 }
 
+                // Merge FTS segments once changes have settled, not while searches
+                // follow a burst of them
+                if merge_due
+                    && !resync_pending
+                    && files_to_index.is_empty()
+                    && files_to_remove.is_empty()
+                    && merge_idle.is_some_and(|idle| now.duration_since(last_event_time) >= idle)
+                {
+                    merge_due = false;
+                    let _operation = stores.activity.begin("fts_merge");
+                    if let Err(e) = Self::merge_fts_segments(&stores).await {
+                        warn!("⚠️  FTS segment merge failed: {}", e);
+                    }
+                }
+
                 // Sleep to avoid busy-waiting, but wake up immediately on shutdown
                 tokio::select! {
                     _ = tokio::time::sleep(tokio::time::Duration::from_millis(100)) => {}
@@ -786,14 +1284,42 @@ impl IndexManager {
                 }
             }
 
+            // Keep events buffered but not yet flushed for the next start
+            if !files_to_index.is_empty() || !files_to_remove.is_empty() {
+                let to_index: Vec<PathBuf> = files_to_index.drain().collect();
+                let to_remove: Vec<PathBuf> = files_to_remove.drain().collect();
+                info!(
+                    "📝 Saving {} unprocessed file event(s) for the next start",
+                    to_index.len() + to_remove.len()
+                );
+                write_journal(&db_path, &to_index, &to_remove);
+            }
+
+            stores.activity.set_watcher_active(false);
             info!("✅ File watcher stopped cleanly");
         });
+        *self.watcher_task.lock().unwrap_or_else(|e| e.into_inner()) = Some(task);
 
         info!("✅ File watcher background task spawned");
 
         Ok(())
     }
 
+    /// Merge FTS segments as `CODESEARCH_FTS_MAX_SEGMENTS` asks
+    ///
+    /// The write lock is only held to start the merges and to reload the
+    /// reader afterwards; searches use the old segments in between.
+    async fn merge_fts_segments(stores: &SharedStores) -> Result<()> {
+        let settings = crate::fts::MergeSettings::from_env();
+        let Some(pending) = stores.fts_store.write().await.start_merge(&settings)? else {
+            return Ok(());
+        };
+        let merged = tokio::task::spawn_blocking(move || pending.wait()).await??;
+        stores.fts_store.write().await.reload()?;
+        info!("🧹 Merged {} FTS segments while idle", merged);
+        Ok(())
+    }
+
     /// Process a batch of file events using shared stores.
     /// This is more efficient than processing files one by one.
     async fn process_batch_with_stores(
@@ -803,12 +1329,10 @@ impl IndexManager {
         files_to_index: Vec<PathBuf>,
         files_to_remove: Vec<PathBuf>,
     ) -> Result<()> {
-        use crate::output::set_quiet;
-
         let start = std::time::Instant::now();
 
-        // Enable quiet mode during FSW batch processing to suppress verbose embedding output
-        set_quiet(true);
+        // Keep the embedding output of FSW batches off the console (unless -v)
+        let _background = crate::output::background_output();
 
         // First, remove deleted files
         for file_path in &files_to_remove {
@@ -824,63 +1348,40 @@ impl IndexManager {
             // produce a Remove event for the directory itself, not for individual files.
             // Find all tracked files under this path prefix and remove them too.
             {
-                use crate::cache::FileMetaStore;
-
                 // Load FileMetaStore from disk to query tracked files
-                let metadata_path = db_path.join("metadata.json");
-                if metadata_path.exists() {
-                    if let Ok(metadata_str) = std::fs::read_to_string(&metadata_path) {
-                        if let Ok(metadata) =
-                            serde_json::from_str::<serde_json::Value>(&metadata_str)
-                        {
-                            let dimensions =
-                                metadata["dimensions"].as_u64().unwrap_or(384) as usize;
-                            let model_name = metadata["model_short_name"]
-                                .as_str()
-                                .unwrap_or("minilm-l6-q");
-
-                            if let Ok(file_meta_store) =
-                                FileMetaStore::load_or_create(db_path, model_name, dimensions)
+                if let Some(file_meta_store) = load_file_meta(db_path) {
+                    // Normalize the directory prefix for consistent matching
+                    // (tracked files are normalized to forward slashes)
+                    let dir_prefix = normalize_path(file_path);
+                    let dir_prefix_slash = if dir_prefix.ends_with('/') {
+                        dir_prefix.clone()
+                    } else {
+                        format!("{}/", dir_prefix)
+                    };
+
+                    let files_under_dir: Vec<String> = file_meta_store
+                        .tracked_files()
+                        .filter(|f| f.starts_with(&dir_prefix_slash))
+                        .cloned()
+                        .collect();
+
+                    if !files_under_dir.is_empty() {
+                        info!(
+                            "🗑️  Directory deleted: {} ({} files under it)",
+                            file_path.display(),
+                            files_under_dir.len()
+                        );
+                        for tracked_file in &files_under_dir {
+                            let tracked_path = PathBuf::from(tracked_file);
+                            if let Err(e) = Self::remove_file_from_index_with_stores(
+                                codebase_path,
+                                db_path,
+                                stores,
+                                &tracked_path,
+                            )
+                            .await
                             {
-                                // Normalize the directory prefix for consistent matching
-                                // (tracked files are normalized to forward slashes)
-                                let dir_prefix = normalize_path(file_path);
-                                let dir_prefix_slash = if dir_prefix.ends_with('/') {
-                                    dir_prefix.clone()
-                                } else {
-                                    format!("{}/", dir_prefix)
-                                };
-
-                                let files_under_dir: Vec<String> = file_meta_store
-                                    .tracked_files()
-                                    .filter(|f| f.starts_with(&dir_prefix_slash))
-                                    .cloned()
-                                    .collect();
-
-                                if !files_under_dir.is_empty() {
-                                    info!(
-                                        "🗑️  Directory deleted: {} ({} files under it)",
-                                        file_path.display(),
-                                        files_under_dir.len()
-                                    );
-                                    for tracked_file in &files_under_dir {
-                                        let tracked_path = PathBuf::from(tracked_file);
-                                        if let Err(e) = Self::remove_file_from_index_with_stores(
-                                            codebase_path,
-                                            db_path,
-                                            stores,
-                                            &tracked_path,
-                                        )
-                                        .await
-                                        {
-                                            warn!(
-                                                "⚠️  Failed to remove {}: {}",
-                                                tracked_path.display(),
-                                                e
-                                            );
-                                        }
-                                    }
-                                }
+                                warn!("⚠️  Failed to remove {}: {}", tracked_path.display(), e);
                             }
                         }
                     }
@@ -896,17 +1397,42 @@ impl IndexManager {
             store.build_index()?;
         }
 
-        // Then, index modified/new files
+        // Then, index modified/new files, skipping ones still backing off
+        // after repeated failures (parser crash, pathological size)
+        let backing_off: HashSet<&PathBuf> = match load_file_meta(db_path) {
+            Some(file_meta) => files_to_index
+                .iter()
+                .filter(|p| file_meta.should_skip(p))
+                .collect(),
+            None => HashSet::new(),
+        };
         for file_path in &files_to_index {
+            if backing_off.contains(file_path) {
+                debug!(
+                    "⏸️  Skipping {} (backing off after repeated failures)",
+                    file_path.display()
+                );
+                continue;
+            }
             debug!("📄 Indexing: {}", file_path.display());
             if let Err(e) = Self::index_single_file(codebase_path, file_path, stores).await {
                 warn!("⚠️  Failed to index {}: {}", file_path.display(), e);
+                if let Some(mut file_meta) = load_file_meta(db_path) {
+                    let failure = file_meta.record_failure(file_path, &e.to_string());
+                    if failure.is_quarantined() {
+                        warn!(
+                            "🚫 {} failed {} times in a row, retrying at most hourly",
+                            file_path.display(),
+                            failure.count
+                        );
+                    }
+                    if let Err(e) = file_meta.save(db_path) {
+                        warn!("⚠️  Failed to save file metadata: {}", e);
+                    }
+                }
             }
         }
 
-        // Disable quiet mode after batch processing is complete
-        set_quiet(false);
-
         let elapsed = start.elapsed();
         info!(
             "✅ Batch complete: {} indexed, {} removed in {:.2}s",
@@ -920,8 +1446,9 @@ impl IndexManager {
 
     /// Perform a full incremental refresh using shared stores.
     ///
-    /// This is called on git branch changes to ensure the index reflects the
-    /// current state of the working tree. Unlike `process_batch_with_stores`
+    /// This is called on git branch changes and after lost file events (watch
+    /// queue overflow) to ensure the index reflects the current state of the
+    /// working tree. Unlike `process_batch_with_stores`
     /// which operates on a known list of changed files, this function:
     ///
     /// 1. Walks the filesystem to discover all current files
@@ -937,35 +1464,32 @@ impl IndexManager {
     ) -> Result<()> {
         use crate::cache::FileMetaStore;
         use crate::file::FileWalker;
-        use crate::output::set_quiet;
 
         let start = std::time::Instant::now();
-        set_quiet(true);
+        let _background = crate::output::background_output();
 
         let result: Result<()> = async {
         // Phase 1: Discover current files on disk
-        let walker = FileWalker::new(codebase_path.to_path_buf());
+        let walker = FileWalker::new(codebase_path.to_path_buf())
+            .with_languages(super::read_language_filter(db_path))
+            .with_excluded_dirs(super::read_pruned_dirs(db_path));
         let (files, stats) = walker.walk()?;
         info!(
-            "🔍 Branch refresh: discovered {} indexable files ({} skipped)",
+            "🔍 Full refresh: discovered {} indexable files ({} skipped)",
             files.len(),
             stats.total_files - stats.indexable_files
         );
 
         // Phase 2: Load file metadata and analyze changes
-        let metadata_path = db_path.join("metadata.json");
-        if !metadata_path.exists() {
-            info!("⚠️ No metadata.json found, skipping branch refresh");
+        let Some(metadata) = IndexMetadata::load(db_path)? else {
+            info!("⚠️ No metadata.json found, skipping full refresh");
             return Ok(());
-        }
-        let metadata_str = std::fs::read_to_string(&metadata_path)?;
-        let metadata: serde_json::Value = serde_json::from_str(&metadata_str)?;
-        let dimensions = metadata["dimensions"].as_u64().unwrap_or(384) as usize;
-        let model_name = metadata["model_short_name"]
-            .as_str()
-            .unwrap_or("minilm-l6-q");
+        };
+        let dimensions = metadata.dimensions;
+        let model_name = metadata.model_short_name.as_str();
 
-        let mut file_meta_store = FileMetaStore::load_or_create(db_path, model_name, dimensions)?;
+        let mut file_meta_store =
+            FileMetaStore::load_or_create(db_path, model_name, dimensions)?;
 
         // Find files that need re-indexing (new or content changed)
         let mut files_to_reindex: Vec<PathBuf> = Vec::new();
@@ -983,12 +1507,16 @@ impl IndexManager {
         let deleted_files = file_meta_store.find_deleted_files();
 
         if files_to_reindex.is_empty() && deleted_files.is_empty() {
-            info!("✅ Branch refresh: index is up to date, no changes needed");
+            // Nothing changed, but chunks of untracked files may still linger
+            let mut vstore = stores.vector_store.write().await;
+            let mut fstore = stores.fts_store.write().await;
+            super::orphans::sweep_orphans(&mut vstore, &mut fstore, &file_meta_store)?;
+            info!("✅ Full refresh: index is up to date, no changes needed");
             return Ok(());
         }
 
         info!(
-            "🔍 Branch refresh analysis: {} to re-index, {} stale to remove, {} old chunks to clean",
+            "🔍 Full refresh analysis: {} to re-index, {} stale to remove, {} old chunks to clean",
             files_to_reindex.len(),
             deleted_files.len(),
             chunks_to_delete.len()
@@ -1032,53 +1560,14 @@ impl IndexManager {
 
         // Phase 3.5: VectorStore-direct orphan cleanup
         // FileMetaStore may not track all ghost chunks (from pre-fix indexing runs).
-        // Directly scan the VectorStore for chunks referencing files not on disk.
+        // Files about to be re-indexed are untracked, so stray chunks of those
+        // go too instead of ending up as duplicates.
         {
-            let vstore = stores.vector_store.read().await;
-            let vs_file_chunks = vstore.get_chunks_by_file()?;
-            drop(vstore); // Release read lock before potential write
-
-            let mut orphan_chunk_ids: Vec<u32> = Vec::new();
-            let mut orphan_file_count = 0usize;
-
-            for (vs_path, chunk_ids) in &vs_file_chunks {
-                if !std::path::Path::new(vs_path).exists() {
-                    orphan_chunk_ids.extend(chunk_ids);
-                    orphan_file_count += 1;
-                }
-            }
-
-            if !orphan_chunk_ids.is_empty() {
-                info!(
-                    "🧹 Found {} orphan chunks across {} ghost files in VectorStore (not tracked by FileMetaStore)",
-                    orphan_chunk_ids.len(),
-                    orphan_file_count
-                );
-
-                // Delete orphan chunks from VectorStore
-                {
-                    let mut vstore = stores.vector_store.write().await;
-                    vstore.delete_chunks(&orphan_chunk_ids)?;
-                    vstore.build_index()?;
-                }
-
-                // Delete orphan chunks from FtsStore
-                {
-                    let mut fstore = stores.fts_store.write().await;
-                    for &cid in &orphan_chunk_ids {
-                        let _ = fstore.delete_chunk(cid);
-                    }
-                    fstore.commit()?;
-                }
-
-                info!(
-                    "✅ Cleaned {} orphan chunks from {} ghost files in VectorStore",
-                    orphan_chunk_ids.len(),
-                    orphan_file_count
-                );
-
-                deleted_count += orphan_file_count;
-            }
+            let mut vstore = stores.vector_store.write().await;
+            let mut fstore = stores.fts_store.write().await;
+            let sweep =
+                super::orphans::sweep_orphans(&mut vstore, &mut fstore, &file_meta_store)?;
+            deleted_count += sweep.files;
         }
 
         // Phase 4: Re-index changed/new files
@@ -1091,16 +1580,17 @@ impl IndexManager {
 
         let elapsed = start.elapsed();
         info!(
-            "✅ Branch refresh complete: {} re-indexed, {} stale removed in {:.2}s",
+            "✅ Full refresh complete: {} re-indexed, {} stale removed in {:.2}s",
             reindex_count,
             deleted_count,
             elapsed.as_secs_f64()
         );
 
+        super::hooks::spawn_post(super::hooks::Hook::PostRefresh, codebase_path, db_path);
+
         Ok(())
         }
         .await;
-        set_quiet(false);
         result
     }
 
@@ -1130,13 +1620,16 @@ impl IndexManager {
         let start = std::time::Instant::now();
 
         // Call the index function from the parent module
-        // Parameters: path, dry_run, force, global, model
+        // Parameters: path, dry_run, force, global, model, prose_model, languages, max_chunks
         super::index(
             Some(path.to_path_buf()),
             false,
             false,
             false,
             None,
+            None,
+            None,
+            None,
             CancellationToken::new(),
         )
         .await?;
@@ -1177,7 +1670,6 @@ impl IndexManager {
     ) -> Result<()> {
         use crate::cache::FileMetaStore;
         use crate::chunker::{Chunker, SemanticChunker};
-        use crate::embed::EmbeddingService;
         use crate::file::Language;
 
         let db_path = codebase_path.join(DB_DIR_NAME);
@@ -1193,9 +1685,26 @@ impl IndexManager {
             debug!("File not indexable, skipping: {}", file_path.display());
             return Ok(());
         }
+        if super::read_language_filter(&db_path).is_some_and(|l| !l.contains(&language)) {
+            debug!(
+                "Language outside index scope, skipping: {}",
+                file_path.display()
+            );
+            return Ok(());
+        }
+        if super::read_pruned_dirs(&db_path)
+            .iter()
+            .any(|dir| file_path.starts_with(codebase_path.join(dir)))
+        {
+            debug!(
+                "Directory pruned for the size budget, skipping: {}",
+                file_path.display()
+            );
+            return Ok(());
+        }
 
         // Read file content
-        let content = match std::fs::read_to_string(file_path) {
+        let content = match crate::file::read_source(file_path) {
             Ok(c) => c,
             Err(e) => {
                 warn!("Failed to read file {}: {}", file_path.display(), e);
@@ -1204,7 +1713,8 @@ impl IndexManager {
         };
 
         // Chunk the file
-        let chunker = SemanticChunker::new(100, 4000, 2);
+        let chunker =
+            SemanticChunker::new(100, 4000, 2).with_context_lines(super::context_lines(&db_path));
         let chunks = chunker.chunk_file(file_path, &content)?;
 
         if chunks.is_empty() {
@@ -1222,21 +1732,29 @@ impl IndexManager {
         let cache_dir = crate::constants::get_global_models_cache_dir()?;
         let mut embedding_service =
             EmbeddingService::with_cache_dir(ModelType::default(), Some(cache_dir.as_path()))?;
-        let embedded_chunks = embedding_service.embed_chunks(chunks)?;
+        let stored = stores.vector_store.read().await.moved_vectors(&chunks)?;
+        let mut prose_service = stores.prose_service(&db_path).await;
+        let embedded = super::embed_reusing(
+            chunks,
+            stored,
+            &mut embedding_service,
+            prose_service.as_deref_mut(),
+        )?;
+        drop(prose_service);
+        let (embedded_chunks, prose_embeddings) = (embedded.chunks, embedded.prose);
 
         // Load metadata to get dimensions
-        let metadata_path = db_path.join("metadata.json");
-        let metadata: serde_json::Value =
-            serde_json::from_str(&std::fs::read_to_string(&metadata_path)?)?;
-        let dimensions = metadata["dimensions"].as_u64().unwrap_or(384) as usize;
-        let model_name = metadata["model_short_name"]
-            .as_str()
-            .unwrap_or("minilm-l6-q");
+        let metadata = IndexMetadata::require(&db_path)?;
+        let dimensions = metadata.dimensions;
+        let model_name = metadata.model_short_name.as_str();
 
         // Use shared stores with write lock
         let chunk_ids = {
             let mut store = stores.vector_store.write().await;
             let chunk_ids = store.insert_chunks_with_ids(embedded_chunks.clone())?;
+            if let Some(prose_embeddings) = &prose_embeddings {
+                store.insert_prose_embeddings(&chunk_ids, prose_embeddings)?;
+            }
             // Rebuild the vector index after inserting new chunks
             store.build_index()?;
             chunk_ids
@@ -1247,13 +1765,13 @@ impl IndexManager {
             let mut fts_store = stores.fts_store.write().await;
             for (chunk, chunk_id) in embedded_chunks.iter().zip(chunk_ids.iter()) {
                 let path_str = chunk.chunk.path.to_string();
-                let signature = chunk.chunk.signature.as_deref();
+                let signature = chunk.chunk.fts_signature();
                 let kind = format!("{:?}", chunk.chunk.kind);
                 fts_store.add_chunk(
                     *chunk_id,
                     &chunk.chunk.content,
                     &path_str,
-                    signature,
+                    signature.as_deref(),
                     &kind,
                 )?;
             }
@@ -1285,49 +1803,35 @@ impl IndexManager {
         use crate::cache::FileMetaStore;
 
         // Load metadata to get dimensions and model
-        let metadata_path = db_path.join("metadata.json");
-        if !metadata_path.exists() {
+        let Some(metadata) = IndexMetadata::load(db_path)? else {
             debug!("No metadata found, skipping removal");
             return Ok(());
-        }
-        let metadata: serde_json::Value =
-            serde_json::from_str(&std::fs::read_to_string(&metadata_path)?)?;
-        let dimensions = metadata["dimensions"].as_u64().unwrap_or(384) as usize;
-        let model_name = metadata["model_short_name"]
-            .as_str()
-            .unwrap_or("minilm-l6-q");
+        };
+        let dimensions = metadata.dimensions;
+        let model_name = metadata.model_short_name.as_str();
 
         // Load file metadata to get chunk IDs
         let mut file_meta_store = FileMetaStore::load_or_create(db_path, model_name, dimensions)?;
 
         // Get chunk IDs from file metadata directly (not check_file which reads from disk)
         // The file is already deleted, so we can't read mtime/size/hash
-        let meta = file_meta_store.remove_file(file_path);
-        let chunk_ids = match meta {
-            Some(m) if !m.chunk_ids.is_empty() => m.chunk_ids,
-            Some(_) => {
-                debug!("No chunks to remove for file: {}", file_path.display());
-                file_meta_store.save(db_path)?;
-                return Ok(());
-            }
-            None => {
-                debug!("No metadata found for file: {}", file_path.display());
-                return Ok(());
-            }
-        };
-
-        debug!(
-            "Removing {} chunks for file: {}",
-            chunk_ids.len(),
-            file_path.display()
-        );
-
-        // Delete chunks from vector store with write lock
-        {
+        let chunk_ids = file_meta_store
+            .remove_file(file_path)
+            .map(|meta| meta.chunk_ids)
+            .unwrap_or_default();
+        let path = normalize_path(file_path);
+
+        // Delete chunks from vector store with write lock. Deleting by path as
+        // well catches chunks the file metadata lost track of.
+        let removed = {
             let mut store = stores.vector_store.write().await;
-            for chunk_id in &chunk_ids {
-                store.delete_chunks(&[*chunk_id])?;
-            }
+            store.delete_chunks(&chunk_ids)?;
+            chunk_ids.len() + store.delete_by_path(&path)?.len()
+        };
+        if removed == 0 {
+            debug!("No chunks to remove for file: {}", file_path.display());
+            file_meta_store.save(db_path)?;
+            return Ok(());
         }
 
         // Delete from FTS with write lock
@@ -1336,17 +1840,14 @@ impl IndexManager {
             for chunk_id in &chunk_ids {
                 fts_store.delete_chunk(*chunk_id)?;
             }
+            fts_store.delete_by_path(&path)?;
             fts_store.commit()?;
         }
 
         // Save file metadata (remove_file was already called above)
         file_meta_store.save(db_path)?;
 
-        info!(
-            "✅ Removed {} chunks for {}",
-            chunk_ids.len(),
-            file_path.display()
-        );
+        info!("✅ Removed {} chunks for {}", removed, file_path.display());
 
         Ok(())
     }
@@ -1371,6 +1872,44 @@ mod tests {
         .unwrap();
     }
 
+    #[test]
+    fn test_journal_roundtrip_is_consumed_once() {
+        let dir = tempdir().unwrap();
+        let to_index = vec![PathBuf::from("/repo/src/a.rs")];
+        let to_remove = vec![PathBuf::from("/repo/src/old.rs")];
+
+        write_journal(dir.path(), &to_index, &to_remove);
+        let pending = take_journal(dir.path()).expect("journal should exist");
+        assert_eq!(pending.to_index, to_index);
+        assert_eq!(pending.to_remove, to_remove);
+        assert!(take_journal(dir.path()).is_none());
+
+        write_journal(dir.path(), &to_index, &[]);
+        clear_journal(dir.path());
+        assert!(take_journal(dir.path()).is_none());
+    }
+
+    #[test]
+    fn test_index_activity_tracks_operation_and_refresh() {
+        let activity = IndexActivity::default();
+        assert!(activity.current_operation().is_none());
+        assert!(activity.last_refresh().is_none());
+
+        {
+            let _operation = activity.begin("file_batch");
+            assert_eq!(activity.current_operation(), Some("file_batch"));
+            activity.start_progress(4);
+            activity.advance(1);
+            assert_eq!(activity.progress(), Some((1, 4)));
+            activity.finish_progress();
+            activity.mark_refreshed();
+        }
+
+        assert!(activity.current_operation().is_none());
+        assert!(activity.progress().is_none());
+        assert!(activity.last_refresh().is_some());
+    }
+
     /// Helper: create writable SharedStores for testing (no writer lock)
     async fn create_test_stores(db_path: &Path, dimensions: usize) -> SharedStores {
         use crate::fts::FtsStore;
@@ -1381,6 +1920,8 @@ mod tests {
             fts_store: Arc::new(RwLock::new(FtsStore::new_with_writer(db_path).unwrap())),
             writer_lock: None,
             readonly: false,
+            activity: IndexActivity::default(),
+            prose_service: Mutex::default(),
         }
     }
 
@@ -0,0 +1,219 @@
+//! Switching an index to another code embedding model, chunk by chunk
+//!
+//! `codesearch index --model <new>` on an index built with another model
+//! doesn't re-index from scratch. The stored chunks are embedded again with
+//! the new model, a batch at a time, and each vector is staged next to the old
+//! one along with the embedding version (model and chunker schema) it came
+//! from. Searches keep using the old vectors meanwhile. A run that is
+//! interrupted or stops at `--max-chunks` keeps what it staged, so the next
+//! run only embeds the chunks still missing a vector of the new model. Once
+//! every chunk has one, the staged vectors replace the old ones.
+
+use anyhow::Result;
+use colored::Colorize;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::Path;
+use tokio_util::sync::CancellationToken;
+
+use super::build_vector_index_with_spinner;
+use crate::cache::{FileMetaStore, IndexMetadata, ModelMigration};
+use crate::constants::{CHUNKER_SCHEMA_VERSION, INDEX_EMBED_BATCH_CHUNKS};
+use crate::embed::{EmbeddingService, ModelType};
+use crate::out_print;
+use crate::vectordb::VectorStore;
+
+/// What a staged vector was embedded with: vectors of one version are
+/// interchangeable, vectors of different versions never are
+pub fn embedding_version(model: ModelType) -> String {
+    format!("{}@{}", model.short_name(), CHUNKER_SCHEMA_VERSION)
+}
+
+/// Embed the chunks of the index at `db_path` with `target`, at most
+/// `max_chunks` in this run, and switch the index to it once all are done
+pub(super) fn migrate_model(
+    db_path: &Path,
+    mut metadata: IndexMetadata,
+    target: ModelType,
+    max_chunks: Option<usize>,
+    quiet: bool,
+    cancel_token: &CancellationToken,
+) -> Result<()> {
+    macro_rules! log_print {
+        ($($arg:tt)*) => {
+            if !quiet {
+                out_print!($($arg)*);
+            }
+        };
+    }
+
+    let version = embedding_version(target);
+    log_print!(
+        "\n{}",
+        format!(
+            "🔀 Switching the index from {} to {}",
+            metadata.model_short_name,
+            target.short_name()
+        )
+        .bright_cyan()
+    );
+    log_print!("{}", "-".repeat(60));
+
+    let cache_dir = crate::constants::get_global_models_cache_dir()?;
+    let mut embedding_service =
+        EmbeddingService::with_cache_dir(target, Some(cache_dir.as_path()))?;
+    if crate::constants::check_shutdown(cancel_token) {
+        log_print!(
+            "\n{}",
+            "⚠️  Migration cancelled during model loading".yellow()
+        );
+        return Ok(());
+    }
+
+    let mut store = VectorStore::new(db_path, metadata.dimensions)?;
+    let discarded = store.discard_staged_vectors(&version)?;
+    if discarded > 0 {
+        log_print!("   Dropped {} vectors staged for another model", discarded);
+    }
+    let (already, total) = store.migration_progress(&version)?;
+    log_print!(
+        "   {} of {} chunks already have a {} vector",
+        already,
+        total,
+        target.short_name()
+    );
+
+    let batch = std::env::var("CODESEARCH_INDEX_EMBED_BATCH_CHUNKS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&n: &usize| n > 0)
+        .unwrap_or(INDEX_EMBED_BATCH_CHUNKS);
+    let pb = if quiet {
+        ProgressBar::hidden()
+    } else {
+        let pb = ProgressBar::new(total as u64);
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
+                .unwrap()
+                .progress_chars(if crate::output::is_plain() {
+                    "#>-"
+                } else {
+                    "█▓▒░ "
+                }),
+        );
+        pb
+    };
+    pb.set_position(already as u64);
+
+    // Chunks are visited in ID order, so each batch starts after the last one
+    let mut cursor = None;
+    let mut embedded = 0;
+    let mut cancelled = false;
+    loop {
+        let limit = match max_chunks {
+            Some(max) if embedded >= max => break,
+            Some(max) => batch.min(max - embedded),
+            None => batch,
+        };
+        let ids = store.chunks_to_migrate(&version, cursor, limit)?;
+        let Some(&last) = ids.last() else {
+            break;
+        };
+        cursor = Some(last);
+
+        let mut chunk_ids = Vec::with_capacity(ids.len());
+        let mut chunks = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(metadata) = store.get_chunk(id)? {
+                chunk_ids.push(id);
+                chunks.push(metadata.to_chunk());
+            }
+        }
+        let embeddings: Vec<Vec<f32>> = match embedding_service.embed_chunks(chunks) {
+            Ok(embedded) => embedded.into_iter().map(|c| c.embedding).collect(),
+            Err(_) if crate::constants::is_shutdown_requested() => {
+                cancelled = true;
+                break;
+            }
+            Err(e) => return Err(e),
+        };
+        store.stage_migration_vectors(&version, &chunk_ids, &embeddings)?;
+        embedded += chunk_ids.len();
+        pb.inc(chunk_ids.len() as u64);
+
+        if crate::constants::check_shutdown(cancel_token) {
+            cancelled = true;
+            break;
+        }
+    }
+    pb.finish_and_clear();
+    drop(embedding_service);
+
+    let (migrated, total) = store.migration_progress(&version)?;
+    if migrated < total {
+        metadata.migration = Some(ModelMigration {
+            model_short_name: target.short_name().to_string(),
+            dimensions: target.dimensions(),
+            migrated_chunks: migrated,
+            total_chunks: total,
+        });
+        metadata.save(db_path)?;
+        log_print!(
+            "\n{} Embedded {} chunks; {} of {} now have a {} vector",
+            if cancelled { "⚠️ " } else { "⏸️ " },
+            embedded,
+            migrated,
+            total,
+            target.short_name()
+        );
+        log_print!(
+            "   Searches use {} until the rest are done. Run {} to continue.",
+            metadata.model_short_name,
+            format!("codesearch index --model {}", target.short_name()).bright_cyan()
+        );
+        return Ok(());
+    }
+
+    // Every chunk has a new vector: switch over
+    store.finish_migration(&version, target.dimensions())?;
+    let previous = metadata.model_short_name.clone();
+    metadata.model_short_name = target.short_name().to_string();
+    metadata.model_name = target.name().to_string();
+    metadata.dimensions = target.dimensions();
+    metadata.migration = None;
+    metadata.indexed_at = Some(chrono::Utc::now().to_rfc3339());
+    metadata.save(db_path)?;
+    // The files are unchanged; only the model they are embedded with is new
+    if let Some(mut file_meta) = FileMetaStore::read(db_path) {
+        file_meta.model_name = target.short_name().to_string();
+        file_meta.dimensions = target.dimensions();
+        file_meta.save(db_path)?;
+    }
+
+    log_print!(
+        "🔨 Rebuilding the vector index with {} vectors...",
+        target.short_name()
+    );
+    if !build_vector_index_with_spinner(&mut store, cancel_token, quiet)? {
+        log_print!(
+            "\n{}",
+            "⚠️  Cancelled while building the vector index".yellow()
+        );
+        log_print!(
+            "   Run {} to finish the index",
+            "codesearch index".bright_cyan()
+        );
+        return Ok(());
+    }
+    log_print!(
+        "\n{}",
+        format!(
+            "✅ Index switched from {} to {} ({} chunks)",
+            previous,
+            target.short_name(),
+            total
+        )
+        .green()
+    );
+    Ok(())
+}
@@ -1,3 +1,5 @@
+use crate::out_print;
+use crate::output::format_bytes;
 use anyhow::Result;
 use colored::Colorize;
 use indicatif::{ProgressBar, ProgressStyle};
@@ -7,17 +9,37 @@ use std::time::Instant;
 use tokio_util::sync::CancellationToken;
 use tracing::{debug, info};
 
-use crate::cache::{normalize_path, FileMetaStore};
-use crate::chunker::SemanticChunker;
+use crate::cache::{normalize_path, FileMetaStore, IndexMetadata};
+use crate::chunker::{Chunk, SemanticChunker, DEFAULT_CONTEXT_LINES};
+use crate::constants::{
+    INDEX_EMBED_BATCH_CHUNKS, INDEX_MAX_CONSECUTIVE_FAILURES, INDEX_TRANCHE_CHUNKS,
+};
 use crate::db_discovery::{find_best_database, register_repository, unregister_repository};
-use crate::embed::{EmbeddingService, ModelType};
-use crate::file::FileWalker;
-use crate::fts::FtsStore;
-use crate::vectordb::VectorStore;
-
+use crate::embed::{CacheUsageLog, EmbeddedChunk, EmbeddingService, ModelType};
+use crate::file::{CodeOwners, FileWalker, Language};
+use crate::fts::{FtsStore, MergeSettings};
+use crate::vectordb::{BuildProgress, StoredVectors, VectorStore};
+
+mod backup;
+mod budget;
+mod crash_context;
+mod estimate;
+mod hooks;
 // Index manager module
 mod manager;
-pub use manager::{IndexManager, SharedStores};
+mod migration;
+mod orphans;
+mod publish;
+mod quarantine;
+mod remote;
+mod size_history;
+pub use backup::{backup, restore};
+pub use crash_context::install_panic_hook;
+use crash_context::IndexingTracker;
+pub use manager::{is_database_locked, IndexManager, SharedStores};
+pub use orphans::{find_orphans, sweep_orphans};
+pub use publish::{publish, pull};
+pub use quarantine::recover_if_corrupt;
 
 /// Get the database path and project path for a given directory
 /// Uses automatic database discovery to find indexes in parent/global directories
@@ -55,7 +77,7 @@ fn get_db_path_smart(
     if force {
         if let Some(ref db_info) = existing_db {
             // Delete existing database (local or global)
-            println!(
+            out_print!(
                 "{}",
                 format!(
                     "🗑️  Force rebuild: deleting existing database at {}",
@@ -68,7 +90,7 @@ fn get_db_path_smart(
             // from LMDB/tantivy may not be immediately released after deletion)
             // Increased to 1000ms to handle slow file handle release on Windows
             std::thread::sleep(std::time::Duration::from_millis(1000));
-            println!("✅ Existing database deleted");
+            out_print!("✅ Existing database deleted");
         }
         // After deletion, continue to create new database
     }
@@ -79,7 +101,7 @@ fn get_db_path_smart(
         if let Some(ref db_info) = existing_db {
             if !force && db_info.is_global {
                 // Global database already exists, use it
-                println!(
+                out_print!(
                     "{}",
                     format!(
                         "🌍 Using existing global database: {}",
@@ -90,7 +112,7 @@ fn get_db_path_smart(
                 return Ok((db_info.db_path.clone(), db_info.project_path.clone()));
             } else if !force && !db_info.is_global {
                 // Local database exists but user wants global
-                println!(
+                out_print!(
                     "{}",
                     format!(
                         "⚠️  Local database exists at {}\n   Moving to global database...",
@@ -100,7 +122,7 @@ fn get_db_path_smart(
                 );
                 // Delete local database
                 std::fs::remove_dir_all(&db_info.db_path)?;
-                println!("✅ Local database removed");
+                out_print!("✅ Local database removed");
             }
         }
         // Create or use global database
@@ -121,7 +143,7 @@ fn get_db_path_smart(
                 } else {
                     db_info.project_path.display().to_string()
                 };
-                println!(
+                out_print!(
                     "{}",
                     format!(
                         "📂 Using database from: {}\n   (indexing from subfolder, project root: {})",
@@ -333,13 +355,15 @@ fn get_global_db_path(path: Option<PathBuf>) -> Result<(PathBuf, PathBuf)> {
 
     // Create global database directory
     let home = home_dir().ok_or_else(|| anyhow::anyhow!("No home directory found"))?;
-    let global_db_dir = home.join(".codesearch.dbs").join(project_name);
+    let global_db_dir = home
+        .join(crate::constants::GLOBAL_DB_DIR_NAME)
+        .join(project_name);
     let db_path = global_db_dir.join(".codesearch.db");
 
     // Register this repository in the global tracking
     register_repository(&canonical_path)?;
 
-    println!(
+    out_print!(
         "{}",
         format!(
             "🌍 Using global database: {}\n   (project: {})",
@@ -360,16 +384,35 @@ fn get_global_db_path(path: Option<PathBuf>) -> Result<(PathBuf, PathBuf)> {
 /// * `force` - Delete existing index and rebuild from scratch
 /// * `global` - Create global index instead of local
 /// * `model` - Override embedding model
+/// * `prose_model` - Also embed every chunk with this natural-language model
+/// * `languages` - Only index these languages (names or extensions, `all` clears)
+/// * `max_chunks` - Embed at most this many chunks when switching to another model
 /// * `quiet` - Suppress verbose output (for server/MCP mode)
+#[allow(clippy::too_many_arguments)]
 pub async fn index(
     path: Option<PathBuf>,
     dry_run: bool,
     force: bool,
     global: bool,
     model: Option<ModelType>,
+    prose_model: Option<ModelType>,
+    languages: Option<Vec<String>>,
+    max_chunks: Option<usize>,
     cancel_token: CancellationToken,
 ) -> Result<()> {
-    index_with_options(path, dry_run, force, global, model, false, cancel_token).await
+    index_with_options(
+        path,
+        dry_run,
+        force,
+        global,
+        model,
+        prose_model,
+        languages,
+        max_chunks,
+        crate::output::is_quiet(),
+        cancel_token,
+    )
+    .await
 }
 
 /// Index a repository with quiet mode option (for server/MCP use)
@@ -378,27 +421,366 @@ pub async fn index_quiet(
     force: bool,
     cancel_token: CancellationToken,
 ) -> Result<()> {
-    index_with_options(path, false, force, false, None, true, cancel_token).await
+    index_with_options(
+        path,
+        false,
+        force,
+        false,
+        None,
+        None,
+        None,
+        None,
+        true,
+        cancel_token,
+    )
+    .await
+}
+
+/// Parse `--lang` / `CODESEARCH_LANGUAGES` entries into a language filter
+///
+/// Accepts language names ("rust", "TypeScript") and extensions ("rs", "ts").
+/// `all` clears the filter (`None`).
+fn parse_languages(names: &[String]) -> Result<Option<Vec<Language>>> {
+    let mut languages = Vec::new();
+    for name in names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        if name.eq_ignore_ascii_case("all") {
+            return Ok(None);
+        }
+        let language = Language::from_name(name)
+            .filter(|l| l.is_indexable())
+            .ok_or_else(|| anyhow::anyhow!("Unknown language: '{}'", name))?;
+        if !languages.contains(&language) {
+            languages.push(language);
+        }
+    }
+    Ok(if languages.is_empty() {
+        None
+    } else {
+        Some(languages)
+    })
+}
+
+/// Languages an index is restricted to, from metadata.json (`None` = all)
+pub(crate) fn read_language_filter(db_path: &Path) -> Option<Vec<Language>> {
+    match IndexMetadata::load(db_path) {
+        Ok(metadata) => metadata?.language_filter(),
+        Err(e) => {
+            tracing::warn!("⚠️  {:#}; indexing all languages", e);
+            None
+        }
+    }
+}
+
+/// Context lines to store around each chunk: `CODESEARCH_CONTEXT_LINES`,
+/// else what the index in `db_path` was built with
+pub(crate) fn context_lines(db_path: &Path) -> usize {
+    configured_context_lines().unwrap_or_else(|| {
+        IndexMetadata::load(db_path)
+            .ok()
+            .flatten()
+            .map_or(DEFAULT_CONTEXT_LINES, |metadata| metadata.context_lines())
+    })
+}
+
+/// `CODESEARCH_CONTEXT_LINES`, when set
+fn configured_context_lines() -> Option<usize> {
+    std::env::var("CODESEARCH_CONTEXT_LINES")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+}
+
+/// Directories pruned to keep the index under its size budget (see [`budget`])
+pub(crate) fn read_pruned_dirs(db_path: &Path) -> Vec<String> {
+    match IndexMetadata::load(db_path) {
+        Ok(metadata) => metadata.map(|m| m.pruned_dirs).unwrap_or_default(),
+        Err(e) => {
+            tracing::warn!("⚠️  {:#}; indexing every directory", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Record the language filter in an existing metadata.json
+fn write_language_filter(db_path: &Path, languages: Option<&[Language]>) -> Result<()> {
+    let Some(mut metadata) = IndexMetadata::load(db_path)? else {
+        return Ok(());
+    };
+    metadata.set_language_filter(languages);
+    metadata.save(db_path)
+}
+
+/// Save the project's CODEOWNERS rules in the database
+///
+/// Failures are logged: results then just show no owners.
+fn save_code_owners(project_path: &Path, db_path: &Path) {
+    if let Err(e) = CodeOwners::save_for_project(project_path, db_path) {
+        tracing::warn!("⚠️  {:#}; search results won't show owners", e);
+    }
+}
+
+/// Write metadata.json: embedding model(s), language scope and context
+/// lines of the index
+///
+/// `model` is `(short_name, name, dimensions)`, `prose` is `(short_name, dimensions)`.
+fn write_index_metadata(
+    db_path: &Path,
+    model: (&str, &str, usize),
+    prose: Option<&(String, usize)>,
+    languages: Option<&[Language]>,
+    context_lines: usize,
+) -> Result<()> {
+    let (model_short_name, model_name, model_dimensions) = model;
+    let mut metadata = IndexMetadata::new(model_short_name, model_name, model_dimensions);
+    // file_meta.json is unchanged by this, so its checksum and language
+    // breakdown still hold, and directories pruned for the size budget stay
+    // out of the index
+    if let Some(existing) = IndexMetadata::load(db_path).ok().flatten() {
+        metadata.content_checksum = existing.content_checksum;
+        metadata.set_language_stats(existing.language_stats);
+        metadata.pruned_dirs = existing.pruned_dirs;
+        metadata.migration = existing.migration;
+    }
+    if let Some((prose_short_name, prose_dimensions)) = prose {
+        metadata.prose_model_short_name = Some(prose_short_name.clone());
+        metadata.prose_dimensions = Some(*prose_dimensions);
+    }
+    metadata.set_language_filter(languages);
+    metadata.context_lines = Some(context_lines);
+    metadata.save(db_path)
+}
+
+/// Load the prose embedding model of a dual-embedded database
+///
+/// Returns `Ok(None)` for single-model databases. The model loads on a
+/// blocking thread with the retries of the code model (see
+/// [`EmbeddingService::with_cache_dir_retry_async`]).
+pub(crate) async fn load_prose_service(db_path: &Path) -> Result<Option<EmbeddingService>> {
+    let Some(prose_model) = IndexMetadata::load(db_path)?.and_then(|m| m.prose_model()) else {
+        return Ok(None);
+    };
+    let cache_dir = crate::constants::get_global_models_cache_dir()?;
+    EmbeddingService::with_cache_dir_retry_async(prose_model?, cache_dir, |_| {})
+        .await
+        .map(Some)
+}
+
+/// Chunks with their vectors, ready to be stored
+pub(crate) struct EmbeddedGroup {
+    pub chunks: Vec<EmbeddedChunk>,
+    /// Prose vectors of a dual-embedded database, parallel to `chunks`
+    pub prose: Option<Vec<Vec<f32>>>,
+    /// Chunks that took the stored vectors of moved code
+    pub reused: usize,
+}
+
+/// Code vectors (and prose vectors, given `prose_service`) of `chunks`
+///
+/// `stored` comes from [`VectorStore::moved_vectors`]: a chunk with the
+/// content of one deleted earlier in the run takes its vectors, so code that
+/// moved between files isn't embedded again. The others are embedded.
+pub(crate) fn embed_reusing(
+    chunks: Vec<Chunk>,
+    stored: Vec<Option<StoredVectors>>,
+    service: &mut EmbeddingService,
+    prose_service: Option<&mut EmbeddingService>,
+) -> Result<EmbeddedGroup> {
+    let without = |has: &dyn Fn(&StoredVectors) -> bool| -> Vec<Chunk> {
+        chunks
+            .iter()
+            .zip(&stored)
+            .filter(|(_, vectors)| !vectors.as_ref().is_some_and(has))
+            .map(|(chunk, _)| chunk.clone())
+            .collect()
+    };
+
+    let prose = match prose_service {
+        Some(prose_service) => {
+            let mut computed = prose_service
+                .embed_chunks(without(&|vectors| vectors.prose.is_some()))?
+                .into_iter();
+            let mut prose = Vec::with_capacity(chunks.len());
+            for vectors in &stored {
+                match vectors.as_ref().and_then(|vectors| vectors.prose.clone()) {
+                    Some(vector) => prose.push(vector),
+                    None => prose.extend(computed.next().map(|ec| ec.embedding)),
+                }
+            }
+            Some(prose)
+        }
+        None => None,
+    };
+
+    let mut computed = service.embed_chunks(without(&|_| true))?.into_iter();
+    let mut embedded = Vec::with_capacity(chunks.len());
+    let mut reused = 0;
+    for (chunk, vectors) in chunks.into_iter().zip(stored) {
+        match vectors {
+            Some(vectors) => {
+                embedded.push(EmbeddedChunk::new(chunk, vectors.code));
+                reused += 1;
+            }
+            None => embedded.extend(computed.next()),
+        }
+    }
+    Ok(EmbeddedGroup {
+        chunks: embedded,
+        prose,
+        reused,
+    })
+}
+
+/// Print what indexing would do: files and chunks to (re)index, estimated
+/// embedding time from a quick benchmark, and projected database size
+fn print_dry_run_estimate(
+    db_path: &Path,
+    files: &[crate::file::FileInfo],
+    force: bool,
+    model_type: ModelType,
+    prose_model: Option<ModelType>,
+) -> Result<()> {
+    use estimate::{format_duration, IndexEstimate};
+
+    out_print!("\n{}", "Phase 2: Estimate".bright_cyan());
+    out_print!("{}", "-".repeat(60));
+
+    // Incremental: only changed files would be re-chunked and re-embedded
+    let is_incremental = db_path.exists() && !force;
+    let files: Vec<crate::file::FileInfo> = if is_incremental {
+        let file_meta_store = FileMetaStore::load_or_create(
+            db_path,
+            model_type.short_name(),
+            model_type.dimensions(),
+        )?;
+        let mut changed = Vec::new();
+        for file in files {
+            if file_meta_store.check_file(&file.path)?.0 {
+                changed.push(file.clone());
+            }
+        }
+        out_print!(
+            "   Existing index: {} changed, {} deleted, {} unchanged files",
+            changed.len(),
+            file_meta_store.find_deleted_files().len(),
+            files.len() - changed.len()
+        );
+        changed
+    } else {
+        files.to_vec()
+    };
+
+    let estimate = IndexEstimate::scan(&files, model_type);
+    out_print!("   Files to index: {}", estimate.files);
+    out_print!(
+        "   Chunks: {} ({} already in the embedding cache)",
+        estimate.chunks,
+        estimate.cached_chunks
+    );
+    if estimate.chunks == 0 {
+        return Ok(());
+    }
+
+    let cache_dir = crate::constants::get_global_models_cache_dir()?;
+    let mut seconds = 0.0;
+    let mut benchmarked = true;
+    for (label, benchmark_model, chunks) in
+        std::iter::once(("", model_type, estimate.uncached_chunks()))
+            .chain(prose_model.map(|p| (" (prose)", p, estimate.chunks)))
+    {
+        match estimate.benchmark(benchmark_model, &cache_dir) {
+            Ok(Some(rate)) => {
+                out_print!(
+                    "   Embedding speed{}: {:.0} chunks/s ({})",
+                    label,
+                    rate,
+                    benchmark_model.short_name()
+                );
+                seconds += chunks as f64 / rate;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                out_print!(
+                    "   {}",
+                    format!(
+                        "⚠️  Benchmark with {} failed: {}",
+                        benchmark_model.short_name(),
+                        e
+                    )
+                    .yellow()
+                );
+                benchmarked = false;
+            }
+        }
+    }
+    if benchmarked {
+        out_print!("   Estimated embedding time: ~{}", format_duration(seconds));
+    }
+
+    let projected =
+        estimate.projected_db_bytes(model_type.dimensions(), prose_model.map(|p| p.dimensions()));
+    if is_incremental {
+        out_print!("   Projected database growth: ~{}", format_bytes(projected));
+    } else {
+        out_print!("   Projected database size: ~{}", format_bytes(projected));
+    }
+    Ok(())
 }
 
 /// Internal index function with all options
+#[allow(clippy::too_many_arguments)]
 async fn index_with_options(
     path: Option<PathBuf>,
     dry_run: bool,
     force: bool,
     global: bool,
     model: Option<ModelType>,
+    prose_model: Option<ModelType>,
+    languages: Option<Vec<String>>,
+    max_chunks: Option<usize>,
     quiet: bool,
     cancel_token: CancellationToken,
 ) -> Result<()> {
-    let (db_path, project_path) = get_db_path_smart(path, global, force)?;
-    let model_type = model.unwrap_or_default();
+    // A dry run must never delete the existing index, even with --force
+    let (db_path, project_path) = get_db_path_smart(path, global, force && !dry_run)?;
+    let existing = if force {
+        None
+    } else {
+        IndexMetadata::load(&db_path)?
+    };
+
+    // Without --model, keep the model the existing index was built with
+    let model_type = model
+        .or_else(|| existing.as_ref().map(|metadata| metadata.model_type().0))
+        .unwrap_or_default();
+
+    // Dual embedding: keep using the prose model an existing index was built with
+    let existing_prose = existing
+        .as_ref()
+        .and_then(IndexMetadata::prose_model)
+        .transpose()?;
+    let prose_model = prose_model.or(existing_prose);
+
+    // Language scope: --lang, else CODESEARCH_LANGUAGES, else the existing index's scope
+    let languages = match languages {
+        Some(names) => parse_languages(&names)?,
+        None => match std::env::var("CODESEARCH_LANGUAGES") {
+            Ok(value) => parse_languages(&value.split(',').map(String::from).collect::<Vec<_>>())?,
+            Err(_) if !force => read_language_filter(&db_path),
+            Err(_) => None,
+        },
+    };
+
+    // Context lines: CODESEARCH_CONTEXT_LINES, else what the existing index used
+    let context_lines = match configured_context_lines() {
+        Some(lines) => lines,
+        None if !force => self::context_lines(&db_path),
+        None => DEFAULT_CONTEXT_LINES,
+    };
 
     // Macro to conditionally print
     macro_rules! log_print {
         ($($arg:tt)*) => {
             if !quiet {
-                println!($($arg)*);
+                out_print!($($arg)*);
             }
         };
     }
@@ -412,9 +794,76 @@ async fn index_with_options(
         model_type.name(),
         model_type.dimensions()
     );
+    if let Some(prose_type) = prose_model {
+        log_print!(
+            "📝 Prose model: {} ({} dims)",
+            prose_type.name(),
+            prose_type.dimensions()
+        );
+    }
+
+    if let Some(ref languages) = languages {
+        let names: Vec<&str> = languages.iter().map(|l| l.name()).collect();
+        log_print!("🌐 Languages: {}", names.join(", "));
+    }
+
+    if context_lines != DEFAULT_CONTEXT_LINES {
+        log_print!("📏 Context lines: {}", context_lines);
+    }
+
+    // Another model: embed the stored chunks again instead of re-indexing
+    if let Some(existing) = existing.filter(|metadata| {
+        !dry_run
+            && (metadata.model_short_name != model_type.short_name()
+                || metadata.dimensions != model_type.dimensions())
+    }) {
+        return migration::migrate_model(
+            &db_path,
+            existing,
+            model_type,
+            max_chunks,
+            quiet,
+            &cancel_token,
+        );
+    }
+    if let Some(migration) = IndexMetadata::load(&db_path)
+        .ok()
+        .flatten()
+        .and_then(|metadata| metadata.migration)
+    {
+        log_print!(
+            "{}",
+            format!(
+                "⏸️  Switch to {} is {} of {} chunks done; run 'codesearch index --model {}' to continue",
+                migration.model_short_name,
+                migration.migrated_chunks,
+                migration.total_chunks,
+                migration.model_short_name
+            )
+            .yellow()
+        );
+    }
 
     if dry_run {
         log_print!("\n{}", "🔍 DRY RUN MODE".bright_yellow());
+    } else if !force {
+        if let Some(quarantined) = recover_if_corrupt(&db_path)? {
+            log_print!(
+                "{}",
+                format!(
+                    "⚠️  Database was corrupted and moved to {}; rebuilding the index",
+                    quarantined.display()
+                )
+                .yellow()
+            );
+        }
+    }
+
+    if !dry_run {
+        if let Some(command) = hooks::Hook::PreIndex.command() {
+            log_print!("🪝 Running pre-index hook: {}", command);
+            hooks::run(hooks::Hook::PreIndex, &project_path, &db_path).await?;
+        }
     }
 
     // Phase 1: File Discovery
@@ -422,7 +871,13 @@ async fn index_with_options(
     log_print!("{}", "-".repeat(60));
 
     let start = Instant::now();
-    let walker = FileWalker::new(project_path.clone());
+    let walker = FileWalker::new(project_path.clone())
+        .with_languages(languages.clone())
+        .with_excluded_dirs(if force {
+            Vec::new()
+        } else {
+            read_pruned_dirs(&db_path)
+        });
     let (mut files, stats) = walker.walk()?;
     let discovery_duration = start.elapsed();
 
@@ -441,12 +896,21 @@ async fn index_with_options(
     }
 
     if dry_run {
+        print_dry_run_estimate(&db_path, &files, force, model_type, prose_model)?;
         log_print!("\n{}", "Dry run complete!".green());
         return Ok(());
     }
 
     let is_incremental = db_path.exists() && !force;
 
+    if is_incremental && prose_model.is_some() && existing_prose.is_none() {
+        log_print!(
+            "{}",
+            "⚠️  Existing index has no prose embeddings; only changed files get them. Use --force to dual-embed everything."
+                .yellow()
+        );
+    }
+
     // Load FileMetaStore for incremental indexing (will be used later to update metadata)
     let mut file_meta_store = if is_incremental {
         log_print!("\n{}", "📊 Incremental Indexing".bright_cyan());
@@ -462,6 +926,11 @@ async fn index_with_options(
     };
 
     if is_incremental {
+        // Files of languages dropped from the scope show up as deleted below
+        write_language_filter(&db_path, languages.as_deref())?;
+        // Ownership can change without any indexed file changing
+        save_code_owners(&project_path, &db_path);
+
         let file_meta_store = file_meta_store.as_mut().unwrap();
 
         // Find changed and deleted files
@@ -509,58 +978,69 @@ async fn index_with_options(
 
         if total_chunks_to_delete > 0 {
             log_print!("\n🔄 Deleting {} old chunks...", total_chunks_to_delete);
+        }
 
-            let mut store = VectorStore::new(&db_path, 384)?; // Will load dimensions from DB
-            let mut fts_store = FtsStore::new_with_writer(&db_path)?;
-
-            // Delete deleted files' metadata and chunks
-            for (file_path, chunk_ids) in deleted_files {
-                if !chunk_ids.is_empty() {
-                    info!(
-                        "🗑️  Deleting {} chunks for deleted file: {}",
-                        chunk_ids.len(),
-                        file_path
-                    );
-                    debug!("   File path: {}", file_path);
-                    store.delete_chunks(&chunk_ids)?;
-                    for chunk_id in &chunk_ids {
-                        fts_store.delete_chunk(*chunk_id)?;
-                    }
-                }
-                file_meta_store.remove_file(Path::new(&file_path));
+        let mut store = VectorStore::new(&db_path, 384)?; // Will load dimensions from DB
+        let mut fts_store = FtsStore::new_with_writer(&db_path)?;
+        let mut deleted_chunks = 0;
+
+        // Delete deleted files' metadata and chunks. Deleting by path as
+        // well catches chunks the file metadata lost track of.
+        for (file_path, chunk_ids) in deleted_files {
+            if !chunk_ids.is_empty() {
+                info!(
+                    "🗑️  Deleting {} chunks for deleted file: {}",
+                    chunk_ids.len(),
+                    file_path
+                );
+                debug!("   File path: {}", file_path);
+            }
+            deleted_chunks += store.delete_chunks(&chunk_ids)?;
+            deleted_chunks += store.delete_by_path(&file_path)?.len();
+            for chunk_id in &chunk_ids {
+                fts_store.delete_chunk(*chunk_id)?;
             }
+            fts_store.delete_by_path(&file_path)?;
+            file_meta_store.remove_file(Path::new(&file_path));
+        }
 
-            // Delete changed files' old chunks
-            for file in &changed_files {
-                let (_, old_chunk_ids) = file_meta_store.check_file(&file.path)?;
-                if !old_chunk_ids.is_empty() {
-                    let file_path_str = file.path.to_string_lossy().to_string();
-                    info!(
-                        "🔄 Deleting {} old chunks for changed file: {}",
-                        old_chunk_ids.len(),
-                        file_path_str
-                    );
-                    debug!("   File path: {}", file.path.display());
-                    store.delete_chunks(&old_chunk_ids)?;
-                    for chunk_id in &old_chunk_ids {
-                        fts_store.delete_chunk(*chunk_id)?;
-                    }
-                }
+        // Delete changed files' old chunks
+        for file in &changed_files {
+            let (_, old_chunk_ids) = file_meta_store.check_file(&file.path)?;
+            let file_path_str = normalize_path(&file.path);
+            if !old_chunk_ids.is_empty() {
+                info!(
+                    "🔄 Deleting {} old chunks for changed file: {}",
+                    old_chunk_ids.len(),
+                    file_path_str
+                );
+                debug!("   File path: {}", file.path.display());
+            }
+            deleted_chunks += store.delete_chunks(&old_chunk_ids)?;
+            deleted_chunks += store.delete_by_path(&file_path_str)?.len();
+            for chunk_id in &old_chunk_ids {
+                fts_store.delete_chunk(*chunk_id)?;
             }
+            fts_store.delete_by_path(&file_path_str)?;
+        }
 
-            fts_store.commit()?;
+        fts_store.commit()?;
 
+        if deleted_chunks > 0 {
             // Rebuild vector index after deletions - critical for ANN search correctness
             log_print!("🔨 Rebuilding vector index after deletions...");
-            store.build_index()?;
-
-            log_print!("✅ Deleted {} chunks", total_chunks_to_delete);
+            if !build_vector_index_with_spinner(&mut store, &cancel_token, quiet)? {
+                log_print!("\n{}", "⚠️  Indexing cancelled by user".yellow());
+                return Ok(());
+            }
 
-            // Explicitly drop stores to release LMDB memory map before Phase 2
-            drop(store);
-            drop(fts_store);
+            log_print!("✅ Deleted {} chunks", deleted_chunks);
         }
 
+        // Explicitly drop stores to release LMDB memory map before Phase 2
+        drop(store);
+        drop(fts_store);
+
         // Only process changed files
         log_print!("\n🔄 Processing {} changed files...", changed_files.len());
         files = changed_files;
@@ -570,6 +1050,10 @@ async fn index_with_options(
         // only runs when not in incremental mode, i.e., fresh index creation.
     }
 
+    // Recently touched files first: together with the searchable tranches below
+    // they are found within seconds of starting an index on a large repository
+    crate::file::order_by_recency(&project_path, &mut files);
+
     // Phase 2: Semantic Chunking + Embedding + Storage (Streaming)
     // We process files one at a time to keep memory usage low
     log_print!(
@@ -579,8 +1063,9 @@ async fn index_with_options(
     log_print!("{}", "-".repeat(60));
 
     let chunking_start = Instant::now();
-    let mut chunker = SemanticChunker::new(100, 2000, 10);
+    let mut chunker = SemanticChunker::new(100, 2000, 10).with_context_lines(context_lines);
     let mut total_chunks = 0;
+    let mut moved_chunks = 0;
 
     let pb = if quiet {
         ProgressBar::hidden()
@@ -590,7 +1075,11 @@ async fn index_with_options(
             ProgressStyle::default_bar()
                 .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos}/{len} {msg}")
                 .unwrap()
-                .progress_chars("█▓▒░ "),
+                .progress_chars(if crate::output::is_plain() {
+                    "#>-"
+                } else {
+                    "█▓▒░ "
+                }),
         );
         pb
     };
@@ -599,6 +1088,13 @@ async fn index_with_options(
     let cache_dir = crate::constants::get_global_models_cache_dir()?;
     let mut embedding_service =
         EmbeddingService::with_cache_dir(model_type, Some(cache_dir.as_path()))?;
+    let mut prose_service = match prose_model {
+        Some(prose_type) => Some(EmbeddingService::with_cache_dir(
+            prose_type,
+            Some(cache_dir.as_path()),
+        )?),
+        None => None,
+    };
 
     // Check for shutdown after model loading (can take 5-10 seconds)
     if crate::constants::check_shutdown(&cancel_token) {
@@ -609,8 +1105,30 @@ async fn index_with_options(
         return Ok(());
     }
 
+    // Capture model info for metadata.json (written per tranche and at the end)
+    let model_short_name = embedding_service.model_short_name().to_string();
+    let model_name = embedding_service.model_name().to_string();
+    let model_dimensions = embedding_service.dimensions();
+    let prose_info = prose_service
+        .as_ref()
+        .map(|ps| (ps.model_short_name().to_string(), ps.dimensions()));
+
     // Initialize vector store
     let mut store = VectorStore::new(&db_path, embedding_service.dimensions())?;
+    if let Some(prose_service) = &prose_service {
+        store.enable_prose_index(prose_service.dimensions())?;
+    }
+
+    // Pre-size the LMDB map for the whole run, so a large index doesn't grow
+    // through MDB_MAP_FULL retries that redo the failed write
+    let projected = estimate::project_from_sizes(&files);
+    let added = projected.projected_db_bytes(store.dimensions(), store.prose_dimensions());
+    if let Some(map_size_mb) = store.reserve(added)? {
+        info!(
+            "📐 Database map sized to {} MB for ~{} chunks",
+            map_size_mb, projected.chunks
+        );
+    }
 
     // Initialize FTS store
     let mut fts_store = FtsStore::new_with_writer(&db_path)?;
@@ -621,9 +1139,28 @@ async fn index_with_options(
 
     // Arena reset interval: periodically recreate the ONNX session to free
     // arena allocator memory that grows monotonically. Model is on disk, so
-    let mut skipped_files: Vec<String> = Vec::new();
+    let mut skipped_files: Vec<(String, String)> = Vec::new();
+    // Files that failed in a row; a long streak means the model or the disk is
+    // broken rather than the files, and ends the run
+    let mut consecutive_failures = 0;
     let mut cancelled = false;
-    for file in &files {
+
+    // Searchable tranches: every `tranche_chunks` chunks the vector index is
+    // built and metadata saved, so searches find the files indexed so far
+    let tranche_chunks = std::env::var("CODESEARCH_INDEX_TRANCHE_CHUNKS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(INDEX_TRANCHE_CHUNKS);
+    let mut next_tranche = tranche_chunks;
+    let embed_batch = std::env::var("CODESEARCH_INDEX_EMBED_BATCH_CHUNKS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&n: &usize| n > 0)
+        .unwrap_or(INDEX_EMBED_BATCH_CHUNKS);
+    // Files indexed since the last tranche, not yet in the saved file metadata
+    let mut unsaved_files: Vec<String> = Vec::new();
+    let tracker = IndexingTracker::start(&db_path, files.len());
+    for (files_done, file) in files.iter().enumerate() {
         // Check for cancellation before processing each file
         // Uses BOTH global AtomicBool (set by ctrlc OS handler) AND CancellationToken (for programmatic cancel)
         if crate::constants::check_shutdown(&cancel_token) {
@@ -637,38 +1174,52 @@ async fn index_with_options(
         ));
 
         debug!("📄 Processing file: {}", file.path.display());
+        tracker.file(&file.path, files_done, total_chunks);
 
-        // Read file content with encoding fallback (UTF-8 → lossy)
-        let source_code = match std::fs::read_to_string(&file.path) {
-            Ok(content) => {
-                // UTF-8 succeeded
-                content
-            }
-            Err(utf8_err) if utf8_err.kind() == std::io::ErrorKind::InvalidData => {
-                // UTF-8 failed — try lossy decode (handles ISO-8859-1, Windows-1252, etc.)
-                match std::fs::read(&file.path) {
-                    Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
-                    Err(read_err) => {
-                        skipped_files.push(format!(
-                            "{} (read failed: {})",
-                            file.path.display(),
-                            read_err
-                        ));
-                        pb.inc(1);
-                        continue;
-                    }
+        // A file that fails to chunk, embed or store is skipped and reported
+        // after the run instead of aborting it
+        macro_rules! skip_file {
+            ($stage:expr, $err:expr) => {{
+                let err: anyhow::Error = $err;
+                tracing::warn!(
+                    "Skipping {}: {} failed: {:#}",
+                    file.path.display(),
+                    $stage,
+                    err
+                );
+                consecutive_failures += 1;
+                if consecutive_failures >= INDEX_MAX_CONSECUTIVE_FAILURES {
+                    return Err(err.context(format!(
+                        "{} files in a row failed to index; stopping",
+                        consecutive_failures
+                    )));
                 }
-            }
+                skipped_files.push((
+                    file.path.display().to_string(),
+                    format!("{} failed: {:#}", $stage, err),
+                ));
+                pb.inc(1);
+                continue;
+            }};
+        }
+
+        // Read file content, transcoding UTF-16/Latin-1 to UTF-8
+        let source_code = match crate::file::read_source(&file.path) {
+            Ok(content) => content,
             Err(e) => {
-                // Other error (permission denied, file not found, etc.)
-                skipped_files.push(format!("{} ({})", file.path.display(), e));
+                // Permission denied, file not found, etc.
+                skipped_files.push((file.path.display().to_string(), format!("{}", e)));
                 pb.inc(1);
                 continue;
             }
         };
 
         // Phase 2a: Chunk this file only (memory efficient!)
-        let chunks = chunker.chunk_semantic(file.language, &file.path, &source_code)?;
+        tracker.step("chunking");
+        let chunks = match chunk_isolated(&mut chunker, file, &source_code) {
+            Ok(chunks) => chunks,
+            Err(e) => skip_file!("chunking", e),
+        };
         let chunk_count = chunks.len();
         debug!(
             "   Created {} chunks for {}",
@@ -682,65 +1233,127 @@ async fn index_with_options(
             // but unchunkable" — record it with an empty chunk list so check_file()
             // returns (unchanged) on future runs and doctor doesn't flag it.
             let path_str = file.path.to_string_lossy().to_string();
-            file_chunks.insert(path_str, vec![]);
+            file_chunks.insert(path_str.clone(), vec![]);
+            unsaved_files.push(path_str);
             pb.inc(1);
             continue;
         }
 
-        // Phase 2b: Embed chunks for this file only (batched internally)
-        // If embedding is interrupted by CTRL-C, catch it as cancellation (not error)
-        let embedded_chunks = match embedding_service.embed_chunks(chunks) {
-            Ok(chunks) => chunks,
-            Err(_) if crate::constants::is_shutdown_requested() => {
+        // Phase 2b-2e: Embed, store and full-text index the file's chunks in
+        // groups of `embed_batch` chunks. A group is stored before the next
+        // one is embedded, so a huge file never holds all of its embeddings
+        // (and their copies for prose and FTS) in memory at once.
+        let mut chunk_ids: Vec<u32> = Vec::with_capacity(chunk_count);
+        let mut failure: Option<(&str, anyhow::Error)> = None;
+        let mut remaining = chunks.into_iter();
+        loop {
+            let group: Vec<_> = remaining.by_ref().take(embed_batch).collect();
+            if group.is_empty() {
+                break;
+            }
+
+            // If embedding is interrupted by CTRL-C, catch it as cancellation (not error).
+            // Dual embedding: second vector per chunk from the prose model, same IDs
+            tracker.step("embedding");
+            let embedded = store.moved_vectors(&group).and_then(|stored| {
+                embed_reusing(
+                    group,
+                    stored,
+                    &mut embedding_service,
+                    prose_service.as_mut(),
+                )
+            });
+            let (embedded_chunks, prose_embeddings) = match embedded {
+                Ok(embedded) => {
+                    moved_chunks += embedded.reused;
+                    (embedded.chunks, embedded.prose)
+                }
+                Err(_) if crate::constants::is_shutdown_requested() => {
+                    cancelled = true;
+                    break;
+                }
+                Err(e) => {
+                    failure = Some(("embedding", e));
+                    break;
+                }
+            };
+
+            // Check cancellation after embedding (most CPU-intensive step)
+            if crate::constants::check_shutdown(&cancel_token) {
                 cancelled = true;
                 break;
             }
-            Err(e) => return Err(e),
-        };
 
-        // Check cancellation after embedding (most CPU-intensive step)
-        if crate::constants::check_shutdown(&cancel_token) {
-            cancelled = true;
-            break;
-        }
+            // Extract lightweight FTS data before handing ownership to vector store.
+            // We capture just the strings needed for FTS (content, path, signature, kind)
+            // so we can pass full EmbeddedChunks to the vector store without cloning.
+            let fts_data: Vec<(String, String, Option<String>, String)> = embedded_chunks
+                .iter()
+                .map(|ec| {
+                    (
+                        ec.chunk.content.clone(),
+                        ec.chunk.path.clone(),
+                        ec.chunk.fts_signature(),
+                        format!("{:?}", ec.chunk.kind),
+                    )
+                })
+                .collect();
+
+            // Insert into vector store (takes ownership, no clone needed)
+            tracker.step("storing");
+            let group_ids = match store.insert_chunks_with_ids(embedded_chunks) {
+                Ok(ids) => ids,
+                Err(e) => {
+                    failure = Some(("storing", e));
+                    break;
+                }
+            };
+            chunk_ids.extend_from_slice(&group_ids);
+            if let Some(prose_embeddings) = &prose_embeddings {
+                if let Err(e) = store.insert_prose_embeddings(&group_ids, prose_embeddings) {
+                    failure = Some(("storing", e));
+                    break;
+                }
+            }
 
-        // Phase 2c: Extract lightweight FTS data before handing ownership to vector store.
-        // We capture just the strings needed for FTS (content, path, signature, kind)
-        // so we can pass full EmbeddedChunks to the vector store without cloning.
-        let fts_data: Vec<(String, String, Option<String>, String)> = embedded_chunks
-            .iter()
-            .map(|ec| {
-                (
-                    ec.chunk.content.clone(),
-                    ec.chunk.path.clone(),
-                    ec.chunk.signature.clone(),
-                    format!("{:?}", ec.chunk.kind),
-                )
-            })
-            .collect();
-
-        // Phase 2d: Insert into vector store (takes ownership, no clone needed)
-        let chunk_ids = store.insert_chunks_with_ids(embedded_chunks)?;
-
-        // Phase 2e: Insert into FTS with real chunk IDs from vector store.
-        // FTS failures are non-fatal: vector search is the primary search method,
-        // FTS (BM25) is supplementary for hybrid search. If tantivy encounters
-        // I/O errors (common on Windows due to antivirus interference), we log
-        // a warning and continue rather than aborting the entire indexing run.
-        for ((content, path, signature, kind), &chunk_id) in fts_data.iter().zip(chunk_ids.iter()) {
-            if let Err(e) = fts_store.add_chunk(chunk_id, content, path, signature.as_deref(), kind)
+            // Insert into FTS with real chunk IDs from vector store.
+            // FTS failures are non-fatal: vector search is the primary search method,
+            // FTS (BM25) is supplementary for hybrid search. If tantivy encounters
+            // I/O errors (common on Windows due to antivirus interference), we log
+            // a warning and continue rather than aborting the entire indexing run.
+            for ((content, path, signature, kind), &chunk_id) in
+                fts_data.iter().zip(group_ids.iter())
             {
-                tracing::warn!(
-                    "FTS add_chunk failed in {}: {} (continuing without FTS for this chunk)",
-                    file.path.display(),
-                    e
-                );
+                if let Err(e) =
+                    fts_store.add_chunk(chunk_id, content, path, signature.as_deref(), kind)
+                {
+                    tracing::warn!(
+                        "FTS add_chunk failed in {}: {} (continuing without FTS for this chunk)",
+                        file.path.display(),
+                        e
+                    );
+                }
             }
         }
+        if (cancelled || failure.is_some()) && !chunk_ids.is_empty() {
+            // Don't keep part of a file: drop the groups already stored
+            let _ = store.delete_chunks(&chunk_ids);
+            for &chunk_id in &chunk_ids {
+                let _ = fts_store.delete_chunk(chunk_id);
+            }
+        }
+        if cancelled {
+            break;
+        }
+        if let Some((stage, e)) = failure {
+            skip_file!(stage, e);
+        }
+        consecutive_failures = 0;
 
         // Track chunk IDs per file for metadata (only paths and IDs, not chunk content)
         let file_path = file.path.to_string_lossy().to_string();
-        file_chunks.insert(file_path, chunk_ids.clone());
+        file_chunks.insert(file_path.clone(), chunk_ids.clone());
+        unsaved_files.push(file_path);
 
         total_chunks += chunk_count;
         pb.inc(1);
@@ -758,6 +1371,39 @@ async fn index_with_options(
             }
         }
 
+        if tranche_chunks > 0 && total_chunks >= next_tranche {
+            next_tranche = total_chunks + tranche_chunks;
+            if let Err(e) = fts_store.commit() {
+                tracing::warn!(
+                    "FTS commit failed at tranche of {} chunks: {}",
+                    total_chunks,
+                    e
+                );
+            }
+            tracker.phase("building vector index (tranche)");
+            if !build_vector_index(&mut store, &cancel_token, &pb)? {
+                cancelled = true;
+                break;
+            }
+            write_index_metadata(
+                &db_path,
+                (&model_short_name, &model_name, model_dimensions),
+                prose_info.as_ref(),
+                languages.as_deref(),
+                context_lines,
+            )?;
+            let meta = file_meta_store.get_or_insert_with(|| {
+                FileMetaStore::new(model_type.short_name().to_string(), model_type.dimensions())
+            });
+            for path in unsaved_files.drain(..) {
+                if let Some(chunk_ids) = file_chunks.get(&path) {
+                    meta.update_file(Path::new(&path), chunk_ids.clone())?;
+                }
+            }
+            meta.save(&db_path)?;
+            info!("🔎 {} chunks searchable", total_chunks);
+        }
+
         // Memory is freed here - chunks/embeddings dropped before next file
     }
 
@@ -768,6 +1414,7 @@ async fn index_with_options(
 
         // Free ONNX model memory immediately
         drop(embedding_service);
+        drop(prose_service);
         drop(chunker);
 
         // Don't call build_index() — it blocks for 10-30 seconds on large datasets.
@@ -797,14 +1444,21 @@ async fn index_with_options(
         return Ok(());
     }
 
-    // Capture model info before dropping the ONNX model
-    let model_short_name = embedding_service.model_short_name().to_string();
-    let model_name = embedding_service.model_name().to_string();
-    let model_dimensions = embedding_service.dimensions();
+    // Persistent-cache hits vs ONNX computations of this run, kept for stats/doctor
+    let mut cache_usage = embedding_service.cache_usage();
+    if let Some(prose_service) = &prose_service {
+        cache_usage.add(&prose_service.cache_usage());
+    }
+    let earlier_ms_per_chunk =
+        CacheUsageLog::load(&db_path).and_then(|log| log.total.ms_per_chunk());
+    if let Err(e) = CacheUsageLog::record(&db_path, &cache_usage) {
+        tracing::warn!("Failed to record embedding cache usage: {}", e);
+    }
 
     // Free ONNX model + arena allocator memory before final index operations
     // This releases hundreds of MB of inference buffers
     drop(embedding_service);
+    drop(prose_service);
     drop(chunker);
 
     // Commit FTS store (non-fatal: vector search works without FTS)
@@ -817,11 +1471,11 @@ async fn index_with_options(
 
     if !skipped_files.is_empty() {
         log_print!(
-            "   ⚠️  Skipped {} files (failed to read):",
+            "   ⚠️  Skipped {} files (listed by 'codesearch doctor' until they index):",
             skipped_files.len()
         );
-        for path in &skipped_files {
-            log_print!("      - {}", path);
+        for (path, reason) in &skipped_files {
+            log_print!("      - {} ({})", path, reason);
         }
     }
 
@@ -838,33 +1492,43 @@ async fn index_with_options(
         // Still save file metadata for files that were processed but produced 0 chunks
         // (e.g. minified JS, binary-like files). Without this, those files would be
         // detected as "changed" on every subsequent run and never stabilise.
-        if !file_chunks.is_empty() {
-            if is_incremental {
-                let mut store = file_meta_store.take().unwrap();
-                let file_count = file_chunks.len();
-                for (file_path, chunk_ids) in file_chunks {
-                    store.update_file(Path::new(&file_path), chunk_ids)?;
-                }
-                store.save(&db_path)?;
+        // Incremental runs always save: a run that only removed files (deleted
+        // from disk or dropped from the language scope) must persist that too.
+        if is_incremental {
+            let mut store = file_meta_store.take().unwrap();
+            let file_count = file_chunks.len();
+            for (file_path, chunk_ids) in file_chunks {
+                store.update_file(Path::new(&file_path), chunk_ids)?;
+            }
+            record_skipped_files(&mut store, &skipped_files);
+            store.save(&db_path)?;
+            if file_count > 0 {
                 log_print!(
                     "✅ Updated metadata for {} unchunkable files (0 chunks produced)",
                     file_count
                 );
-            } else {
-                let mut store = FileMetaStore::new(
-                    model_type.short_name().to_string(),
-                    model_type.dimensions(),
-                );
-                for (file_path, chunk_ids) in file_chunks {
-                    store.update_file(Path::new(&file_path), chunk_ids)?;
-                }
-                store.save(&db_path)?;
             }
+        } else if !file_chunks.is_empty() || !skipped_files.is_empty() {
+            let mut store =
+                FileMetaStore::new(model_type.short_name().to_string(), model_type.dimensions());
+            for (file_path, chunk_ids) in file_chunks {
+                store.update_file(Path::new(&file_path), chunk_ids)?;
+            }
+            record_skipped_files(&mut store, &skipped_files);
+            store.save(&db_path)?;
         }
         log_print!("\n{}", "No chunks created!".yellow());
         return Ok(());
     }
 
+    // Merge the segments this run committed while nothing searches yet
+    tracker.phase("merging full-text segments");
+    match fts_store.merge_segments(&MergeSettings::from_env()) {
+        Ok(0) => {}
+        Ok(merged) => debug!("Merged {} FTS segments", merged),
+        Err(e) => tracing::warn!("Failed to merge FTS segments: {}", e),
+    }
+
     // Capture FTS stats before dropping the store to free memory
     let _fts_stats = fts_store.stats()?;
 
@@ -875,20 +1539,32 @@ async fn index_with_options(
 
     // Build vector index (now that all chunks are inserted)
     let storage_start = Instant::now();
-    store.build_index()?;
+    tracker.phase("building vector index");
+    if !build_vector_index_with_spinner(&mut store, &cancel_token, quiet)? {
+        log_print!(
+            "\n{}",
+            "⚠️  Indexing cancelled while building the vector index".yellow()
+        );
+        log_print!(
+            "   Run {} to finish the index",
+            "codesearch index".bright_cyan()
+        );
+        return Ok(());
+    }
     let _storage_duration = storage_start.elapsed();
 
     // Save model metadata
-    let metadata = serde_json::json!({
-        "model_short_name": model_short_name,
-        "model_name": model_name,
-        "dimensions": model_dimensions,
-        "indexed_at": chrono::Utc::now().to_rfc3339(),
-    });
-    std::fs::write(
-        db_path.join("metadata.json"),
-        serde_json::to_string_pretty(&metadata)?,
+    tracker.phase("saving metadata");
+    write_index_metadata(
+        &db_path,
+        (&model_short_name, &model_name, model_dimensions),
+        prose_info.as_ref(),
+        languages.as_deref(),
+        context_lines,
     )?;
+    if !is_incremental {
+        save_code_owners(&project_path, &db_path);
+    }
 
     // Update FileMetaStore with new chunk IDs (incremental mode)
     if is_incremental {
@@ -903,6 +1579,7 @@ async fn index_with_options(
         for (file_path, chunk_ids) in file_chunks {
             file_meta_store.update_file(Path::new(&file_path), chunk_ids)?;
         }
+        record_skipped_files(&mut file_meta_store, &skipped_files);
 
         // Save FileMetaStore (includes both unchanged + updated files)
         file_meta_store.save(&db_path)?;
@@ -920,11 +1597,32 @@ async fn index_with_options(
         for (file_path, chunk_ids) in file_chunks {
             file_meta_store.update_file(Path::new(&file_path), chunk_ids)?;
         }
+        record_skipped_files(&mut file_meta_store, &skipped_files);
 
         // Save FileMetaStore
         file_meta_store.save(&db_path)?;
     }
 
+    if budget::size_budget().is_some() {
+        tracker.phase("enforcing size budget");
+        let mut fts_store = FtsStore::new_with_writer(&db_path)?;
+        if let Some(pruning) =
+            budget::enforce_size_budget(&db_path, &project_path, &mut store, &mut fts_store)?
+        {
+            log_print!("✂️  {}", pruning.summary());
+            if pruning.over_budget() {
+                log_print!(
+                    "{}",
+                    "⚠️  Still over CODESEARCH_INDEX_MAX_MB after pruning".yellow()
+                );
+            }
+        }
+    }
+
+    if let Err(e) = size_history::SizeHistory::record(&db_path, &store) {
+        tracing::warn!("Failed to record index size: {}", e);
+    }
+
     // Show final stats
     let db_stats = store.stats()?;
     log_print!("\n{}", "📊 Final Statistics".bright_green().bold());
@@ -951,20 +1649,114 @@ async fn index_with_options(
         total_size as f64 / (1024.0 * 1024.0)
     );
 
+    log_print!(
+        "   Embedding cache: {}",
+        cache_usage.summary(earlier_ms_per_chunk)
+    );
+    if moved_chunks > 0 {
+        log_print!(
+            "   Moved code: {} chunks kept their vectors, not embedded again",
+            moved_chunks
+        );
+    }
+
     log_print!("\n{}", "✨ Indexing complete".bright_green().bold());
     log_print!(
         "   Run {} to search your codebase",
         "codesearch search <query>".bright_cyan()
     );
 
+    if let Some(command) = hooks::Hook::PostIndex.command() {
+        log_print!("🪝 Running post-index hook: {}", command);
+        hooks::run_post(hooks::Hook::PostIndex, &project_path, &db_path).await;
+    }
+
     Ok(())
 }
 
+/// Chunk one file, turning a parser panic into an error
+///
+/// After a panic the chunker is replaced, since its parser state is unknown.
+fn chunk_isolated(
+    chunker: &mut SemanticChunker,
+    file: &crate::file::FileInfo,
+    source: &str,
+) -> Result<Vec<crate::chunker::Chunk>> {
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        chunker.chunk_semantic(file.language, &file.path, source)
+    }));
+    result.unwrap_or_else(|panic| {
+        *chunker = SemanticChunker::new(100, 2000, 10).with_context_lines(chunker.context_lines());
+        let message = panic
+            .downcast_ref::<&str>()
+            .map(|s| s.to_string())
+            .or_else(|| panic.downcast_ref::<String>().cloned())
+            .unwrap_or_else(|| "unknown panic".to_string());
+        Err(anyhow::anyhow!("chunker panicked: {}", message))
+    })
+}
+
+/// Build the vector index, showing tree-build progress as the message of `pb`
+///
+/// Returns `Ok(false)` when indexing is cancelled (Ctrl-C or `cancel_token`)
+/// during the build; the store keeps its previous index then.
+fn build_vector_index(
+    store: &mut VectorStore,
+    cancel_token: &CancellationToken,
+    pb: &ProgressBar,
+) -> Result<bool> {
+    let result = store.build_index_with(
+        &|| crate::constants::check_shutdown(cancel_token),
+        &|progress: BuildProgress| {
+            debug!("🌲 Vector index: {}", progress.describe());
+            pb.set_message(progress.describe());
+        },
+    );
+    match result {
+        Ok(()) => Ok(true),
+        Err(_) if crate::constants::check_shutdown(cancel_token) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// [`build_vector_index`] with its own spinner, for builds outside the file loop
+fn build_vector_index_with_spinner(
+    store: &mut VectorStore,
+    cancel_token: &CancellationToken,
+    quiet: bool,
+) -> Result<bool> {
+    let spinner = if quiet {
+        ProgressBar::hidden()
+    } else {
+        let spinner = ProgressBar::new_spinner();
+        let mut style = ProgressStyle::default_spinner()
+            .template("{spinner} {msg}")
+            .unwrap();
+        if crate::output::is_plain() {
+            style = style.tick_chars("|/-\\ ");
+        }
+        spinner.set_style(style);
+        spinner.enable_steady_tick(std::time::Duration::from_millis(120));
+        spinner
+    };
+    let built = build_vector_index(store, cancel_token, &spinner);
+    spinner.finish_and_clear();
+    built
+}
+
+/// Record the files a run skipped in the file metadata, so `codesearch doctor`
+/// lists them until they index successfully
+fn record_skipped_files(store: &mut FileMetaStore, skipped: &[(String, String)]) {
+    for (path, reason) in skipped {
+        store.record_failure(Path::new(path), reason);
+    }
+}
+
 /// List all indexed repositories
 #[allow(dead_code)] // Reserved for 'list' command implementation
 pub async fn list() -> Result<()> {
-    println!("{}", "📚 Indexed Repositories".bright_cyan().bold());
-    println!("{}", "=".repeat(60));
+    out_print!("{}", "📚 Indexed Repositories".bright_cyan().bold());
+    out_print!("{}", "=".repeat(60));
 
     // TODO: Scan all repositories in ~/.codesearch/repos.json
     // For now just check current directory
@@ -974,7 +1766,7 @@ pub async fn list() -> Result<()> {
     let current_db = current_dir.join(".codesearch.db");
 
     if current_db.exists() {
-        println!("\n{}", "Current Directory:".bright_green());
+        out_print!("\n{}", "Current Directory:".bright_green());
         print_repo_stats(&current_dir, &current_db)?;
     }
 
@@ -985,31 +1777,113 @@ pub async fn list() -> Result<()> {
 }
 
 /// Show statistics about the vector database
+/// Print how the MCP servers on the database were used: calls, errors and
+/// latencies per tool, and the slowest calls
+pub fn tool_stats(path: Option<PathBuf>) -> Result<()> {
+    use crate::mcp::usage::ToolUsage;
+
+    let (db_path, project_path) = get_db_path(path)?;
+    out_print!("{}", "🛠️  MCP Tool Usage".bright_cyan().bold());
+    out_print!("{}", "=".repeat(60));
+    out_print!("📂 Project: {}", project_path.display());
+
+    let Some(usage) = ToolUsage::load(&db_path).filter(|u| !u.tools.is_empty()) else {
+        out_print!("\nNo tool calls recorded yet. They are recorded by `codesearch mcp`.");
+        return Ok(());
+    };
+    if let Some(since) = usage.since {
+        out_print!("📅 Since: {}", since.format("%Y-%m-%d %H:%M UTC"));
+    }
+
+    out_print!(
+        "\n{:<20} {:>7} {:>7} {:>8} {:>8} {:>8} {:>8}",
+        "Tool",
+        "Calls",
+        "Errors",
+        "Avg ms",
+        "p50 ms",
+        "p95 ms",
+        "Max ms"
+    );
+    for (tool, stats) in usage.by_calls() {
+        let percentile = |p| {
+            stats
+                .percentile_ms(p)
+                .map_or("-".to_string(), |ms| ms.to_string())
+        };
+        out_print!(
+            "{:<20} {:>7} {:>7} {:>8} {:>8} {:>8} {:>8}",
+            tool,
+            stats.calls,
+            stats.errors,
+            stats.avg_ms(),
+            percentile(50),
+            percentile(95),
+            stats.max_ms
+        );
+    }
+
+    out_print!("\n{}", "Slowest calls:".bright_green());
+    for slow in &usage.slowest {
+        out_print!(
+            "   {:>6} ms  {:<18} {}  ({})",
+            slow.ms,
+            slow.tool,
+            slow.query.as_deref().unwrap_or("-"),
+            slow.at.format("%Y-%m-%d %H:%M")
+        );
+    }
+    Ok(())
+}
+
 pub async fn stats(path: Option<PathBuf>) -> Result<()> {
     let (db_path, project_path) = get_db_path(path)?;
 
     if !db_path.exists() {
-        println!("{}", "❌ No database found!".red());
-        println!("   Run {} first", "codesearch index".bright_cyan());
+        out_print!("{}", "❌ No database found!".red());
+        out_print!("   Run {} first", "codesearch index".bright_cyan());
         return Ok(());
     }
 
-    println!("{}", "📊 Database Statistics".bright_cyan().bold());
-    println!("{}", "=".repeat(60));
-    println!("💾 Database: {}", db_path.display());
-    println!("📂 Project: {}", project_path.display());
+    out_print!("{}", "📊 Database Statistics".bright_cyan().bold());
+    out_print!("{}", "=".repeat(60));
+    out_print!("💾 Database: {}", db_path.display());
+    out_print!("📂 Project: {}", project_path.display());
 
     let store = VectorStore::new(&db_path, 384)?; // We'll need to store dimensions in metadata
     let stats = store.stats()?;
 
-    println!("\n{}", "Vector Store:".bright_green());
-    println!("   Total chunks: {}", stats.total_chunks);
-    println!("   Total files: {}", stats.total_files);
-    println!(
+    out_print!("\n{}", "Vector Store:".bright_green());
+    out_print!("   Total chunks: {}", stats.total_chunks);
+    out_print!("   Total files: {}", stats.total_files);
+    out_print!(
         "   Indexed: {}",
         if stats.indexed { "✅ Yes" } else { "❌ No" }
     );
-    println!("   Dimensions: {}", stats.dimensions);
+    out_print!("   Dimensions: {}", stats.dimensions);
+
+    let metadata = IndexMetadata::load(&db_path).ok().flatten();
+    if let Some(migration) = metadata.as_ref().and_then(|m| m.migration.as_ref()) {
+        out_print!(
+            "   Switching to: {} ({} of {} chunks embedded)",
+            migration.model_short_name,
+            migration.migrated_chunks,
+            migration.total_chunks
+        );
+    }
+    if let Some(metadata) = metadata.filter(|m| !m.language_stats.is_empty()) {
+        out_print!("\n{}", "Languages:".bright_green());
+        for language in &metadata.language_stats {
+            let primary = metadata.primary_language.as_deref() == Some(&language.language);
+            out_print!(
+                "   {:<12} {:>6} files {:>8} chunks{}",
+                language.language,
+                language.files,
+                language.chunks,
+                if primary { " (primary)" } else { "" }
+            );
+        }
+    }
 
     // Calculate database size
     let mut total_size = 0u64;
@@ -1018,16 +1892,95 @@ pub async fn stats(path: Option<PathBuf>) -> Result<()> {
         total_size += entry.metadata()?.len();
     }
 
-    println!("\n{}", "Storage:".bright_green());
-    println!(
+    out_print!("\n{}", "Storage:".bright_green());
+    out_print!(
         "   Database size: {:.2} MB",
         total_size as f64 / (1024.0 * 1024.0)
     );
-    println!(
+    out_print!(
         "   Avg per chunk: {:.2} KB",
         (total_size as f64 / stats.total_chunks as f64) / 1024.0
     );
 
+    let page_stats = store.lmdb_page_stats()?;
+    let map_cap = size_history::map_cap_bytes();
+    out_print!(
+        "   LMDB: {} used of {} map ({:.0}%), {} on disk, grows up to {}",
+        format_bytes(page_stats.used_bytes),
+        format_bytes(page_stats.map_size),
+        page_stats.used_bytes as f64 * 100.0 / page_stats.map_size.max(1) as f64,
+        format_bytes(page_stats.disk_size),
+        format_bytes(map_cap)
+    );
+    let fts_stats = FtsStore::new(&db_path)?.stats()?;
+    let fts_bytes: u64 = fts_stats.segments.iter().map(|s| s.bytes).sum();
+    let deleted_docs: u32 = fts_stats.segments.iter().map(|s| s.deleted_docs).sum();
+    out_print!(
+        "   FTS: {} in {} segments ({} deleted docs awaiting merge)",
+        format_bytes(fts_bytes),
+        fts_stats.segments.len(),
+        deleted_docs
+    );
+    for segment in fts_stats.segments.iter().take(5) {
+        out_print!(
+            "      {:>10}  {} docs",
+            format_bytes(segment.bytes),
+            segment.docs
+        );
+    }
+
+    let history = match size_history::SizeHistory::record(&db_path, &store) {
+        Ok(history) => Some(history),
+        Err(e) => {
+            tracing::warn!("Failed to record index size: {}", e);
+            size_history::SizeHistory::load(&db_path)
+        }
+    };
+    match history.as_ref().and_then(|h| h.lmdb_growth_per_day()) {
+        Some((per_day, days)) => {
+            out_print!(
+                "   Growth: {}{}/day over the last {:.0} days",
+                if per_day < 0.0 { "-" } else { "+" },
+                format_bytes(per_day.abs() as u64),
+                days
+            );
+            if let Some(days_left) = history.as_ref().and_then(|h| h.days_until_cap(map_cap)) {
+                let advice = format!(
+                    "At current growth the map will hit its {} cap in {}",
+                    format_bytes(map_cap),
+                    size_history::format_days(days_left)
+                );
+                if days_left < 60.0 {
+                    out_print!(
+                        "   {}",
+                        format!(
+                            "⚠️  {}; raise CODESEARCH_LMDB_MAP_SIZE_MB or set CODESEARCH_INDEX_MAX_MB",
+                            advice
+                        )
+                        .yellow()
+                    );
+                } else {
+                    out_print!("   {}", advice);
+                }
+            }
+        }
+        None => out_print!(
+            "   Growth: {}",
+            "not enough history yet (recorded by index runs and stats)".dimmed()
+        ),
+    }
+
+    if let Some(usage) = CacheUsageLog::load(&db_path) {
+        let ms_per_chunk = usage.total.ms_per_chunk();
+        out_print!("\n{}", "Embedding Cache:".bright_green());
+        out_print!("   Last run: {}", usage.last_run.summary(ms_per_chunk));
+        out_print!(
+            "   All {} runs: {}",
+            usage.runs,
+            usage.total.summary(ms_per_chunk)
+        );
+    }
+
     Ok(())
 }
 
@@ -1036,17 +1989,17 @@ pub async fn clear(path: Option<PathBuf>, yes: bool) -> Result<()> {
     let (db_path, project_path) = get_db_path(path)?;
 
     if !db_path.exists() {
-        println!("{}", "❌ No database found!".red());
+        out_print!("{}", "❌ No database found!".red());
         return Ok(());
     }
 
-    println!("{}", "🗑️  Clear Database".bright_yellow().bold());
-    println!("{}", "=".repeat(60));
-    println!("💾 Database: {}", db_path.display());
-    println!("📂 Project: {}", project_path.display());
+    out_print!("{}", "🗑️  Clear Database".bright_yellow().bold());
+    out_print!("{}", "=".repeat(60));
+    out_print!("💾 Database: {}", db_path.display());
+    out_print!("📂 Project: {}", project_path.display());
 
     if !yes {
-        println!("\n{}", "⚠️  This will delete all indexed data!".yellow());
+        out_print!("\n{}", "⚠️  This will delete all indexed data!".yellow());
         print!("Are you sure? (y/N): ");
         use std::io::{self, Write};
         io::stdout().flush()?;
@@ -1055,15 +2008,15 @@ pub async fn clear(path: Option<PathBuf>, yes: bool) -> Result<()> {
         io::stdin().read_line(&mut input)?;
 
         if !input.trim().eq_ignore_ascii_case("y") {
-            println!("{}", "Cancelled.".dimmed());
+            out_print!("{}", "Cancelled.".dimmed());
             return Ok(());
         }
     }
 
-    println!("\n🔄 Removing database...");
+    out_print!("\n🔄 Removing database...");
     std::fs::remove_dir_all(&db_path)?;
 
-    println!("{}", "✅ Database cleared!".green());
+    out_print!("{}", "✅ Database cleared!".green());
 
     Ok(())
 }
@@ -1071,23 +2024,24 @@ pub async fn clear(path: Option<PathBuf>, yes: bool) -> Result<()> {
 /// Helper to print repository stats
 #[allow(dead_code)] // Used by list() function
 fn print_repo_stats(repo_path: &Path, db_path: &Path) -> Result<()> {
-    println!("   📂 {}", repo_path.display());
+    out_print!("   📂 {}", repo_path.display());
 
     // Try to load stats
     match VectorStore::new(db_path, 384) {
         Ok(store) => match store.stats() {
             Ok(stats) => {
-                println!(
+                out_print!(
                     "      {} chunks in {} files",
-                    stats.total_chunks, stats.total_files
+                    stats.total_chunks,
+                    stats.total_files
                 );
             }
             Err(_) => {
-                println!("      {}", "Could not load stats".dimmed());
+                out_print!("      {}", "Could not load stats".dimmed());
             }
         },
         Err(_) => {
-            println!("      {}", "Could not open database".dimmed());
+            out_print!("      {}", "Could not open database".dimmed());
         }
     }
 
@@ -1103,31 +2057,31 @@ pub async fn add_to_index(
     let project_path = path.as_deref().unwrap_or_else(|| Path::new("."));
     let canonical_path = project_path.canonicalize()?;
 
-    println!("{}", "➕ Add to Index".bright_green().bold());
-    println!("{}", "=".repeat(60));
-    println!("📂 Project: {}", canonical_path.display());
+    out_print!("{}", "➕ Add to Index".bright_green().bold());
+    out_print!("{}", "=".repeat(60));
+    out_print!("📂 Project: {}", canonical_path.display());
 
     // Check if ANY index exists (current directory OR parent directories OR global)
     let db_info = find_best_database(path.as_deref())?;
 
     if let Some(db) = db_info {
-        println!("\n{}", "⚠️  An index already exists!".yellow());
-        println!("\n{}", "Existing Index:".cyan());
-        println!("   Path: {}", db.db_path.display());
+        out_print!("\n{}", "⚠️  An index already exists!".yellow());
+        out_print!("\n{}", "Existing Index:".cyan());
+        out_print!("   Path: {}", db.db_path.display());
 
         if db.is_global {
-            println!("   Type: {}", "Global".bright_green());
+            out_print!("   Type: {}", "Global".bright_green());
         } else if !db.is_current {
-            println!("   Type: {} (parent directory)", "Local".bright_green());
+            out_print!("   Type: {} (parent directory)", "Local".bright_green());
         } else {
-            println!("   Type: {}", "Local".bright_green());
+            out_print!("   Type: {}", "Local".bright_green());
         }
 
-        println!(
+        out_print!(
             "\n{}",
             "You cannot create a separate index for a subdirectory.".yellow()
         );
-        println!(
+        out_print!(
             "{}",
             if db.is_global {
                 "The global index will be used for all projects."
@@ -1138,8 +2092,8 @@ pub async fn add_to_index(
             }
         );
 
-        println!("\n{}", "To use the existing index, simply run:".cyan());
-        println!("  codesearch index");
+        out_print!("\n{}", "To use the existing index, simply run:".cyan());
+        out_print!("  codesearch index");
 
         return Err(anyhow::anyhow!(
             "Index already exists in parent or current directory"
@@ -1170,47 +2124,53 @@ pub async fn add_to_index(
 
     // Conflict checks
     if global && has_local {
-        println!("\n{}", "❌ Error: Local index already exists!".red());
-        println!("   A local index already exists at: {}", local_db.display());
-        println!("   Remove it first with: codesearch index rm");
+        out_print!("\n{}", "❌ Error: Local index already exists!".red());
+        out_print!("   A local index already exists at: {}", local_db.display());
+        out_print!("   Remove it first with: codesearch index rm");
         return Err(anyhow::anyhow!("Local index exists"));
     }
 
     if has_local || has_global {
-        println!(
+        out_print!(
             "\n{}",
             "⚠️  Index already exists for this project!".yellow()
         );
-        println!("   Local: {}", if has_local { "✅" } else { "❌" });
-        println!("   Global: {}", if has_global { "✅" } else { "❌" });
+        out_print!("   Local: {}", if has_local { "✅" } else { "❌" });
+        out_print!("   Global: {}", if has_global { "✅" } else { "❌" });
         return Ok(());
     }
 
     // Create the index
     if global {
-        println!("\n{}", "Creating global index...".cyan());
+        out_print!("\n{}", "Creating global index...".cyan());
         index(
             Some(canonical_path.clone()),
             false,
             false,
             true,
             None,
+            None,
+            None,
+            None,
             cancel_token.clone(),
         )
         .await?;
-        println!("\n{}", "✅ Global index created!".green());
+        out_print!("\n{}", "✅ Global index created!".green());
     } else {
-        println!("\n{}", "Creating local index...".cyan());
+        out_print!("\n{}", "Creating local index...".cyan());
         index(
             Some(canonical_path.clone()),
             false,
             false,
             false,
             None,
+            None,
+            None,
+            None,
             cancel_token,
         )
         .await?;
-        println!("\n{}", "✅ Local index created!".green());
+        out_print!("\n{}", "✅ Local index created!".green());
     }
 
     Ok(())
@@ -1221,9 +2181,9 @@ pub async fn remove_from_index(path: Option<PathBuf>) -> Result<()> {
     let project_path = path.unwrap_or_else(|| PathBuf::from("."));
     let canonical_path = project_path.canonicalize()?;
 
-    println!("{}", "➖ Remove Index".bright_red().bold());
-    println!("{}", "=".repeat(60));
-    println!("📂 Project: {}", canonical_path.display());
+    out_print!("{}", "➖ Remove Index".bright_red().bold());
+    out_print!("{}", "=".repeat(60));
+    out_print!("📂 Project: {}", canonical_path.display());
 
     // Check what exists
     let local_db = canonical_path.join(".codesearch.db");
@@ -1248,33 +2208,33 @@ pub async fn remove_from_index(path: Option<PathBuf>) -> Result<()> {
     };
 
     if !has_local && !has_global {
-        println!("\n{}", "⚠️  No index found for this project.".yellow());
+        out_print!("\n{}", "⚠️  No index found for this project.".yellow());
         return Ok(());
     }
 
     // If both exist (shouldn't happen), remove local with warning
     if has_local && has_global {
-        println!(
+        out_print!(
             "\n{}",
             "⚠️  Warning: Both local and global indexes exist!".yellow()
         );
-        println!("   Removing local index...");
+        out_print!("   Removing local index...");
         fs::remove_dir_all(&local_db)?;
-        println!("   {}", "✅ Local index removed".green());
-        println!("   (Global index remains)");
+        out_print!("   {}", "✅ Local index removed".green());
+        out_print!("   (Global index remains)");
         return Ok(());
     }
 
     // Remove whichever exists
     if has_local {
-        println!("\n{}", "Removing local index...".cyan());
+        out_print!("\n{}", "Removing local index...".cyan());
         // Note: fastembed cache is inside .codesearch.db/fastembed_cache, so it's removed automatically
         fs::remove_dir_all(&local_db)?;
-        println!("{}", "✅ Local index removed!".green());
+        out_print!("{}", "✅ Local index removed!".green());
     } else if has_global {
-        println!("\n{}", "Removing global index...".cyan());
+        out_print!("\n{}", "Removing global index...".cyan());
         unregister_repository(&canonical_path)?;
-        println!("{}", "✅ Global index removed!".green());
+        out_print!("{}", "✅ Global index removed!".green());
     }
 
     Ok(())
@@ -1282,43 +2242,43 @@ pub async fn remove_from_index(path: Option<PathBuf>) -> Result<()> {
 
 /// Show index status (local or global)
 pub async fn list_index_status() -> Result<()> {
-    println!("{}", "📋 Index Status".bright_cyan().bold());
-    println!("{}", "=".repeat(60));
+    out_print!("{}", "📋 Index Status".bright_cyan().bold());
+    out_print!("{}", "=".repeat(60));
 
     // Try to find the database
     let db_info = find_best_database(Some(Path::new(".")))?;
 
     if let Some(db) = db_info {
-        println!("\n{}", "💾 Database:".cyan());
-        println!("   Path: {}", db.db_path.display());
+        out_print!("\n{}", "💾 Database:".cyan());
+        out_print!("   Path: {}", db.db_path.display());
 
         if db.is_global {
-            println!("   Type: {}", "Global".bright_green());
+            out_print!("   Type: {}", "Global".bright_green());
         } else {
-            println!("   Type: {}", "Local".bright_green());
+            out_print!("   Type: {}", "Local".bright_green());
         }
 
         // Show if this is from a parent directory
         if !db.is_current && !db.is_global {
-            println!("   {}", "(from parent directory)".dimmed());
+            out_print!("   {}", "(from parent directory)".dimmed());
         }
 
         // Get stats
         if let Ok(stats) = get_db_stats(&db.db_path).await {
-            println!("   Status: {}", "✅ Indexed".green());
-            println!("   Chunks: {}", stats.chunk_count);
-            println!("   Size: {:.2} MB", stats.size_mb);
+            out_print!("   Status: {}", "✅ Indexed".green());
+            out_print!("   Chunks: {}", stats.chunk_count);
+            out_print!("   Size: {:.2} MB", stats.size_mb);
             if stats.bloat_ratio.map(|r| r > 0.0).unwrap_or(false) {
-                println!("   Bloat Ratio: {:.2}%", stats.bloat_ratio.unwrap_or(0.0));
+                out_print!("   Bloat Ratio: {:.2}%", stats.bloat_ratio.unwrap_or(0.0));
             }
         } else {
-            println!("   Status: {}", "⚠️  Could not read database".yellow());
+            out_print!("   Status: {}", "⚠️  Could not read database".yellow());
         }
     } else {
-        println!("\n{}", "No index found for this project.".dimmed());
-        println!("\nCreate an index with:");
-        println!("  codesearch index add          # Create local index");
-        println!("  codesearch index add -g       # Create global index");
+        out_print!("\n{}", "No index found for this project.".dimmed());
+        out_print!("\nCreate an index with:");
+        out_print!("  codesearch index add          # Create local index");
+        out_print!("  codesearch index add -g       # Create global index");
     }
 
     Ok(())
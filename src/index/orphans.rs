@@ -0,0 +1,146 @@
+//! Sweep of orphaned chunks
+//!
+//! Older versions could leave chunks behind for files that were deleted or
+//! renamed, or whose file metadata was lost. A refresh only knows the files in
+//! `FileMetaStore`, so such chunks show up as ghost search results forever.
+//! The sweep scans every chunk in the vector store and removes those whose file
+//! is not tracked or no longer on disk from both stores.
+
+use anyhow::Result;
+use std::path::Path;
+
+use crate::cache::FileMetaStore;
+use crate::fts::FtsStore;
+use crate::vectordb::VectorStore;
+
+/// What a sweep removed
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OrphanSweep {
+    pub files: usize,
+    pub chunks: usize,
+}
+
+/// Chunk IDs by path of the stored files that are untracked or no longer exist
+pub fn find_orphans(
+    store: &VectorStore,
+    file_meta: &FileMetaStore,
+) -> Result<Vec<(String, Vec<u32>)>> {
+    let mut orphans: Vec<(String, Vec<u32>)> = store
+        .get_chunks_by_file()?
+        .into_iter()
+        .filter(|(path, _)| {
+            let path = Path::new(path);
+            !file_meta.is_tracked(path) || !path.exists()
+        })
+        .collect();
+    orphans.sort();
+    Ok(orphans)
+}
+
+/// Remove orphaned chunks from both stores
+///
+/// Rebuilds the vector index when anything was removed. The caller must hold
+/// the writer lock of the database.
+pub fn sweep_orphans(
+    store: &mut VectorStore,
+    fts_store: &mut FtsStore,
+    file_meta: &FileMetaStore,
+) -> Result<OrphanSweep> {
+    let mut sweep = OrphanSweep::default();
+    for (path, chunk_ids) in find_orphans(store, file_meta)? {
+        tracing::debug!(
+            "🧹 Removing {} orphaned chunks of {}",
+            chunk_ids.len(),
+            path
+        );
+        store.delete_chunks(&chunk_ids)?;
+        for &chunk_id in &chunk_ids {
+            fts_store.delete_chunk(chunk_id)?;
+        }
+        fts_store.delete_by_path(&path)?;
+        sweep.files += 1;
+        sweep.chunks += chunk_ids.len();
+    }
+
+    if sweep.chunks > 0 {
+        fts_store.commit()?;
+        store.build_index()?;
+        tracing::info!(
+            "🧹 Removed {} orphaned chunks of {} files",
+            sweep.chunks,
+            sweep.files
+        );
+    }
+    Ok(sweep)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::normalize_path;
+    use crate::chunker::{Chunk, ChunkKind};
+    use crate::embed::EmbeddedChunk;
+
+    #[test]
+    fn test_sweep_removes_untracked_and_missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join(".codesearch.db");
+        let mut store = VectorStore::new(&db_path, 4).unwrap();
+        let mut fts_store = FtsStore::new_with_writer(&db_path).unwrap();
+        let mut file_meta = FileMetaStore::new("test-model".to_string(), 4);
+
+        let mut insert = |name: &str| {
+            let path = dir.path().join(name);
+            std::fs::write(&path, "fn f() {}").unwrap();
+            let chunk = Chunk::new(
+                "fn f() {}".to_string(),
+                0,
+                1,
+                ChunkKind::Function,
+                normalize_path(&path),
+            );
+            let ids = store
+                .insert_chunks_with_ids(vec![EmbeddedChunk::new(chunk, vec![1.0, 0.0, 0.5, 0.2])])
+                .unwrap();
+            fts_store
+                .add_chunk(
+                    ids[0],
+                    "fn f() {}",
+                    &normalize_path(&path),
+                    None,
+                    "function",
+                )
+                .unwrap();
+            (path, ids)
+        };
+        let (kept, kept_ids) = insert("kept.rs");
+        let (deleted, deleted_ids) = insert("deleted.rs");
+        let (untracked, _) = insert("untracked.rs");
+        fts_store.commit().unwrap();
+        file_meta.update_file(&kept, kept_ids).unwrap();
+        file_meta.update_file(&deleted, deleted_ids).unwrap();
+        std::fs::remove_file(&deleted).unwrap();
+
+        let orphans: Vec<String> = find_orphans(&store, &file_meta)
+            .unwrap()
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(
+            orphans,
+            [normalize_path(&deleted), normalize_path(&untracked)]
+        );
+
+        let sweep = sweep_orphans(&mut store, &mut fts_store, &file_meta).unwrap();
+        assert_eq!(
+            sweep,
+            OrphanSweep {
+                files: 2,
+                chunks: 2
+            }
+        );
+        assert_eq!(store.stats().unwrap().total_chunks, 1);
+        assert_eq!(fts_store.stats().unwrap().num_documents, 1);
+        assert!(find_orphans(&store, &file_meta).unwrap().is_empty());
+    }
+}
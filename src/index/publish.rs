@@ -0,0 +1,484 @@
+//! `codesearch publish` / `codesearch pull`
+//!
+//! A team shares one index through a remote (see `remote`), so each member
+//! embeds only the files they changed themselves. The remote is
+//! content-addressed:
+//!
+//! - `packs/<embedding version>/<key>.pack` holds the chunks and code vectors of
+//!   one file, zstd-compressed bincode; the key hashes the file's path relative
+//!   to the project and its content hash, so an unchanged file maps to the same
+//!   pack on every publish and is never uploaded twice
+//! - `manifest.json` lists the published files and their content hashes; it is
+//!   uploaded last, so a pull never sees a manifest whose packs are missing
+//!
+//! Pulling downloads the packs of files whose local content matches the
+//! manifest into the persistent embedding cache, then runs a regular
+//! incremental index: published chunks are cache hits, everything else
+//! (local changes, prose vectors) is embedded locally.
+
+use crate::out_print;
+use anyhow::{anyhow, bail, Context, Result};
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+
+use super::migration::embedding_version;
+use super::remote::Remote;
+use super::{get_db_path, get_db_path_smart};
+use crate::cache::{normalize_path, FileMetaStore, IndexMetadata};
+use crate::chunker::Chunk;
+use crate::embed::{embedding_text, ModelType, PersistentEmbeddingCache};
+use crate::vectordb::{ChunkMetadata, VectorStore};
+
+/// Remote key of the manifest
+const MANIFEST_KEY: &str = "manifest.json";
+/// Manifest format version, bumped on incompatible layout changes
+const PUBLISH_FORMAT_VERSION: u32 = 1;
+/// zstd level of uploaded packs
+const PACK_COMPRESSION_LEVEL: i32 = 3;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Manifest {
+    format_version: u32,
+    codesearch_version: String,
+    published_at: String,
+    model_short_name: String,
+    dimensions: usize,
+    /// Model and chunk layout of the vectors (see `embedding_version`)
+    embedding_version: String,
+    /// Project root of the publisher
+    root: String,
+    /// Content hash of every published file, by path relative to the root
+    files: BTreeMap<String, String>,
+}
+
+/// Chunks and code vectors of one file
+#[derive(Serialize, Deserialize)]
+struct Pack {
+    /// Project root of the publisher, the prefix of the stored paths
+    root: String,
+    chunks: Vec<(ChunkMetadata, Vec<f32>)>,
+}
+
+/// Outcome of a publish, in files
+#[derive(Debug, Default, PartialEq)]
+struct PublishSummary {
+    uploaded: usize,
+    unchanged: usize,
+    /// Tracked files whose chunks are missing from the store
+    skipped: usize,
+}
+
+/// Outcome of a pull, in files
+#[derive(Debug, Default, PartialEq)]
+struct PullSummary {
+    downloaded: usize,
+    /// Already indexed locally with the published content
+    current: usize,
+    /// Missing or different locally, left to the local index run
+    changed: usize,
+}
+
+/// Publish the index of `path` to `remote`
+pub async fn publish(path: Option<PathBuf>, remote: &str) -> Result<()> {
+    let remote = Remote::parse(remote)?;
+    let (db_path, project_path) = get_db_path(path)?;
+    if !db_path.exists() {
+        out_print!(
+            "{}",
+            "❌ No database found! Run 'codesearch index' first.".red()
+        );
+        return Ok(());
+    }
+
+    out_print!("{}", "📤 Publish Index".bright_cyan().bold());
+    out_print!("{}", "=".repeat(60));
+    out_print!("💾 Database: {}", db_path.display());
+    out_print!("🌐 Remote: {}", remote);
+
+    let summary = tokio::task::spawn_blocking(move || {
+        publish_to(&remote, &db_path, &normalize_path(&project_path))
+    })
+    .await??;
+
+    if summary.skipped > 0 {
+        out_print!(
+            "{}",
+            format!(
+                "⚠️  {} files skipped (chunks missing, run 'codesearch index' to repair)",
+                summary.skipped
+            )
+            .yellow()
+        );
+    }
+    out_print!(
+        "{}",
+        format!(
+            "✅ Published {} files ({} uploaded, {} unchanged)",
+            summary.uploaded + summary.unchanged,
+            summary.uploaded,
+            summary.unchanged
+        )
+        .green()
+    );
+    Ok(())
+}
+
+/// Pull the index published at `remote` and index `path` with it
+pub async fn pull(
+    path: Option<PathBuf>,
+    remote: &str,
+    cancel_token: CancellationToken,
+) -> Result<()> {
+    let remote = Remote::parse(remote)?;
+    let (db_path, project_path) = get_db_path_smart(path, false, false)?;
+
+    out_print!("{}", "📥 Pull Index".bright_cyan().bold());
+    out_print!("{}", "=".repeat(60));
+    out_print!("🌐 Remote: {}", remote);
+    out_print!("📂 Project: {}", project_path.display());
+
+    let local = IndexMetadata::load(&db_path)?;
+    let local_meta = FileMetaStore::read(&db_path);
+    let root = normalize_path(&project_path);
+    let (manifest, summary) = tokio::task::spawn_blocking(move || -> Result<_> {
+        let Some(manifest) = read_manifest(&remote)? else {
+            bail!("Nothing published at {}", remote);
+        };
+        let model = ModelType::parse(&manifest.model_short_name).ok_or_else(|| {
+            anyhow!(
+                "Published with unknown model '{}' (newer codesearch?)",
+                manifest.model_short_name
+            )
+        })?;
+        let cache = PersistentEmbeddingCache::open(model.short_name())?;
+        let summary = pull_from(&remote, &manifest, &root, local_meta.as_ref(), |entries| {
+            let entries: Vec<(&str, &[f32])> = entries
+                .iter()
+                .map(|(key, vector)| (key.as_str(), vector.as_slice()))
+                .collect();
+            cache.put_batch(&entries)
+        })?;
+        Ok((manifest, summary))
+    })
+    .await??;
+
+    out_print!(
+        "   Published {} by codesearch {} ({} files)",
+        manifest.published_at,
+        manifest.codesearch_version,
+        manifest.files.len()
+    );
+    out_print!(
+        "{}",
+        format!(
+            "✅ Downloaded {} files ({} already indexed, {} changed locally)",
+            summary.downloaded, summary.current, summary.changed
+        )
+        .green()
+    );
+
+    // Reuse the downloaded vectors only with the model they were made with
+    let model = match local {
+        Some(local) if local.model_short_name != manifest.model_short_name => {
+            out_print!(
+                "{}",
+                format!(
+                    "⚠️  The local index uses {}, the published one {}.\n   \
+                     Run 'codesearch index --model {}' to switch and reuse the published vectors.",
+                    local.model_short_name, manifest.model_short_name, manifest.model_short_name
+                )
+                .yellow()
+            );
+            return Ok(());
+        }
+        Some(_) => None,
+        None => ModelType::parse(&manifest.model_short_name),
+    };
+    super::index(
+        Some(project_path),
+        false,
+        false,
+        false,
+        model,
+        None,
+        None,
+        None,
+        cancel_token,
+    )
+    .await
+}
+
+/// Upload the packs of the index at `db_path` that `remote` lacks, then the manifest
+fn publish_to(remote: &Remote, db_path: &Path, root: &str) -> Result<PublishSummary> {
+    let metadata = IndexMetadata::require(db_path)?;
+    if let Some(migration) = &metadata.migration {
+        bail!(
+            "A switch to {} is in progress; finish it with 'codesearch index' before publishing",
+            migration.model_short_name
+        );
+    }
+    let file_meta = FileMetaStore::read(db_path)
+        .ok_or_else(|| anyhow!("No file metadata in {}", db_path.display()))?;
+    let store = VectorStore::open_readonly(db_path, metadata.dimensions)?;
+    let version = embedding_version(metadata.model_type().0);
+
+    // Packs of the previous manifest are known to exist
+    let previous: Vec<String> = match read_manifest(remote)? {
+        Some(previous) if previous.embedding_version == version => previous
+            .files
+            .iter()
+            .map(|(path, hash)| pack_key(&version, path, hash))
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    let prefix = format!("{}/", root.trim_end_matches('/'));
+    let mut entries: Vec<_> = file_meta
+        .entries()
+        .filter_map(|(path, meta)| Some((path.strip_prefix(&prefix)?, meta)))
+        .collect();
+    entries.sort_by(|a, b| a.0.cmp(b.0));
+
+    let mut summary = PublishSummary::default();
+    let mut files = BTreeMap::new();
+    for (relative, meta) in entries {
+        let key = pack_key(&version, relative, &meta.hash);
+        if previous.contains(&key) || remote.exists(&key)? {
+            summary.unchanged += 1;
+            files.insert(relative.to_string(), meta.hash.clone());
+            continue;
+        }
+
+        let mut chunks = Vec::with_capacity(meta.chunk_ids.len());
+        for &id in &meta.chunk_ids {
+            match (store.get_chunk(id)?, store.chunk_vector(id)?) {
+                (Some(chunk), Some(vector)) => chunks.push((chunk, vector)),
+                _ => break,
+            }
+        }
+        if chunks.len() != meta.chunk_ids.len() {
+            summary.skipped += 1;
+            continue;
+        }
+
+        let pack = Pack {
+            root: root.to_string(),
+            chunks,
+        };
+        let bytes = zstd::encode_all(&bincode::serialize(&pack)?[..], PACK_COMPRESSION_LEVEL)?;
+        remote
+            .put(&key, &bytes)
+            .with_context(|| format!("Cannot upload {}", relative))?;
+        summary.uploaded += 1;
+        files.insert(relative.to_string(), meta.hash.clone());
+    }
+
+    let manifest = Manifest {
+        format_version: PUBLISH_FORMAT_VERSION,
+        codesearch_version: env!("CARGO_PKG_VERSION").to_string(),
+        published_at: chrono::Utc::now().to_rfc3339(),
+        model_short_name: metadata.model_short_name.clone(),
+        dimensions: metadata.dimensions,
+        embedding_version: version,
+        root: root.to_string(),
+        files,
+    };
+    remote.put(MANIFEST_KEY, &serde_json::to_vec_pretty(&manifest)?)?;
+    Ok(summary)
+}
+
+/// Download the packs of the files under `root` that match `manifest` and
+/// aren't indexed yet, handing `(cache key, vector)` entries to `store` pack by pack
+fn pull_from(
+    remote: &Remote,
+    manifest: &Manifest,
+    root: &str,
+    local_meta: Option<&FileMetaStore>,
+    mut store: impl FnMut(&[(String, Vec<f32>)]) -> Result<()>,
+) -> Result<PullSummary> {
+    let root = root.trim_end_matches('/');
+    let indexed: HashMap<&String, &String> = local_meta
+        .map(|meta| {
+            meta.entries()
+                .map(|(path, meta)| (path, &meta.hash))
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut summary = PullSummary::default();
+    for (relative, hash) in &manifest.files {
+        let path = format!("{}/{}", root, relative);
+        if indexed.get(&path) == Some(&hash) {
+            summary.current += 1;
+            continue;
+        }
+        if FileMetaStore::compute_hash(Path::new(&path)).ok().as_ref() != Some(hash) {
+            summary.changed += 1;
+            continue;
+        }
+
+        let key = pack_key(&manifest.embedding_version, relative, hash);
+        let bytes = remote
+            .get(&key)?
+            .ok_or_else(|| anyhow!("Published pack of {} is missing", relative))?;
+        let pack: Pack = bincode::deserialize(&zstd::decode_all(&bytes[..])?)
+            .with_context(|| format!("Published pack of {} is invalid", relative))?;
+
+        let entries: Vec<(String, Vec<f32>)> = pack
+            .chunks
+            .into_iter()
+            .map(|(chunk, vector)| {
+                let chunk = rebase(chunk.to_chunk(), &pack.root, root);
+                (Chunk::compute_hash(&embedding_text(&chunk)), vector)
+            })
+            .collect();
+        store(&entries)?;
+        summary.downloaded += 1;
+    }
+    Ok(summary)
+}
+
+/// A published chunk as it is chunked under the local project root
+///
+/// Paths and the file breadcrumb are absolute, so they differ between checkouts.
+fn rebase(mut chunk: Chunk, from: &str, to: &str) -> Chunk {
+    let from = from.trim_end_matches('/');
+    if let Some(rest) = chunk.path.strip_prefix(from) {
+        chunk.path = format!("{}{}", to, rest);
+    }
+    let file_crumb = format!("File: {}", from);
+    for crumb in &mut chunk.context {
+        if let Some(rest) = crumb.strip_prefix(&file_crumb) {
+            *crumb = format!("File: {}{}", to, rest);
+        }
+    }
+    chunk
+}
+
+fn read_manifest(remote: &Remote) -> Result<Option<Manifest>> {
+    let Some(bytes) = remote.get(MANIFEST_KEY)? else {
+        return Ok(None);
+    };
+    let manifest: Manifest = serde_json::from_slice(&bytes)
+        .with_context(|| format!("Invalid manifest at {}", remote))?;
+    if manifest.format_version > PUBLISH_FORMAT_VERSION {
+        bail!(
+            "The index at {} was published by a newer codesearch ({}); upgrade to pull it",
+            remote,
+            manifest.codesearch_version
+        );
+    }
+    Ok(Some(manifest))
+}
+
+/// Remote key of the pack of a file with content `hash`
+fn pack_key(version: &str, relative: &str, hash: &str) -> String {
+    let digest = Sha256::digest(format!("{}\n{}", relative, hash).as_bytes());
+    let name: String = digest.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("packs/{}/{}.pack", version, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunker::ChunkKind;
+    use crate::embed::EmbeddedChunk;
+
+    /// Chunk of `path` as the chunker makes it, with the absolute file breadcrumb
+    fn chunk_of(path: &Path, content: &str) -> Chunk {
+        let mut chunk = Chunk::new(
+            content.to_string(),
+            0,
+            1,
+            ChunkKind::Function,
+            normalize_path(path),
+        );
+        chunk.context = vec![
+            format!("File: {}", normalize_path(path)),
+            "Function: f".into(),
+        ];
+        chunk.signature = Some("fn f()".to_string());
+        chunk
+    }
+
+    #[test]
+    fn test_publish_and_pull() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let remote = Remote::Dir(dir.path().join("remote"));
+        let model = ModelType::all()[0];
+        let vector = |seed: f32| vec![seed; model.dimensions()];
+
+        // Publisher checkout with an index of two files
+        let publisher = dir.path().join("alice");
+        let db_path = publisher.join(".codesearch.db");
+        std::fs::create_dir_all(&publisher)?;
+        // Written elsewhere: the process can't reopen an environment read-only
+        let writer_db = dir.path().join("writer.db");
+        let mut store = VectorStore::new(&writer_db, model.dimensions())?;
+        let mut file_meta = FileMetaStore::new(model.name().to_string(), model.dimensions());
+        for (name, seed) in [("a.rs", 0.1), ("b.rs", 0.2)] {
+            let path = publisher.join(name);
+            std::fs::write(&path, format!("fn f() {{ {} }}", name))?;
+            let chunk = chunk_of(&path, &format!("fn f() {{ {} }}", name));
+            let ids =
+                store.insert_chunks_with_ids(vec![EmbeddedChunk::new(chunk, vector(seed))])?;
+            file_meta.update_file(&path, ids)?;
+        }
+        drop(store);
+        std::fs::create_dir_all(&db_path)?;
+        VectorStore::copy_environment(&writer_db, &db_path.join("data.mdb"))?;
+        file_meta.save(&db_path)?;
+        IndexMetadata::new(model.short_name(), model.name(), model.dimensions()).save(&db_path)?;
+
+        let root = normalize_path(&publisher);
+        let summary = publish_to(&remote, &db_path, &root)?;
+        assert_eq!(summary.uploaded, 2);
+        // Nothing changed, nothing uploaded
+        let summary = publish_to(&remote, &db_path, &root)?;
+        assert_eq!((summary.uploaded, summary.unchanged), (0, 2));
+
+        // A teammate's checkout in another place, with b.rs edited
+        let puller = dir.path().join("bob");
+        std::fs::create_dir_all(&puller)?;
+        std::fs::write(puller.join("a.rs"), "fn f() { a.rs }")?;
+        std::fs::write(puller.join("b.rs"), "fn f() { edited }")?;
+
+        let manifest = read_manifest(&remote)?.expect("manifest");
+        assert_eq!(manifest.files.len(), 2);
+        let mut cached = Vec::new();
+        let summary = pull_from(
+            &remote,
+            &manifest,
+            &normalize_path(&puller),
+            None,
+            |entries| {
+                cached.extend_from_slice(entries);
+                Ok(())
+            },
+        )?;
+        assert_eq!((summary.downloaded, summary.changed), (1, 1));
+
+        // The vector is cached under the key the local indexer will look up
+        let local = chunk_of(&puller.join("a.rs"), "fn f() { a.rs }");
+        assert_eq!(
+            cached,
+            [(Chunk::compute_hash(&embedding_text(&local)), vector(0.1))]
+        );
+
+        // Files already indexed locally with the same content are skipped
+        let mut local_meta = FileMetaStore::new(model.name().to_string(), model.dimensions());
+        local_meta.update_file(&puller.join("a.rs"), vec![0])?;
+        let summary = pull_from(
+            &remote,
+            &manifest,
+            &normalize_path(&puller),
+            Some(&local_meta),
+            |_| Ok(()),
+        )?;
+        assert_eq!((summary.downloaded, summary.current), (0, 1));
+        Ok(())
+    }
+}
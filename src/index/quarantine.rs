@@ -0,0 +1,166 @@
+//! Quarantine of corrupted databases
+//!
+//! When the vector store or full-text index can't be opened because its files
+//! are damaged, the database directory is renamed to
+//! `.codesearch.db.corrupt-<timestamp>` and an empty one takes its place, so the
+//! next refresh rebuilds the index instead of every startup failing the same
+//! way. The valid `metadata.json` (embedding model, language scope) carries
+//! over so the rebuild uses the same settings. Set
+//! `CODESEARCH_QUARANTINE_CORRUPT=0` to fail instead and keep the files in place.
+
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use super::is_database_locked;
+use crate::cache::IndexMetadata;
+use crate::embed::ModelType;
+use crate::fts::FtsStore;
+use crate::vectordb::VectorStore;
+
+/// Error messages of LMDB and tantivy that mean the files on disk are damaged
+const CORRUPTION_MARKERS: &[&str] = &[
+    "mdb_corrupted",
+    "mdb_invalid",
+    "mdb_page_notfound",
+    "mdb_version_mismatch",
+    "data corrupted",
+    "datacorruption",
+];
+
+/// Whether an error opening a store means its files are damaged
+///
+/// Lock contention, missing files and permission errors are not corruption.
+pub fn is_corruption_error(err: &anyhow::Error) -> bool {
+    let msg = format!("{:#}", err).to_lowercase();
+    CORRUPTION_MARKERS.iter().any(|m| msg.contains(m))
+}
+
+/// Whether corrupted databases are quarantined (`CODESEARCH_QUARANTINE_CORRUPT`, default on)
+pub fn quarantine_enabled() -> bool {
+    std::env::var("CODESEARCH_QUARANTINE_CORRUPT")
+        .map(|v| {
+            !matches!(
+                v.trim().to_lowercase().as_str(),
+                "0" | "false" | "no" | "off"
+            )
+        })
+        .unwrap_or(true)
+}
+
+/// Whether `err` from opening a store should quarantine the database
+pub fn should_quarantine(err: &anyhow::Error) -> bool {
+    quarantine_enabled() && is_corruption_error(err)
+}
+
+/// Move a corrupted database aside and leave an empty one with its metadata.json
+///
+/// Returns where the corrupted database was moved. The caller must not hold
+/// the writer lock (the directory can't be renamed with the lock file open on
+/// Windows).
+pub fn quarantine_database(db_path: &Path, reason: &anyhow::Error) -> Result<PathBuf> {
+    let file_name = db_path
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| crate::constants::DB_DIR_NAME.to_string());
+    let quarantined = db_path.with_file_name(format!(
+        "{}.corrupt-{}",
+        file_name,
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    std::fs::rename(db_path, &quarantined)?;
+
+    std::fs::create_dir_all(db_path)?;
+    let metadata = std::fs::read_to_string(quarantined.join("metadata.json")).ok();
+    if let Some(metadata) = metadata
+        .filter(|m| serde_json::from_str::<serde_json::Value>(m).is_ok_and(|j| j.is_object()))
+    {
+        std::fs::write(db_path.join("metadata.json"), metadata)?;
+    }
+
+    tracing::warn!(
+        "⚠️  Database at {} is corrupted ({:#}); moved to {} and rebuilding",
+        db_path.display(),
+        reason,
+        quarantined.display()
+    );
+    Ok(quarantined)
+}
+
+/// Open both stores of an existing database and quarantine it if they are corrupted
+///
+/// Returns the quarantine location, or `None` when the database opened fine,
+/// doesn't exist, or is in use by another process (which would fail on its own
+/// if the files were damaged). Corruption with quarantine disabled is an error.
+pub fn recover_if_corrupt(db_path: &Path) -> Result<Option<PathBuf>> {
+    if !db_path.join("data.mdb").exists() || is_database_locked(db_path) {
+        return Ok(None);
+    }
+    // The store is opened with the dimensions it was built with; a damaged
+    // metadata.json doesn't stop the check
+    let dimensions = IndexMetadata::load(db_path)
+        .ok()
+        .flatten()
+        .map_or(ModelType::default().dimensions(), |m| m.dimensions);
+    let opened = VectorStore::new(db_path, dimensions)
+        .map(drop)
+        .and_then(|()| {
+            // Opening a missing full-text index would create an empty one
+            if db_path.join("fts").join("meta.json").exists() {
+                FtsStore::new(db_path).map(drop)
+            } else {
+                Ok(())
+            }
+        });
+    match opened {
+        Ok(()) => Ok(None),
+        Err(e) if !is_corruption_error(&e) => Ok(None),
+        Err(e) if !quarantine_enabled() => Err(e.context(format!(
+            "Database at {} is corrupted (CODESEARCH_QUARANTINE_CORRUPT is off). \
+             Re-index with 'codesearch index --force'",
+            db_path.display()
+        ))),
+        Err(e) => quarantine_database(db_path, &e).map(Some),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_corrupted_database_is_quarantined() {
+        assert!(is_corruption_error(&anyhow::anyhow!(
+            "MDB_CORRUPTED: Located page was wrong type"
+        )));
+        assert!(is_corruption_error(
+            &anyhow::anyhow!("Data corrupted: meta.json").context("Failed to open FTS index")
+        ));
+        assert!(!is_corruption_error(&anyhow::anyhow!(
+            "Failed to acquire Lockfile: LockBusy"
+        )));
+
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join(".codesearch.db");
+        std::fs::create_dir_all(&db_path).unwrap();
+        std::fs::write(
+            db_path.join("metadata.json"),
+            r#"{"schema_version": 1, "model_short_name": "tiny", "model_name": "tiny", "dimensions": 4}"#,
+        )
+        .unwrap();
+        std::fs::write(db_path.join("data.mdb"), b"not an lmdb file").unwrap();
+
+        let quarantined = recover_if_corrupt(&db_path).unwrap().unwrap();
+        assert!(quarantined
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with(".codesearch.db.corrupt-"));
+        assert!(quarantined.join("data.mdb").exists());
+        assert!(!db_path.join("data.mdb").exists());
+        assert!(db_path.join("metadata.json").exists());
+
+        // A healthy database is left alone
+        drop(VectorStore::new(&db_path, 4).unwrap());
+        assert_eq!(recover_if_corrupt(&db_path).unwrap(), None);
+    }
+}
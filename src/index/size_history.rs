@@ -0,0 +1,187 @@
+//! Index size over time, for `codesearch stats`
+//!
+//! Every index run, startup refresh and `stats` call records how much of the
+//! LMDB map and the full-text index is in use. The growth rate between the
+//! samples tells how long until the map reaches the largest size it may grow
+//! to, so the cap can be raised before writes start failing.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::constants::{DEFAULT_LMDB_MAP_SIZE_MB, MAX_LMDB_MAP_SIZE_MB, SIZE_HISTORY_FILE};
+use crate::vectordb::VectorStore;
+
+/// Samples kept; older ones are dropped
+const MAX_SAMPLES: usize = 200;
+
+/// A sample this soon after the previous one replaces it
+const MIN_SAMPLE_INTERVAL_SECS: i64 = 3600;
+
+/// Growth is measured over samples from this many days back
+const GROWTH_WINDOW_DAYS: i64 = 30;
+
+/// Samples must span at least this long for a growth rate
+const MIN_GROWTH_SPAN_SECS: i64 = 24 * 3600;
+
+/// Sizes of an index at one point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizeSample {
+    pub at: DateTime<Utc>,
+    /// LMDB pages in use
+    pub lmdb_bytes: u64,
+    /// Full-text index files
+    pub fts_bytes: u64,
+}
+
+impl SizeSample {
+    /// Current sizes of the index in `db_path`
+    pub fn measure(db_path: &Path, store: &VectorStore) -> Result<Self> {
+        Ok(Self {
+            at: Utc::now(),
+            lmdb_bytes: store.lmdb_page_stats()?.used_bytes,
+            fts_bytes: dir_size(&db_path.join("fts")),
+        })
+    }
+}
+
+/// Size samples of an index, kept in [`SIZE_HISTORY_FILE`]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SizeHistory {
+    /// Oldest first
+    pub samples: Vec<SizeSample>,
+}
+
+impl SizeHistory {
+    pub fn load(db_path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(db_path.join(SIZE_HISTORY_FILE)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Measure the index in `db_path` and add the sample to its history
+    pub fn record(db_path: &Path, store: &VectorStore) -> Result<Self> {
+        let mut history = Self::load(db_path).unwrap_or_default();
+        history.push(SizeSample::measure(db_path, store)?);
+        std::fs::write(
+            db_path.join(SIZE_HISTORY_FILE),
+            serde_json::to_string_pretty(&history)?,
+        )?;
+        Ok(history)
+    }
+
+    fn push(&mut self, sample: SizeSample) {
+        if let Some(last) = self.samples.last_mut() {
+            if (sample.at - last.at).num_seconds() < MIN_SAMPLE_INTERVAL_SECS {
+                *last = sample;
+                return;
+            }
+        }
+        self.samples.push(sample);
+        if self.samples.len() > MAX_SAMPLES {
+            self.samples.drain(..self.samples.len() - MAX_SAMPLES);
+        }
+    }
+
+    /// LMDB bytes added per day over the last 30 days, with the days measured;
+    /// `None` until the samples span a day
+    pub fn lmdb_growth_per_day(&self) -> Option<(f64, f64)> {
+        let last = self.samples.last()?;
+        let first = self
+            .samples
+            .iter()
+            .find(|s| (last.at - s.at).num_days() <= GROWTH_WINDOW_DAYS)?;
+        let span = (last.at - first.at).num_seconds();
+        if span < MIN_GROWTH_SPAN_SECS {
+            return None;
+        }
+        let days = span as f64 / 86400.0;
+        Some((
+            (last.lmdb_bytes as f64 - first.lmdb_bytes as f64) / days,
+            days,
+        ))
+    }
+
+    /// Days until the LMDB map reaches `cap` bytes at the current growth;
+    /// `None` when it isn't growing
+    pub fn days_until_cap(&self, cap: u64) -> Option<f64> {
+        let (per_day, _) = self.lmdb_growth_per_day()?;
+        let used = self.samples.last()?.lmdb_bytes;
+        (per_day > 0.0).then(|| cap.saturating_sub(used) as f64 / per_day)
+    }
+}
+
+/// Largest size the LMDB map may grow to: the configured size when set above
+/// the automatic resize limit
+pub fn map_cap_bytes() -> u64 {
+    let configured = std::env::var("CODESEARCH_LMDB_MAP_SIZE_MB")
+        .ok()
+        .and_then(|s| s.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_LMDB_MAP_SIZE_MB);
+    configured.max(MAX_LMDB_MAP_SIZE_MB) as u64 * 1024 * 1024
+}
+
+/// "~5 days", "~3 weeks", "~4 months"
+pub fn format_days(days: f64) -> String {
+    if days < 14.0 {
+        format!("~{} days", days.ceil().max(1.0))
+    } else if days < 70.0 {
+        format!("~{} weeks", (days / 7.0).round())
+    } else {
+        format!("~{} months", (days / 30.0).round())
+    }
+}
+
+fn dir_size(dir: &Path) -> u64 {
+    std::fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_growth_and_cap_estimate() {
+        let start = Utc::now() - Duration::days(60);
+        let sample = |days: i64, mb: u64| SizeSample {
+            at: start + Duration::days(days),
+            lmdb_bytes: mb * 1024 * 1024,
+            fts_bytes: 0,
+        };
+        let mut history = SizeHistory::default();
+        history.push(sample(0, 10));
+        assert_eq!(history.lmdb_growth_per_day(), None);
+
+        // Outside the 30-day window
+        history.push(sample(15, 100));
+        history.push(sample(40, 200));
+        // Within the hour: replaces the previous sample
+        history.push(sample(40, 210));
+        history.push(sample(50, 310));
+        assert_eq!(history.samples.len(), 4);
+
+        let (per_day, days) = history.lmdb_growth_per_day().unwrap();
+        assert_eq!(days, 10.0);
+        assert_eq!(per_day, 10.0 * 1024.0 * 1024.0);
+        assert_eq!(
+            history.days_until_cap(1024 * 1024 * 1024),
+            Some((1024.0 - 310.0) / 10.0)
+        );
+
+        // Shrinking: no estimate
+        history.push(sample(55, 100));
+        assert_eq!(history.days_until_cap(1024 * 1024 * 1024), None);
+
+        assert_eq!(format_days(3.2), "~4 days");
+        assert_eq!(format_days(15.0), "~2 weeks");
+        assert_eq!(format_days(200.0), "~7 months");
+    }
+}
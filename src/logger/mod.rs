@@ -1,25 +1,38 @@
 //! Logging module for codesearch
 //!
 //! Provides centralized logging configuration with:
-//! - Daily log file rotation (via tracing-appender)
-//! - Periodic cleanup of old log files (by age and count)
+//! - Daily and size-based log file rotation, with gzipped archives
+//! - Periodic cleanup of old log files (by age, count and total size)
 //! - Per-database log storage in .codesearch.db/logs/
 //! - Configurable via environment variables
 //!
-//! Daily rotation creates files named `codesearch.log.YYYY-MM-DD`.
-//! Cleanup removes files older than `retention_days` and enforces `max_files`.
+//! Daily rotation creates files named `codesearch.log.YYYY-MM-DD`; a file that
+//! reaches `max_file_bytes` is rotated to `codesearch.log.YYYY-MM-DD.N.gz`.
+//! Cleanup removes files older than `retention_days` and enforces `max_files`
+//! and `max_total_bytes`.
+
+mod rotation;
 
 use anyhow::Result;
 use chrono::{NaiveDate, Utc};
 use std::fs;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
+use std::sync::Mutex;
 use tokio_util::sync::CancellationToken;
-use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::fmt::MakeWriter;
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 
 use crate::constants::{
-    DEFAULT_LOG_MAX_FILES, DEFAULT_LOG_RETENTION_DAYS, LOG_DIR_NAME, LOG_FILE_NAME,
+    DEFAULT_LOG_MAX_FILES, DEFAULT_LOG_MAX_FILE_MB, DEFAULT_LOG_MAX_TOTAL_MB,
+    DEFAULT_LOG_RETENTION_DAYS, LOG_DIR_NAME, LOG_FILE_NAME,
 };
+use crate::err_print;
+use crate::output::Verbosity;
+pub use rotation::LogAppender;
+
+/// Held while log files are compressed or cleaned up
+static CLEANUP_LOCK: Mutex<()> = Mutex::new(());
 
 /// Result of logger initialization, indicating whether file logging is active
 #[derive(Debug)]
@@ -53,6 +66,19 @@ impl LogLevel {
         }
     }
 
+    /// Level of `--loglevel` when given, else of the verbosity: `info`,
+    /// `debug` with `-v`, `trace` with `-vv`
+    ///
+    /// `-q` doesn't lower the level: it keeps logs off the console, while log
+    /// files still get everything from `info` up.
+    pub fn resolve(explicit: Option<&str>, verbosity: Verbosity) -> Self {
+        explicit.and_then(Self::parse).unwrap_or(match verbosity {
+            Verbosity::Quiet | Verbosity::Normal => LogLevel::Info,
+            Verbosity::Verbose => LogLevel::Debug,
+            Verbosity::Trace => LogLevel::Trace,
+        })
+    }
+
     /// Convert to string
     pub fn as_str(&self) -> &'static str {
         match self {
@@ -72,6 +98,21 @@ pub struct LogRotationConfig {
     pub max_files: usize,
     /// Number of days to retain log files
     pub retention_days: i64,
+    /// Size from which a log file is rotated (0 = daily rotation only)
+    pub max_file_bytes: u64,
+    /// Total size of the log files to retain (0 = unlimited)
+    pub max_total_bytes: u64,
+}
+
+impl Default for LogRotationConfig {
+    fn default() -> Self {
+        Self {
+            max_files: DEFAULT_LOG_MAX_FILES,
+            retention_days: DEFAULT_LOG_RETENTION_DAYS as i64,
+            max_file_bytes: DEFAULT_LOG_MAX_FILE_MB * 1024 * 1024,
+            max_total_bytes: DEFAULT_LOG_MAX_TOTAL_MB * 1024 * 1024,
+        }
+    }
 }
 
 impl LogRotationConfig {
@@ -86,7 +127,54 @@ impl LogRotationConfig {
                 .ok()
                 .and_then(|s| s.parse().ok())
                 .unwrap_or(DEFAULT_LOG_RETENTION_DAYS as i64),
+            max_file_bytes: std::env::var("CODESEARCH_LOG_MAX_FILE_MB")
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(DEFAULT_LOG_MAX_FILE_MB)
+                * 1024
+                * 1024,
+            max_total_bytes: std::env::var("CODESEARCH_LOG_MAX_TOTAL_MB")
+                .ok()
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(DEFAULT_LOG_MAX_TOTAL_MB)
+                * 1024
+                * 1024,
+        }
+    }
+}
+
+/// Log writers of `M` that drop emoji in plain output mode (`--no-emoji`)
+pub struct Plain<M>(pub M);
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for Plain<M> {
+    type Writer = PlainWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        PlainWriter(self.0.make_writer())
+    }
+}
+
+/// Writer made by [`Plain`]
+pub struct PlainWriter<W>(W);
+
+impl<W: std::io::Write> std::io::Write for PlainWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if !crate::output::is_plain() {
+            return self.0.write(buf);
         }
+        // Each log event arrives in one write, so lines aren't split
+        match std::str::from_utf8(buf) {
+            Ok(text) => {
+                self.0
+                    .write_all(crate::output::plain_text(text).as_bytes())?;
+                Ok(buf.len())
+            }
+            Err(_) => self.0.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
     }
 }
 
@@ -106,7 +194,7 @@ pub fn ensure_log_dir(log_dir: &Path) -> Result<()> {
 
 /// Try to extract a date from a daily-rotated log filename.
 ///
-/// tracing-appender DAILY rotation produces files named `<prefix>.YYYY-MM-DD`.
+/// Daily rotation produces files named `<prefix>.YYYY-MM-DD`.
 /// Returns `None` if the filename doesn't match the expected pattern.
 fn parse_log_date(file_name: &str) -> Option<NaiveDate> {
     // Pattern: "codesearch.log.YYYY-MM-DD"
@@ -114,20 +202,65 @@ fn parse_log_date(file_name: &str) -> Option<NaiveDate> {
     NaiveDate::parse_from_str(suffix, "%Y-%m-%d").ok()
 }
 
-/// Remove old log files based on retention period and max file count.
+/// A log file name: `codesearch.log.YYYY-MM-DD`, optionally followed by the
+/// part number of a size rotation and `.gz`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct LogFileName {
+    pub date: NaiveDate,
+    /// Part number of a file rotated by size
+    pub part: Option<u32>,
+    pub compressed: bool,
+}
+
+impl LogFileName {
+    /// Whether this is the file written to for its date
+    pub fn is_live(&self) -> bool {
+        self.part.is_none() && !self.compressed
+    }
+
+    /// Sort key, oldest first; the file of a date comes after its parts
+    fn age_key(&self) -> (NaiveDate, u32, bool) {
+        (self.date, self.part.unwrap_or(u32::MAX), self.is_live())
+    }
+}
+
+pub(crate) fn parse_log_name(file_name: &str) -> Option<LogFileName> {
+    let (name, compressed) = match file_name.strip_suffix(".gz") {
+        Some(name) => (name, true),
+        None => (file_name, false),
+    };
+    if let Some(date) = parse_log_date(name) {
+        return Some(LogFileName {
+            date,
+            part: None,
+            compressed,
+        });
+    }
+    let (name, part) = name.rsplit_once('.')?;
+    Some(LogFileName {
+        date: parse_log_date(name)?,
+        part: Some(part.parse().ok()?),
+        compressed,
+    })
+}
+
+/// Remove old log files based on retention period, max file count and total size.
 ///
-/// Two independent criteria:
+/// Three independent criteria:
 /// 1. Files older than `retention_days` are always removed.
 /// 2. If more than `max_files` remain, the oldest are removed.
+/// 3. If the rest take more than `max_total_bytes`, the oldest are removed,
+///    except today's live file.
 pub fn cleanup_old_logs(log_dir: &Path, config: &LogRotationConfig) -> Result<()> {
     if !log_dir.exists() {
         return Ok(());
     }
+    let _cleanup = rotation::lock_cleanup();
 
     let today = Utc::now().date_naive();
 
-    // Collect dated log files: (date, path)
-    let mut dated_files: Vec<(NaiveDate, PathBuf)> = Vec::new();
+    // Collect log files: (name, path)
+    let mut dated_files: Vec<(LogFileName, PathBuf)> = Vec::new();
 
     for entry in fs::read_dir(log_dir)? {
         let entry = entry?;
@@ -138,20 +271,20 @@ pub fn cleanup_old_logs(log_dir: &Path, config: &LogRotationConfig) -> Result<()
         }
 
         if let Some(file_name) = path.file_name().and_then(|n| n.to_str()) {
-            if let Some(date) = parse_log_date(file_name) {
-                dated_files.push((date, path));
+            if let Some(name) = parse_log_name(file_name) {
+                dated_files.push((name, path));
             }
         }
     }
 
     // Sort by date, oldest first
-    dated_files.sort_by_key(|(date, _)| *date);
+    dated_files.sort_by_key(|(name, _)| name.age_key());
 
     let mut removed_count = 0u32;
 
     // Pass 1: remove files older than retention_days
-    dated_files.retain(|(date, path)| {
-        let age_days = (today - *date).num_days();
+    dated_files.retain(|(name, path)| {
+        let age_days = (today - name.date).num_days();
         if age_days > config.retention_days {
             if let Err(e) = fs::remove_file(path) {
                 tracing::warn!("Failed to remove old log file {:?}: {}", path, e);
@@ -168,8 +301,8 @@ pub fn cleanup_old_logs(log_dir: &Path, config: &LogRotationConfig) -> Result<()
     // Pass 2: enforce max_files (remove oldest beyond the limit)
     if dated_files.len() > config.max_files {
         let excess = dated_files.len() - config.max_files;
-        for (_, path) in dated_files.iter().take(excess) {
-            if let Err(e) = fs::remove_file(path) {
+        for (_, path) in dated_files.drain(..excess) {
+            if let Err(e) = fs::remove_file(&path) {
                 tracing::warn!("Failed to remove excess log file {:?}: {}", path, e);
             } else {
                 tracing::debug!("Removed excess log file {:?}", path);
@@ -178,12 +311,37 @@ pub fn cleanup_old_logs(log_dir: &Path, config: &LogRotationConfig) -> Result<()
         }
     }
 
+    // Pass 3: enforce max_total_bytes (remove oldest until the rest fits)
+    if config.max_total_bytes > 0 {
+        let sizes: Vec<u64> = dated_files
+            .iter()
+            .map(|(_, path)| fs::metadata(path).map_or(0, |m| m.len()))
+            .collect();
+        let mut total: u64 = sizes.iter().sum();
+        for ((name, path), size) in dated_files.iter().zip(sizes) {
+            if total <= config.max_total_bytes {
+                break;
+            }
+            if name.is_live() && name.date == today {
+                continue;
+            }
+            if let Err(e) = fs::remove_file(path) {
+                tracing::warn!("Failed to remove log file {:?}: {}", path, e);
+            } else {
+                tracing::debug!("Removed log file {:?} (total size over the cap)", path);
+                total -= size;
+                removed_count += 1;
+            }
+        }
+    }
+
     if removed_count > 0 {
         tracing::info!(
-            "Log cleanup: removed {} file(s) (retention={}d, max_files={})",
+            "Log cleanup: removed {} file(s) (retention={}d, max_files={}, max_total={} MB)",
             removed_count,
             config.retention_days,
-            config.max_files
+            config.max_files,
+            config.max_total_bytes / (1024 * 1024)
         );
     }
 
@@ -210,9 +368,9 @@ pub fn init_logger(db_path: &Path, log_level: LogLevel, quiet: bool) -> Result<L
 
     let config = LogRotationConfig::from_env();
 
-    // Create file appender with DAILY rotation.
-    // Produces files like: logs/codesearch.log.2026-02-09
-    let file_appender = RollingFileAppender::new(Rotation::DAILY, &log_dir, LOG_FILE_NAME);
+    // Create file appender with DAILY and size rotation.
+    // Produces files like: logs/codesearch.log.2026-02-09(.1.gz)
+    let file_appender = Plain(LogAppender::new(&log_dir, config.clone()));
 
     // Build EnvFilter with per-crate directives.
     // Specific crate directives override the default level.
@@ -238,7 +396,7 @@ pub fn init_logger(db_path: &Path, log_level: LogLevel, quiet: bool) -> Result<L
             .try_init();
 
         if let Err(e) = result {
-            eprintln!(
+            err_print!(
                 "Logger: subscriber already set ({}), file logging not active",
                 e
             );
@@ -249,8 +407,8 @@ pub fn init_logger(db_path: &Path, log_level: LogLevel, quiet: bool) -> Result<L
         let result = subscriber
             .with(
                 fmt::layer()
-                    .with_writer(std::io::stderr)
-                    .with_ansi(true)
+                    .with_writer(Plain(std::io::stderr))
+                    .with_ansi(std::io::stderr().is_terminal())
                     .with_target(true)
                     .with_thread_ids(false),
             )
@@ -264,7 +422,7 @@ pub fn init_logger(db_path: &Path, log_level: LogLevel, quiet: bool) -> Result<L
             .try_init();
 
         if let Err(e) = result {
-            eprintln!(
+            err_print!(
                 "Logger: subscriber already set ({}), file logging not active",
                 e
             );
@@ -273,11 +431,13 @@ pub fn init_logger(db_path: &Path, log_level: LogLevel, quiet: bool) -> Result<L
     }
 
     tracing::info!(
-        "Logger initialized: level={}, log_dir={:?}, max_files={}, retention_days={}",
+        "Logger initialized: level={}, log_dir={:?}, max_files={}, retention_days={}, max_file={} MB, max_total={} MB",
         log_level.as_str(),
         log_dir,
         config.max_files,
         config.retention_days,
+        config.max_file_bytes / (1024 * 1024),
+        config.max_total_bytes / (1024 * 1024),
     );
 
     Ok(LoggerInitResult::FileLogging)
@@ -286,7 +446,7 @@ pub fn init_logger(db_path: &Path, log_level: LogLevel, quiet: bool) -> Result<L
 /// Start periodic log cleanup task.
 ///
 /// Runs every `CODESEARCH_LOG_CLEANUP_INTERVAL_HOURS` hours (default: 24)
-/// and removes old log files based on retention_days and max_files.
+/// and removes old log files based on retention_days, max_files and max_total_bytes.
 pub fn start_cleanup_task(
     log_dir: PathBuf,
     config: LogRotationConfig,
@@ -342,6 +502,23 @@ mod tests {
         assert_eq!(LogLevel::parse("invalid"), None);
     }
 
+    #[test]
+    fn test_log_level_resolve() {
+        assert_eq!(LogLevel::resolve(None, Verbosity::Normal), LogLevel::Info);
+        assert_eq!(LogLevel::resolve(None, Verbosity::Quiet), LogLevel::Info);
+        assert_eq!(LogLevel::resolve(None, Verbosity::Verbose), LogLevel::Debug);
+        assert_eq!(LogLevel::resolve(None, Verbosity::Trace), LogLevel::Trace);
+        // An explicit --loglevel wins over -v
+        assert_eq!(
+            LogLevel::resolve(Some("warn"), Verbosity::Verbose),
+            LogLevel::Warn
+        );
+        assert_eq!(
+            LogLevel::resolve(Some("bogus"), Verbosity::Verbose),
+            LogLevel::Debug
+        );
+    }
+
     #[test]
     fn test_log_level_as_str() {
         assert_eq!(LogLevel::Error.as_str(), "error");
@@ -376,6 +553,66 @@ mod tests {
         assert_eq!(parse_log_date("other.log.2026-02-09"), None);
     }
 
+    #[test]
+    fn test_parse_log_name() {
+        let date = NaiveDate::from_ymd_opt(2026, 2, 9).unwrap();
+        let live = parse_log_name("codesearch.log.2026-02-09").unwrap();
+        assert!(live.is_live());
+        assert_eq!(
+            parse_log_name("codesearch.log.2026-02-09.3.gz"),
+            Some(LogFileName {
+                date,
+                part: Some(3),
+                compressed: true
+            })
+        );
+        assert!(
+            live.age_key()
+                > parse_log_name("codesearch.log.2026-02-09.3.gz")
+                    .unwrap()
+                    .age_key()
+        );
+        assert!(!parse_log_name("codesearch.log.2026-02-09.gz")
+            .unwrap()
+            .is_live());
+        assert_eq!(parse_log_name("codesearch.log.2026-02-09.x"), None);
+        assert_eq!(parse_log_name("codesearch.log.1"), None);
+    }
+
+    #[test]
+    fn test_cleanup_old_logs_by_total_size() {
+        let temp_dir = TempDir::new().unwrap();
+        let log_dir = temp_dir.path();
+        let today = Utc::now().date_naive().format("%Y-%m-%d");
+
+        // Live file and two rotated parts of today, 400 bytes each
+        let live = log_dir.join(format!("{}.{}", LOG_FILE_NAME, today));
+        let first = log_dir.join(format!("{}.{}.1.gz", LOG_FILE_NAME, today));
+        let second = log_dir.join(format!("{}.{}.2.gz", LOG_FILE_NAME, today));
+        for path in [&live, &first, &second] {
+            fs::write(path, [b'x'; 400]).unwrap();
+        }
+
+        let config = LogRotationConfig {
+            max_files: 100,
+            retention_days: 30,
+            max_total_bytes: 1000,
+            ..Default::default()
+        };
+        cleanup_old_logs(log_dir, &config).unwrap();
+        assert!(!first.exists(), "Oldest part should be removed");
+        assert!(second.exists() && live.exists());
+
+        // The live file is kept even when it alone is over the cap
+        let config = LogRotationConfig {
+            max_total_bytes: 100,
+            ..config
+        };
+        cleanup_old_logs(log_dir, &config).unwrap();
+        assert!(!second.exists());
+        assert!(live.exists(), "Live log file should be retained");
+    }
+
     #[test]
     fn test_cleanup_old_logs_by_retention() {
         let temp_dir = TempDir::new().unwrap();
@@ -398,6 +635,7 @@ mod tests {
         let config = LogRotationConfig {
             max_files: 100, // high limit so only retention matters
             retention_days: 5,
+            ..Default::default()
         };
 
         cleanup_old_logs(log_dir, &config).unwrap();
@@ -429,6 +667,7 @@ mod tests {
         let config = LogRotationConfig {
             max_files: 3,
             retention_days: 30, // high limit so only max_files matters
+            ..Default::default()
         };
 
         cleanup_old_logs(log_dir, &config).unwrap();
@@ -448,6 +687,7 @@ mod tests {
         let config = LogRotationConfig {
             max_files: 5,
             retention_days: 5,
+            ..Default::default()
         };
         // Should not error on empty directory
         assert!(cleanup_old_logs(temp_dir.path(), &config).is_ok());
@@ -458,6 +698,7 @@ mod tests {
         let config = LogRotationConfig {
             max_files: 5,
             retention_days: 5,
+            ..Default::default()
         };
         // Should not error on non-existent directory
         assert!(cleanup_old_logs(Path::new("/nonexistent/path"), &config).is_ok());
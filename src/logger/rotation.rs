@@ -0,0 +1,243 @@
+//! Log file appender with daily and size-based rotation
+//!
+//! Writes `codesearch.log.YYYY-MM-DD` (by UTC date, the names daily rotation
+//! has always used). A file that would grow past `max_file_bytes` is renamed to
+//! `codesearch.log.YYYY-MM-DD.N` and a new one is started. Files closed by
+//! either rotation are gzipped to `.gz` in the background, after which
+//! [`cleanup_old_logs`] enforces the retention, count and total size limits.
+//! Several processes may append to the same file; one that finds the file it
+//! has open rotated away by another reopens the live path before writing.
+
+use chrono::{NaiveDate, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use tracing_subscriber::fmt::MakeWriter;
+
+use super::{cleanup_old_logs, LogRotationConfig, CLEANUP_LOCK};
+use crate::constants::LOG_FILE_NAME;
+
+/// The file being written
+struct ActiveLog {
+    file: File,
+    date: NaiveDate,
+    /// Size of the file, including what other processes appended before it was opened
+    bytes: u64,
+}
+
+/// Rotating log file appender for the file logging layer
+pub struct LogAppender {
+    dir: PathBuf,
+    config: LogRotationConfig,
+    active: Mutex<Option<ActiveLog>>,
+}
+
+impl LogAppender {
+    pub fn new(dir: &Path, config: LogRotationConfig) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            config,
+            active: Mutex::new(None),
+        }
+    }
+
+    /// Live log file of `date`
+    fn live_path(&self, date: NaiveDate) -> PathBuf {
+        self.dir
+            .join(format!("{}.{}", LOG_FILE_NAME, date.format("%Y-%m-%d")))
+    }
+
+    fn open(&self, date: NaiveDate) -> io::Result<ActiveLog> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.live_path(date))?;
+        let bytes = file.metadata()?.len();
+        Ok(ActiveLog { file, date, bytes })
+    }
+
+    /// Whether `log` is still the file at its live path
+    ///
+    /// Another process may have rotated it away; what is appended to it after
+    /// that would be lost when it is compressed.
+    fn is_live(&self, log: &ActiveLog) -> bool {
+        let (Ok(at_path), Ok(open)) = (fs::metadata(self.live_path(log.date)), log.file.metadata())
+        else {
+            return false;
+        };
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::MetadataExt;
+            at_path.dev() == open.dev() && at_path.ino() == open.ino()
+        }
+        #[cfg(not(unix))]
+        {
+            at_path.len() == open.len()
+        }
+    }
+
+    /// Rename the live file of `date` to its next free part number, unless
+    /// another process already did and `incoming` bytes still fit
+    fn rotate(&self, date: NaiveDate, incoming: u64) -> io::Result<Option<PathBuf>> {
+        let live = self.live_path(date);
+        if fs::metadata(&live).map_or(true, |m| m.len() + incoming <= self.config.max_file_bytes) {
+            return Ok(None);
+        }
+        let part = (1u32..)
+            .find(|n| {
+                let part = format!("{}.{}", live.display(), n);
+                !Path::new(&part).exists() && !Path::new(&format!("{}.gz", part)).exists()
+            })
+            .unwrap_or(u32::MAX);
+        let rotated = PathBuf::from(format!("{}.{}", live.display(), part));
+        fs::rename(&live, &rotated)?;
+        Ok(Some(rotated))
+    }
+
+    fn write_event(&self, buf: &[u8]) -> io::Result<()> {
+        let today = Utc::now().date_naive();
+        let mut active = self.active.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut closed = None;
+        if let Some(log) = active.as_ref() {
+            if log.date != today {
+                closed = Some(self.live_path(log.date));
+                *active = None;
+            } else if self.config.max_file_bytes > 0
+                && log.bytes > 0
+                && log.bytes + buf.len() as u64 > self.config.max_file_bytes
+            {
+                *active = None;
+                closed = self.rotate(today, buf.len() as u64)?;
+            } else if !self.is_live(log) {
+                // Rotated by another process, which also archives it
+                *active = None;
+            }
+        }
+        if active.is_none() {
+            *active = Some(self.open(today)?);
+        }
+        if let Some(log) = active.as_mut() {
+            log.file.write_all(buf)?;
+            log.bytes += buf.len() as u64;
+        }
+        drop(active);
+
+        // Logging from here would re-enter the appender, so archiving reports nothing
+        if let Some(closed) = closed {
+            let dir = self.dir.clone();
+            let config = self.config.clone();
+            std::thread::spawn(move || {
+                let _ = archive_log(&dir, &config, &closed);
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Gzip a closed log file, then clean up the log directory
+fn archive_log(dir: &Path, config: &LogRotationConfig, path: &Path) -> anyhow::Result<()> {
+    {
+        let _cleanup = lock_cleanup();
+        compress_log(path)?;
+    }
+    cleanup_old_logs(dir, config)
+}
+
+/// Replace `path` with a gzipped copy `<path>.gz`
+pub(super) fn compress_log(path: &Path) -> io::Result<PathBuf> {
+    let compressed = PathBuf::from(format!("{}.gz", path.display()));
+    let result = (|| {
+        let mut input = File::open(path)?;
+        let mut encoder = GzEncoder::new(File::create(&compressed)?, Compression::default());
+        io::copy(&mut input, &mut encoder)?;
+        encoder.finish()?.sync_all()
+    })();
+    if let Err(e) = result {
+        let _ = fs::remove_file(&compressed);
+        return Err(e);
+    }
+    fs::remove_file(path)?;
+    Ok(compressed)
+}
+
+/// Serializes compression and cleanup, which may run from several threads
+pub(super) fn lock_cleanup() -> MutexGuard<'static, ()> {
+    CLEANUP_LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Writer made by [`LogAppender`]; each log event arrives in one write
+pub struct LogWriter<'a>(&'a LogAppender);
+
+impl Write for LogWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_event(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<'a> MakeWriter<'a> for LogAppender {
+    type Writer = LogWriter<'a>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        LogWriter(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_size_rotation_and_compression() {
+        let temp_dir = TempDir::new().unwrap();
+        let appender = LogAppender::new(
+            temp_dir.path(),
+            LogRotationConfig {
+                max_files: 10,
+                retention_days: 5,
+                max_file_bytes: 100,
+                max_total_bytes: 0,
+            },
+        );
+        let first = format!("{}\n", "a".repeat(59));
+        let second = format!("{}\n", "b".repeat(59));
+        appender.make_writer().write_all(first.as_bytes()).unwrap();
+        appender.make_writer().write_all(second.as_bytes()).unwrap();
+
+        let live = appender.live_path(Utc::now().date_naive());
+        assert_eq!(fs::read_to_string(&live).unwrap(), second);
+
+        // The rotated part is compressed in the background
+        let archive = PathBuf::from(format!("{}.1.gz", live.display()));
+        for _ in 0..100 {
+            if archive.exists() && !Path::new(&format!("{}.1", live.display())).exists() {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+        let mut content = String::new();
+        GzDecoder::new(File::open(&archive).unwrap())
+            .read_to_string(&mut content)
+            .unwrap();
+        assert_eq!(content, first);
+        assert!(!Path::new(&format!("{}.1", live.display())).exists());
+
+        // Rotated away by another process: the next event goes to a new live file
+        let moved = temp_dir.path().join("moved.log");
+        fs::rename(&live, &moved).unwrap();
+        let third = "c\n";
+        appender.make_writer().write_all(third.as_bytes()).unwrap();
+        assert_eq!(fs::read_to_string(&live).unwrap(), third);
+        assert_eq!(fs::read_to_string(&moved).unwrap(), second);
+    }
+}
@@ -27,19 +27,17 @@ use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 async fn main() -> Result<()> {
     // Parse CLI to get loglevel (need this before tracing init)
     let args: Vec<String> = std::env::args().collect();
-    let is_quiet = args.iter().any(|a| a == "-q" || a == "--quiet");
+    let verbosity = output::Verbosity::from_args(&args);
+    let is_quiet = verbosity == output::Verbosity::Quiet;
     let is_json = args.iter().any(|a| a == "--json");
+    output::set_plain(args.iter().any(|a| a == "--no-emoji") || output::plain_from_env());
 
-    // Parse loglevel from args (default: info)
+    // Loglevel from args, else from -v/-vv (default: info)
     let loglevel = args
         .iter()
         .position(|a| a == "-l" || a == "--loglevel")
-        .and_then(|pos| args.get(pos + 1))
-        .cloned()
-        .unwrap_or_else(|| "info".to_string());
-
-    // Validate loglevel
-    let log_level = logger::LogLevel::parse(&loglevel).unwrap_or(logger::LogLevel::Info);
+        .and_then(|pos| args.get(pos + 1));
+    let log_level = logger::LogLevel::resolve(loglevel.map(String::as_str), verbosity);
     let log_level_str = log_level.as_str();
 
     // Create cancellation token for async shutdown (MCP server, file watcher)
@@ -51,11 +49,11 @@ async fn main() -> Result<()> {
     ctrlc::set_handler(move || {
         if constants::SHUTDOWN_REQUESTED.load(Ordering::SeqCst) {
             // Second CTRL-C: force exit
-            eprintln!("\n⚠️  Force shutdown!");
+            err_print!("\n⚠️  Force shutdown!");
             std::process::exit(130);
         }
         if !is_quiet && !is_json {
-            eprintln!("\n🛑 Shutting down gracefully... (press Ctrl-C again to force)");
+            err_print!("\n🛑 Shutting down gracefully... (press Ctrl-C again to force)");
         }
         constants::SHUTDOWN_REQUESTED.store(true, Ordering::SeqCst);
         cancel_clone.cancel();
@@ -65,7 +63,10 @@ async fn main() -> Result<()> {
     // For MCP/serve commands: DON'T initialize tracing here.
     // init_logger() in cli/mod.rs will set up console+file logging as the FIRST
     // and ONLY global subscriber (you can only set it once per process).
-    let is_mcp_or_serve = args.iter().any(|a| a == "mcp" || a == "serve");
+    // The Windows service daemon sets up its own log file the same way.
+    let is_mcp_or_serve = args
+        .iter()
+        .any(|a| a == "mcp" || a == "serve" || a == "--windows-service");
 
     if !is_quiet && !is_json && !is_mcp_or_serve {
         // Console-only tracing for short-lived CLI commands (search, index, stats, etc.)
@@ -75,7 +76,7 @@ async fn main() -> Result<()> {
                 tracing_subscriber::EnvFilter::try_from_default_env()
                     .unwrap_or_else(|_| format!("codesearch={}", log_level_str).into()),
             )
-            .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
+            .with(tracing_subscriber::fmt::layer().with_writer(logger::Plain(std::io::stderr)))
             .init();
 
         info!(
@@ -85,6 +86,9 @@ async fn main() -> Result<()> {
         );
     }
 
+    // Log what the indexer was doing if the process panics
+    index::install_panic_hook();
+
     // Run CLI — for MCP/serve commands, cancel_token enables graceful shutdown.
     // For short-lived commands, the token is simply unused.
     cli::run(cancel_token).await
@@ -0,0 +1,464 @@
+//! Streamable HTTP transport of the MCP server (`codesearch mcp --http <PORT>`)
+//!
+//! Over stdio each client starts its own server. Over HTTP one server, with
+//! one set of stores and one file watcher, serves every client on the machine
+//! (several editor and agent sessions at once). Each client gets a session of
+//! its own, named by the `Mcp-Session-Id` header of the `initialize` response:
+//!
+//! - `POST /mcp` carries JSON-RPC messages; requests are answered in the
+//!   response body as JSON, notifications and responses get `202 Accepted`
+//! - `GET /mcp` opens an SSE stream of the messages the server sends on its
+//!   own (notifications not tied to a request)
+//! - `DELETE /mcp` ends the session
+//!
+//! Requests from browsers on other origins are refused, so a web page can't
+//! reach the server through DNS rebinding.
+
+use anyhow::Result;
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+use rmcp::model::{
+    ClientJsonRpcMessage, ClientRequest, JsonRpcMessage, RequestId, ServerJsonRpcMessage,
+};
+use rmcp::service::RoleServer;
+use rmcp::transport::Transport;
+use rmcp::{ServerHandler, ServiceExt};
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, oneshot};
+use tokio_util::io::ReaderStream;
+use tokio_util::sync::CancellationToken;
+
+/// Header naming the session of a request
+const SESSION_HEADER: &str = "mcp-session-id";
+/// Buffer between a session's event stream and its HTTP response
+const STREAM_BUFFER_BYTES: usize = 64 * 1024;
+/// Idle time after which an event stream sends a comment, so proxies and
+/// clients don't time the connection out
+const SSE_KEEPALIVE: Duration = Duration::from_secs(15);
+
+type ServiceFactory<S> = dyn Fn() -> Result<S> + Send + Sync;
+
+/// Serve MCP sessions on `listener` until `cancel_token` fires, each with a
+/// service made by `new_service`
+pub async fn serve<S: ServerHandler>(
+    listener: TcpListener,
+    new_service: impl Fn() -> Result<S> + Send + Sync + 'static,
+    cancel_token: CancellationToken,
+) -> Result<()> {
+    let sessions = Arc::new(Sessions {
+        new_service: Box::new(new_service),
+        sessions: Mutex::new(HashMap::new()),
+        cancel_token: cancel_token.clone(),
+    });
+    let router = Router::new()
+        .route(
+            "/mcp",
+            post(post_handler::<S>)
+                .get(get_handler::<S>)
+                .delete(delete_handler::<S>),
+        )
+        .with_state(sessions);
+
+    axum::serve(listener, router)
+        .with_graceful_shutdown(cancel_token.cancelled_owned())
+        .await?;
+    Ok(())
+}
+
+/// The live sessions of a server
+struct Sessions<S> {
+    new_service: Box<ServiceFactory<S>>,
+    sessions: Mutex<HashMap<String, Arc<Session>>>,
+    /// Parent of the sessions' tokens: ends them all on shutdown
+    cancel_token: CancellationToken,
+}
+
+impl<S: ServerHandler> Sessions<S> {
+    /// Start a session whose service runs until the session is closed
+    fn open(self: &Arc<Self>) -> Result<(String, Arc<Session>)> {
+        let service = (self.new_service)()?;
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let (incoming, receiver) = mpsc::unbounded_channel();
+        let session = Arc::new(Session {
+            incoming,
+            pending: Mutex::new(HashMap::new()),
+            events: Mutex::new(None),
+            cancel_token: self.cancel_token.child_token(),
+        });
+        let transport = SessionTransport {
+            session: session.clone(),
+            incoming: receiver,
+        };
+        lock(&self.sessions).insert(id.clone(), session.clone());
+        tracing::info!("🔌 MCP session {} opened", id);
+
+        let sessions = self.clone();
+        let session_id = id.clone();
+        let cancel_token = session.cancel_token.clone();
+        tokio::spawn(async move {
+            match service.serve_with_ct(transport, cancel_token).await {
+                Ok(running) => {
+                    let _ = running.waiting().await;
+                }
+                Err(e) => tracing::warn!("MCP session {} failed to initialize: {}", session_id, e),
+            }
+            sessions.close(&session_id);
+        });
+        Ok((id, session))
+    }
+
+    fn get(&self, id: &str) -> Option<Arc<Session>> {
+        lock(&self.sessions).get(id).cloned()
+    }
+
+    /// End a session: its service stops and waiting requests fail
+    fn close(&self, id: &str) -> bool {
+        let Some(session) = lock(&self.sessions).remove(id) else {
+            return false;
+        };
+        session.cancel_token.cancel();
+        lock(&session.pending).clear();
+        lock(&session.events).take();
+        tracing::info!("🔌 MCP session {} closed", id);
+        true
+    }
+}
+
+/// One client's connection to its service
+struct Session {
+    /// Messages from the client, in the order they were posted
+    incoming: mpsc::UnboundedSender<ClientJsonRpcMessage>,
+    /// Posted requests waiting for their response
+    pending: Mutex<HashMap<RequestId, oneshot::Sender<ServerJsonRpcMessage>>>,
+    /// The client's event stream (`GET /mcp`), if it opened one
+    events: Mutex<Option<mpsc::UnboundedSender<ServerJsonRpcMessage>>>,
+    cancel_token: CancellationToken,
+}
+
+impl Session {
+    /// Hand a message of the service to the request waiting for it, or else
+    /// to the event stream
+    fn deliver(&self, message: ServerJsonRpcMessage) {
+        let id = match &message {
+            JsonRpcMessage::Response(response) => Some(&response.id),
+            JsonRpcMessage::Error(error) => Some(&error.id),
+            _ => None,
+        };
+        if let Some(waiting) = id.and_then(|id| lock(&self.pending).remove(id)) {
+            let _ = waiting.send(message);
+            return;
+        }
+        match lock(&self.events).as_ref() {
+            Some(events) => {
+                let _ = events.send(message);
+            }
+            None => tracing::debug!("No event stream open, dropped MCP message"),
+        }
+    }
+}
+
+/// The service's side of a session
+struct SessionTransport {
+    session: Arc<Session>,
+    incoming: mpsc::UnboundedReceiver<ClientJsonRpcMessage>,
+}
+
+impl Transport<RoleServer> for SessionTransport {
+    type Error = Infallible;
+
+    fn send(
+        &mut self,
+        item: ServerJsonRpcMessage,
+    ) -> impl Future<Output = Result<(), Self::Error>> + Send + 'static {
+        self.session.deliver(item);
+        std::future::ready(Ok(()))
+    }
+
+    fn receive(&mut self) -> impl Future<Output = Option<ClientJsonRpcMessage>> + Send {
+        self.incoming.recv()
+    }
+
+    async fn close(&mut self) -> Result<(), Self::Error> {
+        self.incoming.close();
+        Ok(())
+    }
+}
+
+async fn post_handler<S: ServerHandler>(
+    State(sessions): State<Arc<Sessions<S>>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Response {
+    if let Some(refused) = refuse_foreign_origin(&headers) {
+        return refused;
+    }
+    let (messages, batch) = match parse_messages(&body) {
+        Ok(parsed) => parsed,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Invalid JSON-RPC message: {}", e),
+            )
+                .into_response()
+        }
+    };
+
+    let initialize = messages.iter().any(|message| {
+        matches!(message, JsonRpcMessage::Request(request)
+            if matches!(request.request, ClientRequest::InitializeRequest(_)))
+    });
+    let (id, session) = match session_id(&headers) {
+        Some(id) => match sessions.get(id) {
+            Some(session) => (id.to_string(), session),
+            None => return (StatusCode::NOT_FOUND, "Unknown MCP session").into_response(),
+        },
+        None if initialize => match sessions.open() {
+            Ok(opened) => opened,
+            Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response(),
+        },
+        None => return (StatusCode::BAD_REQUEST, "Missing Mcp-Session-Id header").into_response(),
+    };
+
+    let mut waiting = Vec::new();
+    for message in messages {
+        if let JsonRpcMessage::Request(request) = &message {
+            let (sender, receiver) = oneshot::channel();
+            lock(&session.pending).insert(request.id.clone(), sender);
+            waiting.push(receiver);
+        }
+        if session.incoming.send(message).is_err() {
+            return (StatusCode::NOT_FOUND, "MCP session closed").into_response();
+        }
+    }
+    if waiting.is_empty() {
+        return (StatusCode::ACCEPTED, [(SESSION_HEADER, id)]).into_response();
+    }
+
+    let mut responses = Vec::with_capacity(waiting.len());
+    for receiver in waiting {
+        match receiver.await {
+            Ok(response) => responses.push(response),
+            Err(_) => return (StatusCode::NOT_FOUND, "MCP session closed").into_response(),
+        }
+    }
+    let headers = [(SESSION_HEADER, id)];
+    if batch {
+        (headers, Json(responses)).into_response()
+    } else {
+        (headers, Json(responses.remove(0))).into_response()
+    }
+}
+
+async fn get_handler<S: ServerHandler>(
+    State(sessions): State<Arc<Sessions<S>>>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(refused) = refuse_foreign_origin(&headers) {
+        return refused;
+    }
+    let Some(session) = session_id(&headers).and_then(|id| sessions.get(id)) else {
+        return (StatusCode::NOT_FOUND, "Unknown MCP session").into_response();
+    };
+
+    // A new stream replaces the previous one
+    let (sender, mut events) = mpsc::unbounded_channel();
+    *lock(&session.events) = Some(sender);
+
+    let (reader, mut writer) = tokio::io::duplex(STREAM_BUFFER_BYTES);
+    let cancel_token = session.cancel_token.clone();
+    tokio::spawn(async move {
+        loop {
+            let frame = tokio::select! {
+                event = events.recv() => match event.map(|e| serde_json::to_string(&e)) {
+                    Some(Ok(json)) => format!("event: message\ndata: {}\n\n", json),
+                    Some(Err(e)) => {
+                        tracing::warn!("Could not serialize MCP message: {}", e);
+                        continue;
+                    }
+                    None => break,
+                },
+                _ = tokio::time::sleep(SSE_KEEPALIVE) => ": keepalive\n\n".to_string(),
+                _ = cancel_token.cancelled() => break,
+            };
+            // Fails once the client disconnected
+            if writer.write_all(frame.as_bytes()).await.is_err() {
+                break;
+            }
+        }
+    });
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "text/event-stream")
+        .header(header::CACHE_CONTROL, "no-cache")
+        .body(Body::from_stream(ReaderStream::new(reader)))
+        .unwrap_or_else(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response())
+}
+
+async fn delete_handler<S: ServerHandler>(
+    State(sessions): State<Arc<Sessions<S>>>,
+    headers: HeaderMap,
+) -> Response {
+    if let Some(refused) = refuse_foreign_origin(&headers) {
+        return refused;
+    }
+    match session_id(&headers) {
+        Some(id) if sessions.close(id) => StatusCode::NO_CONTENT.into_response(),
+        _ => (StatusCode::NOT_FOUND, "Unknown MCP session").into_response(),
+    }
+}
+
+/// The messages of a request body, and whether they came as a batch
+fn parse_messages(body: &[u8]) -> serde_json::Result<(Vec<ClientJsonRpcMessage>, bool)> {
+    match serde_json::from_slice::<serde_json::Value>(body)? {
+        serde_json::Value::Array(values) => Ok((
+            values
+                .into_iter()
+                .map(serde_json::from_value)
+                .collect::<serde_json::Result<_>>()?,
+            true,
+        )),
+        value => Ok((vec![serde_json::from_value(value)?], false)),
+    }
+}
+
+fn session_id(headers: &HeaderMap) -> Option<&str> {
+    headers.get(SESSION_HEADER)?.to_str().ok()
+}
+
+/// The refusal of a request a browser sends from a page not served by this machine
+fn refuse_foreign_origin(headers: &HeaderMap) -> Option<Response> {
+    let origin = headers.get(header::ORIGIN)?;
+    match origin.to_str() {
+        Ok(origin) if is_local_origin(origin) => None,
+        _ => Some((StatusCode::FORBIDDEN, "Origin not allowed").into_response()),
+    }
+}
+
+/// Whether an `Origin` names this machine (`http://localhost:3000`, `http://[::1]`, ...)
+fn is_local_origin(origin: &str) -> bool {
+    let host = origin.split_once("://").map_or(origin, |(_, rest)| rest);
+    let host = match host.strip_prefix('[') {
+        Some(ipv6) => ipv6.split(']').next().unwrap_or(ipv6),
+        None => host.split(':').next().unwrap_or(host),
+    };
+    matches!(host, "localhost" | "127.0.0.1" | "::1")
+}
+
+/// Lock a mutex, also when a panicking holder poisoned it
+fn lock<T>(mutex: &Mutex<T>) -> MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A server with no tools
+    struct EmptyServer;
+
+    impl ServerHandler for EmptyServer {}
+
+    #[tokio::test]
+    async fn test_streamable_http_session() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/mcp", listener.local_addr().unwrap());
+        let cancel_token = CancellationToken::new();
+        let server = tokio::spawn(serve(listener, || Ok(EmptyServer), cancel_token.clone()));
+
+        let responses = tokio::task::spawn_blocking(move || {
+            let post = |session: Option<&str>, body: serde_json::Value| {
+                let mut request = ureq::post(&url);
+                if let Some(session) = session {
+                    request = request.set(SESSION_HEADER, session);
+                }
+                // Failures as their HTTP status
+                request.send_json(body).map_err(|e| match e {
+                    ureq::Error::Status(status, _) => status,
+                    e => panic!("{}", e),
+                })
+            };
+            let initialize = serde_json::json!({
+                "jsonrpc": "2.0", "id": 1, "method": "initialize",
+                "params": {
+                    "protocolVersion": "2025-03-26",
+                    "capabilities": {},
+                    "clientInfo": {"name": "test", "version": "1.0"}
+                }
+            });
+
+            // Requests outside a session are refused
+            let list = serde_json::json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list"});
+            assert_eq!(post(None, list.clone()).unwrap_err(), 400);
+
+            let response = post(None, initialize.clone()).unwrap();
+            let session = response.header(SESSION_HEADER).unwrap().to_string();
+            let initialized: serde_json::Value = response.into_json().unwrap();
+            let notified = post(
+                Some(&session),
+                serde_json::json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+            )
+            .unwrap();
+            assert_eq!(notified.status(), 202);
+            let tools: serde_json::Value = post(Some(&session), list.clone())
+                .unwrap()
+                .into_json()
+                .unwrap();
+
+            // A second client gets a session of its own
+            let other = post(None, initialize).unwrap();
+            assert_ne!(other.header(SESSION_HEADER).unwrap(), session);
+
+            // A browser page elsewhere is refused
+            let foreign = ureq::post(&url)
+                .set(SESSION_HEADER, &session)
+                .set("Origin", "https://example.com")
+                .send_json(list.clone());
+            assert!(matches!(foreign, Err(ureq::Error::Status(403, _))));
+
+            // A closed session is gone
+            assert_eq!(
+                ureq::delete(&url)
+                    .set(SESSION_HEADER, &session)
+                    .call()
+                    .unwrap()
+                    .status(),
+                204
+            );
+            assert_eq!(post(Some(&session), list).unwrap_err(), 404);
+            (initialized, tools)
+        })
+        .await
+        .unwrap();
+
+        let (initialized, tools) = responses;
+        assert_eq!(initialized["id"], 1);
+        assert!(initialized["result"]["serverInfo"].is_object());
+        assert_eq!(tools["id"], 2);
+        assert!(tools["result"]["tools"].is_array());
+
+        cancel_token.cancel();
+        server.await.unwrap().unwrap();
+    }
+
+    #[test]
+    fn test_is_local_origin() {
+        assert!(is_local_origin("http://localhost:3000"));
+        assert!(is_local_origin("http://127.0.0.1"));
+        assert!(is_local_origin("http://[::1]:8080"));
+        assert!(!is_local_origin("https://example.com"));
+        assert!(!is_local_origin("http://localhost.example.com"));
+        assert!(!is_local_origin("null"));
+    }
+}
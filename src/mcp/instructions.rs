@@ -0,0 +1,200 @@
+//! Instructions the MCP server hands to agents in `get_info`
+//!
+//! The built-in text explains every tool and the recommended workflows.
+//! `CODESEARCH_MCP_INSTRUCTIONS` picks it in full (the default), as a terse
+//! summary for agents that already know the tools, or leaves it out. Teams
+//! add project-specific guidance in `.codesearch-instructions.md` at the
+//! project root (or the file named by `CODESEARCH_MCP_INSTRUCTIONS_FILE`);
+//! it is appended after the built-in text, or replaces it when that is off.
+
+use std::path::{Path, PathBuf};
+
+use crate::constants::INSTRUCTIONS_FILE;
+
+/// Custom instructions longer than this are cut, to bound the tokens they cost
+const MAX_CUSTOM_INSTRUCTIONS_BYTES: usize = 16 * 1024;
+
+/// How much of the built-in instruction text is sent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstructionsStyle {
+    /// Every tool with its parameters, workflows and troubleshooting
+    Full,
+    /// One line per tool and the rules that matter most
+    Terse,
+    /// Only the custom instructions
+    None,
+}
+
+impl InstructionsStyle {
+    /// Style from `CODESEARCH_MCP_INSTRUCTIONS` (`full`, `terse` or `none`)
+    pub fn from_env() -> Self {
+        match std::env::var("CODESEARCH_MCP_INSTRUCTIONS")
+            .map(|v| v.trim().to_lowercase())
+            .as_deref()
+        {
+            Ok("terse") => Self::Terse,
+            Ok("none") | Ok("off") => Self::None,
+            Ok("full") | Err(_) => Self::Full,
+            Ok(other) => {
+                tracing::warn!(
+                    "⚠️  Unknown CODESEARCH_MCP_INSTRUCTIONS '{}', using full instructions",
+                    other
+                );
+                Self::Full
+            }
+        }
+    }
+}
+
+/// File with the custom instructions of the project at `project_path`
+fn custom_instructions_path(project_path: &Path) -> PathBuf {
+    match std::env::var("CODESEARCH_MCP_INSTRUCTIONS_FILE") {
+        Ok(path) if !path.trim().is_empty() => PathBuf::from(path.trim()),
+        _ => project_path.join(INSTRUCTIONS_FILE),
+    }
+}
+
+/// Custom instructions of the project at `project_path`, if it has any
+pub fn custom_instructions(project_path: &Path) -> Option<String> {
+    let path = custom_instructions_path(project_path);
+    let content = match std::fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return None,
+        Err(e) => {
+            tracing::warn!("⚠️  Ignoring instructions file {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    let mut content = content.trim().to_string();
+    if content.len() > MAX_CUSTOM_INSTRUCTIONS_BYTES {
+        let mut end = MAX_CUSTOM_INSTRUCTIONS_BYTES;
+        while !content.is_char_boundary(end) {
+            end -= 1;
+        }
+        content.truncate(end);
+        tracing::warn!(
+            "⚠️  Instructions file {} is over {} KB; the rest is left out",
+            path.display(),
+            MAX_CUSTOM_INSTRUCTIONS_BYTES / 1024
+        );
+    }
+    (!content.is_empty()).then_some(content)
+}
+
+/// Built-in text in `style` (from `full` for the full text), followed by the
+/// custom instructions
+pub fn compose(
+    style: InstructionsStyle,
+    full: impl FnOnce() -> String,
+    project_path: &Path,
+    db_path: &Path,
+    custom: Option<String>,
+) -> String {
+    let builtin = match style {
+        InstructionsStyle::Full => Some(full()),
+        InstructionsStyle::Terse => Some(terse(project_path, db_path)),
+        InstructionsStyle::None => None,
+    };
+    match (builtin, custom) {
+        (Some(builtin), Some(custom)) => {
+            format!(
+                "{}\n\nPROJECT INSTRUCTIONS:\n\n{}\n",
+                builtin.trim_end(),
+                custom
+            )
+        }
+        (Some(builtin), None) => builtin,
+        (None, Some(custom)) => custom,
+        // Nothing to send: keep the agent from guessing what the server is
+        (None, None) => terse(project_path, db_path),
+    }
+}
+
+/// Summary of the tools for agents that already know codesearch
+fn terse(project_path: &Path, db_path: &Path) -> String {
+    format!(
+        r#"codesearch - semantic code search over a local index (read-only; the user runs 'codesearch index').
+
+Tools: find_databases, index_status, semantic_search, batch_search, find_references,
+get_definition, file_outline, related_chunks, neighbors, tests_for, implementation_for, owners,
+use_database.
+
+- Search with natural-language queries; results are compact metadata, read the lines you need.
+- Use find_references, not grep, for the usages of a symbol, and get_definition for where it is defined.
+- Narrow with filter_path, changed_only or project_only.
+
+Project: {}
+Database: {}
+"#,
+        project_path.display(),
+        db_path.display()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_instructions() {
+        let project = tempdir().unwrap();
+        let db_path = project.path().join(".codesearch.db");
+        let full = || "FULL\n".to_string();
+
+        assert_eq!(custom_instructions(project.path()), None);
+        assert_eq!(
+            compose(
+                InstructionsStyle::Full,
+                full,
+                project.path(),
+                &db_path,
+                None
+            ),
+            "FULL\n"
+        );
+
+        std::fs::write(
+            project.path().join(INSTRUCTIONS_FILE),
+            "\nRun `make check` before committing.\n",
+        )
+        .unwrap();
+        let custom = custom_instructions(project.path());
+        assert_eq!(
+            custom.as_deref(),
+            Some("Run `make check` before committing.")
+        );
+        assert_eq!(
+            compose(
+                InstructionsStyle::Full,
+                full,
+                project.path(),
+                &db_path,
+                custom.clone()
+            ),
+            "FULL\n\nPROJECT INSTRUCTIONS:\n\nRun `make check` before committing.\n"
+        );
+        assert_eq!(
+            compose(
+                InstructionsStyle::None,
+                full,
+                project.path(),
+                &db_path,
+                custom.clone()
+            ),
+            "Run `make check` before committing."
+        );
+
+        let terse = compose(
+            InstructionsStyle::Terse,
+            full,
+            project.path(),
+            &db_path,
+            custom,
+        );
+        assert!(terse.starts_with("codesearch - semantic code search"));
+        assert!(terse.contains("find_references, not grep"));
+        assert!(terse.ends_with("Run `make check` before committing.\n"));
+        assert!(terse.len() < 1000);
+    }
+}
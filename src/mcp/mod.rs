@@ -12,6 +12,8 @@
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
     fn test_mcp_no_raw_stdout_calls() {
         // Verify that no raw print!/println! calls exist in the MCP module sources.
@@ -49,196 +51,928 @@ mod tests {
                 .join("\n")
         );
     }
+
+    #[test]
+    fn test_classify_error_kinds() {
+        let cases = [
+            (
+                "Query embedding dimension mismatch: expected 768, got 384",
+                ToolErrorKind::DimensionMismatch,
+            ),
+            (
+                "Index not built. Call build_index() after inserting chunks.",
+                ToolErrorKind::NotIndexed,
+            ),
+            (
+                "Database does not exist at: /tmp/x",
+                ToolErrorKind::NotIndexed,
+            ),
+            (
+                "Failed to acquire Lockfile: LockBusy",
+                ToolErrorKind::Locked,
+            ),
+            (
+                "MDB_CORRUPTED: Located page was wrong type",
+                ToolErrorKind::Io,
+            ),
+            ("something unexpected", ToolErrorKind::Internal),
+        ];
+        for (msg, expected) in cases {
+            assert_eq!(
+                classify_error(&anyhow::anyhow!("{}", msg)),
+                expected,
+                "{}",
+                msg
+            );
+        }
+
+        let io_err: anyhow::Error =
+            std::io::Error::new(std::io::ErrorKind::PermissionDenied, "nope").into();
+        assert_eq!(classify_error(&io_err), ToolErrorKind::Io);
+    }
+
+    #[test]
+    fn test_tool_error_is_structured() {
+        let result = tool_error(ToolErrorKind::NotIndexed, "no index");
+        assert_eq!(result.is_error, Some(true));
+
+        let value = result.structured_content.unwrap();
+        assert_eq!(value["error"], "not_indexed");
+        assert_eq!(value["message"], "no index");
+        assert!(value["hint"].is_string());
+
+        let internal = tool_error(ToolErrorKind::Internal, "boom");
+        let value = internal.structured_content.unwrap();
+        assert_eq!(value["error"], "internal");
+        assert!(value.get("hint").is_none());
+    }
+
+    #[test]
+    fn test_index_progress_percent() {
+        let progress = IndexProgressInfo::new(1, 3);
+        assert_eq!(progress.percent_complete, 33.3);
+        assert_eq!(IndexProgressInfo::new(0, 0).percent_complete, 100.0);
+        assert_eq!(IndexProgressInfo::new(200, 200).percent_complete, 100.0);
+    }
+
+    #[test]
+    fn test_search_scope_validation() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = ActiveDatabase::open(
+            dir.path().join(".codesearch.db"),
+            dir.path().to_path_buf(),
+            None,
+        )
+        .unwrap();
+
+        let scope = db.search_scope(Some("src/**/*.rs"), false, true).unwrap();
+        assert!(scope.path_filter.is_some());
+        assert!(scope.changed_files.is_none());
+        assert!(scope.project_only);
+
+        let error = |result: Result<SearchScope, CallToolResult>| {
+            let value = result.err().unwrap().structured_content.unwrap();
+            value["error"].as_str().unwrap().to_string()
+        };
+        assert_eq!(
+            error(db.search_scope(Some("src/["), false, false)),
+            "invalid_request"
+        );
+        // A temporary directory is not a git work tree
+        assert_eq!(error(db.search_scope(None, true, false)), "invalid_request");
+    }
+
+    #[test]
+    fn test_result_projection() {
+        let result = crate::vectordb::SearchResult {
+            id: 7,
+            content: "fn f() {}".to_string(),
+            path: "src/f.rs".to_string(),
+            start_line: 1,
+            end_line: 2,
+            kind: "Function".to_string(),
+            signature: Some("fn f()".to_string()),
+            docstring: None,
+            context: None,
+            hash: String::new(),
+            distance: 0.1,
+            score: 0.9,
+            context_prev: Some("// before".to_string()),
+            context_next: None,
+            text_match: false,
+        };
+        let json = |projection: ResultProjection| {
+            serde_json::to_value(projection.item(result.clone(), None)).unwrap()
+        };
+
+        let compact = json(ResultProjection::new(true, None).unwrap());
+        assert_eq!(compact["chunk_id"], 7);
+        assert!(compact.get("content").is_none() && compact.get("context_prev").is_none());
+        let full = json(ResultProjection::new(false, None).unwrap());
+        assert_eq!(full["content"], "fn f() {}");
+        assert_eq!(full["context_prev"], "// before");
+
+        // fields overrides compact, in either direction
+        let fields = ["signature".to_string(), "path".to_string()];
+        let projected = json(ResultProjection::new(false, Some(&fields)).unwrap());
+        assert_eq!(
+            projected,
+            serde_json::json!({"path": "src/f.rs", "signature": "fn f()"})
+        );
+        let fields = ["content".to_string()];
+        let projected = json(ResultProjection::new(true, Some(&fields)).unwrap());
+        assert_eq!(projected, serde_json::json!({"content": "fn f() {}"}));
+
+        // Ranked results are scored relative to the top one
+        let fields = ["score".to_string(), "confidence".to_string()];
+        let ranked = ResultProjection::new(true, Some(&fields))
+            .unwrap()
+            .items(vec![result.clone()]);
+        assert_eq!(
+            serde_json::to_value(&ranked).unwrap(),
+            serde_json::json!([{"score": 100.0, "confidence": "high"}])
+        );
+
+        // Owners come from the CODEOWNERS rules saved at index time
+        let code_owners =
+            CodeOwners::parse("/src/ @alice", std::path::Path::new("/repo"), "CODEOWNERS");
+        let fields = ["owners".to_string()];
+        let owned = ResultProjection::new(true, Some(&fields))
+            .unwrap()
+            .with_owners(Some(Arc::new(code_owners)));
+        assert_eq!(json(owned), serde_json::json!({"owners": ["@alice"]}));
+
+        // Vendored files are flagged relative to the project root
+        let fields = ["path".to_string(), "vendored".to_string()];
+        let vendored = ResultProjection::new(true, Some(&fields))
+            .unwrap()
+            .with_provenance(None, std::path::Path::new("/repo"))
+            .item(
+                crate::vectordb::SearchResult {
+                    path: "/repo/third_party/zlib/inflate.c".to_string(),
+                    ..result.clone()
+                },
+                None,
+            );
+        assert_eq!(vendored.vendored, Some(true));
+
+        let unknown = ["file".to_string()];
+        assert!(ResultProjection::new(true, Some(&unknown)).is_err());
+    }
+
+    #[test]
+    fn test_merge_project_results() {
+        let result = |path: &str, score: f32| crate::vectordb::SearchResult {
+            id: 1,
+            content: String::new(),
+            path: path.to_string(),
+            start_line: 1,
+            end_line: 2,
+            kind: "Function".to_string(),
+            signature: None,
+            docstring: None,
+            context: None,
+            hash: String::new(),
+            distance: 0.1,
+            score,
+            context_prev: None,
+            context_next: None,
+            text_match: false,
+        };
+        // Ranked by position in each project, not by their raw scores
+        let merged = merge_project_results(
+            vec![
+                vec![result("api/a.rs", 0.02), result("api/b.rs", 0.01)],
+                Vec::new(),
+                vec![result("web/c.ts", 0.05), result("web/d.ts", 0.04)],
+            ],
+            3,
+        );
+        let ranked: Vec<(usize, &str)> = merged
+            .iter()
+            .map(|(origin, r)| (*origin, r.path.as_str()))
+            .collect();
+        assert_eq!(ranked, [(0, "api/a.rs"), (2, "web/c.ts"), (0, "api/b.rs")]);
+
+        assert!(merge_project_results(vec![Vec::new()], 10).is_empty());
+    }
 }
 
+mod http;
+mod instructions;
+mod session;
 pub mod types;
+pub mod usage;
 
 use anyhow::Result;
 use rmcp::{
     handler::server::router::tool::ToolRouter,
     handler::server::wrapper::Parameters,
     model::{CallToolResult, Content, ServerCapabilities, ServerInfo},
-    tool, tool_handler, tool_router, ErrorData as McpError, ServerHandler,
+    tool, tool_router, ErrorData as McpError, ServerHandler,
 };
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, RwLock};
 use tokio_util::sync::CancellationToken;
 
-use crate::db_discovery::{find_best_database, find_databases};
-use crate::embed::{EmbeddingService, ModelType};
-use crate::file::Language;
+use crate::cache::{normalize_path, FileMetaStore, IndexMetadata};
+use crate::db_discovery::{find_best_database, find_databases, missing_components};
+use crate::embed::{backoff_delay, EmbeddingService, ModelLoadState, ModelType};
+use crate::file::{is_vendored_path, CodeOwners};
 use crate::fts::FtsStore;
 use crate::index::{IndexManager, SharedStores};
 use crate::rerank::{rrf_fusion, rrf_fusion_with_exact, EXACT_MATCH_RRF_K};
-use crate::search::{adapt_rrf_k, boost_kind, detect_identifiers, detect_structural_intent};
+use crate::search::{
+    adapt_rrf_k, apply_corrections, apply_min_score, boost_kind, boost_language_intent,
+    boost_languages, correct_identifiers, detect_identifiers, detect_structural_intent,
+    expand_acronyms, expand_context, find_definition, find_references, implementation_for,
+    language_intent, merge_adjacent_results, no_confident_matches, penalize_hard_negatives,
+    rewrite_question, route_query, score_confidence, search_fts_query_forms, search_query_forms,
+    tests_for, thresholding_requested, uncommitted_chunk_paths, Confidence, EmbeddingSpace,
+    LanguageIntent, NoConfidentMatches, PathFilter, SpellingCorrection,
+};
 use crate::vectordb::VectorStore;
+use instructions::InstructionsStyle;
+use session::SessionContext;
+use usage::{slow_query_ms, ToolCall, ToolUsage};
 
 // Re-export types
 pub use types::*;
 
-/// Codesearch MCP service
-pub struct CodesearchService {
-    tool_router: ToolRouter<CodesearchService>,
+/// Classify an error into a machine-readable kind for tool results
+fn classify_error(err: &anyhow::Error) -> ToolErrorKind {
+    let msg = format!("{:#}", err).to_lowercase();
+    if msg.contains("dimension mismatch") {
+        ToolErrorKind::DimensionMismatch
+    } else if msg.contains("locked")
+        || msg.contains("lockbusy")
+        || msg.contains("lockfile")
+        || msg.contains("resource temporarily unavailable")
+        || msg.contains("access is denied")
+    {
+        ToolErrorKind::Locked
+    } else if msg.contains("index not built")
+        || msg.contains("does not exist")
+        || msg.contains("database not found")
+        || msg.contains("no database found")
+    {
+        ToolErrorKind::NotIndexed
+    } else if err
+        .chain()
+        .any(|c| c.downcast_ref::<std::io::Error>().is_some())
+        || msg.contains("mdb_")
+        || msg.contains("i/o error")
+        || msg.contains("io error")
+    {
+        ToolErrorKind::Io
+    } else {
+        ToolErrorKind::Internal
+    }
+}
+
+/// Default hint shown to agents for each error kind
+fn error_hint(kind: ToolErrorKind) -> Option<&'static str> {
+    match kind {
+        ToolErrorKind::NotIndexed => Some(
+            "Ask the user to run 'codesearch index' in the project root, or call find_databases() to locate an existing index.",
+        ),
+        ToolErrorKind::Locked => {
+            Some("Another codesearch process holds the database. Retry shortly.")
+        }
+        ToolErrorKind::DimensionMismatch => Some(
+            "The index was built with a different embedding model. Re-index with 'codesearch index --force'.",
+        ),
+        ToolErrorKind::Io => Some(
+            "The database may be corrupted. Try re-indexing with 'codesearch index --force'.",
+        ),
+        ToolErrorKind::ModelUnavailable => {
+            Some("The embedding model could not be loaded. Check network access and retry.")
+        }
+        ToolErrorKind::InvalidRequest | ToolErrorKind::Internal => None,
+    }
+}
+
+/// Build a failed tool result (`isError: true`) with a structured error payload
+fn tool_error(kind: ToolErrorKind, message: impl Into<String>) -> CallToolResult {
+    let response = ToolErrorResponse {
+        error: kind,
+        message: message.into(),
+        hint: error_hint(kind).map(str::to_string),
+    };
+    match serde_json::to_value(&response) {
+        Ok(value) => CallToolResult::structured_error(value),
+        Err(_) => CallToolResult::error(vec![Content::text(response.message)]),
+    }
+}
+
+/// Build a failed tool result from an error, classifying its kind
+fn tool_error_from(context: &str, err: &anyhow::Error) -> CallToolResult {
+    tool_error(classify_error(err), format!("{}: {}", context, err))
+}
+
+/// Fields of a search result item, in response order
+const RESULT_FIELDS: [&str; 14] = [
+    "chunk_id",
+    "path",
+    "start_line",
+    "end_line",
+    "kind",
+    "score",
+    "confidence",
+    "signature",
+    "owners",
+    "license",
+    "vendored",
+    "content",
+    "context_prev",
+    "context_next",
+];
+
+/// Fields a compact result leaves out
+const FULL_ONLY_FIELDS: [&str; 3] = ["content", "context_prev", "context_next"];
+
+/// The result fields a request asked for, with `compact` or `fields`
+#[derive(Debug, Clone)]
+struct ResultProjection {
+    fields: Vec<&'static str>,
+    /// CODEOWNERS rules for the `owners` field
+    code_owners: Option<Arc<CodeOwners>>,
+    /// File metadata for the `license` field
+    file_meta: Option<Arc<FileMetaStore>>,
+    /// Project root the `vendored` field is judged relative to
+    project_root: String,
+}
+
+impl ResultProjection {
+    /// `fields`, when given, overrides `compact`
+    fn new(compact: bool, fields: Option<&[String]>) -> Result<Self, CallToolResult> {
+        let Some(requested) = fields else {
+            let fields = RESULT_FIELDS
+                .into_iter()
+                .filter(|f| !compact || !FULL_ONLY_FIELDS.contains(f))
+                .collect();
+            return Ok(Self {
+                fields,
+                code_owners: None,
+                file_meta: None,
+                project_root: String::new(),
+            });
+        };
+        if let Some(unknown) = requested
+            .iter()
+            .find(|f| !RESULT_FIELDS.contains(&f.as_str()))
+        {
+            return Err(tool_error(
+                ToolErrorKind::InvalidRequest,
+                format!(
+                    "Unknown result field '{}'; available: {}",
+                    unknown,
+                    RESULT_FIELDS.join(", ")
+                ),
+            ));
+        }
+        let fields = RESULT_FIELDS
+            .into_iter()
+            .filter(|f| requested.iter().any(|r| r == f))
+            .collect();
+        Ok(Self {
+            fields,
+            code_owners: None,
+            file_meta: None,
+            project_root: String::new(),
+        })
+    }
+
+    /// Fill the `owners` field from these CODEOWNERS rules
+    fn with_owners(mut self, code_owners: Option<Arc<CodeOwners>>) -> Self {
+        self.code_owners = code_owners;
+        self
+    }
+
+    /// Fill the `license` and `vendored` fields for the project at `project_root`
+    fn with_provenance(
+        mut self,
+        file_meta: Option<Arc<FileMetaStore>>,
+        project_root: &Path,
+    ) -> Self {
+        self.file_meta = file_meta;
+        self.project_root = project_root.to_string_lossy().into_owned();
+        self
+    }
+
+    fn has(&self, field: &str) -> bool {
+        self.fields.contains(&field)
+    }
+
+    /// Convert ranked search results to the response format, scored 0-100
+    fn items(&self, results: Vec<crate::vectordb::SearchResult>) -> Vec<SearchResultItem> {
+        score_confidence(&results)
+            .into_iter()
+            .zip(results)
+            .map(|(scored, r)| self.item(r, Some(scored)))
+            .collect()
+    }
+
+    /// `scored` is the normalized score and confidence; unranked items have none
+    fn item(
+        &self,
+        r: crate::vectordb::SearchResult,
+        scored: Option<(f32, Confidence)>,
+    ) -> SearchResultItem {
+        let owners = self
+            .code_owners
+            .as_ref()
+            .filter(|_| self.has("owners"))
+            .map(|code_owners| code_owners.owners_of(&r.path).to_vec())
+            .filter(|owners| !owners.is_empty());
+        let license = self
+            .file_meta
+            .as_ref()
+            .filter(|_| self.has("license"))
+            .and_then(|file_meta| file_meta.license_of(&r.path))
+            .map(str::to_string);
+        let vendored =
+            (self.has("vendored") && is_vendored_path(&r.path, &self.project_root)).then_some(true);
+        SearchResultItem {
+            chunk_id: self.has("chunk_id").then_some(r.id),
+            path: self.has("path").then_some(r.path),
+            start_line: self.has("start_line").then_some(r.start_line),
+            end_line: self.has("end_line").then_some(r.end_line),
+            kind: self.has("kind").then_some(r.kind),
+            score: scored.map(|(score, _)| score).filter(|_| self.has("score")),
+            confidence: scored
+                .map(|(_, confidence)| confidence)
+                .filter(|_| self.has("confidence")),
+            signature: r.signature.filter(|_| self.has("signature")),
+            owners,
+            license,
+            vendored,
+            content: self.has("content").then_some(r.content),
+            context_prev: r.context_prev.filter(|_| self.has("context_prev")),
+            context_next: r.context_next.filter(|_| self.has("context_next")),
+        }
+    }
+}
+
+/// Results worth returning for `query`, or how to rephrase it if none are
+///
+/// Only a positive `min_score` drops results; see [`apply_min_score`].
+fn thresholded(
+    query: &str,
+    results: Vec<crate::vectordb::SearchResult>,
+    min_score: Option<f32>,
+) -> Result<Vec<crate::vectordb::SearchResult>, NoConfidentMatches> {
+    if thresholding_requested(min_score) {
+        if let Some(guidance) = no_confident_matches(query, &results) {
+            return Err(guidance);
+        }
+    }
+    Ok(apply_min_score(results, min_score))
+}
+
+/// Report spelling corrections in the result's `_meta`, with a note for the agent
+fn with_corrections(
+    mut result: CallToolResult,
+    corrections: &[SpellingCorrection],
+) -> CallToolResult {
+    if corrections.is_empty() {
+        return result;
+    }
+    let notes: Vec<String> = corrections
+        .iter()
+        .map(|c| {
+            if c.applied {
+                format!("Searched for '{}' instead of '{}'.", c.to, c.from)
+            } else {
+                format!(
+                    "No matches for '{}'; did you mean '{}'? Pass auto_correct=true to search for it.",
+                    c.from, c.to
+                )
+            }
+        })
+        .collect();
+    result.content.push(Content::text(notes.join(" ")));
+    let mut meta = rmcp::model::Meta::new();
+    meta.insert(
+        "corrections".to_string(),
+        serde_json::to_value(corrections).unwrap_or_default(),
+    );
+    result.meta = Some(meta);
+    result
+}
+
+/// Report the languages inferred from the query in the result's `_meta`, with a note
+fn with_language_intent(
+    mut result: CallToolResult,
+    intent: Option<LanguageIntent>,
+) -> CallToolResult {
+    let Some(intent) = intent else {
+        return result;
+    };
+    result.content.push(Content::text(format!(
+        "Results in {} were ranked first.",
+        intent.describe()
+    )));
+    result
+        .meta
+        .get_or_insert_with(rmcp::model::Meta::new)
+        .insert(
+            "language_intent".to_string(),
+            serde_json::to_value(&intent).unwrap_or_default(),
+        );
+    result
+}
+
+/// Message of a failed tool result, to report it inside another result
+fn tool_error_message(result: &CallToolResult) -> String {
+    result
+        .structured_content
+        .as_ref()
+        .and_then(|value| value["message"].as_str())
+        .unwrap_or("search failed")
+        .to_string()
+}
+
+/// Merge the ranked results of several projects into one ranking of at most
+/// `limit`, each with the index of its project
+///
+/// Scores of different databases aren't comparable: each comes from fusing
+/// that index's own vector and full-text lists. The projects' rankings are
+/// fused by rank instead (RRF over per-project ranks); ties keep project order.
+fn merge_project_results(
+    per_project: Vec<Vec<crate::vectordb::SearchResult>>,
+    limit: usize,
+) -> Vec<(usize, crate::vectordb::SearchResult)> {
+    let mut merged: Vec<(f32, usize, crate::vectordb::SearchResult)> = per_project
+        .into_iter()
+        .enumerate()
+        .flat_map(|(origin, results)| {
+            results.into_iter().enumerate().map(move |(rank, r)| {
+                (
+                    1.0 / (crate::rerank::DEFAULT_RRF_K + rank as f32 + 1.0),
+                    origin,
+                    r,
+                )
+            })
+        })
+        .collect();
+    merged.sort_by(|(a, _, _), (b, _, _)| b.total_cmp(a));
+    merged.truncate(limit);
+    merged
+        .into_iter()
+        .map(|(_, origin, r)| (origin, r))
+        .collect()
+}
+
+/// Embedding of a query, and of its question rewrite if it has one
+type QueryEmbeddings = (Vec<f32>, Option<Vec<f32>>);
+
+/// Queries per `batch_search` call
+const MAX_BATCH_QUERIES: usize = 20;
+
+/// Filters shared by the queries of a search request
+struct SearchScope {
+    path_filter: Option<PathFilter>,
+    /// Chunk paths of the files with uncommitted changes (`changed_only`)
+    changed_files: Option<Vec<String>>,
+    /// Leave out files in vendored directories (`project_only`)
+    project_only: bool,
+}
+
+/// A database the MCP service searches, with the embedding models that go with it
+struct ActiveDatabase {
     db_path: PathBuf,
     project_path: PathBuf,
     model_type: ModelType,
     dimensions: usize,
     // Lazily initialized on first search
     embedding_service: Mutex<Option<EmbeddingService>>,
+    // Load state of the embedding model, readable while a load is in progress
+    model_state: Mutex<ModelLoadState>,
+    // Prose model of a dual-embedded index (None for single-model indexes)
+    prose_model: Option<ModelType>,
+    // Lazily initialized on the first natural-language query
+    prose_embedding_service: Mutex<Option<EmbeddingService>>,
+    // Set after the prose model fails to load; queries then use the code space only
+    prose_disabled: std::sync::atomic::AtomicBool,
     // Shared stores for concurrent access (optional - only set when running with IndexManager)
     shared_stores: Option<Arc<SharedStores>>,
+    // Recent queries of the session and the directories they led to
+    session: Mutex<SessionContext>,
+}
+
+/// Codesearch MCP service
+pub struct CodesearchService {
+    tool_router: ToolRouter<CodesearchService>,
+    // Database the tools search; `use_database` switches it for the session
+    active: RwLock<Arc<ActiveDatabase>>,
+    // Database the server started with, whose stores the IndexManager keeps fresh
+    startup: Arc<ActiveDatabase>,
+    // Databases search_all covers besides the startup one (`--multi-repo`);
+    // None hides the tool
+    federated: Option<Arc<Vec<Arc<ActiveDatabase>>>>,
 }
 
 impl std::fmt::Debug for CodesearchService {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let db = self.db();
         f.debug_struct("CodesearchService")
-            .field("db_path", &self.db_path)
-            .field("model_type", &self.model_type)
-            .field("dimensions", &self.dimensions)
-            .field("has_shared_stores", &self.shared_stores.is_some())
+            .field("db_path", &db.db_path)
+            .field("model_type", &db.model_type)
+            .field("dimensions", &db.dimensions)
+            .field("has_shared_stores", &db.shared_stores.is_some())
             .finish()
     }
 }
 
-// === Tool Router Implementation ===
-
-#[tool_router]
-impl CodesearchService {
-    /// Create a new CodesearchService (standalone mode - opens its own VectorStore)
-    #[allow(dead_code)] // Reserved for standalone MCP server mode
-    pub fn new(requested_path: Option<PathBuf>) -> Result<Self> {
-        Self::new_with_stores(requested_path, None)
-    }
-
-    /// Create a new CodesearchService with shared stores (for use with IndexManager)
-    pub fn new_with_stores(
-        requested_path: Option<PathBuf>,
+impl ActiveDatabase {
+    /// Read the model(s) of a database from its metadata.json
+    fn open(
+        db_path: PathBuf,
+        project_path: PathBuf,
         shared_stores: Option<Arc<SharedStores>>,
     ) -> Result<Self> {
-        // Find the best database to use
-        let db_info = find_best_database(requested_path.as_deref())?;
-
-        if db_info.is_none() {
-            return Err(anyhow::anyhow!(
-                "No database found in current directory, parent directories, or globally tracked repositories. \
-                 Run 'codesearch index' first to index the codebase."
-            ));
-        }
-
-        let db_info = db_info.unwrap();
-        let db_path = db_info.db_path;
-        let project_path = db_info.project_path;
-
         // Read model metadata from database
-        let metadata_path = db_path.join("metadata.json");
-        let (model_type, dimensions) = if metadata_path.exists() {
-            let content = std::fs::read_to_string(&metadata_path)?;
-            let json: serde_json::Value = serde_json::from_str(&content)?;
-            let model_name = json
-                .get("model_short_name")
-                .and_then(|v| v.as_str())
-                .unwrap_or("minilm-l6");
-            let dims = json
-                .get("dimensions")
-                .and_then(|v| v.as_u64())
-                .unwrap_or(384) as usize;
-            let mt = ModelType::parse(model_name).unwrap_or_default();
-            (mt, dims)
-        } else {
-            (ModelType::default(), 384)
+        let metadata = IndexMetadata::load(&db_path)?;
+        let (model_type, dimensions) = match &metadata {
+            Some(metadata) => {
+                let (mt, corrected) = metadata.model_type();
+                if corrected {
+                    tracing::warn!(
+                        "⚠️  Index model '{}' ({} dims) doesn't match a known model; using {} ({} dims)",
+                        metadata.model_short_name,
+                        metadata.dimensions,
+                        mt.short_name(),
+                        mt.dimensions()
+                    );
+                }
+                (mt, metadata.dimensions)
+            }
+            None => {
+                let mt = ModelType::default();
+                (mt, mt.dimensions())
+            }
+        };
+        let prose_model = match metadata.as_ref().and_then(IndexMetadata::prose_model) {
+            Some(Ok(model)) => Some(model),
+            Some(Err(e)) => {
+                tracing::warn!("⚠️  Prose search disabled: {}", e);
+                None
+            }
+            None => None,
         };
 
         Ok(Self {
-            tool_router: Self::tool_router(),
             db_path,
             project_path,
             model_type,
             dimensions,
             embedding_service: Mutex::new(None),
+            model_state: Mutex::new(ModelLoadState::NotLoaded),
+            prose_model,
+            prose_embedding_service: Mutex::new(None),
+            prose_disabled: std::sync::atomic::AtomicBool::new(false),
             shared_stores,
+            session: Mutex::new(SessionContext::default()),
         })
     }
 
     /// Get or initialize the embedding service
-    fn get_embedding_service(&self) -> Result<std::sync::MutexGuard<'_, Option<EmbeddingService>>> {
-        let mut guard = self.embedding_service.lock().unwrap();
-        if guard.is_none() {
-            let cache_dir = crate::constants::get_global_models_cache_dir()?;
-            *guard = Some(EmbeddingService::with_cache_dir(
-                self.model_type,
-                Some(&cache_dir),
-            )?);
+    ///
+    /// Initialization is retried with exponential backoff, without holding the
+    /// service lock: other searches fail fast while the model is loading
+    /// instead of waiting on it. If all attempts fail, the failure is recorded
+    /// and later calls retry once the backoff window has passed, so a transient
+    /// download failure doesn't break the service until restart.
+    async fn get_embedding_service(
+        &self,
+    ) -> Result<std::sync::MutexGuard<'_, Option<EmbeddingService>>> {
+        let lock = || {
+            self.embedding_service
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Mutex poisoned: {}", e))
+        };
+        if lock()?.is_some() {
+            return lock();
         }
-        Ok(guard)
-    }
 
-    /// Check if database exists and return error if not
-    fn ensure_database_exists(&self) -> Result<(), String> {
-        if !self.db_path.exists() {
-            return Err(format!(
-                "❌ No index database found at: {}\n\n\
-                 ⚠️  IMPORTANT: This MCP server cannot index the codebase itself. Indexing takes 30-60 seconds and must be done manually.\n\n\
-                 To fix this, run the following command in your terminal:\n\
-                 $ cd {}\n\
-                 $ codesearch index\n\n\
-                 For more information about database locations, use the find_databases tool.",
-                self.db_path.display(),
-                self.project_path.display()
-            ));
+        let previous_failures = {
+            let mut state = self
+                .model_state
+                .lock()
+                .map_err(|e| anyhow::anyhow!("Mutex poisoned: {}", e))?;
+            let failures = match &*state {
+                ModelLoadState::Failed {
+                    message,
+                    failures,
+                    retry_at,
+                } => {
+                    let now = std::time::Instant::now();
+                    if now < *retry_at {
+                        return Err(anyhow::anyhow!(
+                            "Embedding model failed to load ({}); next retry in {}s",
+                            message,
+                            (*retry_at - now).as_secs() + 1
+                        ));
+                    }
+                    *failures
+                }
+                ModelLoadState::Downloading { attempt } => {
+                    return Err(anyhow::anyhow!(
+                        "Embedding model is still loading (attempt {}); try again shortly",
+                        attempt
+                    ));
+                }
+                ModelLoadState::NotLoaded | ModelLoadState::Ready => 0,
+            };
+            *state = ModelLoadState::Downloading { attempt: 1 };
+            failures
+        };
+
+        let loaded = match crate::constants::get_global_models_cache_dir() {
+            Ok(cache_dir) => {
+                EmbeddingService::with_cache_dir_retry_async(
+                    self.model_type,
+                    cache_dir,
+                    |attempt| self.set_model_load_state(ModelLoadState::Downloading { attempt }),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        }
+        .and_then(|service| {
+            service.check_index_dimensions(self.dimensions)?;
+            Ok(service)
+        });
+        match loaded {
+            Ok(service) => {
+                let mut guard = lock()?;
+                *guard = Some(service);
+                self.set_model_load_state(ModelLoadState::Ready);
+                Ok(guard)
+            }
+            Err(e) => {
+                let failures = previous_failures + 1;
+                let delay = backoff_delay(
+                    std::time::Duration::from_millis(crate::constants::MODEL_LOAD_BASE_BACKOFF_MS),
+                    failures,
+                    std::time::Duration::from_millis(crate::constants::MODEL_LOAD_MAX_BACKOFF_MS),
+                );
+                self.set_model_load_state(ModelLoadState::Failed {
+                    message: e.to_string(),
+                    failures,
+                    retry_at: std::time::Instant::now() + delay,
+                });
+                Err(e)
+            }
         }
-        Ok(())
     }
 
-    #[tool(
-        description = "Search code semantically using natural language. Returns compact metadata by default (path, line numbers, kind, signature, score). Use the read tool with the returned line numbers to view actual code. Set compact=false only when you need full content inline. Use filter_path to narrow results to a specific directory."
-    )]
-    async fn semantic_search(
-        &self,
-        Parameters(request): Parameters<SemanticSearchRequest>,
-    ) -> Result<CallToolResult, McpError> {
-        let limit = request.limit.unwrap_or(10);
-        let compact = request.compact.unwrap_or(true);
-
-        tracing::debug!(
-            "MCP semantic_search: query='{}', limit={}, compact={}",
-            request.query,
-            limit,
-            compact
-        );
+    /// Embed a query, and its declarative rewrite when it is a question
+    ///
+    /// The embedding service lock is released before returning, so callers
+    /// can await afterwards. Errors are returned as failed tool results.
+    async fn embed_query_forms(&self, query: &str) -> Result<QueryEmbeddings, CallToolResult> {
+        tracing::debug!("MCP: Getting embedding service...");
+        let mut service_guard = match self.get_embedding_service().await {
+            Ok(g) => g,
+            Err(e) => {
+                tracing::error!("MCP: Failed to get embedding service: {:?}", e);
+                return Err(tool_error(
+                    ToolErrorKind::ModelUnavailable,
+                    format!("Error initializing embedding service: {}", e),
+                ));
+            }
+        };
 
-        // Ensure database exists
-        if let Err(e) = self.ensure_database_exists() {
-            return Ok(CallToolResult::success(vec![Content::text(e)]));
-        }
+        let service = service_guard.as_mut().unwrap();
+        tracing::debug!("MCP: Embedding query...");
+        let query_embedding = match service.embed_query(query) {
+            Ok(e) => e,
+            Err(e) => {
+                tracing::error!("MCP: Failed to embed query: {:?}", e);
+                return Err(tool_error_from("Error embedding query", &e));
+            }
+        };
+        // Question-style queries also search their declarative rewrite
+        let rewritten_embedding = rewrite_question(query).and_then(|rewritten| {
+            tracing::debug!("MCP: Rewritten query: '{}'", rewritten);
+            service.embed_query(&rewritten).ok()
+        });
+        Ok((query_embedding, rewritten_embedding))
+    }
 
-        // Get embedding service and embed query
-        // Note: We must drop the MutexGuard before any await points
-        tracing::debug!("MCP: Getting embedding service...");
-        let query_embedding = {
-            let mut service_guard = match self.get_embedding_service() {
-                Ok(g) => g,
-                Err(e) => {
-                    tracing::error!("MCP: Failed to get embedding service: {:?}", e);
-                    return Ok(CallToolResult::success(vec![Content::text(format!(
-                        "Error initializing embedding service: {}",
-                        e
-                    ))]));
-                }
-            };
+    /// Embed a query with the prose model of a dual-embedded index
+    ///
+    /// Returns `None` for single-model indexes, or when the prose model can't be
+    /// loaded; the caller then searches the code space only.
+    fn embed_prose_query(&self, query: &str) -> Option<Vec<f32>> {
+        use std::sync::atomic::Ordering;
 
-            let service = service_guard.as_mut().unwrap();
-            tracing::debug!("MCP: Embedding query...");
-            match service.embed_query(&request.query) {
-                Ok(e) => e,
+        let prose_model = self.prose_model?;
+        if self.prose_disabled.load(Ordering::Relaxed) {
+            return None;
+        }
+        let mut guard = self.prose_embedding_service.lock().ok()?;
+        if guard.is_none() {
+            let cache_dir = crate::constants::get_global_models_cache_dir().ok()?;
+            match EmbeddingService::with_cache_dir(prose_model, Some(&cache_dir)) {
+                Ok(service) => *guard = Some(service),
                 Err(e) => {
-                    tracing::error!("MCP: Failed to embed query: {:?}", e);
-                    return Ok(CallToolResult::success(vec![Content::text(format!(
-                        "Error embedding query: {}",
+                    tracing::warn!(
+                        "⚠️  Prose model '{}' failed to load, using code space only: {}",
+                        prose_model.short_name(),
                         e
-                    ))]));
+                    );
+                    self.prose_disabled.store(true, Ordering::Relaxed);
+                    return None;
                 }
             }
-            // service_guard is dropped here, before any await
+        }
+        match guard.as_mut()?.embed_query(query) {
+            Ok(embedding) => Some(embedding),
+            Err(e) => {
+                tracing::warn!("MCP: Failed to embed query with prose model: {:?}", e);
+                None
+            }
+        }
+    }
+
+    /// Current embedding model load state
+    fn model_load_state(&self) -> ModelLoadState {
+        self.model_state
+            .lock()
+            .map(|s| s.clone())
+            .unwrap_or(ModelLoadState::NotLoaded)
+    }
+
+    fn set_model_load_state(&self, state: ModelLoadState) {
+        if let Ok(mut s) = self.model_state.lock() {
+            *s = state;
+        }
+    }
+
+    /// Parse the filters shared by the queries of a search request
+    fn search_scope(
+        &self,
+        filter_path: Option<&str>,
+        changed_only: bool,
+        project_only: bool,
+    ) -> Result<SearchScope, CallToolResult> {
+        let path_filter = filter_path
+            .map(|filter| PathFilter::new(filter, self.project_path.to_str().unwrap_or("")))
+            .transpose()
+            .map_err(|e| tool_error(ToolErrorKind::InvalidRequest, format!("{:#}", e)))?;
+        // changed_only: rank every chunk of the files with uncommitted changes
+        let changed_files = if changed_only {
+            Some(uncommitted_chunk_paths(&self.project_path).ok_or_else(|| {
+                tool_error(
+                    ToolErrorKind::InvalidRequest,
+                    "changed_only needs the project to be in a git work tree",
+                )
+            })?)
+        } else {
+            None
+        };
+        Ok(SearchScope {
+            path_filter,
+            changed_files,
+            project_only,
+        })
+    }
+
+    /// Hybrid search for a query whose code embedding(s) are already computed
+    ///
+    /// Fuses vector and full-text results, then applies the language, kind and
+    /// hard-negative adjustments. Errors are returned as failed tool results.
+    async fn search_embedded(
+        &self,
+        query: &str,
+        query_embedding: &[f32],
+        rewritten_embedding: Option<&[f32]>,
+        limit: usize,
+        scope: &SearchScope,
+    ) -> Result<Vec<crate::vectordb::SearchResult>, CallToolResult> {
+        let rewritten_query = rewrite_question(query);
+
+        // Dual-embedded index: natural-language queries also search the prose space
+        let space = route_query(query);
+        let prose_embedding = if space == EmbeddingSpace::Code {
+            None
+        } else {
+            self.embed_prose_query(query)
+        };
+
+        let search_store = |store: &VectorStore| match &scope.changed_files {
+            Some(files) => store.search_in_files(query_embedding, files, limit * 3),
+            None => search_query_forms(
+                store,
+                query_embedding,
+                rewritten_embedding,
+                prose_embedding.as_deref(),
+                space,
+                limit * 3,
+            ),
         };
 
         // Search using shared stores if available, otherwise open a new store
@@ -249,14 +983,11 @@ impl CodesearchService {
         let vector_results = if let Some(ref stores) = self.shared_stores {
             // Use shared store with read lock
             let store = stores.vector_store.read().await;
-            match store.search(&query_embedding, limit * 3) {
+            match search_store(&store) {
                 Ok(r) => r,
                 Err(e) => {
                     tracing::error!("MCP: Search failed (shared store): {:?}", e);
-                    return Ok(CallToolResult::success(vec![Content::text(format!(
-                        "Error searching: {}",
-                        e
-                    ))]));
+                    return Err(tool_error_from("Error searching", &e));
                 }
             }
         } else {
@@ -266,20 +997,14 @@ impl CodesearchService {
                 Ok(s) => s,
                 Err(e) => {
                     tracing::error!("MCP: Failed to open vector store: {:?}", e);
-                    return Ok(CallToolResult::success(vec![Content::text(format!(
-                        "Error opening database: {}. The database may be corrupted or not indexed yet.",
-                        e
-                    ))]));
+                    return Err(tool_error_from("Error opening database", &e));
                 }
             };
-            match store.search(&query_embedding, limit * 3) {
+            match search_store(&store) {
                 Ok(r) => r,
                 Err(e) => {
                     tracing::error!("MCP: Search failed: {:?}", e);
-                    return Ok(CallToolResult::success(vec![Content::text(format!(
-                        "Error searching: {}",
-                        e
-                    ))]));
+                    return Err(tool_error_from("Error searching", &e));
                 }
             }
         };
@@ -289,9 +1014,9 @@ impl CodesearchService {
         // --- Hybrid search with all improvements ---
 
         // Detect identifiers and structural intent from query
-        let identifiers = detect_identifiers(&request.query);
-        let structural_intent = detect_structural_intent(&request.query);
-        let (vector_k, fts_k) = adapt_rrf_k(&request.query);
+        let identifiers = detect_identifiers(query);
+        let structural_intent = detect_structural_intent(query);
+        let (vector_k, fts_k) = adapt_rrf_k(query);
 
         tracing::debug!(
             "MCP: Query analysis - identifiers: {:?}, structural_intent: {:?}, rrf_k: ({}, {})",
@@ -301,13 +1026,27 @@ impl CodesearchService {
             fts_k
         );
 
+        let project_root = self.project_path.to_string_lossy();
+        let in_filter = |path: &str| {
+            scope
+                .path_filter
+                .as_ref()
+                .is_none_or(|filter| filter.matches(path))
+                && !(scope.project_only && is_vendored_path(path, &project_root))
+        };
+
         // Perform FTS search and fusion
         let mut results = match FtsStore::new(&self.db_path) {
             Ok(fts_store) => {
                 // FTS search
-                let fts_results = fts_store
-                    .search(&request.query, limit * 3, structural_intent)
-                    .unwrap_or_default();
+                let fts_results = search_fts_query_forms(
+                    &fts_store,
+                    query,
+                    rewritten_query.as_deref(),
+                    limit * 3,
+                    structural_intent,
+                )
+                .unwrap_or_default();
 
                 let fused = if identifiers.is_empty() {
                     // No identifiers: standard RRF fusion
@@ -349,11 +1088,19 @@ impl CodesearchService {
                     &crate::vectordb::SearchResult,
                 > = vector_results.iter().map(|r| (r.id, r)).collect();
 
+                // Filter before truncating so filter_path doesn't eat into the limit
                 let mut mapped: Vec<crate::vectordb::SearchResult> = Vec::new();
-                for f in fused.into_iter().take(limit) {
+                for f in fused {
+                    if mapped.len() >= limit {
+                        break;
+                    }
                     if let Some(result) = chunk_to_result.get(&f.chunk_id) {
+                        if !in_filter(&result.path) {
+                            continue;
+                        }
                         let mut r = (*result).clone();
                         r.score = f.rrf_score;
+                        r.text_match = f.fts_rank.is_some();
                         mapped.push(r);
                     }
                 }
@@ -362,194 +1109,1034 @@ impl CodesearchService {
             Err(e) => {
                 // FTS unavailable, fall back to vector-only results
                 tracing::warn!("MCP: FTS store unavailable, using vector-only: {:?}", e);
-                vector_results.into_iter().take(limit).collect()
+                vector_results
+                    .into_iter()
+                    .filter(|r| in_filter(&r.path))
+                    .take(limit)
+                    .collect()
             }
         };
 
-        // Apply language boost (improvement 2)
-        if let Some((_, _, Some(primary_lang))) = crate::search::read_metadata(&self.db_path) {
-            for result in &mut results {
-                let file_lang = format!(
-                    "{:?}",
-                    Language::from_path(std::path::Path::new(&result.path))
-                );
-                if file_lang.to_lowercase() == primary_lang.to_lowercase() {
-                    result.score *= 1.2;
-                }
-            }
-            results.sort_by(|a, b| {
-                b.score
-                    .partial_cmp(&a.score)
-                    .unwrap_or(std::cmp::Ordering::Equal)
-            });
+        // Apply language boost (improvement 2): the languages the query asks
+        // for, or else the project's main languages
+        let metadata = IndexMetadata::load(&self.db_path).ok().flatten();
+        if let Some(intent) = language_intent(query) {
+            boost_language_intent(&mut results, &intent);
+        } else if let Some(metadata) = &metadata {
+            boost_languages(&mut results, metadata);
+        }
+
+        // Apply kind boost (improvement 3)
+        if let Some(target_kind) = structural_intent {
+            boost_kind(&mut results, target_kind);
+        }
+
+        // Demote fixtures, snapshots, golden files and minified assets
+        penalize_hard_negatives(&mut results, query);
+
+        // Context the index didn't store is read from disk before merging
+        if let Some(metadata) = &metadata {
+            expand_context(&mut results, &self.project_path, metadata.context_lines());
+        }
+
+        // One result per run of adjacent matching chunks
+        let results = merge_adjacent_results(results);
+
+        tracing::debug!("MCP: Final {} results after hybrid search", results.len());
+
+        Ok(results)
+    }
+
+    /// Run `f` on the vector store: the shared one under a read lock, or one
+    /// opened for the call in standalone mode
+    async fn with_vector_store<T>(
+        &self,
+        f: impl FnOnce(&VectorStore) -> Result<T> + Send,
+    ) -> Result<T> {
+        if let Some(ref stores) = self.shared_stores {
+            let store = stores.vector_store.read().await;
+            f(&store)
+        } else {
+            f(&VectorStore::new(&self.db_path, self.dimensions)?)
+        }
+    }
+
+    /// File metadata saved when the project was indexed
+    fn file_meta(&self) -> Option<Arc<FileMetaStore>> {
+        FileMetaStore::read(&self.db_path).map(Arc::new)
+    }
+
+    /// CODEOWNERS rules saved when the project was indexed
+    fn code_owners(&self) -> Option<Arc<CodeOwners>> {
+        match CodeOwners::load(&self.db_path) {
+            Ok(code_owners) => code_owners.map(Arc::new),
+            Err(e) => {
+                tracing::warn!("⚠️  {:#}", e);
+                None
+            }
+        }
+    }
+
+    /// Corrections for identifiers in `query` that match nothing in the index
+    async fn spelling_corrections(&self, query: &str) -> Vec<SpellingCorrection> {
+        let db_path = &self.db_path;
+        self.with_vector_store(|store| {
+            let fts = FtsStore::new(db_path)?;
+            Ok(correct_identifiers(query, &fts, store))
+        })
+        .await
+        .unwrap_or_default()
+    }
+
+    /// The query as searched, and the spelling corrections found for it
+    ///
+    /// Misspelled identifiers are replaced by their correction with
+    /// `auto_correct` (and only suggested otherwise), then abbreviations are
+    /// expanded to the symbols they stand for.
+    async fn prepare_query(
+        &self,
+        query: &str,
+        auto_correct: bool,
+    ) -> (String, Vec<SpellingCorrection>) {
+        // Identifiers that match nothing are probably misspelled
+        let mut corrections = self.spelling_corrections(query).await;
+        let query = if auto_correct && !corrections.is_empty() {
+            apply_corrections(query, &mut corrections)
+        } else {
+            query.to_string()
+        };
+        // Developers search by abbreviation: add the symbols they stand for
+        let query = self
+            .with_vector_store(|store| Ok(expand_acronyms(&query, store)))
+            .await
+            .ok()
+            .flatten()
+            .unwrap_or(query);
+        (query, corrections)
+    }
+
+    /// Boost results near what the session viewed before, then remember them
+    fn with_session_context(
+        &self,
+        query: &str,
+        mut results: Vec<crate::vectordb::SearchResult>,
+    ) -> Vec<crate::vectordb::SearchResult> {
+        if let Ok(mut session) = self.session.lock() {
+            session.boost(&mut results);
+            session.record(query, &results);
+        }
+        results
+    }
+
+    /// Check if database exists and return a `not_indexed` tool error if not
+    fn ensure_database_exists(&self) -> Result<(), CallToolResult> {
+        if !self.db_path.exists() {
+            return Err(tool_error(
+                ToolErrorKind::NotIndexed,
+                format!(
+                    "No index database found at: {} (project: {}). \
+                     This MCP server cannot index the codebase itself; run 'codesearch index' in the project root.",
+                    self.db_path.display(),
+                    self.project_path.display()
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+// === Tool Router Implementation ===
+
+#[tool_router]
+impl CodesearchService {
+    /// Create a new CodesearchService (standalone mode - opens its own VectorStore)
+    #[allow(dead_code)] // Reserved for standalone MCP server mode
+    pub fn new(requested_path: Option<PathBuf>) -> Result<Self> {
+        Self::new_with_stores(requested_path, None)
+    }
+
+    /// Create a new CodesearchService with shared stores (for use with IndexManager)
+    pub fn new_with_stores(
+        requested_path: Option<PathBuf>,
+        shared_stores: Option<Arc<SharedStores>>,
+    ) -> Result<Self> {
+        // Find the best database to use
+        let db_info = find_best_database(requested_path.as_deref())?;
+
+        if db_info.is_none() {
+            return Err(anyhow::anyhow!(
+                "No database found in current directory, parent directories, or globally tracked repositories. \
+                 Run 'codesearch index' first to index the codebase."
+            ));
+        }
+
+        let db_info = db_info.unwrap();
+        let db = Arc::new(ActiveDatabase::open(
+            db_info.db_path,
+            db_info.project_path,
+            shared_stores,
+        )?);
+        Ok(Self {
+            tool_router: Self::tool_router(),
+            active: RwLock::new(db.clone()),
+            startup: db,
+            federated: None,
+        })
+    }
+
+    /// Let search_all search these databases too (`--multi-repo`)
+    fn with_federated(mut self, databases: Option<Arc<Vec<Arc<ActiveDatabase>>>>) -> Self {
+        self.federated = databases;
+        self
+    }
+
+    /// The database the tools currently search
+    fn db(&self) -> Arc<ActiveDatabase> {
+        self.active
+            .read()
+            .map(|db| db.clone())
+            .unwrap_or_else(|e| e.into_inner().clone())
+    }
+
+    #[tool(
+        description = "Search code semantically using natural language. Returns compact metadata by default (path, line numbers, kind, signature, score). Use the read tool with the returned line numbers to view actual code. Set compact=false only when you need full content inline. Use filter_path to narrow results to a specific directory, changed_only to search just the files with uncommitted changes, or project_only to leave out vendored third-party code. Pass fields (e.g. [\"path\", \"start_line\", \"signature\"]) to get only the result fields you need."
+    )]
+    async fn semantic_search(
+        &self,
+        Parameters(request): Parameters<SemanticSearchRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let db = self.db();
+        let limit = request.limit.unwrap_or(10);
+        let compact = request.compact.unwrap_or(true);
+        let projection = match ResultProjection::new(compact, request.fields.as_deref()) {
+            Ok(projection) => projection
+                .with_owners(db.code_owners())
+                .with_provenance(db.file_meta(), &db.project_path),
+            Err(e) => return Ok(e),
+        };
+
+        tracing::debug!(
+            "MCP semantic_search: query='{}', limit={}, compact={}",
+            request.query,
+            limit,
+            compact
+        );
+
+        if request.query.trim().is_empty() {
+            return Ok(tool_error(
+                ToolErrorKind::InvalidRequest,
+                "Query must not be empty",
+            ));
+        }
+
+        let scope = match db.search_scope(
+            request.filter_path.as_deref(),
+            request.changed_only.unwrap_or(false),
+            request.project_only.unwrap_or(false),
+        ) {
+            Ok(scope) => scope,
+            Err(e) => return Ok(e),
+        };
+
+        // Ensure database exists
+        if let Err(e) = db.ensure_database_exists() {
+            return Ok(e);
+        }
+
+        let (query, corrections) = db
+            .prepare_query(&request.query, request.auto_correct.unwrap_or(false))
+            .await;
+
+        let (query_embedding, rewritten_embedding) = match db.embed_query_forms(&query).await {
+            Ok(embeddings) => embeddings,
+            Err(e) => return Ok(e),
+        };
+
+        let results = match db
+            .search_embedded(
+                &query,
+                &query_embedding,
+                rewritten_embedding.as_deref(),
+                limit,
+                &scope,
+            )
+            .await
+        {
+            Ok(results) => db.with_session_context(&query, results),
+            Err(e) => return Ok(e),
+        };
+
+        if results.is_empty() {
+            return Ok(with_corrections(
+                CallToolResult::success(vec![Content::text(
+                    "No results found for the query. Try rephrasing your query or using broader terms.",
+                )]),
+                &corrections,
+            ));
+        }
+
+        let results = match thresholded(&query, results, request.min_score) {
+            Ok(results) => results,
+            Err(guidance) => {
+                let response = NoConfidentMatchesResponse {
+                    status: "no_confident_matches",
+                    message: "No result matches the query confidently. Look up the identifiers \
+                              with find_references, or search again with a reformulation.",
+                    guidance,
+                };
+                let json = serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string());
+                return Ok(with_corrections(
+                    CallToolResult::success(vec![Content::text(json)]),
+                    &corrections,
+                ));
+            }
+        };
+        let items = projection.items(results);
+
+        let json = serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string());
+        Ok(with_language_intent(
+            with_corrections(
+                CallToolResult::success(vec![Content::text(json)]),
+                &corrections,
+            ),
+            language_intent(&query),
+        ))
+    }
+
+    #[tool(
+        description = "Run several semantic searches in one call. Use this when a task breaks down into multiple sub-queries (e.g. \"where are requests authenticated?\", \"how are sessions stored?\"): all queries are embedded in one batch and results come back grouped per query, in the same compact format as semantic_search. Each query gets semantic_search's spelling corrections (in \"corrections\") and abbreviation expansion. filter_path, changed_only and project_only apply to every query."
+    )]
+    async fn batch_search(
+        &self,
+        Parameters(request): Parameters<BatchSearchRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let db = self.db();
+        let limit = request.limit.unwrap_or(10);
+        let compact = request.compact.unwrap_or(true);
+        let projection = match ResultProjection::new(compact, request.fields.as_deref()) {
+            Ok(projection) => projection
+                .with_owners(db.code_owners())
+                .with_provenance(db.file_meta(), &db.project_path),
+            Err(e) => return Ok(e),
+        };
+
+        tracing::debug!(
+            "MCP batch_search: {} queries, limit={}, compact={}",
+            request.queries.len(),
+            limit,
+            compact
+        );
+
+        if request.queries.is_empty() || request.queries.iter().any(|q| q.trim().is_empty()) {
+            return Ok(tool_error(
+                ToolErrorKind::InvalidRequest,
+                "Queries must be a non-empty list of non-empty strings",
+            ));
+        }
+        if request.queries.len() > MAX_BATCH_QUERIES {
+            return Ok(tool_error(
+                ToolErrorKind::InvalidRequest,
+                format!(
+                    "At most {} queries per batch_search call ({} given)",
+                    MAX_BATCH_QUERIES,
+                    request.queries.len()
+                ),
+            ));
+        }
+
+        let scope = match db.search_scope(
+            request.filter_path.as_deref(),
+            request.changed_only.unwrap_or(false),
+            request.project_only.unwrap_or(false),
+        ) {
+            Ok(scope) => scope,
+            Err(e) => return Ok(e),
+        };
+
+        // Ensure database exists
+        if let Err(e) = db.ensure_database_exists() {
+            return Ok(e);
+        }
+
+        // Corrected and expanded as in semantic_search
+        let mut prepared = Vec::with_capacity(request.queries.len());
+        for query in &request.queries {
+            prepared.push(
+                db.prepare_query(query, request.auto_correct.unwrap_or(false))
+                    .await,
+            );
+        }
+
+        // Queries and their question rewrites, embedded in one batch
+        let rewritten: Vec<Option<String>> =
+            prepared.iter().map(|(q, _)| rewrite_question(q)).collect();
+        let texts: Vec<String> = prepared
+            .iter()
+            .map(|(q, _)| q.clone())
+            .chain(rewritten.iter().flatten().cloned())
+            .collect();
+        let mut embeddings = {
+            let mut service_guard = match db.get_embedding_service().await {
+                Ok(g) => g,
+                Err(e) => {
+                    tracing::error!("MCP: Failed to get embedding service: {:?}", e);
+                    return Ok(tool_error(
+                        ToolErrorKind::ModelUnavailable,
+                        format!("Error initializing embedding service: {}", e),
+                    ));
+                }
+            };
+            match service_guard.as_mut().unwrap().embed_queries_batch(&texts) {
+                Ok(embeddings) => embeddings.into_iter(),
+                Err(e) => {
+                    tracing::error!("MCP: Failed to embed queries: {:?}", e);
+                    return Ok(tool_error_from("Error embedding queries", &e));
+                }
+            }
+            // service_guard is dropped here, before any await
+        };
+        let query_embeddings: Vec<Vec<f32>> =
+            embeddings.by_ref().take(request.queries.len()).collect();
+
+        let mut groups = Vec::with_capacity(request.queries.len());
+        for (((requested, (query, corrections)), query_embedding), rewritten) in request
+            .queries
+            .iter()
+            .zip(prepared)
+            .zip(&query_embeddings)
+            .zip(&rewritten)
+        {
+            let rewritten_embedding = rewritten.as_ref().and_then(|_| embeddings.next());
+            let results = match db
+                .search_embedded(
+                    &query,
+                    query_embedding,
+                    rewritten_embedding.as_deref(),
+                    limit,
+                    &scope,
+                )
+                .await
+            {
+                Ok(results) => db.with_session_context(&query, results),
+                Err(e) => return Ok(e),
+            };
+            let (results, no_confident_matches) =
+                match thresholded(&query, results, request.min_score) {
+                    Ok(results) => (projection.items(results), None),
+                    Err(guidance) => (Vec::new(), Some(guidance)),
+                };
+            groups.push(BatchSearchGroup {
+                query: requested.clone(),
+                results,
+                language_intent: language_intent(&query),
+                corrections,
+                no_confident_matches,
+            });
+        }
+
+        let json = serde_json::to_string(&groups).unwrap_or_else(|_| "[]".to_string());
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(
+        description = "Search every project this server opened (started with --multi-repo) with one query. Each project is searched like semantic_search, then the results are merged into one ranking and tagged with the project_root they come from. Use this when the code you need may live in another repository than the current one."
+    )]
+    async fn search_all(
+        &self,
+        Parameters(request): Parameters<SearchAllRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let Some(federated) = &self.federated else {
+            return Ok(tool_error(
+                ToolErrorKind::InvalidRequest,
+                "search_all needs the server to be started with 'codesearch mcp --multi-repo'",
+            ));
+        };
+        let limit = request.limit.unwrap_or(10);
+        let compact = request.compact.unwrap_or(true);
+        let projection = match ResultProjection::new(compact, request.fields.as_deref()) {
+            Ok(projection) => projection,
+            Err(e) => return Ok(e),
+        };
+
+        tracing::debug!(
+            "MCP search_all: query='{}', limit={}, {} projects",
+            request.query,
+            limit,
+            federated.len() + 1
+        );
+
+        if request.query.trim().is_empty() {
+            return Ok(tool_error(
+                ToolErrorKind::InvalidRequest,
+                "Query must not be empty",
+            ));
+        }
+
+        let databases: Vec<Arc<ActiveDatabase>> = std::iter::once(self.startup.clone())
+            .chain(federated.iter().cloned())
+            .collect();
+        // The query is embedded once per model, with the first database using it
+        let mut embeddings: Vec<(ModelType, Result<QueryEmbeddings, String>)> = Vec::new();
+        let mut per_project = Vec::with_capacity(databases.len());
+        let mut skipped = Vec::new();
+        for db in &databases {
+            let project_root = db.project_path.display().to_string();
+            let scope = match db.search_scope(
+                request.filter_path.as_deref(),
+                false,
+                request.project_only.unwrap_or(false),
+            ) {
+                Ok(scope) => scope,
+                Err(e) => return Ok(e),
+            };
+            if let Err(e) = db.ensure_database_exists() {
+                skipped.push(SkippedProject {
+                    project_root,
+                    reason: tool_error_message(&e),
+                });
+                continue;
+            }
+
+            let model = match embeddings
+                .iter()
+                .position(|(model, _)| *model == db.model_type)
+            {
+                Some(model) => model,
+                None => {
+                    let embedded = db
+                        .embed_query_forms(&request.query)
+                        .await
+                        .map_err(|e| tool_error_message(&e));
+                    embeddings.push((db.model_type, embedded));
+                    embeddings.len() - 1
+                }
+            };
+            let (query_embedding, rewritten_embedding) = match &embeddings[model].1 {
+                Ok(embedded) => embedded,
+                Err(reason) => {
+                    skipped.push(SkippedProject {
+                        project_root,
+                        reason: reason.clone(),
+                    });
+                    continue;
+                }
+            };
+
+            let results = match db
+                .search_embedded(
+                    &request.query,
+                    query_embedding,
+                    rewritten_embedding.as_deref(),
+                    limit,
+                    &scope,
+                )
+                .await
+            {
+                Ok(results) => results,
+                Err(e) => {
+                    skipped.push(SkippedProject {
+                        project_root,
+                        reason: tool_error_message(&e),
+                    });
+                    continue;
+                }
+            };
+            // Each project keeps only the results that match it confidently
+            let results =
+                thresholded(&request.query, results, request.min_score).unwrap_or_default();
+            per_project.push((db.clone(), results));
+        }
+
+        let projects: Vec<String> = per_project
+            .iter()
+            .map(|(db, _)| db.project_path.display().to_string())
+            .collect();
+        let projections: Vec<ResultProjection> = per_project
+            .iter()
+            .map(|(db, _)| {
+                projection
+                    .clone()
+                    .with_owners(db.code_owners())
+                    .with_provenance(db.file_meta(), &db.project_path)
+            })
+            .collect();
+        let (origins, results): (Vec<usize>, Vec<crate::vectordb::SearchResult>) =
+            merge_project_results(
+                per_project
+                    .into_iter()
+                    .map(|(_, results)| results)
+                    .collect(),
+                limit,
+            )
+            .into_iter()
+            .unzip();
+        let results = score_confidence(&results)
+            .into_iter()
+            .zip(results)
+            .zip(origins)
+            .map(|((scored, r), origin)| ProjectResultItem {
+                project_root: projects[origin].clone(),
+                result: projections[origin].item(r, Some(scored)),
+            })
+            .collect();
+
+        let response = SearchAllResponse {
+            results,
+            projects,
+            skipped,
+        };
+        let json = serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(
+        description = "Find code similar to a chunk already found by semantic_search or batch_search (\"show me code like this function\"). Pass the result's chunk_id. Uses the chunk's stored embedding, so it is cheaper than a new query. Returns results in the same format as semantic_search."
+    )]
+    async fn related_chunks(
+        &self,
+        Parameters(request): Parameters<RelatedChunksRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let db = self.db();
+        let limit = request.limit.unwrap_or(10);
+        let compact = request.compact.unwrap_or(true);
+        let projection = match ResultProjection::new(compact, request.fields.as_deref()) {
+            Ok(projection) => projection
+                .with_owners(db.code_owners())
+                .with_provenance(db.file_meta(), &db.project_path),
+            Err(e) => return Ok(e),
+        };
+
+        tracing::debug!(
+            "MCP related_chunks: chunk_id={}, limit={}",
+            request.chunk_id,
+            limit
+        );
+
+        // Ensure database exists
+        if let Err(e) = db.ensure_database_exists() {
+            return Ok(e);
+        }
+
+        let related = match db
+            .with_vector_store(|store| store.related_chunks(request.chunk_id, limit))
+            .await
+        {
+            Ok(Some(related)) => {
+                db.with_session_context(&format!("related_chunks({})", request.chunk_id), related)
+            }
+            Ok(None) => {
+                return Ok(tool_error(
+                    ToolErrorKind::InvalidRequest,
+                    format!(
+                        "No chunk with id {}; the index may have changed since it was returned",
+                        request.chunk_id
+                    ),
+                ))
+            }
+            Err(e) => return Ok(tool_error_from("Error finding related chunks", &e)),
+        };
+
+        let json =
+            serde_json::to_string(&projection.items(related)).unwrap_or_else(|_| "[]".to_string());
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(
+        description = "Get the chunks just before and after a chunk in its file (by line range), to walk a file chunk by chunk without reading it whole, e.g. when a function's context spans the definitions around it. Pass a chunk_id from any search result; call again with a returned chunk_id to keep walking. Includes content by default."
+    )]
+    async fn neighbors(
+        &self,
+        Parameters(request): Parameters<NeighborsRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let db = self.db();
+        let compact = request.compact.unwrap_or(false);
+        let projection = match ResultProjection::new(compact, None) {
+            Ok(projection) => projection
+                .with_owners(db.code_owners())
+                .with_provenance(db.file_meta(), &db.project_path),
+            Err(e) => return Ok(e),
+        };
+
+        tracing::debug!("MCP neighbors: chunk_id={}", request.chunk_id);
+
+        // Ensure database exists
+        if let Err(e) = db.ensure_database_exists() {
+            return Ok(e);
+        }
+
+        let neighbors = match db
+            .with_vector_store(|store| store.chunk_neighbors(request.chunk_id))
+            .await
+        {
+            Ok(Some(neighbors)) => neighbors,
+            Ok(None) => {
+                return Ok(tool_error(
+                    ToolErrorKind::InvalidRequest,
+                    format!(
+                        "No chunk with id {}; the index may have changed since it was returned",
+                        request.chunk_id
+                    ),
+                ))
+            }
+            Err(e) => return Ok(tool_error_from("Error reading neighboring chunks", &e)),
+        };
+
+        // Walking a file keeps the session in its directory
+        if let Some(chunk) = neighbors.previous.as_ref().or(neighbors.next.as_ref()) {
+            if let Ok(mut session) = db.session.lock() {
+                session.record(
+                    &format!("neighbors({})", request.chunk_id),
+                    std::slice::from_ref(chunk),
+                );
+            }
+        }
+        let response = NeighborsResponse {
+            previous: neighbors.previous.map(|r| projection.item(r, None)),
+            next: neighbors.next.map(|r| projection.item(r, None)),
+        };
+        let json = serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(
+        description = "List the indexed chunks of a file (kind, signature, start/end lines) ordered by line number: a table of contents of the file without reading it. Use it to see what a file defines, then read only the lines you need or pass a chunk_id to neighbors."
+    )]
+    async fn file_outline(
+        &self,
+        Parameters(request): Parameters<FileOutlineRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let db = self.db();
+        let path = request.path.trim();
+
+        tracing::debug!("MCP file_outline: path='{}'", path);
+
+        if path.is_empty() {
+            return Ok(tool_error(
+                ToolErrorKind::InvalidRequest,
+                "Path must not be empty",
+            ));
+        }
+        if let Err(e) = db.ensure_database_exists() {
+            return Ok(e);
+        }
+
+        // Chunk paths are absolute; accept paths relative to the project too
+        let candidates = [
+            normalize_path(&db.project_path.join(path)),
+            normalize_path(Path::new(path)),
+        ];
+        let found = db
+            .with_vector_store(|store| {
+                for candidate in &candidates {
+                    let chunks = store.file_chunks(candidate)?;
+                    if !chunks.is_empty() {
+                        return Ok(Some((candidate.clone(), chunks)));
+                    }
+                }
+                Ok(None)
+            })
+            .await;
+        let (path, chunks) = match found {
+            Ok(Some(found)) => found,
+            Ok(None) => {
+                return Ok(CallToolResult::success(vec![Content::text(format!(
+                    "No indexed chunks for '{}'. Check the path, or run 'codesearch coverage {}' to see why the file isn't indexed.",
+                    request.path, path
+                ))]))
+            }
+            Err(e) => return Ok(tool_error_from("Error reading the file's chunks", &e)),
+        };
+
+        let response = FileOutlineResponse {
+            path,
+            chunks: chunks
+                .into_iter()
+                .map(|chunk| OutlineItem {
+                    chunk_id: chunk.id,
+                    start_line: chunk.start_line,
+                    end_line: chunk.end_line,
+                    kind: chunk.kind,
+                    signature: chunk.signature,
+                })
+                .collect(),
+        };
+        let json = serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
+    #[tool(
+        description = "Find all references/usages of a symbol (function, class, method, variable) across the codebase. USE THIS INSTEAD OF GREP when you need to find where a symbol is used — for refactoring, impact analysis, or understanding call sites. Returns compact list of file paths, line numbers, and containing function signatures."
+    )]
+    async fn find_references(
+        &self,
+        Parameters(request): Parameters<FindReferencesRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let db = self.db();
+        let limit = request.limit.unwrap_or(20);
+
+        tracing::debug!(
+            "MCP find_references: symbol='{}', limit={}",
+            request.symbol,
+            limit
+        );
+
+        if request.symbol.trim().is_empty() {
+            return Ok(tool_error(
+                ToolErrorKind::InvalidRequest,
+                "Symbol must not be empty",
+            ));
+        }
+
+        // Ensure database exists
+        if let Err(e) = db.ensure_database_exists() {
+            return Ok(e);
+        }
+
+        // Full-text search on the symbol name, resolved to chunks in the vector store
+        let db_path = &db.db_path;
+        let references = match db
+            .with_vector_store(|store| {
+                find_references(&request.symbol, &FtsStore::new(db_path)?, store, limit)
+            })
+            .await
+        {
+            Ok(references) => references,
+            Err(e) => return Ok(tool_error_from("Error searching for references", &e)),
+        };
+
+        if references.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No references found for '{}'. The symbol may not be indexed or try a different name.",
+                request.symbol
+            ))]));
         }
 
-        // Apply kind boost (improvement 3)
-        if let Some(target_kind) = structural_intent {
-            boost_kind(&mut results, target_kind);
-        }
+        let items: Vec<ReferenceItem> = references.into_iter().map(ReferenceItem::from).collect();
+        let json = serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string());
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
 
-        tracing::debug!("MCP: Final {} results after hybrid search", results.len());
+    #[tool(
+        description = "Find where a symbol (function, method, class, struct, type) is defined. USE THIS INSTEAD OF GREP to jump to a definition. Prefers chunks whose signature declares the symbol, types over functions and code over tests; qualify a method with its type (Server::handle) to pick among same-named ones. Returns the single best definition with its path and line range."
+    )]
+    async fn get_definition(
+        &self,
+        Parameters(request): Parameters<GetDefinitionRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let db = self.db();
 
-        if results.is_empty() {
-            return Ok(CallToolResult::success(vec![Content::text(
-                "No results found for the query. Try rephrasing your query or using broader terms.",
-            )]));
-        }
+        tracing::debug!("MCP get_definition: symbol='{}'", request.symbol);
 
-        // Convert to response format, applying compact mode and filter_path
-        // Pre-compute normalized project root for stripping absolute paths
-        let project_root_normalized = {
-            let root = crate::cache::normalize_path_str(self.project_path.to_str().unwrap_or(""));
-            root.trim_end_matches('/').to_string()
-        };
+        if request.symbol.trim().is_empty() {
+            return Ok(tool_error(
+                ToolErrorKind::InvalidRequest,
+                "Symbol must not be empty",
+            ));
+        }
+        if let Err(e) = db.ensure_database_exists() {
+            return Ok(e);
+        }
 
-        let items: Vec<SearchResultItem> = results
-            .into_iter()
-            .filter(|r| {
-                // Apply filter_path if specified
-                if let Some(ref fp) = request.filter_path {
-                    let normalized_path = crate::cache::normalize_path_str(&r.path);
-                    // Strip project root to convert absolute → relative path
-                    let normalized_path = normalized_path
-                        .strip_prefix(&project_root_normalized)
-                        .unwrap_or(&normalized_path)
-                        .trim_start_matches('/')
-                        .trim_start_matches("./");
-                    let normalized_filter = crate::cache::normalize_path_str(fp);
-                    let normalized_filter = normalized_filter
-                        .trim_start_matches("./")
-                        .trim_end_matches('/');
-                    normalized_path.starts_with(normalized_filter)
-                } else {
-                    true
-                }
+        let db_path = &db.db_path;
+        let project_root = db.project_path.to_string_lossy();
+        let definition = match db
+            .with_vector_store(|store| {
+                find_definition(
+                    request.symbol.trim(),
+                    &FtsStore::new(db_path)?,
+                    store,
+                    &project_root,
+                )
             })
-            .map(|r| SearchResultItem {
-                path: r.path,
-                start_line: r.start_line,
-                end_line: r.end_line,
-                kind: r.kind,
-                score: r.score,
-                signature: r.signature,
-                content: if compact { None } else { Some(r.content) },
-                context_prev: if compact { None } else { r.context_prev },
-                context_next: if compact { None } else { r.context_next },
-            })
-            .collect();
+            .await
+        {
+            Ok(definition) => definition,
+            Err(e) => return Ok(tool_error_from("Error finding the definition", &e)),
+        };
 
-        let json = serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string());
+        let Some(definition) = definition else {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No definition found for '{}'. It may be defined outside the index (a dependency), or try find_references.",
+                request.symbol
+            ))]));
+        };
+        let item = DefinitionItem::from(definition);
+        let json = serde_json::to_string(&item).unwrap_or_else(|_| "{}".to_string());
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
     #[tool(
-        description = "Find all references/usages of a symbol (function, class, method, variable) across the codebase. USE THIS INSTEAD OF GREP when you need to find where a symbol is used — for refactoring, impact analysis, or understanding call sites. Returns compact list of file paths, line numbers, and containing function signatures."
+        description = "Find the tests that exercise a function, method or type: tests that call it, or whose name spells it (test_parse_config for parse_config). Use this before changing code to know which tests to run or update. Returns test locations with call counts, most direct first."
     )]
-    async fn find_references(
+    async fn tests_for(
         &self,
-        Parameters(request): Parameters<FindReferencesRequest>,
+        Parameters(request): Parameters<TestsForRequest>,
     ) -> Result<CallToolResult, McpError> {
-        let limit = request.limit.unwrap_or(20);
+        let db = self.db();
+        let limit = request.limit.unwrap_or(10);
 
         tracing::debug!(
-            "MCP find_references: symbol='{}', limit={}",
+            "MCP tests_for: symbol='{}', limit={}",
             request.symbol,
             limit
         );
 
-        // Ensure database exists
-        if let Err(e) = self.ensure_database_exists() {
-            return Ok(CallToolResult::success(vec![Content::text(e)]));
+        if request.symbol.trim().is_empty() {
+            return Ok(tool_error(
+                ToolErrorKind::InvalidRequest,
+                "Symbol must not be empty",
+            ));
+        }
+        if let Err(e) = db.ensure_database_exists() {
+            return Ok(e);
         }
 
-        // Open FTS store for full-text search on the symbol name
-        let fts_store = match FtsStore::new(&self.db_path) {
-            Ok(s) => s,
-            Err(e) => {
-                return Ok(CallToolResult::success(vec![Content::text(format!(
-                    "Error opening FTS store: {}. Try re-indexing with 'codesearch index --force'.",
-                    e
-                ))]));
-            }
-        };
-
-        // Search FTS for the symbol — returns chunk_id + score
-        let fts_results = match fts_store.search(&request.symbol, limit * 2, None) {
-            Ok(r) => r,
-            Err(e) => {
-                return Ok(CallToolResult::success(vec![Content::text(format!(
-                    "Error searching for references: {}",
-                    e
-                ))]));
-            }
+        let db_path = &db.db_path;
+        let links = match db
+            .with_vector_store(|store| {
+                tests_for(
+                    request.symbol.trim(),
+                    &FtsStore::new(db_path)?,
+                    store,
+                    limit,
+                )
+            })
+            .await
+        {
+            Ok(links) => links,
+            Err(e) => return Ok(tool_error_from("Error finding tests", &e)),
         };
 
-        if fts_results.is_empty() {
+        if links.is_empty() {
             return Ok(CallToolResult::success(vec![Content::text(format!(
-                "No references found for '{}'. The symbol may not be indexed or try a different name.",
+                "No tests found for '{}'. It may be untested, or tested only through its callers.",
                 request.symbol
             ))]));
         }
+        let items: Vec<TestLinkItem> = links.into_iter().map(TestLinkItem::from).collect();
+        let json = serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string());
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
 
-        // Resolve chunk metadata from VectorStore using chunk_ids
-        let items: Vec<ReferenceItem> = if let Some(ref stores) = self.shared_stores {
-            let store = stores.vector_store.read().await;
-            fts_results
-                .iter()
-                .filter_map(|fts_result| {
-                    if let Ok(Some(chunk)) = store.get_chunk(fts_result.chunk_id) {
-                        Some(ReferenceItem {
-                            path: chunk.path,
-                            line: chunk.start_line,
-                            kind: chunk.kind,
-                            signature: chunk.signature,
-                            score: fts_result.score,
-                        })
-                    } else {
-                        None
-                    }
-                })
-                .take(limit)
-                .collect()
-        } else {
-            // Standalone mode — open a new store
-            let store = match VectorStore::new(&self.db_path, self.dimensions) {
-                Ok(s) => s,
-                Err(e) => {
-                    return Ok(CallToolResult::success(vec![Content::text(format!(
-                        "Error opening database: {}",
-                        e
-                    ))]));
-                }
-            };
-            fts_results
-                .iter()
-                .filter_map(|fts_result| {
-                    if let Ok(Some(chunk)) = store.get_chunk(fts_result.chunk_id) {
-                        Some(ReferenceItem {
-                            path: chunk.path,
-                            line: chunk.start_line,
-                            kind: chunk.kind,
-                            signature: chunk.signature,
-                            score: fts_result.score,
-                        })
-                    } else {
-                        None
-                    }
-                })
-                .take(limit)
-                .collect()
+    #[tool(
+        description = "Find the code a test exercises: the definitions of the functions it calls, with the one its name spells (parse_config for test_parse_config) first. Pass the test function's name. Use this to go from a failing test to the implementation."
+    )]
+    async fn implementation_for(
+        &self,
+        Parameters(request): Parameters<ImplementationForRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let db = self.db();
+        let limit = request.limit.unwrap_or(10);
+
+        tracing::debug!(
+            "MCP implementation_for: test='{}', limit={}",
+            request.test,
+            limit
+        );
+
+        if request.test.trim().is_empty() {
+            return Ok(tool_error(
+                ToolErrorKind::InvalidRequest,
+                "Test name must not be empty",
+            ));
+        }
+        if let Err(e) = db.ensure_database_exists() {
+            return Ok(e);
+        }
+
+        let db_path = &db.db_path;
+        let links = match db
+            .with_vector_store(|store| {
+                implementation_for(request.test.trim(), &FtsStore::new(db_path)?, store, limit)
+            })
+            .await
+        {
+            Ok(Some(links)) => links,
+            Ok(None) => {
+                return Ok(tool_error(
+                    ToolErrorKind::InvalidRequest,
+                    format!(
+                        "No indexed test named '{}'; pass the test function's name",
+                        request.test
+                    ),
+                ))
+            }
+            Err(e) => return Ok(tool_error_from("Error finding the implementation", &e)),
         };
 
+        let items: Vec<TestLinkItem> = links.into_iter().map(TestLinkItem::from).collect();
         let json = serde_json::to_string(&items).unwrap_or_else(|_| "[]".to_string());
         Ok(CallToolResult::success(vec![Content::text(json)]))
     }
 
+    #[tool(
+        description = "Find who owns a file or directory according to the project's CODEOWNERS, to tell the user whom to consult about a subsystem. For a file, returns its owners and the matching rule; for a directory, the owners of its indexed files with how many files each owns. Search results also carry an owners field."
+    )]
+    async fn owners(
+        &self,
+        Parameters(request): Parameters<OwnersRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let db = self.db();
+        let path = request.path.trim();
+
+        tracing::debug!("MCP owners: path='{}'", path);
+
+        if let Err(e) = db.ensure_database_exists() {
+            return Ok(e);
+        }
+        let Some(code_owners) = db.code_owners() else {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No CODEOWNERS file was found when the project was indexed \
+                 (looked in .github/, the project root, docs/ and .gitlab/).",
+            )]));
+        };
+
+        let response = if path.is_empty() || db.project_path.join(path).is_dir() {
+            let files = FileMetaStore::read(&db.db_path);
+            let directory = code_owners
+                .directory_owners(path, files.iter().flat_map(|files| files.tracked_files()));
+            OwnersResponse {
+                path: path.to_string(),
+                source: code_owners.source.clone(),
+                owners: directory
+                    .by_owner
+                    .iter()
+                    .map(|(owner, _)| owner.clone())
+                    .collect(),
+                rule: None,
+                files_by_owner: directory
+                    .by_owner
+                    .into_iter()
+                    .map(|(owner, files)| OwnerFiles { owner, files })
+                    .collect(),
+                unowned_files: Some(directory.unowned_files),
+            }
+        } else {
+            let rule = code_owners.rule_for(path);
+            OwnersResponse {
+                path: path.to_string(),
+                source: code_owners.source.clone(),
+                owners: rule.map(|rule| rule.owners.clone()).unwrap_or_default(),
+                rule: rule.map(|rule| rule.pattern.clone()),
+                files_by_owner: Vec::new(),
+                unowned_files: None,
+            }
+        };
+        let json = serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
+
     #[tool(
         description = "Get the status of the semantic search index including model info and statistics. Check this before searching to verify the index is ready."
     )]
     async fn index_status(&self) -> Result<CallToolResult, McpError> {
-        let indexed = self.db_path.exists();
+        let db = self.db();
+        let indexed = db.db_path.exists();
+        let model_state = db.model_load_state();
+        let model_error = match &model_state {
+            ModelLoadState::Failed { message, .. } => Some(message.clone()),
+            _ => None,
+        };
 
         if !indexed {
             let response = IndexStatusResponse {
@@ -561,91 +2148,97 @@ impl CodesearchService {
                 model: "none".to_string(),
                 dimensions: 0,
                 max_chunk_id: 0,
-                db_path: self.db_path.display().to_string(),
-                project_path: self.project_path.display().to_string(),
+                primary_language: None,
+                languages: Vec::new(),
+                db_path: db.db_path.display().to_string(),
+                project_path: db.project_path.display().to_string(),
                 error_message: None,
+                error_kind: None,
+                model_status: model_state.as_str().to_string(),
+                model_error: model_error.clone(),
+                failing_files: Vec::new(),
+                progress: None,
+                watcher_active: false,
+                readonly: false,
+                last_refresh_time: None,
+                pending_events: 0,
+                current_operation: None,
             };
             let json = serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string());
             return Ok(CallToolResult::success(vec![Content::text(json)]));
         }
 
         // Get stats using shared stores if available
-        let stats = if let Some(ref stores) = self.shared_stores {
+        let stats_result = if let Some(ref stores) = db.shared_stores {
             let store = stores.vector_store.read().await;
-            match store.stats() {
-                Ok(s) => s,
-                Err(e) => {
-                    let response = IndexStatusResponse {
-                        indexed: false,
-                        status: "error".to_string(),
-                        status_message: format!("Error getting index stats: {}", e),
-                        total_chunks: 0,
-                        total_files: 0,
-                        model: self.model_type.short_name().to_string(),
-                        dimensions: 0,
-                        max_chunk_id: 0,
-                        db_path: self.db_path.display().to_string(),
-                        project_path: self.project_path.display().to_string(),
-                        error_message: Some(format!("Error getting stats: {}", e)),
-                    };
-                    let json =
-                        serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string());
-                    return Ok(CallToolResult::success(vec![Content::text(json)]));
-                }
-            }
+            store.stats().map_err(|e| ("Error getting index stats", e))
         } else {
             // Fallback: open a new store (standalone mode)
-            let store = match VectorStore::new(&self.db_path, self.dimensions) {
-                Ok(s) => s,
-                Err(e) => {
-                    let response = IndexStatusResponse {
-                        indexed: false,
-                        status: "error".to_string(),
-                        status_message: format!("Error opening database: {}", e),
-                        total_chunks: 0,
-                        total_files: 0,
-                        model: self.model_type.short_name().to_string(),
-                        dimensions: 0,
-                        max_chunk_id: 0,
-                        db_path: self.db_path.display().to_string(),
-                        project_path: self.project_path.display().to_string(),
-                        error_message: Some(format!("Error opening database: {}", e)),
-                    };
-                    let json =
-                        serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string());
-                    return Ok(CallToolResult::success(vec![Content::text(json)]));
-                }
-            };
+            match VectorStore::new(&db.db_path, db.dimensions) {
+                Ok(store) => store.stats().map_err(|e| ("Error getting index stats", e)),
+                Err(e) => Err(("Error opening database", e)),
+            }
+        };
 
-            match store.stats() {
-                Ok(s) => s,
-                Err(e) => {
-                    let response = IndexStatusResponse {
-                        indexed: false,
-                        status: "error".to_string(),
-                        status_message: format!("Error getting index stats: {}", e),
-                        total_chunks: 0,
-                        total_files: 0,
-                        model: self.model_type.short_name().to_string(),
-                        dimensions: 0,
-                        max_chunk_id: 0,
-                        db_path: self.db_path.display().to_string(),
-                        project_path: self.project_path.display().to_string(),
-                        error_message: Some(format!("Error getting stats: {}", e)),
-                    };
-                    let json =
-                        serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string());
-                    return Ok(CallToolResult::success(vec![Content::text(json)]));
-                }
+        let stats = match stats_result {
+            Ok(s) => s,
+            Err((context, e)) => {
+                let kind = classify_error(&e);
+                let response = IndexStatusResponse {
+                    indexed: false,
+                    status: "error".to_string(),
+                    status_message: format!("{}: {}", context, e),
+                    total_chunks: 0,
+                    total_files: 0,
+                    model: db.model_type.short_name().to_string(),
+                    dimensions: 0,
+                    max_chunk_id: 0,
+                    primary_language: None,
+                    languages: Vec::new(),
+                    db_path: db.db_path.display().to_string(),
+                    project_path: db.project_path.display().to_string(),
+                    error_message: Some(format!("{}: {}", context, e)),
+                    error_kind: Some(kind),
+                    model_status: model_state.as_str().to_string(),
+                    model_error,
+                    failing_files: Vec::new(),
+                    progress: None,
+                    watcher_active: false,
+                    readonly: false,
+                    last_refresh_time: None,
+                    pending_events: 0,
+                    current_operation: None,
+                };
+                return Ok(match serde_json::to_value(&response) {
+                    Ok(value) => CallToolResult::structured_error(value),
+                    Err(_) => tool_error(kind, format!("{}: {}", context, e)),
+                });
             }
         };
 
         // Determine status based on database state
-        let (status, status_message) = if stats.total_chunks == 0 {
+        let stores = db.shared_stores.as_deref();
+        let progress = stores
+            .and_then(|stores| stores.activity.progress())
+            .map(|(done, total)| IndexProgressInfo::new(done, total));
+        let (status, status_message) = if let Some(progress) = &progress {
+            (
+                "building".to_string(),
+                format!(
+                    "Indexing in the background: {}/{} files ({:.1}%). Results are partial until indexing completes.",
+                    progress.files_indexed, progress.files_total, progress.percent_complete
+                ),
+            )
+        } else if stats.total_chunks == 0 {
             (
                 "building".to_string(),
                 "Index is being built in the background. Searches may fail until indexing completes. Please check back in a few minutes.".to_string(),
             )
+        } else if stores.is_some_and(|s| s.readonly) {
+            (
+                "ready".to_string(),
+                "Index is ready for searching, but read-only: another codesearch instance owns updates, so results may lag behind recent edits.".to_string(),
+            )
         } else {
             (
                 "ready".to_string(),
@@ -653,18 +2246,47 @@ impl CodesearchService {
             )
         };
 
+        let metadata = IndexMetadata::load(&db.db_path).ok().flatten();
         let response = IndexStatusResponse {
             indexed: stats.indexed,
             status,
             status_message,
             total_chunks: stats.total_chunks,
             total_files: stats.total_files,
-            model: self.model_type.short_name().to_string(),
+            model: db.model_type.short_name().to_string(),
             dimensions: stats.dimensions,
             max_chunk_id: stats.max_chunk_id,
-            db_path: self.db_path.display().to_string(),
-            project_path: self.project_path.display().to_string(),
+            primary_language: metadata
+                .as_ref()
+                .and_then(|metadata| metadata.primary_language.clone()),
+            languages: metadata
+                .map(|metadata| metadata.language_stats)
+                .unwrap_or_default(),
+            db_path: db.db_path.display().to_string(),
+            project_path: db.project_path.display().to_string(),
             error_message: None,
+            error_kind: None,
+            model_status: model_state.as_str().to_string(),
+            model_error,
+            failing_files: FileMetaStore::read_failing_files(&db.db_path)
+                .into_iter()
+                .map(|(path, failure)| FailingFileInfo {
+                    path,
+                    failures: failure.count,
+                    quarantined: failure.is_quarantined(),
+                    last_error: failure.last_error,
+                })
+                .collect(),
+            progress,
+            watcher_active: stores.is_some_and(|s| s.activity.watcher_active()),
+            readonly: stores.is_some_and(|s| s.readonly),
+            last_refresh_time: stores
+                .and_then(|s| s.activity.last_refresh())
+                .map(|t| t.to_rfc3339()),
+            pending_events: stores.map_or(0, |s| s.activity.pending_events()),
+            current_operation: stores
+                .and_then(|s| s.activity.current_operation())
+                .map(str::to_string),
         };
 
         let json = serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string());
@@ -672,87 +2294,59 @@ impl CodesearchService {
     }
 
     #[tool(
-        description = "Find all available codesearch databases in current directory, parent directories, and globally tracked repositories. Use this to discover which databases are available for searching."
+        description = "Find all available codesearch databases in current directory, parent directories, and globally tracked repositories. Each entry reports its health (valid/incomplete with missing components), last_indexed time and stale_files count, so you can pick the freshest database when several are available."
     )]
     async fn find_databases(&self) -> Result<CallToolResult, McpError> {
-        let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-        let dbs = find_databases().unwrap_or_default();
-
-        let mut response_dbs = Vec::new();
-
-        for db_info in &dbs {
-            // Get stats for this database
-            let (total_chunks, total_files, model) = if db_info.db_path.exists() {
-                // Try to read model from metadata
-                let metadata_path = db_info.db_path.join("metadata.json");
-                let model_name = if metadata_path.exists() {
-                    if let Ok(content) = std::fs::read_to_string(&metadata_path) {
-                        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) {
-                            json.get("model_short_name")
-                                .and_then(|v| v.as_str())
-                                .unwrap_or("unknown")
-                                .to_string()
-                        } else {
-                            "unknown".to_string()
-                        }
-                    } else {
-                        "unknown".to_string()
-                    }
-                } else {
-                    "unknown".to_string()
-                };
+        let response = list_databases();
 
-                // Try to get stats - need to infer dimensions from model name
-                let dims = match model_name.as_str() {
-                    "minilm-l6" | "minilm-l6-q" | "minilm-l12" | "minilm-l12-q" | "bge-small"
-                    | "bge-small-q" | "e5-multilingual" => 384,
-                    "bge-base" | "jina-code" | "nomic-v1.5" => 768,
-                    "bge-large" | "mxbai-large" => 1024,
-                    _ => 384, // default
-                };
+        let json = serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string());
+        Ok(CallToolResult::success(vec![Content::text(json)]))
+    }
 
-                // Try to get stats
-                if let Ok(store) = VectorStore::new(&db_info.db_path, dims) {
-                    if let Ok(stats) = store.stats() {
-                        (stats.total_chunks, stats.total_files, model_name)
-                    } else {
-                        (0, 0, model_name)
-                    }
-                } else {
-                    (0, 0, model_name)
-                }
-            } else {
-                (0, 0, "not found".to_string())
-            };
+    #[tool(
+        description = "Pin the database searched by all tools for the rest of this session, overriding automatic discovery. Use this when find_databases shows that the wrong index was picked (e.g. a parent or globally registered repository in a nested-repo setup). Pass the project directory or its .codesearch.db directory."
+    )]
+    async fn use_database(
+        &self,
+        Parameters(request): Parameters<UseDatabaseRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        tracing::debug!("MCP use_database: path='{}'", request.path);
 
-            response_dbs.push(DatabaseInfoResponse {
-                database_path: db_info.db_path.display().to_string(),
-                project_path: db_info.project_path.display().to_string(),
-                is_current_directory: db_info.is_current,
-                depth_from_current: db_info.depth,
-                total_chunks,
-                total_files,
-                model,
-            });
-        }
+        let info = match crate::db_discovery::resolve_database(&PathBuf::from(&request.path)) {
+            Ok(info) => info,
+            Err(e) => return Ok(tool_error(ToolErrorKind::NotIndexed, e.to_string())),
+        };
 
-        // Build message based on what was found
-        let message = if dbs.is_empty() {
-            "❌ No databases found. Run 'codesearch index' to create an index.".to_string()
-        } else if dbs.iter().any(|d| d.is_current) {
-            format!(
-                "✅ Found {} database(s). Current directory has an index.",
-                dbs.len()
-            )
+        // Going back to the startup database reuses its live stores and loaded model
+        let db = if info.db_path == self.startup.db_path {
+            self.startup.clone()
+        } else if info.db_path == self.db().db_path {
+            self.db()
         } else {
-            format!("⚠️  Found {} database(s) in parent/global directories, but not in current directory.", dbs.len())
+            match ActiveDatabase::open(info.db_path, info.project_path, None) {
+                Ok(db) => Arc::new(db),
+                Err(e) => return Ok(tool_error_from("Error opening database", &e)),
+            }
         };
+        match self.active.write() {
+            Ok(mut active) => *active = db.clone(),
+            Err(e) => *e.into_inner() = db.clone(),
+        }
 
-        let response = FindDatabasesResponse {
-            databases: response_dbs,
-            message,
-            current_directory: current_dir.display().to_string(),
+        let live_updates = db.shared_stores.as_ref().is_some_and(|s| !s.readonly);
+        let response = UseDatabaseResponse {
+            database_path: db.db_path.display().to_string(),
+            project_path: db.project_path.display().to_string(),
+            model: db.model_type.short_name().to_string(),
+            dimensions: db.dimensions,
+            live_updates,
+            message: if live_updates {
+                "✅ Searching this database for the rest of the session.".to_string()
+            } else {
+                "✅ Searching this database for the rest of the session. It is not watched by this server; run 'codesearch index' there to update it.".to_string()
+            },
         };
+        tracing::info!("📌 Session pinned to database {}", response.database_path);
 
         let json = serde_json::to_string(&response).unwrap_or_else(|_| "{}".to_string());
         Ok(CallToolResult::success(vec![Content::text(json)]))
@@ -761,22 +2355,60 @@ impl CodesearchService {
 
 // === Server Handler Implementation ===
 
-#[tool_handler]
 impl ServerHandler for CodesearchService {
+    async fn call_tool(
+        &self,
+        request: rmcp::model::CallToolRequestParam,
+        context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let db_path = self.db().db_path.clone();
+        let tool = request.name.to_string();
+        let query = ToolCall::query_of(request.arguments.as_ref());
+        let start = std::time::Instant::now();
+        let tcc = rmcp::handler::server::tool::ToolCallContext::new(self, request, context);
+        let result = self.tool_router.call(tcc).await;
+
+        let call = ToolCall {
+            tool,
+            query,
+            ms: start.elapsed().as_millis() as u64,
+            failed: !matches!(&result, Ok(r) if r.is_error != Some(true)),
+        };
+        if slow_query_ms().is_some_and(|slow| call.ms >= slow) {
+            tracing::warn!(
+                "🐢 Slow {} call ({} ms): {}",
+                call.tool,
+                call.ms,
+                call.query.as_deref().unwrap_or("-")
+            );
+        }
+        if let Err(e) = ToolUsage::record(&db_path, &call) {
+            tracing::debug!("Failed to record tool usage: {}", e);
+        }
+        result
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<rmcp::model::PaginatedRequestParam>,
+        _context: rmcp::service::RequestContext<rmcp::RoleServer>,
+    ) -> Result<rmcp::model::ListToolsResult, McpError> {
+        let tools = self
+            .tool_router
+            .list_all()
+            .into_iter()
+            .filter(|tool| self.federated.is_some() || tool.name != "search_all")
+            .collect();
+        Ok(rmcp::model::ListToolsResult::with_all_items(tools))
+    }
+
     fn get_info(&self) -> ServerInfo {
+        let db = self.db();
         let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
-        let db_exists = self.db_path.exists();
+        let db_exists = db.db_path.exists();
 
-        ServerInfo {
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
-            server_info: rmcp::model::Implementation {
-                name: "codesearch".to_string(),
-                version: env!("CARGO_PKG_VERSION").to_string(),
-                title: None,
-                icons: None,
-                website_url: None,
-            },
-            instructions: Some(format!(
+        let full = || {
+            format!(
                 r#"codesearch - Semantic Code Search MCP Server
 
 codesearch provides fast, local semantic code search using natural language queries.
@@ -796,12 +2428,30 @@ AVAILABLE TOOLS:
    Check if the current index is ready for searching.
    Use this AFTER find_databases() to verify the database is accessible.
    Returns: Index status, stats, model info, and any error messages.
+   model_status is "model_downloading" while the embedding model loads and
+   "model_failed" if loading failed (it is retried automatically with backoff).
 
-3. semantic_search(query, limit=10, compact=true, filter_path=null)
+3. semantic_search(query, limit=10, compact=true, filter_path=null, changed_only=false, project_only=false)
    Search the codebase using natural language queries.
    By default returns COMPACT results (path, line numbers, kind, signature, score only).
    Set compact=false to include full code content (use sparingly - high token cost).
-   Use filter_path to narrow results to a specific directory (e.g., "src/api/").
+   Use filter_path to narrow results to a specific directory (e.g., "src/api/")
+   or glob patterns (e.g., "src/**/handlers/*.rs,!**/*_test.go").
+   Use changed_only=true to search only files with uncommitted changes.
+   Use project_only=true to leave out vendored third-party code (third_party/,
+   external/, ...). Results carry the file's SPDX "license" and "vendored".
+   Use fields=["path", "start_line", "signature"] to return only those fields
+   (overrides compact; also accepted by batch_search and related_chunks).
+   Scores run 0-100 relative to the top result; "confidence" (high, medium,
+   low) says how closely each result matches the query itself.
+   min_score (0-100) drops results scoring below it and results unrelated
+   to the query; full-text and exact identifier matches are always kept.
+   With min_score, when no result is a confident match, a
+   "no_confident_matches" status comes back instead, with identifiers to
+   look up and reformulated queries to try.
+   Identifiers in the query that match nothing in the index get the closest
+   indexed symbol suggested (in "_meta" and a note); auto_correct=true
+   searches for the suggestion instead.
    Query examples:
      - "where do we handle user authentication?"
      - "how is error logging implemented?"
@@ -817,6 +2467,55 @@ AVAILABLE TOOLS:
      - find_references("UserService") - Find all usages of UserService
      - find_references("handleRequest") - Find all call sites
    Returns: Compact list of file paths, line numbers, kind, and score.
+   To jump to where a symbol is declared instead, use get_definition(symbol).
+
+5. batch_search(queries, limit=10, compact=true, filter_path=null, changed_only=false, project_only=false)
+   Run several semantic searches in one call when a task splits into sub-queries.
+   All queries are embedded in one batch. Returns one entry per query with
+   its "query" and "results" (same format as semantic_search). At most 20 queries.
+
+6. related_chunks(chunk_id, limit=10, compact=true)
+   Find code similar to a result you already have, by its chunk_id.
+   No new query is embedded, so it is a cheap way to explore
+   "code like this function".
+
+7. neighbors(chunk_id, compact=false)
+   The chunks just before and after a chunk in the same file, with content.
+   Walk a file chunk by chunk by passing a returned chunk_id again.
+
+8. use_database(path)
+   Pin the database all tools search for the rest of this session.
+   Use this when find_databases() shows the automatically chosen index is the
+   wrong one (e.g. a parent or global repository in a nested-repo setup).
+
+9. tests_for(symbol, limit=10)
+   The tests that exercise a function, method or type: tests that call it,
+   or whose name spells it (test_parse_config for parse_config).
+   Use it before a change to know which tests to run.
+
+10. implementation_for(test, limit=10)
+   The definitions a test calls, the one its name spells first.
+   Use it to go from a failing test to the code under test.
+
+11. owners(path)
+   Who owns a file or directory according to CODEOWNERS, to tell the user
+   whom to consult. Search results carry an "owners" field too.
+
+12. get_definition(symbol)
+   Where a function, method, class or type is defined: the single best
+   chunk whose signature declares it, with path and line range.
+   Qualify a method with its type ("Server::handle") to pick among
+   same-named ones. Use it instead of grep to jump to a definition.
+
+13. file_outline(path)
+   The indexed chunks of a file (kind, signature, start/end lines) in
+   line order: a table of contents without reading the file.
+
+14. search_all(query, limit=10, compact=true, filter_path=null, project_only=false)
+   Only offered when the server runs with --multi-repo. Searches every
+   project the server opened (see find_databases) with one query and
+   merges the results into one ranking; each carries its "project_root".
+   Use it when the code may live in another repository than this one.
 
 TOKEN-EFFICIENT WORKFLOW (IMPORTANT):
 
@@ -883,9 +2582,10 @@ BEST PRACTICES:
 DATABASE LOCATIONS:
 
 Priority order for database selection:
+0. A database pinned with use_database() (or 'codesearch use <path>' in the CLI)
 1. Current directory (.codesearch.db/)
-2. Parent directories (up to 5 levels)
-3. Globally tracked repositories (~/.codesearch/repos.json)
+2. Parent directories (up to {depth} levels)
+3. Globally tracked repositories (~/.codesearch/repos.json){global}
 
 Current project: {project}
 Current database: {db}
@@ -894,7 +2594,12 @@ Current directory: {cwd}
 
 ERROR HANDLING:
 
-If you get "No index found" errors:
+Failed tool calls are flagged with isError=true and return a JSON object:
+  {{"error": "<kind>", "message": "...", "hint": "..."}}
+Error kinds: not_indexed, locked, dimension_mismatch, io, model_unavailable,
+invalid_request, internal. An empty result set is NOT an error.
+
+If you get "not_indexed" errors:
 1. Call find_databases() to see what's available
 2. Check if you're in the right directory
 3. Verify the user has run 'codesearch index'
@@ -917,18 +2622,169 @@ For detailed documentation, visit: https://github.com/flupkede/codesearch
 Model: {model}
 Dimensions: {dims}
 "#,
-                project = self.project_path.display(),
-                db = self.db_path.display(),
+                project = db.project_path.display(),
+                db = db.db_path.display(),
                 exists = if db_exists { "✅ Yes" } else { "❌ No" },
                 cwd = current_dir.display(),
-                model = self.model_type.short_name(),
-                dims = self.dimensions
+                model = db.model_type.short_name(),
+                dims = db.dimensions,
+                depth = crate::db_discovery::discovery_depth(),
+                global = if crate::db_discovery::global_discovery_enabled() {
+                    ""
+                } else {
+                    " - disabled"
+                }
+            )
+        };
+
+        ServerInfo {
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            server_info: rmcp::model::Implementation {
+                name: "codesearch".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                title: None,
+                icons: None,
+                website_url: None,
+            },
+            instructions: Some(instructions::compose(
+                InstructionsStyle::from_env(),
+                full,
+                &db.project_path,
+                &db.db_path,
+                instructions::custom_instructions(&db.project_path),
             )),
             ..Default::default()
         }
     }
 }
 
+/// Databases in and above the current directory and globally registered
+/// ones, with their health and freshness (the `find_databases` tool)
+pub fn list_databases() -> FindDatabasesResponse {
+    let current_dir = std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
+    let dbs = find_databases().unwrap_or_default();
+
+    let mut response_dbs = Vec::new();
+
+    for db_info in &dbs {
+        let missing = missing_components(&db_info.db_path);
+
+        // Read model and dimensions from metadata
+        let metadata = IndexMetadata::load(&db_info.db_path);
+        let (model, dims) = match &metadata {
+            Ok(Some(metadata)) => (metadata.model_short_name.clone(), metadata.dimensions),
+            Ok(None) => ("unknown".to_string(), ModelType::default().dimensions()),
+            Err(_) => (
+                "invalid metadata.json".to_string(),
+                ModelType::default().dimensions(),
+            ),
+        };
+
+        // Try to get stats (opening a missing vector store would create one)
+        let (total_chunks, total_files) = if missing.contains(&"data.mdb") {
+            (0, 0)
+        } else {
+            VectorStore::new(&db_info.db_path, dims)
+                .and_then(|store| store.stats())
+                .map_or((0, 0), |stats| (stats.total_chunks, stats.total_files))
+        };
+
+        let file_meta = FileMetaStore::read(&db_info.db_path);
+        let last_indexed = crate::db_discovery::last_indexed(&db_info.db_path)
+            .map(|t| chrono::DateTime::<chrono::Utc>::from(t).to_rfc3339())
+            .or_else(|| metadata.ok().flatten().and_then(|m| m.indexed_at));
+
+        response_dbs.push(DatabaseInfoResponse {
+            database_path: db_info.db_path.display().to_string(),
+            project_path: db_info.project_path.display().to_string(),
+            is_current_directory: db_info.is_current,
+            depth_from_current: db_info.depth,
+            total_chunks,
+            total_files,
+            model,
+            health: if missing.is_empty() {
+                DatabaseHealth::Valid
+            } else {
+                DatabaseHealth::Incomplete
+            },
+            missing_components: missing.iter().map(|c| c.to_string()).collect(),
+            last_indexed,
+            stale_files: file_meta.map(|store| store.count_stale_files()),
+        });
+    }
+
+    // Build message based on what was found
+    let incomplete = response_dbs
+        .iter()
+        .filter(|d| d.health == DatabaseHealth::Incomplete)
+        .count();
+    let mut message = if dbs.is_empty() {
+        "❌ No databases found. Run 'codesearch index' to create an index.".to_string()
+    } else if dbs.iter().any(|d| d.is_current) {
+        format!(
+            "✅ Found {} database(s). Current directory has an index.",
+            dbs.len()
+        )
+    } else {
+        format!(
+            "⚠️  Found {} database(s) in parent/global directories, but not in current directory.",
+            dbs.len()
+        )
+    };
+    if incomplete > 0 {
+        message.push_str(&format!(
+            " {} incomplete (see missing_components); run 'codesearch index' there to rebuild.",
+            incomplete
+        ));
+    }
+
+    FindDatabasesResponse {
+        databases: response_dbs,
+        message,
+        current_directory: current_dir.display().to_string(),
+    }
+}
+
+/// Open the valid databases `find_databases` reports, besides the one at
+/// `startup_db`, for search_all (`--multi-repo`)
+///
+/// They are opened readonly and follow the commits of whichever process
+/// keeps them up to date. A database that fails to open is left out.
+fn open_federated(startup_db: &Path, cancel_token: &CancellationToken) -> Vec<Arc<ActiveDatabase>> {
+    let canonical =
+        |path: &Path| std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut seen = vec![canonical(startup_db)];
+    let mut databases = Vec::new();
+    for info in find_databases().unwrap_or_default() {
+        let db_path = canonical(&info.db_path);
+        if seen.contains(&db_path) || !missing_components(&info.db_path).is_empty() {
+            continue;
+        }
+        seen.push(db_path);
+
+        let opened = ActiveDatabase::open(info.db_path.clone(), info.project_path, None).and_then(
+            |mut db| {
+                let stores = Arc::new(SharedStores::new_readonly(&db.db_path, db.dimensions)?);
+                stores.follow_writer(cancel_token.clone());
+                db.shared_stores = Some(stores);
+                Ok(db)
+            },
+        );
+        match opened {
+            Ok(db) => {
+                tracing::info!("📂 search_all also covers {}", db.project_path.display());
+                databases.push(Arc::new(db));
+            }
+            Err(e) => tracing::warn!(
+                "⚠️  Leaving {} out of search_all: {:#}",
+                info.db_path.display(),
+                e
+            ),
+        }
+    }
+    databases
+}
+
 // === Server Entry Point ===
 
 /// Run the MCP server using stdio transport with file watching for live index updates.
@@ -945,6 +2801,8 @@ Dimensions: {dims}
 pub async fn run_mcp_server(
     path: Option<PathBuf>,
     create_index: bool,
+    http: Option<String>,
+    multi_repo: bool,
     log_level: crate::logger::LogLevel,
     quiet: bool,
     cancel_token: CancellationToken,
@@ -992,6 +2850,9 @@ pub async fn run_mcp_server(
             db_path.display()
         );
 
+        // An incomplete database left behind may be unreadable; move it aside
+        crate::index::recover_if_corrupt(&db_path)?;
+
         // Create directory
         std::fs::create_dir_all(&db_path)?;
 
@@ -1002,14 +2863,7 @@ pub async fn run_mcp_server(
         let dimensions = model_type.dimensions();
 
         // Create minimal metadata.json (matching format used by build_index)
-        let metadata_path = db_path.join("metadata.json");
-        let metadata = serde_json::json!({
-            "model_short_name": model_short_name,
-            "model_name": model_name,
-            "dimensions": dimensions,
-            "indexed_at": chrono::Utc::now().to_rfc3339()
-        });
-        tokio::fs::write(&metadata_path, serde_json::to_string_pretty(&metadata)?).await?;
+        IndexMetadata::new(&model_short_name, &model_name, dimensions).save(&db_path)?;
 
         // Create minimal file_meta.json (matching FileMetaStore format)
         let file_meta = crate::cache::FileMetaStore::new(model_short_name.clone(), dimensions);
@@ -1023,7 +2877,9 @@ pub async fn run_mcp_server(
         let _store = crate::vectordb::VectorStore::new(&db_path, dimensions)?;
 
         tracing::info!("✅ Minimal database created successfully");
-        tracing::info!("🔄 Background indexing will begin shortly via incremental refresh");
+        tracing::info!(
+            "🔄 Initial index will be built in the background (progress via index_status)"
+        );
 
         (effective_path, db_path)
     };
@@ -1037,25 +2893,17 @@ pub async fn run_mcp_server(
     tracing::info!("📂 Project: {}", project_path.display());
     tracing::info!("💾 Database: {}", db_path.display());
 
-    // Read model metadata to get dimensions (fallback to 384 if missing/corrupt)
-    let metadata_path = db_path.join("metadata.json");
-    let dimensions = if metadata_path.exists() {
-        match std::fs::read_to_string(&metadata_path)
-            .ok()
-            .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
-            .and_then(|j| j.get("dimensions").and_then(|v| v.as_u64()))
-        {
-            Some(d) => d as usize,
-            None => {
-                tracing::warn!(
-                    "⚠️  Could not parse dimensions from metadata.json, using default 384"
-                );
-                384
-            }
+    // Read model metadata to get dimensions (fallback to the default model if missing)
+    let dimensions = match IndexMetadata::load(&db_path)? {
+        Some(metadata) => metadata.dimensions,
+        None => {
+            let dimensions = ModelType::default().dimensions();
+            tracing::warn!(
+                "⚠️  metadata.json not found, using default dimensions {}",
+                dimensions
+            );
+            dimensions
         }
-    } else {
-        tracing::warn!("⚠️  metadata.json not found, using default dimensions 384");
-        384
     };
 
     // Create shared stores - try write mode first, fall back to readonly if locked
@@ -1066,28 +2914,63 @@ pub async fn run_mcp_server(
 
     if is_readonly {
         tracing::warn!("🔒 Running in READONLY mode (another instance has write access)");
-        tracing::warn!("   ↳ Searches work normally, but index won't auto-update");
+        tracing::warn!("   ↳ Searches follow the other instance's index updates");
         tracing::warn!("   ↳ Close the other instance to enable write mode");
     }
 
-    // Create MCP service with shared stores (ready immediately)
-    let service = CodesearchService::new_with_stores(
-        Some(project_path.clone()),
-        Some(shared_stores.clone()),
-    )?;
+    // --multi-repo: open the other databases search_all covers
+    let federated = multi_repo.then(|| Arc::new(open_federated(&db_path, &cancel_token)));
+    if let Some(federated) = &federated {
+        tracing::info!(
+            "🗂️  Multi-repo mode: search_all covers {} project(s)",
+            federated.len() + 1
+        );
+    }
+
+    // Create MCP service with shared stores (ready immediately); over HTTP,
+    // one per client session
+    let new_service = {
+        let project_path = project_path.clone();
+        let shared_stores = shared_stores.clone();
+        move || {
+            Ok(CodesearchService::new_with_stores(
+                Some(project_path.clone()),
+                Some(shared_stores.clone()),
+            )?
+            .with_federated(federated.clone()))
+        }
+    };
+    let service = new_service()?;
 
-    tracing::info!("🧠 Model: {}", service.model_type.name());
+    tracing::info!("🧠 Model: {}", service.db().model_type.name());
 
     // START MCP SERVER NOW - fixes timeout!
     tracing::info!(
         "🚀 Starting MCP server{}...",
         if is_readonly { " (readonly)" } else { "" }
     );
-    let server = service.serve(stdio()).await?;
+    let server: std::pin::Pin<Box<dyn std::future::Future<Output = Result<()>> + Send>> =
+        match &http {
+            None => {
+                let server = service.serve(stdio()).await?;
+                Box::pin(async move {
+                    server.waiting().await?;
+                    Ok(())
+                })
+            }
+            Some(addr) => {
+                let listener = tokio::net::TcpListener::bind(addr)
+                    .await
+                    .map_err(|e| anyhow::anyhow!("Cannot listen on {}: {}", addr, e))?;
+                tracing::info!("🌐 MCP endpoint: http://{}/mcp", addr);
+                Box::pin(http::serve(listener, new_service, cancel_token.clone()))
+            }
+        };
 
     tracing::info!("MCP server ready. Waiting for requests...");
 
     // Only run background tasks if we have write access
+    let mut shutdown_manager: Option<Arc<IndexManager>> = None;
     if !is_readonly {
         // Create IndexManager with shared stores (skip initial refresh - do in background)
         tracing::info!("🔍 Initializing index manager...");
@@ -1100,6 +2983,7 @@ pub async fn run_mcp_server(
         let db_path_clone = db_path.clone();
         let shared_stores_clone = shared_stores.clone();
         let index_manager_arc = Arc::new(index_manager);
+        shutdown_manager = Some(index_manager_arc.clone());
         let bg_cancel_token = cancel_token.clone();
         tokio::spawn(async move {
             // Step 0: Pre-start FSW to collect file change events during refresh
@@ -1143,7 +3027,7 @@ pub async fn run_mcp_server(
         });
 
         // Start periodic log cleanup task
-        let db_path_for_cleanup = db_path.clone();
+        let log_dir_for_cleanup = crate::logger::get_log_dir(&db_path);
         let cleanup_cancel_token = cancel_token.clone();
         tokio::spawn(async move {
             use crate::logger::{cleanup_old_logs, LogRotationConfig};
@@ -1151,24 +3035,27 @@ pub async fn run_mcp_server(
             // Run initial cleanup on startup
             let rotation_config = LogRotationConfig::from_env();
             tracing::info!("🧹 Running initial log cleanup...");
-            if let Err(e) = cleanup_old_logs(&db_path_for_cleanup, &rotation_config) {
+            if let Err(e) = cleanup_old_logs(&log_dir_for_cleanup, &rotation_config) {
                 tracing::warn!("Initial log cleanup failed: {}", e);
             }
 
             // Start periodic cleanup task (every 24 hours by default)
             crate::logger::start_cleanup_task(
-                db_path_for_cleanup.clone(),
+                log_dir_for_cleanup,
                 rotation_config,
                 cleanup_cancel_token,
             );
         });
     } else {
         tracing::info!("📖 Readonly mode: skipping background refresh and file watcher");
+
+        // Follow the writer instance's commits so searches don't go stale
+        shared_stores.follow_writer(cancel_token.clone());
     }
 
     // Wait for shutdown: either MCP transport closes or cancellation token fires
     tokio::select! {
-        result = server.waiting() => {
+        result = server => {
             tracing::info!("MCP server transport closed");
             result?;
         }
@@ -1177,6 +3064,16 @@ pub async fn run_mcp_server(
         }
     }
 
+    // Let an in-flight index update commit before the runtime shuts down
+    if let Some(manager) = shutdown_manager {
+        cancel_token.cancel();
+        manager
+            .wait_for_idle(std::time::Duration::from_secs(
+                crate::constants::SHUTDOWN_FLUSH_TIMEOUT_SECS,
+            ))
+            .await;
+    }
+
     tracing::info!("✅ MCP server shut down cleanly");
     Ok(())
 }
@@ -0,0 +1,170 @@
+//! Session context for multi-turn navigation
+//!
+//! Agents usually ask several questions about the same subsystem in a row.
+//! The session remembers its recent queries and the directories of the
+//! results they led to, and lightly boosts later results from those
+//! directories. Recent visits weigh more than older ones.
+
+use std::collections::VecDeque;
+use std::path::Path;
+
+use crate::vectordb::SearchResult;
+
+/// Queries (and chunk lookups) remembered per session
+const SESSION_HISTORY: usize = 10;
+
+/// Top results of a query whose directories count as viewed
+const SESSION_VIEWED_RESULTS: usize = 3;
+
+/// Score boost for results in the directory of the latest visit
+///
+/// Override with `CODESEARCH_SESSION_BOOST` (0 disables session boosting).
+const DEFAULT_SESSION_BOOST: f32 = 0.1;
+
+/// A query, or a chunk lookup, and the directories it showed
+#[derive(Debug)]
+struct Visit {
+    query: String,
+    dirs: Vec<String>,
+}
+
+/// Recent queries of an MCP session and the directories they led to
+#[derive(Debug)]
+pub struct SessionContext {
+    /// Most recent first
+    history: VecDeque<Visit>,
+    boost: f32,
+}
+
+impl Default for SessionContext {
+    fn default() -> Self {
+        let boost = std::env::var("CODESEARCH_SESSION_BOOST")
+            .ok()
+            .and_then(|v| v.parse::<f32>().ok())
+            .unwrap_or(DEFAULT_SESSION_BOOST)
+            .max(0.0);
+        Self {
+            history: VecDeque::new(),
+            boost,
+        }
+    }
+}
+
+impl SessionContext {
+    /// Remember `query` and the directories of its top results
+    pub fn record(&mut self, query: &str, results: &[SearchResult]) {
+        let mut dirs: Vec<String> = Vec::new();
+        for result in results.iter().take(SESSION_VIEWED_RESULTS) {
+            let dir = directory_of(&result.path);
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        if dirs.is_empty() {
+            return;
+        }
+        if let Some(previous) = self.history.front() {
+            tracing::debug!(
+                "MCP session: '{}' follows '{}' ({} earlier queries)",
+                query,
+                previous.query,
+                self.history.len()
+            );
+        }
+        self.history.push_front(Visit {
+            query: query.to_string(),
+            dirs,
+        });
+        self.history.truncate(SESSION_HISTORY);
+    }
+
+    /// Boost results in recently viewed directories and re-sort them
+    pub fn boost(&self, results: &mut [SearchResult]) {
+        if self.boost == 0.0 || self.history.is_empty() {
+            return;
+        }
+        let mut boosted = false;
+        for result in results.iter_mut() {
+            let dir = directory_of(&result.path);
+            // The most recent visit to the directory counts, fading with age
+            if let Some(age) = self.history.iter().position(|v| v.dirs.contains(&dir)) {
+                let weight = 1.0 - age as f32 / SESSION_HISTORY as f32;
+                result.score *= 1.0 + self.boost * weight;
+                boosted = true;
+            }
+        }
+        if boosted {
+            results.sort_by(|a, b| {
+                b.score
+                    .partial_cmp(&a.score)
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            });
+        }
+    }
+}
+
+/// Normalized parent directory of a chunk path
+fn directory_of(path: &str) -> String {
+    let path = crate::cache::normalize_path_str(path);
+    Path::new(&path)
+        .parent()
+        .map(|p| p.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn result(path: &str, score: f32) -> SearchResult {
+        SearchResult {
+            id: 0,
+            content: String::new(),
+            path: path.to_string(),
+            start_line: 0,
+            end_line: 1,
+            kind: "Function".to_string(),
+            signature: None,
+            docstring: None,
+            context: None,
+            hash: String::new(),
+            distance: 1.0 - score,
+            score,
+            context_prev: None,
+            context_next: None,
+            text_match: false,
+        }
+    }
+
+    #[test]
+    fn test_recent_directories_are_boosted() {
+        let mut session = SessionContext {
+            history: VecDeque::new(),
+            boost: 0.1,
+        };
+        session.record(
+            "where are sessions stored?",
+            &[result("/p/src/auth/session.rs", 0.9)],
+        );
+
+        let mut results = vec![
+            result("/p/src/db/pool.rs", 0.50),
+            result("/p/src/auth/token.rs", 0.48),
+            result("/p/src/db/auth.rs", 0.30),
+        ];
+        session.boost(&mut results);
+        assert_eq!(results[0].path, "/p/src/auth/token.rs");
+        assert!((results[0].score - 0.48 * 1.1).abs() < 1e-6);
+        assert_eq!(results[2].score, 0.30);
+
+        // Older visits weigh less
+        session.record("connection pooling", &[result("/p/src/db/pool.rs", 0.9)]);
+        let mut results = vec![
+            result("/p/src/auth/a.rs", 0.5),
+            result("/p/src/db/b.rs", 0.5),
+        ];
+        session.boost(&mut results);
+        assert_eq!(results[0].path, "/p/src/db/b.rs");
+        assert!(results[0].score > results[1].score && results[1].score > 0.5);
+    }
+}
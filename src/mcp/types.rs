@@ -18,8 +18,119 @@ pub struct SemanticSearchRequest {
     /// Use compact=true (default) and then read specific files with line offsets for the code you need.
     pub compact: Option<bool>,
 
-    /// Only return results from files under this path prefix (e.g., "src/api/")
+    /// Only return results from files under this path prefix (e.g., "src/api/"),
+    /// or matching comma-separated glob patterns relative to the project root
+    /// (e.g., "src/**/handlers/*.rs"); a leading "!" excludes ("!**/*_test.go")
     pub filter_path: Option<String>,
+
+    /// Only search files with uncommitted changes (modified, staged or untracked)
+    /// in the git work tree - the code you are currently working on (default: false)
+    pub changed_only: Option<bool>,
+
+    /// Leave out files in vendored directories (third_party/, external/, ...) -
+    /// code copied from other projects (default: false)
+    pub project_only: Option<bool>,
+
+    /// Return only these result fields, overriding compact (e.g. ["path", "start_line",
+    /// "signature"]). Available: chunk_id, path, start_line, end_line, kind, score,
+    /// confidence, signature, content, context_prev, context_next
+    pub fields: Option<Vec<String>>,
+
+    /// Drop results scoring below this (0-100, relative to the top result), and
+    /// results unrelated to the query. Full-text and exact identifier matches are
+    /// kept. Without it (or with 0) every result is returned
+    pub min_score: Option<f32>,
+
+    /// Search identifiers that match nothing in the index as the closest indexed
+    /// symbol (default: false - the correction is only suggested)
+    pub auto_correct: Option<bool>,
+}
+
+/// Request for several semantic searches at once
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct BatchSearchRequest {
+    /// The search queries (natural language or code snippets), at most 20
+    pub queries: Vec<String>,
+
+    /// Maximum number of results per query (default: 10)
+    pub limit: Option<usize>,
+
+    /// Return compact results (metadata only) to save tokens (default: true)
+    pub compact: Option<bool>,
+
+    /// Only return results from files matching this path prefix or glob patterns,
+    /// as in semantic_search
+    pub filter_path: Option<String>,
+
+    /// Only search files with uncommitted changes in the git work tree (default: false)
+    pub changed_only: Option<bool>,
+
+    /// Leave out files in vendored directories (default: false)
+    pub project_only: Option<bool>,
+
+    /// Return only these result fields, as in semantic_search
+    pub fields: Option<Vec<String>>,
+
+    /// Drop results scoring below this (0-100), as in semantic_search
+    pub min_score: Option<f32>,
+
+    /// Search misspelled identifiers as their correction, as in semantic_search
+    /// (default: false)
+    pub auto_correct: Option<bool>,
+}
+
+/// Request for a semantic search across every project of a `--multi-repo` server
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct SearchAllRequest {
+    /// The search query (natural language or code snippet)
+    pub query: String,
+
+    /// Maximum number of results over all projects (default: 10)
+    pub limit: Option<usize>,
+
+    /// Return compact results (metadata only) to save tokens (default: true)
+    pub compact: Option<bool>,
+
+    /// Only return results from files matching this path prefix or glob patterns,
+    /// relative to each project's root, as in semantic_search
+    pub filter_path: Option<String>,
+
+    /// Leave out files in vendored directories (default: false)
+    pub project_only: Option<bool>,
+
+    /// Return only these result fields, as in semantic_search
+    pub fields: Option<Vec<String>>,
+
+    /// Drop results scoring below this (0-100) within their project, as in semantic_search
+    pub min_score: Option<f32>,
+}
+
+/// Request for the chunks most similar to an already found chunk.
+/// Use this to explore code like a search result without writing a new query.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct RelatedChunksRequest {
+    /// The chunk_id of a result from semantic_search or batch_search
+    pub chunk_id: u32,
+
+    /// Maximum number of related chunks to return (default: 10)
+    pub limit: Option<usize>,
+
+    /// Return compact results (metadata only) to save tokens (default: true)
+    pub compact: Option<bool>,
+
+    /// Return only these result fields, as in semantic_search
+    pub fields: Option<Vec<String>>,
+}
+
+/// Request for the chunks around a chunk in its file.
+/// Use this to read the definitions next to a result without reading the whole file.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct NeighborsRequest {
+    /// The chunk_id of a result from any search tool
+    pub chunk_id: u32,
+
+    /// Leave out code content (default: false, since walking a file is about the code)
+    pub compact: Option<bool>,
 }
 
 /// Request to find references/call sites of a symbol.
@@ -34,16 +145,100 @@ pub struct FindReferencesRequest {
     pub limit: Option<usize>,
 }
 
+/// Request for the definition of a symbol.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct GetDefinitionRequest {
+    /// The symbol to look up (e.g., "parse_config", "UserService"); qualify a
+    /// method with its type to pick among same-named ones (e.g., "Server::handle")
+    pub symbol: String,
+}
+
+/// Request for the outline of a file.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct FileOutlineRequest {
+    /// The file, relative to the project root or absolute (e.g., "src/server.rs")
+    pub path: String,
+}
+
+/// Request for the tests that exercise a symbol.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct TestsForRequest {
+    /// The function, method or type the tests should cover (e.g., "parse_config")
+    pub symbol: String,
+
+    /// Maximum number of tests to return (default: 10)
+    pub limit: Option<usize>,
+}
+
+/// Request for the code a test exercises.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct ImplementationForRequest {
+    /// The test function's name (e.g., "test_parse_config", "TestServe")
+    pub test: String,
+
+    /// Maximum number of definitions to return (default: 10)
+    pub limit: Option<usize>,
+}
+
+/// Request for who owns a file or directory, from CODEOWNERS.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct OwnersRequest {
+    /// File or directory, relative to the project root (e.g., "src/api", "src/main.rs")
+    pub path: String,
+}
+
+/// Request to pin the database searched for the rest of the session.
+/// Use this when find_databases shows the automatically chosen index is the wrong one.
+#[derive(Debug, Deserialize, JsonSchema)]
+pub struct UseDatabaseRequest {
+    /// Project directory (or its .codesearch.db directory) whose index to search
+    pub path: String,
+}
+
+/// Database now searched by the tools - returned by use_database
+#[derive(Debug, Serialize)]
+pub struct UseDatabaseResponse {
+    pub database_path: String,
+    pub project_path: String,
+    pub model: String,
+    pub dimensions: usize,
+    /// Whether the index is kept up to date by this server's file watcher
+    pub live_updates: bool,
+    pub message: String,
+}
+
 /// Search result item - returned by semantic_search
+///
+/// Fields left out by `compact` or `fields` are omitted from the JSON.
 #[derive(Debug, Serialize)]
 pub struct SearchResultItem {
-    pub path: String,
-    pub start_line: usize,
-    pub end_line: usize,
-    pub kind: String,
-    pub score: f32,
+    /// Chunk ID, for follow-up calls such as related_chunks
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chunk_id: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub start_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+    /// high, medium or low, from the similarity to the query and the lead over the next result
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub confidence: Option<crate::search::Confidence>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub signature: Option<String>,
+    /// Owners of the file, from CODEOWNERS
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub owners: Option<Vec<String>>,
+    /// SPDX license declared in the file's header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub license: Option<String>,
+    /// Set when the file lies in a vendored (third-party) directory
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub vendored: Option<bool>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub content: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -52,6 +247,128 @@ pub struct SearchResultItem {
     pub context_next: Option<String>,
 }
 
+/// Results of one query - returned by batch_search
+#[derive(Debug, Serialize)]
+pub struct BatchSearchGroup {
+    pub query: String,
+    pub results: Vec<SearchResultItem>,
+    /// Languages the query asks for, whose results were ranked first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language_intent: Option<crate::search::LanguageIntent>,
+    /// Identifiers of the query that match nothing, with their closest indexed symbol
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub corrections: Vec<crate::search::SpellingCorrection>,
+    /// Set (and `results` empty) when nothing matched the query confidently
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub no_confident_matches: Option<crate::search::NoConfidentMatches>,
+}
+
+/// Search result of one of several projects - returned by search_all
+#[derive(Debug, Serialize)]
+pub struct ProjectResultItem {
+    /// Root of the project the result was found in
+    pub project_root: String,
+    #[serde(flatten)]
+    pub result: SearchResultItem,
+}
+
+/// Results over several projects - returned by search_all
+#[derive(Debug, Serialize)]
+pub struct SearchAllResponse {
+    pub results: Vec<ProjectResultItem>,
+    /// Roots of the projects that were searched
+    pub projects: Vec<String>,
+    /// Projects that could not be searched, with the reason
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedProject>,
+}
+
+/// A project search_all could not search
+#[derive(Debug, Serialize)]
+pub struct SkippedProject {
+    pub project_root: String,
+    pub reason: String,
+}
+
+/// Returned by semantic_search instead of results that all match poorly
+#[derive(Debug, Serialize)]
+pub struct NoConfidentMatchesResponse {
+    /// Always "no_confident_matches"
+    pub status: &'static str,
+    pub message: &'static str,
+    #[serde(flatten)]
+    pub guidance: crate::search::NoConfidentMatches,
+}
+
+/// Chunks around a chunk in its file - returned by neighbors
+///
+/// A missing side means the chunk is the first or last one of its file.
+#[derive(Debug, Serialize)]
+pub struct NeighborsResponse {
+    pub previous: Option<SearchResultItem>,
+    pub next: Option<SearchResultItem>,
+}
+
+/// Test or tested definition - returned by tests_for and implementation_for
+#[derive(Debug, Serialize)]
+pub struct TestLinkItem {
+    pub chunk_id: u32,
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub kind: String,
+    /// Name of the test (tests_for) or of the tested definition (implementation_for)
+    pub symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Calls from the test to the tested definition
+    pub calls: usize,
+    /// Whether the test's name spells the tested definition's name
+    pub name_match: bool,
+}
+
+impl From<crate::search::TestLink> for TestLinkItem {
+    fn from(link: crate::search::TestLink) -> Self {
+        Self {
+            chunk_id: link.result.id,
+            path: link.result.path,
+            start_line: link.result.start_line,
+            end_line: link.result.end_line,
+            kind: link.result.kind,
+            symbol: link.symbol,
+            signature: link.result.signature,
+            calls: link.calls,
+            name_match: link.name_match,
+        }
+    }
+}
+
+/// Owners of a file or directory - returned by owners
+#[derive(Debug, Serialize)]
+pub struct OwnersResponse {
+    pub path: String,
+    /// CODEOWNERS file the rules came from
+    pub source: String,
+    /// Owners of the file, or of the directory's files (most files first)
+    pub owners: Vec<String>,
+    /// CODEOWNERS pattern that assigns the file's owners
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rule: Option<String>,
+    /// For a directory: indexed files per owner
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub files_by_owner: Vec<OwnerFiles>,
+    /// For a directory: indexed files no rule assigns owners to
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unowned_files: Option<usize>,
+}
+
+/// An owner and the number of files they own in a directory
+#[derive(Debug, Serialize)]
+pub struct OwnerFiles {
+    pub owner: String,
+    pub files: usize,
+}
+
 /// Reference/call site item - returned by find_references
 #[derive(Debug, Serialize)]
 pub struct ReferenceItem {
@@ -68,6 +385,75 @@ pub struct ReferenceItem {
     pub score: f32,
 }
 
+impl From<crate::search::Reference> for ReferenceItem {
+    fn from(reference: crate::search::Reference) -> Self {
+        Self {
+            path: reference.path,
+            line: reference.line,
+            kind: reference.kind,
+            signature: reference.signature,
+            score: reference.score,
+        }
+    }
+}
+
+/// Outline of a file - returned by file_outline
+#[derive(Debug, Serialize)]
+pub struct FileOutlineResponse {
+    /// Indexed path of the file
+    pub path: String,
+    /// The file's chunks, ordered by line
+    pub chunks: Vec<OutlineItem>,
+}
+
+/// One chunk of a file outline
+#[derive(Debug, Serialize)]
+pub struct OutlineItem {
+    pub chunk_id: u32,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// The kind of chunk (e.g., "Struct", "Method", "Imports")
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+}
+
+/// Definition of a symbol - returned by get_definition
+#[derive(Debug, Serialize)]
+pub struct DefinitionItem {
+    pub chunk_id: u32,
+    pub path: String,
+    pub start_line: usize,
+    pub end_line: usize,
+    /// The kind of the definition (e.g., "Struct", "Function", "Method")
+    pub kind: String,
+    /// Name the definition declares
+    pub symbol: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub signature: Option<String>,
+    /// Breadcrumbs (e.g., "File: server.rs > Impl: Server > Method: handle")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+    /// FTS relevance score
+    pub score: f32,
+}
+
+impl From<crate::search::Definition> for DefinitionItem {
+    fn from(definition: crate::search::Definition) -> Self {
+        Self {
+            chunk_id: definition.result.id,
+            path: definition.result.path,
+            start_line: definition.result.start_line,
+            end_line: definition.result.end_line,
+            kind: definition.result.kind,
+            symbol: definition.symbol,
+            signature: definition.result.signature,
+            context: definition.result.context,
+            score: definition.score,
+        }
+    }
+}
+
 /// Index status response
 #[derive(Debug, Serialize)]
 pub struct IndexStatusResponse {
@@ -81,10 +467,86 @@ pub struct IndexStatusResponse {
     pub model: String,
     pub dimensions: usize,
     pub max_chunk_id: u32,
+    /// Language with the most chunks, docs and config aside; boosted in search
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub primary_language: Option<String>,
+    /// Indexed files and chunks per language, most chunks first
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub languages: Vec<crate::cache::LanguageStats>,
     pub db_path: String,
     pub project_path: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub error_message: Option<String>,
+    /// Machine-readable error kind when `status` is "error"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error_kind: Option<ToolErrorKind>,
+    /// Embedding model state: "not_loaded", "model_downloading", "ready", "model_failed"
+    pub model_status: String,
+    /// Last model load error when `model_status` is "model_failed"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_error: Option<String>,
+    /// Files skipped after repeatedly failing to index
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failing_files: Vec<FailingFileInfo>,
+    /// File watcher running and applying changes to the index
+    pub watcher_active: bool,
+    /// Another instance owns the writer lock: this one searches but never updates
+    pub readonly: bool,
+    /// When this server last brought the index up to date (RFC 3339)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_refresh_time: Option<String>,
+    /// File events waiting for the next watcher batch
+    pub pending_events: usize,
+    /// Background operation currently writing to the index:
+    /// "incremental_refresh", "branch_refresh", "resync" or "file_batch"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub current_operation: Option<String>,
+    /// Progress of the background index build when `status` is "building"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub progress: Option<IndexProgressInfo>,
+}
+
+/// Progress of a background index build or refresh
+#[derive(Debug, Serialize)]
+pub struct IndexProgressInfo {
+    pub files_indexed: usize,
+    pub files_total: usize,
+    pub percent_complete: f32,
+}
+
+impl IndexProgressInfo {
+    pub fn new(files_indexed: usize, files_total: usize) -> Self {
+        let percent_complete = if files_total == 0 {
+            100.0
+        } else {
+            (files_indexed as f32 * 1000.0 / files_total as f32).round() / 10.0
+        };
+        Self {
+            files_indexed,
+            files_total,
+            percent_complete,
+        }
+    }
+}
+
+/// A file that repeatedly fails to chunk or embed
+#[derive(Debug, Serialize)]
+pub struct FailingFileInfo {
+    pub path: String,
+    /// Consecutive failures
+    pub failures: u32,
+    pub last_error: String,
+    /// Circuit breaker open: retried at most hourly
+    pub quarantined: bool,
+}
+
+/// Whether a discovered database has all its components
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DatabaseHealth {
+    Valid,
+    /// Missing metadata.json, data.mdb or fts/ (e.g. an interrupted first index)
+    Incomplete,
 }
 
 /// Database info response
@@ -97,6 +559,13 @@ pub struct DatabaseInfoResponse {
     pub total_chunks: usize,
     pub total_files: usize,
     pub model: String,
+    pub health: DatabaseHealth,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_components: Vec<String>,
+    /// When the index was last updated (RFC 3339)
+    pub last_indexed: Option<String>,
+    /// Indexed files changed or deleted on disk since (None = unknown)
+    pub stale_files: Option<usize>,
 }
 
 /// Find databases response
@@ -106,3 +575,36 @@ pub struct FindDatabasesResponse {
     pub message: String,
     pub current_directory: String,
 }
+
+/// Machine-readable error kind for failed tool calls
+///
+/// Returned in the `error` field of tool results flagged with `isError: true`,
+/// so agents can tell "no results" apart from a broken or missing index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToolErrorKind {
+    /// No index exists yet, or the index has not been built
+    NotIndexed,
+    /// The database is locked by another process
+    Locked,
+    /// Query embedding dimensions don't match the index
+    DimensionMismatch,
+    /// Filesystem or database I/O failure
+    Io,
+    /// The embedding model could not be loaded
+    ModelUnavailable,
+    /// The request parameters were invalid
+    InvalidRequest,
+    /// Anything else
+    Internal,
+}
+
+/// Structured error payload for failed tool calls
+#[derive(Debug, Serialize)]
+pub struct ToolErrorResponse {
+    pub error: ToolErrorKind,
+    pub message: String,
+    /// Suggested next step for the agent or user
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+}
@@ -0,0 +1,230 @@
+//! Tool usage statistics of the MCP server, for `codesearch stats --tools`
+//!
+//! Every tool call adds to the counts and latencies of its tool in the
+//! database's [`TOOL_USAGE_FILE`], and the slowest calls are kept with their
+//! query. Calls slower than `CODESEARCH_SLOW_QUERY_MS` are also logged, so
+//! they show up in the database's log file next to what the server was doing
+//! at the time.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use crate::constants::TOOL_USAGE_FILE;
+
+/// Calls kept in the slow-query list
+const MAX_SLOW_CALLS: usize = 20;
+
+/// Latest latencies kept per tool, for the percentiles
+const RECENT_LATENCIES: usize = 200;
+
+/// Query text kept per slow call
+const MAX_QUERY_CHARS: usize = 200;
+
+/// Calls from this long are logged as slow, unless configured
+const DEFAULT_SLOW_QUERY_MS: u64 = 1000;
+
+/// Latency from which a tool call is logged as slow, from
+/// `CODESEARCH_SLOW_QUERY_MS` (`0` = never)
+pub fn slow_query_ms() -> Option<u64> {
+    let ms = std::env::var("CODESEARCH_SLOW_QUERY_MS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_SLOW_QUERY_MS);
+    (ms > 0).then_some(ms)
+}
+
+/// One finished tool call
+#[derive(Debug, Clone)]
+pub struct ToolCall {
+    pub tool: String,
+    /// What the call searched for or looked up, when it takes a query
+    pub query: Option<String>,
+    pub ms: u64,
+    pub failed: bool,
+}
+
+impl ToolCall {
+    /// The query of a call from its arguments: the first of `query`,
+    /// `symbol`, `test`, `path` and `chunk_id`, or the `queries` of a batch
+    pub fn query_of(
+        arguments: Option<&serde_json::Map<String, serde_json::Value>>,
+    ) -> Option<String> {
+        let arguments = arguments?;
+        let query = ["query", "symbol", "test", "path", "chunk_id"]
+            .iter()
+            .find_map(|key| match arguments.get(*key)? {
+                serde_json::Value::String(s) => Some(s.clone()),
+                serde_json::Value::Number(n) => Some(n.to_string()),
+                _ => None,
+            })
+            .or_else(|| {
+                let queries = arguments.get("queries")?.as_array()?;
+                let queries: Vec<&str> = queries.iter().filter_map(|q| q.as_str()).collect();
+                Some(queries.join(" | "))
+            })?;
+        Some(query.chars().take(MAX_QUERY_CHARS).collect())
+    }
+}
+
+/// Calls and latencies of one tool
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolStats {
+    pub calls: u64,
+    /// Calls that returned an error
+    pub errors: u64,
+    pub total_ms: u64,
+    pub max_ms: u64,
+    /// Latencies of the latest calls, oldest first
+    pub recent_ms: Vec<u64>,
+    pub last_call: Option<DateTime<Utc>>,
+}
+
+impl ToolStats {
+    pub fn avg_ms(&self) -> u64 {
+        self.total_ms / self.calls.max(1)
+    }
+
+    /// Latency `p` percent of the latest calls stay within
+    pub fn percentile_ms(&self, p: usize) -> Option<u64> {
+        let mut sorted = self.recent_ms.clone();
+        sorted.sort_unstable();
+        let rank = (sorted.len() * p).div_ceil(100).max(1);
+        sorted.get(rank - 1).copied()
+    }
+}
+
+/// A call in the slow-query list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlowCall {
+    pub at: DateTime<Utc>,
+    pub tool: String,
+    pub query: Option<String>,
+    pub ms: u64,
+}
+
+/// Tool usage of the MCP servers on a database, kept in [`TOOL_USAGE_FILE`]
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ToolUsage {
+    /// First recorded call
+    pub since: Option<DateTime<Utc>>,
+    pub tools: BTreeMap<String, ToolStats>,
+    /// Slowest calls, slowest first
+    pub slowest: Vec<SlowCall>,
+}
+
+impl ToolUsage {
+    pub fn load(db_path: &Path) -> Option<Self> {
+        let content = std::fs::read_to_string(db_path.join(TOOL_USAGE_FILE)).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    /// Add `call` to the usage recorded in `db_path`
+    pub fn record(db_path: &Path, call: &ToolCall) -> Result<()> {
+        let mut usage = Self::load(db_path).unwrap_or_default();
+        usage.add(call, Utc::now());
+        std::fs::write(
+            db_path.join(TOOL_USAGE_FILE),
+            serde_json::to_string_pretty(&usage)?,
+        )?;
+        Ok(())
+    }
+
+    fn add(&mut self, call: &ToolCall, at: DateTime<Utc>) {
+        self.since.get_or_insert(at);
+        let stats = self.tools.entry(call.tool.clone()).or_default();
+        stats.calls += 1;
+        stats.errors += call.failed as u64;
+        stats.total_ms += call.ms;
+        stats.max_ms = stats.max_ms.max(call.ms);
+        stats.recent_ms.push(call.ms);
+        if stats.recent_ms.len() > RECENT_LATENCIES {
+            stats
+                .recent_ms
+                .drain(..stats.recent_ms.len() - RECENT_LATENCIES);
+        }
+        stats.last_call = Some(at);
+
+        if self.slowest.len() == MAX_SLOW_CALLS
+            && self.slowest.last().is_some_and(|slow| slow.ms >= call.ms)
+        {
+            return;
+        }
+        let position = self.slowest.partition_point(|slow| slow.ms >= call.ms);
+        self.slowest.insert(
+            position,
+            SlowCall {
+                at,
+                tool: call.tool.clone(),
+                query: call.query.clone(),
+                ms: call.ms,
+            },
+        );
+        self.slowest.truncate(MAX_SLOW_CALLS);
+    }
+
+    /// Tools by number of calls, most used first
+    pub fn by_calls(&self) -> Vec<(&str, &ToolStats)> {
+        let mut tools: Vec<(&str, &ToolStats)> = self
+            .tools
+            .iter()
+            .map(|(name, stats)| (name.as_str(), stats))
+            .collect();
+        tools.sort_by(|a, b| b.1.calls.cmp(&a.1.calls).then(a.0.cmp(b.0)));
+        tools
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tool_usage() {
+        let call = |tool: &str, query: &str, ms: u64| ToolCall {
+            tool: tool.to_string(),
+            query: Some(query.to_string()),
+            ms,
+            failed: false,
+        };
+        let mut usage = ToolUsage::default();
+        let at = Utc::now();
+        for ms in 1..=30 {
+            usage.add(&call("semantic_search", &format!("q{}", ms), ms * 10), at);
+        }
+        usage.add(
+            &ToolCall {
+                failed: true,
+                ..call("find_references", "parse", 5)
+            },
+            at,
+        );
+
+        let search = &usage.tools["semantic_search"];
+        assert_eq!((search.calls, search.errors), (30, 0));
+        assert_eq!((search.avg_ms(), search.max_ms), (155, 300));
+        assert_eq!(search.percentile_ms(50), Some(150));
+        assert_eq!(search.percentile_ms(95), Some(290));
+        assert_eq!(usage.tools["find_references"].errors, 1);
+        assert_eq!(usage.by_calls()[0].0, "semantic_search");
+
+        // Only the slowest calls are kept, slowest first
+        assert_eq!(usage.slowest.len(), MAX_SLOW_CALLS);
+        assert_eq!(usage.slowest[0].query.as_deref(), Some("q30"));
+        assert_eq!(usage.slowest.last().unwrap().ms, 110);
+
+        let arguments = serde_json::json!({"queries": ["auth", "sessions"], "limit": 5});
+        assert_eq!(
+            ToolCall::query_of(arguments.as_object()).as_deref(),
+            Some("auth | sessions")
+        );
+        let arguments = serde_json::json!({"chunk_id": 42});
+        assert_eq!(
+            ToolCall::query_of(arguments.as_object()).as_deref(),
+            Some("42")
+        );
+        assert_eq!(ToolCall::query_of(None), None);
+    }
+}
@@ -1,8 +1,16 @@
-//! Output control for quiet mode and JSON output
+//! Output control for verbosity, plain output and JSON output
 //!
-//! Provides a global quiet mode flag to suppress non-essential output.
+//! Provides the global verbosity (`-q`, `-v`, `-vv`) that decides which
+//! informational output is printed and which log level applies, and a plain
+//! mode (`--no-emoji` or `CODESEARCH_NO_EMOJI`) that replaces emoji with
+//! ASCII for terminals and log aggregators that render them as mojibake.
+//! User-facing output goes through `out_print!`, `err_print!`, `info_print!`
+//! and `warn_print!` so it respects both. Data (JSON, template output, paths
+//! and code from the index) goes through `data_print!`, which prints it
+//! unchanged in plain mode.
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
 
 #[cfg(test)]
 mod tests {
@@ -81,6 +89,61 @@ mod tests {
         set_quiet(false);
     }
 
+    #[test]
+    fn test_verbosity() {
+        let _guard = TEST_LOCK.lock().unwrap();
+        let args = |args: &[&str]| {
+            let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+            Verbosity::from_args(&args)
+        };
+        assert_eq!(args(&["codesearch", "index"]), Verbosity::Normal);
+        assert_eq!(args(&["codesearch", "-v", "index"]), Verbosity::Verbose);
+        assert_eq!(args(&["codesearch", "index", "-vv"]), Verbosity::Trace);
+        assert_eq!(args(&["codesearch", "--verbose", "-v"]), Verbosity::Trace);
+        assert_eq!(args(&["codesearch", "-q", "-v"]), Verbosity::Quiet);
+        assert_eq!(args(&["codesearch", "search", "-"]), Verbosity::Normal);
+
+        // Background work is quiet, unless verbose
+        set_verbosity(Verbosity::Normal);
+        let background = background_output();
+        assert!(is_quiet());
+        set_verbosity(Verbosity::Verbose);
+        assert!(!is_quiet());
+        drop(background);
+
+        // Finishing background work keeps -q
+        set_verbosity(Verbosity::Quiet);
+        drop(background_output());
+        assert!(is_quiet());
+        set_quiet(false);
+        assert_eq!(verbosity(), Verbosity::Normal);
+        assert!(!is_quiet());
+    }
+
+    #[test]
+    fn test_plain_text() {
+        assert!(matches!(plain_text("no emoji → here"), Cow::Borrowed(_)));
+        assert_eq!(plain_text("📂 Project: /repo"), "Project: /repo");
+        assert_eq!(plain_text("   💾 Database: x"), "   Database: x");
+        assert_eq!(plain_text("⚠️  Failed to save"), "[!] Failed to save");
+        assert_eq!(plain_text("   Indexed: ✅ Yes"), "   Indexed: [OK] Yes");
+        assert_eq!(
+            plain_text("❌ No database found!"),
+            "[X] No database found!"
+        );
+        assert_eq!(plain_text("Done 🎉\nNext 🛠️  step"), "Done\nNext step");
+        assert_eq!(plain_text("1️⃣ Install"), "1 Install");
+        assert_eq!(plain_text("🚀"), "");
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(512), "512B");
+        assert_eq!(format_bytes(2048), "2.0KB");
+        assert_eq!(format_bytes(2_097_152), "2.0MB");
+        assert_eq!(format_bytes(2_147_483_648), "2.00GB");
+    }
+
     #[test]
     fn test_multiple_print_calls() {
         set_quiet(false);
@@ -95,24 +158,251 @@ mod tests {
     }
 }
 
-/// Global quiet mode flag
-static QUIET_MODE: AtomicBool = AtomicBool::new(false);
+/// How much is printed and logged
+///
+/// One setting for every subsystem: indexing progress, watcher batches,
+/// doctor checks and the console logs of the CLI, MCP and HTTP servers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    /// Results and errors only (`-q`)
+    Quiet,
+    /// Progress, summaries and info logs
+    Normal,
+    /// Debug logs, and the output of background work like watcher batches (`-v`)
+    Verbose,
+    /// Trace logs (`-vv`)
+    Trace,
+}
+
+impl Verbosity {
+    /// Verbosity of `-q` and the number of `-v`; `-q` wins
+    pub fn from_flags(quiet: bool, verbose: u8) -> Self {
+        match (quiet, verbose) {
+            (true, _) => Self::Quiet,
+            (false, 0) => Self::Normal,
+            (false, 1) => Self::Verbose,
+            (false, _) => Self::Trace,
+        }
+    }
+
+    /// Verbosity from the raw command line, for logging set up before the
+    /// arguments are parsed
+    pub fn from_args(args: &[String]) -> Self {
+        let quiet = args.iter().any(|a| a == "-q" || a == "--quiet");
+        let verbose: usize = args
+            .iter()
+            .map(|a| match a.strip_prefix('-') {
+                Some("-verbose") => 1,
+                Some(flags) if !flags.is_empty() && flags.chars().all(|c| c == 'v') => flags.len(),
+                _ => 0,
+            })
+            .sum();
+        Self::from_flags(quiet, verbose.min(u8::MAX as usize) as u8)
+    }
+
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Quiet,
+            1 => Self::Normal,
+            2 => Self::Verbose,
+            _ => Self::Trace,
+        }
+    }
+}
+
+/// Global verbosity
+static VERBOSITY: AtomicU8 = AtomicU8::new(Verbosity::Normal as u8);
+
+/// Background operations currently running (see [`background_output`])
+static BACKGROUND_OPERATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// Global plain (emoji-free) output flag
+static PLAIN_MODE: AtomicBool = AtomicBool::new(false);
+
+/// Set the global verbosity
+pub fn set_verbosity(verbosity: Verbosity) {
+    VERBOSITY.store(verbosity as u8, Ordering::SeqCst);
+}
+
+/// The global verbosity
+pub fn verbosity() -> Verbosity {
+    Verbosity::from_u8(VERBOSITY.load(Ordering::SeqCst))
+}
 
 /// Enable quiet mode (suppresses informational output)
 pub fn set_quiet(quiet: bool) {
-    QUIET_MODE.store(quiet, Ordering::SeqCst);
+    set_verbosity(if quiet {
+        Verbosity::Quiet
+    } else {
+        Verbosity::Normal
+    });
 }
 
-/// Check if quiet mode is enabled
+/// Check if informational output is suppressed: with `-q`, or while
+/// background work runs without `-v`
 pub fn is_quiet() -> bool {
-    QUIET_MODE.load(Ordering::SeqCst)
+    match verbosity() {
+        Verbosity::Quiet => true,
+        Verbosity::Normal => BACKGROUND_OPERATIONS.load(Ordering::SeqCst) > 0,
+        Verbosity::Verbose | Verbosity::Trace => false,
+    }
+}
+
+/// Keeps informational output quiet while it lives, unless `-v` was given
+///
+/// Background work of the servers (watcher batches, refreshes) holds one so
+/// the embedding progress of every file doesn't flood the console. Dropping
+/// it restores the verbosity the user chose.
+#[must_use]
+pub struct BackgroundOutput(());
+
+/// Start background work, see [`BackgroundOutput`]
+pub fn background_output() -> BackgroundOutput {
+    BACKGROUND_OPERATIONS.fetch_add(1, Ordering::SeqCst);
+    BackgroundOutput(())
+}
+
+impl Drop for BackgroundOutput {
+    fn drop(&mut self) {
+        BACKGROUND_OPERATIONS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Enable plain mode (emoji replaced with ASCII)
+pub fn set_plain(plain: bool) {
+    PLAIN_MODE.store(plain, Ordering::SeqCst);
+}
+
+/// Check if plain mode is enabled
+pub fn is_plain() -> bool {
+    PLAIN_MODE.load(Ordering::SeqCst)
+}
+
+/// Whether `CODESEARCH_NO_EMOJI` asks for plain output
+pub fn plain_from_env() -> bool {
+    std::env::var("CODESEARCH_NO_EMOJI")
+        .map(|v| !matches!(v.trim(), "" | "0" | "false"))
+        .unwrap_or(false)
+}
+
+/// Characters that render as emoji, including the variation selectors,
+/// joiners and keycaps that combine them
+fn is_emoji(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF
+            | 0x2600..=0x27BF
+            | 0x2300..=0x23FF
+            | 0x2B00..=0x2BFF
+            | 0xFE00..=0xFE0F
+            | 0x200D
+            | 0x20E3
+            | 0x2139
+    )
+}
+
+/// ASCII stand-in for the emoji that carry meaning
+fn ascii_symbol(c: char) -> Option<&'static str> {
+    match c {
+        '✅' | '✔' | '✓' => Some("[OK]"),
+        '❌' | '✗' | '✘' => Some("[X]"),
+        '⚠' => Some("[!]"),
+        _ => None,
+    }
+}
+
+/// `text` without emoji: success, failure and warning marks become `[OK]`,
+/// `[X]` and `[!]`, other emoji are dropped with the spaces that set them off
+pub fn plain_text(text: &str) -> Cow<'_, str> {
+    if !text.chars().any(is_emoji) {
+        return Cow::Borrowed(text);
+    }
+    let mut plain = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !is_emoji(c) {
+            plain.push(c);
+            continue;
+        }
+        // A run of emoji (with their modifiers) and the spaces after it
+        let mut symbols = String::new();
+        symbols.extend(ascii_symbol(c));
+        while let Some(&next) = chars.peek().filter(|&&next| is_emoji(next)) {
+            symbols.extend(ascii_symbol(next));
+            chars.next();
+        }
+        let mut spaces = 0;
+        while chars.next_if_eq(&' ').is_some() {
+            spaces += 1;
+        }
+        let line_end = chars.peek().is_none_or(|&next| next == '\n');
+        if !symbols.is_empty() {
+            plain.push_str(&symbols);
+            if spaces > 0 && !line_end {
+                plain.push(' ');
+            }
+        } else if line_end {
+            plain.truncate(plain.trim_end_matches(' ').len());
+        } else if !plain.is_empty() && !plain.ends_with(char::is_whitespace) {
+            plain.push_str(&" ".repeat(spaces));
+        }
+    }
+    Cow::Owned(plain)
+}
+
+/// `args` formatted for output: without emoji in plain mode
+fn formatted(args: std::fmt::Arguments<'_>) -> String {
+    let text = args.to_string();
+    if is_plain() {
+        plain_text(&text).into_owned()
+    } else {
+        text
+    }
+}
+
+/// Print a line to stdout (non-macro version of `out_print!`)
+pub fn print_out(args: std::fmt::Arguments<'_>) {
+    println!("{}", formatted(args));
+}
+
+/// Print a line of data to stdout unchanged (non-macro version of `data_print!`)
+pub fn print_data(args: std::fmt::Arguments<'_>) {
+    println!("{}", args);
+}
+
+/// `decoration` (an emoji and the space after it) as plain mode prints it,
+/// for decorating lines printed with `data_print!`
+pub fn decoration(decoration: &str) -> Cow<'_, str> {
+    if is_plain() {
+        plain_text(decoration)
+    } else {
+        Cow::Borrowed(decoration)
+    }
+}
+
+/// Format bytes in human-readable format
+pub fn format_bytes(bytes: u64) -> String {
+    if bytes < 1024 {
+        format!("{}B", bytes)
+    } else if bytes < 1024 * 1024 {
+        format!("{:.1}KB", bytes as f64 / 1024.0)
+    } else if bytes < 1024 * 1024 * 1024 {
+        format!("{:.1}MB", bytes as f64 / (1024.0 * 1024.0))
+    } else {
+        format!("{:.2}GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+    }
+}
+
+/// Print a line to stderr (non-macro version of `err_print!`)
+pub fn print_err(args: std::fmt::Arguments<'_>) {
+    eprintln!("{}", formatted(args));
 }
 
 /// Print a message only if not in quiet mode (non-macro version for better compatibility)
 /// Uses stderr to avoid corrupting stdout-based protocols (MCP, JSON output)
 pub fn print_info(args: std::fmt::Arguments<'_>) {
     if !is_quiet() {
-        eprintln!("{}", args);
+        eprintln!("{}", formatted(args));
     }
 }
 
@@ -120,10 +410,40 @@ pub fn print_info(args: std::fmt::Arguments<'_>) {
 #[allow(dead_code)] // Used by warn_print! macro
 pub fn print_warn(args: std::fmt::Arguments<'_>) {
     if !is_quiet() {
-        eprintln!("{}", args);
+        eprintln!("{}", formatted(args));
     }
 }
 
+/// Print a line to stdout, without emoji in plain mode
+#[macro_export]
+macro_rules! out_print {
+    () => {
+        $crate::output::print_out(format_args!(""))
+    };
+    ($($arg:tt)*) => {
+        $crate::output::print_out(format_args!($($arg)*))
+    };
+}
+
+/// Print a line of data to stdout, unchanged even in plain mode
+#[macro_export]
+macro_rules! data_print {
+    ($($arg:tt)*) => {
+        $crate::output::print_data(format_args!($($arg)*))
+    };
+}
+
+/// Print a line to stderr, without emoji in plain mode
+#[macro_export]
+macro_rules! err_print {
+    () => {
+        $crate::output::print_err(format_args!(""))
+    };
+    ($($arg:tt)*) => {
+        $crate::output::print_err(format_args!($($arg)*))
+    };
+}
+
 /// Print a message only if not in quiet mode
 #[macro_export]
 macro_rules! info_print {
@@ -120,6 +120,37 @@ pub fn vector_only(vector_results: &[SearchResult]) -> Vec<FusedResult> {
         .collect()
 }
 
+/// Fuse results from the code and prose embedding spaces of a dual-embedded index
+///
+/// Ranks are merged with RRF; each returned result keeps the higher of its two
+/// cosine similarities so downstream confidence checks (distance thresholds)
+/// still see a real similarity rather than an RRF score.
+pub fn fuse_vector_spaces(
+    code_results: &[SearchResult],
+    prose_results: &[SearchResult],
+    k: f32,
+) -> Vec<SearchResult> {
+    let mut fused: HashMap<u32, (f32, SearchResult)> = HashMap::new();
+
+    for results in [code_results, prose_results] {
+        for (rank, result) in results.iter().enumerate() {
+            let rrf_score = 1.0 / (k + rank as f32 + 1.0);
+            let entry = fused
+                .entry(result.id)
+                .or_insert_with(|| (0.0, result.clone()));
+            entry.0 += rrf_score;
+            if result.score > entry.1.score {
+                entry.1.score = result.score;
+                entry.1.distance = result.distance;
+            }
+        }
+    }
+
+    let mut ranked: Vec<(f32, SearchResult)> = fused.into_values().collect();
+    ranked.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(std::cmp::Ordering::Equal));
+    ranked.into_iter().map(|(_, result)| result).collect()
+}
+
 /// Reciprocal Rank Fusion with exact match boosting
 ///
 /// Three-way RRF fusion: vector, FTS, and exact matches.
@@ -256,6 +287,7 @@ mod tests {
             signature: None,
             context_prev: None,
             context_next: None,
+            text_match: false,
             distance: 0.0,
             context: None,
             docstring: None,
@@ -335,4 +367,18 @@ mod tests {
         assert_eq!(results[0].rrf_score, 0.9);
         assert!(results[0].fts_score.is_none());
     }
+
+    #[test]
+    fn test_fuse_vector_spaces() {
+        let code = vec![make_vector_result(1, 0.9), make_vector_result(2, 0.5)];
+        let prose = vec![make_vector_result(2, 0.8), make_vector_result(3, 0.7)];
+
+        let fused = fuse_vector_spaces(&code, &prose, 20.0);
+
+        // ID 2 appears in both spaces and wins; it keeps its best similarity
+        assert_eq!(fused.len(), 3);
+        assert_eq!(fused[0].id, 2);
+        assert_eq!(fused[0].score, 0.8);
+        assert!(fused.iter().any(|r| r.id == 3));
+    }
 }
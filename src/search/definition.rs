@@ -0,0 +1,193 @@
+//! Where a symbol is defined
+//!
+//! Among the chunks the full-text index finds for a name, only those whose
+//! signature (or innermost breadcrumb) declares it qualify. A qualified name
+//! (`Server::handle`, `Server.handle`) prefers definitions inside `Server`;
+//! then types win over functions and other declarations, code over tests and
+//! vendored code, and the FTS score breaks the remaining ties.
+
+use anyhow::Result;
+use std::path::Path;
+
+use super::test_links::{declared_symbol, is_test_chunk};
+use crate::file::is_vendored_path;
+use crate::fts::FtsStore;
+use crate::vectordb::{SearchResult, VectorStore};
+
+/// FTS hits inspected for a declaration; usages of a popular symbol rank
+/// alongside its definition
+const DEFINITION_CANDIDATES: usize = 100;
+
+/// The definition of a symbol
+#[derive(Debug, Clone)]
+pub struct Definition {
+    pub result: SearchResult,
+    /// Name the chunk declares
+    pub symbol: String,
+    /// FTS relevance score
+    pub score: f32,
+}
+
+/// How much a chunk kind looks like the definition of a name, higher first
+fn kind_rank(kind: &str) -> u8 {
+    match kind {
+        "Class" | "Struct" | "Enum" | "Trait" | "Interface" | "TypeAlias" => 3,
+        "Function" | "Method" | "Mod" | "Const" | "Static" => 2,
+        _ => 1,
+    }
+}
+
+/// Whether the chunk declaring `name` lies inside `qualifier`: an enclosing
+/// breadcrumb, a receiver before the name in its signature
+/// (`func (s *Server) Handle`), or its file's stem
+fn inside(result: &SearchResult, name: &str, qualifier: &str) -> bool {
+    let enclosing = result.context.as_deref().is_some_and(|context| {
+        let (enclosing, _) = context.rsplit_once(" > ").unwrap_or_default();
+        enclosing.split(" > ").any(|crumb| {
+            crumb
+                .split_once(": ")
+                .is_some_and(|(_, name)| name.trim() == qualifier)
+        })
+    });
+    let receiver = result.signature.as_deref().is_some_and(|signature| {
+        let (before, _) = signature.split_once(name).unwrap_or_default();
+        before
+            .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+            .any(|token| token == qualifier)
+    });
+    let stem = Path::new(&result.path)
+        .file_stem()
+        .is_some_and(|stem| stem == qualifier);
+    enclosing || receiver || stem
+}
+
+/// The best definition of `symbol` in the project at `project_root`, or
+/// `None` when no indexed chunk declares it
+pub fn find_definition(
+    symbol: &str,
+    fts: &FtsStore,
+    store: &VectorStore,
+    project_root: &str,
+) -> Result<Option<Definition>> {
+    let mut segments = symbol.rsplit(['.', ':']).filter(|s| !s.is_empty());
+    let Some(name) = segments.next() else {
+        return Ok(None);
+    };
+    let qualifier = segments.next();
+
+    let mut best: Option<(Definition, (bool, u8, bool, bool))> = None;
+    for hit in fts.search(name, DEFINITION_CANDIDATES, None)? {
+        let Some(result) = store.get_chunk_as_result(hit.chunk_id)? else {
+            continue;
+        };
+        if declared_symbol(&result).as_deref() != Some(name) {
+            continue;
+        }
+        let rank = (
+            qualifier.is_some_and(|qualifier| inside(&result, name, qualifier)),
+            kind_rank(&result.kind),
+            !is_test_chunk(&result),
+            !is_vendored_path(&result.path, project_root),
+        );
+        // FTS hits come best first, so the first of equal rank wins
+        if best
+            .as_ref()
+            .is_some_and(|(_, best_rank)| *best_rank >= rank)
+        {
+            continue;
+        }
+        best = Some((
+            Definition {
+                result,
+                symbol: name.to_string(),
+                score: hit.score,
+            },
+            rank,
+        ));
+    }
+    Ok(best.map(|(definition, _)| definition))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunker::{Chunk, ChunkKind};
+    use crate::embed::EmbeddedChunk;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_find_definition() -> Result<()> {
+        let dir = tempdir()?;
+        let chunks = [
+            (
+                "src/main.rs",
+                ChunkKind::Function,
+                "fn main()",
+                "File: main.rs > Function: main",
+                "fn main() { let config = Config::load(); run(config); }",
+            ),
+            (
+                "tests/config.rs",
+                ChunkKind::Function,
+                "fn load()",
+                "File: config.rs > Function: load",
+                "fn load() { Config::load(); }",
+            ),
+            (
+                "src/config.rs",
+                ChunkKind::Struct,
+                "pub struct Config",
+                "File: config.rs > Struct: Config",
+                "pub struct Config { path: String }",
+            ),
+            (
+                "src/config.rs",
+                ChunkKind::Method,
+                "pub fn load() -> Config",
+                "File: config.rs > Impl: Config > Method: load",
+                "pub fn load() -> Config { Config { path: read() } }",
+            ),
+            (
+                "src/cache.rs",
+                ChunkKind::Method,
+                "pub fn load() -> Cache",
+                "File: cache.rs > Impl: Cache > Method: load",
+                "pub fn load() -> Cache { Cache::default() }",
+            ),
+        ];
+        let mut store = VectorStore::new(&dir.path().join("db"), 4)?;
+        let mut fts = FtsStore::new(dir.path())?;
+        let embedded = chunks
+            .iter()
+            .map(|(path, kind, signature, context, content)| {
+                let mut chunk = Chunk::new(content.to_string(), 0, 5, *kind, path.to_string());
+                chunk.signature = Some(signature.to_string());
+                chunk.context = context.split(" > ").map(str::to_string).collect();
+                EmbeddedChunk::new(chunk, vec![1.0, 0.0, 0.0, 0.0])
+            })
+            .collect();
+        let ids = store.insert_chunks_with_ids(embedded)?;
+        for (id, (path, kind, signature, _, content)) in ids.iter().zip(chunks) {
+            fts.add_chunk(*id, content, path, Some(signature), &format!("{:?}", kind))?;
+        }
+        fts.commit()?;
+
+        // The struct, not the chunks that use it
+        let config = find_definition("Config", &fts, &store, "")?.unwrap();
+        assert_eq!(
+            (config.result.path.as_str(), config.result.kind.as_str()),
+            ("src/config.rs", "Struct")
+        );
+
+        // Code before tests; the qualifier picks among same-named methods
+        let load = find_definition("load", &fts, &store, "")?.unwrap();
+        assert!(load.result.path.starts_with("src/"));
+        let load = find_definition("Cache::load", &fts, &store, "")?.unwrap();
+        assert_eq!(load.result.path, "src/cache.rs");
+        let load = find_definition("Config.load", &fts, &store, "")?.unwrap();
+        assert_eq!(load.result.path, "src/config.rs");
+
+        assert!(find_definition("run", &fts, &store, "")?.is_none());
+        Ok(())
+    }
+}
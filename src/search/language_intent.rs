@@ -0,0 +1,195 @@
+//! Languages a query asks for
+//!
+//! "react hook for fetching users" wants TypeScript or JavaScript, "how are
+//! goroutines cancelled" wants Go. A query names its languages through file
+//! extensions (`.tsx`, `*.py`), language names ("rust", "c++") or idioms
+//! of one ecosystem ("goroutine", "django"). Results in those languages are
+//! boosted, so a mixed repository doesn't answer with the wrong stack; other
+//! results stay, in case the guess is wrong.
+
+use serde::Serialize;
+use std::path::Path;
+
+use crate::file::Language;
+use crate::vectordb::SearchResult;
+
+/// Words that name languages, or idioms of their ecosystems
+const LANGUAGE_CUES: &[(&str, &[Language])] = &[
+    ("rust", &[Language::Rust]),
+    ("tokio", &[Language::Rust]),
+    ("serde", &[Language::Rust]),
+    ("cargo", &[Language::Rust]),
+    ("python", &[Language::Python]),
+    ("django", &[Language::Python]),
+    ("flask", &[Language::Python]),
+    ("pytest", &[Language::Python]),
+    ("asyncio", &[Language::Python]),
+    ("numpy", &[Language::Python]),
+    ("pandas", &[Language::Python]),
+    ("golang", &[Language::Go]),
+    ("goroutine", &[Language::Go]),
+    ("goroutines", &[Language::Go]),
+    ("java", &[Language::Java]),
+    ("junit", &[Language::Java]),
+    ("javascript", &[Language::JavaScript]),
+    ("typescript", &[Language::TypeScript]),
+    ("react", &[Language::TypeScript, Language::JavaScript]),
+    ("jsx", &[Language::TypeScript, Language::JavaScript]),
+    ("tsx", &[Language::TypeScript]),
+    ("npm", &[Language::JavaScript, Language::TypeScript]),
+    ("node.js", &[Language::JavaScript, Language::TypeScript]),
+    ("nodejs", &[Language::JavaScript, Language::TypeScript]),
+    ("c++", &[Language::Cpp]),
+    ("cpp", &[Language::Cpp]),
+    ("c#", &[Language::CSharp]),
+    ("csharp", &[Language::CSharp]),
+    ("linq", &[Language::CSharp]),
+    ("ruby", &[Language::Ruby]),
+    ("rails", &[Language::Ruby]),
+    ("rspec", &[Language::Ruby]),
+    ("php", &[Language::Php]),
+    ("laravel", &[Language::Php]),
+    ("swift", &[Language::Swift]),
+    ("swiftui", &[Language::Swift]),
+    ("kotlin", &[Language::Kotlin]),
+];
+
+/// Languages inferred from a query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct LanguageIntent {
+    /// Language names (e.g. "TypeScript"), in the order the query implies them
+    pub languages: Vec<String>,
+    /// Words of the query the languages were inferred from
+    pub inferred_from: Vec<String>,
+}
+
+impl LanguageIntent {
+    /// One line for people: "TypeScript, JavaScript (from 'react')"
+    pub fn describe(&self) -> String {
+        format!(
+            "{} (from {})",
+            self.languages.join(", "),
+            self.inferred_from
+                .iter()
+                .map(|cue| format!("'{}'", cue))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+}
+
+/// Language of a `.ext` or `*.ext` token, from the extensions indexing uses
+fn extension_language(token: &str) -> Option<Language> {
+    let ext = token.strip_prefix('*').unwrap_or(token).strip_prefix('.')?;
+    if ext.is_empty() || !ext.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    Some(Language::from_extension(ext))
+        .filter(|l| !matches!(l, Language::Unknown | Language::PlainText))
+}
+
+/// The languages `query` asks for, if it names any
+pub fn detect_language_intent(query: &str) -> Option<LanguageIntent> {
+    let mut languages: Vec<Language> = Vec::new();
+    let mut inferred_from = Vec::new();
+    for word in query.split_whitespace() {
+        let token = word
+            .trim_matches(|c: char| !(c.is_alphanumeric() || "+#.*_".contains(c)))
+            .trim_end_matches(['.', '?'])
+            .to_lowercase();
+        // "react's" names React as well
+        let token = token.strip_suffix("'s").unwrap_or(&token);
+        let found: Vec<Language> = match extension_language(token) {
+            Some(language) => vec![language],
+            None => LANGUAGE_CUES
+                .iter()
+                .find(|(cue, _)| *cue == token)
+                .map(|(_, languages)| languages.to_vec())
+                .unwrap_or_default(),
+        };
+        if found.is_empty() {
+            continue;
+        }
+        inferred_from.push(token.to_string());
+        for language in found {
+            if !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+    }
+    (!languages.is_empty()).then(|| LanguageIntent {
+        languages: languages.iter().map(|l| l.name().to_string()).collect(),
+        inferred_from,
+    })
+}
+
+/// [`detect_language_intent`], unless `CODESEARCH_LANGUAGE_INTENT_BOOST` is 0
+pub fn language_intent(query: &str) -> Option<LanguageIntent> {
+    if language_intent_boost() == 0.0 {
+        return None;
+    }
+    detect_language_intent(query)
+}
+
+fn language_intent_boost() -> f32 {
+    std::env::var("CODESEARCH_LANGUAGE_INTENT_BOOST")
+        .ok()
+        .and_then(|s| s.parse::<f32>().ok())
+        .unwrap_or(crate::constants::DEFAULT_LANGUAGE_INTENT_BOOST)
+        .clamp(0.0, 1.0)
+}
+
+/// Boost results in the languages the query asks for
+pub fn boost_language_intent(results: &mut [SearchResult], intent: &LanguageIntent) {
+    let boost = language_intent_boost();
+    for result in results.iter_mut() {
+        let language = Language::from_path(Path::new(&result.path)).name();
+        if intent.languages.iter().any(|l| l == language) {
+            result.score *= 1.0 + boost;
+        }
+    }
+    // Re-sort after boosting
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_language_intent() {
+        let languages = |query: &str| detect_language_intent(query).map(|intent| intent.languages);
+
+        let react = detect_language_intent("react hook for fetching users").unwrap();
+        assert_eq!(react.languages, ["TypeScript", "JavaScript"]);
+        assert_eq!(react.inferred_from, ["react"]);
+        assert_eq!(react.describe(), "TypeScript, JavaScript (from 'react')");
+
+        assert_eq!(languages("how are goroutines cancelled?").unwrap(), ["Go"]);
+        assert_eq!(
+            languages("components in *.tsx files").unwrap(),
+            ["TypeScript"]
+        );
+        assert_eq!(
+            languages("where does the .py loader live").unwrap(),
+            ["Python"]
+        );
+        assert_eq!(
+            languages("C++ allocator, and the Rust bindings").unwrap(),
+            ["C++", "Rust"]
+        );
+        assert_eq!(
+            languages("JavaScript's event loop").unwrap(),
+            ["JavaScript"]
+        );
+
+        // Common words and file names aren't languages
+        assert_eq!(languages("go to the config loader"), None);
+        assert_eq!(languages("parse config.json at startup"), None);
+        assert_eq!(languages("java.util.List wrapper"), None);
+    }
+}
@@ -1,18 +1,32 @@
 use anyhow::Result;
 use colored::Colorize;
 use rayon::prelude::*;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
-use crate::cache::FileMetaStore;
+use crate::cache::{normalize_path, FileMetaStore, IndexMetadata};
 use crate::chunker::SemanticChunker;
 use crate::embed::{EmbeddingService, ModelType};
-use crate::file::FileWalker;
+use crate::file::{is_vendored_path, CodeOwners, FileWalker};
 use crate::fts::FtsStore;
 use crate::rerank::{rrf_fusion, vector_only, FusedResult, NeuralReranker, DEFAULT_RRF_K};
 use crate::vectordb::VectorStore;
-use crate::{info_print, warn_print};
+use crate::{data_print, info_print, out_print, warn_print};
+
+mod definition;
+mod language_intent;
+mod path_filter;
+mod references;
+mod template;
+mod test_links;
+
+pub use definition::{find_definition, Definition};
+pub use language_intent::{boost_language_intent, language_intent, LanguageIntent};
+pub use path_filter::PathFilter;
+pub use references::{find_references, Reference};
+pub use template::OutputTemplate;
+pub use test_links::{implementation_for, tests_for, TestLink};
 
 /// Configuration options for search operations
 #[derive(Debug, Clone)]
@@ -31,8 +45,14 @@ pub struct SearchOptions {
     pub sync: bool,
     /// JSON output mode
     pub json: bool,
+    /// Print each result as one line shaped by this template
+    pub template: Option<OutputTemplate>,
+    /// Echoed as `id` in JSON output, to match bulk queries to their results
+    pub query_id: Option<serde_json::Value>,
     /// Optional path filter
     pub filter_path: Option<String>,
+    /// Only search files with uncommitted changes in the git work tree
+    pub changed_only: bool,
     /// Optional model override
     pub model_override: Option<String>,
     /// Vector-only mode (skip FTS)
@@ -45,6 +65,19 @@ pub struct SearchOptions {
     pub rerank_top: Option<usize>,
     /// Automatically create index if it doesn't exist
     pub create_index: bool,
+    /// Return every chunk in the index, ranked (overrides `max_results`)
+    pub all: bool,
+    /// Print results as JSON lines while they are read instead of collecting them
+    pub stream: bool,
+    /// Search every child-directory database and merge the results when the
+    /// directory has no database of its own
+    pub merge_children: bool,
+    /// Drop results scoring below this (0-100); 0 keeps every result
+    pub min_score: Option<f32>,
+    /// Search misspelled identifiers as their closest indexed symbol
+    pub auto_correct: bool,
+    /// Leave out files in vendored (third-party) directories
+    pub project_only: bool,
 }
 
 impl Default for SearchOptions {
@@ -57,13 +90,22 @@ impl Default for SearchOptions {
             compact: false,
             sync: false,
             json: false,
+            template: None,
+            query_id: None,
             filter_path: None,
+            changed_only: false,
             model_override: None,
             vector_only: false,
             rrf_k: None,
             rerank: false,
             rerank_top: None,
             create_index: false,
+            all: false,
+            stream: false,
+            merge_children: false,
+            min_score: None,
+            auto_correct: false,
+            project_only: false,
         }
     }
 }
@@ -71,14 +113,29 @@ impl Default for SearchOptions {
 /// JSON output format for search results
 #[derive(Serialize)]
 struct JsonOutput {
+    /// `id` of the bulk query this answers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<serde_json::Value>,
     query: String,
     results: Vec<JsonResult>,
+    /// Query identifiers with no hits, and the indexed symbols closest to them
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    corrections: Vec<SpellingCorrection>,
+    /// Languages the query asks for, whose results were boosted
+    #[serde(skip_serializing_if = "Option::is_none")]
+    language_intent: Option<LanguageIntent>,
+    /// Set (and `results` empty) when nothing matched confidently
+    #[serde(skip_serializing_if = "Option::is_none")]
+    no_confident_matches: Option<NoConfidentMatches>,
     #[serde(skip_serializing_if = "Option::is_none")]
     timing: Option<JsonTiming>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, Default)]
 struct JsonResult {
+    /// Project the result came from when several databases were merged
+    #[serde(skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
     path: String,
     start_line: usize,
     end_line: usize,
@@ -86,14 +143,48 @@ struct JsonResult {
     #[serde(skip_serializing_if = "Option::is_none")]
     content: Option<String>,
     score: f32,
+    /// How far the result can be trusted (see [`score_confidence`])
+    #[serde(skip_serializing_if = "Option::is_none")]
+    confidence: Option<Confidence>,
     #[serde(skip_serializing_if = "Option::is_none")]
     signature: Option<String>,
+    /// Owners of the file, from CODEOWNERS
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    owners: Vec<String>,
+    /// SPDX license declared in the file's header
+    #[serde(skip_serializing_if = "Option::is_none")]
+    license: Option<String>,
+    /// Whether the file lies in a vendored (third-party) directory
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    vendored: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     context_prev: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     context_next: Option<String>,
 }
 
+impl JsonResult {
+    /// Compact mode leaves out the content and surrounding context
+    fn new(r: &crate::vectordb::SearchResult, compact: bool) -> Self {
+        Self {
+            project: None,
+            path: r.path.clone(),
+            start_line: r.start_line,
+            end_line: r.end_line,
+            kind: r.kind.clone(),
+            content: (!compact).then(|| r.content.clone()),
+            score: r.score,
+            confidence: None,
+            signature: r.signature.clone(),
+            owners: Vec::new(),
+            license: None,
+            vendored: false,
+            context_prev: r.context_prev.clone().filter(|_| !compact),
+            context_next: r.context_next.clone().filter(|_| !compact),
+        }
+    }
+}
+
 #[derive(Serialize)]
 struct JsonTiming {
     total_ms: u64,
@@ -110,21 +201,232 @@ fn get_db_path(path: Option<PathBuf>) -> Result<(PathBuf, PathBuf)> {
     resolve_database_with_message(path.as_deref(), "searching")
 }
 
-/// Read model metadata from database
-pub fn read_metadata(db_path: &Path) -> Option<(String, usize, Option<String>)> {
-    let metadata_path = db_path.join("metadata.json");
-    if let Ok(content) = std::fs::read_to_string(&metadata_path) {
-        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&content) {
-            let model = json.get("model_short_name")?.as_str()?.to_string();
-            let dims = json.get("dimensions")?.as_u64()? as usize;
-            let primary_language = json
-                .get("primary_language")
-                .and_then(|v| v.as_str())
-                .map(|s| s.to_string());
-            return Some((model, dims, primary_language));
-        }
-    }
-    None
+/// Embedding space(s) a query is routed to in a dual-embedded index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddingSpace {
+    /// Code model only (identifier-heavy queries)
+    Code,
+    /// Prose model only (natural-language questions)
+    Prose,
+    /// Both spaces, fused with RRF
+    Both,
+}
+
+/// Route a query to the code space, the prose space, or both
+///
+/// Identifier-heavy queries ("VectorStore::search", "parse_config") go to the
+/// code model; questions without identifiers ("how are files watched?") go
+/// to the prose model; everything else searches both spaces.
+pub fn route_query(query: &str) -> EmbeddingSpace {
+    let words: Vec<&str> = query.split_whitespace().collect();
+    let identifiers = detect_identifiers(query).len()
+        + words
+            .iter()
+            .filter(|w| w.contains("::") || w.contains('(') || w.contains('.'))
+            .count();
+
+    if identifiers > 0 && identifiers * 2 >= words.len() {
+        EmbeddingSpace::Code
+    } else if is_question(query) && identifiers == 0 {
+        EmbeddingSpace::Prose
+    } else {
+        EmbeddingSpace::Both
+    }
+}
+
+/// Words that open a question-style query
+const QUESTION_WORDS: &[&str] = &[
+    "how", "what", "where", "why", "when", "which", "who", "does", "do", "is", "are", "can",
+    "should", "explain", "describe",
+];
+
+/// Words carrying no search signal in a question ("where is the config loaded?")
+const QUESTION_FILLER_WORDS: &[&str] = &[
+    "how",
+    "what",
+    "where",
+    "why",
+    "when",
+    "which",
+    "who",
+    "whom",
+    "whose",
+    "does",
+    "do",
+    "did",
+    "is",
+    "are",
+    "was",
+    "were",
+    "be",
+    "been",
+    "can",
+    "could",
+    "should",
+    "would",
+    "will",
+    "shall",
+    "explain",
+    "describe",
+    "show",
+    "tell",
+    "find",
+    "me",
+    "us",
+    "we",
+    "i",
+    "you",
+    "it",
+    "its",
+    "this",
+    "that",
+    "there",
+    "our",
+    "my",
+    "the",
+    "a",
+    "an",
+    "of",
+    "in",
+    "on",
+    "for",
+    "to",
+    "get",
+    "gets",
+    "please",
+    "happen",
+    "happens",
+    "defined",
+    "implemented",
+    "located",
+];
+
+/// Check whether a query is phrased as a question
+fn is_question(query: &str) -> bool {
+    query.trim_end().ends_with('?')
+        || query
+            .split_whitespace()
+            .next()
+            .map(|w| QUESTION_WORDS.contains(&w.to_lowercase().as_str()))
+            .unwrap_or(false)
+}
+
+/// Rewrite a question-style query into a declarative search string
+///
+/// Interrogatives, auxiliaries and filler words are stripped so the remaining
+/// noun phrase reads like code or a doc comment:
+/// "how does the watcher debounce events?" → "watcher debounce events".
+/// Returns `None` when the query is not a question or nothing would change.
+pub fn rewrite_question(query: &str) -> Option<String> {
+    if !is_question(query) {
+        return None;
+    }
+
+    let kept: Vec<&str> = query
+        .split_whitespace()
+        .map(|w| w.trim_matches(|c: char| matches!(c, '?' | '!' | ',' | '"' | '\'' | '`')))
+        .filter(|w| !w.is_empty() && !QUESTION_FILLER_WORDS.contains(&w.to_lowercase().as_str()))
+        .collect();
+
+    let rewritten = kept.join(" ");
+    if rewritten.is_empty() || rewritten == query.trim() {
+        None
+    } else {
+        Some(rewritten)
+    }
+}
+
+/// Run a routed vector search for the original query and, if present, its
+/// declarative rewrite, fusing both result lists with RRF
+pub fn search_query_forms(
+    store: &VectorStore,
+    code_embedding: &[f32],
+    rewritten_embedding: Option<&[f32]>,
+    prose_embedding: Option<&[f32]>,
+    space: EmbeddingSpace,
+    limit: usize,
+) -> Result<Vec<crate::vectordb::SearchResult>> {
+    let results = search_embedding_spaces(store, code_embedding, prose_embedding, space, limit)?;
+    match rewritten_embedding {
+        Some(embedding) => {
+            let rewritten_results = store.search(embedding, limit)?;
+            Ok(crate::rerank::fuse_vector_spaces(
+                &results,
+                &rewritten_results,
+                DEFAULT_RRF_K,
+            ))
+        }
+        None => Ok(results),
+    }
+}
+
+/// Full-text search for the original query and, if present, its declarative
+/// rewrite; a chunk matched by both keeps its higher BM25 score
+pub fn search_fts_query_forms(
+    fts_store: &FtsStore,
+    query: &str,
+    rewritten: Option<&str>,
+    limit: usize,
+    structural_intent: Option<crate::chunker::ChunkKind>,
+) -> Result<Vec<crate::fts::FtsResult>> {
+    let mut results = fts_store.search(query, limit, structural_intent)?;
+    if let Some(rewritten) = rewritten {
+        for result in fts_store.search(rewritten, limit, structural_intent)? {
+            match results.iter_mut().find(|r| r.chunk_id == result.chunk_id) {
+                Some(existing) => existing.score = existing.score.max(result.score),
+                None => results.push(result),
+            }
+        }
+        results.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        results.truncate(limit);
+    }
+    Ok(results)
+}
+
+/// Combine code-space and prose-space vector results for a routed query
+///
+/// Falls back to the code results when the prose space returned nothing
+/// (e.g. the index is not dual-embedded).
+pub fn combine_embedding_spaces(
+    space: EmbeddingSpace,
+    code_results: Vec<crate::vectordb::SearchResult>,
+    prose_results: Vec<crate::vectordb::SearchResult>,
+) -> Vec<crate::vectordb::SearchResult> {
+    if prose_results.is_empty() {
+        return code_results;
+    }
+    match space {
+        EmbeddingSpace::Code => code_results,
+        EmbeddingSpace::Prose => prose_results,
+        EmbeddingSpace::Both => {
+            crate::rerank::fuse_vector_spaces(&code_results, &prose_results, DEFAULT_RRF_K)
+        }
+    }
+}
+
+/// Run a vector search in the embedding space(s) a query was routed to
+///
+/// `prose_embedding` is the query embedded with the index's prose model; it is
+/// ignored for code-routed queries and for indexes without a prose space.
+pub fn search_embedding_spaces(
+    store: &VectorStore,
+    code_embedding: &[f32],
+    prose_embedding: Option<&[f32]>,
+    space: EmbeddingSpace,
+    limit: usize,
+) -> Result<Vec<crate::vectordb::SearchResult>> {
+    let code_results = store.search(code_embedding, limit)?;
+    let prose_results = match prose_embedding {
+        Some(embedding) if space != EmbeddingSpace::Code && store.has_prose_index() => {
+            store.search_prose(embedding, limit)?
+        }
+        _ => Vec::new(),
+    };
+    Ok(combine_embedding_spaces(space, code_results, prose_results))
 }
 
 /// Detect if query contains likely code identifiers
@@ -159,6 +461,115 @@ pub fn detect_identifiers(query: &str) -> Vec<String> {
     identifiers
 }
 
+/// A query identifier that matches nothing in the index, and the closest indexed symbol
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct SpellingCorrection {
+    pub from: String,
+    pub to: String,
+    /// Whether the query was searched with `to` substituted
+    pub applied: bool,
+}
+
+/// Edits allowed between a misspelled identifier and its correction
+/// (one for identifiers shorter than six characters)
+const MAX_SPELLING_EDITS: usize = 2;
+
+/// Corrections for the query identifiers that have no full-text hits
+///
+/// The closest signature term is looked up in the chunk it came from, to
+/// return the symbol as written rather than as the lowercase index term.
+pub fn correct_identifiers(
+    query: &str,
+    fts: &FtsStore,
+    store: &VectorStore,
+) -> Vec<SpellingCorrection> {
+    let mut corrections = Vec::new();
+    for identifier in detect_identifiers(query) {
+        if !fts
+            .search(&identifier, 1, None)
+            .map_or(true, |hits| hits.is_empty())
+        {
+            continue;
+        }
+        let max_edits = if identifier.chars().count() < 6 {
+            1
+        } else {
+            MAX_SPELLING_EDITS
+        };
+        let Ok(Some((term, chunk_id))) = fts.closest_signature_term(&identifier, max_edits) else {
+            continue;
+        };
+        let written = store
+            .get_chunk_as_result(chunk_id)
+            .ok()
+            .flatten()
+            .and_then(|chunk| {
+                chunk
+                    .signature?
+                    .split(|c: char| !c.is_alphanumeric())
+                    .find(|word| word.to_lowercase() == term)
+                    .map(str::to_string)
+            });
+        corrections.push(SpellingCorrection {
+            from: identifier,
+            to: written.unwrap_or(term),
+            applied: false,
+        });
+    }
+    corrections
+}
+
+/// `query` with every correction substituted, marking the ones it contained applied
+pub fn apply_corrections(query: &str, corrections: &mut [SpellingCorrection]) -> String {
+    query
+        .split_whitespace()
+        .map(
+            |token| match corrections.iter_mut().find(|c| c.from == token) {
+                Some(correction) => {
+                    correction.applied = true;
+                    correction.to.clone()
+                }
+                None => token.to_string(),
+            },
+        )
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Symbols added to the query per acronym
+const ACRONYM_EXPANSIONS: usize = 2;
+
+/// `query` with the symbols its acronyms abbreviate added after them
+///
+/// All-caps words of three or more letters ("HSM", "HSMs") are looked up in
+/// the store's acronym index: "HSM timeout" becomes
+/// "HSM (HierarchicalStateMachine) timeout". `None` when nothing expands.
+pub fn expand_acronyms(query: &str, store: &VectorStore) -> Option<String> {
+    let mut expanded = false;
+    let words: Vec<String> = query
+        .split_whitespace()
+        .map(|word| {
+            let bare = word.trim_matches(|c: char| !c.is_alphanumeric());
+            let acronym = bare.strip_suffix('s').unwrap_or(bare);
+            if acronym.len() < 3 || !acronym.chars().all(|c| c.is_ascii_uppercase()) {
+                return word.to_string();
+            }
+            let symbols = store.acronym_symbols(acronym).unwrap_or_default();
+            if symbols.is_empty() {
+                return word.to_string();
+            }
+            expanded = true;
+            let names: Vec<&str> = symbols
+                .iter()
+                .take(ACRONYM_EXPANSIONS)
+                .map(String::as_str)
+                .collect();
+            format!("{} ({})", word, names.join(" "))
+        })
+        .collect();
+    expanded.then(|| words.join(" "))
+}
+
 /// Detects structural intent in user queries (e.g., "class X", "function foo")
 /// Returns the ChunkKind that matches the intent, if any
 ///
@@ -251,6 +662,422 @@ pub fn boost_kind(
     results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
 }
 
+/// Boost results in the project's main languages (see `IndexMetadata::language_shares`)
+///
+/// Each of the top languages gets the boost in proportion to its chunks
+/// relative to the largest: a single-language project boosts that language
+/// fully, a Go/C++ project of equal halves boosts both alike.
+pub fn boost_languages(results: &mut [crate::vectordb::SearchResult], metadata: &IndexMetadata) {
+    let boost = std::env::var("CODESEARCH_LANGUAGE_BOOST")
+        .ok()
+        .and_then(|s| s.parse::<f32>().ok())
+        .unwrap_or(crate::constants::DEFAULT_LANGUAGE_BOOST)
+        .clamp(0.0, 1.0);
+    let shares = metadata.language_shares(crate::constants::LANGUAGE_BOOST_TOP_LANGUAGES);
+    if boost == 0.0 || shares.is_empty() {
+        return;
+    }
+
+    for result in results.iter_mut() {
+        let language = crate::file::Language::from_path(Path::new(&result.path));
+        if let Some((_, share)) = shares.iter().find(|(l, _)| *l == language) {
+            result.score *= 1.0 + boost * share;
+        }
+    }
+    // Re-sort after boosting
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// Check whether a path is a test fixture, snapshot, golden file or minified asset
+///
+/// These files often contain copies of production code and drown out the
+/// real implementation in results.
+pub fn is_hard_negative_path(path: &str) -> bool {
+    let normalized = crate::cache::normalize_path_str(path).to_lowercase();
+    let components: Vec<&str> = normalized.split('/').collect();
+    let file_name = components.last().copied().unwrap_or("");
+
+    let in_fixtures = components.windows(2).any(|pair| {
+        matches!(pair[0], "tests" | "test" | "__tests__") && matches!(pair[1], "fixtures")
+    });
+    let in_snapshots = components
+        .iter()
+        .any(|c| *c == "__snapshots__" || *c == "golden" || *c == "goldens");
+
+    in_fixtures
+        || in_snapshots
+        || file_name.ends_with(".snap")
+        || file_name.contains(".golden")
+        || file_name.contains(".min.")
+}
+
+/// Demote fixture, snapshot, golden and minified results (see `is_hard_negative_path`)
+///
+/// Skipped when the query itself asks for such files ("snapshot for parser").
+pub fn penalize_hard_negatives(results: &mut [crate::vectordb::SearchResult], query: &str) {
+    let penalty = std::env::var("CODESEARCH_FIXTURE_PENALTY")
+        .ok()
+        .and_then(|s| s.parse::<f32>().ok())
+        .unwrap_or(crate::constants::DEFAULT_FIXTURE_PENALTY)
+        .clamp(0.0, 1.0);
+    let query_lower = query.to_lowercase();
+    let targets_fixtures = ["fixture", "snapshot", "golden", "minified", ".min."]
+        .iter()
+        .any(|w| query_lower.contains(w));
+    if penalty == 0.0 || targets_fixtures {
+        return;
+    }
+
+    for result in results.iter_mut() {
+        if is_hard_negative_path(&result.path) {
+            result.score *= 1.0 - penalty;
+        }
+    }
+    // Re-sort after penalizing
+    results.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+}
+
+/// How far a result can be trusted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Confidence {
+    /// Close to the query and clearly ahead of the next result
+    High,
+    /// Related to the query, or close but with runners-up just as good
+    Medium,
+    /// Little similarity to the query; rephrasing may find better matches
+    Low,
+}
+
+impl std::fmt::Display for Confidence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Confidence::High => "high",
+            Confidence::Medium => "medium",
+            Confidence::Low => "low",
+        })
+    }
+}
+
+/// Scores scaled to 0-100 (the top result scores 100), with a confidence each
+///
+/// Fused scores only mean something relative to each other, so the confidence
+/// comes from each result's vector distance to the query and its lead over the
+/// next result. `results` must be sorted by score.
+pub fn score_confidence(results: &[crate::vectordb::SearchResult]) -> Vec<(f32, Confidence)> {
+    use crate::constants::{
+        CONFIDENCE_HIGH_MAX_DISTANCE, CONFIDENCE_HIGH_MIN_LEAD, CONFIDENCE_MEDIUM_MAX_DISTANCE,
+    };
+
+    let top = results.first().map_or(0.0, |r| r.score);
+    results
+        .iter()
+        .enumerate()
+        .map(|(i, result)| {
+            let normalized = if top > 0.0 {
+                (result.score / top * 1000.0).round() / 10.0
+            } else {
+                0.0
+            };
+            let lead = match results.get(i + 1) {
+                Some(next) if result.score > 0.0 => (result.score - next.score) / result.score,
+                Some(_) => 0.0,
+                None => 1.0,
+            };
+            let confidence = if result.distance <= CONFIDENCE_HIGH_MAX_DISTANCE
+                && lead >= CONFIDENCE_HIGH_MIN_LEAD
+            {
+                Confidence::High
+            } else if result.distance <= CONFIDENCE_MEDIUM_MAX_DISTANCE {
+                Confidence::Medium
+            } else {
+                Confidence::Low
+            };
+            (normalized.clamp(0.0, 100.0), confidence)
+        })
+        .collect()
+}
+
+/// Whether `min_score` asks for weak results to be dropped
+///
+/// The distance cut-offs don't depend on the embedding model, so results are
+/// only filtered when the caller opts in with a positive `min_score`.
+pub fn thresholding_requested(min_score: Option<f32>) -> bool {
+    min_score.is_some_and(|min_score| min_score > 0.0)
+}
+
+/// Drop results too weak to be worth reading, when `min_score` asks for it
+///
+/// Without a positive `min_score` every result is kept. With one, results
+/// unrelated to the query (farther than [`IRRELEVANT_DISTANCE`]) and results
+/// scoring below it (0-100, see [`score_confidence`]) go. Results that
+/// full-text or exact identifier search found are always kept.
+///
+/// [`IRRELEVANT_DISTANCE`]: crate::constants::IRRELEVANT_DISTANCE
+pub fn apply_min_score(
+    results: Vec<crate::vectordb::SearchResult>,
+    min_score: Option<f32>,
+) -> Vec<crate::vectordb::SearchResult> {
+    if !thresholding_requested(min_score) {
+        return results;
+    }
+    let min_score = min_score.unwrap_or_default();
+    let scored = score_confidence(&results);
+    results
+        .into_iter()
+        .zip(scored)
+        .filter(|(result, (score, _))| {
+            result.text_match
+                || (result.distance <= crate::constants::IRRELEVANT_DISTANCE && *score >= min_score)
+        })
+        .map(|(result, _)| result)
+        .collect()
+}
+
+/// Ways to rephrase a query that found no confident matches
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NoConfidentMatches {
+    /// Code identifiers in the query, worth looking up exactly
+    pub identifiers: Vec<String>,
+    /// Symbols of the closest (low confidence) results
+    pub nearby_symbols: Vec<String>,
+    /// Rephrased queries to try
+    pub reformulations: Vec<String>,
+}
+
+/// Symbols suggested when nothing matches confidently
+const NEARBY_SYMBOLS: usize = 5;
+
+/// Guidance for a query whose results are all low confidence (or empty)
+///
+/// Returns `None` when at least one result is a confident match, or was
+/// found by full-text or exact identifier search.
+pub fn no_confident_matches(
+    query: &str,
+    results: &[crate::vectordb::SearchResult],
+) -> Option<NoConfidentMatches> {
+    if results.iter().any(|r| r.text_match)
+        || score_confidence(results)
+            .iter()
+            .any(|(_, confidence)| *confidence != Confidence::Low)
+    {
+        return None;
+    }
+
+    let identifiers = detect_identifiers(query);
+    let mut nearby_symbols: Vec<String> = Vec::new();
+    for name in results
+        .iter()
+        .filter_map(|r| r.signature.as_deref().and_then(symbol_name))
+    {
+        if !nearby_symbols.contains(&name) && !identifiers.contains(&name) {
+            nearby_symbols.push(name);
+        }
+        if nearby_symbols.len() == NEARBY_SYMBOLS {
+            break;
+        }
+    }
+
+    // Identifiers spelled as words match prose and differently named code
+    let mut reformulations = Vec::new();
+    if !identifiers.is_empty() {
+        let spelled: Vec<String> = query
+            .split_whitespace()
+            .map(|token| {
+                if identifiers.iter().any(|id| id == token) {
+                    identifier_words(token)
+                } else {
+                    token.to_string()
+                }
+            })
+            .collect();
+        reformulations.push(spelled.join(" "));
+    }
+    for symbol in nearby_symbols.iter().take(3) {
+        let words = identifier_words(symbol);
+        if !reformulations.contains(&words) {
+            reformulations.push(words);
+        }
+    }
+
+    Some(NoConfidentMatches {
+        identifiers,
+        nearby_symbols,
+        reformulations,
+    })
+}
+
+/// Name declared by a signature (`pub fn load_config(path: &Path)` → `load_config`)
+fn symbol_name(signature: &str) -> Option<String> {
+    const DECLARATIONS: [&str; 12] = [
+        "fn",
+        "def",
+        "function",
+        "func",
+        "class",
+        "struct",
+        "enum",
+        "trait",
+        "interface",
+        "type",
+        "module",
+        "mod",
+    ];
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let head = signature.lines().next()?;
+    let tokens: Vec<&str> = head
+        .split(|c| !is_ident(c))
+        .filter(|t| !t.is_empty())
+        .collect();
+    let declared = tokens
+        .windows(2)
+        .find(|pair| DECLARATIONS.contains(&pair[0]))
+        .map(|pair| pair[1]);
+    // Otherwise the name before the parameter list (`def`-less methods)
+    let called = || {
+        let (before, _) = head.split_once('(')?;
+        before.split(|c| !is_ident(c)).rfind(|t| !t.is_empty())
+    };
+    declared
+        .or_else(called)
+        .filter(|name| !name.starts_with(|c: char| c.is_ascii_digit()))
+        .map(str::to_string)
+}
+
+/// `parseConfig`, `parse_config` → `parse config`
+fn identifier_words(identifier: &str) -> String {
+    let mut words = String::new();
+    let mut prev_lower = false;
+    for c in identifier.chars() {
+        if c == '_' || c == '-' {
+            if !words.ends_with(' ') && !words.is_empty() {
+                words.push(' ');
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower {
+            words.push(' ');
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        words.extend(c.to_lowercase());
+    }
+    words.trim_end().to_string()
+}
+
+/// Merge matching chunks of a file that touch or overlap into one result
+///
+/// Adjacent chunks repeat each other's code in `context_prev`/`context_next`,
+/// so returning both wastes tokens. Chunks count as adjacent when their context
+/// windows meet (at most `DEFAULT_CONTEXT_LINES` lines apart). The merged result
+/// keeps the rank, score and signature of the better match and covers the
+/// combined line range. Results must be sorted by score.
+pub fn merge_adjacent_results(
+    results: Vec<crate::vectordb::SearchResult>,
+) -> Vec<crate::vectordb::SearchResult> {
+    use crate::chunker::DEFAULT_CONTEXT_LINES;
+
+    let mut merged: Vec<crate::vectordb::SearchResult> = Vec::with_capacity(results.len());
+    for result in results {
+        let adjacent = merged.iter_mut().find(|kept| {
+            kept.path == result.path
+                && result.start_line <= kept.end_line + DEFAULT_CONTEXT_LINES
+                && kept.start_line <= result.end_line + DEFAULT_CONTEXT_LINES
+        });
+        let Some(kept) = adjacent else {
+            merged.push(result);
+            continue;
+        };
+
+        let (first, second) = if kept.start_line <= result.start_line {
+            (&*kept, &result)
+        } else {
+            (&result, &*kept)
+        };
+        let mut content = first.content.clone();
+        if second.end_line > first.end_line {
+            let mut lines: Vec<&str> = Vec::new();
+            if second.start_line > first.end_line {
+                // The gap between them is the start of the first chunk's context_next
+                let gap = second.start_line - first.end_line;
+                lines.extend(first.context_next.iter().flat_map(|c| c.lines()).take(gap));
+                lines.extend(second.content.lines());
+            } else {
+                let overlap = first.end_line - second.start_line;
+                lines.extend(second.content.lines().skip(overlap));
+            }
+            for line in lines {
+                content.push('\n');
+                content.push_str(line);
+            }
+        }
+        let context_prev = first.context_prev.clone();
+        let (end_line, context_next) = if second.end_line > first.end_line {
+            (second.end_line, second.context_next.clone())
+        } else {
+            (first.end_line, first.context_next.clone())
+        };
+        kept.start_line = kept.start_line.min(result.start_line);
+        kept.end_line = end_line;
+        kept.content = content;
+        kept.context_prev = context_prev;
+        kept.context_next = context_next;
+    }
+    merged
+}
+
+/// Read the context lines around results from the files on disk
+///
+/// Indexes built with fewer than `DEFAULT_CONTEXT_LINES` context lines (see
+/// `CODESEARCH_CONTEXT_LINES`) store shorter windows, or none. Results get
+/// the default windows back from their file, so displays and merging of
+/// adjacent results work as usual. Files that no longer match a chunk at its
+/// lines are left alone. `stored_lines` is the index's setting.
+pub fn expand_context(
+    results: &mut [crate::vectordb::SearchResult],
+    project_path: &Path,
+    stored_lines: usize,
+) {
+    use crate::chunker::{context_windows, DEFAULT_CONTEXT_LINES};
+
+    if stored_lines >= DEFAULT_CONTEXT_LINES {
+        return;
+    }
+    let mut sources: std::collections::HashMap<String, Option<String>> =
+        std::collections::HashMap::new();
+    for result in results {
+        let source = sources
+            .entry(result.path.clone())
+            .or_insert_with(|| std::fs::read_to_string(project_path.join(&result.path)).ok());
+        let Some(source) = source else {
+            continue;
+        };
+        let lines: Vec<&str> = source.lines().collect();
+        let unchanged = result.end_line <= lines.len()
+            && lines.get(result.start_line).map(|line| line.trim())
+                == result.content.lines().next().map(str::trim);
+        if !unchanged {
+            continue;
+        }
+        let (prev, next) = context_windows(
+            &lines,
+            result.start_line,
+            result.end_line,
+            DEFAULT_CONTEXT_LINES,
+        );
+        result.context_prev = prev;
+        result.context_next = next;
+    }
+}
+
 /// Expand query with variants for better matching
 ///
 /// OPTIMIZATION: Generate fewer, more targeted variants based on query complexity.
@@ -407,6 +1234,31 @@ pub fn adapt_rrf_k(query: &str) -> (f64, f64) {
 
 /// Search the codebase
 pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions) -> Result<()> {
+    if options.merge_children {
+        let children = crate::db_discovery::child_databases_to_merge(path.as_deref())?;
+        if !children.is_empty() {
+            return search_children(query, children, options);
+        }
+    }
+
+    let Some((db_path, project_path)) = resolve_database(path, &options).await? else {
+        return Ok(());
+    };
+
+    let mut options = options;
+    let Some(run) = search_database(query, &db_path, &project_path, &mut options)? else {
+        return Ok(());
+    };
+    print_results(query, run, &options, &[])
+}
+
+/// Database and project path to search, creating the index with `options.create_index`
+///
+/// `None`, after telling the user how to create one, when there is no index.
+async fn resolve_database(
+    path: Option<PathBuf>,
+    options: &SearchOptions,
+) -> Result<Option<(PathBuf, PathBuf)>> {
     let (db_path, project_path) = get_db_path(path.clone())?;
 
     if !db_path.exists() {
@@ -420,63 +1272,332 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
             crate::index::index_quiet(path, false, cancel_token).await?;
             crate::output::print_info(format_args!("{}", "✅ Index created successfully!".green()));
         } else {
-            println!("{}", "❌ No database found!".red());
-            println!("   Run {} first", "codesearch index".bright_cyan());
-            println!();
-            println!(
+            out_print!("{}", "❌ No database found!".red());
+            out_print!("   Run {} first", "codesearch index".bright_cyan());
+            out_print!();
+            out_print!(
                 "{}",
                 "💡 Tip: Use --create-index=true to automatically create the index.".dimmed()
             );
-            println!(
+            out_print!(
                 "{}",
                 "💡 Tip: codesearch can find databases in parent directories. Use 'codesearch list' to see all indexed projects.".dimmed()
             );
-            return Ok(());
+            return Ok(None);
         }
     }
+    Ok(Some((db_path, project_path)))
+}
+
+/// One line of `--stdin` input given as JSON
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BulkQuery {
+    query: String,
+    /// Echoed back as `id` with the results
+    #[serde(default)]
+    id: Option<serde_json::Value>,
+    /// Overrides `--max-results` for this query
+    #[serde(default)]
+    max_results: Option<usize>,
+    /// Overrides `--filter-path` for this query
+    #[serde(default)]
+    filter_path: Option<String>,
+}
+
+impl BulkQuery {
+    /// A JSON object with a `query`, or else the whole line as the query
+    fn parse(line: &str) -> Result<Self> {
+        if line.starts_with('{') {
+            return serde_json::from_str(line)
+                .map_err(|e| anyhow::anyhow!("Invalid query object: {}", e));
+        }
+        Ok(Self {
+            query: line.to_string(),
+            id: None,
+            max_results: None,
+            filter_path: None,
+        })
+    }
+}
+
+/// Output line of a bulk query that failed
+#[derive(Serialize)]
+struct BulkError {
+    /// Input line number, from 1
+    line: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id: Option<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    query: Option<String>,
+    error: String,
+}
+
+/// Search each query read from stdin, one per line, printing one JSON line per query
+///
+/// Lines are plain queries or JSON objects (see [`BulkQuery`]). The database
+/// and model are loaded once for all of them. A query that fails prints an
+/// `error` line and the rest still run.
+pub async fn search_stdin(path: Option<PathBuf>, options: SearchOptions) -> Result<()> {
+    use std::io::BufRead;
+
+    let Some((db_path, project_path)) = resolve_database(path, &options).await? else {
+        return Ok(());
+    };
+    let mut options = SearchOptions {
+        json: true,
+        ..options
+    };
+    let mut session = SearchSession::open(&db_path, &project_path, &mut options)?;
 
-    // Read model metadata from database FIRST (needed for sync)
-    let (model_type, dimensions, primary_language) =
-        if let Some(ref model_name) = options.model_override {
-            // User specified a model - use it (warning: may not match indexed data!)
+    for (index, line) in std::io::stdin().lock().lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = match BulkQuery::parse(line) {
+            Ok(bulk) => match run_bulk_query(&mut session, &bulk, &options) {
+                Ok(()) => continue,
+                Err(e) => BulkError {
+                    line: index + 1,
+                    id: bulk.id,
+                    query: Some(bulk.query),
+                    error: format!("{:#}", e),
+                },
+            },
+            Err(e) => BulkError {
+                line: index + 1,
+                id: None,
+                query: None,
+                error: format!("{:#}", e),
+            },
+        };
+        data_print!("{}", serde_json::to_string(&error)?);
+    }
+    Ok(())
+}
+
+/// Search one `--stdin` query and print its JSON line
+fn run_bulk_query(
+    session: &mut SearchSession,
+    bulk: &BulkQuery,
+    options: &SearchOptions,
+) -> Result<()> {
+    let mut options = SearchOptions {
+        query_id: bulk.id.clone(),
+        ..options.clone()
+    };
+    if let Some(max_results) = bulk.max_results.filter(|_| !options.all) {
+        options.max_results = max_results;
+    }
+    if bulk.filter_path.is_some() {
+        options.filter_path = bulk.filter_path.clone();
+    }
+    let run = search_session(session, &bulk.query, &options)?;
+    print_results(&bulk.query, run, &options, &[])
+}
+
+/// Results of searching one database, with the time spent in each phase
+struct DatabaseResults {
+    results: Vec<crate::vectordb::SearchResult>,
+    corrections: Vec<SpellingCorrection>,
+    /// Ownership and provenance of the result paths that have any
+    files: std::collections::HashMap<String, FileTags>,
+    load_duration: Duration,
+    model_load_duration: Duration,
+    embed_duration: Duration,
+    search_duration: Duration,
+    rerank_duration: Duration,
+}
+
+/// Search one database: embed, retrieve, fuse, boost, rerank and truncate
+///
+/// Returns `None` when the results were streamed (`options.stream`).
+fn search_database(
+    query: &str,
+    db_path: &Path,
+    project_path: &Path,
+    options: &mut SearchOptions,
+) -> Result<Option<DatabaseResults>> {
+    let mut session = SearchSession::open(db_path, project_path, options)?;
+    if options.stream {
+        let query_embedding = session.embedding_service.embed_query(query)?;
+        stream_results(
+            &session.store,
+            &query_embedding,
+            options.max_results,
+            project_path,
+            options,
+        )?;
+        return Ok(None);
+    }
+    search_session(&mut session, query, options).map(Some)
+}
+
+/// A database opened for searching, with its embedding model loaded, so
+/// several queries can run without loading either again
+struct SearchSession<'a> {
+    db_path: &'a Path,
+    project_path: &'a Path,
+    metadata: Option<IndexMetadata>,
+    store: VectorStore,
+    embedding_service: EmbeddingService,
+    /// Model of the prose space of a dual-embedded index, loaded on first use
+    prose_service: Option<EmbeddingService>,
+    /// Full-text index (`None` when it can't be opened: searches are vector-only)
+    fts_store: Option<FtsStore>,
+    /// Neural reranker, loaded by the first query that reranks
+    reranker: Option<NeuralReranker>,
+    cache_dir: PathBuf,
+    load_duration: Duration,
+    model_load_duration: Duration,
+}
+
+impl<'a> SearchSession<'a> {
+    /// Open the database, syncing it first with `options.sync`
+    ///
+    /// With `options.all`, `max_results` is raised to the size of the index.
+    fn open(
+        db_path: &'a Path,
+        project_path: &'a Path,
+        options: &mut SearchOptions,
+    ) -> Result<Self> {
+        // Read model metadata from database FIRST (needed for sync)
+        let metadata = IndexMetadata::load(db_path)?;
+        let (model_type, dimensions) = if let Some(ref model_name) = options.model_override {
+            // User specified a model - it must match the dimensions of the indexed vectors
             let mt = ModelType::parse(model_name).unwrap_or_default();
-            (mt, mt.dimensions(), None)
-        } else if let Some((model_name, dims, lang)) = read_metadata(&db_path) {
-            // Use model from metadata
-            if let Some(mt) = ModelType::parse(&model_name) {
-                (mt, dims, lang)
-            } else {
-                // Model name not recognized, fall back to default
+            match &metadata {
+                Some(metadata) if metadata.dimensions != mt.dimensions() => {
+                    let (index_mt, _) = metadata.model_type();
+                    let dims = metadata.dimensions;
+                    warn_print!(
+                        "{}",
+                        format!(
+                            "⚠️  Model '{}' ({} dims) doesn't match the index ({} dims); using {}",
+                            mt.short_name(),
+                            mt.dimensions(),
+                            dims,
+                            index_mt.short_name()
+                        )
+                        .yellow()
+                    );
+                    (index_mt, dims)
+                }
+                _ => (mt, mt.dimensions()),
+            }
+        } else if let Some(metadata) = &metadata {
+            // Use model from metadata, correcting for unknown names or dimension mismatches
+            let (mt, corrected) = metadata.model_type();
+            if corrected {
                 warn_print!(
                     "{}",
-                    "⚠️  Unknown model in metadata, using default".yellow()
+                    format!(
+                        "⚠️  Index model '{}' ({} dims) not recognized, using {}",
+                        metadata.model_short_name,
+                        metadata.dimensions,
+                        mt.short_name()
+                    )
+                    .yellow()
                 );
-                (ModelType::default(), 384, None)
             }
+            (mt, metadata.dimensions)
         } else {
             // No metadata, fall back to default
-            (ModelType::default(), 384, None)
+            let mt = ModelType::default();
+            (mt, mt.dimensions())
         };
 
-    // Perform incremental sync if requested (after we know the model)
-    if options.sync {
-        info_print!("{}", "🔄 Syncing database...".yellow());
-        sync_database(&db_path, model_type)?;
+        // Perform incremental sync if requested (after we know the model)
+        if options.sync {
+            info_print!("{}", "🔄 Syncing database...".yellow());
+            sync_database(db_path, model_type)?;
+        }
+
+        // Load database
+        let start = Instant::now();
+        let store = VectorStore::new(db_path, dimensions)?;
+        let fts_store = FtsStore::new(db_path).ok();
+        let load_duration = start.elapsed();
+
+        // Initialize embedding service with the correct model
+        let start = Instant::now();
+        let cache_dir = crate::constants::get_global_models_cache_dir()?;
+        let embedding_service = EmbeddingService::with_cache_dir(model_type, Some(&cache_dir))?;
+        let model_load_duration = start.elapsed();
+
+        if options.all {
+            options.max_results = store.stats()?.total_chunks.max(1);
+        }
+
+        Ok(Self {
+            db_path,
+            project_path,
+            metadata,
+            store,
+            embedding_service,
+            prose_service: None,
+            fts_store,
+            reranker: None,
+            cache_dir,
+            load_duration,
+            model_load_duration,
+        })
     }
+}
 
-    // Load database
-    let start = Instant::now();
-    let store = VectorStore::new(&db_path, dimensions)?;
-    let load_duration = start.elapsed();
+/// Run one query on an open database
+fn search_session(
+    session: &mut SearchSession,
+    query: &str,
+    options: &SearchOptions,
+) -> Result<DatabaseResults> {
+    let (db_path, project_path) = (session.db_path, session.project_path);
+    let (store, metadata) = (&session.store, &session.metadata);
+    let fts_store = session.fts_store.as_ref();
+    let cache_dir = &session.cache_dir;
+    let embedding_service = &mut session.embedding_service;
+
+    let changed_files = options.changed_only.then(|| changed_paths(project_path));
+
+    // Identifiers that match nothing are probably misspelled
+    let mut corrections = if options.vector_only {
+        Vec::new()
+    } else {
+        fts_store
+            .map(|fts| correct_identifiers(query, fts, store))
+            .unwrap_or_default()
+    };
+    let corrected_query;
+    let query = if options.auto_correct && !corrections.is_empty() {
+        corrected_query = apply_corrections(query, &mut corrections);
+        info_print!(
+            "{}",
+            format!("✏️  Searching for \"{}\"", corrected_query).yellow()
+        );
+        corrected_query.as_str()
+    } else {
+        query
+    };
 
-    // Initialize embedding service with the correct model
-    let start = Instant::now();
-    let cache_dir = crate::constants::get_global_models_cache_dir()?;
-    let mut embedding_service = EmbeddingService::with_cache_dir(model_type, Some(&cache_dir))?;
-    let model_load_duration = start.elapsed();
+    // Developers search by abbreviation: add the symbols they stand for
+    let expanded_query = expand_acronyms(query, store);
+    if let Some(expanded) = &expanded_query {
+        info_print!("{}", format!("🔤 Expanded to \"{}\"", expanded).dimmed());
+    }
+    let query = expanded_query.as_deref().unwrap_or(query);
 
     // Expand query with variants for better matching
-    let query_variants = expand_query(query);
+    let mut query_variants = expand_query(query);
+
+    // Question-style queries also search their declarative rewrite
+    let rewritten_query = rewrite_question(query);
+    if let Some(rewritten) = &rewritten_query {
+        if !query_variants.contains(rewritten) {
+            query_variants.push(rewritten.clone());
+        }
+    }
 
     // Embed all query variants in a single batch (OPTIMIZATION: batched ONNX calls)
     let start = Instant::now();
@@ -507,7 +1628,11 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
 
     let vector_search_results: Vec<Vec<crate::vectordb::SearchResult>> = all_query_embeddings
         .par_iter()
-        .map(|query_emb| store.search(query_emb, retrieval_limit))
+        .map(|query_emb| match &changed_files {
+            // Few files: rank all of their chunks instead of a global top-k
+            Some(files) => store.search_in_files(query_emb, files, retrieval_limit),
+            None => store.search(query_emb, retrieval_limit),
+        })
         .collect::<Result<Vec<_>>>()?;
 
     // OPTIMIZATION: Deduplicate with top-N tracking using BinaryHeap
@@ -589,6 +1714,30 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
     // Sort by score descending
     vector_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
 
+    // Dual-embedded index: route natural-language queries through the prose space
+    let space = route_query(query);
+    if space != EmbeddingSpace::Code && store.has_prose_index() {
+        if let Some(prose_type) = metadata.as_ref().and_then(IndexMetadata::prose_model) {
+            let slot = &mut session.prose_service;
+            let prose_results = prose_type
+                .and_then(move |prose_type| prose_service(slot, prose_type, cache_dir))
+                .and_then(|prose_service| prose_service.embed_query(query))
+                .and_then(|embedding| store.search_prose(&embedding, retrieval_limit));
+            match prose_results {
+                Ok(prose_results) => {
+                    vector_results = combine_embedding_spaces(space, vector_results, prose_results);
+                }
+                Err(e) => {
+                    warn_print!(
+                        "{}",
+                        format!("⚠️  Prose search unavailable, using code space only: {}", e)
+                            .yellow()
+                    );
+                }
+            }
+        }
+    }
+
     // OPTIMIZATION: Early termination for high-confidence exact matches
     // If top results have very high confidence (very low distance), skip FTS search
     // This saves ~30-50ms per search for queries with clear matches
@@ -626,8 +1775,8 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
         vector_only(&vector_results)
     } else {
         // Hybrid search with RRF fusion
-        match FtsStore::new(&db_path) {
-            Ok(fts_store) => {
+        match fts_store {
+            Some(fts_store) => {
                 // Detect identifiers for exact match boosting
                 let identifiers = detect_identifiers(query);
                 // Detect structural intent for kind field boosting
@@ -635,14 +1784,24 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
 
                 if identifiers.is_empty() {
                     // No identifiers - standard hybrid search
-                    let fts_results =
-                        fts_store.search(query, retrieval_limit, structural_intent)?;
+                    let fts_results = search_fts_query_forms(
+                        fts_store,
+                        query,
+                        rewritten_query.as_deref(),
+                        retrieval_limit,
+                        structural_intent,
+                    )?;
                     let k = options.rrf_k.unwrap_or(DEFAULT_RRF_K as usize) as f32;
                     rrf_fusion(&vector_results, &fts_results, k)
                 } else {
                     // Has identifiers - use exact match boosting
-                    let fts_results =
-                        fts_store.search(query, retrieval_limit, structural_intent)?;
+                    let fts_results = search_fts_query_forms(
+                        fts_store,
+                        query,
+                        rewritten_query.as_deref(),
+                        retrieval_limit,
+                        structural_intent,
+                    )?;
 
                     // Search for each identifier and combine exact results
                     let mut all_exact_results = Vec::new();
@@ -679,7 +1838,7 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
                     )
                 }
             }
-            Err(_) => {
+            None => {
                 // FTS not available, fall back to vector-only
                 warn_print!(
                     "{}",
@@ -697,22 +1856,26 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
 
     // OPTIMIZATION: Apply path filter BEFORE expensive operations (reranking, boosting)
     // This avoids processing results that will be filtered out anyway
-    let should_filter_by_path = options.filter_path.is_some();
-    let filter_path_normalized = options.filter_path.as_ref().map(|f| {
-        crate::cache::normalize_path_str(f)
-            .trim_start_matches("./")
-            .to_string()
-    });
-
-    // Normalize project root for stripping absolute paths to relative
-    let project_root_normalized = {
-        let root = crate::cache::normalize_path_str(project_path.to_str().unwrap_or(""));
-        root.trim_end_matches('/').to_string()
+    let path_filter = options
+        .filter_path
+        .as_deref()
+        .map(|filter| PathFilter::new(filter, project_path.to_str().unwrap_or("")))
+        .transpose()?;
+    let project_only = options.project_only;
+    let project_root = project_path.to_string_lossy().into_owned();
+    let should_filter_by_path = path_filter.is_some() || project_only;
+    let outside_filter = |path: &str| {
+        matches!(&path_filter, Some(filter) if !filter.matches(path))
+            || (project_only && is_vendored_path(path, &project_root))
     };
+
     // Take top rerank_top results for reranking (or max_results if not reranking)
     // OPTIMIZATION: Take extra results when path filtering is active to ensure we have enough after filtering
     let take_multiplier = if should_filter_by_path { 3 } else { 1 };
-    let take_count = if options.rerank {
+    let take_count = if changed_files.is_some() {
+        // Candidates outside the changed files are dropped below
+        fused_results.len()
+    } else if options.rerank {
         options
             .rerank_top
             .unwrap_or(options.max_results)
@@ -721,48 +1884,44 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
         options.max_results * take_multiplier
     };
 
+    let changed_set: Option<std::collections::HashSet<&str>> = changed_files
+        .as_ref()
+        .map(|files| files.iter().map(String::as_str).collect());
+    let outside_changes =
+        |path: &str| matches!(&changed_set, Some(changed) if !changed.contains(path));
+
     for fused in fused_results.iter().take(take_count) {
         if let Some(result) = chunk_id_to_result.get(&fused.chunk_id) {
+            if outside_changes(&result.path) {
+                continue;
+            }
             // OPTIMIZATION: Skip early if path filter doesn't match
-            if should_filter_by_path {
-                if let Some(ref filter) = filter_path_normalized {
-                    let path_normalized = crate::cache::normalize_path_str(&result.path);
-                    // Strip project root to convert absolute → relative path
-                    let path_relative = path_normalized
-                        .strip_prefix(&project_root_normalized)
-                        .unwrap_or(&path_normalized)
-                        .trim_start_matches('/')
-                        .trim_start_matches("./");
-                    if !path_relative.starts_with(filter.as_str()) {
-                        continue;
-                    }
-                }
+            if outside_filter(&result.path) {
+                continue;
             }
 
             // Update score to RRF score
             let mut r = (*result).clone();
             r.score = fused.rrf_score;
+            r.text_match = fused.fts_rank.is_some();
             results.push(r);
         } else {
             // Result only from FTS, need to fetch from store
             if let Ok(Some(mut result)) = store.get_chunk_as_result(fused.chunk_id) {
+                if outside_changes(&result.path) {
+                    continue;
+                }
                 // OPTIMIZATION: Skip early if path filter doesn't match
-                if should_filter_by_path {
-                    if let Some(ref filter) = filter_path_normalized {
-                        let path_normalized = crate::cache::normalize_path_str(&result.path);
-                        // Strip project root to convert absolute → relative path
-                        let path_normalized = path_normalized
-                            .strip_prefix(&project_root_normalized)
-                            .unwrap_or(&path_normalized)
-                            .trim_start_matches('/')
-                            .trim_start_matches("./");
-                        if !path_normalized.starts_with(filter.as_str()) {
-                            continue;
-                        }
-                    }
+                if outside_filter(&result.path) {
+                    continue;
                 }
+                // No vector hit: measure the similarity for its confidence
+                result.distance = store
+                    .distance_to(&all_query_embeddings[0], fused.chunk_id)?
+                    .unwrap_or(1.0);
 
                 result.score = fused.rrf_score;
+                result.text_match = true;
                 results.push(result);
             }
         }
@@ -773,36 +1932,27 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
         let candidates_processed = take_count;
         let results_after_filtering = results.len();
         let filtered_out = candidates_processed.saturating_sub(results_after_filtering);
+        let filter_label = match options.filter_path.as_deref() {
+            Some(filter) if project_only => format!("Path filter '{}' (project only)", filter),
+            Some(filter) => format!("Path filter '{}'", filter),
+            None => "Project-only filter".to_string(),
+        };
         info_print!(
             "{}",
             format!(
-                "🔍 Path filter '{}': {} candidates → {} results ({} filtered out)",
-                filter_path_normalized.as_ref().unwrap_or(&"".to_string()),
-                candidates_processed,
-                results_after_filtering,
-                filtered_out
+                "🔍 {}: {} candidates → {} results ({} filtered out)",
+                filter_label, candidates_processed, results_after_filtering, filtered_out
             )
             .blue()
         );
     }
 
-    // Language awareness: Boost results from primary language
-    // Extract language from file path (since SearchResult doesn't have language field)
-    if let Some(ref lang) = primary_language {
-        use crate::file::Language;
-        let lang_boost = 0.2; // Boost results from primary language by 20%
-        for result in results.iter_mut() {
-            // Detect language from file path
-            let file_lang = format!(
-                "{:?}",
-                Language::from_path(std::path::Path::new(&result.path))
-            );
-            if file_lang == *lang {
-                result.score *= 1.0 + lang_boost;
-            }
-        }
-        // Re-sort after boosting
-        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+    // Language awareness: Boost results in the languages the query asks for,
+    // or else in the project's main languages
+    if let Some(intent) = language_intent(query) {
+        boost_language_intent(&mut results, &intent);
+    } else if let Some(metadata) = metadata {
+        boost_languages(&mut results, metadata);
     }
 
     // ChunkKind-Aware Ranking: Boost results matching structural intent
@@ -810,6 +1960,9 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
         boost_kind(&mut results, intent);
     }
 
+    // Hard negatives: demote fixtures, snapshots, golden files and minified assets
+    penalize_hard_negatives(&mut results, query);
+
     // Negative Result Check: Report when no exact matches found for identifier queries
     let identifiers = detect_identifiers(query);
     if !identifiers.is_empty() && results.is_empty() {
@@ -831,8 +1984,12 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
     if options.rerank && !results.is_empty() {
         let start = Instant::now();
 
-        // Initialize neural reranker (Jina Reranker v1 Turbo)
-        match NeuralReranker::new() {
+        // Initialize neural reranker (Jina Reranker v1 Turbo) on first use
+        let reranker = match session.reranker.take() {
+            Some(reranker) => Ok(reranker),
+            None => NeuralReranker::new(),
+        };
+        match reranker {
             Ok(mut reranker) => {
                 // Prepare documents for reranking
                 let documents: Vec<String> = results.iter().map(|r| r.content.clone()).collect();
@@ -856,6 +2013,7 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
                         warn_print!("{}", format!("⚠️  Reranking failed: {}", e).yellow());
                     }
                 }
+                session.reranker = Some(reranker);
             }
             Err(e) => {
                 warn_print!("{}", format!("⚠️  Could not load reranker: {}", e).yellow());
@@ -866,52 +2024,199 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
     }
 
     // Filter by path if specified (post-reranking pass)
-    if let Some(ref filter) = options.filter_path {
-        let filter_normalized = crate::cache::normalize_path_str(filter);
-        let filter_normalized = filter_normalized.trim_start_matches("./");
-        results.retain(|r| {
-            let path_normalized = crate::cache::normalize_path_str(&r.path);
-            // Strip project root to convert absolute → relative path
-            let path_relative = path_normalized
-                .strip_prefix(&project_root_normalized)
-                .unwrap_or(&path_normalized)
-                .trim_start_matches('/')
-                .trim_start_matches("./");
-            path_relative.starts_with(filter_normalized)
-        });
+    if should_filter_by_path {
+        results.retain(|r| !outside_filter(&r.path));
+    }
+
+    // Context the index didn't store is read from disk before merging
+    if let Some(metadata) = IndexMetadata::load(db_path).ok().flatten() {
+        expand_context(&mut results, project_path, metadata.context_lines());
     }
 
-    // Truncate to max_results after reranking and filtering
+    // One result per run of adjacent matching chunks, then truncate to
+    // max_results after reranking and filtering
+    let mut results = merge_adjacent_results(results);
     results.truncate(options.max_results);
+    let files = result_file_tags(db_path, project_path, &results);
+
+    Ok(DatabaseResults {
+        results,
+        corrections,
+        files,
+        // Only the first search of a session spent time loading
+        load_duration: std::mem::take(&mut session.load_duration),
+        model_load_duration: std::mem::take(&mut session.model_load_duration),
+        embed_duration,
+        search_duration,
+        rerank_duration,
+    })
+}
+
+/// The prose embedding model in `slot`, loading it on first use
+fn prose_service<'s>(
+    slot: &'s mut Option<EmbeddingService>,
+    model: ModelType,
+    cache_dir: &Path,
+) -> Result<&'s mut EmbeddingService> {
+    let service = match slot.take() {
+        Some(service) => service,
+        None => EmbeddingService::with_cache_dir(model, Some(cache_dir))?,
+    };
+    Ok(slot.insert(service))
+}
+
+/// Ownership and provenance of a result's file
+#[derive(Debug, Default)]
+struct FileTags {
+    /// Owners from CODEOWNERS
+    owners: Vec<String>,
+    /// SPDX license declared in the file's header
+    license: Option<String>,
+    /// Whether the file lies in a vendored (third-party) directory
+    vendored: bool,
+}
+
+/// Tags of each result's file that has any, from the CODEOWNERS rules and
+/// file licenses saved at index time
+fn result_file_tags(
+    db_path: &Path,
+    project_path: &Path,
+    results: &[crate::vectordb::SearchResult],
+) -> std::collections::HashMap<String, FileTags> {
+    let code_owners = CodeOwners::load(db_path).unwrap_or_else(|e| {
+        warn_print!("{}", format!("⚠️  {:#}", e).yellow());
+        None
+    });
+    let file_meta = FileMetaStore::read(db_path);
+    let root = project_path.to_string_lossy();
+    results
+        .iter()
+        .filter_map(|r| {
+            let tags = FileTags {
+                owners: code_owners
+                    .as_ref()
+                    .map_or_else(Vec::new, |c| c.owners_of(&r.path).to_vec()),
+                license: file_meta
+                    .as_ref()
+                    .and_then(|meta| meta.license_of(&r.path))
+                    .map(str::to_string),
+                vendored: is_vendored_path(&r.path, &root),
+            };
+            (!tags.owners.is_empty() || tags.license.is_some() || tags.vendored)
+                .then(|| (r.path.clone(), tags))
+        })
+        .collect()
+}
+
+/// Chunk paths of the files with uncommitted changes in `project_path`'s git
+/// work tree, or `None` outside a git work tree
+pub fn uncommitted_chunk_paths(project_path: &Path) -> Option<Vec<String>> {
+    // Chunk paths are canonical, like the work tree top level git reports
+    let root = project_path
+        .canonicalize()
+        .unwrap_or_else(|_| project_path.to_path_buf());
+    let files = crate::file::uncommitted_files(&root)?;
+    Some(files.iter().map(|file| normalize_path(file)).collect())
+}
+
+/// [`uncommitted_chunk_paths`], reporting the scope of a `--changed` search
+///
+/// Empty, with a warning, when the project isn't in a git work tree.
+fn changed_paths(project_path: &Path) -> Vec<String> {
+    match uncommitted_chunk_paths(project_path) {
+        Some(files) => {
+            info_print!(
+                "{}",
+                format!(
+                    "🔍 Searching {} files with uncommitted changes",
+                    files.len()
+                )
+                .blue()
+            );
+            files
+        }
+        None => {
+            warn_print!(
+                "{}",
+                format!(
+                    "⚠️  {} is not in a git work tree; --changed finds nothing",
+                    project_path.display()
+                )
+                .yellow()
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Print search results as JSON, file paths (compact) or the standard listing
+///
+/// `projects` maps normalized project roots to the labels shown for their
+/// results when several databases were merged.
+fn print_results(
+    query: &str,
+    run: DatabaseResults,
+    options: &SearchOptions,
+    projects: &[(String, String)],
+) -> Result<()> {
+    let DatabaseResults {
+        results,
+        corrections,
+        files,
+        load_duration,
+        model_load_duration,
+        embed_duration,
+        search_duration,
+        rerank_duration,
+    } = run;
+
+    // With --min-score, rather than weak hits, say nothing matched and how to rephrase
+    let found_any = !results.is_empty();
+    let guidance = if thresholding_requested(options.min_score) {
+        no_confident_matches(query, &results)
+    } else {
+        None
+    };
+    let results = if guidance.is_some() {
+        Vec::new()
+    } else {
+        apply_min_score(results, options.min_score)
+    };
+
+    // Scores relative to the top result (0-100), each with a confidence
+    let results: Vec<(crate::vectordb::SearchResult, Confidence)> = score_confidence(&results)
+        .into_iter()
+        .zip(results)
+        .map(|((score, confidence), mut result)| {
+            result.score = score;
+            (result, confidence)
+        })
+        .collect();
 
     // Output results
+    let json_result =
+        |r: &crate::vectordb::SearchResult, confidence: Confidence, compact| JsonResult {
+            project: project_label(&r.path, projects).map(str::to_string),
+            confidence: Some(confidence),
+            owners: files
+                .get(&r.path)
+                .map_or_else(Vec::new, |tags| tags.owners.clone()),
+            license: files.get(&r.path).and_then(|tags| tags.license.clone()),
+            vendored: files.get(&r.path).is_some_and(|tags| tags.vendored),
+            ..JsonResult::new(r, compact)
+        };
+
+    if let Some(template) = &options.template {
+        for (r, confidence) in &results {
+            data_print!("{}", template.render(&json_result(r, *confidence, false)));
+        }
+        return Ok(());
+    }
+
     if options.json {
-        let compact = options.compact;
         let json_results: Vec<JsonResult> = results
             .iter()
-            .map(|r| JsonResult {
-                path: r.path.clone(),
-                start_line: r.start_line,
-                end_line: r.end_line,
-                kind: r.kind.clone(),
-                content: if compact {
-                    None
-                } else {
-                    Some(r.content.clone())
-                },
-                score: r.score,
-                signature: r.signature.clone(),
-                context_prev: if compact {
-                    None
-                } else {
-                    r.context_prev.clone()
-                },
-                context_next: if compact {
-                    None
-                } else {
-                    r.context_next.clone()
-                },
-            })
+            .map(|(r, confidence)| json_result(r, *confidence, options.compact))
             .collect();
 
         let timing = if options.show_scores {
@@ -935,21 +2240,25 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
         };
 
         let output = JsonOutput {
+            id: options.query_id.clone(),
             query: query.to_string(),
             results: json_results,
+            corrections,
+            language_intent: language_intent(query),
+            no_confident_matches: guidance,
             timing,
         };
 
-        println!("{}", serde_json::to_string(&output)?);
+        data_print!("{}", serde_json::to_string(&output)?);
         return Ok(());
     }
 
     if options.compact {
         // Show only file paths (like grep -l)
         let mut seen_files = std::collections::HashSet::new();
-        for result in &results {
+        for (result, _) in &results {
             if !seen_files.contains(&result.path) {
-                println!("{}", result.path);
+                out_print!("{}", result.path);
                 seen_files.insert(result.path.clone());
             }
         }
@@ -957,30 +2266,48 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
     }
 
     // Standard output
-    println!("{}", "🔍 Search Results".bright_cyan().bold());
-    println!("{}", "=".repeat(60));
-    println!("Query: \"{}\"", query.bright_yellow());
+    out_print!("{}", "🔍 Search Results".bright_cyan().bold());
+    out_print!("{}", "=".repeat(60));
+    out_print!("Query: \"{}\"", query.bright_yellow());
+    for correction in &corrections {
+        if correction.applied {
+            out_print!(
+                "Searched for {} instead of {}",
+                correction.to.bright_cyan(),
+                correction.from
+            );
+        } else {
+            out_print!(
+                "Did you mean {}? (no matches for {}; --auto-correct searches for it)",
+                correction.to.bright_cyan(),
+                correction.from
+            );
+        }
+    }
+    if let Some(intent) = language_intent(query) {
+        out_print!("Ranked first: {}", intent.describe().bright_cyan());
+    }
     if let Some(pf) = options.per_file {
-        println!(
+        out_print!(
             "Found {} results (showing up to {} per file)",
             results.len(),
             pf
         );
     } else {
-        println!("Found {} results", results.len());
+        out_print!("Found {} results", results.len());
     }
-    println!();
+    out_print!();
 
     if options.show_scores {
-        println!("Timing:");
-        println!("   Database load: {:?}", load_duration);
-        println!("   Model load:    {:?}", model_load_duration);
-        println!("   Query embed:   {:?}", embed_duration);
-        println!("   Search:        {:?}", search_duration);
+        out_print!("Timing:");
+        out_print!("   Database load: {:?}", load_duration);
+        out_print!("   Model load:    {:?}", model_load_duration);
+        out_print!("   Query embed:   {:?}", embed_duration);
+        out_print!("   Search:        {:?}", search_duration);
         if options.rerank {
-            println!("   Reranking:     {:?}", rerank_duration);
+            out_print!("   Reranking:     {:?}", rerank_duration);
         }
-        println!(
+        out_print!(
             "   Total:         {:?}",
             load_duration
                 + model_load_duration
@@ -988,16 +2315,29 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
                 + search_duration
                 + rerank_duration
         );
-        println!();
+        out_print!();
     }
 
     // Check if no results
     if results.is_empty() {
-        println!("{}", "No matches found.".dimmed());
-        println!("Try:");
-        println!("  - Using different keywords");
-        println!("  - Making your query more general");
-        println!(
+        if found_any {
+            out_print!("{}", "No confident matches found.".dimmed());
+        } else {
+            out_print!("{}", "No matches found.".dimmed());
+        }
+        if let Some(guidance) = guidance.as_ref().filter(|g| !g.nearby_symbols.is_empty()) {
+            out_print!("Closest symbols: {}", guidance.nearby_symbols.join(", "));
+        }
+        out_print!("Try:");
+        for reformulation in guidance.iter().flat_map(|g| &g.reformulations) {
+            out_print!(
+                "  - {}",
+                format!("codesearch search \"{}\"", reformulation).bright_cyan()
+            );
+        }
+        out_print!("  - Using different keywords");
+        out_print!("  - Making your query more general");
+        out_print!(
             "  - Running {} if the codebase changed",
             "codesearch index --force".bright_cyan()
         );
@@ -1010,26 +2350,32 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
             let mut by_file: std::collections::HashMap<String, Vec<_>> =
                 std::collections::HashMap::new();
 
-            for result in results {
-                by_file.entry(result.path.clone()).or_default().push(result);
+            for (result, confidence) in results {
+                by_file
+                    .entry(result.path.clone())
+                    .or_default()
+                    .push((result, confidence));
             }
 
-            let mut files: Vec<_> = by_file.into_iter().collect();
-            files.sort_by(|a, b| {
+            let mut grouped: Vec<_> = by_file.into_iter().collect();
+            grouped.sort_by(|a, b| {
                 b.1.iter()
-                    .map(|r| r.score)
+                    .map(|(r, _)| r.score)
                     .fold(0.0f32, f32::max)
-                    .partial_cmp(&a.1.iter().map(|r| r.score).fold(0.0f32, f32::max))
+                    .partial_cmp(&a.1.iter().map(|(r, _)| r.score).fold(0.0f32, f32::max))
                     .unwrap()
             });
 
-            for (_file_path, mut file_results) in files {
-                file_results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap());
+            for (_file_path, mut file_results) in grouped {
+                file_results.sort_by(|a, b| b.0.score.partial_cmp(&a.0.score).unwrap());
                 file_results.truncate(per_file);
 
-                for (idx, result) in file_results.iter().enumerate() {
+                for (idx, (result, confidence)) in file_results.iter().enumerate() {
                     print_result(
                         result,
+                        *confidence,
+                        files.get(&result.path),
+                        project_label(&result.path, projects),
                         idx == 0,
                         options.content_lines > 0,
                         options.show_scores,
@@ -1038,20 +2384,201 @@ pub async fn search(query: &str, path: Option<PathBuf>, options: SearchOptions)
             }
         } else {
             // Show all results
-            for result in &results {
-                print_result(result, true, options.content_lines > 0, options.show_scores)?;
+            for (result, confidence) in &results {
+                print_result(
+                    result,
+                    *confidence,
+                    files.get(&result.path),
+                    project_label(&result.path, projects),
+                    true,
+                    options.content_lines > 0,
+                    options.show_scores,
+                )?;
             }
         }
     } else {
         // Show all results
-        for result in &results {
-            print_result(result, true, options.content_lines > 0, options.show_scores)?;
+        for (result, confidence) in &results {
+            print_result(
+                result,
+                *confidence,
+                files.get(&result.path),
+                project_label(&result.path, projects),
+                true,
+                options.content_lines > 0,
+                options.show_scores,
+            )?;
         }
     }
 
     Ok(())
 }
 
+/// Search several child-directory databases and print the merged results
+///
+/// Each database is searched with the same options; results are ranked
+/// together by score and labelled with the name of their project directory.
+/// A database that fails to search is skipped with a warning.
+fn search_children(
+    query: &str,
+    children: Vec<crate::db_discovery::DatabaseInfo>,
+    options: SearchOptions,
+) -> Result<()> {
+    let mut merged = DatabaseResults {
+        results: Vec::new(),
+        corrections: Vec::new(),
+        files: std::collections::HashMap::new(),
+        load_duration: Duration::ZERO,
+        model_load_duration: Duration::ZERO,
+        embed_duration: Duration::ZERO,
+        search_duration: Duration::ZERO,
+        rerank_duration: Duration::ZERO,
+    };
+    let mut projects = Vec::new();
+
+    for child in children {
+        let label = child
+            .project_path
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_else(|| child.project_path.display().to_string());
+        info_print!(
+            "{}",
+            format!("📂 Searching {}", child.db_path.display()).dimmed()
+        );
+
+        let mut child_options = options.clone();
+        let run = match search_database(
+            query,
+            &child.db_path,
+            &child.project_path,
+            &mut child_options,
+        ) {
+            Ok(Some(run)) => run,
+            Ok(None) => continue,
+            Err(e) => {
+                warn_print!(
+                    "{}",
+                    format!("⚠️  Skipping {}: {:#}", child.db_path.display(), e).yellow()
+                );
+                continue;
+            }
+        };
+
+        // Relative paths are made relative to the parent directory, so they
+        // stay unambiguous and openable from where the search was run
+        let relabel = |path: String| {
+            if Path::new(&path).is_relative() {
+                format!("{}/{}", label, path.trim_start_matches("./"))
+            } else {
+                path
+            }
+        };
+        merged
+            .results
+            .extend(run.results.into_iter().map(|mut result| {
+                result.path = relabel(result.path);
+                result
+            }));
+        merged.files.extend(
+            run.files
+                .into_iter()
+                .map(|(path, tags)| (relabel(path), tags)),
+        );
+        for correction in run.corrections {
+            if !merged.corrections.contains(&correction) {
+                merged.corrections.push(correction);
+            }
+        }
+        merged.load_duration += run.load_duration;
+        merged.model_load_duration += run.model_load_duration;
+        merged.embed_duration += run.embed_duration;
+        merged.search_duration += run.search_duration;
+        merged.rerank_duration += run.rerank_duration;
+
+        let root = crate::cache::normalize_path_str(&child.project_path.to_string_lossy())
+            .trim_end_matches('/')
+            .to_string();
+        projects.push((root, label));
+    }
+
+    merged.results.sort_by(|a, b| b.score.total_cmp(&a.score));
+    merged.results.truncate(options.max_results);
+    print_results(query, merged, &options, &projects)
+}
+
+/// Label of the project a result path belongs to
+///
+/// `projects` holds `(normalized project root, label)` pairs; a path matches
+/// when it lies under the root or starts with the label (parent-relative paths).
+fn project_label<'a>(path: &str, projects: &'a [(String, String)]) -> Option<&'a str> {
+    let path = crate::cache::normalize_path_str(path);
+    projects
+        .iter()
+        .find(|(root, label)| {
+            path.strip_prefix(root.as_str())
+                .or_else(|| path.strip_prefix(label.as_str()))
+                .is_some_and(|rest| rest.starts_with('/'))
+        })
+        .map(|(_, label)| label.as_str())
+}
+
+/// Print results as JSON lines while they are read (`search --stream`)
+///
+/// Only chunk IDs and distances are ranked up front; each chunk is loaded,
+/// printed and dropped in turn, so memory stays flat for thousands of matches.
+/// Ranking is vector-only: hybrid fusion and reranking need every candidate
+/// in memory at once.
+fn stream_results(
+    store: &VectorStore,
+    query_embedding: &[f32],
+    limit: usize,
+    project_path: &Path,
+    options: &SearchOptions,
+) -> Result<()> {
+    use std::io::Write;
+
+    let filter = options
+        .filter_path
+        .as_deref()
+        .map(|filter| PathFilter::new(filter, project_path.to_str().unwrap_or("")))
+        .transpose()?;
+
+    let changed: Option<std::collections::HashSet<String>> = options
+        .changed_only
+        .then(|| changed_paths(project_path).into_iter().collect());
+
+    let mut out = std::io::BufWriter::new(std::io::stdout().lock());
+    for (id, distance) in store.search_ids(query_embedding, limit)? {
+        let Some(mut result) = store.get_chunk_as_result(id)? else {
+            continue;
+        };
+        if matches!(&changed, Some(changed) if !changed.contains(&result.path)) {
+            continue;
+        }
+        if let Some(ref filter) = filter {
+            if !filter.matches(&result.path) {
+                continue;
+            }
+        }
+        if options.project_only && is_vendored_path(&result.path, &project_path.to_string_lossy()) {
+            continue;
+        }
+        result.distance = distance;
+        result.score = 1.0 - distance;
+        let line = match &options.template {
+            Some(template) => template.render(&JsonResult::new(&result, false)),
+            None => serde_json::to_string(&JsonResult::new(&result, options.compact))?,
+        };
+        // A closed pipe (e.g. `| head`) just ends the stream
+        if writeln!(out, "{}", line).is_err() {
+            return Ok(());
+        }
+    }
+    let _ = out.flush();
+    Ok(())
+}
+
 /// Sync database by re-indexing changed files
 fn sync_database(db_path: &Path, model_type: ModelType) -> Result<()> {
     let project_path = db_path.parent().unwrap_or(std::path::Path::new("."));
@@ -1061,7 +2588,9 @@ fn sync_database(db_path: &Path, model_type: ModelType) -> Result<()> {
         FileMetaStore::load_or_create(db_path, model_type.short_name(), model_type.dimensions())?;
 
     // Walk the file system
-    let walker = FileWalker::new(project_path.to_path_buf());
+    let walker = FileWalker::new(project_path.to_path_buf())
+        .with_languages(crate::index::read_language_filter(db_path))
+        .with_excluded_dirs(crate::index::read_pruned_dirs(db_path));
     let (files, _stats) = walker.walk()?;
 
     // Initialize services
@@ -1081,15 +2610,14 @@ fn sync_database(db_path: &Path, model_type: ModelType) -> Result<()> {
         }
 
         changes += 1;
-        println!("  📝 {}", file.path.display());
+        out_print!("  📝 {}", file.path.display());
 
-        // Delete old chunks
-        if !old_chunk_ids.is_empty() {
-            store.delete_chunks(&old_chunk_ids)?;
-        }
+        // Delete old chunks, including any the file metadata lost track of
+        store.delete_chunks(&old_chunk_ids)?;
+        store.delete_by_path(&normalize_path(&file.path))?;
 
         // Read and chunk file
-        let source_code = match std::fs::read_to_string(&file.path) {
+        let source_code = match crate::file::read_source(&file.path) {
             Ok(content) => content,
             Err(_) => continue,
         };
@@ -1111,21 +2639,20 @@ fn sync_database(db_path: &Path, model_type: ModelType) -> Result<()> {
     let deleted_files = file_meta.find_deleted_files();
     for (path, chunk_ids) in &deleted_files {
         changes += 1;
-        println!("  🗑️  {} (deleted)", path);
-        if !chunk_ids.is_empty() {
-            store.delete_chunks(chunk_ids)?;
-        }
+        out_print!("  🗑️  {} (deleted)", path);
+        store.delete_chunks(chunk_ids)?;
+        store.delete_by_path(path)?;
         file_meta.remove_file(std::path::Path::new(path));
     }
 
     // Rebuild index if changes were made
     if changes > 0 {
-        println!("  🔨 Rebuilding index...");
+        out_print!("  🔨 Rebuilding index...");
         store.build_index()?;
         file_meta.save(db_path)?;
-        println!("  ✅ {} file(s) synced", changes);
+        out_print!("  ✅ {} file(s) synced", changes);
     } else {
-        println!("  ✅ Already up to date");
+        out_print!("  ✅ Already up to date");
     }
 
     Ok(())
@@ -1133,14 +2660,21 @@ fn sync_database(db_path: &Path, model_type: ModelType) -> Result<()> {
 
 fn print_result(
     result: &crate::vectordb::SearchResult,
+    confidence: Confidence,
+    tags: Option<&FileTags>,
+    project: Option<&str>,
     show_file: bool,
     show_content: bool,
     show_scores: bool,
 ) -> Result<()> {
     if show_file {
-        println!("{}", "─".repeat(60));
-        let file_display = format!("📄 {}", result.path);
-        println!("{}", file_display.bright_green());
+        out_print!("{}", "─".repeat(60));
+        let icon = crate::output::decoration("📄 ");
+        let file_display = match project {
+            Some(project) => format!("{}[{}] {}", icon, project, result.path),
+            None => format!("{}{}", icon, result.path),
+        };
+        data_print!("{}", file_display.bright_green());
     }
 
     // Show location and kind
@@ -1148,62 +2682,69 @@ fn print_result(
         "   Lines {}-{} • {}",
         result.start_line, result.end_line, result.kind
     );
-    println!("{}", location.dimmed());
+    data_print!("{}", location.dimmed());
 
     // Show signature if available
     if let Some(sig) = &result.signature {
-        println!("   {}", sig.bright_cyan());
+        data_print!("   {}", sig.bright_cyan());
     }
 
-    // Show score if requested
-    if show_scores {
-        let score_color = if result.score > 0.8 {
-            "green"
-        } else if result.score > 0.6 {
-            "yellow"
-        } else {
-            "red"
+    if let Some(tags) = tags {
+        if !tags.owners.is_empty() {
+            out_print!("   Owners: {}", tags.owners.join(" ").dimmed());
+        }
+        let provenance = match (&tags.license, tags.vendored) {
+            (Some(license), true) => Some(format!("License: {} (vendored)", license)),
+            (Some(license), false) => Some(format!("License: {}", license)),
+            (None, true) => Some("Vendored".to_string()),
+            (None, false) => None,
         };
+        if let Some(provenance) = provenance {
+            out_print!("   {}", provenance.dimmed());
+        }
+    }
 
-        let score_text = format!("   Score: {:.3}", result.score);
-        println!(
+    // Show score if requested
+    if show_scores {
+        let score_text = format!("   Score: {:.0} ({})", result.score, confidence);
+        out_print!(
             "{}",
-            match score_color {
-                "green" => score_text.green(),
-                "yellow" => score_text.yellow(),
-                _ => score_text.red(),
+            match confidence {
+                Confidence::High => score_text.green(),
+                Confidence::Medium => score_text.yellow(),
+                Confidence::Low => score_text.red(),
             }
         );
     }
 
     // Show context if available
     if let Some(ctx) = &result.context {
-        println!("   Context: {}", ctx.dimmed());
+        data_print!("   Context: {}", ctx.dimmed());
     }
 
     // Show content if requested
     if show_content {
         // Show context before (if available)
         if let Some(ctx_prev) = &result.context_prev {
-            println!("\n   {}:", "Context (before)".dimmed());
+            out_print!("\n   {}:", "Context (before)".dimmed());
             for line in ctx_prev.lines() {
-                println!("   │ {}", line.bright_black());
+                data_print!("   │ {}", line.bright_black());
             }
         }
 
-        println!("\n   {}:", "Content".bright_yellow());
+        out_print!("\n   {}:", "Content".bright_yellow());
         for line in result.content.lines().take(10) {
-            println!("   │ {}", line.dimmed());
+            data_print!("   │ {}", line.dimmed());
         }
         if result.content.lines().count() > 10 {
-            println!("   │ {}", "...".dimmed());
+            out_print!("   │ {}", "...".dimmed());
         }
 
         // Show context after (if available)
         if let Some(ctx_next) = &result.context_next {
-            println!("\n   {}:", "Context (after)".dimmed());
+            out_print!("\n   {}:", "Context (after)".dimmed());
             for line in ctx_next.lines() {
-                println!("   │ {}", line.bright_black());
+                data_print!("   │ {}", line.bright_black());
             }
         }
     } else {
@@ -1216,10 +2757,10 @@ fn print_result(
             snippet
         };
 
-        println!("   {}", snippet.dimmed());
+        data_print!("   {}", snippet.dimmed());
     }
 
-    println!();
+    out_print!();
 
     Ok(())
 }
@@ -1263,6 +2804,65 @@ mod tests {
         assert!(ids.contains(&"find_git_root".to_string()));
     }
 
+    // ── route_query ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_route_query_identifiers_go_to_code_space() {
+        assert_eq!(route_query("VectorStore::search"), EmbeddingSpace::Code);
+        assert_eq!(route_query("parse_config"), EmbeddingSpace::Code);
+    }
+
+    #[test]
+    fn test_route_query_questions_go_to_prose_space() {
+        assert_eq!(
+            route_query("how are file changes debounced"),
+            EmbeddingSpace::Prose
+        );
+        assert_eq!(
+            route_query("retry logic for downloads?"),
+            EmbeddingSpace::Prose
+        );
+    }
+
+    #[test]
+    fn test_route_query_mixed_uses_both_spaces() {
+        assert_eq!(
+            route_query("where does FtsStore commit pending writes"),
+            EmbeddingSpace::Both
+        );
+        assert_eq!(route_query("database locking"), EmbeddingSpace::Both);
+    }
+
+    #[test]
+    fn test_is_hard_negative_path() {
+        assert!(is_hard_negative_path("/repo/tests/fixtures/sample.rs"));
+        assert!(is_hard_negative_path(
+            r"C:\repo\src\__snapshots__\app.test.js.snap"
+        ));
+        assert!(is_hard_negative_path("/repo/testdata/golden/output.txt"));
+        assert!(is_hard_negative_path("/repo/static/vendor.min.js"));
+        assert!(!is_hard_negative_path("/repo/src/search/mod.rs"));
+        assert!(!is_hard_negative_path("/repo/src/fixtures.rs"));
+    }
+
+    #[test]
+    fn test_rewrite_question_strips_interrogatives() {
+        assert_eq!(
+            rewrite_question("how does the watcher debounce events?").as_deref(),
+            Some("watcher debounce events")
+        );
+        assert_eq!(
+            rewrite_question("Where is FtsStore::commit defined?").as_deref(),
+            Some("FtsStore::commit")
+        );
+    }
+
+    #[test]
+    fn test_rewrite_question_ignores_declarative_queries() {
+        assert_eq!(rewrite_question("database locking"), None);
+        assert_eq!(rewrite_question("how?"), None);
+    }
+
     // ── detect_structural_intent ─────────────────────────────────────────────
 
     #[test]
@@ -1325,18 +2925,275 @@ mod tests {
         crate::output::set_quiet(false);
     }
 
+    // ── Merged child databases ───────────────────────────────────────────────
+
+    #[test]
+    fn test_project_label() {
+        let projects = vec![
+            ("/work/frontend".to_string(), "frontend".to_string()),
+            ("/work/backend".to_string(), "backend".to_string()),
+        ];
+        assert_eq!(
+            project_label("/work/backend/src/main.rs", &projects),
+            Some("backend")
+        );
+        assert_eq!(
+            project_label("frontend/src/app.ts", &projects),
+            Some("frontend")
+        );
+        assert_eq!(project_label("/work/frontend-old/a.ts", &projects), None);
+        assert_eq!(project_label("src/app.ts", &[]), None);
+    }
+
+    #[test]
+    fn test_merge_adjacent_results() {
+        let result = |path: &str, lines: (usize, usize), content: &str, score: f32| {
+            crate::vectordb::SearchResult {
+                id: lines.0 as u32,
+                content: content.to_string(),
+                path: path.to_string(),
+                start_line: lines.0,
+                end_line: lines.1,
+                kind: "Function".to_string(),
+                signature: None,
+                docstring: None,
+                context: None,
+                hash: String::new(),
+                distance: 1.0 - score,
+                score,
+                context_prev: Some(format!("before {}", lines.0)),
+                context_next: Some(format!("gap {}\nafter {}", lines.1, lines.1)),
+                text_match: false,
+            }
+        };
+        let results = vec![
+            // Follows the next chunk after a one-line gap
+            result("a.rs", (3, 5), "d\ne", 0.9),
+            result("b.rs", (0, 2), "x\ny", 0.8),
+            result("a.rs", (0, 2), "a\nb", 0.7),
+            // Overlaps the first chunk by one line
+            result("a.rs", (4, 6), "e\nf", 0.6),
+            result("a.rs", (20, 22), "far", 0.5),
+        ];
+
+        let merged = merge_adjacent_results(results);
+        let summary: Vec<(&str, usize, usize, f32)> = merged
+            .iter()
+            .map(|r| (r.path.as_str(), r.start_line, r.end_line, r.score))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                ("a.rs", 0, 6, 0.9),
+                ("b.rs", 0, 2, 0.8),
+                ("a.rs", 20, 22, 0.5)
+            ]
+        );
+        assert_eq!(merged[0].content, "a\nb\ngap 2\nd\ne\nf");
+        assert_eq!(merged[0].context_prev.as_deref(), Some("before 0"));
+        assert_eq!(merged[0].context_next.as_deref(), Some("gap 6\nafter 6"));
+    }
+
+    #[test]
+    fn test_expand_context() {
+        let dir = tempfile::tempdir().unwrap();
+        let source: Vec<String> = (0..10).map(|i| format!("line {}", i)).collect();
+        std::fs::write(dir.path().join("a.rs"), source.join("\n")).unwrap();
+        let result = |path: &str, start_line: usize, content: &str| crate::vectordb::SearchResult {
+            id: 0,
+            content: content.to_string(),
+            path: path.to_string(),
+            start_line,
+            end_line: start_line + 2,
+            kind: "Function".to_string(),
+            signature: None,
+            docstring: None,
+            context: None,
+            hash: String::new(),
+            distance: 0.0,
+            score: 1.0,
+            context_prev: None,
+            context_next: None,
+            text_match: false,
+        };
+        let mut results = vec![
+            result("a.rs", 4, "line 4\nline 5"),
+            // Edited since it was indexed
+            result("a.rs", 1, "fn old()"),
+            result("gone.rs", 4, "line 4"),
+        ];
+
+        // Indexes storing the default windows are left as they are
+        expand_context(&mut results, dir.path(), 3);
+        assert!(results[0].context_prev.is_none());
+
+        expand_context(&mut results, dir.path(), 0);
+        assert_eq!(
+            results[0].context_prev.as_deref(),
+            Some("line 1\nline 2\nline 3")
+        );
+        assert_eq!(
+            results[0].context_next.as_deref(),
+            Some("line 6\nline 7\nline 8")
+        );
+        assert!(results[1].context_prev.is_none());
+        assert!(results[2].context_next.is_none());
+    }
+
+    #[test]
+    fn test_apply_corrections() {
+        let correction = |from: &str, to: &str| SpellingCorrection {
+            from: from.to_string(),
+            to: to.to_string(),
+            applied: false,
+        };
+        let mut corrections = vec![
+            correction("parseConfg", "parseConfig"),
+            correction("loadFle", "loadFile"),
+        ];
+        assert_eq!(
+            apply_corrections("where is parseConfg called", &mut corrections),
+            "where is parseConfig called"
+        );
+        assert!(corrections[0].applied);
+        assert!(!corrections[1].applied);
+    }
+
+    #[test]
+    fn test_score_confidence() {
+        let result = |score: f32, distance: f32| crate::vectordb::SearchResult {
+            id: 0,
+            content: String::new(),
+            path: "src/lib.rs".to_string(),
+            start_line: 0,
+            end_line: 1,
+            kind: "Function".to_string(),
+            signature: None,
+            docstring: None,
+            context: None,
+            hash: String::new(),
+            distance,
+            score,
+            context_prev: None,
+            context_next: None,
+            text_match: false,
+        };
+        let scored = score_confidence(&[
+            result(0.032, 0.12), // close and well ahead
+            result(0.024, 0.15), // close, but the next is just behind
+            result(0.023, 0.25),
+            result(0.016, 0.45),
+        ]);
+        assert_eq!(
+            scored,
+            [
+                (100.0, Confidence::High),
+                (75.0, Confidence::Medium),
+                (71.9, Confidence::Medium),
+                (50.0, Confidence::Low)
+            ]
+        );
+
+        // Last result has nothing to lead; zero scores don't divide by zero
+        assert_eq!(
+            score_confidence(&[result(0.0, 0.1)]),
+            [(0.0, Confidence::High)]
+        );
+        assert!(score_confidence(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_min_score_and_no_confident_matches() {
+        let result = |signature: &str, score: f32, distance: f32| crate::vectordb::SearchResult {
+            id: 0,
+            content: String::new(),
+            path: "src/lib.rs".to_string(),
+            start_line: 0,
+            end_line: 1,
+            kind: "Function".to_string(),
+            signature: Some(signature.to_string()),
+            docstring: None,
+            context: None,
+            hash: String::new(),
+            distance,
+            score,
+            context_prev: None,
+            context_next: None,
+            text_match: false,
+        };
+        let results = vec![
+            result("pub fn load_config(path: &Path)", 0.030, 0.12),
+            result("fn parse(input: &str)", 0.024, 0.25),
+            result("class Unrelated:", 0.021, 0.45),
+        ];
+        assert_eq!(no_confident_matches("load settings", &results), None);
+        // Nothing is dropped unless min_score is given
+        assert_eq!(apply_min_score(results.clone(), None).len(), 3);
+        assert_eq!(apply_min_score(results.clone(), Some(0.0)).len(), 3);
+        // The unrelated tail goes; a higher min_score also drops the weaker result
+        assert_eq!(apply_min_score(results.clone(), Some(1.0)).len(), 2);
+        assert_eq!(apply_min_score(results.clone(), Some(90.0)).len(), 1);
+        // Full-text matches are kept however far they are
+        let mut text_matched = results;
+        text_matched[2].text_match = true;
+        assert_eq!(apply_min_score(text_matched, Some(90.0)).len(), 2);
+
+        let weak = vec![
+            result("export function readSettings(file) {", 0.03, 0.36),
+            result("def parseConfig(self, raw):", 0.02, 0.38),
+            result("export function readSettings(file) {", 0.01, 0.39),
+        ];
+        let guidance = no_confident_matches("where is parseConfig called", &weak).unwrap();
+        assert_eq!(guidance.identifiers, ["parseConfig"]);
+        assert_eq!(guidance.nearby_symbols, ["readSettings"]);
+        assert_eq!(
+            guidance.reformulations,
+            ["where is parse config called", "read settings"]
+        );
+        assert!(no_confident_matches("anything", &[]).is_some());
+        let mut text_matched = weak;
+        text_matched[1].text_match = true;
+        assert_eq!(no_confident_matches("parseConfig", &text_matched), None);
+    }
+
+    // ── --stdin query lines ──────────────────────────────────────────────────
+
+    #[test]
+    fn test_bulk_query_parse() {
+        let plain = BulkQuery::parse("where is the config loaded?").unwrap();
+        assert_eq!(plain.query, "where is the config loaded?");
+        assert!(plain.id.is_none());
+
+        let object = BulkQuery::parse(
+            r#"{"query": "retry logic", "id": 7, "max_results": 3, "filter_path": "src/"}"#,
+        )
+        .unwrap();
+        assert_eq!(object.query, "retry logic");
+        assert_eq!(object.id, Some(serde_json::json!(7)));
+        assert_eq!(object.max_results, Some(3));
+        assert_eq!(object.filter_path.as_deref(), Some("src/"));
+
+        assert!(BulkQuery::parse(r#"{"id": 1}"#).is_err());
+        assert!(BulkQuery::parse(r#"{"query": "q", "limit": 3}"#).is_err());
+    }
+
     // ── JsonResult compact serialization ─────────────────────────────────────
 
     #[test]
     fn test_json_result_full_includes_content() {
         let r = JsonResult {
+            project: None,
             path: "src/foo.rs".to_string(),
             start_line: 1,
             end_line: 10,
             kind: "Function".to_string(),
             content: Some("fn foo() {}".to_string()),
             score: 0.9,
+            confidence: None,
             signature: None,
+            owners: Vec::new(),
+            license: None,
+            vendored: false,
             context_prev: None,
             context_next: None,
         };
@@ -1348,13 +3205,18 @@ mod tests {
     #[test]
     fn test_json_result_compact_omits_content() {
         let r = JsonResult {
+            project: None,
             path: "src/foo.rs".to_string(),
             start_line: 1,
             end_line: 10,
             kind: "Function".to_string(),
             content: None,
             score: 0.9,
+            confidence: None,
             signature: None,
+            owners: Vec::new(),
+            license: None,
+            vendored: false,
             context_prev: None,
             context_next: None,
         };
@@ -1367,13 +3229,18 @@ mod tests {
     #[test]
     fn test_json_result_compact_retains_required_fields() {
         let r = JsonResult {
+            project: None,
             path: "src/vectordb/store.rs".to_string(),
             start_line: 42,
             end_line: 80,
             kind: "Struct".to_string(),
             content: None,
             score: 0.75,
+            confidence: None,
             signature: Some("VectorStore".to_string()),
+            owners: Vec::new(),
+            license: None,
+            vendored: false,
             context_prev: None,
             context_next: None,
         };
@@ -1391,13 +3258,18 @@ mod tests {
     #[test]
     fn test_json_result_context_omitted_when_none() {
         let r = JsonResult {
+            project: None,
             path: "src/foo.rs".to_string(),
             start_line: 1,
             end_line: 5,
             kind: "Block".to_string(),
             content: Some("let x = 1;".to_string()),
             score: 0.5,
+            confidence: None,
             signature: None,
+            owners: Vec::new(),
+            license: None,
+            vendored: false,
             context_prev: None,
             context_next: None,
         };
@@ -1407,6 +3279,16 @@ mod tests {
         assert!(!json.contains("\"signature\""));
     }
 
+    #[test]
+    fn test_matches_path_filter_relative_and_absolute() {
+        let root = "/home/dev/project";
+        let matches =
+            |path: &str, filter: &str| PathFilter::new(filter, root).unwrap().matches(path);
+        assert!(matches("/home/dev/project/src/main.rs", "src/"));
+        assert!(matches("./src/lib.rs", "src"));
+        assert!(!matches("/home/dev/project/tests/it.rs", "src/"));
+    }
+
     // ── No stdout in search module ────────────────────────────────────────────
 
     #[test]
@@ -0,0 +1,134 @@
+//! `filter_path` matching: path prefixes and glob patterns
+//!
+//! A filter is a comma-separated list of terms. Terms without glob syntax are
+//! path prefixes (`src/api/`), as before; other terms are globs matched against
+//! the path relative to the project root (`src/**/handlers/*.rs`). A leading
+//! `!` excludes matches (`!**/*_test.go`). A path passes when it matches any
+//! including term (or there are none) and no excluding term.
+
+use anyhow::{Context, Result};
+use globset::{GlobBuilder, GlobMatcher};
+
+/// A parsed `filter_path` value
+#[derive(Debug, Clone)]
+pub struct PathFilter {
+    /// Normalized project root, stripped from absolute result paths
+    project_root: String,
+    include: Vec<Term>,
+    exclude: Vec<Term>,
+}
+
+#[derive(Debug, Clone)]
+enum Term {
+    Prefix(String),
+    Glob(GlobMatcher),
+}
+
+impl Term {
+    fn parse(term: &str) -> Result<Self> {
+        let term = crate::cache::normalize_path_str(term);
+        let term = term.trim_start_matches("./");
+        if !term.contains(['*', '?', '[', '{']) {
+            return Ok(Term::Prefix(term.to_string()));
+        }
+        let glob = GlobBuilder::new(term)
+            // `*` stays within a directory; `**` crosses them
+            .literal_separator(true)
+            .build()
+            .with_context(|| format!("Invalid glob pattern in filter_path: '{}'", term))?;
+        Ok(Term::Glob(glob.compile_matcher()))
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        match self {
+            Term::Prefix(prefix) => path.starts_with(prefix.as_str()),
+            Term::Glob(glob) => glob.is_match(path),
+        }
+    }
+}
+
+impl PathFilter {
+    /// Parse `filter` for results of the project at `project_root`
+    pub fn new(filter: &str, project_root: &str) -> Result<Self> {
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        for term in split_terms(filter) {
+            let term = term.trim();
+            if let Some(negated) = term.strip_prefix('!') {
+                exclude.push(Term::parse(negated)?);
+            } else if !term.is_empty() {
+                include.push(Term::parse(term)?);
+            }
+        }
+        Ok(Self {
+            project_root: crate::cache::normalize_path_str(project_root)
+                .trim_end_matches('/')
+                .to_string(),
+            include,
+            exclude,
+        })
+    }
+
+    /// Whether a result `path` (absolute or relative) passes the filter
+    pub fn matches(&self, path: &str) -> bool {
+        let path = crate::cache::normalize_path_str(path);
+        // Strip project root to convert absolute → relative path
+        let relative = path
+            .strip_prefix(&self.project_root)
+            .unwrap_or(&path)
+            .trim_start_matches('/')
+            .trim_start_matches("./");
+        (self.include.is_empty() || self.include.iter().any(|t| t.matches(relative)))
+            && !self.exclude.iter().any(|t| t.matches(relative))
+    }
+}
+
+/// Split on commas outside `{...}` alternations
+fn split_terms(filter: &str) -> Vec<&str> {
+    let mut terms = Vec::new();
+    let (mut depth, mut start) = (0usize, 0);
+    for (i, c) in filter.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ',' if depth == 0 => {
+                terms.push(&filter[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    terms.push(&filter[start..]);
+    terms
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_prefixes_globs_and_exclusions() {
+        let root = "/work/project";
+        let filter = PathFilter::new("src/api/", root).unwrap();
+        assert!(filter.matches("/work/project/src/api/routes.rs"));
+        assert!(filter.matches("src/api/routes.rs"));
+        assert!(!filter.matches("/work/project/src/db/pool.rs"));
+
+        let filter = PathFilter::new("src/**/handlers/*.rs", root).unwrap();
+        assert!(filter.matches("/work/project/src/api/v1/handlers/user.rs"));
+        assert!(!filter.matches("/work/project/src/api/handlers/nested/user.rs"));
+        assert!(!filter.matches("/work/project/src/api/handlers/user.go"));
+
+        let filter = PathFilter::new("!**/*_test.go", root).unwrap();
+        assert!(filter.matches("/work/project/pkg/server.go"));
+        assert!(!filter.matches("/work/project/pkg/server_test.go"));
+
+        let filter = PathFilter::new("src/**/*.{rs,toml}, !src/generated/", root).unwrap();
+        assert!(filter.matches("/work/project/src/lib.rs"));
+        assert!(filter.matches("/work/project/src/cfg/app.toml"));
+        assert!(!filter.matches("/work/project/src/generated/api.rs"));
+        assert!(!filter.matches("/work/project/README.md"));
+
+        assert!(PathFilter::new("src/[", root).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+//! Where a symbol is used
+//!
+//! The full-text index finds the chunks that mention a name; each one is a
+//! reference at the chunk's first line, best match first. Chunks the vector
+//! store no longer has (deleted after the FTS index was committed) are skipped,
+//! so twice the requested number of hits is inspected.
+
+use anyhow::Result;
+
+use crate::fts::FtsStore;
+use crate::vectordb::VectorStore;
+
+/// A chunk that uses a symbol
+#[derive(Debug, Clone)]
+pub struct Reference {
+    pub path: String,
+    pub line: usize,
+    pub kind: String,
+    /// Signature of the containing function or type
+    pub signature: Option<String>,
+    /// FTS relevance score
+    pub score: f32,
+}
+
+/// At most `limit` references to `symbol`, best match first
+pub fn find_references(
+    symbol: &str,
+    fts: &FtsStore,
+    store: &VectorStore,
+    limit: usize,
+) -> Result<Vec<Reference>> {
+    let mut references = Vec::new();
+    for hit in fts.search(symbol, limit * 2, None)? {
+        if references.len() == limit {
+            break;
+        }
+        if let Some(chunk) = store.get_chunk(hit.chunk_id)? {
+            references.push(Reference {
+                path: chunk.path,
+                line: chunk.start_line,
+                kind: chunk.kind,
+                signature: chunk.signature,
+                score: hit.score,
+            });
+        }
+    }
+    Ok(references)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunker::{Chunk, ChunkKind};
+    use crate::embed::EmbeddedChunk;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_find_references() -> Result<()> {
+        let dir = tempdir()?;
+        let chunks = [
+            ("src/main.rs", "fn main() { let config = load_config(); }"),
+            (
+                "src/config.rs",
+                "pub fn load_config() -> Config { Config::default() }",
+            ),
+            ("src/old.rs", "fn legacy() { load_config(); }"),
+            ("src/util.rs", "fn unrelated() {}"),
+        ];
+        let mut store = VectorStore::new(&dir.path().join("db"), 4)?;
+        let mut fts = FtsStore::new(dir.path())?;
+        let embedded = chunks
+            .iter()
+            .map(|(path, content)| {
+                let chunk = Chunk::new(
+                    content.to_string(),
+                    3,
+                    5,
+                    ChunkKind::Function,
+                    path.to_string(),
+                );
+                EmbeddedChunk::new(chunk, vec![1.0, 0.0, 0.0, 0.0])
+            })
+            .collect();
+        let ids = store.insert_chunks_with_ids(embedded)?;
+        for (id, (path, content)) in ids.iter().zip(chunks) {
+            fts.add_chunk(*id, content, path, None, "Function")?;
+        }
+        fts.commit()?;
+        // Deleted from the store but still in the FTS index
+        store.delete_chunks(&[ids[2]])?;
+
+        let references = find_references("load_config", &fts, &store, 10)?;
+        let mut paths: Vec<&str> = references.iter().map(|r| r.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, ["src/config.rs", "src/main.rs"]);
+        assert!(references.iter().all(|r| r.line == 3));
+
+        assert_eq!(find_references("load_config", &fts, &store, 1)?.len(), 1);
+        assert!(find_references("missing_symbol", &fts, &store, 10)?.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,230 @@
+//! `--template` output: one line per result, shaped by the user
+//!
+//! A template is text with `{field}` placeholders, e.g.
+//! `'{path}:{start_line} {signature}'`; `{{` and `}}` print literal braces.
+//! Values are escaped so each result stays on one line: newlines, tabs and
+//! other control characters print as `\n`, `\t` or `\u{..}`. A filter after
+//! `|` picks another escaping:
+//!
+//! - `{path|shell}` quotes the value for a POSIX shell when it holds spaces,
+//!   quotes or other special characters, so it is always one word
+//! - `{path|json}` prints a JSON string literal
+//! - `{content|raw}` prints the value unchanged
+
+use anyhow::{anyhow, bail, Result};
+
+use super::JsonResult;
+
+/// Fields a template can use
+const FIELDS: &[&str] = &[
+    "path",
+    "start_line",
+    "end_line",
+    "kind",
+    "score",
+    "confidence",
+    "signature",
+    "content",
+    "project",
+    "owners",
+    "license",
+    "vendored",
+];
+
+/// How a value is written into the output
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Escape {
+    /// Control characters escaped, so the result stays on one line
+    Line,
+    Shell,
+    Json,
+    Raw,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(String),
+    Field(&'static str, Escape),
+}
+
+/// A parsed `--template` value
+#[derive(Debug, Clone)]
+pub struct OutputTemplate {
+    parts: Vec<Part>,
+}
+
+impl OutputTemplate {
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut placeholder = String::new();
+                    let mut closed = false;
+                    for c in chars.by_ref() {
+                        if c == '}' {
+                            closed = true;
+                            break;
+                        }
+                        placeholder.push(c);
+                    }
+                    if !closed {
+                        bail!("Unclosed '{{' in template (use '{{{{' for a literal brace)");
+                    }
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(parse_placeholder(&placeholder)?);
+                }
+                '}' => bail!("Unmatched '}}' in template (use '}}}}' for a literal brace)"),
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self { parts })
+    }
+
+    /// The output line of one result
+    pub(super) fn render(&self, result: &JsonResult) -> String {
+        let mut line = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => line.push_str(text),
+                Part::Field(field, escape) => {
+                    line.push_str(&escape_value(&field_value(result, field), *escape))
+                }
+            }
+        }
+        line
+    }
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Part> {
+    let (name, filter) = match placeholder.split_once('|') {
+        Some((name, filter)) => (name.trim(), Some(filter.trim())),
+        None => (placeholder.trim(), None),
+    };
+    let field = FIELDS.iter().find(|&&field| field == name).ok_or_else(|| {
+        anyhow!(
+            "Unknown template field '{{{}}}'; fields: {}",
+            name,
+            FIELDS.join(", ")
+        )
+    })?;
+    let escape = match filter {
+        None => Escape::Line,
+        Some("shell") => Escape::Shell,
+        Some("json") => Escape::Json,
+        Some("raw") => Escape::Raw,
+        Some(other) => bail!(
+            "Unknown template filter '{}' in '{{{}}}'; filters: shell, json, raw",
+            other,
+            placeholder
+        ),
+    };
+    Ok(Part::Field(field, escape))
+}
+
+/// Value of `field`; missing values are empty
+fn field_value(result: &JsonResult, field: &str) -> String {
+    match field {
+        "path" => result.path.clone(),
+        "start_line" => result.start_line.to_string(),
+        "end_line" => result.end_line.to_string(),
+        "kind" => result.kind.clone(),
+        "score" => format!("{:.2}", result.score),
+        "confidence" => result
+            .confidence
+            .map_or_else(String::new, |confidence| confidence.to_string()),
+        "signature" => result.signature.clone().unwrap_or_default(),
+        "content" => result.content.clone().unwrap_or_default(),
+        "project" => result.project.clone().unwrap_or_default(),
+        "owners" => result.owners.join(","),
+        "license" => result.license.clone().unwrap_or_default(),
+        "vendored" => result.vendored.to_string(),
+        _ => String::new(),
+    }
+}
+
+fn escape_value(value: &str, escape: Escape) -> String {
+    match escape {
+        Escape::Raw => value.to_string(),
+        Escape::Json => serde_json::Value::String(value.to_string()).to_string(),
+        Escape::Shell => shell_quote(value),
+        Escape::Line => {
+            let mut escaped = String::with_capacity(value.len());
+            for c in value.chars() {
+                match c {
+                    '\n' => escaped.push_str("\\n"),
+                    '\r' => escaped.push_str("\\r"),
+                    '\t' => escaped.push_str("\\t"),
+                    c if c.is_control() => escaped.push_str(&format!("\\u{{{:x}}}", c as u32)),
+                    c => escaped.push(c),
+                }
+            }
+            escaped
+        }
+    }
+}
+
+/// Quote `value` as one POSIX shell word, leaving plain words as they are
+fn shell_quote(value: &str) -> String {
+    let plain = !value.is_empty()
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "_-./:@%+=,".contains(c));
+    if plain {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_template() {
+        let result = JsonResult {
+            path: "src/my dir/it's.rs".to_string(),
+            start_line: 12,
+            end_line: 20,
+            kind: "Function".to_string(),
+            content: Some("fn load() {\n\tok\n}".to_string()),
+            score: 87.456,
+            signature: Some("fn load()".to_string()),
+            ..Default::default()
+        };
+        let render = |template: &str| OutputTemplate::parse(template).unwrap().render(&result);
+
+        assert_eq!(
+            render("{path}:{start_line} {signature}"),
+            "src/my dir/it's.rs:12 fn load()"
+        );
+        assert_eq!(render("{path|shell}"), r"'src/my dir/it'\''s.rs'");
+        assert_eq!(render("{kind|shell} {score}"), "Function 87.46");
+        assert_eq!(render("{content}"), r"fn load() {\n\tok\n}");
+        assert_eq!(render("{content|json}"), r#""fn load() {\n\tok\n}""#);
+        assert_eq!(render("{content|raw}"), "fn load() {\n\tok\n}");
+        assert_eq!(render("{{{ license }}}{project}"), "{}");
+
+        assert!(OutputTemplate::parse("{path").is_err());
+        assert!(OutputTemplate::parse("path}").is_err());
+        let unknown = OutputTemplate::parse("{file}").unwrap_err().to_string();
+        assert!(unknown.contains("fields: path, start_line"));
+        assert!(OutputTemplate::parse("{path|upper}").is_err());
+    }
+}
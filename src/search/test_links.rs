@@ -0,0 +1,347 @@
+//! Links between tests and the code they exercise
+//!
+//! Heuristic, computed at query time from the indexed chunks: a test is
+//! linked to a symbol when it calls it, or when the test's name spells the
+//! symbol's name (`test_parse_config` → `parse_config`, `TestParseConfig` →
+//! `ParseConfig`).
+
+use anyhow::Result;
+
+use super::{identifier_words, symbol_name};
+use crate::fts::FtsStore;
+use crate::vectordb::{SearchResult, VectorStore};
+
+/// FTS hits inspected when looking for tests of a symbol
+const TEST_CANDIDATES: usize = 200;
+
+/// FTS hits inspected per called identifier when looking for its definition
+const DEFINITION_CANDIDATES: usize = 20;
+
+/// Calls too generic to point at the code under test
+const UNINFORMATIVE_CALLS: &[&str] = &[
+    "assert",
+    "assert_eq",
+    "assert_ne",
+    "assertEqual",
+    "assertEquals",
+    "assertTrue",
+    "expect",
+    "unwrap",
+    "to_string",
+    "into",
+    "clone",
+    "new",
+    "len",
+    "Some",
+    "Ok",
+    "Err",
+    "println",
+    "format",
+    "vec",
+    "push",
+    "iter",
+    "collect",
+    "map",
+    "describe",
+    "it",
+    "test",
+    "toBe",
+    "toEqual",
+    "Equal",
+    "Fatalf",
+    "Errorf",
+];
+
+/// Markers that make a chunk a test, wherever it lives
+const TEST_MARKERS: &[&str] = &[
+    "#[test]",
+    "#[tokio::test",
+    "@Test",
+    "[Test]",
+    "[Fact]",
+    "[Theory]",
+    "[TestMethod]",
+    "*testing.T",
+];
+
+/// A chunk linked to a test or a tested symbol
+#[derive(Debug, Clone)]
+pub struct TestLink {
+    pub result: SearchResult,
+    /// Symbol the chunk declares
+    pub symbol: String,
+    /// Calls from the test to the tested symbol
+    pub calls: usize,
+    /// Whether the test's name spells the tested symbol's name
+    pub name_match: bool,
+}
+
+/// Whether a path belongs to a test suite (`tests/`, `foo_test.go`, `foo.spec.ts`)
+fn is_test_path(path: &str) -> bool {
+    let path = path.replace('\\', "/");
+    let mut components = path.split('/');
+    let file = components.next_back().unwrap_or_default();
+    let in_test_dir = components.any(|c| {
+        matches!(
+            c,
+            "test" | "tests" | "__tests__" | "spec" | "specs" | "testing"
+        )
+    });
+    let stem = file.split('.').next().unwrap_or_default();
+    in_test_dir
+        || stem.starts_with("test_")
+        || stem.ends_with("_test")
+        || stem.ends_with("_spec")
+        || stem.ends_with("Test")
+        || stem.ends_with("Tests")
+        || file.contains(".test.")
+        || file.contains(".spec.")
+}
+
+/// Whether a symbol name is a test's (`test_foo`, `TestFoo`, `testFoo`)
+fn is_test_name(name: &str) -> bool {
+    identifier_words(name)
+        .split(' ')
+        .next()
+        .is_some_and(|word| word == "test" || word == "tests")
+}
+
+/// Whether a chunk is (part of) a test
+pub fn is_test_chunk(result: &SearchResult) -> bool {
+    is_test_path(&result.path)
+        || declared_symbol(result).is_some_and(|name| is_test_name(&name))
+        || TEST_MARKERS
+            .iter()
+            .any(|marker| result.content.contains(marker))
+}
+
+/// Symbol a chunk declares, from its signature or innermost breadcrumb
+pub(super) fn declared_symbol(result: &SearchResult) -> Option<String> {
+    result
+        .signature
+        .as_deref()
+        .and_then(symbol_name)
+        .or_else(|| {
+            let (_, name) = result
+                .context
+                .as_deref()?
+                .rsplit(" > ")
+                .next()?
+                .split_once(": ")?;
+            Some(name.trim().to_string())
+        })
+}
+
+/// Identifiers called in `content` (`foo(`, `obj.foo(`, `Type::foo(`), with repeats
+fn called_identifiers(content: &str) -> Vec<&str> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    let mut calls = Vec::new();
+    let mut start = None;
+    for (i, c) in content.char_indices() {
+        match (is_ident(c), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                let ident = &content[s..i];
+                // `name!(` is a macro call, not a function call
+                if content[i..].trim_start().starts_with('(')
+                    && !ident.starts_with(|c: char| c.is_ascii_digit())
+                {
+                    calls.push(ident);
+                }
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    calls
+}
+
+/// Whether the test's name, minus its `test` prefix, spells `symbol`
+fn names_symbol(test_name: &str, symbol: &str) -> bool {
+    let test_words = identifier_words(test_name);
+    let test_words = test_words
+        .strip_prefix("tests ")
+        .or_else(|| test_words.strip_prefix("test "))
+        .unwrap_or(&test_words);
+    let symbol_words = identifier_words(symbol);
+    !symbol_words.is_empty() && format!(" {test_words} ").contains(&format!(" {symbol_words} "))
+}
+
+/// Tests that exercise `symbol`, most direct first
+///
+/// A test qualifies when it calls the symbol or its name spells it.
+pub fn tests_for(
+    symbol: &str,
+    fts: &FtsStore,
+    store: &VectorStore,
+    limit: usize,
+) -> Result<Vec<TestLink>> {
+    let symbol = symbol.rsplit(['.', ':']).next().unwrap_or(symbol);
+    let mut links = Vec::new();
+    for hit in fts.search(symbol, TEST_CANDIDATES, None)? {
+        let Some(result) = store.get_chunk_as_result(hit.chunk_id)? else {
+            continue;
+        };
+        if !is_test_chunk(&result) {
+            continue;
+        }
+        let Some(test_name) = declared_symbol(&result) else {
+            continue;
+        };
+        let calls = called_identifiers(&result.content)
+            .into_iter()
+            .filter(|call| *call == symbol)
+            .count();
+        let name_match = names_symbol(&test_name, symbol);
+        if calls > 0 || name_match {
+            links.push(TestLink {
+                result,
+                symbol: test_name,
+                calls,
+                name_match,
+            });
+        }
+    }
+    rank(&mut links, limit);
+    Ok(links)
+}
+
+/// Definitions a test exercises, most direct first
+///
+/// `None` when no indexed test is named `test`.
+pub fn implementation_for(
+    test: &str,
+    fts: &FtsStore,
+    store: &VectorStore,
+    limit: usize,
+) -> Result<Option<Vec<TestLink>>> {
+    let mut test_chunk = None;
+    for hit in fts.search(test, DEFINITION_CANDIDATES, None)? {
+        if let Some(result) = store.get_chunk_as_result(hit.chunk_id)? {
+            if declared_symbol(&result).as_deref() == Some(test) && is_test_chunk(&result) {
+                test_chunk = Some(result);
+                break;
+            }
+        }
+    }
+    let Some(test_chunk) = test_chunk else {
+        return Ok(None);
+    };
+
+    // Called identifiers with their call counts, in order of first call
+    let mut candidates: Vec<(&str, usize)> = Vec::new();
+    for call in called_identifiers(&test_chunk.content) {
+        if call == test || UNINFORMATIVE_CALLS.contains(&call) {
+            continue;
+        }
+        match candidates.iter_mut().find(|(name, _)| *name == call) {
+            Some((_, count)) => *count += 1,
+            None => candidates.push((call, 1)),
+        }
+    }
+
+    let mut links: Vec<TestLink> = Vec::new();
+    for (name, calls) in candidates {
+        for hit in fts.search(name, DEFINITION_CANDIDATES, None)? {
+            let Some(result) = store.get_chunk_as_result(hit.chunk_id)? else {
+                continue;
+            };
+            if declared_symbol(&result).as_deref() != Some(name)
+                || is_test_chunk(&result)
+                || links.iter().any(|link| link.result.id == result.id)
+            {
+                continue;
+            }
+            links.push(TestLink {
+                result,
+                symbol: name.to_string(),
+                calls,
+                name_match: names_symbol(test, name),
+            });
+        }
+    }
+    rank(&mut links, limit);
+    Ok(Some(links))
+}
+
+/// Name matches first, then the most calls
+fn rank(links: &mut Vec<TestLink>, limit: usize) {
+    links.sort_by_key(|link| std::cmp::Reverse((link.name_match, link.calls)));
+    links.truncate(limit);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunker::{Chunk, ChunkKind};
+    use crate::embed::EmbeddedChunk;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_tests_and_implementation_linked() -> Result<()> {
+        let dir = tempdir()?;
+        let chunks = [
+            ("src/config.rs", "fn parse_config(text: &str) -> Config", "fn parse_config(text: &str) -> Config { Config::default() }"),
+            ("src/config.rs", "fn load_file(path: &Path) -> String", "fn load_file(path: &Path) -> String { read(path) }"),
+            ("src/config.rs", "fn test_parse_config()", "#[test]\nfn test_parse_config() {\n    let text = load_file(path);\n    assert_eq!(parse_config(&text), parse_config(\"\"));\n}"),
+            ("tests/load.rs", "fn loads_from_disk()", "fn loads_from_disk() { load_file(Path::new(\"a\")); }"),
+        ];
+        let mut store = VectorStore::new(&dir.path().join("db"), 4)?;
+        let mut fts = FtsStore::new(dir.path())?;
+        let embedded = chunks
+            .iter()
+            .map(|(path, signature, content)| {
+                let mut chunk = Chunk::new(
+                    content.to_string(),
+                    0,
+                    5,
+                    ChunkKind::Function,
+                    path.to_string(),
+                );
+                chunk.signature = Some(signature.to_string());
+                EmbeddedChunk::new(chunk, vec![1.0, 0.0, 0.0, 0.0])
+            })
+            .collect();
+        let ids = store.insert_chunks_with_ids(embedded)?;
+        for (id, (path, signature, content)) in ids.iter().zip(chunks) {
+            fts.add_chunk(*id, content, path, Some(signature), "Function")?;
+        }
+        fts.commit()?;
+
+        let tests = tests_for("parse_config", &fts, &store, 10)?;
+        assert_eq!(tests.len(), 1);
+        assert_eq!(tests[0].symbol, "test_parse_config");
+        assert_eq!(tests[0].calls, 2);
+        assert!(tests[0].name_match);
+
+        // Tests in test directories count too, by calls alone
+        let tests = tests_for("load_file", &fts, &store, 10)?;
+        let mut names: Vec<&str> = tests.iter().map(|t| t.symbol.as_str()).collect();
+        names.sort();
+        assert_eq!(names, ["loads_from_disk", "test_parse_config"]);
+
+        let implementation = implementation_for("test_parse_config", &fts, &store, 10)?.unwrap();
+        let names: Vec<&str> = implementation.iter().map(|l| l.symbol.as_str()).collect();
+        assert_eq!(names, ["parse_config", "load_file"]);
+        assert!(implementation_for("parse_config", &fts, &store, 10)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_test_detection() {
+        assert!(is_test_path("tests/search.rs"));
+        assert!(is_test_path("pkg/server_test.go"));
+        assert!(is_test_path("src/app.spec.ts"));
+        assert!(is_test_path("src/test/java/FooTest.java"));
+        assert!(!is_test_path("src/contest.rs"));
+        assert!(is_test_name("TestServe"));
+        assert!(is_test_name("test_serve"));
+        assert!(!is_test_name("testimony"));
+        assert_eq!(
+            called_identifiers("let x = Foo::new(); x.run (1); vec![y(2)]; 3(z)"),
+            ["new", "run", "y"]
+        );
+        assert!(names_symbol("TestParseConfig", "parse_config"));
+        assert!(!names_symbol("test_parse_configs_twice", "parse_config"));
+    }
+}
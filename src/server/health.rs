@@ -0,0 +1,244 @@
+//! Liveness and readiness probes of `codesearch serve`, for container orchestrators
+//!
+//! The listener is bound before the index is refreshed and the model loaded,
+//! so `/healthz` answers from the start and `/readyz` turns ready once the
+//! store is open and its vector index built. On SIGTERM (or Ctrl-C) `/readyz`
+//! reports draining for `CODESEARCH_DRAIN_SECS` while requests are still
+//! served, giving load balancers time to stop routing here; then the listener
+//! closes and the requests in flight finish.
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, State},
+    http::{request::Parts, StatusCode},
+    Json,
+};
+use serde::Serialize;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+use super::ServerState;
+
+/// Seconds `/readyz` reports draining before the listener closes, unless configured
+const DEFAULT_DRAIN_SECS: u64 = 5;
+
+/// Where the server is between start and shutdown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub(super) enum Phase {
+    /// Finding the database
+    Starting,
+    /// Creating or refreshing the index
+    Indexing,
+    /// Loading the embedding model and opening the store
+    Loading,
+    Ready,
+    /// Shutting down; requests are still served
+    Draining,
+}
+
+impl Phase {
+    const ALL: [Phase; 5] = [
+        Phase::Starting,
+        Phase::Indexing,
+        Phase::Loading,
+        Phase::Ready,
+        Phase::Draining,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Phase::Starting => "starting",
+            Phase::Indexing => "indexing",
+            Phase::Loading => "loading",
+            Phase::Ready => "ready",
+            Phase::Draining => "draining",
+        }
+    }
+}
+
+/// Router state: the phase, and the index once it is loaded
+pub(super) struct App {
+    phase: AtomicU8,
+    server: OnceLock<Arc<ServerState>>,
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self {
+            phase: AtomicU8::new(Phase::Starting as u8),
+            server: OnceLock::new(),
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        Phase::ALL[self.phase.load(Ordering::SeqCst) as usize]
+    }
+
+    pub fn set_phase(&self, phase: Phase) {
+        // A shutdown that started during loading is not undone
+        let _ = self
+            .phase
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |current| {
+                (current != Phase::Draining as u8).then_some(phase as u8)
+            });
+    }
+
+    /// Serve requests from `state` from now on
+    pub fn loaded(&self, state: Arc<ServerState>) {
+        let _ = self.server.set(state);
+        self.set_phase(Phase::Ready);
+    }
+}
+
+/// Extracts the loaded index for the handlers that need it; 503 until then
+pub(super) struct Loaded(pub Arc<ServerState>);
+
+#[async_trait]
+impl FromRequestParts<Arc<App>> for Loaded {
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(
+        _parts: &mut Parts,
+        app: &Arc<App>,
+    ) -> Result<Self, Self::Rejection> {
+        app.server.get().cloned().map(Loaded).ok_or_else(|| {
+            (
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("Index not loaded yet ({})", app.phase().as_str()),
+            )
+        })
+    }
+}
+
+/// Probe response body
+#[derive(Debug, Serialize)]
+pub(super) struct ProbeResponse {
+    status: &'static str,
+    phase: &'static str,
+    store_open: bool,
+    indexed: bool,
+}
+
+async fn probe(
+    app: &App,
+    ok: impl FnOnce(Phase, bool, bool) -> bool,
+) -> (StatusCode, Json<ProbeResponse>) {
+    let phase = app.phase();
+    let (store_open, indexed) = match app.server.get() {
+        Some(state) => match state.stores.vector_store.read().await.stats() {
+            // A project without indexable files has no vector index to build
+            Ok(stats) => (true, stats.indexed || stats.total_chunks == 0),
+            Err(e) => {
+                tracing::warn!("⚠️  Probe: vector store unavailable: {}", e);
+                (false, false)
+            }
+        },
+        None => (false, false),
+    };
+    let ok = ok(phase, store_open, indexed);
+    let status = if ok {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (
+        status,
+        Json(ProbeResponse {
+            status: if ok { "ok" } else { "unavailable" },
+            phase: phase.as_str(),
+            store_open,
+            indexed,
+        }),
+    )
+}
+
+/// Liveness: fails only when the store of a loaded server can't be read
+pub(super) async fn healthz_handler(
+    State(app): State<Arc<App>>,
+) -> (StatusCode, Json<ProbeResponse>) {
+    probe(&app, |phase, store_open, _| {
+        store_open || matches!(phase, Phase::Starting | Phase::Indexing | Phase::Loading)
+    })
+    .await
+}
+
+/// Readiness: the index is loaded and built, and the server isn't draining
+pub(super) async fn readyz_handler(
+    State(app): State<Arc<App>>,
+) -> (StatusCode, Json<ProbeResponse>) {
+    probe(&app, |phase, store_open, indexed| {
+        phase == Phase::Ready && store_open && indexed
+    })
+    .await
+}
+
+/// Seconds to report draining before closing the listener, from `CODESEARCH_DRAIN_SECS`
+fn drain_secs() -> u64 {
+    std::env::var("CODESEARCH_DRAIN_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(DEFAULT_DRAIN_SECS)
+}
+
+/// Wait for Ctrl-C (via the cancellation token) or, on Unix, SIGTERM from the service manager
+/// or container runtime
+pub async fn wait_for_shutdown(cancel_token: &CancellationToken) {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut sigterm) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = cancel_token.cancelled() => {}
+                _ = sigterm.recv() => {}
+            }
+            return;
+        }
+    }
+    cancel_token.cancelled().await;
+}
+
+/// Resolves when the listener should close: after SIGTERM or Ctrl-C and the drain period
+pub(super) async fn shutdown_signal(app: Arc<App>, cancel_token: CancellationToken) {
+    wait_for_shutdown(&cancel_token).await;
+    app.set_phase(Phase::Draining);
+    let drain = drain_secs();
+    tracing::info!(
+        "🛑 Shutting down: draining for {}s, then finishing requests in flight",
+        drain
+    );
+    tokio::time::sleep(Duration::from_secs(drain)).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_probes_before_loading_and_draining() {
+        let app = Arc::new(App::new());
+        let (status, Json(body)) = healthz_handler(State(app.clone())).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body.phase, "starting");
+        let (status, _) = readyz_handler(State(app.clone())).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+
+        app.set_phase(Phase::Indexing);
+        assert_eq!(app.phase(), Phase::Indexing);
+        assert!(
+            Loaded::from_request_parts(&mut axum::http::Request::new(()).into_parts().0, &app)
+                .await
+                .is_err()
+        );
+
+        // Draining sticks, even if loading finishes afterwards
+        app.set_phase(Phase::Draining);
+        app.set_phase(Phase::Ready);
+        assert_eq!(app.phase(), Phase::Draining);
+        let (status, Json(body)) = readyz_handler(State(app)).await;
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(body.phase, "draining");
+    }
+}
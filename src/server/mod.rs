@@ -1,35 +1,43 @@
+use crate::out_print;
 use anyhow::Result;
 use axum::{
-    extract::{Json, State},
-    http::StatusCode,
+    body::Body,
+    extract::Json,
+    http::{header, StatusCode},
+    response::Response,
     routing::{get, post},
     Router,
 };
 use colored::Colorize;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 use tokio::sync::RwLock;
+use tokio_util::io::ReaderStream;
 
-use crate::cache::FileMetaStore;
-use crate::chunker::SemanticChunker;
 use crate::db_discovery::find_best_database;
-use crate::embed::{EmbeddingService, ModelType};
-use crate::file::FileWalker;
-use crate::output::set_quiet;
-use crate::vectordb::VectorStore;
-use crate::watch::{FileEvent, FileWatcher};
+use crate::embed::{resolve_pool_size, EmbedderPool, EmbedderPoolStats, ModelType};
+use crate::index::{IndexManager, SharedStores};
+use crate::mcp::{list_databases, FindDatabasesResponse};
+use crate::search::find_references;
+use crate::vectordb::{ChunkHead, VectorStore};
+
+mod health;
+mod projects;
+
+pub use health::wait_for_shutdown;
+use health::{App, Loaded, Phase};
+pub use projects::{serve_projects, ServedProject};
 
 /// Shared server state
 struct ServerState {
-    store: RwLock<VectorStore>,
-    embedding_service: Mutex<EmbeddingService>,
-    chunker: Mutex<SemanticChunker>,
-    file_meta: RwLock<FileMetaStore>,
+    stores: Arc<SharedStores>,
+    embedders: EmbedderPool,
+    /// Model the index was built with
+    model: ModelType,
     root: PathBuf,
-    db_path: PathBuf,
 }
 
 /// Search request body
@@ -40,12 +48,38 @@ struct SearchRequest {
     limit: usize,
     #[serde(default)]
     path: Option<String>,
+    /// Return every chunk in the index, ranked (overrides `limit`)
+    #[serde(default)]
+    all: bool,
+    /// Project to search: its directory name or a path inside it
+    #[serde(default)]
+    project: Option<String>,
 }
 
+/// Chunks loaded per store read lock while streaming results
+const STREAM_BATCH_CHUNKS: usize = 256;
+/// Bytes buffered between the streaming task and the HTTP response
+const STREAM_BUFFER_BYTES: usize = 64 * 1024;
+
 fn default_limit() -> usize {
     25
 }
 
+/// References request body
+#[derive(Debug, Deserialize)]
+struct ReferencesRequest {
+    symbol: String,
+    #[serde(default = "default_references_limit")]
+    limit: usize,
+    /// Project to search: its directory name or a path inside it
+    #[serde(default)]
+    project: Option<String>,
+}
+
+fn default_references_limit() -> usize {
+    20
+}
+
 /// Search response
 #[derive(Debug, Serialize)]
 struct SearchResponse {
@@ -64,6 +98,24 @@ struct SearchResult {
     score: f32,
 }
 
+/// References response
+#[derive(Debug, Serialize)]
+struct ReferencesResponse {
+    references: Vec<ReferenceResult>,
+    symbol: String,
+    took_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct ReferenceResult {
+    path: String,
+    line: usize,
+    kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    signature: Option<String>,
+    score: f32,
+}
+
 /// Health check response
 #[derive(Debug, Serialize)]
 struct HealthResponse {
@@ -81,6 +133,10 @@ struct StatusResponse {
     indexed: bool,
     model: String,
     dimensions: usize,
+    /// Another instance holds the writer lock and updates the index
+    readonly: bool,
+    /// Whether file changes are being indexed
+    watching: bool,
 }
 
 /// Run the background server with live file watching
@@ -90,22 +146,89 @@ struct StatusResponse {
 /// 2. Built-in file watching with native notify crate
 /// 3. Two-level change detection (mtime + hash)
 /// 4. Tracks chunk IDs for efficient incremental updates
+///
+/// The listener is bound first, so `/healthz` and `/readyz` answer while the
+/// index is refreshed and the model loaded. SIGTERM and Ctrl-C drain the
+/// server (see [`health`]) before it exits.
 pub async fn serve(
+    host: String,
     port: u16,
     path: Option<PathBuf>,
     create_index: bool,
-    _cancel_token: tokio_util::sync::CancellationToken,
+    embedders: Option<usize>,
+    cancel_token: tokio_util::sync::CancellationToken,
 ) -> Result<()> {
+    let addr = format!("{}:{}", host, port);
+    let listener = tokio::net::TcpListener::bind(&addr).await?;
+    let app = Arc::new(App::new());
+    let mut http = tokio::spawn(run_http(listener, app.clone(), cancel_token.clone()));
+
+    out_print!("{}", "🚀 Codesearch Server".bright_cyan().bold());
+    out_print!("{}", "=".repeat(60));
+    out_print!("🌐 Listening: http://{}", addr);
+    out_print!("  Probes: http://{}/healthz, http://{}/readyz", addr, addr);
+
+    // A shutdown while loading stops the server without waiting for the load
+    let state = tokio::select! {
+        state = load(&app, path, create_index, embedders) => state?,
+        result = &mut http => return result?,
+    };
+    app.loaded(state.clone());
+
+    // Keep the index up to date with the MCP server's file watcher, or follow
+    // the instance that does
+    let manager = if state.stores.readonly {
+        state.stores.follow_writer(cancel_token.clone());
+        None
+    } else {
+        let manager =
+            Arc::new(IndexManager::new_without_refresh(&state.root, state.stores.clone()).await?);
+        manager.start_file_watcher(cancel_token.clone()).await?;
+        Some(manager)
+    };
+
+    out_print!("\n{}", "🌐 Server ready!".bright_green().bold());
+    out_print!("  Health: http://{}/health", addr);
+    out_print!("  Search: POST http://{}/search", addr);
+    out_print!("  Stream: POST http://{}/search/stream", addr);
+    out_print!("  References: POST http://{}/references", addr);
+    out_print!("  Databases: http://{}/databases", addr);
+    out_print!("  Metrics: http://{}/metrics", addr);
+    if manager.is_some() {
+        out_print!("\n{}", "👀 Watching for file changes...".dimmed());
+    }
+
+    let result = http.await?;
+    // Let an in-flight index update commit before the runtime shuts down
+    if let Some(manager) = manager {
+        cancel_token.cancel();
+        manager
+            .wait_for_idle(Duration::from_secs(
+                crate::constants::SHUTDOWN_FLUSH_TIMEOUT_SECS,
+            ))
+            .await;
+    }
+    result
+}
+
+/// Find (or create) and refresh the index, load the embedding model and open the store
+async fn load(
+    app: &App,
+    path: Option<PathBuf>,
+    create_index: bool,
+    embedders: Option<usize>,
+) -> Result<Arc<ServerState>> {
     // Find the best database to use
     let mut db_info = find_best_database(path.as_deref())?;
 
     if db_info.is_none() {
         if create_index {
             // Automatically create index
-            println!("{}", "🚀 No index found, creating one...".bright_cyan());
+            app.set_phase(Phase::Indexing);
+            out_print!("{}", "🚀 No index found, creating one...".bright_cyan());
             let cancel_token_index = tokio_util::sync::CancellationToken::new();
             crate::index::index_quiet(path.clone(), false, cancel_token_index).await?;
-            println!("{}", "✅ Index created successfully!".green());
+            out_print!("{}", "✅ Index created successfully!".green());
 
             // Re-discover database after indexing
             db_info = find_best_database(path.as_deref())?;
@@ -126,363 +249,94 @@ pub async fn serve(
     let db_path = db_info.db_path;
     let root = db_info.project_path;
 
-    println!("{}", "🚀 Codesearch Server".bright_cyan().bold());
-    println!("{}", "=".repeat(60));
-    println!("📂 Root: {}", root.display());
-    println!("💾 Database: {}", db_path.display());
-    println!("🌐 Port: {}", port);
+    out_print!("📂 Root: {}", root.display());
+    out_print!("💾 Database: {}", db_path.display());
 
     if db_info.is_global {
-        println!("   {}", "(Global index)".dimmed());
+        out_print!("   {}", "(Global index)".dimmed());
     } else if !db_info.is_current {
-        println!("   {}", "(Parent directory index)".dimmed());
+        out_print!("   {}", "(Parent directory index)".dimmed());
     }
 
     // STEP 1: Perform incremental index refresh
-    println!("\n🔍 Performing incremental index refresh...");
+    app.set_phase(Phase::Indexing);
+    out_print!("\n🔍 Performing incremental index refresh...");
     crate::index::index_quiet(
         Some(root.clone()),
         false,
         tokio_util::sync::CancellationToken::new(),
     )
     .await?;
-    println!("✅ Index refresh completed");
+    out_print!("✅ Index refresh completed");
 
-    // Initialize embedding service
-    let model_type = ModelType::default();
-    println!("\n🔄 Loading embedding model...");
+    // Initialize embedder pool with the model the index was built with
+    app.set_phase(Phase::Loading);
+    let model = match crate::cache::IndexMetadata::load(&db_path)? {
+        Some(metadata) => metadata.model_type().0,
+        None => ModelType::default(),
+    };
+    let pool_size = resolve_pool_size(embedders);
+    out_print!(
+        "\n🔄 Loading embedding model ({} instance(s))...",
+        pool_size
+    );
     let cache_dir = crate::constants::get_global_models_cache_dir()?;
-    let embedding_service = EmbeddingService::with_cache_dir(model_type, Some(&cache_dir))?;
-    let dimensions = embedding_service.dimensions();
-
-    // Load or create file metadata store
-    let file_meta = FileMetaStore::load_or_create(&db_path, model_type.short_name(), dimensions)?;
-
-    // Open or create vector store
-    let store = VectorStore::new(&db_path, dimensions)?;
-    let stats = store.stats()?;
-
-    // If database is empty, do initial index
-    if stats.total_chunks == 0 {
-        println!(
-            "\n{}",
-            "📦 Database empty, performing initial index...".yellow()
-        );
-        let (store, file_meta) = initial_index(root.clone(), db_path.clone(), model_type).await?;
-
-        let state = Arc::new(ServerState {
-            store: RwLock::new(store),
-            embedding_service: Mutex::new(EmbeddingService::with_cache_dir(
-                model_type,
-                Some(&crate::constants::get_global_models_cache_dir()?),
-            )?),
-            chunker: Mutex::new(SemanticChunker::new(100, 2000, 10)),
-            file_meta: RwLock::new(file_meta),
-            root: root.clone(),
-            db_path: db_path.clone(),
-        });
-
-        // STEP 2: Start background file watcher
-        start_server(state, port, root).await
-    } else {
-        println!(
-            "✅ Database loaded: {} chunks from {} files",
-            stats.total_chunks, stats.total_files
+    let embedders = EmbedderPool::load(model, Some(&cache_dir), pool_size)?;
+
+    // The same stores the MCP server uses; another instance holding the writer
+    // lock keeps the index up to date instead
+    let (stores, readonly) = SharedStores::new_or_readonly(&db_path, model.dimensions())?;
+    let stats = stores.vector_store.read().await.stats()?;
+    out_print!(
+        "✅ Database loaded: {} chunks from {} files",
+        stats.total_chunks,
+        stats.total_files
+    );
+    if readonly {
+        out_print!(
+            "   {}",
+            "(Read-only: another instance is updating this index)".dimmed()
         );
-
-        let state = Arc::new(ServerState {
-            store: RwLock::new(store),
-            embedding_service: Mutex::new(embedding_service),
-            chunker: Mutex::new(SemanticChunker::new(100, 2000, 10)),
-            file_meta: RwLock::new(file_meta),
-            root: root.clone(),
-            db_path,
-        });
-
-        // STEP 2: Start background file watcher
-        start_server(state, port, root).await
-    }
-}
-
-async fn initial_index(
-    root: PathBuf,
-    db_path: PathBuf,
-    model_type: ModelType,
-) -> Result<(VectorStore, FileMetaStore)> {
-    // Clear existing database if any
-    if db_path.exists() {
-        std::fs::remove_dir_all(&db_path)?;
-    }
-
-    // File discovery
-    let walker = FileWalker::new(root.clone());
-    let (files, _stats) = walker.walk()?;
-    println!("  Found {} files", files.len());
-
-    if files.is_empty() {
-        let store = VectorStore::new(&db_path, model_type.dimensions())?;
-        let file_meta =
-            FileMetaStore::new(model_type.short_name().to_string(), model_type.dimensions());
-        return Ok((store, file_meta));
-    }
-
-    // Chunking
-    let mut chunker = SemanticChunker::new(100, 2000, 10);
-    let mut all_chunks = Vec::new();
-    let mut file_chunks: HashMap<String, Vec<crate::chunker::Chunk>> = HashMap::new();
-
-    for file in &files {
-        let source_code = match std::fs::read_to_string(&file.path) {
-            Ok(content) => content,
-            Err(_) => continue,
-        };
-        let chunks = chunker.chunk_semantic(file.language, &file.path, &source_code)?;
-        let path_str = file.path.to_string_lossy().to_string();
-        file_chunks.insert(path_str, chunks.clone());
-        all_chunks.extend(chunks);
-    }
-    println!("  Created {} chunks", all_chunks.len());
-
-    // Embedding
-    let cache_dir = crate::constants::get_global_models_cache_dir()?;
-    let mut embedding_service = EmbeddingService::with_cache_dir(model_type, Some(&cache_dir))?;
-    let embedded_chunks = embedding_service.embed_chunks(all_chunks)?;
-    println!("  Generated {} embeddings", embedded_chunks.len());
-
-    // Storage
-    let mut store = VectorStore::new(&db_path, model_type.dimensions())?;
-    let chunk_ids = store.insert_chunks_with_ids(embedded_chunks)?;
-    store.build_index()?;
-
-    // Build file metadata
-    let mut file_meta =
-        FileMetaStore::new(model_type.short_name().to_string(), model_type.dimensions());
-
-    let mut chunk_id_iter = chunk_ids.iter();
-    for file in &files {
-        let path_str = file.path.to_string_lossy().to_string();
-        if let Some(chunks) = file_chunks.get(&path_str) {
-            let ids: Vec<u32> = chunk_id_iter.by_ref().take(chunks.len()).copied().collect();
-            file_meta.update_file(&file.path, ids)?;
-        }
     }
-    file_meta.mark_full_index();
-    file_meta.save(&db_path)?;
-
-    println!("  ✅ Initial index complete");
 
-    Ok((store, file_meta))
+    Ok(Arc::new(ServerState {
+        stores: Arc::new(stores),
+        embedders,
+        model,
+        root,
+    }))
 }
 
-async fn start_server(state: Arc<ServerState>, port: u16, root: PathBuf) -> Result<()> {
-    // Start file watcher in background
-    let watcher_state = state.clone();
-    let watcher_root = root.clone();
-    tokio::spawn(async move {
-        if let Err(e) = run_file_watcher(watcher_state, watcher_root).await {
-            eprintln!("File watcher error: {}", e);
-        }
-    });
-
-    // Build HTTP router
-    let app = Router::new()
+/// Serve HTTP on `listener` until shutdown; routes other than the probes
+/// answer 503 until the index is loaded
+async fn run_http(
+    listener: tokio::net::TcpListener,
+    app: Arc<App>,
+    cancel_token: tokio_util::sync::CancellationToken,
+) -> Result<()> {
+    let router = Router::new()
+        .route("/healthz", get(health::healthz_handler))
+        .route("/readyz", get(health::readyz_handler))
         .route("/health", get(health_handler))
         .route("/status", get(status_handler))
         .route("/search", post(search_handler))
-        .with_state(state);
-
-    let addr = format!("127.0.0.1:{}", port);
-    println!("\n{}", "🌐 Server ready!".bright_green().bold());
-    println!("  Health: http://{}/health", addr);
-    println!("  Search: POST http://{}/search", addr);
-    println!("\n{}", "👀 Watching for file changes...".dimmed());
-
-    let listener = tokio::net::TcpListener::bind(&addr).await?;
-    axum::serve(listener, app).await?;
-
-    Ok(())
-}
-
-async fn run_file_watcher(state: Arc<ServerState>, root: PathBuf) -> Result<()> {
-    let mut watcher = FileWatcher::new(root);
-    watcher.start(300)?; // 300ms debounce
-
-    loop {
-        let events = watcher.wait_for_events(Duration::from_secs(1));
-
-        if events.is_empty() {
-            continue;
-        }
-
-        println!("\n📁 {} file change(s) detected", events.len());
-
-        // Enable quiet mode during FSW indexing to suppress verbose output
-        set_quiet(true);
-
-        for event in events {
-            match event {
-                FileEvent::Modified(path) => {
-                    if let Err(e) = handle_file_modified(&state, &path).await {
-                        eprintln!("  ❌ Error processing {}: {}", path.display(), e);
-                    }
-                }
-                FileEvent::Deleted(path) => {
-                    if let Err(e) = handle_file_deleted(&state, &path).await {
-                        eprintln!("  ❌ Error processing deletion {}: {}", path.display(), e);
-                    }
-                }
-                FileEvent::Renamed(from, to) => {
-                    // Treat as delete + create
-                    let _ = handle_file_deleted(&state, &from).await;
-                    let _ = handle_file_modified(&state, &to).await;
-                }
-            }
-        }
-
-        // Rebuild index after changes
-        let mut store = state.store.write().await;
-        if !store.is_indexed() {
-            store.build_index()?;
-        }
-
-        // Save metadata
-        let file_meta = state.file_meta.read().await;
-        file_meta.save(&state.db_path)?;
-
-        // Disable quiet mode after FSW indexing is complete
-        set_quiet(false);
-    }
-}
-
-async fn handle_file_modified(state: &ServerState, path: &PathBuf) -> Result<()> {
-    // Check if file needs re-indexing
-    let file_meta = state.file_meta.read().await;
-    let (needs_reindex, old_chunk_ids) = file_meta.check_file(path)?;
-    drop(file_meta);
-
-    if !needs_reindex {
-        return Ok(());
-    }
-
-    println!("  📝 Re-indexing: {}", path.display());
-
-    // Delete old chunks if any
-    if !old_chunk_ids.is_empty() {
-        let mut store = state.store.write().await;
-        store.delete_chunks(&old_chunk_ids)?;
-    }
-
-    // Read and chunk file
-    let source_code = std::fs::read_to_string(path)?;
-    let language = crate::file::Language::from_path(path);
-
-    let chunks = {
-        let mut chunker = state
-            .chunker
-            .lock()
-            .map_err(|e| anyhow::anyhow!("Chunker mutex poisoned: {}", e))?;
-        chunker.chunk_semantic(language, path, &source_code)?
-    };
-
-    if chunks.is_empty() {
-        // Update metadata with no chunks
-        let mut file_meta = state.file_meta.write().await;
-        file_meta.update_file(path, vec![])?;
-        return Ok(());
-    }
-
-    // Embed chunks
-    let embedded_chunks = {
-        let mut embedding_service = state
-            .embedding_service
-            .lock()
-            .map_err(|e| anyhow::anyhow!("Embedding service mutex poisoned: {}", e))?;
-        embedding_service.embed_chunks(chunks)?
-    };
-
-    // Insert into store
-    let chunk_ids = {
-        let mut store = state.store.write().await;
-        store.insert_chunks_with_ids(embedded_chunks)?
-    };
-
-    // Update metadata
-    let mut file_meta = state.file_meta.write().await;
-    file_meta.update_file(path, chunk_ids)?;
-
-    Ok(())
-}
-
-async fn handle_file_deleted(state: &ServerState, path: &Path) -> Result<()> {
-    let mut file_meta = state.file_meta.write().await;
-
-    if let Some(meta) = file_meta.remove_file(path) {
-        // Single file deletion
-        if !meta.chunk_ids.is_empty() {
-            println!(
-                "  🗑️  Removing: {} ({} chunks)",
-                path.display(),
-                meta.chunk_ids.len()
-            );
-            let mut store = state.store.write().await;
-            store.delete_chunks(&meta.chunk_ids)?;
-        }
-    } else {
-        // Path not found as a tracked file — might be a directory deletion.
-        // On Windows, rm -rf of a directory may only produce a Remove event
-        // for the directory itself, not for individual files within it.
-        let path_prefix = path.to_string_lossy().to_string();
-
-        // DEBUG: Log path prefix and first few tracked files
-        println!("  🐛 DEBUG: Deleted path prefix = {:?}", path_prefix);
-        let tracked_count = file_meta.tracked_files().count();
-        println!("  🐛 DEBUG: Total tracked files = {}", tracked_count);
-        let first_files: Vec<_> = file_meta.tracked_files().take(3).cloned().collect();
-        for (i, f) in first_files.iter().enumerate() {
-            println!("  🐛 DEBUG: Tracked file[{}] = {}", i, f);
-        }
-
-        let files_to_remove: Vec<String> = file_meta
-            .tracked_files()
-            .filter(|f| {
-                let starts = f.starts_with(&path_prefix);
-                if !starts && f.contains("test_fsw_project") {
-                    println!("  🐛 DEBUG: '{}' does NOT start with '{}'", f, path_prefix);
-                }
-                starts
-            })
-            .cloned()
-            .collect();
-
-        if !files_to_remove.is_empty() {
-            println!(
-                "  🗑️  Directory deleted: {} ({} files)",
-                path.display(),
-                files_to_remove.len()
-            );
-            let mut store = state.store.write().await;
-            for file_path in files_to_remove {
-                if let Some(meta) = file_meta.remove_file(Path::new(&file_path)) {
-                    if !meta.chunk_ids.is_empty() {
-                        println!(
-                            "    🗑️  {}: {} chunks removed",
-                            file_path,
-                            meta.chunk_ids.len()
-                        );
-                        store.delete_chunks(&meta.chunk_ids)?;
-                    }
-                }
-            }
-        }
-    }
-
+        .route("/search/stream", post(search_stream_handler))
+        .route("/references", post(references_handler))
+        .route("/databases", get(databases_handler))
+        .route("/metrics", get(metrics_handler))
+        .with_state(app.clone());
+
+    axum::serve(listener, router)
+        .with_graceful_shutdown(health::shutdown_signal(app, cancel_token))
+        .await?;
+    out_print!("{}", "👋 Server stopped".dimmed());
     Ok(())
 }
 
 // HTTP Handlers
 
-async fn health_handler(State(state): State<Arc<ServerState>>) -> Json<HealthResponse> {
-    let store = state.store.read().await;
+async fn health_handler(Loaded(state): Loaded) -> Json<HealthResponse> {
+    let store = state.stores.vector_store.read().await;
     let stats = store.stats().unwrap_or(crate::vectordb::StoreStats {
         total_chunks: 0,
         total_files: 0,
@@ -491,18 +345,16 @@ async fn health_handler(State(state): State<Arc<ServerState>>) -> Json<HealthRes
         max_chunk_id: 0,
     });
 
-    let file_meta = state.file_meta.read().await;
-
     Json(HealthResponse {
         status: "ready".to_string(),
         indexed_files: stats.total_files,
         indexed_chunks: stats.total_chunks,
-        model: file_meta.model_name.clone(),
+        model: state.model.short_name().to_string(),
     })
 }
 
-async fn status_handler(State(state): State<Arc<ServerState>>) -> Json<StatusResponse> {
-    let store = state.store.read().await;
+async fn status_handler(Loaded(state): Loaded) -> Json<StatusResponse> {
+    let store = state.stores.vector_store.read().await;
     let stats = store.stats().unwrap_or(crate::vectordb::StoreStats {
         total_chunks: 0,
         total_files: 0,
@@ -511,80 +363,339 @@ async fn status_handler(State(state): State<Arc<ServerState>>) -> Json<StatusRes
         max_chunk_id: 0,
     });
 
-    let file_meta = state.file_meta.read().await;
-
     Json(StatusResponse {
         files: stats.total_files,
         chunks: stats.total_chunks,
         indexed: stats.indexed,
-        model: file_meta.model_name.clone(),
-        dimensions: file_meta.dimensions,
+        model: state.model.short_name().to_string(),
+        dimensions: state.model.dimensions(),
+        readonly: state.stores.readonly,
+        watching: state.stores.activity.watcher_active(),
     })
 }
 
 async fn search_handler(
-    State(state): State<Arc<ServerState>>,
+    Loaded(state): Loaded,
     Json(req): Json<SearchRequest>,
 ) -> Result<Json<SearchResponse>, (StatusCode, String)> {
+    check_project(&state.root, req.project.as_deref())?;
     let start = std::time::Instant::now();
+    let query_embedding = embed_query(&state.embedders, &req.query).await?;
+    let results = search_store(
+        &state.stores.vector_store,
+        &state.root,
+        &query_embedding,
+        &req,
+    )
+    .await?;
 
-    // Embed query
-    let query_embedding = {
-        let mut embedding_service = state.embedding_service.lock().map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Mutex poisoned: {}", e),
-            )
-        })?;
-        embedding_service
-            .embed_query(&req.query)
-            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
-    };
+    Ok(Json(SearchResponse {
+        results,
+        query: req.query,
+        took_ms: start.elapsed().as_millis() as u64,
+    }))
+}
 
-    // Search
-    let store = state.store.read().await;
-    let results = store
-        .search(&query_embedding, req.limit)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+/// Streaming search: one JSON result per line (`application/x-ndjson`)
+async fn search_stream_handler(
+    Loaded(state): Loaded,
+    Json(req): Json<SearchRequest>,
+) -> Result<Response, (StatusCode, String)> {
+    check_project(&state.root, req.project.as_deref())?;
+    let query_embedding = embed_query(&state.embedders, &req.query).await?;
+    stream_store(
+        state.stores.vector_store.clone(),
+        state.root.clone(),
+        &query_embedding,
+        req,
+    )
+    .await
+}
+
+/// Symbol references: chunks whose text mentions `symbol`, best match first
+async fn references_handler(
+    Loaded(state): Loaded,
+    Json(req): Json<ReferencesRequest>,
+) -> Result<Json<ReferencesResponse>, (StatusCode, String)> {
+    check_project(&state.root, req.project.as_deref())?;
+    let start = std::time::Instant::now();
+    let references = find_store_references(&state.stores, &state.root, &req).await?;
+
+    Ok(Json(ReferencesResponse {
+        references,
+        symbol: req.symbol,
+        took_ms: start.elapsed().as_millis() as u64,
+    }))
+}
+
+/// Databases found from the server's working directory, as the MCP
+/// `find_databases` tool lists them
+async fn databases_handler() -> Result<Json<FindDatabasesResponse>, (StatusCode, String)> {
+    tokio::task::spawn_blocking(list_databases)
+        .await
+        .map(Json)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
 
-    // Convert to response format
-    let search_results: Vec<SearchResult> = results
+/// References to `req.symbol` in `stores`, with root-relative paths
+async fn find_store_references(
+    stores: &SharedStores,
+    root: &Path,
+    req: &ReferencesRequest,
+) -> Result<Vec<ReferenceResult>, (StatusCode, String)> {
+    let symbol = req.symbol.trim();
+    if symbol.is_empty() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            "Symbol must not be empty".to_string(),
+        ));
+    }
+    // Same lock order as the index manager's writers
+    let store = stores.vector_store.read().await;
+    let fts = stores.fts_store.read().await;
+    let references = find_references(symbol, &fts, &store, req.limit)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(references
         .into_iter()
-        .filter(|r| {
-            // Filter by path if specified
-            if let Some(ref path_filter) = req.path {
-                r.path.contains(path_filter)
-            } else {
-                true
-            }
+        .map(|reference| ReferenceResult {
+            path: relative_path(root, &reference.path).to_string(),
+            line: reference.line,
+            kind: reference.kind,
+            signature: reference.signature,
+            score: reference.score,
         })
-        .map(|r| {
-            // Make path relative to root
-            let rel_path = r
-                .path
-                .strip_prefix(state.root.to_str().unwrap_or(""))
-                .unwrap_or(&r.path)
-                .trim_start_matches('/')
-                .to_string();
-
-            SearchResult {
-                path: rel_path,
-                content: truncate_content(&r.content, 200),
-                start_line: r.start_line,
-                end_line: r.end_line,
-                kind: r.kind,
-                score: r.score,
+        .collect())
+}
+
+/// Rank the results of `req` in `store`, then read only the fields the response shows
+async fn search_store(
+    store: &RwLock<VectorStore>,
+    root: &Path,
+    query_embedding: &[f32],
+    req: &SearchRequest,
+) -> Result<Vec<SearchResult>, (StatusCode, String)> {
+    let store = store.read().await;
+    let limit = result_limit(&store, req);
+    let ranked = store
+        .search_ids(query_embedding, limit)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let mut results = Vec::with_capacity(ranked.len());
+    store
+        .visit_chunk_heads(&ranked, |head, distance| {
+            if matches_path(head.path, req) {
+                results.push(to_search_result(root, head, distance));
             }
         })
-        .collect();
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(results)
+}
 
-    let took_ms = start.elapsed().as_millis() as u64;
+/// Stream the results of `req` in `store`
+///
+/// Only chunk IDs are ranked up front. A background task loads the chunks in
+/// small batches and writes them to the chunked response, so large result
+/// sets (`"all": true`) never sit in memory, and a slow client only holds the
+/// store lock for one batch at a time.
+async fn stream_store(
+    store: Arc<RwLock<VectorStore>>,
+    root: PathBuf,
+    query_embedding: &[f32],
+    req: SearchRequest,
+) -> Result<Response, (StatusCode, String)> {
+    let ranked = {
+        let store = store.read().await;
+        let limit = result_limit(&store, &req);
+        store
+            .search_ids(query_embedding, limit)
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    };
 
-    Ok(Json(SearchResponse {
-        results: search_results,
-        query: req.query,
-        took_ms,
-    }))
+    let (reader, mut writer) = tokio::io::duplex(STREAM_BUFFER_BYTES);
+    tokio::spawn(async move {
+        for batch in ranked.chunks(STREAM_BATCH_CHUNKS) {
+            let mut lines = String::new();
+            {
+                // Chunks deleted by the watcher since ranking are skipped
+                let store = store.read().await;
+                let visited = store.visit_chunk_heads(batch, |head, distance| {
+                    if !matches_path(head.path, &req) {
+                        return;
+                    }
+                    let result = to_search_result(&root, head, distance);
+                    if let Ok(line) = serde_json::to_string(&result) {
+                        lines.push_str(&line);
+                        lines.push('\n');
+                    }
+                });
+                if let Err(e) = visited {
+                    tracing::warn!("Failed to read streamed results: {}", e);
+                    return;
+                }
+            }
+            // The client went away
+            if writer.write_all(lines.as_bytes()).await.is_err() {
+                return;
+            }
+        }
+    });
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/x-ndjson")
+        .body(Body::from_stream(ReaderStream::new(reader)))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Embed a query on whichever pooled instance is idle first
+async fn embed_query(
+    embedders: &EmbedderPool,
+    query: &str,
+) -> Result<Vec<f32>, (StatusCode, String)> {
+    let mut embedder = embedders
+        .acquire()
+        .await
+        .map_err(|e| (StatusCode::SERVICE_UNAVAILABLE, e.to_string()))?;
+    embedder
+        .embed_query(query)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Whether `selector` names the project at `root`: its directory name, or a
+/// path inside it
+fn is_project(root: &Path, selector: &str) -> bool {
+    let selector_path = Path::new(selector);
+    root.file_name().is_some_and(|name| name == selector)
+        || selector_path.starts_with(root)
+        || selector_path
+            .canonicalize()
+            .is_ok_and(|path| path.starts_with(root))
+}
+
+/// Reject a request for another project than the one served
+fn check_project(root: &Path, project: Option<&str>) -> Result<(), (StatusCode, String)> {
+    match project {
+        Some(project) if !is_project(root, project) => Err((
+            StatusCode::NOT_FOUND,
+            format!(
+                "This server only serves {}, not '{}'",
+                root.display(),
+                project
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Number of results a request asks for (`all` = every chunk in the index)
+fn result_limit(store: &VectorStore, req: &SearchRequest) -> usize {
+    if req.all {
+        store.stats().map(|s| s.total_chunks).unwrap_or(req.limit)
+    } else {
+        req.limit
+    }
+}
+
+/// Whether a result's path passes the request's path filter
+fn matches_path(path: &str, req: &SearchRequest) -> bool {
+    req.path
+        .as_ref()
+        .is_none_or(|path_filter| path.contains(path_filter))
+}
+
+/// Convert a chunk to the response format, with a root-relative path and a
+/// content preview
+fn to_search_result(root: &Path, head: ChunkHead<'_>, distance: f32) -> SearchResult {
+    SearchResult {
+        path: relative_path(root, head.path).to_string(),
+        content: truncate_content(head.content, 200),
+        start_line: head.start_line,
+        end_line: head.end_line,
+        kind: head.kind.to_string(),
+        score: 1.0 - distance,
+    }
+}
+
+/// `path` relative to the project root
+fn relative_path<'a>(root: &Path, path: &'a str) -> &'a str {
+    path.strip_prefix(root.to_str().unwrap_or(""))
+        .unwrap_or(path)
+        .trim_start_matches('/')
+}
+
+/// Prometheus text-format metrics for the embedder pool
+async fn metrics_handler(Loaded(state): Loaded) -> String {
+    render_pool_metrics(&state.embedders.stats())
+}
+
+fn render_pool_metrics(stats: &EmbedderPoolStats) -> String {
+    let mut out = String::new();
+    out.push_str("# HELP codesearch_embedder_pool_size Number of embedder instances in the pool\n");
+    out.push_str("# TYPE codesearch_embedder_pool_size gauge\n");
+    out.push_str(&format!("codesearch_embedder_pool_size {}\n", stats.size));
+    out.push_str("# HELP codesearch_embedder_pool_available Idle embedder instances\n");
+    out.push_str("# TYPE codesearch_embedder_pool_available gauge\n");
+    out.push_str(&format!(
+        "codesearch_embedder_pool_available {}\n",
+        stats.available
+    ));
+    out.push_str("# HELP codesearch_embedder_pool_waiting Requests waiting for an instance\n");
+    out.push_str("# TYPE codesearch_embedder_pool_waiting gauge\n");
+    out.push_str(&format!(
+        "codesearch_embedder_pool_waiting {}\n",
+        stats.waiting
+    ));
+    out.push_str(
+        "# HELP codesearch_embedder_pool_utilization Mean fraction of uptime spent embedding\n",
+    );
+    out.push_str("# TYPE codesearch_embedder_pool_utilization gauge\n");
+    out.push_str(&format!(
+        "codesearch_embedder_pool_utilization {:.6}\n",
+        stats.utilization()
+    ));
+
+    out.push_str(
+        "# HELP codesearch_embedder_pool_uptime_seconds Seconds since the pool was loaded\n",
+    );
+    out.push_str("# TYPE codesearch_embedder_pool_uptime_seconds gauge\n");
+    out.push_str(&format!(
+        "codesearch_embedder_pool_uptime_seconds {:.3}\n",
+        stats.uptime_secs
+    ));
+
+    out.push_str("# HELP codesearch_embedder_in_use Whether a slot is currently embedding\n");
+    out.push_str("# TYPE codesearch_embedder_in_use gauge\n");
+    for slot in &stats.slots {
+        out.push_str(&format!(
+            "codesearch_embedder_in_use{{slot=\"{}\"}} {}\n",
+            slot.id, slot.in_use as u8
+        ));
+    }
+    out.push_str("# HELP codesearch_embedder_requests_total Embedding requests served per slot\n");
+    out.push_str("# TYPE codesearch_embedder_requests_total counter\n");
+    for slot in &stats.slots {
+        out.push_str(&format!(
+            "codesearch_embedder_requests_total{{slot=\"{}\"}} {}\n",
+            slot.id, slot.requests
+        ));
+    }
+    out.push_str("# HELP codesearch_embedder_busy_seconds_total Time spent embedding per slot\n");
+    out.push_str("# TYPE codesearch_embedder_busy_seconds_total counter\n");
+    for slot in &stats.slots {
+        out.push_str(&format!(
+            "codesearch_embedder_busy_seconds_total{{slot=\"{}\"}} {:.6}\n",
+            slot.id, slot.busy_secs
+        ));
+    }
+    out.push_str(
+        "# HELP codesearch_embedder_utilization Fraction of uptime spent embedding per slot\n",
+    );
+    out.push_str("# TYPE codesearch_embedder_utilization gauge\n");
+    for slot in &stats.slots {
+        out.push_str(&format!(
+            "codesearch_embedder_utilization{{slot=\"{}\"}} {:.6}\n",
+            slot.id, slot.utilization
+        ));
+    }
+    out
 }
 
 fn truncate_content(content: &str, max_len: usize) -> String {
@@ -0,0 +1,255 @@
+//! HTTP search over several projects, for `codesearch daemon --port`
+//!
+//! The daemon keeps the stores of every project open and up to date; this
+//! API reads them. `GET /projects` lists the projects, and the search and
+//! references endpoints take the same body as `codesearch serve`, with
+//! `project` naming the project to search (its directory name or a path
+//! inside it; optional when there is only one). One embedder pool is loaded
+//! per model in use.
+
+use anyhow::{anyhow, Result};
+use axum::{
+    extract::{Json, State},
+    http::StatusCode,
+    response::Response,
+    routing::{get, post},
+    Router,
+};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio_util::sync::CancellationToken;
+
+use super::{
+    databases_handler, embed_query, find_store_references, is_project, search_store, stream_store,
+    ReferencesRequest, ReferencesResponse, SearchRequest, SearchResponse,
+};
+use crate::embed::{resolve_pool_size, EmbedderPool, ModelType};
+use crate::index::SharedStores;
+
+/// A project the daemon keeps up to date
+pub struct ServedProject {
+    pub root: PathBuf,
+    pub model: ModelType,
+    pub stores: Arc<SharedStores>,
+}
+
+struct ProjectsState {
+    projects: Vec<ServedProject>,
+    /// One pool per model used by the projects
+    embedders: Vec<(ModelType, EmbedderPool)>,
+}
+
+impl ProjectsState {
+    /// Project a request names in `selector`
+    fn project(&self, selector: Option<&str>) -> Result<&ServedProject, (StatusCode, String)> {
+        match (selector, self.projects.as_slice()) {
+            (None, [only]) => Ok(only),
+            (None, _) => Err((
+                StatusCode::BAD_REQUEST,
+                format!(
+                    "Pass \"project\" to pick one of: {}",
+                    self.names().join(", ")
+                ),
+            )),
+            (Some(selector), _) => self
+                .projects
+                .iter()
+                .find(|project| is_project(&project.root, selector))
+                .ok_or_else(|| {
+                    (
+                        StatusCode::NOT_FOUND,
+                        format!(
+                            "Unknown project '{}'; projects: {}",
+                            selector,
+                            self.names().join(", ")
+                        ),
+                    )
+                }),
+        }
+    }
+
+    fn names(&self) -> Vec<String> {
+        self.projects
+            .iter()
+            .map(|project| project_name(&project.root))
+            .collect()
+    }
+
+    fn embedders(&self, model: ModelType) -> Result<&EmbedderPool> {
+        self.embedders
+            .iter()
+            .find(|(pool_model, _)| *pool_model == model)
+            .map(|(_, pool)| pool)
+            .ok_or_else(|| anyhow!("No embedder pool loaded for model {}", model.short_name()))
+    }
+}
+
+fn project_name(root: &std::path::Path) -> String {
+    root.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| root.display().to_string())
+}
+
+/// Entry of `GET /projects`
+#[derive(Debug, Serialize)]
+struct ProjectInfo {
+    name: String,
+    root: String,
+    model: String,
+    files: usize,
+    chunks: usize,
+    indexed: bool,
+}
+
+/// Serve the search API over `projects` on `listener` until `cancel_token` is cancelled
+pub async fn serve_projects(
+    listener: tokio::net::TcpListener,
+    projects: Vec<ServedProject>,
+    cancel_token: CancellationToken,
+) -> Result<()> {
+    let cache_dir = crate::constants::get_global_models_cache_dir()?;
+    let pool_size = resolve_pool_size(None);
+    let mut embedders: Vec<(ModelType, EmbedderPool)> = Vec::new();
+    for project in &projects {
+        if !embedders.iter().any(|(model, _)| *model == project.model) {
+            let pool = EmbedderPool::load(project.model, Some(&cache_dir), pool_size)?;
+            embedders.push((project.model, pool));
+        }
+    }
+
+    let state = Arc::new(ProjectsState {
+        projects,
+        embedders,
+    });
+    let router = Router::new()
+        .route("/healthz", get(|| async { "ok" }))
+        .route("/projects", get(projects_handler))
+        .route("/search", post(search_handler))
+        .route("/search/stream", post(search_stream_handler))
+        .route("/references", post(references_handler))
+        .route("/databases", get(databases_handler))
+        .with_state(state);
+
+    tracing::info!(
+        "🌐 Search API for all projects on http://{}",
+        listener.local_addr()?
+    );
+    axum::serve(listener, router)
+        .with_graceful_shutdown(cancel_token.cancelled_owned())
+        .await?;
+    Ok(())
+}
+
+async fn projects_handler(State(state): State<Arc<ProjectsState>>) -> Json<Vec<ProjectInfo>> {
+    let mut infos = Vec::with_capacity(state.projects.len());
+    for project in &state.projects {
+        let stats = project.stores.vector_store.read().await.stats().ok();
+        infos.push(ProjectInfo {
+            name: project_name(&project.root),
+            root: project.root.display().to_string(),
+            model: project.model.short_name().to_string(),
+            files: stats.as_ref().map_or(0, |s| s.total_files),
+            chunks: stats.as_ref().map_or(0, |s| s.total_chunks),
+            indexed: stats.is_some_and(|s| s.indexed),
+        });
+    }
+    Json(infos)
+}
+
+async fn search_handler(
+    State(state): State<Arc<ProjectsState>>,
+    Json(req): Json<SearchRequest>,
+) -> Result<Json<SearchResponse>, (StatusCode, String)> {
+    let project = state.project(req.project.as_deref())?;
+    let start = std::time::Instant::now();
+    let embedders = state
+        .embedders(project.model)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let query_embedding = embed_query(embedders, &req.query).await?;
+    let results = search_store(
+        &project.stores.vector_store,
+        &project.root,
+        &query_embedding,
+        &req,
+    )
+    .await?;
+
+    Ok(Json(SearchResponse {
+        results,
+        query: req.query,
+        took_ms: start.elapsed().as_millis() as u64,
+    }))
+}
+
+async fn search_stream_handler(
+    State(state): State<Arc<ProjectsState>>,
+    Json(req): Json<SearchRequest>,
+) -> Result<Response, (StatusCode, String)> {
+    let project = state.project(req.project.as_deref())?;
+    let embedders = state
+        .embedders(project.model)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let query_embedding = embed_query(embedders, &req.query).await?;
+    stream_store(
+        project.stores.vector_store.clone(),
+        project.root.clone(),
+        &query_embedding,
+        req,
+    )
+    .await
+}
+
+async fn references_handler(
+    State(state): State<Arc<ProjectsState>>,
+    Json(req): Json<ReferencesRequest>,
+) -> Result<Json<ReferencesResponse>, (StatusCode, String)> {
+    let project = state.project(req.project.as_deref())?;
+    let start = std::time::Instant::now();
+    let references = find_store_references(&project.stores, &project.root, &req).await?;
+
+    Ok(Json(ReferencesResponse {
+        references,
+        symbol: req.symbol,
+        took_ms: start.elapsed().as_millis() as u64,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_selection() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = |name: &str| {
+            let root = dir.path().join(name);
+            let db_path = root.join(".codesearch.db");
+            std::fs::create_dir_all(root.join("src")).unwrap();
+            std::fs::create_dir_all(&db_path).unwrap();
+            ServedProject {
+                stores: Arc::new(SharedStores::new(&db_path, 4).unwrap()),
+                root,
+                model: ModelType::default(),
+            }
+        };
+        let state = ProjectsState {
+            projects: vec![project("api"), project("web")],
+            embedders: Vec::new(),
+        };
+
+        let web = state.project(Some("web")).unwrap();
+        assert!(web.root.ends_with("web"));
+        let inside = dir.path().join("api").join("src");
+        let api = state.project(Some(&inside.display().to_string())).unwrap();
+        assert!(api.root.ends_with("api"));
+
+        assert_eq!(
+            state.project(None).err().unwrap().0,
+            StatusCode::BAD_REQUEST
+        );
+        let (status, message) = state.project(Some("docs")).err().unwrap();
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(message.contains("api, web"));
+    }
+}
@@ -0,0 +1,81 @@
+//! Initialisms of declared symbol names (`HierarchicalStateMachine` → `hsm`)
+//!
+//! Developers search by abbreviations ("where does the HSM handle timeouts?")
+//! that neither the embedding nor BM25 connect to the full name. The store
+//! keeps an index from each initialism to the symbols it abbreviates, so query
+//! expansion can add the names.
+
+/// Fewest words (and so letters) a name needs to get an initialism;
+/// two-letter initialisms match too many unrelated names
+pub const MIN_ACRONYM_LEN: usize = 3;
+
+/// Symbol a chunk declares, from its innermost breadcrumb (`Struct: Foo`)
+///
+/// `context` is the stored breadcrumb trail, joined with `" > "`.
+pub fn declared_name(context: &str) -> Option<&str> {
+    let (_, name) = context.rsplit(" > ").next()?.split_once(": ")?;
+    Some(name.trim())
+}
+
+/// Lowercase initialism of a compound identifier
+///
+/// Words are split at `_` and case changes (`HTTPServerConfig` is HTTP,
+/// Server, Config → `hsc`). `None` for names that aren't identifiers or have
+/// fewer than [`MIN_ACRONYM_LEN`] words.
+pub fn initialism(name: &str) -> Option<String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return None;
+    }
+    let chars: Vec<char> = name.chars().collect();
+    let mut letters = String::new();
+    for (i, &c) in chars.iter().enumerate() {
+        if !c.is_ascii_alphabetic() {
+            continue;
+        }
+        let prev = i.checked_sub(1).map(|p| chars[p]);
+        let next = chars.get(i + 1).copied();
+        let starts_word = match prev {
+            None => true,
+            Some(p) if !p.is_ascii_alphanumeric() => true,
+            Some(p) if p.is_ascii_digit() => false,
+            // camelCase boundary, or the last capital of a run before a
+            // lowercase letter (HTTPServer → HTTP, Server)
+            Some(p) => {
+                c.is_ascii_uppercase()
+                    && (p.is_ascii_lowercase() || next.is_some_and(|n| n.is_ascii_lowercase()))
+            }
+        };
+        if starts_word {
+            letters.push(c.to_ascii_lowercase());
+        }
+    }
+    (letters.len() >= MIN_ACRONYM_LEN).then_some(letters)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_initialism() {
+        assert_eq!(
+            initialism("HierarchicalStateMachine").as_deref(),
+            Some("hsm")
+        );
+        assert_eq!(
+            initialism("hierarchical_state_machine").as_deref(),
+            Some("hsm")
+        );
+        assert_eq!(initialism("HTTPServerConfig").as_deref(), Some("hsc"));
+        assert_eq!(initialism("parseJsonValue").as_deref(), Some("pjv"));
+        assert_eq!(initialism("UserService"), None);
+        assert_eq!(initialism("/users/{id}"), None);
+
+        assert_eq!(
+            declared_name("File: src/hsm.rs > Impl: Machine > Method: run_state_machine"),
+            Some("run_state_machine")
+        );
+        assert_eq!(declared_name("File: src/hsm.rs"), Some("src/hsm.rs"));
+        assert_eq!(declared_name("Block"), None);
+    }
+}
@@ -0,0 +1,160 @@
+//! zstd compression of stored chunk metadata
+//!
+//! Chunk content is stored twice per chunk (as content and inside the
+//! searchable text) next to its context lines, which made chunk metadata the
+//! bulk of data.mdb. Values are compressed with a dictionary trained on code,
+//! so even a chunk of a few hundred bytes compresses well, and the repeated
+//! content within a value costs next to nothing.
+//!
+//! The dictionary (`code.dict`) was trained with `zstd --train --maxdict=32768`
+//! on about 14,000 chunk-sized excerpts of Rust, Python, JavaScript, C, PHP and
+//! other sources. Compressed values name it by ID, so replacing it makes them
+//! unreadable: a new dictionary needs a new ID and must be loaded next to this
+//! one.
+
+use heed::{BoxedError, BytesDecode, BytesEncode};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::io;
+use std::marker::PhantomData;
+use std::sync::OnceLock;
+use zstd::bulk::{Compressor, Decompressor};
+use zstd::dict::{DecoderDictionary, EncoderDictionary};
+
+/// Dictionary trained on source code
+static CODE_DICTIONARY: &[u8] = include_bytes!("code.dict");
+
+/// zstd level; higher levels gain little on values this small
+const COMPRESSION_LEVEL: i32 = 3;
+
+/// First bytes of every zstd frame
+///
+/// Uncompressed bincode of a value starting with a string or an integer below
+/// 4 GB never begins with them.
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xB5, 0x2F, 0xFD];
+
+/// Largest value decompressed; frames record their size, so this only caps
+/// corrupt ones
+const MAX_VALUE_BYTES: usize = 256 * 1024 * 1024;
+
+thread_local! {
+    static COMPRESSOR: RefCell<Option<Compressor<'static>>> = const { RefCell::new(None) };
+    static DECOMPRESSOR: RefCell<Option<Decompressor<'static>>> = const { RefCell::new(None) };
+}
+
+fn encoder_dictionary() -> &'static EncoderDictionary<'static> {
+    static DICTIONARY: OnceLock<EncoderDictionary<'static>> = OnceLock::new();
+    DICTIONARY.get_or_init(|| EncoderDictionary::new(CODE_DICTIONARY, COMPRESSION_LEVEL))
+}
+
+fn decoder_dictionary() -> &'static DecoderDictionary<'static> {
+    static DICTIONARY: OnceLock<DecoderDictionary<'static>> = OnceLock::new();
+    DICTIONARY.get_or_init(|| DecoderDictionary::new(CODE_DICTIONARY))
+}
+
+fn compress(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    COMPRESSOR.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        let compressor = match cell.as_mut() {
+            Some(compressor) => compressor,
+            None => cell.insert(Compressor::with_prepared_dictionary(encoder_dictionary())?),
+        };
+        compressor.compress(bytes)
+    })
+}
+
+fn decompress(bytes: &[u8]) -> io::Result<Vec<u8>> {
+    DECOMPRESSOR.with(|cell| {
+        let mut cell = cell.borrow_mut();
+        let decompressor = match cell.as_mut() {
+            Some(decompressor) => decompressor,
+            None => cell.insert(Decompressor::with_prepared_dictionary(decoder_dictionary())?),
+        };
+        decompressor.decompress(bytes, MAX_VALUE_BYTES)
+    })
+}
+
+/// The bincode of a stored value: borrowed when it was stored uncompressed
+pub(super) fn bincode_bytes(bytes: &[u8]) -> io::Result<Cow<'_, [u8]>> {
+    if bytes.starts_with(&ZSTD_MAGIC) {
+        Ok(Cow::Owned(decompress(bytes)?))
+    } else {
+        Ok(Cow::Borrowed(bytes))
+    }
+}
+
+/// `SerdeBincode` with the bincode compressed by zstd
+///
+/// Values that don't shrink are stored as plain bincode, and plain bincode
+/// written before compression was introduced still decodes, so databases need
+/// no migration: values are compressed as they are rewritten.
+pub struct CompressedBincode<T>(PhantomData<T>);
+
+impl<'a, T: Serialize + 'a> BytesEncode<'a> for CompressedBincode<T> {
+    type EItem = T;
+
+    fn bytes_encode(item: &'a T) -> Result<Cow<'a, [u8]>, BoxedError> {
+        let bytes = bincode::serialize(item)?;
+        let compressed = compress(&bytes)?;
+        Ok(Cow::Owned(if compressed.len() < bytes.len() {
+            compressed
+        } else {
+            bytes
+        }))
+    }
+}
+
+impl<'a, T: DeserializeOwned + 'a> BytesDecode<'a> for CompressedBincode<T> {
+    type DItem = T;
+
+    fn bytes_decode(bytes: &'a [u8]) -> Result<T, BoxedError> {
+        Ok(bincode::deserialize(&bincode_bytes(bytes)?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, serde::Deserialize)]
+    struct Value {
+        content: String,
+        searchable_text: String,
+    }
+
+    #[test]
+    fn test_compressed_bincode() {
+        let content = "pub fn handle_request(req: &Request) -> Result<Response> {\n    \
+                       let user = authenticate(req)?;\n    \
+                       Ok(Response::new(user.name.clone()))\n}\n";
+        let value = Value {
+            content: content.to_string(),
+            searchable_text: format!("fn handle_request {}", content),
+        };
+
+        let encoded = CompressedBincode::<Value>::bytes_encode(&value).unwrap();
+        let plain = bincode::serialize(&value).unwrap();
+        assert!(encoded.starts_with(&ZSTD_MAGIC));
+        assert!(encoded.len() * 2 < plain.len());
+        assert_eq!(
+            CompressedBincode::<Value>::bytes_decode(&encoded).unwrap(),
+            value
+        );
+
+        // Values written before compression still decode
+        assert_eq!(
+            CompressedBincode::<Value>::bytes_decode(&plain).unwrap(),
+            value
+        );
+
+        // Values too small to shrink are kept as they are
+        let tiny = Value {
+            content: String::new(),
+            searchable_text: "x".to_string(),
+        };
+        let encoded = CompressedBincode::<Value>::bytes_encode(&tiny).unwrap();
+        assert_eq!(encoded.as_ref(), bincode::serialize(&tiny).unwrap());
+    }
+}
@@ -0,0 +1,202 @@
+//! Coarse clusters of the code vectors, for fast search on huge indexes
+//!
+//! Above `CODESEARCH_FAST_SEARCH_MIN_CHUNKS` chunks, the index build groups
+//! the code vectors into about √n clusters with spherical k-means (an IVF,
+//! inverted file, index). A search scores the query against the cluster
+//! centroids only, and the arroy search is limited to the members of the
+//! `CODESEARCH_FAST_SEARCH_PROBES` nearest clusters. Neighbours in clusters
+//! that weren't probed are missed, so recall drops slightly, but a query on a
+//! million chunks no longer walks trees spanning all of them.
+//!
+//! Centroids are trained once and kept while the index grows or shrinks by
+//! less than half; chunks added since are assigned to their nearest centroid.
+
+use heed::{BoxedError, BytesDecode, BytesEncode};
+use rand::Rng;
+use rayon::prelude::*;
+use roaring::RoaringBitmap;
+use std::borrow::Cow;
+
+/// Chunk count from which clusters are built, unless configured
+const DEFAULT_FAST_SEARCH_MIN_CHUNKS: u64 = 500_000;
+
+/// Clusters searched per query, unless configured
+const DEFAULT_FAST_SEARCH_PROBES: usize = 16;
+
+/// Most clusters built, however large the index
+const MAX_CLUSTERS: usize = 4096;
+
+/// Vectors sampled per cluster to train the centroids
+const TRAINING_VECTORS_PER_CLUSTER: usize = 40;
+
+/// k-means rounds; centroids barely move after a few
+const TRAINING_ITERATIONS: usize = 10;
+
+/// Whether an index of `chunks` is searched by cluster: from
+/// `CODESEARCH_FAST_SEARCH_MIN_CHUNKS` chunks on (`0` = never)
+pub(super) fn fast_search_enabled(chunks: u64) -> bool {
+    let min = std::env::var("CODESEARCH_FAST_SEARCH_MIN_CHUNKS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_FAST_SEARCH_MIN_CHUNKS);
+    min > 0 && chunks >= min
+}
+
+/// Clusters searched per query, from `CODESEARCH_FAST_SEARCH_PROBES`
+pub(super) fn fast_search_probes() -> usize {
+    std::env::var("CODESEARCH_FAST_SEARCH_PROBES")
+        .ok()
+        .and_then(|v| v.trim().parse::<usize>().ok())
+        .unwrap_or(DEFAULT_FAST_SEARCH_PROBES)
+        .max(1)
+}
+
+/// Clusters for an index of `items` vectors: about √n
+pub(super) fn cluster_count(items: u64) -> usize {
+    ((items as f64).sqrt().round() as usize).clamp(1, MAX_CLUSTERS)
+}
+
+/// Whether centroids trained as `trained` clusters still suit `wanted`
+pub(super) fn centroids_fit(trained: usize, wanted: usize) -> bool {
+    trained * 2 >= wanted && trained <= wanted * 2
+}
+
+/// Vectors sampled to train `clusters` centroids
+pub(super) fn training_sample_size(clusters: usize) -> usize {
+    clusters * TRAINING_VECTORS_PER_CLUSTER
+}
+
+/// Train `k` unit-length centroids on `sample` with spherical k-means
+///
+/// Vectors are compared by cosine similarity, like the arroy index. A
+/// cluster left without vectors keeps its previous centroid.
+pub(super) fn train(sample: &[Vec<f32>], k: usize, rng: &mut impl Rng) -> Vec<Vec<f32>> {
+    let sample: Vec<Vec<f32>> = sample.iter().map(|v| normalized(v)).collect();
+    let k = k.min(sample.len());
+    let mut centroids: Vec<Vec<f32>> = rand::seq::index::sample(rng, sample.len(), k)
+        .into_iter()
+        .map(|i| sample[i].clone())
+        .collect();
+    let Some(dimensions) = centroids.first().map(Vec::len) else {
+        return centroids;
+    };
+
+    for _ in 0..TRAINING_ITERATIONS {
+        let assignments: Vec<usize> = sample
+            .par_iter()
+            .map(|vector| nearest(&centroids, vector))
+            .collect();
+        let mut sums = vec![vec![0.0f32; dimensions]; centroids.len()];
+        for (vector, &cluster) in sample.iter().zip(&assignments) {
+            for (sum, x) in sums[cluster].iter_mut().zip(vector) {
+                *sum += x;
+            }
+        }
+        for (centroid, sum) in centroids.iter_mut().zip(sums) {
+            if sum.iter().any(|&x| x != 0.0) {
+                *centroid = normalized(&sum);
+            }
+        }
+    }
+    centroids
+}
+
+/// Index of the centroid most similar to `vector`
+pub(super) fn nearest(centroids: &[Vec<f32>], vector: &[f32]) -> usize {
+    centroids
+        .iter()
+        .map(|centroid| dot(centroid, vector))
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .map_or(0, |(i, _)| i)
+}
+
+/// Indices of the `n` centroids most similar to `query`, best first
+pub(super) fn nearest_clusters(centroids: &[Vec<f32>], query: &[f32], n: usize) -> Vec<usize> {
+    let mut scored: Vec<(usize, f32)> = centroids
+        .iter()
+        .map(|centroid| dot(centroid, query))
+        .enumerate()
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scored.into_iter().take(n).map(|(i, _)| i).collect()
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+fn normalized(vector: &[f32]) -> Vec<f32> {
+    let norm = dot(vector, vector).sqrt();
+    if norm == 0.0 {
+        return vector.to_vec();
+    }
+    vector.iter().map(|x| x / norm).collect()
+}
+
+/// Stores the chunk IDs of a cluster as a serialized `RoaringBitmap`
+pub(super) struct RoaringBitmapCodec;
+
+impl BytesEncode<'_> for RoaringBitmapCodec {
+    type EItem = RoaringBitmap;
+
+    fn bytes_encode(item: &RoaringBitmap) -> Result<Cow<'_, [u8]>, BoxedError> {
+        let mut bytes = Vec::with_capacity(item.serialized_size());
+        item.serialize_into(&mut bytes)?;
+        Ok(Cow::Owned(bytes))
+    }
+}
+
+impl BytesDecode<'_> for RoaringBitmapCodec {
+    type DItem = RoaringBitmap;
+
+    fn bytes_decode(bytes: &[u8]) -> Result<RoaringBitmap, BoxedError> {
+        Ok(RoaringBitmap::deserialize_from(bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_clusters() {
+        // Three well separated groups around the axes
+        let mut rng = StdRng::seed_from_u64(7);
+        let axes = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+        let sample: Vec<Vec<f32>> = (0..300)
+            .map(|i| {
+                axes[i % 3]
+                    .iter()
+                    .map(|x| x * 10.0 + rng.gen_range(-1.0..1.0))
+                    .collect()
+            })
+            .collect();
+
+        let centroids = train(&sample, 3, &mut rng);
+        assert_eq!(centroids.len(), 3);
+        let clusters: Vec<usize> = axes.iter().map(|axis| nearest(&centroids, axis)).collect();
+        assert!(clusters[0] != clusters[1] && clusters[1] != clusters[2]);
+        assert!(clusters[0] != clusters[2]);
+        for (vector, i) in sample.iter().zip(0..) {
+            assert_eq!(nearest(&centroids, vector), clusters[i % 3]);
+        }
+
+        let query = [0.9, 0.1, 0.0];
+        assert_eq!(
+            nearest_clusters(&centroids, &query, 2),
+            [clusters[0], clusters[1]]
+        );
+
+        assert_eq!(cluster_count(1_000_000), 1000);
+        assert_eq!(cluster_count(100_000_000), MAX_CLUSTERS);
+        assert!(centroids_fit(1000, 1400));
+        assert!(!centroids_fit(1000, 2100));
+
+        let members = RoaringBitmap::from_iter([1, 5, 70_000]);
+        let bytes = RoaringBitmapCodec::bytes_encode(&members).unwrap();
+        assert_eq!(RoaringBitmapCodec::bytes_decode(&bytes).unwrap(), members);
+    }
+}
@@ -1,3 +1,8 @@
+mod acronyms;
+mod compression;
+mod ivf;
 mod store;
 
-pub use store::{SearchResult, StoreStats, VectorStore};
+pub use store::{
+    BuildProgress, ChunkHead, ChunkMetadata, SearchResult, StoreStats, StoredVectors, VectorStore,
+};
@@ -1,6 +1,10 @@
+use super::acronyms::{declared_name, initialism};
+use super::compression::{bincode_bytes, CompressedBincode};
+use super::ivf::{self, RoaringBitmapCodec};
+use crate::chunker::{Chunk, ChunkKind};
 use crate::constants::MAX_LMDB_MAP_SIZE_MB;
 use crate::embed::EmbeddedChunk;
-use crate::info_print;
+use crate::{err_print, info_print};
 use anyhow::{anyhow, Result};
 use arroy::distances::Cosine;
 use arroy::{Database as ArroyDatabase, ItemId, Reader, Writer};
@@ -9,12 +13,120 @@ use heed::types::*;
 use heed::{Database, EnvFlags, EnvOpenOptions};
 use rand::rngs::StdRng;
 use rand::SeedableRng;
+use roaring::RoaringBitmap;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::num::NonZeroUsize;
 use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tracing::warn;
 
+/// Arroy index holding the primary (code model) vectors
+const CODE_VECTOR_INDEX: u16 = 0;
+
+/// Arroy index holding the optional prose model vectors
+///
+/// Dual-embedded databases store a second vector per chunk, produced by a
+/// natural-language model, under the same chunk ID in this index.
+const PROSE_VECTOR_INDEX: u16 = 1;
+
+/// Arroy index holding one vector per file (mean of its chunk vectors)
+///
+/// Each file vector is stored under the smallest chunk ID of the file; the
+/// `file_chunks` database maps that ID to all chunk IDs of the file.
+const FILE_VECTOR_INDEX: u16 = 2;
+
+/// Arroy index holding vectors from the model an index is migrating to
+///
+/// They are staged under the chunk IDs, a batch at a time, and replace the
+/// code vectors once every chunk has one.
+const MIGRATION_VECTOR_INDEX: u16 = 3;
+
+type FileChunksDb = Database<U32<BigEndian>, SerdeBincode<Vec<u32>>>;
+
+/// File path -> IDs of the chunks stored for that file
+type PathChunksDb = Database<Str, SerdeBincode<Vec<u32>>>;
+
+/// Initialism -> (symbol name, chunk ID) of the chunks declaring a symbol it abbreviates
+type AcronymsDb = Database<Str, SerdeBincode<Vec<(String, u32)>>>;
+
+/// Chunk ID -> embedding version (model and schema) of its staged migration vector
+type ChunkModelsDb = Database<U32<BigEndian>, Str>;
+
+/// Key of the text embedded for a chunk (see [`moved_vector_key`]) -> vectors
+/// of a deleted chunk
+type MovedVectorsDb = Database<Str, SerdeBincode<StoredVectors>>;
+
+/// Vectors of deleted chunks kept at most; when full, the kept ones are
+/// dropped to make room for newly deleted ones
+const MAX_MOVED_VECTORS: u64 = 10_000;
+
+/// SHA-256 of the text embedded for `chunk`
+///
+/// The embedded text includes the end of the file path and the breadcrumb
+/// context, so vectors are only reused for a chunk that embeds exactly like
+/// the deleted one.
+fn moved_vector_key(chunk: &Chunk) -> String {
+    use sha2::{Digest, Sha256};
+    format!(
+        "{:x}",
+        Sha256::digest(crate::embed::embedding_text(chunk).as_bytes())
+    )
+}
+
+/// Cluster number -> centroid of the code vectors in that cluster
+type ClusterCentroidsDb = Database<U32<BigEndian>, SerdeBincode<Vec<f32>>>;
+
+/// Cluster number -> IDs of the chunks whose code vector is in that cluster
+type ClusterMembersDb = Database<U32<BigEndian>, RoaringBitmapCodec>;
+
+/// Map size estimates include this much room (in percent of the data) for
+/// pages written while a tree rebuild still holds the old ones
+const LMDB_HEADROOM_PERCENT: u64 = 150;
+
+/// Granularity of pre-sized maps
+const LMDB_MAP_STEP_MB: usize = 256;
+
+/// How often a running vector index build reports progress
+const BUILD_PROGRESS_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Progress of a vector index build (see [`VectorStore::build_index_with`])
+#[derive(Debug, Clone, Copy)]
+pub struct BuildProgress {
+    /// Arroy index being built: "code", "prose" or "files"
+    pub index: &'static str,
+    /// Position of `index` among the indexes of this build (1-based)
+    pub step: usize,
+    pub steps: usize,
+    /// Time since the build started
+    pub elapsed: Duration,
+    /// Whether `index` is finished
+    pub done: bool,
+}
+
+impl BuildProgress {
+    /// One-line description, e.g. "building code trees (1/2), 12s"
+    pub fn describe(&self) -> String {
+        format!(
+            "{} {} trees ({}/{}), {}s",
+            if self.done { "built" } else { "building" },
+            self.index,
+            self.step,
+            self.steps,
+            self.elapsed.as_secs()
+        )
+    }
+}
+
+/// Dimensions of the built prose index, if any
+fn read_prose_dimensions(rtxn: &heed::RoTxn, vectors: ArroyDatabase<Cosine>) -> Option<usize> {
+    Reader::open(rtxn, PROSE_VECTOR_INDEX, vectors)
+        .ok()
+        .map(|reader| reader.dimensions())
+}
+
 /// Chunk metadata stored in the database
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChunkMetadata {
@@ -34,11 +146,66 @@ pub struct ChunkMetadata {
     #[serde(default)]
     pub context_next: Option<String>,
     /// Searchable text combining signature, name, and content for better searchability
+    ///
+    /// Only the tags are left once [`VectorStore::strip_chunks`] shrank it.
     #[serde(default)]
     pub searchable_text: String,
 }
 
 impl ChunkMetadata {
+    /// The chunk as it was embedded, to embed it again with another model
+    ///
+    /// Tags aren't stored on their own; they are recovered from the
+    /// searchable text (see [`tags`](Self::tags)).
+    pub fn to_chunk(&self) -> Chunk {
+        // The kind isn't part of the embedded text
+        let mut chunk = Chunk::new(
+            self.content.clone(),
+            self.start_line,
+            self.end_line,
+            ChunkKind::Other,
+            self.path.clone(),
+        );
+        chunk.context = self
+            .context
+            .as_deref()
+            .map(|context| context.split(" > ").map(str::to_string).collect())
+            .unwrap_or_default();
+        chunk.signature = self.signature.clone();
+        chunk.docstring = self.docstring.clone();
+        chunk.tags = self.tags();
+        chunk
+    }
+
+    /// Tags of the chunk
+    ///
+    /// The searchable text lists them between the docstring and the kind, or
+    /// holds nothing else once stripped.
+    fn tags(&self) -> Vec<String> {
+        let mut rest = self.searchable_text.as_str();
+        for prefix in [&self.signature, &self.docstring].into_iter().flatten() {
+            rest = rest
+                .strip_prefix(prefix.as_str())
+                .and_then(|rest| rest.strip_prefix('\n'))
+                .unwrap_or(rest);
+        }
+        rest.strip_suffix(&format!("{}\n{}", self.kind, self.content))
+            .unwrap_or(&self.searchable_text)
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    /// Shrink the searchable text to the tags, which nothing else stores
+    ///
+    /// Returns whether it changed.
+    fn strip_searchable_text(&mut self) -> bool {
+        let tags = self.tags().join("\n");
+        let changed = self.searchable_text != tags;
+        self.searchable_text = tags;
+        changed
+    }
+
     fn from_embedded_chunk(chunk: &EmbeddedChunk) -> Self {
         // Build searchable text from signature, docstring, and content
         let searchable_text = {
@@ -54,6 +221,9 @@ impl ChunkMetadata {
                 parts.push(doc.clone());
             }
 
+            // Add tags (e.g., HTTP routes like "POST /users")
+            parts.extend(chunk.chunk.tags.iter().cloned());
+
             // Add kind (e.g., "Function", "Struct", "Impl")
             parts.push(format!("{:?}", chunk.chunk.kind));
 
@@ -94,10 +264,39 @@ impl ChunkMetadata {
 pub struct VectorStore {
     env: heed::Env,
     vectors: ArroyDatabase<Cosine>,
-    chunks: Database<U32<BigEndian>, SerdeBincode<ChunkMetadata>>,
+    chunks: Database<U32<BigEndian>, CompressedBincode<ChunkMetadata>>,
     next_id: u32,
     dimensions: usize,
+    /// Dimensions of the secondary prose vectors (None when not dual-embedded)
+    prose_dimensions: Option<usize>,
+    /// File vector representative ID -> chunk IDs of that file
+    file_chunks: Option<FileChunksDb>,
+    /// File path -> chunk IDs, so a file's chunks can be deleted without
+    /// knowing their IDs (None in read-only databases created before it)
+    path_chunks: Option<PathChunksDb>,
+    /// Initialisms of declared symbol names (None in read-only databases
+    /// created before it)
+    acronyms: Option<AcronymsDb>,
+    /// Code vector clusters for fast search (None in read-only databases
+    /// created before them)
+    cluster_centroids: Option<ClusterCentroidsDb>,
+    cluster_members: Option<ClusterMembersDb>,
+    /// Decoded cluster centroids, read on the first clustered search instead
+    /// of on every one; dropped whenever the clusters may have changed
+    centroids: Mutex<Option<Arc<Vec<Vec<f32>>>>>,
+    /// Vectors of deleted chunks by content hash, reused when the same code
+    /// is indexed again elsewhere (None in read-only databases created before it)
+    moved_vectors: Option<MovedVectorsDb>,
+    /// Which chunks have a staged migration vector, and of which model (None
+    /// in read-only databases created before it)
+    chunk_models: Option<ChunkModelsDb>,
+    /// Whether file-level vectors are maintained for every file.
+    /// Only databases created with file vectors get them, so an older index
+    /// never ends up with vectors for just the files changed since.
+    file_index: bool,
     indexed: bool,
+    /// Last committed transaction ID seen when the cached fields were read
+    generation: usize,
     pub map_size_mb: usize,
 }
 
@@ -132,8 +331,50 @@ impl VectorStore {
         let mut wtxn = env.write_txn()?;
 
         let vectors: ArroyDatabase<Cosine> = env.create_database(&mut wtxn, Some("vectors"))?;
-        let chunks: Database<U32<BigEndian>, SerdeBincode<ChunkMetadata>> =
+        let chunks: Database<U32<BigEndian>, CompressedBincode<ChunkMetadata>> =
             env.create_database(&mut wtxn, Some("chunks"))?;
+        let file_chunks: FileChunksDb = env.create_database(&mut wtxn, Some("file_chunks"))?;
+        let path_chunks: PathChunksDb = env.create_database(&mut wtxn, Some("path_chunks"))?;
+        // Databases created before the path index: fill it from the chunks
+        if path_chunks.is_empty(&wtxn)? && !chunks.is_empty(&wtxn)? {
+            let mut by_path: std::collections::HashMap<String, Vec<u32>> =
+                std::collections::HashMap::new();
+            for result in chunks.iter(&wtxn)? {
+                let (id, metadata) = result?;
+                by_path.entry(metadata.path).or_default().push(id);
+            }
+            for (path, ids) in &by_path {
+                path_chunks.put(&mut wtxn, path, ids)?;
+            }
+        }
+        // Likewise the acronym index, the first time it is created
+        let acronyms: AcronymsDb = match env.open_database(&wtxn, Some("acronyms"))? {
+            Some(acronyms) => acronyms,
+            None => {
+                let acronyms: AcronymsDb = env.create_database(&mut wtxn, Some("acronyms"))?;
+                let mut by_acronym: std::collections::HashMap<String, Vec<(String, u32)>> =
+                    std::collections::HashMap::new();
+                for result in chunks.iter(&wtxn)? {
+                    let (id, metadata) = result?;
+                    if let Some((acronym, name)) =
+                        metadata.context.as_deref().and_then(chunk_acronym)
+                    {
+                        by_acronym.entry(acronym).or_default().push((name, id));
+                    }
+                }
+                for (acronym, symbols) in &by_acronym {
+                    acronyms.put(&mut wtxn, acronym, symbols)?;
+                }
+                acronyms
+            }
+        };
+        let cluster_centroids: ClusterCentroidsDb =
+            env.create_database(&mut wtxn, Some("cluster_centroids"))?;
+        let cluster_members: ClusterMembersDb =
+            env.create_database(&mut wtxn, Some("cluster_members"))?;
+        let moved_vectors: MovedVectorsDb =
+            env.create_database(&mut wtxn, Some("moved_vectors"))?;
+        let chunk_models: ChunkModelsDb = env.create_database(&mut wtxn, Some("chunk_models"))?;
 
         // Get the next ID from the maximum existing key + 1
         // Using len() is wrong after delete+insert cycles: deleted IDs create gaps
@@ -142,13 +383,14 @@ impl VectorStore {
             Some((max_key, _)) => max_key + 1,
             None => 0,
         };
+        let file_index = next_id == 0 || !file_chunks.is_empty(&wtxn)?;
 
         wtxn.commit()?;
 
         // Check if database is already indexed by trying to open a reader
         let indexed = if next_id > 0 {
             let rtxn = env.read_txn()?;
-            match Reader::open(&rtxn, 0, vectors) {
+            match Reader::open(&rtxn, CODE_VECTOR_INDEX, vectors) {
                 Ok(_) => {
                     tracing::debug!("Index detected: Reader::open succeeded");
                     true
@@ -162,7 +404,13 @@ impl VectorStore {
             false
         };
 
+        let prose_dimensions = {
+            let rtxn = env.read_txn()?;
+            read_prose_dimensions(&rtxn, vectors)
+        };
+
         info_print!("✅ Database opened (next_id: {})", next_id);
+        let generation = env.info().last_txn_id;
 
         Ok(Self {
             env,
@@ -170,7 +418,18 @@ impl VectorStore {
             chunks,
             next_id,
             dimensions,
+            prose_dimensions,
+            file_chunks: Some(file_chunks),
+            path_chunks: Some(path_chunks),
+            acronyms: Some(acronyms),
+            cluster_centroids: Some(cluster_centroids),
+            cluster_members: Some(cluster_members),
+            centroids: Mutex::default(),
+            moved_vectors: Some(moved_vectors),
+            chunk_models: Some(chunk_models),
+            file_index,
             indexed,
+            generation,
             map_size_mb,
         })
     }
@@ -212,9 +471,20 @@ impl VectorStore {
         let vectors: ArroyDatabase<Cosine> = env
             .open_database(&rtxn, Some("vectors"))?
             .ok_or_else(|| anyhow::anyhow!("vectors database not found"))?;
-        let chunks: Database<U32<BigEndian>, SerdeBincode<ChunkMetadata>> = env
+        let chunks: Database<U32<BigEndian>, CompressedBincode<ChunkMetadata>> = env
             .open_database(&rtxn, Some("chunks"))?
             .ok_or_else(|| anyhow::anyhow!("chunks database not found"))?;
+        // Absent in databases created before file-level vectors
+        let file_chunks: Option<FileChunksDb> = env.open_database(&rtxn, Some("file_chunks"))?;
+        let path_chunks: Option<PathChunksDb> = env.open_database(&rtxn, Some("path_chunks"))?;
+        let acronyms: Option<AcronymsDb> = env.open_database(&rtxn, Some("acronyms"))?;
+        let cluster_centroids: Option<ClusterCentroidsDb> =
+            env.open_database(&rtxn, Some("cluster_centroids"))?;
+        let cluster_members: Option<ClusterMembersDb> =
+            env.open_database(&rtxn, Some("cluster_members"))?;
+        let moved_vectors: Option<MovedVectorsDb> =
+            env.open_database(&rtxn, Some("moved_vectors"))?;
+        let chunk_models: Option<ChunkModelsDb> = env.open_database(&rtxn, Some("chunk_models"))?;
 
         // Get the next ID from the maximum existing key + 1
         // Using len() is wrong after delete+insert cycles: deleted IDs create gaps
@@ -222,15 +492,22 @@ impl VectorStore {
             Some((max_key, _)) => max_key + 1,
             None => 0,
         };
+        let file_index = match file_chunks {
+            Some(db) => !db.is_empty(&rtxn)?,
+            None => false,
+        };
 
         // Check if database is already indexed
         let indexed = if next_id > 0 {
-            Reader::open(&rtxn, 0, vectors).is_ok()
+            Reader::open(&rtxn, CODE_VECTOR_INDEX, vectors).is_ok()
         } else {
             false
         };
+        let prose_dimensions = read_prose_dimensions(&rtxn, vectors);
 
-        drop(rtxn);
+        // Handles opened in an aborted transaction are closed with it
+        rtxn.commit()?;
+        let generation = env.info().last_txn_id;
 
         tracing::debug!(
             "✅ Database opened read-only (next_id: {}, indexed: {})",
@@ -244,11 +521,120 @@ impl VectorStore {
             chunks,
             next_id,
             dimensions,
+            prose_dimensions,
+            file_chunks,
+            path_chunks,
+            acronyms,
+            cluster_centroids,
+            cluster_members,
+            centroids: Mutex::default(),
+            moved_vectors,
+            chunk_models,
+            file_index,
             indexed,
+            generation,
             map_size_mb,
         })
     }
 
+    /// Whether another process committed since the last snapshot refresh
+    pub fn has_new_commits(&self) -> bool {
+        self.env.info().last_txn_id != self.generation
+    }
+
+    /// Pick up what another process committed since the store was opened
+    ///
+    /// Every search starts a fresh read transaction, but whether the index is
+    /// built, the next ID, the prose/file indexes and the cluster centroids are
+    /// cached. They are re-read when the environment's last transaction ID
+    /// changed. A writer that grew the map makes new transactions fail with
+    /// `MDB_MAP_RESIZED` until the new size is adopted here. Meant for
+    /// read-only stores; returns whether anything was committed since.
+    pub fn refresh_snapshot(&mut self) -> Result<bool> {
+        let generation = self.env.info().last_txn_id;
+        if generation == self.generation {
+            return Ok(false);
+        }
+
+        let rtxn = match self.env.read_txn() {
+            Err(heed::Error::Mdb(heed::MdbError::MapResized)) => {
+                // Safety: as in `resize_environment`, no transaction of this
+                // store is active. A size of 0 adopts the writer's map size.
+                unsafe { self.env.resize(0)? };
+                self.map_size_mb = self.env.info().map_size / (1024 * 1024);
+                tracing::debug!("LMDB map grown by the writer, now {}MB", self.map_size_mb);
+                self.env.read_txn()?
+            }
+            result => result?,
+        };
+
+        if self.file_chunks.is_none() {
+            self.file_chunks = self.env.open_database(&rtxn, Some("file_chunks"))?;
+        }
+        if self.path_chunks.is_none() {
+            self.path_chunks = self.env.open_database(&rtxn, Some("path_chunks"))?;
+        }
+        if self.acronyms.is_none() {
+            self.acronyms = self.env.open_database(&rtxn, Some("acronyms"))?;
+        }
+        if self.cluster_centroids.is_none() {
+            self.cluster_centroids = self.env.open_database(&rtxn, Some("cluster_centroids"))?;
+            self.cluster_members = self.env.open_database(&rtxn, Some("cluster_members"))?;
+        }
+        if self.moved_vectors.is_none() {
+            self.moved_vectors = self.env.open_database(&rtxn, Some("moved_vectors"))?;
+        }
+        if self.chunk_models.is_none() {
+            self.chunk_models = self.env.open_database(&rtxn, Some("chunk_models"))?;
+        }
+        self.next_id = match self.chunks.last(&rtxn)? {
+            Some((max_key, _)) => max_key + 1,
+            None => 0,
+        };
+        self.file_index = match self.file_chunks {
+            Some(db) => !db.is_empty(&rtxn)?,
+            None => false,
+        };
+        self.indexed =
+            self.next_id > 0 && Reader::open(&rtxn, CODE_VECTOR_INDEX, self.vectors).is_ok();
+        self.prose_dimensions = read_prose_dimensions(&rtxn, self.vectors);
+        self.forget_centroids()?;
+        // Keeps the handles opened above, as in `open_readonly`
+        rtxn.commit()?;
+
+        self.generation = generation;
+        tracing::debug!(
+            "🔄 Vector store snapshot refreshed (next_id: {}, indexed: {})",
+            self.next_id,
+            self.indexed
+        );
+        Ok(true)
+    }
+
+    /// Write a compacted copy of the LMDB environment at `db_path` to `dest`
+    ///
+    /// The copy runs inside a single read transaction, so it is consistent even
+    /// while another process is writing. `dest` must not exist yet. Opens with
+    /// the same options as [`VectorStore::new`] so an environment this process
+    /// already holds is reused rather than rejected.
+    pub fn copy_environment(db_path: &Path, dest: &Path) -> Result<()> {
+        if !db_path.join("data.mdb").exists() {
+            return Err(anyhow!("No vector database at: {}", db_path.display()));
+        }
+        let map_size_mb = std::env::var("CODESEARCH_LMDB_MAP_SIZE_MB")
+            .ok()
+            .and_then(|s| s.parse::<usize>().ok())
+            .unwrap_or(crate::constants::DEFAULT_LMDB_MAP_SIZE_MB);
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(map_size_mb * 1024 * 1024)
+                .max_dbs(10)
+                .open(db_path)?
+        };
+        env.copy_to_file(dest, heed::CompactionOption::Enabled)?;
+        Ok(())
+    }
+
     /// Check if an error is an MDB_MAP_FULL error
     /// MDB_MAP_FULL error code is -28
     fn is_map_full_error(&self, error: &dyn std::error::Error) -> bool {
@@ -257,7 +643,6 @@ impl VectorStore {
     }
 
     /// Resize the LMDB environment to a new map size
-    /// This requires closing and reopening the environment
     fn resize_environment(&mut self, new_size_mb: usize) -> Result<()> {
         if new_size_mb > MAX_LMDB_MAP_SIZE_MB {
             return Err(anyhow::anyhow!(
@@ -267,66 +652,47 @@ impl VectorStore {
             ));
         }
 
-        tracing::warn!("🔧 Resizing LMDB environment to {}MB", new_size_mb);
-
-        // Store the current database path
-        let db_path = self.env.path().to_path_buf();
-
-        // Note: We don't need to explicitly drop the env/databases
-        // They will be dropped when we replace them below
-
-        // Open a new environment with the new map size
-        // Note: This is a simple implementation that just increases the map size
-        // In production, you might want to handle this more gracefully
-        let env = unsafe {
-            EnvOpenOptions::new()
-                .map_size(new_size_mb * 1024 * 1024)
-                .max_dbs(10)
-                .open(&db_path)?
-        };
-
-        // Reopen databases
-        let mut wtxn = env.write_txn()?;
-        let vectors: ArroyDatabase<Cosine> = env.create_database(&mut wtxn, Some("vectors"))?;
-        let chunks: Database<U32<BigEndian>, SerdeBincode<ChunkMetadata>> =
-            env.create_database(&mut wtxn, Some("chunks"))?;
-
-        // Get the next ID
-        let next_id = match chunks.last(&wtxn)? {
-            Some((max_key, _)) => max_key + 1,
-            None => 0,
-        };
-
-        wtxn.commit()?;
-
-        // Check if database is already indexed
-        let indexed = if next_id > 0 {
-            let rtxn = env.read_txn()?;
-            Reader::open(&rtxn, 0, vectors).is_ok()
-        } else {
-            false
-        };
-
-        // Replace the old environment with the new one
-        self.env = env;
-        self.vectors = vectors;
-        self.chunks = chunks;
-        self.next_id = next_id;
-        self.indexed = indexed;
-
-        // Update the map size tracking
+        // Safety: LMDB allows resizing an open environment while no transaction
+        // is active. Transactions never outlive a method of this store, and
+        // `&mut self` rules out one running concurrently. (Reopening the
+        // environment instead fails: heed returns the already open one.)
+        unsafe { self.env.resize(new_size_mb * 1024 * 1024)? };
         self.map_size_mb = new_size_mb;
 
-        tracing::info!(
-            "✅ LMDB environment resized to {}MB (next_id: {}, indexed: {})",
-            new_size_mb,
-            next_id,
-            indexed
-        );
-
+        tracing::info!("✅ LMDB environment resized to {}MB", new_size_mb);
         Ok(())
     }
 
+    /// Map size in MB needed to add `added` bytes (as projected by
+    /// `IndexEstimate::projected_db_bytes`) to what the database already holds
+    pub fn required_map_size_mb(&self, added: u64) -> Result<usize> {
+        let used = self.env.non_free_pages_size()?;
+        // Rebuilding the trees writes new pages before the old ones are freed
+        let needed = (used + added) * LMDB_HEADROOM_PERCENT / 100;
+        let step = (LMDB_MAP_STEP_MB * 1024 * 1024) as u64;
+        Ok((needed.div_ceil(step) * step / (1024 * 1024)) as usize)
+    }
+
+    /// Grow the map ahead of a bulk insert instead of retrying on MDB_MAP_FULL
+    ///
+    /// Returns the new map size in MB when it was grown (at most
+    /// `MAX_LMDB_MAP_SIZE_MB`); a map that is already large enough is left alone.
+    pub fn reserve(&mut self, added: u64) -> Result<Option<usize>> {
+        let needed = self.required_map_size_mb(added)?;
+        if needed > MAX_LMDB_MAP_SIZE_MB {
+            warn!(
+                "Estimated database size {}MB exceeds the {}MB limit; indexing may fail with MDB_MAP_FULL",
+                needed, MAX_LMDB_MAP_SIZE_MB
+            );
+        }
+        let target = needed.min(MAX_LMDB_MAP_SIZE_MB);
+        if target <= self.map_size_mb {
+            return Ok(None);
+        }
+        self.resize_environment(target)?;
+        Ok(Some(target))
+    }
+
     /// Insert embedded chunks into the database
     ///
     /// Returns the number of chunks inserted
@@ -336,10 +702,10 @@ impl VectorStore {
             return Ok(0);
         }
 
-        eprintln!("📊 Inserting {} chunks...", chunks.len());
+        err_print!("📊 Inserting {} chunks...", chunks.len());
 
         let mut wtxn = self.env.write_txn()?;
-        let writer = Writer::new(self.vectors, 0, self.dimensions);
+        let writer = Writer::new(self.vectors, CODE_VECTOR_INDEX, self.dimensions);
 
         for chunk in &chunks {
             let id = self.next_id;
@@ -363,12 +729,16 @@ impl VectorStore {
             self.next_id += 1;
         }
 
+        let ids: Vec<u32> = (self.next_id - chunks.len() as u32..self.next_id).collect();
+        self.put_path_chunks(&mut wtxn, &chunks, &ids)?;
+        self.put_acronyms(&mut wtxn, &chunks, &ids)?;
+
         wtxn.commit()?;
 
         // Mark as not indexed (need to rebuild index after inserts)
         self.indexed = false;
 
-        eprintln!(
+        err_print!(
             "✅ Inserted {} chunks (IDs: {}-{})",
             chunks.len(),
             self.next_id - chunks.len() as u32,
@@ -384,13 +754,26 @@ impl VectorStore {
     /// This is the heaviest LMDB write operation (arroy tree build),
     /// so it includes retry logic for MDB_MAP_FULL errors.
     pub fn build_index(&mut self) -> Result<()> {
+        self.build_index_with(&|| false, &|_| {})
+    }
+
+    /// Build the vector index, reporting progress and stopping when `cancel` returns true
+    ///
+    /// `progress` is called when each arroy index starts and finishes, and
+    /// every few seconds while its trees are built (from the build threads).
+    /// A cancelled build returns an error and leaves the previous index in place.
+    pub fn build_index_with(
+        &mut self,
+        cancel: &(dyn Fn() -> bool + Sync),
+        progress: &(dyn Fn(BuildProgress) + Sync),
+    ) -> Result<()> {
         let mut attempts = 0;
         let max_attempts = 3;
 
         loop {
             attempts += 1;
 
-            let result = self.build_index_impl();
+            let result = self.build_index_impl(cancel, progress);
 
             match &result {
                 Ok(_) => return result,
@@ -419,16 +802,217 @@ impl VectorStore {
     }
 
     /// Implementation of build_index without retry logic
-    fn build_index_impl(&mut self) -> Result<()> {
+    fn build_index_impl(
+        &mut self,
+        cancel: &(dyn Fn() -> bool + Sync),
+        progress: &(dyn Fn(BuildProgress) + Sync),
+    ) -> Result<()> {
+        let mut indexes = vec![("code", CODE_VECTOR_INDEX, self.dimensions)];
+        if let Some(prose_dimensions) = self.prose_dimensions {
+            indexes.push(("prose", PROSE_VECTOR_INDEX, prose_dimensions));
+        }
+        if self.file_index {
+            indexes.push(("files", FILE_VECTOR_INDEX, self.dimensions));
+        }
+
+        let started = Instant::now();
         let mut wtxn = self.env.write_txn()?;
-        let writer = Writer::new(self.vectors, 0, self.dimensions);
         let mut rng = StdRng::seed_from_u64(rand::random());
-        writer.builder(&mut rng).build(&mut wtxn)?;
+        for (i, &(index, index_id, dimensions)) in indexes.iter().enumerate() {
+            let report = |done: bool| {
+                progress(BuildProgress {
+                    index,
+                    step: i + 1,
+                    steps: indexes.len(),
+                    elapsed: started.elapsed(),
+                    done,
+                })
+            };
+            report(false);
+
+            // arroy polls the cancel closure throughout the tree build, which
+            // also makes it the place to report that the build is alive
+            let interval = BUILD_PROGRESS_INTERVAL.as_millis() as u64;
+            let next_report = AtomicU64::new(started.elapsed().as_millis() as u64 + interval);
+            let writer = Writer::new(self.vectors, index_id, dimensions);
+            writer
+                .builder(&mut rng)
+                .cancel(|| {
+                    let now = started.elapsed().as_millis() as u64;
+                    let due = next_report.load(Ordering::Relaxed);
+                    if now >= due
+                        && next_report
+                            .compare_exchange(
+                                due,
+                                now + interval,
+                                Ordering::Relaxed,
+                                Ordering::Relaxed,
+                            )
+                            .is_ok()
+                    {
+                        report(false);
+                    }
+                    cancel()
+                })
+                .build(&mut wtxn)
+                .map_err(|e| match e {
+                    arroy::Error::BuildCancelled => anyhow!("Vector index build cancelled"),
+                    e => e.into(),
+                })?;
+            report(true);
+        }
+        let chunks = Reader::open(&wtxn, CODE_VECTOR_INDEX, self.vectors)?.n_items();
+        self.update_clusters(&mut wtxn, &mut rng, ivf::fast_search_enabled(chunks))?;
         wtxn.commit()?;
         self.indexed = true;
         Ok(())
     }
+
+    /// Bring the code vector clusters of fast search up to date with a build
+    ///
+    /// Centroids are trained when the index reaches the fast search size, and
+    /// again when it grew or shrank too much for them; otherwise only chunks
+    /// added or deleted since are moved in or out of their clusters. Without
+    /// `enabled`, below the fast search size, the clusters are dropped.
+    fn update_clusters(
+        &self,
+        wtxn: &mut heed::RwTxn,
+        rng: &mut StdRng,
+        enabled: bool,
+    ) -> Result<()> {
+        let (Some(centroids_db), Some(members_db)) = (self.cluster_centroids, self.cluster_members)
+        else {
+            return Ok(());
+        };
+        self.forget_centroids()?;
+        let reader = Reader::open(wtxn, CODE_VECTOR_INDEX, self.vectors)?;
+        let items = reader.item_ids().clone();
+        if !enabled {
+            drop(reader);
+            centroids_db.clear(wtxn)?;
+            members_db.clear(wtxn)?;
+            return Ok(());
+        }
+
+        let wanted = ivf::cluster_count(items.len());
+        let mut centroids = Vec::new();
+        for result in centroids_db.iter(wtxn)? {
+            centroids.push(result?.1);
+        }
+        let mut members: Vec<RoaringBitmap>;
+        let added = if ivf::centroids_fit(centroids.len(), wanted) {
+            members = Vec::with_capacity(centroids.len());
+            for cluster in 0..centroids.len() as u32 {
+                members.push(members_db.get(wtxn, &cluster)?.unwrap_or_default());
+            }
+            let clustered = members.iter().fold(RoaringBitmap::new(), |all, m| all | m);
+            for cluster in &mut members {
+                *cluster &= &items;
+            }
+            items - clustered
+        } else {
+            let started = Instant::now();
+            let sample_size = ivf::training_sample_size(wanted).min(items.len() as usize);
+            let mut sample = Vec::with_capacity(sample_size);
+            for position in rand::seq::index::sample(rng, items.len() as usize, sample_size) {
+                if let Some(id) = items.select(position as u32) {
+                    sample.extend(reader.item_vector(wtxn, id)?);
+                }
+            }
+            centroids = ivf::train(&sample, wanted, rng);
+            tracing::info!(
+                "Trained {} fast search clusters on {} vectors in {:.1}s",
+                centroids.len(),
+                sample.len(),
+                started.elapsed().as_secs_f64()
+            );
+            members = vec![RoaringBitmap::new(); centroids.len()];
+            items
+        };
+
+        // Assigned in batches: all vectors of a huge index don't fit in memory
+        const ASSIGN_BATCH: usize = 10_000;
+        let added: Vec<ItemId> = added.into_iter().collect();
+        for batch in added.chunks(ASSIGN_BATCH) {
+            let mut vectors = Vec::with_capacity(batch.len());
+            for &id in batch {
+                if let Some(vector) = reader.item_vector(wtxn, id)? {
+                    vectors.push((id, vector));
+                }
+            }
+            let assigned: Vec<(ItemId, usize)> = {
+                use rayon::prelude::*;
+                vectors
+                    .par_iter()
+                    .map(|(id, vector)| (*id, ivf::nearest(&centroids, vector)))
+                    .collect()
+            };
+            for (id, cluster) in assigned {
+                members[cluster].insert(id);
+            }
+        }
+        drop(reader);
+
+        centroids_db.clear(wtxn)?;
+        members_db.clear(wtxn)?;
+        for (cluster, (centroid, members)) in centroids.iter().zip(&members).enumerate() {
+            centroids_db.put(wtxn, &(cluster as u32), centroid)?;
+            members_db.put(wtxn, &(cluster as u32), members)?;
+        }
+        Ok(())
+    }
+
     pub fn search(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<SearchResult>> {
+        self.search_index(CODE_VECTOR_INDEX, self.dimensions, query_embedding, limit)
+    }
+
+    /// Search the prose embedding space of a dual-embedded database
+    ///
+    /// The query must be embedded with the prose model recorded in metadata.json.
+    pub fn search_prose(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<SearchResult>> {
+        let dimensions = self
+            .prose_dimensions
+            .ok_or_else(|| anyhow!("Database has no prose embeddings"))?;
+        self.search_index(PROSE_VECTOR_INDEX, dimensions, query_embedding, limit)
+    }
+
+    /// Rank chunk IDs by distance to the query, without loading their metadata
+    ///
+    /// Used to stream very large result sets: the caller fetches each chunk with
+    /// [`get_chunk_as_result`](Self::get_chunk_as_result) as it goes.
+    pub fn search_ids(&self, query_embedding: &[f32], limit: usize) -> Result<Vec<(ItemId, f32)>> {
+        let rtxn = self.env.read_txn()?;
+        self.rank_ids(
+            &rtxn,
+            CODE_VECTOR_INDEX,
+            self.dimensions,
+            query_embedding,
+            limit,
+        )
+    }
+
+    fn search_index(
+        &self,
+        index: u16,
+        dimensions: usize,
+        query_embedding: &[f32],
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let rtxn = self.env.read_txn()?;
+        let results = self.rank_ids(&rtxn, index, dimensions, query_embedding, limit)?;
+        self.with_metadata(&rtxn, results)
+    }
+
+    /// Rank the chunks of the given files exactly, bypassing the ANN index
+    ///
+    /// For searches scoped to a few files, such as uncommitted changes, which a
+    /// global top-k could miss entirely. `paths` are normalized chunk paths.
+    pub fn search_in_files(
+        &self,
+        query_embedding: &[f32],
+        paths: &[String],
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
         if query_embedding.len() != self.dimensions {
             return Err(anyhow!(
                 "Query embedding dimension mismatch: expected {}, got {}",
@@ -436,33 +1020,75 @@ impl VectorStore {
                 query_embedding.len()
             ));
         }
+        let Some(path_chunks) = self.path_chunks else {
+            return Ok(Vec::new());
+        };
+
+        let rtxn = self.env.read_txn()?;
+        let reader = Reader::open(&rtxn, CODE_VECTOR_INDEX, self.vectors)?;
+        let mut scored = Vec::new();
+        for path in paths {
+            for id in path_chunks.get(&rtxn, path)?.unwrap_or_default() {
+                if let Some(vector) = reader.item_vector(&rtxn, id)? {
+                    scored.push((id, cosine_distance(&vector, query_embedding)));
+                }
+            }
+        }
+        scored.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(limit);
+        self.with_metadata(&rtxn, scored)
+    }
 
+    /// Nearest chunks to a stored chunk, by its code vector (no re-embedding)
+    ///
+    /// The chunk itself is left out. Returns `None` when there is no such chunk.
+    pub fn related_chunks(&self, chunk_id: u32, limit: usize) -> Result<Option<Vec<SearchResult>>> {
         if !self.indexed {
             return Err(anyhow!(
                 "Index not built. Call build_index() after inserting chunks."
             ));
         }
-
         let rtxn = self.env.read_txn()?;
-        let reader = Reader::open(&rtxn, 0, self.vectors)?;
-
-        // Perform ANN search with quality boost
-        let mut query = reader.nns(limit);
-
-        // Improve search quality by exploring more candidates
-        if let Some(n_trees) = NonZeroUsize::new(reader.n_trees()) {
-            if let Some(search_k) = NonZeroUsize::new(limit * n_trees.get() * 15) {
-                query.search_k(search_k);
-            }
-        }
+        let reader = Reader::open(&rtxn, CODE_VECTOR_INDEX, self.vectors)?;
+        let Some(vector) = reader.item_vector(&rtxn, chunk_id)? else {
+            return Ok(None);
+        };
+        let ranked = self
+            .rank_ids(
+                &rtxn,
+                CODE_VECTOR_INDEX,
+                self.dimensions,
+                &vector,
+                limit + 1,
+            )?
+            .into_iter()
+            .filter(|(id, _)| *id != chunk_id)
+            .take(limit)
+            .collect();
+        self.with_metadata(&rtxn, ranked).map(Some)
+    }
 
-        let results = query.by_vector(&rtxn, query_embedding)?;
+    /// Vector distance between the query and a stored chunk, `None` if it has no vector
+    ///
+    /// Gives results found only by full-text search an absolute similarity.
+    pub fn distance_to(&self, query_embedding: &[f32], chunk_id: u32) -> Result<Option<f32>> {
+        let rtxn = self.env.read_txn()?;
+        let reader = Reader::open(&rtxn, CODE_VECTOR_INDEX, self.vectors)?;
+        Ok(reader
+            .item_vector(&rtxn, chunk_id)?
+            .map(|vector| cosine_distance(&vector, query_embedding)))
+    }
 
-        // Fetch metadata for each result
+    /// Attach chunk metadata to ranked `(id, distance)` pairs
+    fn with_metadata(
+        &self,
+        rtxn: &heed::RoTxn,
+        results: Vec<(ItemId, f32)>,
+    ) -> Result<Vec<SearchResult>> {
         let mut search_results = Vec::new();
 
         for (id, distance) in results {
-            if let Some(metadata) = self.chunks.get(&rtxn, &id)? {
+            if let Some(metadata) = self.chunks.get(rtxn, &id)? {
                 search_results.push(SearchResult {
                     id,
                     content: metadata.content,
@@ -478,6 +1104,7 @@ impl VectorStore {
                     score: 1.0 - distance, // Convert distance to similarity score
                     context_prev: metadata.context_prev,
                     context_next: metadata.context_next,
+                    text_match: false,
                 });
             }
         }
@@ -485,6 +1112,83 @@ impl VectorStore {
         Ok(search_results)
     }
 
+    fn rank_ids(
+        &self,
+        rtxn: &heed::RoTxn,
+        index: u16,
+        dimensions: usize,
+        query_embedding: &[f32],
+        limit: usize,
+    ) -> Result<Vec<(ItemId, f32)>> {
+        if query_embedding.len() != dimensions {
+            return Err(anyhow!(
+                "Query embedding dimension mismatch: expected {}, got {}",
+                dimensions,
+                query_embedding.len()
+            ));
+        }
+
+        if !self.indexed {
+            return Err(anyhow!(
+                "Index not built. Call build_index() after inserting chunks."
+            ));
+        }
+
+        let reader = Reader::open(rtxn, index, self.vectors)?;
+
+        // Large code indexes: pick the best files first, then their chunks
+        let mut results = Vec::new();
+        if index == CODE_VECTOR_INDEX && self.use_file_retrieval(reader.n_items()) {
+            let top_files = crate::constants::FILE_RETRIEVAL_TOP_FILES;
+            if let Some(by_files) = self.search_by_files(rtxn, query_embedding, top_files, limit)? {
+                results = by_files;
+            }
+        }
+
+        // Plain ANN search, also filling up when the top files had too few chunks.
+        // Huge code indexes search the nearest clusters first, and everything
+        // only if those hold too few chunks.
+        let clustered = match index {
+            CODE_VECTOR_INDEX
+                if results.len() < limit && ivf::fast_search_enabled(reader.n_items()) =>
+            {
+                self.cluster_candidates(rtxn, query_embedding, ivf::fast_search_probes())?
+            }
+            _ => None,
+        };
+        for candidates in [clustered.as_ref(), None] {
+            if results.len() >= limit {
+                break;
+            }
+            // Perform ANN search with quality boost
+            let mut query = reader.nns(limit);
+
+            // Improve search quality by exploring more candidates
+            if let Some(n_trees) = NonZeroUsize::new(reader.n_trees()) {
+                if let Some(search_k) = NonZeroUsize::new(limit * n_trees.get() * 15) {
+                    query.search_k(search_k);
+                }
+            }
+            if let Some(candidates) = candidates {
+                query.candidates(candidates);
+            }
+
+            // Set lookup keeps `--all` sized limits linear
+            let mut seen: std::collections::HashSet<ItemId> =
+                results.iter().map(|(id, _)| *id).collect();
+            for (id, distance) in query.by_vector(rtxn, query_embedding)? {
+                if results.len() >= limit {
+                    break;
+                }
+                if seen.insert(id) {
+                    results.push((id, distance));
+                }
+            }
+        }
+
+        Ok(results)
+    }
+
     /// Returns real LMDB page-level stats for accurate bloat detection.
     ///
     /// Uses `env.non_free_pages_size()` (bytes in use) vs `env.real_disk_size()`
@@ -495,6 +1199,7 @@ impl VectorStore {
         Ok(LmdbPageStats {
             used_bytes,
             disk_size,
+            map_size: self.env.info().map_size as u64,
         })
     }
 
@@ -587,18 +1292,99 @@ impl VectorStore {
         }
 
         let mut wtxn = self.env.write_txn()?;
-        let writer = Writer::new(self.vectors, 0, self.dimensions);
+        let writer = Writer::new(self.vectors, CODE_VECTOR_INDEX, self.dimensions);
+        let prose_writer = self
+            .prose_dimensions
+            .map(|dims| Writer::new(self.vectors, PROSE_VECTOR_INDEX, dims));
+        let staging = self.chunk_models.map(|chunk_models| {
+            (
+                chunk_models,
+                Writer::new(self.vectors, MIGRATION_VECTOR_INDEX, self.dimensions),
+            )
+        });
 
         let mut deleted = 0;
+        let mut by_path: std::collections::HashMap<String, Vec<u32>> =
+            std::collections::HashMap::new();
+        let mut by_acronym: std::collections::HashMap<String, Vec<u32>> =
+            std::collections::HashMap::new();
+        let mut moved = Vec::new();
         for &id in chunk_ids {
+            if self.path_chunks.is_some() || self.acronyms.is_some() || self.moved_vectors.is_some()
+            {
+                if let Some(metadata) = self.chunks.get(&wtxn, &id)? {
+                    if let Some((acronym, _)) = metadata.context.as_deref().and_then(chunk_acronym)
+                    {
+                        by_acronym.entry(acronym).or_default().push(id);
+                    }
+                    if let Some(code) = writer.item_vector(&wtxn, id)? {
+                        let prose = match &prose_writer {
+                            Some(prose_writer) => prose_writer.item_vector(&wtxn, id)?,
+                            None => None,
+                        };
+                        moved.push((
+                            moved_vector_key(&metadata.to_chunk()),
+                            StoredVectors { code, prose },
+                        ));
+                    }
+                    by_path.entry(metadata.path).or_default().push(id);
+                }
+            }
             // Delete from vector database
             if writer.del_item(&mut wtxn, id).is_ok() {
                 deleted += 1;
             }
+            if let Some(prose_writer) = &prose_writer {
+                prose_writer.del_item(&mut wtxn, id)?;
+            }
+            if let Some((chunk_models, staging)) = &staging {
+                if chunk_models.delete(&mut wtxn, &id)? {
+                    staging.del_item(&mut wtxn, id)?;
+                }
+            }
+            // Files are deleted as a whole, so dropping the representative
+            // chunk drops the file vector
+            if let Some(file_chunks) = self.file_chunks.filter(|_| self.file_index) {
+                if file_chunks.delete(&mut wtxn, &id)? {
+                    Writer::new(self.vectors, FILE_VECTOR_INDEX, self.dimensions)
+                        .del_item(&mut wtxn, id)?;
+                }
+            }
             // Delete from metadata
             self.chunks.delete(&mut wtxn, &id)?;
         }
 
+        if let Some(path_chunks) = self.path_chunks {
+            for (path, removed) in by_path {
+                let mut ids = path_chunks.get(&wtxn, &path)?.unwrap_or_default();
+                ids.retain(|id| !removed.contains(id));
+                if ids.is_empty() {
+                    path_chunks.delete(&mut wtxn, &path)?;
+                } else {
+                    path_chunks.put(&mut wtxn, &path, &ids)?;
+                }
+            }
+        }
+        if let Some(moved_vectors) = self.moved_vectors {
+            if moved_vectors.len(&wtxn)? + moved.len() as u64 > MAX_MOVED_VECTORS {
+                moved_vectors.clear(&mut wtxn)?;
+            }
+            for (key, vectors) in moved.into_iter().take(MAX_MOVED_VECTORS as usize) {
+                moved_vectors.put(&mut wtxn, &key, &vectors)?;
+            }
+        }
+        if let Some(acronyms) = self.acronyms {
+            for (acronym, removed) in by_acronym {
+                let mut symbols = acronyms.get(&wtxn, &acronym)?.unwrap_or_default();
+                symbols.retain(|(_, id)| !removed.contains(id));
+                if symbols.is_empty() {
+                    acronyms.delete(&mut wtxn, &acronym)?;
+                } else {
+                    acronyms.put(&mut wtxn, &acronym, &symbols)?;
+                }
+            }
+        }
+
         wtxn.commit()?;
 
         // Mark as needing re-index
@@ -609,25 +1395,124 @@ impl VectorStore {
         Ok(deleted)
     }
 
-    /// Delete all chunks from a specific file
-    ///
-    /// Returns the IDs of deleted chunks
-    /// Insert chunks and return their assigned IDs
+    /// Shrink the stored metadata of every chunk, to save space
     ///
-    /// Useful for tracking which chunks belong to which file
-    pub fn insert_chunks_with_ids(&mut self, chunks: Vec<EmbeddedChunk>) -> Result<Vec<u32>> {
-        // Auto-resize retry logic for MDB_MAP_FULL errors
-        let mut attempts = 0;
-        let max_attempts = 3;
+    /// Drops each chunk's surrounding context lines and all of its searchable
+    /// text but the tags, and replaces the content of the chunks `drop_content`
+    /// selects with their signature. Embeddings are kept, so the chunks are still found.
+    /// Returns the IDs of the chunks whose content was dropped.
+    pub fn strip_chunks(
+        &mut self,
+        drop_content: impl Fn(&ChunkMetadata) -> bool,
+    ) -> Result<Vec<u32>> {
+        /// Chunks rewritten per write transaction
+        const STRIP_BATCH: usize = 1000;
 
-        loop {
-            attempts += 1;
-
-            let result = self.insert_chunks_with_ids_impl(&chunks);
-
-            match &result {
-                Ok(_) => return result,
-                Err(e) => {
+        let rtxn = self.env.read_txn()?;
+        let ids = self
+            .chunks
+            .iter(&rtxn)?
+            .map(|entry| entry.map(|(id, _)| id))
+            .collect::<heed::Result<Vec<u32>>>()?;
+        drop(rtxn);
+        let mut stripped = Vec::new();
+        for batch in ids.chunks(STRIP_BATCH) {
+            let mut wtxn = self.env.write_txn()?;
+            for &id in batch {
+                let Some(mut chunk) = self.chunks.get(&wtxn, &id)? else {
+                    continue;
+                };
+                let mut changed = chunk.context_prev.take().is_some();
+                changed |= chunk.context_next.take().is_some();
+                changed |= chunk.strip_searchable_text();
+                if drop_content(&chunk) {
+                    let signature = chunk.signature.clone().unwrap_or_default();
+                    if chunk.content != signature {
+                        chunk.content = signature;
+                        stripped.push(id);
+                        changed = true;
+                    }
+                }
+                if changed {
+                    self.chunks.put(&mut wtxn, &id, &chunk)?;
+                }
+            }
+            wtxn.commit()?;
+        }
+        Ok(stripped)
+    }
+
+    /// Delete all chunks stored for the file at `path` (as stored in the chunks)
+    ///
+    /// Looks the IDs up in the path index, so it also removes chunks that the
+    /// caller's own bookkeeping lost track of. Returns the IDs of deleted chunks.
+    pub fn delete_by_path(&mut self, path: &str) -> Result<Vec<u32>> {
+        let ids = self.chunk_ids_for_path(path)?;
+        self.delete_chunks(&ids)?;
+        Ok(ids)
+    }
+
+    /// Vectors of deleted chunks that embedded exactly like each of `chunks`
+    ///
+    /// Code that moved (e.g. with its directory) is deleted with the old file
+    /// and inserted with the new one; its vectors are kept in the database in
+    /// between, so the moved chunks needn't be embedded again even when the
+    /// two happen in different watcher batches or processes. They are kept
+    /// until more are deleted than fit, or [`forget_moved_vectors`](Self::forget_moved_vectors).
+    pub fn moved_vectors(&self, chunks: &[Chunk]) -> Result<Vec<Option<StoredVectors>>> {
+        let Some(moved_vectors) = self.moved_vectors else {
+            return Ok(vec![None; chunks.len()]);
+        };
+        let rtxn = self.env.read_txn()?;
+        if moved_vectors.is_empty(&rtxn)? {
+            return Ok(vec![None; chunks.len()]);
+        }
+        chunks
+            .iter()
+            .map(|chunk| {
+                Ok(moved_vectors
+                    .get(&rtxn, &moved_vector_key(chunk))?
+                    .filter(|vectors| vectors.code.len() == self.dimensions))
+            })
+            .collect()
+    }
+
+    /// Drop the vectors kept for deleted chunks, e.g. to fit a size budget
+    pub fn forget_moved_vectors(&mut self) -> Result<()> {
+        let Some(moved_vectors) = self.moved_vectors else {
+            return Ok(());
+        };
+        let mut wtxn = self.env.write_txn()?;
+        moved_vectors.clear(&mut wtxn)?;
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    /// IDs of the chunks stored for the file at `path`
+    fn chunk_ids_for_path(&self, path: &str) -> Result<Vec<u32>> {
+        let Some(path_chunks) = self.path_chunks else {
+            return Ok(Vec::new());
+        };
+        let rtxn = self.env.read_txn()?;
+        Ok(path_chunks.get(&rtxn, path)?.unwrap_or_default())
+    }
+
+    /// Insert chunks and return their assigned IDs
+    ///
+    /// Useful for tracking which chunks belong to which file
+    pub fn insert_chunks_with_ids(&mut self, chunks: Vec<EmbeddedChunk>) -> Result<Vec<u32>> {
+        // Auto-resize retry logic for MDB_MAP_FULL errors
+        let mut attempts = 0;
+        let max_attempts = 3;
+
+        loop {
+            attempts += 1;
+
+            let result = self.insert_chunks_with_ids_impl(&chunks);
+
+            match &result {
+                Ok(_) => return result,
+                Err(e) => {
                     if attempts >= max_attempts || !self.is_map_full_error(e.as_ref()) {
                         return result;
                     }
@@ -658,7 +1543,7 @@ impl VectorStore {
 
         let start_id = self.next_id;
         let mut wtxn = self.env.write_txn()?;
-        let writer = Writer::new(self.vectors, 0, self.dimensions);
+        let writer = Writer::new(self.vectors, CODE_VECTOR_INDEX, self.dimensions);
 
         for chunk in chunks {
             let id = self.next_id;
@@ -678,30 +1563,590 @@ impl VectorStore {
             self.next_id += 1;
         }
 
+        let ids: Vec<u32> = (start_id..self.next_id).collect();
+        self.put_file_vectors(&mut wtxn, chunks, &ids)?;
+        self.put_path_chunks(&mut wtxn, chunks, &ids)?;
+        self.put_acronyms(&mut wtxn, chunks, &ids)?;
+
         wtxn.commit()?;
         self.indexed = false;
 
-        let ids: Vec<u32> = (start_id..self.next_id).collect();
         Ok(ids)
     }
 
+    /// Store one mean vector per file for freshly inserted chunks
+    fn put_file_vectors(
+        &self,
+        wtxn: &mut heed::RwTxn,
+        chunks: &[EmbeddedChunk],
+        ids: &[u32],
+    ) -> Result<()> {
+        let Some(file_chunks) = self.file_chunks.filter(|_| self.file_index) else {
+            return Ok(());
+        };
+
+        // Group by path; chunks of one file are contiguous in practice, but a
+        // map keeps this correct for any batch order
+        let mut by_file: std::collections::HashMap<&str, (Vec<u32>, Vec<f32>)> =
+            std::collections::HashMap::new();
+        for (chunk, &id) in chunks.iter().zip(ids) {
+            let (file_ids, sum) = by_file
+                .entry(chunk.chunk.path.as_str())
+                .or_insert_with(|| (Vec::new(), vec![0.0; self.dimensions]));
+            file_ids.push(id);
+            for (acc, v) in sum.iter_mut().zip(&chunk.embedding) {
+                *acc += v;
+            }
+        }
+
+        let writer = Writer::new(self.vectors, FILE_VECTOR_INDEX, self.dimensions);
+        for (path, (mut file_ids, mut sum)) in by_file {
+            // A large file is inserted in several batches: fold the new chunks
+            // into the file vector of its earlier ones, so each file keeps a
+            // single representative
+            let earlier = match self.path_chunks {
+                Some(path_chunks) => path_chunks.get(wtxn, path)?.unwrap_or_default(),
+                None => Vec::new(),
+            };
+            let mut representative = file_ids[0];
+            if let Some(&first) = earlier.first() {
+                if let Some(mut ids) = file_chunks.get(wtxn, &first)? {
+                    if let Some(vector) = writer.item_vector(wtxn, first)? {
+                        for (acc, v) in sum.iter_mut().zip(&vector) {
+                            *acc += v;
+                        }
+                    }
+                    ids.append(&mut file_ids);
+                    file_ids = ids;
+                    representative = first;
+                }
+            }
+            // Cosine distance ignores magnitude, so the sum is as good as the mean
+            writer.add_item(wtxn, representative, &sum)?;
+            file_chunks.put(wtxn, &representative, &file_ids)?;
+        }
+        Ok(())
+    }
+
+    /// Add freshly inserted chunk IDs to the path index
+    fn put_path_chunks(
+        &self,
+        wtxn: &mut heed::RwTxn,
+        chunks: &[EmbeddedChunk],
+        ids: &[u32],
+    ) -> Result<()> {
+        let Some(path_chunks) = self.path_chunks else {
+            return Ok(());
+        };
+        let mut by_path: std::collections::HashMap<&str, Vec<u32>> =
+            std::collections::HashMap::new();
+        for (chunk, &id) in chunks.iter().zip(ids) {
+            by_path
+                .entry(chunk.chunk.path.as_str())
+                .or_default()
+                .push(id);
+        }
+        for (path, new_ids) in by_path {
+            let mut ids = path_chunks.get(wtxn, path)?.unwrap_or_default();
+            ids.extend(new_ids);
+            path_chunks.put(wtxn, path, &ids)?;
+        }
+        Ok(())
+    }
+
+    /// Add the symbols declared by freshly inserted chunks to the acronym index
+    fn put_acronyms(
+        &self,
+        wtxn: &mut heed::RwTxn,
+        chunks: &[EmbeddedChunk],
+        ids: &[u32],
+    ) -> Result<()> {
+        let Some(acronyms) = self.acronyms else {
+            return Ok(());
+        };
+        let mut by_acronym: std::collections::HashMap<String, Vec<(String, u32)>> =
+            std::collections::HashMap::new();
+        for (chunk, &id) in chunks.iter().zip(ids) {
+            if let Some((acronym, name)) = chunk.chunk.context.last().and_then(|l| chunk_acronym(l))
+            {
+                by_acronym.entry(acronym).or_default().push((name, id));
+            }
+        }
+        for (acronym, new_symbols) in by_acronym {
+            let mut symbols = acronyms.get(wtxn, &acronym)?.unwrap_or_default();
+            symbols.extend(new_symbols);
+            acronyms.put(wtxn, &acronym, &symbols)?;
+        }
+        Ok(())
+    }
+
+    /// Names of the indexed symbols whose initialism is `acronym`, most chunks first
+    pub fn acronym_symbols(&self, acronym: &str) -> Result<Vec<String>> {
+        let Some(acronyms) = self.acronyms else {
+            return Ok(Vec::new());
+        };
+        let rtxn = self.env.read_txn()?;
+        let symbols = acronyms
+            .get(&rtxn, &acronym.to_lowercase())?
+            .unwrap_or_default();
+        let mut counts: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+        for (name, _) in symbols {
+            *counts.entry(name).or_default() += 1;
+        }
+        let mut names: Vec<(String, usize)> = counts.into_iter().collect();
+        names.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        Ok(names.into_iter().map(|(name, _)| name).collect())
+    }
+
+    /// Whether a search over `total_chunks` should go through file-level retrieval
+    fn use_file_retrieval(&self, total_chunks: u64) -> bool {
+        let min_chunks = std::env::var("CODESEARCH_FILE_RETRIEVAL_MIN_CHUNKS")
+            .ok()
+            .and_then(|s| s.parse::<u64>().ok())
+            .unwrap_or(crate::constants::DEFAULT_FILE_RETRIEVAL_MIN_CHUNKS as u64);
+        self.file_index && total_chunks >= min_chunks
+    }
+
+    /// Chunks of the `probes` clusters nearest to the query; `None` until
+    /// clusters are built
+    fn cluster_candidates(
+        &self,
+        rtxn: &heed::RoTxn,
+        query_embedding: &[f32],
+        probes: usize,
+    ) -> Result<Option<RoaringBitmap>> {
+        let (Some(centroids_db), Some(members_db)) = (self.cluster_centroids, self.cluster_members)
+        else {
+            return Ok(None);
+        };
+        let cached = self
+            .centroids
+            .lock()
+            .map_err(|e| anyhow!("Mutex poisoned: {}", e))?
+            .clone();
+        let centroids = match cached {
+            Some(centroids) => centroids,
+            None => {
+                let mut centroids = Vec::new();
+                for result in centroids_db.iter(rtxn)? {
+                    centroids.push(result?.1);
+                }
+                let centroids = Arc::new(centroids);
+                *self
+                    .centroids
+                    .lock()
+                    .map_err(|e| anyhow!("Mutex poisoned: {}", e))? = Some(Arc::clone(&centroids));
+                centroids
+            }
+        };
+        if centroids.is_empty() {
+            return Ok(None);
+        }
+
+        let mut candidates = RoaringBitmap::new();
+        for cluster in ivf::nearest_clusters(&centroids, query_embedding, probes) {
+            if let Some(members) = members_db.get(rtxn, &(cluster as u32))? {
+                candidates |= members;
+            }
+        }
+        Ok(Some(candidates))
+    }
+
+    /// Drop the cached centroids, so the next clustered search reads them again
+    fn forget_centroids(&self) -> Result<()> {
+        *self
+            .centroids
+            .lock()
+            .map_err(|e| anyhow!("Mutex poisoned: {}", e))? = None;
+        Ok(())
+    }
+
+    /// Two-stage search: rank files first, then rank the chunks of the top files
+    ///
+    /// Chunks of the selected files are scored exactly (no ANN), so the second
+    /// stage is precise. Returns `None` if the file index can't be read.
+    fn search_by_files(
+        &self,
+        rtxn: &heed::RoTxn,
+        query_embedding: &[f32],
+        top_files: usize,
+        limit: usize,
+    ) -> Result<Option<Vec<(ItemId, f32)>>> {
+        let Some(file_chunks) = self.file_chunks else {
+            return Ok(None);
+        };
+        let Ok(file_reader) = Reader::open(rtxn, FILE_VECTOR_INDEX, self.vectors) else {
+            return Ok(None);
+        };
+        let chunk_reader = Reader::open(rtxn, CODE_VECTOR_INDEX, self.vectors)?;
+
+        let mut scored = Vec::new();
+        for (representative, _) in file_reader
+            .nns(top_files)
+            .by_vector(rtxn, query_embedding)?
+        {
+            for id in file_chunks.get(rtxn, &representative)?.unwrap_or_default() {
+                let Some(vector) = chunk_reader.item_vector(rtxn, id)? else {
+                    continue;
+                };
+                scored.push((id, cosine_distance(&vector, query_embedding)));
+            }
+        }
+
+        scored.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        scored.truncate(limit);
+        Ok(Some(scored))
+    }
+
+    /// Enable the prose embedding space with the given dimensions
+    ///
+    /// Must be called before inserting prose vectors into a database that
+    /// does not have a built prose index yet.
+    pub fn enable_prose_index(&mut self, dimensions: usize) -> Result<()> {
+        match self.prose_dimensions {
+            Some(existing) if existing != dimensions => Err(anyhow!(
+                "Prose embedding dimension mismatch: index has {}, model produces {}",
+                existing,
+                dimensions
+            )),
+            _ => {
+                self.prose_dimensions = Some(dimensions);
+                Ok(())
+            }
+        }
+    }
+
+    /// Dimensions of the code vectors
+    pub fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    /// Dimensions of the prose vectors, if this database stores them
+    pub fn prose_dimensions(&self) -> Option<usize> {
+        self.prose_dimensions
+    }
+
+    /// Whether this database stores a second (prose) vector per chunk
+    pub fn has_prose_index(&self) -> bool {
+        self.prose_dimensions.is_some()
+    }
+
+    /// Store prose vectors for chunks that were already inserted
+    ///
+    /// `chunk_ids` and `embeddings` are parallel; IDs come from
+    /// [`insert_chunks_with_ids`](Self::insert_chunks_with_ids).
+    pub fn insert_prose_embeddings(
+        &mut self,
+        chunk_ids: &[u32],
+        embeddings: &[Vec<f32>],
+    ) -> Result<()> {
+        let dimensions = self
+            .prose_dimensions
+            .ok_or_else(|| anyhow!("Prose index not enabled"))?;
+        if chunk_ids.len() != embeddings.len() {
+            return Err(anyhow!(
+                "Got {} prose embeddings for {} chunks",
+                embeddings.len(),
+                chunk_ids.len()
+            ));
+        }
+        if chunk_ids.is_empty() {
+            return Ok(());
+        }
+
+        let mut wtxn = self.env.write_txn()?;
+        let writer = Writer::new(self.vectors, PROSE_VECTOR_INDEX, dimensions);
+        for (&id, embedding) in chunk_ids.iter().zip(embeddings) {
+            if embedding.len() != dimensions {
+                return Err(anyhow!(
+                    "Prose embedding dimension mismatch: expected {}, got {}",
+                    dimensions,
+                    embedding.len()
+                ));
+            }
+            writer.add_item(&mut wtxn, id, embedding)?;
+        }
+        wtxn.commit()?;
+        self.indexed = false;
+        Ok(())
+    }
+
+    /// Up to `limit` IDs of chunks after `after` without a staged migration
+    /// vector of `version`, lowest first
+    pub fn chunks_to_migrate(
+        &self,
+        version: &str,
+        after: Option<u32>,
+        limit: usize,
+    ) -> Result<Vec<u32>> {
+        let rtxn = self.env.read_txn()?;
+        let start = after.map_or(0, |id| id.saturating_add(1));
+        let mut ids = Vec::new();
+        for result in self
+            .chunks
+            .remap_data_type::<DecodeIgnore>()
+            .range(&rtxn, &(start..))?
+        {
+            let (id, ()) = result?;
+            let staged = match self.chunk_models {
+                Some(chunk_models) => chunk_models.get(&rtxn, &id)?,
+                None => None,
+            };
+            if staged != Some(version) {
+                ids.push(id);
+                if ids.len() >= limit {
+                    break;
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    /// Chunks with a staged migration vector of `version`, and all chunks
+    pub fn migration_progress(&self, version: &str) -> Result<(usize, usize)> {
+        let rtxn = self.env.read_txn()?;
+        let total = self.chunks.len(&rtxn)? as usize;
+        let mut staged = 0;
+        if let Some(chunk_models) = self.chunk_models {
+            for result in chunk_models.iter(&rtxn)? {
+                if result?.1 == version {
+                    staged += 1;
+                }
+            }
+        }
+        Ok((staged, total))
+    }
+
+    /// Drop staged migration vectors of any version but `version`, left by a
+    /// migration to another model; returns how many were dropped
+    pub fn discard_staged_vectors(&mut self, version: &str) -> Result<usize> {
+        let Some(chunk_models) = self.chunk_models else {
+            return Ok(0);
+        };
+        let mut wtxn = self.env.write_txn()?;
+        let mut stale = Vec::new();
+        for result in chunk_models.iter(&wtxn)? {
+            let (id, staged) = result?;
+            if staged != version {
+                stale.push(id);
+            }
+        }
+        let staging = Writer::new(self.vectors, MIGRATION_VECTOR_INDEX, self.dimensions);
+        for id in &stale {
+            staging.del_item(&mut wtxn, *id)?;
+            chunk_models.delete(&mut wtxn, id)?;
+        }
+        wtxn.commit()?;
+        Ok(stale.len())
+    }
+
+    /// Stage vectors of `version` for stored chunks, to replace their code
+    /// vectors once every chunk has one
+    ///
+    /// `chunk_ids` and `embeddings` are parallel. Chunks deleted since their
+    /// IDs were read are skipped.
+    pub fn stage_migration_vectors(
+        &mut self,
+        version: &str,
+        chunk_ids: &[u32],
+        embeddings: &[Vec<f32>],
+    ) -> Result<()> {
+        if chunk_ids.len() != embeddings.len() {
+            return Err(anyhow!(
+                "Got {} migration embeddings for {} chunks",
+                embeddings.len(),
+                chunk_ids.len()
+            ));
+        }
+        self.retry_map_full("stage_migration_vectors()", |store| {
+            store.stage_migration_vectors_impl(version, chunk_ids, embeddings)
+        })
+    }
+
+    fn stage_migration_vectors_impl(
+        &mut self,
+        version: &str,
+        chunk_ids: &[u32],
+        embeddings: &[Vec<f32>],
+    ) -> Result<()> {
+        let chunk_models = self
+            .chunk_models
+            .ok_or_else(|| anyhow!("Database opened read-only"))?;
+        let Some(dimensions) = embeddings.first().map(Vec::len) else {
+            return Ok(());
+        };
+        let mut wtxn = self.env.write_txn()?;
+        let staging = Writer::new(self.vectors, MIGRATION_VECTOR_INDEX, dimensions);
+        let chunks = self.chunks.remap_data_type::<DecodeIgnore>();
+        for (&id, embedding) in chunk_ids.iter().zip(embeddings) {
+            if chunks.get(&wtxn, &id)?.is_none() {
+                continue;
+            }
+            staging.add_item(&mut wtxn, id, embedding)?;
+            chunk_models.put(&mut wtxn, &id, version)?;
+        }
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    /// Make the staged migration vectors of `version` the code vectors
+    ///
+    /// Every chunk must have one. File vectors are recomputed from them; the
+    /// fast search clusters and the vectors kept for moved code belong to the
+    /// old model and are dropped. The index must be built afterwards.
+    pub fn finish_migration(&mut self, version: &str, dimensions: usize) -> Result<()> {
+        let (staged, total) = self.migration_progress(version)?;
+        if staged < total {
+            return Err(anyhow!(
+                "{} of {} chunks have no {} vector yet",
+                total - staged,
+                total,
+                version
+            ));
+        }
+        self.retry_map_full("finish_migration()", |store| {
+            store.finish_migration_impl(dimensions)
+        })?;
+        self.dimensions = dimensions;
+        self.indexed = false;
+        Ok(())
+    }
+
+    fn finish_migration_impl(&mut self, dimensions: usize) -> Result<()> {
+        let chunk_models = self
+            .chunk_models
+            .ok_or_else(|| anyhow!("Database opened read-only"))?;
+        let mut wtxn = self.env.write_txn()?;
+        let staging = Writer::new(self.vectors, MIGRATION_VECTOR_INDEX, dimensions);
+
+        // Copied in batches: all vectors of a huge index don't fit in memory
+        const COPY_BATCH: usize = 10_000;
+        let mut ids = Vec::new();
+        for result in chunk_models.iter(&wtxn)? {
+            ids.push(result?.0);
+        }
+        Writer::new(self.vectors, CODE_VECTOR_INDEX, self.dimensions).clear(&mut wtxn)?;
+        let code = Writer::new(self.vectors, CODE_VECTOR_INDEX, dimensions);
+        for batch in ids.chunks(COPY_BATCH) {
+            let mut vectors = Vec::with_capacity(batch.len());
+            for &id in batch {
+                if let Some(vector) = staging.item_vector(&wtxn, id)? {
+                    vectors.push((id, vector));
+                }
+            }
+            for (id, vector) in vectors {
+                code.add_item(&mut wtxn, id, &vector)?;
+            }
+        }
+
+        if let Some(file_chunks) = self.file_chunks.filter(|_| self.file_index) {
+            let mut file_vectors = Vec::new();
+            for result in file_chunks.iter(&wtxn)? {
+                let (representative, ids) = result?;
+                let mut sum = vec![0.0; dimensions];
+                for id in ids {
+                    if let Some(vector) = staging.item_vector(&wtxn, id)? {
+                        for (acc, v) in sum.iter_mut().zip(&vector) {
+                            *acc += v;
+                        }
+                    }
+                }
+                file_vectors.push((representative, sum));
+            }
+            Writer::new(self.vectors, FILE_VECTOR_INDEX, self.dimensions).clear(&mut wtxn)?;
+            let files = Writer::new(self.vectors, FILE_VECTOR_INDEX, dimensions);
+            for (representative, sum) in file_vectors {
+                files.add_item(&mut wtxn, representative, &sum)?;
+            }
+        }
+
+        staging.clear(&mut wtxn)?;
+        chunk_models.clear(&mut wtxn)?;
+        if let (Some(centroids), Some(members)) = (self.cluster_centroids, self.cluster_members) {
+            centroids.clear(&mut wtxn)?;
+            members.clear(&mut wtxn)?;
+            self.forget_centroids()?;
+        }
+        if let Some(moved_vectors) = self.moved_vectors {
+            moved_vectors.clear(&mut wtxn)?;
+        }
+        wtxn.commit()?;
+        Ok(())
+    }
+
+    /// Run a write, growing the map and retrying on MDB_MAP_FULL
+    fn retry_map_full<T>(
+        &mut self,
+        operation: &str,
+        mut write: impl FnMut(&mut Self) -> Result<T>,
+    ) -> Result<T> {
+        let mut attempts = 0;
+        let max_attempts = 3;
+
+        loop {
+            attempts += 1;
+            let result = write(self);
+            match &result {
+                Ok(_) => return result,
+                Err(e) => {
+                    if attempts >= max_attempts || !self.is_map_full_error(e.as_ref()) {
+                        return result;
+                    }
+                    let new_size = self.map_size_mb * 2;
+                    if new_size > MAX_LMDB_MAP_SIZE_MB {
+                        warn!(
+                            "MDB_MAP_FULL error in {}, already at max size {}MB",
+                            operation, self.map_size_mb
+                        );
+                        return result;
+                    }
+                    warn!(
+                        "MDB_MAP_FULL error in {}, resizing to {}MB (attempt {}/{})",
+                        operation, new_size, attempts, max_attempts
+                    );
+                    self.resize_environment(new_size)?;
+                }
+            }
+        }
+    }
+
     /// Clear all data from the database
     #[allow(dead_code)] // Reserved for database reset operations
     pub fn clear(&mut self) -> Result<()> {
-        eprintln!("🗑️  Clearing database...");
+        err_print!("🗑️  Clearing database...");
 
         let mut wtxn = self.env.write_txn()?;
 
         // Clear both databases
         self.chunks.clear(&mut wtxn)?;
         self.vectors.clear(&mut wtxn)?;
+        if let Some(file_chunks) = self.file_chunks {
+            file_chunks.clear(&mut wtxn)?;
+        }
+        if let Some(path_chunks) = self.path_chunks {
+            path_chunks.clear(&mut wtxn)?;
+        }
+        if let Some(acronyms) = self.acronyms {
+            acronyms.clear(&mut wtxn)?;
+        }
+        if let (Some(centroids), Some(members)) = (self.cluster_centroids, self.cluster_members) {
+            centroids.clear(&mut wtxn)?;
+            members.clear(&mut wtxn)?;
+            self.forget_centroids()?;
+        }
+        if let Some(moved_vectors) = self.moved_vectors {
+            moved_vectors.clear(&mut wtxn)?;
+        }
+        if let Some(chunk_models) = self.chunk_models {
+            chunk_models.clear(&mut wtxn)?;
+        }
 
         wtxn.commit()?;
 
         self.next_id = 0;
         self.indexed = false;
 
-        eprintln!("✅ Database cleared");
+        err_print!("✅ Database cleared");
         Ok(())
     }
 
@@ -711,6 +2156,66 @@ impl VectorStore {
         Ok(self.chunks.get(&rtxn, &id)?)
     }
 
+    /// Code vector of a chunk, also when the vector index isn't built yet
+    pub fn chunk_vector(&self, id: u32) -> Result<Option<Vec<f32>>> {
+        let rtxn = self.env.read_txn()?;
+        Ok(Writer::new(self.vectors, CODE_VECTOR_INDEX, self.dimensions).item_vector(&rtxn, id)?)
+    }
+
+    /// The chunks just before and after a chunk in its file, ordered by line
+    ///
+    /// Returns `None` when there is no such chunk.
+    pub fn chunk_neighbors(&self, chunk_id: u32) -> Result<Option<ChunkNeighbors>> {
+        let (previous, next) = {
+            let rtxn = self.env.read_txn()?;
+            let Some(chunk) = self.chunks.get(&rtxn, &chunk_id)? else {
+                return Ok(None);
+            };
+            let ids = match self.path_chunks {
+                Some(path_chunks) => path_chunks.get(&rtxn, &chunk.path)?.unwrap_or_default(),
+                None => Vec::new(),
+            };
+            let mut in_file = vec![(chunk.start_line, chunk.end_line, chunk_id)];
+            for id in ids.into_iter().filter(|&id| id != chunk_id) {
+                if let Some(meta) = self.chunks.get(&rtxn, &id)? {
+                    in_file.push((meta.start_line, meta.end_line, id));
+                }
+            }
+            in_file.sort_unstable();
+            let position = in_file
+                .iter()
+                .position(|&(_, _, id)| id == chunk_id)
+                .unwrap_or_default();
+            (
+                position.checked_sub(1).map(|i| in_file[i].2),
+                in_file.get(position + 1).map(|&(_, _, id)| id),
+            )
+        };
+        let load = |id: Option<u32>| -> Result<Option<SearchResult>> {
+            Ok(match id {
+                Some(id) => self.get_chunk_as_result(id)?,
+                None => None,
+            })
+        };
+        Ok(Some(ChunkNeighbors {
+            previous: load(previous)?,
+            next: load(next)?,
+        }))
+    }
+
+    /// The chunks of the file at `path` (a normalized chunk path), ordered by
+    /// line, enclosing chunks before the chunks they contain
+    pub fn file_chunks(&self, path: &str) -> Result<Vec<SearchResult>> {
+        let mut chunks = Vec::new();
+        for id in self.chunk_ids_for_path(path)? {
+            if let Some(chunk) = self.get_chunk_as_result(id)? {
+                chunks.push(chunk);
+            }
+        }
+        chunks.sort_by_key(|c| (c.start_line, std::cmp::Reverse(c.end_line), c.id));
+        Ok(chunks)
+    }
+
     /// Get a chunk as SearchResult (for hybrid search)
     pub fn get_chunk_as_result(&self, id: u32) -> Result<Option<SearchResult>> {
         let rtxn = self.env.read_txn()?;
@@ -730,12 +2235,35 @@ impl VectorStore {
                 score: 0.0, // Will be set by caller
                 context_prev: meta.context_prev,
                 context_next: meta.context_next,
+                text_match: false,
             }))
         } else {
             Ok(None)
         }
     }
 
+    /// Call `visit` with the head of each ranked chunk and its distance, in order
+    ///
+    /// The heads borrow from the database (or from one decompressed value at a
+    /// time), so a response showing previews doesn't copy every chunk's content,
+    /// context lines and searchable text. Chunks deleted since ranking are skipped.
+    pub fn visit_chunk_heads(
+        &self,
+        ranked: &[(ItemId, f32)],
+        mut visit: impl FnMut(ChunkHead<'_>, f32),
+    ) -> Result<()> {
+        let rtxn = self.env.read_txn()?;
+        let raw = self.chunks.remap_data_type::<Bytes>();
+        for &(id, distance) in ranked {
+            let Some(bytes) = raw.get(&rtxn, &id)? else {
+                continue;
+            };
+            let bytes = bincode_bytes(bytes)?;
+            visit(bincode::deserialize(&bytes)?, distance);
+        }
+        Ok(())
+    }
+
     /// Get the database file size in bytes
     #[allow(dead_code)] // Reserved for stats display
     pub fn db_size(&self) -> Result<u64> {
@@ -743,12 +2271,33 @@ impl VectorStore {
         Ok(info.map_size as u64)
     }
 
+    /// Dimensions of the vectors actually stored in the built index
+    ///
+    /// Returns `None` when the index has not been built yet. Used to detect a
+    /// mismatch between the recorded metadata and the on-disk vectors.
+    pub fn stored_dimensions(&self) -> Result<Option<usize>> {
+        let rtxn = self.env.read_txn()?;
+        match Reader::open(&rtxn, CODE_VECTOR_INDEX, self.vectors) {
+            Ok(reader) => Ok(Some(reader.dimensions())),
+            Err(_) => Ok(None),
+        }
+    }
+
     /// Check if the index is built
+    #[allow(dead_code)] // Used by tests
     pub fn is_indexed(&self) -> bool {
         self.indexed
     }
 }
 
+/// Vectors stored for a chunk: its code vector and, in dual-embedded
+/// databases, its prose vector
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StoredVectors {
+    pub code: Vec<f32>,
+    pub prose: Option<Vec<f32>>,
+}
+
 /// Search result with metadata
 #[derive(Debug, Clone)]
 #[allow(dead_code)] // Fields docstring/hash used for completeness
@@ -769,6 +2318,30 @@ pub struct SearchResult {
     pub context_prev: Option<String>,
     /// Lines of code immediately after this chunk (for context)
     pub context_next: Option<String>,
+    /// Set when full-text or exact identifier search found the chunk too
+    pub text_match: bool,
+}
+
+/// The chunks around a chunk in its file (see [`VectorStore::chunk_neighbors`])
+#[derive(Debug, Clone)]
+pub struct ChunkNeighbors {
+    /// The chunk starting before it, if any
+    pub previous: Option<SearchResult>,
+    /// The chunk starting after it, if any
+    pub next: Option<SearchResult>,
+}
+
+/// Leading fields of a stored [`ChunkMetadata`], borrowed from its bytes
+///
+/// Must list the fields in `ChunkMetadata`'s order: bincode reads them and
+/// ignores the rest of the value.
+#[derive(Debug, Deserialize)]
+pub struct ChunkHead<'a> {
+    pub content: &'a str,
+    pub path: &'a str,
+    pub start_line: usize,
+    pub end_line: usize,
+    pub kind: &'a str,
 }
 
 /// Statistics about the vector store
@@ -779,6 +2352,8 @@ pub struct LmdbPageStats {
     pub used_bytes: u64,
     /// Actual file size on disk — from `env.real_disk_size()`.
     pub disk_size: u64,
+    /// Current map size, the most the data may occupy before it is resized.
+    pub map_size: u64,
 }
 
 pub struct StoreStats {
@@ -791,6 +2366,21 @@ pub struct StoreStats {
     pub max_chunk_id: u32,
 }
 
+/// Cosine distance on the same scale as arroy's (0 = same direction)
+fn cosine_distance(vector: &[f32], query: &[f32]) -> f32 {
+    let dot: f32 = vector.iter().zip(query).map(|(a, b)| a * b).sum();
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt()
+        * query.iter().map(|v| v * v).sum::<f32>().sqrt();
+    let cos = if norm > 0.0 { dot / norm } else { 0.0 };
+    (1.0 - cos) / 2.0
+}
+
+/// Initialism and name of the symbol a chunk declares, from its breadcrumbs
+fn chunk_acronym(context: &str) -> Option<(String, String)> {
+    let name = declared_name(context)?;
+    Some((initialism(name)?, name.to_string()))
+}
+
 /// Clean up stale .del files from previous crashed runs
 ///
 /// LMDB creates .del files when deleting items, but if the process crashes
@@ -830,6 +2420,22 @@ mod tests {
     use crate::embed::EmbeddedChunk;
     use tempfile::tempdir;
 
+    /// A function chunk of `path` spanning `lines`, embedded as `vector`
+    fn embedded_chunk(
+        path: &str,
+        lines: std::ops::Range<usize>,
+        vector: Vec<f32>,
+    ) -> EmbeddedChunk {
+        let chunk = Chunk::new(
+            format!("fn f{}() {{}}", lines.start),
+            lines.start,
+            lines.end,
+            ChunkKind::Function,
+            path.to_string(),
+        );
+        EmbeddedChunk::new(chunk, vector)
+    }
+
     #[test]
     fn test_vector_store_creation() {
         let temp_dir = tempdir().unwrap();
@@ -843,6 +2449,343 @@ mod tests {
         assert!(!store.is_indexed());
     }
 
+    #[test]
+    fn test_build_index_reports_progress_and_cancels() {
+        let temp_dir = tempdir().unwrap();
+        let mut store = VectorStore::new(&temp_dir.path().join("test.db"), 4).unwrap();
+        let chunks = (0..200)
+            .map(|i| {
+                let x = i as f32;
+                embedded_chunk("lib.rs", i..i + 1, vec![x.sin(), x.cos(), 1.0, x % 7.0])
+            })
+            .collect();
+        store.insert_chunks(chunks).unwrap();
+
+        let err = store.build_index_with(&|| true, &|_| {}).unwrap_err();
+        assert!(err.to_string().contains("cancelled"));
+        assert!(!store.is_indexed());
+
+        let events = std::sync::Mutex::new(Vec::new());
+        store
+            .build_index_with(&|| false, &|p| {
+                events
+                    .lock()
+                    .unwrap()
+                    .push((p.index, p.step, p.steps, p.done))
+            })
+            .unwrap();
+        assert!(store.is_indexed());
+        let events = events.into_inner().unwrap();
+        let steps = events[0].2;
+        assert_eq!(events[0], ("code", 1, steps, false));
+        assert!(events.contains(&("code", 1, steps, true)));
+        assert_eq!(events.last().map(|e| (e.1, e.3)), Some((steps, true)));
+    }
+
+    #[test]
+    fn test_reserve_presizes_map() {
+        let temp_dir = tempdir().unwrap();
+        let mut store = VectorStore::new(&temp_dir.path().join("test.db"), 384).unwrap();
+        let initial = store.map_size_mb;
+
+        assert!(store.required_map_size_mb(0).unwrap() <= LMDB_MAP_STEP_MB);
+        assert_eq!(store.reserve(5_000_000).unwrap(), None);
+
+        // ~1.9GB of data plus headroom
+        let grown = store.reserve(2_000_000_000).unwrap().unwrap();
+        assert!(grown > initial && grown <= MAX_LMDB_MAP_SIZE_MB);
+        assert_eq!(grown % LMDB_MAP_STEP_MB, 0);
+        assert_eq!(store.env.info().map_size, grown * 1024 * 1024);
+
+        // The resized environment keeps working
+        store
+            .insert_chunks(vec![embedded_chunk("a.rs", 0..1, vec![0.5; 384])])
+            .unwrap();
+        store.build_index().unwrap();
+        assert_eq!(store.reserve(5_000_000).unwrap(), None);
+    }
+
+    #[test]
+    fn test_refresh_snapshot_sees_other_writers() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        // Same path and options: both stores share one LMDB environment
+        let mut reader = VectorStore::new(&db_path, 4).unwrap();
+        let mut writer = VectorStore::new(&db_path, 4).unwrap();
+        assert!(!reader.refresh_snapshot().unwrap());
+
+        let chunks = (0..10)
+            .map(|i| embedded_chunk("lib.rs", i..i + 1, vec![i as f32, 1.0, 0.5, 0.0]))
+            .collect();
+        writer.insert_chunks(chunks).unwrap();
+        writer.build_index().unwrap();
+
+        assert!(!reader.is_indexed());
+        assert!(reader.refresh_snapshot().unwrap());
+        assert!(reader.is_indexed());
+        assert_eq!(reader.next_id, 10);
+        assert_eq!(reader.search(&[1.0, 1.0, 0.5, 0.0], 3).unwrap().len(), 3);
+        assert!(!reader.refresh_snapshot().unwrap());
+    }
+
+    #[test]
+    fn test_delete_by_path() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let embedded = |path: &str, n: usize| -> Vec<EmbeddedChunk> {
+            (0..n)
+                .map(|i| embedded_chunk(path, i..i + 1, vec![i as f32, 1.0, 0.5, 0.0]))
+                .collect()
+        };
+
+        let mut store = VectorStore::new(&db_path, 4).unwrap();
+        let a_ids = store
+            .insert_chunks_with_ids(embedded("src/a.rs", 3))
+            .unwrap();
+        store
+            .insert_chunks_with_ids(embedded("src/b.rs", 2))
+            .unwrap();
+        // A second insert for the same path is tracked too (e.g. a lost chunk list)
+        let ghost = store
+            .insert_chunks_with_ids(embedded("src/a.rs", 1))
+            .unwrap();
+
+        store.delete_chunks(&a_ids[..1]).unwrap();
+        let mut deleted = store.delete_by_path("src/a.rs").unwrap();
+        deleted.sort_unstable();
+        assert_eq!(deleted, [a_ids[1], a_ids[2], ghost[0]]);
+        assert!(store.delete_by_path("src/a.rs").unwrap().is_empty());
+
+        let by_file = store.get_chunks_by_file().unwrap();
+        assert_eq!(by_file.keys().collect::<Vec<_>>(), ["src/b.rs"]);
+
+        // Databases from before the path index get it filled on open
+        let mut wtxn = store.env.write_txn().unwrap();
+        store.path_chunks.unwrap().clear(&mut wtxn).unwrap();
+        wtxn.commit().unwrap();
+        drop(store);
+        let mut store = VectorStore::new(&db_path, 4).unwrap();
+        assert_eq!(store.delete_by_path("src/b.rs").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_search_in_files() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let mut store = VectorStore::new(&db_path, 4).unwrap();
+        store
+            .insert_chunks_with_ids(vec![
+                embedded_chunk("src/best.rs", 0..1, vec![1.0, 0.0, 0.0, 0.0]),
+                embedded_chunk("src/changed.rs", 0..1, vec![0.0, 1.0, 0.0, 0.0]),
+                embedded_chunk("src/changed.rs", 0..1, vec![0.6, 0.8, 0.0, 0.0]),
+            ])
+            .unwrap();
+        store.build_index().unwrap();
+
+        // The best global match is outside the files searched
+        let query = [1.0, 0.0, 0.0, 0.0];
+        let results = store
+            .search_in_files(&query, &["src/changed.rs".to_string()], 10)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.path == "src/changed.rs"));
+        assert!(results[0].score > results[1].score);
+        assert_eq!(store.search(&query, 1).unwrap()[0].path, "src/best.rs");
+
+        let unknown = store
+            .search_in_files(&query, &["src/unknown.rs".to_string()], 10)
+            .unwrap();
+        assert!(unknown.is_empty());
+    }
+
+    #[test]
+    fn test_related_chunks() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let mut store = VectorStore::new(&db_path, 4).unwrap();
+        let ids = store
+            .insert_chunks_with_ids(vec![
+                embedded_chunk("src/seed.rs", 0..1, vec![1.0, 0.1, 0.0, 0.0]),
+                embedded_chunk("src/close.rs", 0..1, vec![0.9, 0.2, 0.0, 0.0]),
+                embedded_chunk("src/far.rs", 0..1, vec![0.0, 0.0, 1.0, 0.0]),
+            ])
+            .unwrap();
+        store.build_index().unwrap();
+
+        let related = store.related_chunks(ids[0], 2).unwrap().unwrap();
+        let paths: Vec<&str> = related.iter().map(|r| r.path.as_str()).collect();
+        assert_eq!(paths, ["src/close.rs", "src/far.rs"]);
+        assert!(store.related_chunks(999, 2).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_chunk_neighbors() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let embedded = |path: &str, lines: std::ops::Range<usize>| {
+            embedded_chunk(path, lines, vec![1.0, 0.0, 0.0, 0.0])
+        };
+
+        let mut store = VectorStore::new(&db_path, 4).unwrap();
+        // Inserted out of line order, with another file in between
+        let ids = store
+            .insert_chunks_with_ids(vec![
+                embedded("src/a.rs", 11..20),
+                embedded("src/a.rs", 0..5),
+                embedded("src/b.rs", 6..10),
+                embedded("src/a.rs", 6..10),
+            ])
+            .unwrap();
+
+        let middle = store.chunk_neighbors(ids[3]).unwrap().unwrap();
+        assert_eq!(middle.previous.unwrap().id, ids[1]);
+        assert_eq!(middle.next.unwrap().id, ids[0]);
+
+        let first = store.chunk_neighbors(ids[1]).unwrap().unwrap();
+        assert!(first.previous.is_none());
+        assert_eq!(first.next.unwrap().start_line, 6);
+
+        let only = store.chunk_neighbors(ids[2]).unwrap().unwrap();
+        assert!(only.previous.is_none() && only.next.is_none());
+        assert!(store.chunk_neighbors(999).unwrap().is_none());
+
+        let outline: Vec<(usize, usize)> = store
+            .file_chunks("src/a.rs")
+            .unwrap()
+            .iter()
+            .map(|c| (c.start_line, c.end_line))
+            .collect();
+        assert_eq!(outline, [(0, 5), (6, 10), (11, 20)]);
+        assert!(store.file_chunks("src/c.rs").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_visit_chunk_heads() {
+        let temp_dir = tempdir().unwrap();
+        let mut store = VectorStore::new(&temp_dir.path().join("test.db"), 4).unwrap();
+        let content = "fn compressed() {\n    let value = compute_the_value();\n}\n".repeat(20);
+        let chunk = Chunk::new(
+            content.clone(),
+            3,
+            62,
+            ChunkKind::Function,
+            "src/a.rs".to_string(),
+        );
+        let ids = store
+            .insert_chunks_with_ids(vec![EmbeddedChunk::new(chunk, vec![1.0, 0.0, 0.0, 0.0])])
+            .unwrap();
+
+        // A value written before compression
+        let plain = ChunkMetadata::from_embedded_chunk(&EmbeddedChunk::new(
+            Chunk::new(
+                "fn plain() {}".to_string(),
+                0,
+                1,
+                ChunkKind::Function,
+                "src/b.rs".to_string(),
+            ),
+            vec![0.0; 4],
+        ));
+        let mut wtxn = store.env.write_txn().unwrap();
+        store
+            .chunks
+            .remap_data_type::<SerdeBincode<ChunkMetadata>>()
+            .put(&mut wtxn, &99, &plain)
+            .unwrap();
+        wtxn.commit().unwrap();
+
+        let mut heads = Vec::new();
+        store
+            .visit_chunk_heads(&[(ids[0], 0.1), (999, 0.2), (99, 0.3)], |head, distance| {
+                heads.push((
+                    head.path.to_string(),
+                    head.content.len(),
+                    head.start_line,
+                    head.kind.to_string(),
+                    distance,
+                ))
+            })
+            .unwrap();
+        assert_eq!(
+            heads,
+            [
+                (
+                    "src/a.rs".to_string(),
+                    content.len(),
+                    3,
+                    "Function".to_string(),
+                    0.1
+                ),
+                ("src/b.rs".to_string(), 13, 0, "Function".to_string(), 0.3),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_acronym_index() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let embedded = |path: &str, label: &str| {
+            let mut chunk = Chunk::new(
+                "// body".to_string(),
+                0,
+                1,
+                ChunkKind::Struct,
+                path.to_string(),
+            );
+            chunk.context = vec![format!("File: {}", path), label.to_string()];
+            EmbeddedChunk::new(chunk, vec![1.0, 0.0, 0.0, 0.0])
+        };
+
+        let mut store = VectorStore::new(&db_path, 4).unwrap();
+        store
+            .insert_chunks_with_ids(vec![
+                embedded("src/hsm.rs", "Struct: HierarchicalStateMachine"),
+                embedded("src/hsm.rs", "Impl: HierarchicalStateMachine"),
+                embedded("src/sync.rs", "Function: handle_sync_message"),
+                embedded("src/user.rs", "Struct: UserService"),
+            ])
+            .unwrap();
+        assert_eq!(
+            store.acronym_symbols("HSM").unwrap(),
+            ["HierarchicalStateMachine", "handle_sync_message"]
+        );
+        assert!(store.acronym_symbols("us").unwrap().is_empty());
+
+        store.delete_by_path("src/hsm.rs").unwrap();
+        assert_eq!(
+            store.acronym_symbols("hsm").unwrap(),
+            ["handle_sync_message"]
+        );
+
+        // Databases created before the acronym index get it on open
+        let old_path = temp_dir.path().join("old.db");
+        std::fs::create_dir_all(&old_path).unwrap();
+        // Same options as the store, which reuses the open environment
+        let env = unsafe {
+            EnvOpenOptions::new()
+                .map_size(crate::constants::DEFAULT_LMDB_MAP_SIZE_MB * 1024 * 1024)
+                .max_dbs(10)
+                .open(&old_path)
+                .unwrap()
+        };
+        let mut wtxn = env.write_txn().unwrap();
+        let chunks: Database<U32<BigEndian>, SerdeBincode<ChunkMetadata>> =
+            env.create_database(&mut wtxn, Some("chunks")).unwrap();
+        let metadata = ChunkMetadata::from_embedded_chunk(&embedded(
+            "src/hsm.rs",
+            "Struct: HierarchicalStateMachine",
+        ));
+        chunks.put(&mut wtxn, &0, &metadata).unwrap();
+        wtxn.commit().unwrap();
+        drop(env);
+        let store = VectorStore::new(&old_path, 4).unwrap();
+        assert_eq!(
+            store.acronym_symbols("hsm").unwrap(),
+            ["HierarchicalStateMachine"]
+        );
+    }
+
     #[test]
     fn test_insert_and_search() {
         let temp_dir = tempdir().unwrap();
@@ -991,6 +2934,368 @@ mod tests {
         assert_eq!(metadata.path, "test.rs");
     }
 
+    #[test]
+    fn test_stored_dimensions() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let mut store = VectorStore::new(&db_path, 4).unwrap();
+        assert_eq!(store.stored_dimensions().unwrap(), None);
+
+        let chunks = vec![EmbeddedChunk::new(
+            Chunk::new(
+                "fn test() {}".to_string(),
+                0,
+                1,
+                ChunkKind::Function,
+                "test.rs".to_string(),
+            ),
+            vec![1.0, 0.0, 0.0, 0.0],
+        )];
+        store.insert_chunks(chunks).unwrap();
+        store.build_index().unwrap();
+
+        assert_eq!(store.stored_dimensions().unwrap(), Some(4));
+    }
+
+    #[test]
+    fn test_moved_vectors() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+        let chunk = |path: &str, content: &str| {
+            Chunk::new(
+                content.to_string(),
+                0,
+                1,
+                ChunkKind::Function,
+                path.to_string(),
+            )
+        };
+
+        let mut store = VectorStore::new(&db_path, 4).unwrap();
+        let ids = store
+            .insert_chunks_with_ids(vec![EmbeddedChunk::new(
+                chunk("old/src/a/b/moved.rs", "fn moved() {}"),
+                vec![0.0, 1.0, 0.0, 0.0],
+            )])
+            .unwrap();
+        store.build_index().unwrap();
+        assert_eq!(
+            store
+                .moved_vectors(&[chunk("new/src/a/b/moved.rs", "fn moved() {}")])
+                .unwrap(),
+            [None]
+        );
+
+        // Deleted from the old file, the vectors wait for the new one; only
+        // chunks that embed the same text (including the path's end) match
+        store.delete_chunks(&ids).unwrap();
+        let moved = [
+            chunk("new/src/a/b/moved.rs", "fn moved() {}"),
+            chunk("new/src/a/b/moved.rs", "fn other() {}"),
+            chunk("src/a/b/renamed.rs", "fn moved() {}"),
+        ];
+        let stored = store.moved_vectors(&moved).unwrap();
+        assert_eq!(
+            stored[0],
+            Some(StoredVectors {
+                code: vec![0.0, 1.0, 0.0, 0.0],
+                prose: None,
+            })
+        );
+        assert_eq!(stored[1], None);
+        assert_eq!(stored[2], None);
+
+        // Kept across reopening, for a move picked up by a later run
+        drop(store);
+        let mut store = VectorStore::new(&db_path, 4).unwrap();
+        assert!(store.moved_vectors(&moved).unwrap()[0].is_some());
+
+        store.forget_moved_vectors().unwrap();
+        assert_eq!(store.moved_vectors(&moved).unwrap(), [None, None, None]);
+    }
+
+    #[test]
+    fn test_file_level_retrieval() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let mut store = VectorStore::new(&db_path, 3).unwrap();
+        // a.rs leans towards x, b.rs towards y
+        let ids = store
+            .insert_chunks_with_ids(vec![
+                embedded_chunk("a.rs", 0..1, vec![1.0, 0.0, 0.0]),
+                embedded_chunk("a.rs", 0..1, vec![0.9, 0.0, 0.4]),
+                embedded_chunk("b.rs", 0..1, vec![0.0, 1.0, 0.0]),
+                embedded_chunk("b.rs", 0..1, vec![0.3, 0.9, 0.0]),
+            ])
+            .unwrap();
+        store.build_index().unwrap();
+
+        let rtxn = store.env.read_txn().unwrap();
+        let hits = store
+            .search_by_files(&rtxn, &[1.0, 0.0, 0.0], 1, 10)
+            .unwrap()
+            .unwrap();
+        // Only chunks of the best file, exactly ranked
+        assert_eq!(
+            hits.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![ids[0], ids[1]]
+        );
+        drop(rtxn);
+
+        // Deleting a file drops its file vector
+        store.delete_chunks(&ids[..2]).unwrap();
+        store.build_index().unwrap();
+        let rtxn = store.env.read_txn().unwrap();
+        let hits = store
+            .search_by_files(&rtxn, &[1.0, 0.0, 0.0], 1, 10)
+            .unwrap()
+            .unwrap();
+        assert!(hits.iter().all(|(id, _)| *id == ids[2] || *id == ids[3]));
+    }
+
+    #[test]
+    fn test_file_inserted_in_batches_keeps_one_file_vector() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let mut store = VectorStore::new(&db_path, 3).unwrap();
+        let mut ids = store
+            .insert_chunks_with_ids(vec![embedded_chunk("a.rs", 0..1, vec![1.0, 0.0, 0.0])])
+            .unwrap();
+        ids.extend(
+            store
+                .insert_chunks_with_ids(vec![
+                    embedded_chunk("a.rs", 1..2, vec![0.9, 0.0, 0.4]),
+                    embedded_chunk("b.rs", 0..1, vec![0.0, 1.0, 0.0]),
+                ])
+                .unwrap(),
+        );
+        store.build_index().unwrap();
+
+        let rtxn = store.env.read_txn().unwrap();
+        let file_chunks = store.file_chunks.unwrap();
+        assert_eq!(file_chunks.len(&rtxn).unwrap(), 2);
+        assert_eq!(
+            file_chunks.get(&rtxn, &ids[0]).unwrap(),
+            Some(vec![ids[0], ids[1]])
+        );
+        let hits = store
+            .search_by_files(&rtxn, &[1.0, 0.0, 0.0], 1, 10)
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            hits.iter().map(|(id, _)| *id).collect::<Vec<_>>(),
+            vec![ids[0], ids[1]]
+        );
+    }
+
+    #[test]
+    fn test_fast_search_clusters() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let mut store = VectorStore::new(&db_path, 3).unwrap();
+        let chunk = |i: usize| {
+            let mut embedding = vec![0.1; 3];
+            embedding[i % 3] = 1.0 + i as f32 / 100.0;
+            embedded_chunk(&format!("f{}.rs", i), i..i + 1, embedding)
+        };
+        let ids = store
+            .insert_chunks_with_ids((0..30).map(chunk).collect())
+            .unwrap();
+        store.build_index().unwrap();
+
+        let update = |store: &VectorStore, enabled: bool| {
+            let mut wtxn = store.env.write_txn().unwrap();
+            let mut rng = StdRng::seed_from_u64(1);
+            store.update_clusters(&mut wtxn, &mut rng, enabled).unwrap();
+            wtxn.commit().unwrap();
+        };
+        let candidates = |store: &VectorStore, query: &[f32]| {
+            let rtxn = store.env.read_txn().unwrap();
+            store.cluster_candidates(&rtxn, query, 1).unwrap()
+        };
+        assert_eq!(candidates(&store, &[1.0, 0.0, 0.0]), None);
+
+        // √30 clusters; the one probed holds the chunks along the query's axis
+        update(&store, true);
+        let near_x = candidates(&store, &[1.0, 0.0, 0.0]).unwrap();
+        assert!(!near_x.is_empty() && near_x.len() < 30);
+        // The centroids are kept for the next query until the clusters change
+        assert!(store.centroids.lock().unwrap().is_some());
+        assert!(near_x
+            .iter()
+            .all(|id| ids.iter().position(|&i| i == id).unwrap() % 3 == 0));
+
+        // Deleted chunks leave their cluster, added ones join the nearest
+        store.delete_chunks(&ids[..3]).unwrap();
+        let added = store.insert_chunks_with_ids(vec![chunk(30)]).unwrap();
+        store.build_index().unwrap();
+        update(&store, true);
+        let near_x = candidates(&store, &[1.0, 0.0, 0.0]).unwrap();
+        assert!(!near_x.contains(ids[0]));
+        assert!(candidates(&store, &[1.3, 0.1, 0.1])
+            .unwrap()
+            .contains(added[0]));
+
+        update(&store, false);
+        assert_eq!(candidates(&store, &[1.0, 0.0, 0.0]), None);
+    }
+
+    #[test]
+    fn test_prose_index_roundtrip() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let mut store = VectorStore::new(&db_path, 4).unwrap();
+        assert!(!store.has_prose_index());
+        assert!(store.search_prose(&[1.0, 0.0], 1).is_err());
+
+        let chunks = ["fn parse() {}", "fn render() {}"]
+            .iter()
+            .enumerate()
+            .map(|(i, code)| {
+                let mut embedding = vec![0.0; 4];
+                embedding[i] = 1.0;
+                EmbeddedChunk::new(
+                    Chunk::new(
+                        code.to_string(),
+                        i,
+                        i + 1,
+                        ChunkKind::Function,
+                        "lib.rs".to_string(),
+                    ),
+                    embedding,
+                )
+            })
+            .collect();
+        let ids = store.insert_chunks_with_ids(chunks).unwrap();
+
+        // Prose space has different dimensions and the opposite geometry
+        store.enable_prose_index(2).unwrap();
+        assert!(store.enable_prose_index(3).is_err());
+        store
+            .insert_prose_embeddings(&ids, &[vec![0.0, 1.0], vec![1.0, 0.0]])
+            .unwrap();
+        store.build_index().unwrap();
+
+        let prose = store.search_prose(&[1.0, 0.0], 1).unwrap();
+        assert_eq!(prose[0].id, ids[1]);
+        let code = store.search(&[1.0, 0.0, 0.0, 0.0], 1).unwrap();
+        assert_eq!(code[0].id, ids[0]);
+
+        // Reopening detects the prose space; deletes apply to both spaces
+        drop(store);
+        let mut store = VectorStore::new(&db_path, 4).unwrap();
+        assert!(store.has_prose_index());
+        store.delete_chunks(&[ids[1]]).unwrap();
+        store.build_index().unwrap();
+        let prose = store.search_prose(&[1.0, 0.0], 2).unwrap();
+        assert!(prose.iter().all(|r| r.id != ids[1]));
+    }
+
+    #[test]
+    fn test_model_migration() {
+        let temp_dir = tempdir().unwrap();
+        let db_path = temp_dir.path().join("test.db");
+
+        let mut store = VectorStore::new(&db_path, 4).unwrap();
+        let chunk = |path: &str, i: usize| {
+            let mut embedding = vec![0.0; 4];
+            embedding[i] = 1.0;
+            embedded_chunk(path, i..i + 1, embedding)
+        };
+        let ids = store
+            .insert_chunks_with_ids(vec![chunk("a.rs", 0), chunk("a.rs", 1), chunk("b.rs", 2)])
+            .unwrap();
+        store.build_index().unwrap();
+
+        // Staged a batch at a time; the rest is found after the last batch
+        store
+            .stage_migration_vectors("new@1", &ids[..1], &[vec![1.0, 0.0]])
+            .unwrap();
+        assert_eq!(store.migration_progress("new@1").unwrap(), (1, 3));
+        assert_eq!(
+            store.chunks_to_migrate("new@1", None, 10).unwrap(),
+            ids[1..]
+        );
+        assert_eq!(
+            store.chunks_to_migrate("new@1", Some(ids[1]), 10).unwrap(),
+            ids[2..]
+        );
+        assert!(store.finish_migration("new@1", 2).is_err());
+
+        // Vectors of another version don't count and are dropped
+        store
+            .stage_migration_vectors("other@1", &ids[1..2], &[vec![0.0, 1.0]])
+            .unwrap();
+        assert_eq!(store.migration_progress("new@1").unwrap(), (1, 3));
+        assert_eq!(store.discard_staged_vectors("new@1").unwrap(), 1);
+        store
+            .stage_migration_vectors("new@1", &ids[1..], &[vec![0.9, 0.1], vec![0.0, 1.0]])
+            .unwrap();
+
+        // Searches use the old vectors until the switch
+        assert_eq!(
+            store.search(&[0.0, 0.0, 1.0, 0.0], 1).unwrap()[0].id,
+            ids[2]
+        );
+        store.finish_migration("new@1", 2).unwrap();
+        store.build_index().unwrap();
+        assert_eq!(store.search(&[0.0, 1.0], 1).unwrap()[0].id, ids[2]);
+        assert_eq!(store.migration_progress("new@1").unwrap(), (0, 3));
+
+        // File vectors are recomputed from the new vectors
+        let rtxn = store.env.read_txn().unwrap();
+        let hits = store
+            .search_by_files(&rtxn, &[1.0, 0.0], 1, 10)
+            .unwrap()
+            .unwrap();
+        assert!(hits.iter().all(|(id, _)| ids[..2].contains(id)));
+        drop(rtxn);
+
+        // Deleting a chunk drops its staged vector
+        store
+            .stage_migration_vectors("next@1", &ids[..1], &[vec![1.0, 0.0, 0.0]])
+            .unwrap();
+        store.delete_chunks(&ids[..1]).unwrap();
+        assert_eq!(store.migration_progress("next@1").unwrap(), (0, 2));
+    }
+
+    #[test]
+    fn test_chunk_metadata_to_chunk() {
+        let mut chunk = Chunk::new(
+            "def create(): ...".to_string(),
+            3,
+            9,
+            ChunkKind::Function,
+            "src/api/users.py".to_string(),
+        );
+        chunk.context = vec!["File: users.py".into(), "Function: create".into()];
+        chunk.signature = Some("def create()".into());
+        chunk.docstring = Some("Create a user.\nReturns its ID.".into());
+        chunk.tags = vec!["POST /users".into(), "PUT /users".into()];
+        let embedded = EmbeddedChunk::new(chunk.clone(), vec![0.0]);
+
+        // Embedded again, the stored chunk gives the same text
+        let mut metadata = ChunkMetadata::from_embedded_chunk(&embedded);
+        let restored = metadata.to_chunk();
+        assert_eq!(restored.tags, chunk.tags);
+        assert_eq!(
+            crate::embed::embedding_text(&restored),
+            crate::embed::embedding_text(&chunk)
+        );
+
+        // Stripped to fit a size budget, the tags are kept
+        assert!(metadata.strip_searchable_text());
+        assert!(!metadata.strip_searchable_text());
+        assert_eq!(metadata.to_chunk().tags, chunk.tags);
+        metadata.content = "def create()".into();
+        assert_eq!(metadata.to_chunk().tags, chunk.tags);
+    }
+
     #[test]
     fn test_persistence() {
         let temp_dir = tempdir().unwrap();
@@ -1,16 +1,20 @@
 use anyhow::{anyhow, Result};
 use notify::{RecommendedWatcher, RecursiveMode, Watcher};
 use notify_debouncer_full::{new_debouncer, DebounceEventResult, Debouncer, FileIdMap};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::{channel, Receiver};
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 use tokio::sync::Mutex;
 
 use crate::cache::normalize_path;
-use crate::constants::{ALWAYS_EXCLUDED, ALWAYS_SKIP_EXTENSIONS, ALWAYS_SKIP_FILENAME_SUFFIXES};
-use crate::file::Language;
+use crate::constants::{
+    ALWAYS_EXCLUDED, ALWAYS_SKIP_EXTENSIONS, ALWAYS_SKIP_FILENAME_SUFFIXES, CONFIG_DIR_NAME,
+    DEFAULT_WATCH_POLL_INTERVAL_MS, GLOBAL_DB_DIR_NAME, NETWORK_FILESYSTEMS,
+    WATCH_POLL_RESCAN_EVERY,
+};
+use crate::file::{FileWalker, Language};
 
 /// Normalize a path from notify events to a consistent format.
 /// Strips UNC prefix (`\\?\`) and converts backslashes to forward slashes
@@ -40,6 +44,107 @@ pub enum FileEvent {
     Deleted(PathBuf),
     /// File was renamed (from, to)
     Renamed(PathBuf, PathBuf),
+    /// Events were lost (queue overflow or watch error); the whole tree must
+    /// be diffed against the index to resynchronize
+    Rescan,
+}
+
+/// How the watcher learns about file changes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchStrategy {
+    /// Native notifications, unless the root is on a network filesystem
+    Auto,
+    /// Always use native notifications (inotify, FSEvents, ReadDirectoryChangesW)
+    Native,
+    /// Periodically compare file mtimes and sizes
+    Poll,
+}
+
+impl WatchStrategy {
+    /// Read `CODESEARCH_WATCH_STRATEGY` (`auto`, `native` or `poll`; default `auto`)
+    pub fn from_env() -> Self {
+        match std::env::var("CODESEARCH_WATCH_STRATEGY")
+            .map(|v| v.to_lowercase())
+            .as_deref()
+        {
+            Ok("poll") | Ok("polling") => Self::Poll,
+            Ok("native") | Ok("notify") => Self::Native,
+            _ => Self::Auto,
+        }
+    }
+}
+
+/// Detect whether `path` lives on a network filesystem (NFS, SMB, WSL mounts)
+/// where native change notifications are unreliable
+pub fn is_network_filesystem(path: &Path) -> bool {
+    let normalized = normalize_path(path);
+    // Windows UNC share (\\server\share, normalized to //server/share)
+    if normalized.starts_with("//") {
+        return true;
+    }
+    match std::fs::read_to_string("/proc/mounts") {
+        Ok(mounts) => mount_fs_type(&mounts, &normalized)
+            .is_some_and(|fs_type| NETWORK_FILESYSTEMS.contains(&fs_type)),
+        Err(_) => false,
+    }
+}
+
+/// Filesystem type of the most specific mount point containing `path`,
+/// from the contents of `/proc/mounts`
+fn mount_fs_type<'a>(mounts: &'a str, path: &str) -> Option<&'a str> {
+    mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount_point = fields.nth(1)?.replace("\\040", " ");
+            let fs_type = fields.next()?;
+            let contains = mount_point == "/"
+                || path == mount_point
+                || path.starts_with(&format!("{}/", mount_point.trim_end_matches('/')));
+            contains.then_some((mount_point.len(), fs_type))
+        })
+        .max_by_key(|(len, _)| *len)
+        .map(|(_, fs_type)| fs_type)
+}
+
+/// Paths codesearch writes to at runtime: the database and its logs, the
+/// global config dir (models, embedding cache) and the global databases dir
+///
+/// Both the given and the canonical form of each path are included, since
+/// event paths may or may not have symlinks resolved.
+fn runtime_self_paths(db_path: Option<&Path>) -> Vec<String> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    if let Some(db_path) = db_path {
+        paths.push(db_path.to_path_buf());
+        paths.push(crate::logger::get_log_dir(db_path));
+    }
+    if let Some(home) = dirs::home_dir() {
+        paths.push(home.join(CONFIG_DIR_NAME));
+        paths.push(home.join(GLOBAL_DB_DIR_NAME));
+    }
+
+    let mut normalized: Vec<String> = Vec::new();
+    for path in paths {
+        let canonical = path.canonicalize().ok();
+        for p in std::iter::once(path).chain(canonical) {
+            let p = normalize_path(&p).trim_end_matches('/').to_string();
+            if !p.is_empty() && !normalized.contains(&p) {
+                normalized.push(p);
+            }
+        }
+    }
+    normalized
+}
+
+/// Last seen modification time and size of a file
+type FileStamp = (Option<SystemTime>, u64);
+
+/// State of the polling fallback: a snapshot of every watchable file
+struct Poller {
+    interval: Duration,
+    last_poll: Instant,
+    polls: u32,
+    snapshot: HashMap<PathBuf, FileStamp>,
 }
 
 /// File watcher for incremental indexing
@@ -49,10 +154,21 @@ pub enum FileEvent {
 /// 1. Native Rust implementation (faster than Node.js chokidar)
 /// 2. Built-in debouncing (configurable)
 /// 3. Batched events for efficient processing
+///
+/// On network filesystems (or with `CODESEARCH_WATCH_STRATEGY=poll`) it falls
+/// back to periodically comparing file mtimes, so live updates still work
+/// where notify events never arrive.
 pub struct FileWatcher {
     root: PathBuf,
+    strategy: WatchStrategy,
+    /// How often the polling fallback rescans the tree
+    poll_interval: Duration,
+    /// Directories codesearch writes to at runtime (normalized), whose
+    /// events would otherwise feed back into the watcher
+    self_paths: Vec<String>,
     debouncer: Option<Debouncer<RecommendedWatcher, FileIdMap>>,
     receiver: Option<Receiver<DebounceEventResult>>,
+    poller: Option<std::sync::Mutex<Poller>>,
 }
 
 impl FileWatcher {
@@ -60,13 +176,56 @@ impl FileWatcher {
     pub fn new(root: PathBuf) -> Self {
         Self {
             root,
+            strategy: WatchStrategy::from_env(),
+            poll_interval: Duration::from_millis(
+                std::env::var("CODESEARCH_WATCH_POLL_MS")
+                    .ok()
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(DEFAULT_WATCH_POLL_INTERVAL_MS),
+            ),
+            self_paths: runtime_self_paths(None),
             debouncer: None,
             receiver: None,
+            poller: None,
         }
     }
 
+    /// Also ignore the database directory (and its logs), wherever it lives
+    pub fn with_db_path(mut self, db_path: &Path) -> Self {
+        self.self_paths = runtime_self_paths(Some(db_path));
+        self
+    }
+
+    /// Override the watch strategy (defaults to `CODESEARCH_WATCH_STRATEGY`)
+    #[allow(dead_code)] // Used by tests; callers normally rely on the env setting
+    pub fn with_strategy(mut self, strategy: WatchStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// Override the polling interval (defaults to `CODESEARCH_WATCH_POLL_MS`)
+    #[allow(dead_code)] // Used by tests; callers normally rely on the env setting
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Whether the watcher is polling instead of using native notifications
+    pub fn is_polling(&self) -> bool {
+        self.poller.is_some()
+    }
+
     /// Start watching for file changes
     pub fn start(&mut self, debounce_ms: u64) -> Result<()> {
+        let use_polling = match self.strategy {
+            WatchStrategy::Poll => true,
+            WatchStrategy::Native => false,
+            WatchStrategy::Auto => is_network_filesystem(&self.root),
+        };
+        if use_polling {
+            return self.start_polling();
+        }
+
         let (tx, rx) = channel();
 
         let debouncer = new_debouncer(
@@ -95,9 +254,26 @@ impl FileWatcher {
         Ok(())
     }
 
+    /// Start the mtime-polling fallback, snapshotting the current tree
+    fn start_polling(&mut self) -> Result<()> {
+        let snapshot = self.scan_tree()?;
+        tracing::info!(
+            "👀 Polling {} files every {}ms for changes (native file events unreliable here)",
+            snapshot.len(),
+            self.poll_interval.as_millis()
+        );
+        self.poller = Some(std::sync::Mutex::new(Poller {
+            interval: self.poll_interval,
+            last_poll: Instant::now(),
+            polls: 0,
+            snapshot,
+        }));
+        Ok(())
+    }
+
     /// Check if the watcher is currently started (collecting events)
     pub fn is_started(&self) -> bool {
-        self.debouncer.is_some()
+        self.debouncer.is_some() || self.poller.is_some()
     }
 
     /// Stop watching
@@ -107,11 +283,91 @@ impl FileWatcher {
         }
         self.debouncer = None;
         self.receiver = None;
+        self.poller = None;
+    }
+
+    /// Stamp of every watchable file under the root
+    fn scan_tree(&self) -> Result<HashMap<PathBuf, FileStamp>> {
+        let paths = FileWalker::new(self.root.clone()).walk_paths()?;
+        Ok(paths
+            .into_iter()
+            .map(|p| normalize_event_path(&p))
+            .filter(|p| !self.is_self_path(p))
+            .filter_map(|p| stamp(&p).map(|s| (p, s)))
+            .collect())
+    }
+
+    /// Compare the current tree against the poller's snapshot
+    ///
+    /// Known files are stat'ed on every poll; the tree is only re-walked every
+    /// `WATCH_POLL_RESCAN_EVERY` polls to discover new files.
+    fn poll_snapshot(&self, poller: &std::sync::Mutex<Poller>) -> Vec<FileEvent> {
+        let Ok(mut poller) = poller.lock() else {
+            return vec![];
+        };
+        if poller.last_poll.elapsed() < poller.interval {
+            return vec![];
+        }
+        poller.last_poll = Instant::now();
+        poller.polls = poller.polls.wrapping_add(1);
+
+        let mut events = Vec::new();
+        let rescan = poller.polls % WATCH_POLL_RESCAN_EVERY == 0;
+        let current = if rescan {
+            match self.scan_tree() {
+                Ok(current) => Some(current),
+                Err(e) => {
+                    tracing::warn!("File watch rescan failed: {}", e);
+                    None
+                }
+            }
+        } else {
+            None
+        };
+
+        match current {
+            Some(current) => {
+                for (path, stamp) in &current {
+                    if poller.snapshot.get(path) != Some(stamp) {
+                        events.push(FileEvent::Modified(path.clone()));
+                    }
+                }
+                for path in poller.snapshot.keys() {
+                    if !current.contains_key(path) {
+                        events.push(FileEvent::Deleted(path.clone()));
+                    }
+                }
+                poller.snapshot = current;
+            }
+            None => {
+                let mut deleted = Vec::new();
+                for (path, known) in poller.snapshot.iter_mut() {
+                    match stamp(path) {
+                        Some(now) if now != *known => {
+                            *known = now;
+                            events.push(FileEvent::Modified(path.clone()));
+                        }
+                        Some(_) => {}
+                        None => deleted.push(path.clone()),
+                    }
+                }
+                for path in deleted {
+                    poller.snapshot.remove(&path);
+                    events.push(FileEvent::Deleted(path));
+                }
+            }
+        }
+
+        events
     }
 
     /// Check if a path is in an ignored directory (.git, node_modules, etc.)
-    /// Uses the shared ALWAYS_EXCLUDED constant so FSW and FileWalker agree.
+    /// Uses the shared ALWAYS_EXCLUDED constant so FSW and FileWalker agree,
+    /// plus the runtime paths codesearch itself writes to.
     fn is_in_ignored_dir(&self, path: &Path) -> bool {
+        if self.is_self_path(path) {
+            return true;
+        }
         for component in path.components() {
             if let Some(name) = component.as_os_str().to_str() {
                 if ALWAYS_EXCLUDED.contains(&name) {
@@ -122,6 +378,14 @@ impl FileWatcher {
         false
     }
 
+    /// Check if a path is one of codesearch's own runtime paths (or inside one)
+    fn is_self_path(&self, path: &Path) -> bool {
+        let path = normalize_path(path);
+        self.self_paths
+            .iter()
+            .any(|prefix| path == *prefix || path.starts_with(&format!("{}/", prefix)))
+    }
+
     /// Check if a path should be watched.
     /// Uses the same logic as FileWalker so FSW and index agree on what is indexable:
     /// - Not in an ignored directory (ALWAYS_EXCLUDED)
@@ -165,61 +429,9 @@ impl FileWatcher {
     /// Poll for file events (non-blocking)
     /// Returns a batch of deduplicated events
     pub fn poll_events(&self) -> Vec<FileEvent> {
-        let Some(ref receiver) = self.receiver else {
-            return vec![];
-        };
-
-        let mut events = Vec::new();
-        let mut seen_paths = HashSet::new();
-
-        // Drain all available events
-        while let Ok(result) = receiver.try_recv() {
-            match result {
-                Ok(debounced_events) => {
-                    for event in debounced_events {
-                        for raw_path in &event.paths {
-                            // Normalize path: strip UNC prefix, convert backslashes
-                            let path = normalize_event_path(raw_path);
-
-                            // Skip ignored directories
-                            if self.is_in_ignored_dir(&path) || seen_paths.contains(&path) {
-                                continue;
-                            }
-                            seen_paths.insert(path.clone());
-
-                            // Convert to our event type
-                            use notify::EventKind;
-                            match event.kind {
-                                EventKind::Create(_) | EventKind::Modify(_) => {
-                                    // For creates/modifies, only process indexable files
-                                    if self.is_watchable(&path) && raw_path.exists() {
-                                        events.push(FileEvent::Modified(path));
-                                    }
-                                }
-                                EventKind::Remove(_) => {
-                                    // For removals, don't filter by extension - directory
-                                    // deletions on Windows may only report the directory
-                                    // path (no file extension), not individual files
-                                    events.push(FileEvent::Deleted(path));
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
-                }
-                Err(errors) => {
-                    for error in errors {
-                        tracing::warn!("File watch error: {:?}", error);
-                    }
-                }
-            }
+        if let Some(ref poller) = self.poller {
+            return self.poll_snapshot(poller);
         }
-
-        events
-    }
-
-    /// Block and wait for events (with timeout)
-    pub fn wait_for_events(&self, timeout: Duration) -> Vec<FileEvent> {
         let Some(ref receiver) = self.receiver else {
             return vec![];
         };
@@ -227,15 +439,7 @@ impl FileWatcher {
         let mut events = Vec::new();
         let mut seen_paths = HashSet::new();
 
-        // Wait for first event
-        match receiver.recv_timeout(timeout) {
-            Ok(result) => {
-                self.process_debounce_result(result, &mut events, &mut seen_paths);
-            }
-            Err(_) => return events, // Timeout or disconnected
-        }
-
-        // Drain any additional events that came in
+        // Drain all available events
         while let Ok(result) = receiver.try_recv() {
             self.process_debounce_result(result, &mut events, &mut seen_paths);
         }
@@ -252,6 +456,13 @@ impl FileWatcher {
         match result {
             Ok(debounced_events) => {
                 for event in debounced_events {
+                    // The OS event queue overflowed (inotify IN_Q_OVERFLOW, Windows
+                    // buffer overflow): individual changes were lost
+                    if event.need_rescan() {
+                        tracing::warn!("⚠️  File watch queue overflowed, scheduling full resync");
+                        push_rescan(events);
+                        continue;
+                    }
                     for raw_path in &event.paths {
                         // Normalize path: strip UNC prefix, convert backslashes
                         let path = normalize_event_path(raw_path);
@@ -263,6 +474,9 @@ impl FileWatcher {
                         seen_paths.insert(path.clone());
 
                         use notify::EventKind;
+                        // Creates/modifies of other files end here rather than
+                        // falling through to the later arms
+                        #[allow(clippy::collapsible_match)]
                         match event.kind {
                             EventKind::Create(_) | EventKind::Modify(_) => {
                                 // For creates/modifies, only process indexable files
@@ -282,14 +496,30 @@ impl FileWatcher {
                 }
             }
             Err(errors) => {
+                // Errors mean events may have been dropped; resync rather than
+                // risk missing changes until the next restart
                 for error in errors {
                     tracing::warn!("File watch error: {:?}", error);
                 }
+                push_rescan(events);
             }
         }
     }
 }
 
+/// Queue a single `Rescan` event per batch
+fn push_rescan(events: &mut Vec<FileEvent>) {
+    if !events.contains(&FileEvent::Rescan) {
+        events.push(FileEvent::Rescan);
+    }
+}
+
+/// Current mtime and size of a file, or `None` if it no longer exists
+fn stamp(path: &Path) -> Option<FileStamp> {
+    let metadata = std::fs::metadata(path).ok()?;
+    Some((metadata.modified().ok(), metadata.len()))
+}
+
 impl Drop for FileWatcher {
     fn drop(&mut self) {
         self.stop();
@@ -443,6 +673,75 @@ mod tests {
         assert!(watcher.is_watchable(Path::new("/tmp/Makefile")));
     }
 
+    #[test]
+    fn test_runtime_paths_are_ignored() {
+        let dir = tempdir().unwrap();
+        let db_path = dir.path().join("custom-db");
+        let watcher = FileWatcher::new(dir.path().to_path_buf()).with_db_path(&db_path);
+
+        assert!(watcher.is_in_ignored_dir(&db_path.join("logs").join("codesearch.log")));
+        assert!(watcher.is_in_ignored_dir(&db_path.join("data.mdb")));
+        assert!(!watcher.is_in_ignored_dir(&dir.path().join("custom-db.rs")));
+        assert!(!watcher.is_in_ignored_dir(&dir.path().join("src").join("main.rs")));
+    }
+
+    #[test]
+    fn test_mount_fs_type_picks_most_specific_mount() {
+        let mounts = "/dev/sda1 / ext4 rw 0 0\n\
+                      server:/export /mnt/nfs nfs4 rw 0 0\n\
+                      C:\\134 /mnt/c 9p rw 0 0\n";
+        assert_eq!(mount_fs_type(mounts, "/mnt/nfs/repo/src"), Some("nfs4"));
+        assert_eq!(mount_fs_type(mounts, "/mnt/c/Users/dev"), Some("9p"));
+        assert_eq!(mount_fs_type(mounts, "/mnt/nfsother"), Some("ext4"));
+        assert_eq!(mount_fs_type(mounts, "/home/dev/repo"), Some("ext4"));
+    }
+
+    #[test]
+    fn test_polling_watcher_detects_changes() {
+        let dir = tempdir().unwrap();
+        let kept = dir.path().join("kept.rs");
+        let removed = dir.path().join("removed.rs");
+        fs::write(&kept, "fn a() {}").unwrap();
+        fs::write(&removed, "fn b() {}").unwrap();
+
+        let mut watcher = FileWatcher::new(dir.path().to_path_buf())
+            .with_strategy(WatchStrategy::Poll)
+            .with_poll_interval(Duration::ZERO);
+        watcher.start(100).unwrap();
+        assert!(watcher.is_polling());
+
+        fs::write(&kept, "fn a() { changed(); }").unwrap();
+        fs::remove_file(&removed).unwrap();
+        let events = watcher.poll_events();
+
+        assert!(events.contains(&FileEvent::Modified(normalize_event_path(&kept))));
+        assert!(events.contains(&FileEvent::Deleted(normalize_event_path(&removed))));
+    }
+
+    #[test]
+    fn test_overflow_and_errors_request_rescan() {
+        use notify::event::{EventKind, Flag};
+        use notify_debouncer_full::DebouncedEvent;
+
+        let watcher = FileWatcher::new(PathBuf::from("/tmp"));
+        let mut events = Vec::new();
+        let mut seen = HashSet::new();
+
+        let overflow = notify::Event::new(EventKind::Other).set_flag(Flag::Rescan);
+        watcher.process_debounce_result(
+            Ok(vec![DebouncedEvent::new(overflow, Instant::now())]),
+            &mut events,
+            &mut seen,
+        );
+        watcher.process_debounce_result(
+            Err(vec![notify::Error::generic("queue full")]),
+            &mut events,
+            &mut seen,
+        );
+
+        assert_eq!(events, vec![FileEvent::Rescan]);
+    }
+
     #[test]
     #[ignore] // Requires actual filesystem events
     fn test_file_watcher() {